@@ -13,6 +13,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         seed: Some(42),
         max_steps: Some(10),
         render_mode: None,
+        reward_clip: None,
         params: serde_json::json!({
             "max_turns": 8,
             "reward_type": "coherence",
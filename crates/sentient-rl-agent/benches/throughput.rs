@@ -0,0 +1,92 @@
+//! Throughput of the training hot paths
+//!
+//! ```text
+//! cargo bench -p sentient-rl-agent --no-default-features --features bench
+//! ```
+//!
+//! The benchmarks only use the ndarray networks, so `--no-default-features`
+//! leaves out the `torch` backend and its libtorch requirement.
+//!
+//! Everything runs on CPU from seeded synthetic data. Criterion reports the
+//! change against the previous run; to compare against a fixed point, save
+//! one with `-- --save-baseline main` and pass `-- --baseline main` later.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use sentient_rl_agent::bench::{
+    filled_prioritized_buffer, ppo_minibatch_update, synthetic_experience, SyntheticRollout,
+};
+use sentient_rl_agent::ppo_full::PPOAgentFull;
+use sentient_rl_agent::PPOConfig;
+
+/// Experiences held by the prioritized buffer
+const BUFFER_SIZE: usize = 100_000;
+/// Observation width used throughout
+const OBS_DIM: usize = 16;
+/// Discrete actions for the PPO policy
+const N_ACTIONS: usize = 4;
+/// PPO minibatch size
+const MINIBATCH: usize = 64;
+/// Steps in the rollout GAE runs over
+const ROLLOUT_LEN: usize = 16_384;
+
+fn prioritized_buffer(c: &mut Criterion) {
+    let mut group = c.benchmark_group("prioritized_buffer");
+    group.sample_size(10);
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let experiences: Vec<_> = (0..BUFFER_SIZE).map(|_| synthetic_experience(&mut rng, OBS_DIM)).collect();
+    group.throughput(Throughput::Elements(BUFFER_SIZE as u64));
+    group.bench_function(BenchmarkId::new("push", BUFFER_SIZE), |b| {
+        b.iter_batched(
+            || experiences.clone(),
+            |experiences| {
+                let mut buffer = sentient_rl_agent::PrioritizedReplayBuffer::new(BUFFER_SIZE, 0.6, 0.4);
+                for (i, experience) in experiences.into_iter().enumerate() {
+                    buffer.push(experience, (i % 7) as f64);
+                }
+                buffer
+            },
+            BatchSize::LargeInput,
+        );
+    });
+
+    let buffer = filled_prioritized_buffer(BUFFER_SIZE, OBS_DIM, 0);
+    group.throughput(Throughput::Elements(256));
+    group.bench_function(BenchmarkId::new("sample_256", BUFFER_SIZE), |b| {
+        b.iter(|| buffer.sample(black_box(256)));
+    });
+    group.finish();
+}
+
+fn ppo_update(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let agent = runtime
+        .block_on(PPOAgentFull::new(PPOConfig::default(), OBS_DIM, N_ACTIONS))
+        .unwrap();
+    let mut rollout = SyntheticRollout::new(MINIBATCH, OBS_DIM, N_ACTIONS, 0);
+    rollout.compute_gae(0.99, 0.95).unwrap();
+    let batch = rollout.minibatch(MINIBATCH);
+
+    let mut group = c.benchmark_group("ppo");
+    group.throughput(Throughput::Elements(MINIBATCH as u64));
+    group.bench_function(BenchmarkId::new("minibatch_update", MINIBATCH), |b| {
+        b.iter(|| runtime.block_on(ppo_minibatch_update(&agent, &batch)).unwrap());
+    });
+    group.finish();
+}
+
+fn gae(c: &mut Criterion) {
+    let mut rollout = SyntheticRollout::new(ROLLOUT_LEN, OBS_DIM, N_ACTIONS, 0);
+
+    let mut group = c.benchmark_group("gae");
+    group.throughput(Throughput::Elements(ROLLOUT_LEN as u64));
+    group.bench_function(BenchmarkId::new("rollout", ROLLOUT_LEN), |b| {
+        b.iter(|| rollout.compute_gae(black_box(0.99), black_box(0.95)).unwrap());
+    });
+    group.finish();
+}
+
+criterion_group!(benches, prioritized_buffer, ppo_update, gae);
+criterion_main!(benches);
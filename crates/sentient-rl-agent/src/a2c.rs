@@ -0,0 +1,883 @@
+//! Advantage Actor-Critic (A2C) agent implementation
+//!
+//! A lighter alternative to PPO for small, fast tasks: it shares PPO's
+//! [`RolloutBuffer`] and GAE computation but takes a single gradient step per
+//! rollout, with no epochs, minibatches or ratio clipping.
+
+use anyhow::Result;
+use ndarray::Array1;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, PoisonError};
+use tokio::sync::RwLock;
+
+use sentient_rl_core::observation::VectorObservation;
+use sentient_rl_core::{AgentMode, DiscreteAction, Environment, SpaceSignature, SpaceSpec};
+
+use crate::policy::{
+    sample_categorical, Activation, Init, MLPConfig, MLPPolicy, OutputGradient, PolicyNetwork,
+};
+use crate::ppo_full::{truncated_importance_weight, RolloutBuffer, RolloutReplay};
+use crate::recurrent::{create_recurrent_policy_network, RecurrentConfig};
+use crate::sanitize::ObservationSanitizer;
+
+/// A2C-specific configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct A2CConfig {
+    /// Base agent configuration
+    #[serde(flatten)]
+    pub base: sentient_rl_core::AgentConfig,
+    /// Value loss coefficient
+    pub value_loss_coef: f64,
+    /// Entropy coefficient
+    pub entropy_coef: f64,
+    /// Maximum gradient norm
+    pub max_grad_norm: f64,
+    /// GAE lambda
+    pub gae_lambda: f64,
+    /// Normalize advantages
+    pub normalize_advantages: bool,
+    /// Environment steps collected per rollout
+    #[serde(default = "default_n_steps")]
+    pub n_steps: usize,
+    /// Rollouts each update trains on: the newest plus up to
+    /// `reuse_rollouts - 1` earlier ones, importance-weighted. Their
+    /// advantages date from collection, so keep this small.
+    #[serde(default = "crate::ppo_full::default_reuse_rollouts")]
+    pub reuse_rollouts: usize,
+    /// Cap on the importance weight of a reused transition
+    #[serde(default = "crate::ppo_full::default_max_importance_weight")]
+    pub max_importance_weight: f32,
+}
+
+fn default_n_steps() -> usize {
+    128
+}
+
+impl Default for A2CConfig {
+    fn default() -> Self {
+        Self {
+            base: sentient_rl_core::AgentConfig::default(),
+            value_loss_coef: 0.5,
+            entropy_coef: 0.01,
+            max_grad_norm: 0.5,
+            gae_lambda: 1.0,
+            normalize_advantages: false,
+            n_steps: default_n_steps(),
+            reuse_rollouts: crate::ppo_full::default_reuse_rollouts(),
+            max_importance_weight: crate::ppo_full::default_max_importance_weight(),
+        }
+    }
+}
+
+/// A2C training statistics, measured on the rollout before the update
+#[derive(Debug, Clone)]
+pub struct A2CTrainingStats {
+    /// Policy gradient loss
+    pub policy_loss: f32,
+    /// Mean squared error of the value head against the returns
+    pub value_loss: f32,
+    /// Mean policy entropy
+    pub entropy: f32,
+    /// Transitions trained on, across the newest and reused rollouts
+    pub transitions: usize,
+    /// Mean importance weight of those transitions (1 without reuse)
+    pub mean_importance_weight: f32,
+}
+
+/// A2C Agent for discrete action spaces
+///
+/// Gradients flow through the whole MLP. The GRU cell of
+/// [`A2CAgent::new_recurrent`] policies can't be backpropagated into, so
+/// only their action and value heads learn and the cell acts as a fixed
+/// feature extractor.
+pub struct A2CAgent {
+    config: A2CConfig,
+    observation_dim: usize,
+    action_dim: usize,
+    head_layout: HeadLayout,
+    policy: Arc<RwLock<Box<dyn PolicyNetwork>>>,
+    rollout_buffer: Arc<RwLock<RolloutBuffer>>,
+    /// Earlier rollouts reused by `train`
+    replay: Arc<RwLock<RolloutReplay>>,
+    total_timesteps: Arc<RwLock<usize>>,
+    /// Source of action samples
+    rng: Mutex<StdRng>,
+    /// Repairs observations before the policy sees them
+    sanitizer: ObservationSanitizer,
+    /// Training or evaluation
+    mode: Mutex<AgentMode>,
+}
+
+impl A2CAgent {
+    /// Create a new A2C agent
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compute config is unsupported.
+    pub fn new(config: A2CConfig, observation_space: usize, action_space: usize) -> Result<Self> {
+        Self::build(config, observation_space, action_space, StdRng::from_entropy())
+    }
+
+    /// Create an A2C agent whose initial weights and action sampling are
+    /// driven by `seed`. Paired with a seeded environment, training runs
+    /// are reproducible step for step.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compute config is unsupported.
+    pub fn new_seeded(
+        config: A2CConfig,
+        observation_space: usize,
+        action_space: usize,
+        seed: u64,
+    ) -> Result<Self> {
+        Self::build(config, observation_space, action_space, StdRng::seed_from_u64(seed))
+    }
+
+    fn build(
+        config: A2CConfig,
+        observation_space: usize,
+        action_space: usize,
+        mut rng: StdRng,
+    ) -> Result<Self> {
+        config.base.compute.ensure_supported()?;
+
+        let policy_config = MLPConfig {
+            input_dim: observation_space,
+            hidden_dims: vec![64, 64],
+            output_dim: action_space,
+            activation: Activation::Tanh,
+            use_value_head: true,
+            init_log_std: -0.5,
+            hidden_init: Init::Xavier,
+            policy_init: Init::Xavier,
+            value_init: Init::Xavier,
+        };
+        let head_layout = HeadLayout::for_mlp(&policy_config);
+        let policy: Box<dyn PolicyNetwork> = Box::new(MLPPolicy::with_rng(policy_config, &mut rng));
+
+        Ok(Self {
+            observation_dim: observation_space,
+            action_dim: action_space,
+            head_layout,
+            policy: Arc::new(RwLock::new(policy)),
+            rollout_buffer: Arc::new(RwLock::new(RolloutBuffer::new())),
+            replay: Arc::new(RwLock::new(RolloutReplay::new(config.reuse_rollouts.saturating_sub(1)))),
+            total_timesteps: Arc::new(RwLock::new(0)),
+            rng: Mutex::new(rng),
+            sanitizer: ObservationSanitizer::default(),
+            mode: Mutex::new(AgentMode::Train),
+            config,
+        })
+    }
+
+    /// Create an A2C agent with a GRU policy that keeps memory within an episode
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compute config is unsupported.
+    pub fn new_recurrent(config: A2CConfig, recurrent: &RecurrentConfig) -> Result<Self> {
+        config.base.compute.ensure_supported()?;
+        let recurrent = RecurrentConfig {
+            use_value_head: true,
+            ..recurrent.clone()
+        };
+
+        Ok(Self {
+            observation_dim: recurrent.input_dim,
+            action_dim: recurrent.output_dim,
+            head_layout: HeadLayout::new(
+                recurrent.cell_parameter_count(),
+                recurrent.hidden_dim,
+                recurrent.output_dim,
+            ),
+            policy: Arc::new(RwLock::new(create_recurrent_policy_network(&recurrent))),
+            rollout_buffer: Arc::new(RwLock::new(RolloutBuffer::new())),
+            replay: Arc::new(RwLock::new(RolloutReplay::new(config.reuse_rollouts.saturating_sub(1)))),
+            total_timesteps: Arc::new(RwLock::new(0)),
+            rng: Mutex::new(StdRng::from_entropy()),
+            sanitizer: ObservationSanitizer::default(),
+            mode: Mutex::new(AgentMode::Train),
+            config,
+        })
+    }
+
+    /// Clear the policy's recurrent state before a new episode
+    pub async fn reset_episode(&self) -> Result<()> {
+        self.policy.write().await.reset_hidden_state();
+        Ok(())
+    }
+
+    /// Total environment steps collected
+    pub async fn total_timesteps(&self) -> usize {
+        *self.total_timesteps.read().await
+    }
+
+    /// Whether the agent is training or evaluating
+    pub fn mode(&self) -> AgentMode {
+        *self.mode.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Switch between training and evaluation. In [`AgentMode::Eval`],
+    /// [`act`](Self::act) picks the most probable valid action instead of
+    /// sampling, and [`train`](Self::train) measures the losses without
+    /// updating the weights.
+    pub fn set_mode(&self, mode: AgentMode) {
+        *self.mode.lock().unwrap_or_else(PoisonError::into_inner) = mode;
+    }
+
+    /// Sanitizer applied to every observation the agent acts on or stores.
+    /// Rollouts take its bounds from the environment's observation space;
+    /// set them here when acting without one.
+    pub fn observation_sanitizer(&self) -> &ObservationSanitizer {
+        &self.sanitizer
+    }
+
+    /// Select an action, returning it with its log probability. Samples
+    /// from the policy in training and takes the most probable action in
+    /// evaluation.
+    ///
+    /// A recurrent policy's hidden state advances by one step.
+    pub async fn act(&self, observation: &Array1<f32>) -> Result<(usize, f32)> {
+        self.act_masked(observation, None).await
+    }
+
+    /// Like [`act`](Self::act), choosing only among actions whose `mask`
+    /// entry is `true`
+    pub async fn act_masked(
+        &self,
+        observation: &Array1<f32>,
+        mask: Option<&[bool]>,
+    ) -> Result<(usize, f32)> {
+        let observation = self.sanitizer.sanitized(observation);
+        let mut policy = self.policy.write().await;
+        let output = policy.forward_masked(&observation.view(), mask).await?;
+        if let Some(hidden) = &output.hidden_state {
+            policy.set_hidden_state(hidden)?;
+        }
+        let probs = softmax(&output.action_output);
+        let action_idx = if self.mode().is_eval() {
+            greedy_index(&probs)
+        } else {
+            let sample = self.rng.lock().expect("rng lock poisoned").gen::<f32>();
+            sample_categorical(&probs, sample)
+        };
+
+        Ok((action_idx, probs[action_idx].max(1e-8).ln()))
+    }
+
+    /// Most probable action, for deterministic evaluation. Ties go to the
+    /// lowest index.
+    ///
+    /// A recurrent policy's hidden state advances by one step.
+    pub async fn act_greedy(&self, observation: &Array1<f32>) -> Result<usize> {
+        let observation = self.sanitizer.sanitized(observation);
+        let mut policy = self.policy.write().await;
+        let output = policy.forward(&observation.view()).await?;
+        if let Some(hidden) = &output.hidden_state {
+            policy.set_hidden_state(hidden)?;
+        }
+        Ok(greedy_index(&output.action_output))
+    }
+
+    /// Collect a rollout of `config.n_steps` steps, resetting the
+    /// environment whenever an episode ends or is truncated
+    pub async fn collect_rollout<E>(&self, env: &mut E) -> Result<()>
+    where
+        E: Environment<Observation = VectorObservation, Action = DiscreteAction>,
+    {
+        let mut buffer = self.rollout_buffer.write().await;
+        self.replay.write().await.retain(&buffer);
+        buffer.clear();
+        self.sanitizer.set_space(env.observation_space().as_ref());
+
+        let (mut obs, info) = env.reset().await?;
+        let mut mask = info.action_mask();
+        self.reset_episode().await?;
+
+        for _ in 0..self.config.n_steps {
+            let obs_array = self.to_array(&obs);
+            // Read the value and memory before `act` advances the hidden state
+            let (value, hidden) = {
+                let policy = self.policy.read().await;
+                let value = policy.forward(&obs_array.view()).await?.value.unwrap_or(0.0);
+                (value, policy.hidden_state())
+            };
+            let (action_idx, log_prob) = self.act_masked(&obs_array, mask.as_deref()).await?;
+
+            let step = env.step(DiscreteAction(action_idx)).await?;
+            let done = step.done || step.truncated;
+
+            let mut action = Array1::zeros(self.action_dim);
+            action[action_idx] = 1.0;
+            buffer.add(obs_array, action, step.reward.0 as f32, value, log_prob, done);
+            if step.truncated {
+                let policy = self.policy.read().await;
+                let final_value = policy.forward(&self.to_array(&step.observation).view()).await?.value;
+                buffer.mark_truncated(final_value.unwrap_or(0.0));
+            }
+            if let Some(hidden) = hidden {
+                buffer.add_hidden_state(hidden);
+            }
+            buffer.add_action_mask(mask.take());
+
+            *self.total_timesteps.write().await += 1;
+
+            obs = if done {
+                self.reset_episode().await?;
+                let (obs, info) = env.reset().await?;
+                mask = info.action_mask();
+                obs
+            } else {
+                mask = step.info.action_mask();
+                step.observation
+            };
+        }
+
+        // Bootstrap from the value of the final observation
+        let last_value = {
+            let policy = self.policy.read().await;
+            policy.forward(&self.to_array(&obs).view()).await?.value.unwrap_or(0.0)
+        };
+
+        buffer.compute_returns_and_advantages(
+            last_value,
+            self.config.base.gamma as f32,
+            self.config.gae_lambda as f32,
+        )?;
+
+        if self.config.normalize_advantages {
+            buffer.normalize_advantages()?;
+        }
+
+        Ok(())
+    }
+
+    /// Sanitized network input for an environment observation
+    fn to_array(&self, obs: &VectorObservation) -> Array1<f32> {
+        let mut array: Array1<f32> = obs.data.iter().map(|&x| x as f32).collect();
+        self.sanitizer.sanitize(&mut array);
+        array
+    }
+
+    /// Actions taken and rewards received during the last rollout
+    pub(crate) async fn rollout_trace(&self) -> (Vec<usize>, Vec<f32>) {
+        self.rollout_buffer.read().await.trace()
+    }
+
+    /// Take a single gradient step on the collected rollout, plus up to
+    /// `reuse_rollouts - 1` earlier ones whose transitions are weighted by
+    /// how much likelier the current policy makes their actions (truncated
+    /// at `max_importance_weight`). In [`AgentMode::Eval`] the losses are
+    /// measured but the weights are left unchanged.
+    pub async fn train(&self) -> Result<A2CTrainingStats> {
+        let buffer = self.rollout_buffer.read().await;
+        let replay = self.replay.read().await;
+        let n_samples = buffer.len();
+
+        if n_samples == 0 {
+            return Err(anyhow::anyhow!("No data in rollout buffer"));
+        }
+
+        let rollouts: Vec<&RolloutBuffer> = std::iter::once(&*buffer).chain(replay.rollouts()).collect();
+        let n_transitions: usize = rollouts.iter().map(|rollout| rollout.len()).sum();
+        let mut policy = self.policy.write().await;
+
+        let mut params = policy.get_parameters().await?;
+        let mut gradients = vec![0.0; params.len()];
+        let layout = &self.head_layout;
+        // Replay each step from the memory it was taken with, then restore
+        // the live state so an episode in progress carries on unchanged
+        let live_hidden = policy.hidden_state();
+
+        let mut policy_loss = 0.0;
+        let mut value_loss = 0.0;
+        let mut entropy = 0.0;
+        let mut total_weight = 0.0;
+        let scale = 1.0 / n_transitions as f32;
+        let value_coef = self.config.value_loss_coef as f32;
+        let entropy_coef = self.config.entropy_coef as f32;
+
+        let steps = rollouts
+            .iter()
+            .enumerate()
+            .flat_map(|(r, rollout)| (0..rollout.len()).map(move |i| (r, i)));
+        for (r, i) in steps {
+            let rollout = rollouts[r];
+            if let Some(hidden) = rollout.hidden_states.get(i) {
+                policy.set_hidden_state(hidden)?;
+            }
+            let mask = rollout.action_mask(i);
+            let output = policy.forward_masked(&rollout.observations[i].view(), mask).await?;
+            let probs = softmax(&output.action_output);
+            let action_idx = rollout.actions[i].iter().position(|&x| x == 1.0).unwrap_or(0);
+            let advantage = rollout.advantages[i];
+            let value_error = output.value.unwrap_or(0.0) - rollout.returns[i];
+            let sample_entropy = -probs.iter().map(|&p| if p > 0.0 { p * p.ln() } else { 0.0 }).sum::<f32>();
+
+            // Earlier rollouts were collected by an older policy
+            let weight = if r == 0 {
+                1.0
+            } else {
+                truncated_importance_weight(
+                    probs[action_idx].max(1e-8).ln(),
+                    rollout.log_probs[i],
+                    self.config.max_importance_weight,
+                )
+            };
+            total_weight += weight;
+            let weighted_scale = weight * scale;
+
+            policy_loss -= probs[action_idx].max(1e-8).ln() * advantage * weighted_scale;
+            value_loss += value_error.powi(2) * weighted_scale;
+            entropy += sample_entropy * scale;
+
+            // d(loss)/d(logits) for -log pi(a) * A - c_e * H
+            let logit_grads: Vec<f32> = probs
+                .iter()
+                .enumerate()
+                .map(|(k, &p)| {
+                    let indicator = if k == action_idx { 1.0 } else { 0.0 };
+                    let policy_grad = (p - indicator) * advantage;
+                    let entropy_grad = p * (p.max(1e-8).ln() + sample_entropy);
+                    (policy_grad + entropy_coef * entropy_grad) * weighted_scale
+                })
+                .collect();
+            let value_grad = 2.0 * value_coef * value_error * weighted_scale;
+
+            let output_grad = OutputGradient {
+                action_output: Array1::from(logit_grads),
+                value: value_grad,
+                log_std: None,
+            };
+            match policy.parameter_gradients(&rollout.observations[i].view(), &output_grad) {
+                Some(sample) => {
+                    for (total, g) in gradients.iter_mut().zip(sample) {
+                        *total += g;
+                    }
+                }
+                // Heads only, on top of the features the trunk produced
+                None => {
+                    let logit_grads = &output_grad.action_output;
+                    for (j, &feature) in output.features.iter().enumerate() {
+                        for (k, &g) in logit_grads.iter().enumerate() {
+                            gradients[layout.action_weights + j * layout.action_dim + k] += feature * g;
+                        }
+                        gradients[layout.value_weights + j] += feature * value_grad;
+                    }
+                    for (k, &g) in logit_grads.iter().enumerate() {
+                        gradients[layout.action_bias + k] += g;
+                    }
+                    gradients[layout.value_bias] += value_grad;
+                }
+            }
+        }
+
+        if !self.mode().is_eval() {
+            // Gradient clipping
+            let grad_norm: f32 = gradients.iter().map(|g| g.powi(2)).sum::<f32>().sqrt();
+            let clip = if grad_norm > self.config.max_grad_norm as f32 {
+                self.config.max_grad_norm as f32 / grad_norm
+            } else {
+                1.0
+            };
+
+            let lr = self.config.base.learning_rate as f32;
+            for (param, grad) in params.iter_mut().zip(&gradients) {
+                *param -= lr * clip * grad;
+            }
+            policy.set_parameters(&params).await?;
+        }
+        if let Some(hidden) = &live_hidden {
+            policy.set_hidden_state(hidden)?;
+        }
+
+        Ok(A2CTrainingStats {
+            policy_loss,
+            value_loss,
+            entropy,
+            transitions: n_transitions,
+            mean_importance_weight: total_weight / n_transitions as f32,
+        })
+    }
+
+    /// Save the agent
+    pub async fn save(&self, path: &std::path::Path) -> Result<()> {
+        let policy = self.policy.read().await;
+        let params = policy.get_parameters().await?;
+
+        let save_data = serde_json::json!({
+            "config": self.config,
+            "parameters": params,
+            "total_timesteps": *self.total_timesteps.read().await,
+        });
+
+        tokio::fs::write(path, serde_json::to_string_pretty(&save_data)?).await?;
+        Ok(())
+    }
+
+    /// Load the agent
+    pub async fn load(&mut self, path: &std::path::Path) -> Result<()> {
+        let json = tokio::fs::read_to_string(path).await?;
+        let save_data: serde_json::Value = serde_json::from_str(&json)?;
+
+        if let Some(params) = save_data["parameters"].as_array() {
+            let params: Vec<f32> = params.iter()
+                .filter_map(|v| v.as_f64().map(|f| f as f32))
+                .collect();
+            self.policy.write().await.set_parameters(&params).await?;
+        }
+
+        if let Some(timesteps) = save_data["total_timesteps"].as_u64() {
+            *self.total_timesteps.write().await = timesteps as usize;
+        }
+
+        Ok(())
+    }
+}
+
+impl SpaceSignature for A2CAgent {
+    fn observation_spec(&self) -> SpaceSpec {
+        SpaceSpec::continuous(self.observation_dim)
+    }
+
+    fn action_spec(&self) -> SpaceSpec {
+        SpaceSpec::discrete(self.action_dim)
+    }
+}
+
+/// Offsets of the action and value heads in the flat policy parameters
+struct HeadLayout {
+    action_dim: usize,
+    action_weights: usize,
+    action_bias: usize,
+    value_weights: usize,
+    value_bias: usize,
+}
+
+impl HeadLayout {
+    /// Heads of an `MLPPolicy`, after its hidden layers
+    fn for_mlp(config: &MLPConfig) -> Self {
+        let mut offset = 0;
+        let mut prev_dim = config.input_dim;
+        for &hidden_dim in &config.hidden_dims {
+            offset += prev_dim * hidden_dim + hidden_dim;
+            prev_dim = hidden_dim;
+        }
+        Self::new(offset, prev_dim, config.output_dim)
+    }
+
+    /// Heads reading `feature_dim` features, starting at parameter `offset`
+    fn new(offset: usize, feature_dim: usize, action_dim: usize) -> Self {
+        let action_weights = offset;
+        let action_bias = action_weights + feature_dim * action_dim;
+        let value_weights = action_bias + action_dim;
+        let value_bias = value_weights + feature_dim;
+
+        Self {
+            action_dim,
+            action_weights,
+            action_bias,
+            value_weights,
+            value_bias,
+        }
+    }
+}
+
+/// Index of the largest value; ties go to the lowest index
+fn greedy_index(values: &Array1<f32>) -> usize {
+    let mut best = 0;
+    for (i, &value) in values.iter().enumerate() {
+        if value > values[best] {
+            best = i;
+        }
+    }
+    best
+}
+
+fn softmax(logits: &Array1<f32>) -> Array1<f32> {
+    let max_logit = logits.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+    let exp_logits = logits.mapv(|x| (x - max_logit).exp());
+    let sum_exp = exp_logits.sum();
+    exp_logits / sum_exp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentient_rl_core::action::DiscreteSpace;
+    use sentient_rl_core::observation::BoxObservationSpace;
+    use sentient_rl_core::state::VectorState;
+    use sentient_rl_core::{
+        check_compatibility, ActionSpace, EnvironmentConfig, ObservationSpace, RLError, Reward,
+        Step, StepInfo,
+    };
+    use sentient_rl_env::{CartPoleEnv, TimeLimit};
+
+    /// One-dimensional environment that never ends on its own
+    #[derive(Default)]
+    struct EndlessEnv {
+        t: f64,
+        resets: usize,
+    }
+
+    #[async_trait::async_trait]
+    impl Environment for EndlessEnv {
+        type Observation = VectorObservation;
+        type Action = DiscreteAction;
+        type State = VectorState;
+
+        fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+            Box::new(BoxObservationSpace::new(vec![0.0], vec![100.0], vec![1]).unwrap())
+        }
+
+        fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+            Box::new(DiscreteSpace::new(2))
+        }
+
+        async fn reset(&mut self) -> sentient_rl_core::Result<(Self::Observation, StepInfo)> {
+            self.t = 0.0;
+            self.resets += 1;
+            Ok((VectorObservation { data: vec![self.t] }, StepInfo::default()))
+        }
+
+        async fn step(&mut self, _action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
+            self.t += 1.0;
+            Ok(Step {
+                observation: VectorObservation { data: vec![self.t] },
+                reward: Reward(1.0),
+                done: false,
+                truncated: false,
+                info: StepInfo::default(),
+                state: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_a2c_value_loss_decreases_on_cartpole() {
+        let mut env = CartPoleEnv::new(EnvironmentConfig::default()).unwrap();
+        let config = A2CConfig {
+            base: sentient_rl_core::AgentConfig {
+                learning_rate: 0.01,
+                gamma: 0.9,
+                ..Default::default()
+            },
+            max_grad_norm: 100.0,
+            n_steps: 256,
+            ..A2CConfig::default()
+        };
+        let agent = A2CAgent::new(config, 4, 2).unwrap();
+
+        let mut value_losses = Vec::new();
+        for _ in 0..30 {
+            agent.collect_rollout(&mut env).await.unwrap();
+            let stats = agent.train().await.unwrap();
+            assert!(stats.value_loss.is_finite());
+            value_losses.push(stats.value_loss);
+        }
+
+        let early: f32 = value_losses[..5].iter().sum::<f32>() / 5.0;
+        let late: f32 = value_losses[25..].iter().sum::<f32>() / 5.0;
+        assert!(late < early, "value loss did not decrease: {:?}", value_losses);
+        assert_eq!(agent.total_timesteps().await, 30 * 256);
+    }
+
+    #[tokio::test]
+    async fn test_train_updates_hidden_layers() {
+        let mut env = EndlessEnv::default();
+        let config = A2CConfig {
+            n_steps: 16,
+            ..A2CConfig::default()
+        };
+        let agent = A2CAgent::new_seeded(config, 1, 2, 5).unwrap();
+        let before = agent.policy.read().await.get_parameters().await.unwrap();
+
+        agent.collect_rollout(&mut env).await.unwrap();
+        agent.train().await.unwrap();
+        let after = agent.policy.read().await.get_parameters().await.unwrap();
+
+        // The first hidden layer's weights and biases come first
+        let first_layer = 64 + 64;
+        assert!(
+            before[..first_layer].iter().zip(&after[..first_layer]).any(|(b, a)| b != a),
+            "first hidden layer did not change"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_train_reuses_recent_rollouts() {
+        let mut env = EndlessEnv::default();
+        let config = A2CConfig {
+            n_steps: 8,
+            reuse_rollouts: 2,
+            max_importance_weight: 0.5,
+            ..A2CConfig::default()
+        };
+        let agent = A2CAgent::new(config, 1, 2).unwrap();
+
+        agent.collect_rollout(&mut env).await.unwrap();
+        let first = agent.train().await.unwrap();
+        assert_eq!(first.transitions, 8);
+        assert!((first.mean_importance_weight - 1.0).abs() < 1e-6);
+
+        // The newest rollout counts fully; the retained one is capped at 0.5
+        for _ in 0..2 {
+            agent.collect_rollout(&mut env).await.unwrap();
+            let stats = agent.train().await.unwrap();
+            assert_eq!(stats.transitions, 16);
+            assert!(stats.mean_importance_weight > 0.5);
+            assert!(stats.mean_importance_weight <= 0.75 + 1e-6);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recurrent_rollout_resets_memory_each_episode() {
+        let mut env = CartPoleEnv::new(EnvironmentConfig::default()).unwrap();
+        let recurrent = RecurrentConfig {
+            input_dim: 4,
+            hidden_dim: 8,
+            output_dim: 2,
+            use_value_head: true,
+        };
+        let config = A2CConfig {
+            n_steps: 300,
+            ..A2CConfig::default()
+        };
+        let agent = A2CAgent::new_recurrent(config, &recurrent).unwrap();
+        agent.collect_rollout(&mut env).await.unwrap();
+
+        let buffer = agent.rollout_buffer.read().await;
+        assert_eq!(buffer.hidden_states.len(), 300);
+        assert!(buffer.dones.iter().any(|&d| d));
+        for i in 0..300 {
+            let episode_start = i == 0 || buffer.dones[i - 1];
+            let zero = buffer.hidden_states[i].iter().all(|&h| h == 0.0);
+            assert_eq!(zero, episode_start, "hidden state at step {}", i);
+        }
+        drop(buffer);
+
+        let live = agent.policy.read().await.hidden_state();
+        assert!(agent.train().await.unwrap().value_loss.is_finite());
+        assert_eq!(agent.policy.read().await.hidden_state(), live);
+    }
+
+    #[tokio::test]
+    async fn test_eval_mode_acts_deterministically_and_freezes_weights() {
+        let mut env = CartPoleEnv::new(EnvironmentConfig::default()).unwrap();
+        let config = A2CConfig {
+            n_steps: 64,
+            ..A2CConfig::default()
+        };
+        let agent = A2CAgent::new(config, 4, 2).unwrap();
+        agent.collect_rollout(&mut env).await.unwrap();
+        agent.set_mode(AgentMode::Eval);
+
+        let obs = Array1::from_vec(vec![0.1, -0.2, 0.05, 0.3]);
+        let first = agent.act(&obs).await.unwrap();
+        for _ in 0..50 {
+            assert_eq!(agent.act(&obs).await.unwrap(), first);
+        }
+        assert_eq!(first.0, agent.act_greedy(&obs).await.unwrap());
+
+        let before = agent.policy.read().await.get_parameters().await.unwrap();
+        assert!(agent.train().await.unwrap().value_loss.is_finite());
+        assert_eq!(agent.policy.read().await.get_parameters().await.unwrap(), before);
+
+        agent.set_mode(AgentMode::Train);
+        agent.train().await.unwrap();
+        assert_ne!(agent.policy.read().await.get_parameters().await.unwrap(), before);
+    }
+
+    #[tokio::test]
+    async fn test_observations_are_sanitized_before_use() {
+        // EndlessEnv counts past its declared upper bound of 100
+        let mut env = EndlessEnv::default();
+        let config = A2CConfig {
+            n_steps: 120,
+            ..A2CConfig::default()
+        };
+        let agent = A2CAgent::new(config, 1, 2).unwrap();
+        agent.collect_rollout(&mut env).await.unwrap();
+
+        let buffer = agent.rollout_buffer.read().await;
+        let stored: Vec<f32> = buffer.observations.iter().map(|o| o[0]).collect();
+        assert_eq!(stored[50], 50.0);
+        assert!(stored.iter().all(|&x| (0.0..=100.0).contains(&x)));
+        assert_eq!(stored[119], 100.0);
+        drop(buffer);
+
+        let (action, log_prob) = agent.act(&Array1::from_vec(vec![f32::NAN])).await.unwrap();
+        assert!(action < 2);
+        assert!(log_prob.is_finite());
+        assert!(agent.observation_sanitizer().repaired() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_rollout_resets_on_truncation_and_bootstraps() {
+        let mut env = TimeLimit::new(EndlessEnv::default(), 4);
+        let config = A2CConfig {
+            base: sentient_rl_core::AgentConfig {
+                gamma: 0.9,
+                ..Default::default()
+            },
+            n_steps: 10,
+            ..A2CConfig::default()
+        };
+        let agent = A2CAgent::new(config, 1, 2).unwrap();
+        agent.collect_rollout(&mut env).await.unwrap();
+
+        // Truncated after steps 4 and 8, and collection carried on to 10
+        assert_eq!(env.env.resets, 3);
+        assert_eq!(agent.total_timesteps().await, 10);
+        let buffer = agent.rollout_buffer.read().await;
+        assert_eq!(buffer.len(), 10);
+        let ends: Vec<usize> = (0..10).filter(|&i| buffer.dones[i]).collect();
+        assert_eq!(ends, vec![3, 7]);
+        assert_eq!(buffer.observations[4][0], 0.0);
+        assert_eq!(buffer.observations[8][0], 0.0);
+
+        // A truncated step's return bootstraps from where it was cut off
+        let cut_off = {
+            let policy = agent.policy.read().await;
+            let output = policy.forward(&Array1::from_vec(vec![4.0]).view()).await.unwrap();
+            output.value.unwrap()
+        };
+        for i in ends {
+            assert!((buffer.returns[i] - (1.0 + 0.9 * cut_off)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_mismatched_agent_is_rejected_before_training() {
+        let env = CartPoleEnv::new(EnvironmentConfig::default()).unwrap();
+        assert!(check_compatibility(&A2CAgent::new(A2CConfig::default(), 4, 2).unwrap(), &env).is_ok());
+
+        let agent = A2CAgent::new(A2CConfig::default(), 8, 2).unwrap();
+        match check_compatibility(&agent, &env) {
+            Err(RLError::ShapeMismatch { space, agent, env }) => {
+                assert_eq!(space, "observation");
+                assert_eq!(agent, SpaceSpec::continuous(8));
+                assert_eq!(env, SpaceSpec::continuous(4));
+            }
+            other => panic!("expected a shape mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unsupported_device_is_rejected() {
+        let mut config = A2CConfig::default();
+        config.base.compute.device = sentient_rl_core::Device::Cuda(0);
+        let err = A2CAgent::new(config.clone(), 4, 2).err().unwrap().to_string();
+        assert!(err.contains("device cuda:0 requested"), "{}", err);
+        let recurrent = RecurrentConfig {
+            input_dim: 4,
+            hidden_dim: 8,
+            output_dim: 2,
+            use_value_head: true,
+        };
+        assert!(A2CAgent::new_recurrent(config, &recurrent).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! Hot paths for the throughput benchmarks in `benches/`
+//!
+//! Rollout storage and the PPO update are crate-private, so the benchmarks
+//! reach them through these wrappers, fed with seeded synthetic data. None
+//! of it needs a model file or an environment. Built with the `bench`
+//! feature.
+
+use anyhow::Result;
+use ndarray::Array1;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use sentient_rl_core::action::DiscreteAction;
+use sentient_rl_core::observation::VectorObservation;
+use sentient_rl_core::state::VectorState;
+use sentient_rl_core::{Reward, Transition};
+
+use crate::buffer::{Experience, PrioritizedReplayBuffer};
+use crate::ppo_full::{PPOAgentFull, RolloutBatch, RolloutBuffer};
+
+/// Steps per episode in a [`SyntheticRollout`]
+pub const EPISODE_LEN: usize = 200;
+
+/// Experience type stored by [`filled_prioritized_buffer`]
+pub type BenchExperience = Experience<VectorObservation, DiscreteAction, VectorState>;
+
+/// Random transition with an `obs_dim`-dimensional observation
+#[allow(clippy::cast_precision_loss)]
+pub fn synthetic_experience(rng: &mut impl Rng, obs_dim: usize) -> BenchExperience {
+    let mut observation = || VectorObservation {
+        data: (0..obs_dim).map(|_| rng.gen_range(-1.0..1.0)).collect(),
+    };
+    let (observation, next_observation) = (observation(), observation());
+    Experience::from(Transition {
+        observation,
+        action: DiscreteAction(rng.gen_range(0..4)),
+        reward: Reward(rng.gen_range(-1.0..1.0)),
+        next_observation,
+        done: rng.gen_bool(1.0 / EPISODE_LEN as f64),
+        state: None,
+        next_state: None,
+    })
+}
+
+/// Prioritized buffer holding `size` random experiences with random priorities
+#[must_use]
+pub fn filled_prioritized_buffer(
+    size: usize,
+    obs_dim: usize,
+    seed: u64,
+) -> PrioritizedReplayBuffer<VectorObservation, DiscreteAction, VectorState> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut buffer = PrioritizedReplayBuffer::new(size, 0.6, 0.4);
+    for _ in 0..size {
+        let priority = rng.gen_range(0.0..2.0);
+        buffer.push(synthetic_experience(&mut rng, obs_dim), priority);
+    }
+    buffer
+}
+
+/// On-policy rollout of random steps, an episode ending every
+/// [`EPISODE_LEN`] steps
+pub struct SyntheticRollout {
+    buffer: RolloutBuffer,
+}
+
+impl SyntheticRollout {
+    /// `steps` steps with `obs_dim`-dimensional observations and one-hot
+    /// actions over `n_actions`
+    #[must_use]
+    pub fn new(steps: usize, obs_dim: usize, n_actions: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        #[allow(clippy::cast_precision_loss)]
+        let uniform_log_prob = -(n_actions as f32).ln();
+        let mut buffer = RolloutBuffer::new();
+        for step in 0..steps {
+            let observation: Array1<f32> = (0..obs_dim).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let mut action = Array1::zeros(n_actions);
+            action[rng.gen_range(0..n_actions)] = 1.0;
+            buffer.add(
+                observation,
+                action,
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                uniform_log_prob,
+                (step + 1).is_multiple_of(EPISODE_LEN),
+            );
+        }
+        Self { buffer }
+    }
+
+    /// Steps in the rollout
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Whether the rollout has no steps
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.buffer.len() == 0
+    }
+
+    /// Fill in GAE advantages and returns, bootstrapping from zero
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a reward or value is not finite, which synthetic
+    /// rollouts never produce.
+    pub fn compute_gae(&mut self, gamma: f32, gae_lambda: f32) -> Result<()> {
+        self.buffer.compute_returns_and_advantages(0.0, gamma, gae_lambda)
+    }
+
+    /// Advantages from the last [`compute_gae`](Self::compute_gae)
+    #[must_use]
+    pub fn advantages(&self) -> &[f32] {
+        &self.buffer.advantages
+    }
+
+    /// Returns from the last [`compute_gae`](Self::compute_gae)
+    #[must_use]
+    pub fn returns(&self) -> &[f32] {
+        &self.buffer.returns
+    }
+
+    /// The first `size` steps as a training minibatch. Call
+    /// [`compute_gae`](Self::compute_gae) first.
+    #[must_use]
+    pub fn minibatch(&self, size: usize) -> Minibatch {
+        let indices: Vec<usize> = (0..size.min(self.len())).collect();
+        Minibatch(self.buffer.get_batch(&indices))
+    }
+}
+
+/// Minibatch cut from a [`SyntheticRollout`]
+pub struct Minibatch(RolloutBatch);
+
+impl Minibatch {
+    /// Steps in the minibatch
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.observations.nrows()
+    }
+
+    /// Whether the minibatch has no steps
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// One PPO update on `batch`. Returns policy loss, value loss and entropy.
+///
+/// # Errors
+///
+/// Returns an error if the policy network fails its forward pass or update.
+pub async fn ppo_minibatch_update(agent: &PPOAgentFull, batch: &Minibatch) -> Result<(f32, f32, f32)> {
+    agent.train_minibatch(&batch.0).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ppo::PPOConfig;
+
+    #[test]
+    fn test_prioritized_buffer_samples_weighted_batches() {
+        let buffer = filled_prioritized_buffer(1000, 8, 7);
+        assert_eq!(buffer.len(), 1000);
+
+        let (experiences, weights, indices) = buffer.sample(64).unwrap();
+        assert_eq!(experiences.len(), 64);
+        assert_eq!(weights.len(), 64);
+        assert!(indices.iter().all(|&i| i < 1000));
+        assert!(weights.iter().all(|&w| w > 0.0 && w <= 1.0 + 1e-9));
+        assert_eq!(experiences[0].transition.observation.data.len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_gae_and_ppo_update_shapes() {
+        let mut rollout = SyntheticRollout::new(2 * EPISODE_LEN, 8, 4, 7);
+        rollout.compute_gae(0.99, 0.95).unwrap();
+        assert_eq!(rollout.advantages().len(), rollout.len());
+        assert_eq!(rollout.returns().len(), rollout.len());
+        assert!(rollout.advantages().iter().chain(rollout.returns()).all(|x| x.is_finite()));
+
+        let batch = rollout.minibatch(64);
+        assert_eq!(batch.len(), 64);
+
+        let agent = PPOAgentFull::new(PPOConfig::default(), 8, 4).await.unwrap();
+        let (policy_loss, value_loss, entropy) = ppo_minibatch_update(&agent, &batch).await.unwrap();
+        assert!(policy_loss.is_finite());
+        assert!(value_loss >= 0.0);
+        // Between zero and uniform over four actions
+        assert!(entropy > 0.0 && entropy <= 4f32.ln() + 1e-5);
+    }
+}
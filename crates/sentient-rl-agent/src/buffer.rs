@@ -1,6 +1,7 @@
 //! Experience replay buffers for RL agents
 
 use rand::seq::SliceRandom;
+use rand::Rng;
 use std::collections::VecDeque;
 
 use sentient_rl_core::{Observation, Action, State, Transition};
@@ -32,6 +33,11 @@ where
         }
     }
     
+    /// Stored experiences, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Experience<O, A, S>> {
+        self.buffer.iter()
+    }
+    
     /// Add an experience to the buffer
     pub fn push(&mut self, experience: Experience<O, A, S>) {
         if self.buffer.len() >= self.capacity {
@@ -47,13 +53,18 @@ where
     
     /// Sample a batch of experiences
     pub fn sample(&self, batch_size: usize) -> Option<Vec<Experience<O, A, S>>> {
+        self.sample_with(batch_size, &mut rand::thread_rng())
+    }
+    
+    /// Sample a batch of experiences with `rng`, so a seeded generator
+    /// draws the same batches
+    pub fn sample_with(&self, batch_size: usize, rng: &mut impl Rng) -> Option<Vec<Experience<O, A, S>>> {
         if self.buffer.len() < batch_size {
             return None;
         }
         
-        let mut rng = rand::thread_rng();
         let indices: Vec<usize> = (0..self.buffer.len()).collect();
-        let sample_indices = indices.choose_multiple(&mut rng, batch_size);
+        let sample_indices = indices.choose_multiple(rng, batch_size);
         
         let batch: Vec<_> = sample_indices
             .map(|&i| self.buffer[i].clone())
@@ -80,7 +91,114 @@ where
     }
 }
 
+/// Turns single-step experiences into n-step ones
+///
+/// Each emitted experience keeps the first observation and action, carries the
+/// discounted sum of up to `n` rewards, and ends at the observation reached
+/// after those steps. Episodes ending early flush shorter returns.
+#[derive(Debug, Clone)]
+pub struct NStepAccumulator<O, A, S> {
+    n: usize,
+    gamma: f64,
+    pending: VecDeque<Experience<O, A, S>>,
+}
+
+/// An n-step experience and the discount to apply when bootstrapping from it
+#[derive(Debug, Clone)]
+pub struct NStepExperience<O, A, S> {
+    /// Experience whose reward is the discounted n-step return
+    pub experience: Experience<O, A, S>,
+    /// Number of environment steps accumulated
+    pub steps: usize,
+    /// `gamma^steps`, the weight of the bootstrapped value
+    pub bootstrap_discount: f64,
+}
+
+impl<O, A, S> NStepExperience<O, A, S> {
+    /// TD target given the value of the final observation
+    #[must_use]
+    pub fn target(&self, next_value: f64) -> f64 {
+        let transition = &self.experience.transition;
+        if transition.done {
+            transition.reward.0
+        } else {
+            transition.reward.0 + self.bootstrap_discount * next_value
+        }
+    }
+}
+
+impl<O, A, S> NStepAccumulator<O, A, S>
+where
+    O: Observation + Clone,
+    A: Action + Clone,
+    S: State + Clone,
+{
+    /// Create an accumulator over `n` steps with discount `gamma`
+    #[must_use]
+    pub fn new(n: usize, gamma: f64) -> Self {
+        Self {
+            n: n.max(1),
+            gamma,
+            pending: VecDeque::with_capacity(n),
+        }
+    }
+
+    /// Add a step, returning any n-step experiences that are now complete
+    pub fn push(&mut self, experience: Experience<O, A, S>) -> Vec<NStepExperience<O, A, S>> {
+        let done = experience.transition.done;
+        self.pending.push_back(experience);
+
+        if done {
+            return self.flush();
+        }
+        if self.pending.len() >= self.n {
+            let ready = self.combine();
+            self.pending.pop_front();
+            return vec![ready];
+        }
+        Vec::new()
+    }
+
+    /// Emit shorter returns for every pending step and reset
+    pub fn flush(&mut self) -> Vec<NStepExperience<O, A, S>> {
+        let mut ready = Vec::with_capacity(self.pending.len());
+        while !self.pending.is_empty() {
+            ready.push(self.combine());
+            self.pending.pop_front();
+        }
+        ready
+    }
+
+    /// Combine the pending steps starting at the front
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn combine(&self) -> NStepExperience<O, A, S> {
+        let steps = self.pending.len().min(self.n);
+        let last = &self.pending[steps - 1].transition;
+
+        let mut experience = self.pending[0].clone();
+        experience.transition.reward.0 = self.pending
+            .iter()
+            .take(steps)
+            .enumerate()
+            .map(|(k, e)| self.gamma.powi(k as i32) * e.transition.reward.0)
+            .sum();
+        experience.transition.next_observation = last.next_observation.clone();
+        experience.transition.next_state = last.next_state.clone();
+        experience.transition.done = last.done;
+
+        NStepExperience {
+            experience,
+            steps,
+            bootstrap_discount: self.gamma.powi(steps as i32),
+        }
+    }
+}
+
 /// Prioritized experience replay buffer
+///
+/// With [`with_n_step`](Self::with_n_step), experiences added through
+/// [`push_n_step`](Self::push_n_step) are accumulated into n-step returns
+/// before insertion, so priorities reflect n-step TD errors.
 #[derive(Debug, Clone)]
 pub struct PrioritizedReplayBuffer<O, A, S> {
     /// Base buffer
@@ -99,6 +217,10 @@ pub struct PrioritizedReplayBuffer<O, A, S> {
     position: usize,
     /// Current size
     size: usize,
+    /// n-step accumulation, when enabled
+    n_step: Option<NStepAccumulator<O, A, S>>,
+    /// Bootstrap discount of each stored n-step experience
+    bootstrap_discounts: Vec<Option<f64>>,
 }
 
 impl<O, A, S> PrioritizedReplayBuffer<O, A, S>
@@ -118,25 +240,79 @@ where
             epsilon: 1e-6,
             position: 0,
             size: 0,
+            n_step: None,
+            bootstrap_discounts: vec![None; capacity],
         }
     }
     
+    /// Accumulate `n`-step returns with discount `gamma` before insertion
+    #[must_use]
+    pub fn with_n_step(mut self, n: usize, gamma: f64) -> Self {
+        self.n_step = Some(NStepAccumulator::new(n, gamma));
+        self
+    }
+    
     /// Add an experience with priority
     pub fn push(&mut self, experience: Experience<O, A, S>, priority: f64) {
         let priority = (priority + self.epsilon).powf(self.alpha);
+        self.insert(experience, priority, None);
+    }
+    
+    /// Add a single step, inserting completed n-step experiences at the
+    /// current maximum priority so each is sampled at least once before its
+    /// TD error is known. Without n-step mode this inserts the step as is.
+    pub fn push_n_step(&mut self, experience: Experience<O, A, S>) {
+        let max_priority = self.priorities[..self.size]
+            .iter()
+            .copied()
+            .fold(1.0, f64::max);
         
+        match self.n_step.as_mut() {
+            Some(accumulator) => {
+                for ready in accumulator.push(experience) {
+                    self.insert(ready.experience, max_priority, Some(ready.bootstrap_discount));
+                }
+            }
+            None => self.insert(experience, max_priority, None),
+        }
+    }
+    
+    /// Insert a prepared experience with an already scaled priority
+    fn insert(&mut self, experience: Experience<O, A, S>, priority: f64, bootstrap_discount: Option<f64>) {
         if self.size < self.capacity {
             self.buffer.push(experience);
             self.priorities[self.size] = priority;
+            self.bootstrap_discounts[self.size] = bootstrap_discount;
             self.size += 1;
         } else {
             self.buffer[self.position] = experience;
             self.priorities[self.position] = priority;
+            self.bootstrap_discounts[self.position] = bootstrap_discount;
         }
         
         self.position = (self.position + 1) % self.capacity;
     }
     
+    /// Bootstrap discount (`gamma^n`) of the n-step experience at a sampled index
+    #[must_use]
+    pub fn bootstrap_discount(&self, index: usize) -> Option<f64> {
+        if index < self.size {
+            self.bootstrap_discounts[index]
+        } else {
+            None
+        }
+    }
+    
+    /// Sampling probability of the experience at `index`
+    #[must_use]
+    pub fn probability(&self, index: usize) -> f64 {
+        if index >= self.size {
+            return 0.0;
+        }
+        let sum: f64 = self.priorities[..self.size].iter().sum();
+        self.priorities[index] / sum
+    }
+    
     /// Sample a batch with importance weights
     pub fn sample(&self, batch_size: usize) -> Option<(Vec<Experience<O, A, S>>, Vec<f64>, Vec<usize>)> {
         if self.size < batch_size {
@@ -201,4 +377,68 @@ where
     pub fn is_empty(&self) -> bool {
         self.size == 0
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentient_rl_core::action::DiscreteAction;
+    use sentient_rl_core::observation::VectorObservation;
+    use sentient_rl_core::state::VectorState;
+    use sentient_rl_core::Reward;
+
+    type Exp = Experience<VectorObservation, DiscreteAction, VectorState>;
+
+    fn step(t: usize, reward: f64, done: bool) -> Exp {
+        #[allow(clippy::cast_precision_loss)]
+        let obs = |i: usize| VectorObservation { data: vec![i as f64] };
+        Experience::from(Transition {
+            observation: obs(t),
+            action: DiscreteAction(0),
+            reward: Reward(reward),
+            next_observation: obs(t + 1),
+            done,
+            state: None,
+            next_state: None,
+        })
+    }
+
+    #[test]
+    fn test_n_step_prioritized_replay() {
+        let gamma = 0.5;
+        let mut buffer: PrioritizedReplayBuffer<VectorObservation, DiscreteAction, VectorState> =
+            PrioritizedReplayBuffer::new(16, 1.0, 0.4).with_n_step(3, gamma);
+
+        // Rewards 1, 2, 4, 8 then the episode ends on the fifth step
+        for (t, reward) in [1.0, 2.0, 4.0, 8.0, 16.0].into_iter().enumerate() {
+            buffer.push_n_step(step(t, reward, t == 4));
+        }
+        assert_eq!(buffer.len(), 5);
+
+        let (experiences, _, indices) = buffer.sample(5).unwrap();
+        for (experience, &index) in experiences.iter().zip(&indices) {
+            let transition = &experience.transition;
+            let start = transition.observation.data[0] as usize;
+            let steps = (5 - start).min(3);
+            let expected: f64 = (0..steps)
+                .map(|k| gamma.powi(k as i32) * 2f64.powi((start + k) as i32))
+                .sum();
+            assert!((transition.reward.0 - expected).abs() < 1e-9, "start {}", start);
+            assert_eq!(transition.next_observation.data[0] as usize, start + steps);
+            assert_eq!(buffer.bootstrap_discount(index), Some(gamma.powi(steps as i32)));
+        }
+
+        // Raising the priority of the first n-step experience concentrates sampling on it
+        let first = (0..buffer.len())
+            .find(|&i| buffer.buffer[i].transition.observation.data[0] == 0.0)
+            .unwrap();
+        let before = buffer.probability(first);
+        let priorities: Vec<f64> = (0..buffer.len()).map(|i| if i == first { 100.0 } else { 0.0 }).collect();
+        buffer.update_priorities(&(0..buffer.len()).collect::<Vec<_>>(), &priorities);
+        assert!(buffer.probability(first) > before);
+        assert!(buffer.probability(first) > 0.99);
+
+        let (resampled, _, _) = buffer.sample(5).unwrap();
+        assert!(resampled.iter().all(|e| (e.transition.reward.0 - 3.0).abs() < 1e-9));
+    }
+}
@@ -1,6 +1,27 @@
 //! Deep Q-Network (DQN) agent implementation
+//!
+//! An MLP Q-network trained on transitions sampled from a replay buffer,
+//! regressing on targets from a copy of itself that is synced every
+//! `target_update_freq` updates. Double DQN picks the next action with the
+//! online network and values it with the target one.
 
+use anyhow::Result;
+use ndarray::{Array1, Array2, ArrayView2, Axis};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use sentient_rl_core::observation::VectorObservation;
+use sentient_rl_core::{
+    AgentMode, DiscreteAction, Environment, SpaceSignature, SpaceSpec, Transition, VectorState,
+};
+
+use crate::buffer::ReplayBuffer;
+use crate::exploration::{argmax, EpsilonGreedy, ExplorationStrategy};
+use crate::policy::{Activation, MLPConfig, MLPPolicy, OutputGradient, PolicyNetwork};
+use crate::utils::Adam;
 
 /// DQN-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,12 +35,18 @@ pub struct DQNConfig {
     pub epsilon_end: f64,
     /// Epsilon decay steps
     pub epsilon_decay_steps: usize,
-    /// Update target network every N steps
+    /// Update target network every N steps. Serialized under its own key:
+    /// the flattened base config already has a `target_update_freq`.
+    #[serde(rename = "dqn_target_update_freq")]
     pub target_update_freq: usize,
     /// Use double DQN
     pub double_dqn: bool,
     /// Use dueling DQN
     pub dueling_dqn: bool,
+    /// Weight of the conservative Q-learning (CQL) penalty for offline
+    /// training on fixed datasets; 0 disables it
+    #[serde(default)]
+    pub cql_alpha: f64,
 }
 
 impl Default for DQNConfig {
@@ -32,27 +59,631 @@ impl Default for DQNConfig {
             target_update_freq: 1000,
             double_dqn: true,
             dueling_dqn: false,
+            cql_alpha: 0.0,
         }
     }
 }
 
-/// DQN Agent placeholder
-/// TODO: Implement full DQN agent with neural network support
+/// Loss on one batch of transitions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DQNLoss {
+    /// Mean squared TD error of the taken actions, halved
+    pub td_loss: f32,
+    /// Conservative penalty, already scaled by `cql_alpha`
+    pub cql_loss: f32,
+    /// `td_loss + cql_loss`
+    pub total: f32,
+}
+
+/// Exploration snapshot reported alongside training stats
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DQNStats {
+    /// Latest training step an action was selected for
+    pub step: usize,
+    /// Exploration rate currently in effect
+    pub exploration_rate: f32,
+    /// Whether the rate was set by hand rather than by the schedule
+    pub exploration_overridden: bool,
+}
+
+/// DQN agent for discrete action spaces
 pub struct DQNAgent {
     config: DQNConfig,
+    observation_dim: usize,
+    action_dim: usize,
+    online: MLPPolicy,
+    target: MLPPolicy,
+    optimizer: Adam,
+    replay: ReplayBuffer<VectorObservation, DiscreteAction, VectorState>,
+    exploration: Box<dyn ExplorationStrategy>,
+    /// Source of exploration and replay sampling
+    rng: Mutex<StdRng>,
+    /// Latest step passed to `select_action`
+    step: AtomicUsize,
+    /// Rate set with `set_exploration_rate`, used instead of the schedule
+    rate_override: Mutex<Option<f64>>,
+    /// Training or evaluation
+    mode: Mutex<AgentMode>,
+    /// Updates made, for syncing the target network
+    updates: usize,
+    total_timesteps: usize,
 }
 
 impl DQNAgent {
-    /// Create a new DQN agent
-    pub fn new(config: DQNConfig) -> Self {
-        Self { config }
+    /// Create a new DQN agent with epsilon-greedy exploration from the config
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compute config is unsupported.
+    pub async fn new(config: DQNConfig, observation_dim: usize, action_dim: usize) -> Result<Self> {
+        Self::build(config, observation_dim, action_dim, StdRng::from_entropy()).await
+    }
+
+    /// Create a DQN agent whose initial weights, exploration and replay
+    /// sampling are driven by `seed`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compute config is unsupported.
+    pub async fn new_seeded(
+        config: DQNConfig,
+        observation_dim: usize,
+        action_dim: usize,
+        seed: u64,
+    ) -> Result<Self> {
+        Self::build(config, observation_dim, action_dim, StdRng::seed_from_u64(seed)).await
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    async fn build(
+        config: DQNConfig,
+        observation_dim: usize,
+        action_dim: usize,
+        mut rng: StdRng,
+    ) -> Result<Self> {
+        config.base.compute.ensure_supported()?;
+        let network = MLPConfig {
+            input_dim: observation_dim,
+            hidden_dims: vec![64, 64],
+            output_dim: action_dim,
+            activation: Activation::ReLU,
+            use_value_head: false,
+            init_log_std: 0.0,
+            ..MLPConfig::default()
+        };
+        let online = MLPPolicy::with_rng(network.clone(), &mut rng);
+        let params = online.get_parameters().await?;
+        let mut target = MLPPolicy::with_rng(network, &mut rng);
+        target.set_parameters(&params).await?;
+
+        let exploration = EpsilonGreedy::linear(
+            config.epsilon_start,
+            config.epsilon_end,
+            config.epsilon_decay_steps,
+        );
+        Ok(Self {
+            observation_dim,
+            action_dim,
+            online,
+            target,
+            optimizer: Adam::new(config.base.learning_rate as f32, params.len()),
+            replay: ReplayBuffer::new(config.base.buffer_size),
+            exploration: Box::new(exploration),
+            rng: Mutex::new(rng),
+            step: AtomicUsize::new(0),
+            rate_override: Mutex::new(None),
+            mode: Mutex::new(AgentMode::Train),
+            updates: 0,
+            total_timesteps: 0,
+            config,
+        })
+    }
+
+    /// Replace the exploration strategy
+    #[must_use]
+    pub fn with_exploration(mut self, exploration: impl ExplorationStrategy + 'static) -> Self {
+        self.exploration = Box::new(exploration);
+        self
+    }
+
+    /// Pick an action from Q-values using the exploration strategy, or
+    /// greedily in [`AgentMode::Eval`]
+    pub fn select_action(&self, q_values: &[f32], step: usize) -> usize {
+        if self.mode().is_eval() {
+            return argmax(q_values);
+        }
+        self.step.store(step, Ordering::Relaxed);
+        let rate = self.rate_override().unwrap_or_else(|| self.exploration.rate(step));
+        let mut rng = self.rng.lock().unwrap_or_else(PoisonError::into_inner);
+        self.exploration.select_with_rng(q_values, rate, &mut *rng)
+    }
+
+    /// Q-values the online network gives `observation`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the observation doesn't fit the network.
+    pub async fn q_values(&self, observation: &Array1<f32>) -> Result<Array1<f32>> {
+        Ok(self.online.forward(&observation.view()).await?.action_output)
+    }
+
+    /// Action for `observation` at training step `step`, chosen by
+    /// [`Self::select_action`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the observation doesn't fit the network.
+    pub async fn act(&self, observation: &Array1<f32>, step: usize) -> Result<usize> {
+        let q_values = self.q_values(observation).await?;
+        Ok(self.select_action(q_values.as_slice().unwrap_or_default(), step))
+    }
+
+    /// Environment steps taken by [`Self::collect_and_train`]
+    #[must_use]
+    pub fn total_timesteps(&self) -> usize {
+        self.total_timesteps
+    }
+
+    /// Store a transition for replay
+    pub fn remember(&mut self, transition: Transition<VectorObservation, DiscreteAction, VectorState>) {
+        self.replay.push_transition(transition);
+    }
+
+    /// Actions and rewards of the latest `n` stored transitions, oldest
+    /// first
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn replay_trace(&self, n: usize) -> (Vec<usize>, Vec<f32>) {
+        let skip = self.replay.len().saturating_sub(n);
+        self.replay
+            .iter()
+            .skip(skip)
+            .map(|experience| (experience.transition.action.0, experience.transition.reward.0 as f32))
+            .unzip()
+    }
+
+    /// Step `env` for `n_steps` from a fresh episode, storing each
+    /// transition and, in training, running one update per step once the
+    /// replay buffer holds a batch. Returns the loss of the last update.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the environment or an update fails.
+    pub async fn collect_and_train<E>(&mut self, env: &mut E, n_steps: usize) -> Result<Option<DQNLoss>>
+    where
+        E: Environment<Observation = VectorObservation, Action = DiscreteAction>,
+    {
+        let (mut obs, _) = env.reset().await?;
+        let mut last = None;
+
+        for _ in 0..n_steps {
+            let action = DiscreteAction(self.act(&to_array(&obs), self.total_timesteps).await?);
+            let step = env.step(action).await?;
+            self.remember(Transition {
+                observation: obs,
+                action,
+                reward: step.reward,
+                next_observation: step.observation.clone(),
+                done: step.done,
+                state: None,
+                next_state: None,
+            });
+            self.total_timesteps += 1;
+
+            if !self.mode().is_eval() {
+                if let Some(loss) = self.train().await? {
+                    last = Some(loss);
+                }
+            }
+            obs = if step.done || step.truncated {
+                env.reset().await?.0
+            } else {
+                step.observation
+            };
+        }
+
+        Ok(last)
+    }
+
+    /// One gradient step of [`Self::loss`] on a sampled batch, syncing the
+    /// target network every `target_update_freq` updates. `None` until the
+    /// replay buffer holds `batch_size` transitions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a stored transition doesn't fit the network.
+    #[allow(clippy::cast_possible_truncation)]
+    pub async fn train(&mut self) -> Result<Option<DQNLoss>> {
+        let batch = {
+            let mut rng = self.rng.lock().unwrap_or_else(PoisonError::into_inner);
+            self.replay.sample_with(self.config.base.batch_size.max(1), &mut *rng)
+        };
+        let Some(batch) = batch else {
+            return Ok(None);
+        };
+        let gamma = self.config.base.gamma as f32;
+
+        let mut observations = Vec::with_capacity(batch.len());
+        let mut q_values = Array2::zeros((batch.len(), self.action_dim));
+        let mut actions = Vec::with_capacity(batch.len());
+        let mut targets = Vec::with_capacity(batch.len());
+        for (i, experience) in batch.iter().enumerate() {
+            let t = &experience.transition;
+            let obs = to_array(&t.observation);
+            q_values.row_mut(i).assign(&self.q_values(&obs).await?);
+            observations.push(obs);
+            actions.push(t.action.0);
+
+            let mut target = t.reward.0 as f32;
+            if !t.done {
+                let next = to_array(&t.next_observation);
+                let next_q = self.target.forward(&next.view()).await?.action_output;
+                let next_value = if self.config.double_dqn {
+                    let online = self.q_values(&next).await?;
+                    next_q[argmax(online.as_slice().unwrap_or_default())]
+                } else {
+                    next_q.fold(f32::NEG_INFINITY, |a, &b| a.max(b))
+                };
+                target += gamma * next_value;
+            }
+            targets.push(target);
+        }
+
+        let (loss, grad) = self.loss(q_values.view(), &actions, &targets)?;
+        let mut gradients = vec![0.0; self.optimizer.n_params()];
+        for (obs, row) in observations.iter().zip(grad.axis_iter(Axis(0))) {
+            let output_grad = OutputGradient { action_output: row.to_owned(), value: 0.0, log_std: None };
+            for (total, g) in gradients.iter_mut().zip(self.online.backward(&obs.view(), &output_grad)) {
+                *total += g;
+            }
+        }
+        let mut params = self.online.get_parameters().await?;
+        self.optimizer.step(&mut params, &gradients);
+        self.online.set_parameters(&params).await?;
+
+        self.updates += 1;
+        if self.updates % self.config.target_update_freq.max(1) == 0 {
+            self.target.set_parameters(&params).await?;
+        }
+        Ok(Some(loss))
+    }
+
+    /// Exploration rate in effect: 0 in evaluation, the override if one is
+    /// set, otherwise what the schedule gives at the latest step an action
+    /// was selected for
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn exploration_rate(&self) -> f32 {
+        if self.mode().is_eval() {
+            return 0.0;
+        }
+        let rate = self
+            .rate_override()
+            .unwrap_or_else(|| self.exploration.rate(self.step.load(Ordering::Relaxed)));
+        rate as f32
+    }
+
+    /// Pin the exploration rate to `rate` (negative or NaN means 0),
+    /// overriding the schedule until [`Self::clear_exploration_rate`]
+    pub fn set_exploration_rate(&self, rate: f32) {
+        self.store_rate_override(Some(f64::from(rate.max(0.0))));
+    }
+
+    /// Hand the exploration rate back to the schedule
+    pub fn clear_exploration_rate(&self) {
+        self.store_rate_override(None);
+    }
+
+    /// Whether the agent is training or evaluating
+    #[must_use]
+    pub fn mode(&self) -> AgentMode {
+        *self.mode.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Switch between training and evaluation. Evaluation zeroes the
+    /// exploration rate and leaves the training step where it was; the
+    /// schedule and any override resume when training does.
+    pub fn set_mode(&self, mode: AgentMode) {
+        *self.mode.lock().unwrap_or_else(PoisonError::into_inner) = mode;
+    }
+
+    /// Exploration snapshot for training stats and dashboards
+    #[must_use]
+    pub fn stats(&self) -> DQNStats {
+        DQNStats {
+            step: self.step.load(Ordering::Relaxed),
+            exploration_rate: self.exploration_rate(),
+            exploration_overridden: self.rate_override().is_some(),
+        }
+    }
+
+    /// Loss for a batch and its gradient with respect to `q_values`.
+    ///
+    /// `q_values` holds one row of action values per transition, `actions`
+    /// the action each transition took and `targets` its TD target. With
+    /// `cql_alpha > 0` the loss adds the CQL(H) penalty
+    /// `alpha * mean(logsumexp(Q(s, .)) - Q(s, a))`, which pushes down the
+    /// values of actions the dataset never took, so offline training doesn't
+    /// learn to trust them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch sizes disagree or an action is out of
+    /// range.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub fn loss(
+        &self,
+        q_values: ArrayView2<f32>,
+        actions: &[usize],
+        targets: &[f32],
+    ) -> Result<(DQNLoss, Array2<f32>)> {
+        let (batch, n_actions) = q_values.dim();
+        if actions.len() != batch || targets.len() != batch {
+            anyhow::bail!(
+                "Batch of {} Q-value rows has {} actions and {} targets",
+                batch,
+                actions.len(),
+                targets.len()
+            );
+        }
+        if let Some(&action) = actions.iter().find(|&&a| a >= n_actions) {
+            anyhow::bail!("Action {action} out of range for {n_actions} Q-values");
+        }
+
+        let alpha = self.config.cql_alpha.max(0.0) as f32;
+        let scale = 1.0 / batch.max(1) as f32;
+        let mut grad = Array2::zeros((batch, n_actions));
+        let (mut td_loss, mut cql_loss) = (0.0, 0.0);
+
+        for (i, row) in q_values.axis_iter(Axis(0)).enumerate() {
+            let (action, taken) = (actions[i], row[actions[i]]);
+            let error = taken - targets[i];
+            td_loss += 0.5 * error * error * scale;
+            grad[[i, action]] += error * scale;
+
+            if alpha > 0.0 {
+                // d/dQ logsumexp(Q) is softmax(Q)
+                let max_q = row.fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+                let exp = row.mapv(|q| (q - max_q).exp());
+                let sum = exp.sum();
+                cql_loss += alpha * (max_q + sum.ln() - taken) * scale;
+                for (a, e) in exp.iter().enumerate() {
+                    grad[[i, a]] += alpha * e / sum * scale;
+                }
+                grad[[i, action]] -= alpha * scale;
+            }
+        }
+
+        let loss = DQNLoss {
+            td_loss,
+            cql_loss,
+            total: td_loss + cql_loss,
+        };
+        Ok((loss, grad))
+    }
+
+    // A plain value behind the lock, so a panic elsewhere can't leave it
+    // half-written and poisoning is safe to ignore
+    fn rate_override(&self) -> Option<f64> {
+        *self.rate_override.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn store_rate_override(&self, rate: Option<f64>) {
+        *self.rate_override.lock().unwrap_or_else(PoisonError::into_inner) = rate;
+    }
+}
+
+impl SpaceSignature for DQNAgent {
+    fn observation_spec(&self) -> SpaceSpec {
+        SpaceSpec::continuous(self.observation_dim)
+    }
+
+    fn action_spec(&self) -> SpaceSpec {
+        SpaceSpec::discrete(self.action_dim)
     }
 }
 
-// Full implementation would include:
-// - Neural network Q-function
-// - Experience replay buffer integration
-// - Epsilon-greedy exploration
-// - Target network updates
-// - Training loop
-// - Double DQN and Dueling DQN variants
\ No newline at end of file
+#[allow(clippy::cast_possible_truncation)]
+fn to_array(observation: &VectorObservation) -> Array1<f32> {
+    observation.data.iter().map(|&x| x as f32).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use ndarray::array;
+    use sentient_rl_core::{
+        ActionSpace, BoxObservationSpace, DiscreteSpace, ObservationSpace, Reward, Step, StepInfo,
+    };
+
+    fn block_on<T>(future: impl std::future::Future<Output = T>) -> T {
+        tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(future)
+    }
+
+    fn agent(config: DQNConfig) -> DQNAgent {
+        block_on(DQNAgent::new(config, 1, 2)).unwrap()
+    }
+
+    /// Fit tabular Q-values to a fixed dataset by gradient descent on the
+    /// DQN loss
+    fn fit_offline(cql_alpha: f64) -> (DQNLoss, Array2<f32>) {
+        let agent = agent(DQNConfig {
+            cql_alpha,
+            ..DQNConfig::default()
+        });
+        // Two states; the dataset only ever took actions 0 and 1
+        let actions = [0, 1];
+        let targets = [1.0, 0.5];
+        let mut q = Array2::<f32>::zeros((2, 3));
+
+        let (first, _) = agent.loss(q.view(), &actions, &targets).unwrap();
+        for _ in 0..500 {
+            let (_, grad) = agent.loss(q.view(), &actions, &targets).unwrap();
+            q -= &(grad * 0.5);
+        }
+        (first, q)
+    }
+
+    #[test]
+    fn test_exploration_override_replaces_schedule_until_cleared() {
+        let agent = agent(DQNConfig {
+            epsilon_start: 1.0,
+            epsilon_end: 0.0,
+            epsilon_decay_steps: 100,
+            ..DQNConfig::default()
+        });
+        let q = [0.0, 1.0];
+
+        agent.select_action(&q, 10);
+        assert!((agent.exploration_rate() - 0.9).abs() < 1e-6);
+        assert!(!agent.stats().exploration_overridden);
+
+        // Greedy while pinned, although the schedule says to explore
+        agent.set_exploration_rate(0.0);
+        for _ in 0..200 {
+            assert_eq!(agent.select_action(&q, 10), 1);
+        }
+        let stats = agent.stats();
+        assert!(stats.exploration_overridden);
+        assert!(stats.exploration_rate.abs() < f32::EPSILON);
+        assert_eq!(stats.step, 10);
+
+        agent.clear_exploration_rate();
+        assert!((agent.exploration_rate() - 0.9).abs() < 1e-6);
+        agent.select_action(&q, 100);
+        assert!(agent.exploration_rate().abs() < 1e-6);
+        assert!(!agent.stats().exploration_overridden);
+    }
+
+    #[test]
+    fn test_eval_mode_is_greedy_and_keeps_the_schedule() {
+        let agent = agent(DQNConfig {
+            epsilon_start: 1.0,
+            epsilon_end: 1.0,
+            ..DQNConfig::default()
+        });
+        let q = [0.3, -1.0, 0.9, 0.9];
+        agent.select_action(&q, 5);
+
+        agent.set_mode(AgentMode::Eval);
+        for step in 0..200 {
+            assert_eq!(agent.select_action(&q, step), 2);
+        }
+        assert!(agent.exploration_rate().abs() < f32::EPSILON);
+        assert_eq!(agent.stats().step, 5);
+
+        agent.set_mode(AgentMode::Train);
+        assert!((agent.exploration_rate() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_cql_penalty_pushes_down_unseen_actions() {
+        let (plain_loss, plain_q) = fit_offline(0.0);
+        let (cql_loss, cql_q) = fit_offline(1.0);
+
+        // Same TD error to start with, plus log(3) per row for CQL
+        assert!(plain_loss.cql_loss.abs() < f32::EPSILON);
+        assert!((cql_loss.td_loss - plain_loss.td_loss).abs() < 1e-6);
+        assert!((cql_loss.cql_loss - 3f32.ln()).abs() < 1e-5);
+        assert!((cql_loss.total - cql_loss.td_loss - cql_loss.cql_loss).abs() < 1e-6);
+
+        // Without the penalty the unseen action keeps its initial value;
+        // with it, it ends up below every action the dataset took
+        for (state, &taken) in [0, 1].iter().enumerate() {
+            let unseen = 2;
+            assert!(plain_q[[state, unseen]].abs() < f32::EPSILON);
+            assert!(cql_q[[state, unseen]] < 0.0, "{cql_q:?}");
+            assert!(cql_q[[state, unseen]] < cql_q[[state, taken]] - 0.5, "{cql_q:?}");
+            let plain_gap = plain_q[[state, taken]] - plain_q[[state, unseen]];
+            let cql_gap = cql_q[[state, taken]] - cql_q[[state, unseen]];
+            assert!(cql_gap > plain_gap, "{cql_q:?} vs {plain_q:?}");
+        }
+    }
+
+    #[test]
+    fn test_loss_rejects_mismatched_batch() {
+        let agent = agent(DQNConfig::default());
+        let q = array![[0.0_f32, 1.0], [2.0, 3.0]];
+        assert!(agent.loss(q.view(), &[0], &[1.0, 1.0]).is_err());
+        assert!(agent.loss(q.view(), &[0, 2], &[1.0, 1.0]).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_device_is_rejected() {
+        let mut config = DQNConfig::default();
+        config.base.compute.device = sentient_rl_core::Device::Cuda(0);
+        let err = block_on(DQNAgent::new(config, 1, 2)).err().unwrap().to_string();
+        assert!(err.contains("device cuda:0 requested"), "{}", err);
+    }
+
+    #[test]
+    fn test_config_round_trips_through_json() {
+        let config = DQNConfig {
+            target_update_freq: 7,
+            ..DQNConfig::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        let back: DQNConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.target_update_freq, 7);
+        assert_eq!(back.base.target_update_freq, config.base.target_update_freq);
+    }
+
+    /// One-step episodes where action 1 pays 1 and action 0 nothing
+    struct BanditEnv;
+
+    #[async_trait]
+    impl Environment for BanditEnv {
+        type Observation = VectorObservation;
+        type Action = DiscreteAction;
+        type State = VectorState;
+
+        fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = VectorObservation>> {
+            Box::new(BoxObservationSpace::new(vec![0.0], vec![1.0], vec![1]).unwrap())
+        }
+
+        fn action_space(&self) -> Box<dyn ActionSpace<Action = DiscreteAction>> {
+            Box::new(DiscreteSpace::new(2))
+        }
+
+        async fn reset(&mut self) -> sentient_rl_core::Result<(VectorObservation, StepInfo)> {
+            Ok((VectorObservation { data: vec![1.0] }, StepInfo::default()))
+        }
+
+        async fn step(&mut self, action: DiscreteAction) -> sentient_rl_core::Result<Step<VectorObservation, VectorState>> {
+            Ok(Step {
+                observation: VectorObservation { data: vec![1.0] },
+                reward: Reward(action.0 as f64),
+                done: true,
+                truncated: false,
+                info: StepInfo::default(),
+                state: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_training_learns_the_paying_action() {
+        let mut config = DQNConfig {
+            epsilon_start: 1.0,
+            epsilon_end: 0.2,
+            epsilon_decay_steps: 100,
+            target_update_freq: 10,
+            ..DQNConfig::default()
+        };
+        config.base.batch_size = 16;
+        let mut agent = DQNAgent::new_seeded(config, 1, 2, 3).await.unwrap();
+        let mut env = BanditEnv;
+
+        assert!(agent.train().await.unwrap().is_none());
+        let first = agent.collect_and_train(&mut env, 20).await.unwrap().unwrap();
+        let last = agent.collect_and_train(&mut env, 300).await.unwrap().unwrap();
+        assert!(last.td_loss < first.td_loss, "{:?} vs {:?}", last, first);
+        assert_eq!(agent.total_timesteps(), 320);
+
+        let q = agent.q_values(&array![1.0]).await.unwrap();
+        assert!((q[1] - 1.0).abs() < 0.2, "{}", q);
+        assert!(q[0].abs() < 0.2, "{}", q);
+        agent.set_mode(AgentMode::Eval);
+        assert_eq!(agent.act(&array![1.0], 0).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,172 @@
+//! Exploration strategies for value-based agents
+//!
+//! Strategies pick an action index from a vector of action values. Both
+//! implementations are driven by a [`Schedule`] so exploration can be
+//! annealed over training steps.
+
+use rand::{Rng, RngCore};
+
+use crate::utils::{ConstantSchedule, LinearSchedule, Schedule};
+
+/// Picks an action from Q-values
+pub trait ExplorationStrategy: Send + Sync {
+    /// The strategy's exploration rate (epsilon, temperature) at training
+    /// step `step`
+    fn rate(&self, step: usize) -> f64;
+
+    /// Select an action index given the Q-values and an exploration rate,
+    /// drawing any randomness from `rng`
+    fn select_with_rng(&self, q_values: &[f32], rate: f64, rng: &mut dyn RngCore) -> usize;
+
+    /// Select an action index given the Q-values and an exploration rate
+    fn select_with_rate(&self, q_values: &[f32], rate: f64) -> usize {
+        self.select_with_rng(q_values, rate, &mut rand::thread_rng())
+    }
+
+    /// Select an action index given the Q-values at training step `step`
+    fn select(&self, q_values: &[f32], step: usize) -> usize {
+        self.select_with_rate(q_values, self.rate(step))
+    }
+}
+
+/// Index of the largest Q-value (first one on ties)
+#[must_use]
+pub fn argmax(q_values: &[f32]) -> usize {
+    q_values
+        .iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |best, (i, &q)| if q > best.1 { (i, q) } else { best })
+        .0
+}
+
+/// Random action with probability epsilon, greedy otherwise
+pub struct EpsilonGreedy {
+    epsilon: Box<dyn Schedule>,
+}
+
+impl EpsilonGreedy {
+    /// Create a strategy whose epsilon follows `schedule`
+    pub fn new(schedule: impl Schedule + 'static) -> Self {
+        Self {
+            epsilon: Box::new(schedule),
+        }
+    }
+
+    /// Fixed epsilon
+    #[must_use]
+    pub fn constant(epsilon: f64) -> Self {
+        Self::new(ConstantSchedule { value: epsilon })
+    }
+
+    /// Epsilon decaying linearly from `start` to `end` over `steps`
+    #[must_use]
+    pub fn linear(start: f64, end: f64, steps: usize) -> Self {
+        Self::new(LinearSchedule::new(start, end, steps))
+    }
+
+    /// Epsilon at training step `step`
+    #[must_use]
+    pub fn epsilon(&self, step: usize) -> f64 {
+        self.epsilon.value(step).clamp(0.0, 1.0)
+    }
+}
+
+impl ExplorationStrategy for EpsilonGreedy {
+    fn rate(&self, step: usize) -> f64 {
+        self.epsilon(step)
+    }
+
+    fn select_with_rng(&self, q_values: &[f32], rate: f64, rng: &mut dyn RngCore) -> usize {
+        if !q_values.is_empty() && rng.gen::<f64>() < rate.clamp(0.0, 1.0) {
+            rng.gen_range(0..q_values.len())
+        } else {
+            argmax(q_values)
+        }
+    }
+}
+
+/// Samples actions from a softmax over Q-values divided by a temperature
+pub struct Boltzmann {
+    temperature: Box<dyn Schedule>,
+}
+
+impl Boltzmann {
+    /// Create a strategy whose temperature follows `schedule`
+    pub fn new(schedule: impl Schedule + 'static) -> Self {
+        Self {
+            temperature: Box::new(schedule),
+        }
+    }
+
+    /// Fixed temperature
+    #[must_use]
+    pub fn constant(temperature: f64) -> Self {
+        Self::new(ConstantSchedule { value: temperature })
+    }
+
+    /// Action probabilities at training step `step`
+    #[must_use]
+    pub fn probabilities(&self, q_values: &[f32], step: usize) -> Vec<f32> {
+        Self::probabilities_at(q_values, self.temperature.value(step))
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn probabilities_at(q_values: &[f32], temperature: f64) -> Vec<f32> {
+        let temperature = temperature.max(1e-6) as f32;
+        let max_q = q_values.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+        let exp: Vec<f32> = q_values.iter().map(|&q| ((q - max_q) / temperature).exp()).collect();
+        let sum: f32 = exp.iter().sum();
+        exp.into_iter().map(|e| e / sum).collect()
+    }
+}
+
+impl ExplorationStrategy for Boltzmann {
+    fn rate(&self, step: usize) -> f64 {
+        self.temperature.value(step)
+    }
+
+    fn select_with_rng(&self, q_values: &[f32], rate: f64, rng: &mut dyn RngCore) -> usize {
+        let probs = Self::probabilities_at(q_values, rate);
+        let sample = rng.gen::<f32>();
+        let mut cumsum = 0.0;
+        for (i, &p) in probs.iter().enumerate() {
+            cumsum += p;
+            if sample < cumsum {
+                return i;
+            }
+        }
+        argmax(q_values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const Q: [f32; 4] = [0.5, 2.0, 1.9, -1.0];
+
+    #[test]
+    fn test_epsilon_zero_is_argmax() {
+        let strategy = EpsilonGreedy::constant(0.0);
+        for step in 0..200 {
+            assert_eq!(strategy.select(&Q, step), 1);
+        }
+
+        let decayed = EpsilonGreedy::linear(1.0, 0.0, 100);
+        assert!((decayed.epsilon(50) - 0.5).abs() < 1e-9);
+        assert_eq!(decayed.select(&Q, 100), 1);
+    }
+
+    #[test]
+    fn test_boltzmann_low_temperature_approaches_argmax() {
+        let cold = Boltzmann::constant(0.01);
+        let probs = cold.probabilities(&Q, 0);
+        assert!(probs[1] > 0.99, "{:?}", probs);
+        let greedy = (0..500).filter(|&step| cold.select(&Q, step) == 1).count();
+        assert!(greedy >= 495);
+
+        // A hot softmax spreads probability across actions
+        let hot = Boltzmann::constant(100.0).probabilities(&Q, 0);
+        assert!(hot.iter().all(|&p| p > 0.2));
+    }
+}
@@ -0,0 +1,421 @@
+//! Golden-file replay of seeded training runs
+//!
+//! [`GoldenRecorder`] trains a seeded agent for a few rollouts and records
+//! every action, reward and per-update loss. Saved to a golden file, the run
+//! becomes a regression guard: replaying it with the same seed must reproduce
+//! the actions exactly and the floats within a tolerance, so any change to
+//! the training math shows up as a divergence at a specific step.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use sentient_rl_core::observation::VectorObservation;
+use sentient_rl_core::{DiscreteAction, Environment};
+
+use crate::a2c::{A2CAgent, A2CConfig};
+use crate::dqn::{DQNAgent, DQNConfig};
+use crate::ppo::PPOConfig;
+use crate::ppo_full::PPOAgentFull;
+
+/// Algorithm a golden run trains, with its configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "algorithm", content = "config", rename_all = "lowercase")]
+pub enum GoldenAgent {
+    /// [`A2CAgent`], one update per rollout
+    A2C(A2CConfig),
+    /// [`PPOAgentFull`], one update per rollout
+    PPO(PPOConfig),
+    /// [`DQNAgent`], one update per step once the replay buffer holds a
+    /// batch; a rollout's losses are those of its last update
+    DQN(DQNConfig),
+}
+
+impl Default for GoldenAgent {
+    fn default() -> Self {
+        Self::A2C(A2CConfig::default())
+    }
+}
+
+/// One rollout of a recorded run and the update trained on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenRollout {
+    /// Action taken at each step
+    pub actions: Vec<usize>,
+    /// Reward received at each step
+    pub rewards: Vec<f32>,
+    /// Losses of the update by name: `policy_loss`, `value_loss` and
+    /// `entropy` for A2C and PPO, `td_loss` and `cql_loss` for DQN. Empty
+    /// when DQN hasn't updated yet.
+    pub losses: BTreeMap<String, f32>,
+}
+
+/// A recorded training run, with what is needed to re-run it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenRun {
+    /// Seed of the agent and environment
+    pub seed: u64,
+    /// Algorithm and its configuration
+    #[serde(flatten)]
+    pub agent: GoldenAgent,
+    /// Environment steps per rollout
+    pub rollout_steps: usize,
+    /// Rollouts in the order they were trained on
+    pub rollouts: Vec<GoldenRollout>,
+}
+
+impl GoldenRun {
+    /// Write the run as pretty-printed JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be written.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write golden run {}", path.display()))
+    }
+
+    /// Read a run written by [`GoldenRun::save`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or parsed.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read golden run {}", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Invalid golden run {}", path.display()))
+    }
+
+    /// Check that this run reproduces `expected`: identical actions, and
+    /// rewards and losses within `tol`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error describing the first divergence.
+    pub fn compare(&self, expected: &GoldenRun, tol: f32) -> Result<()> {
+        if self.rollouts.len() != expected.rollouts.len() {
+            anyhow::bail!(
+                "Run has {} rollouts, golden run has {}",
+                self.rollouts.len(),
+                expected.rollouts.len()
+            );
+        }
+
+        for (i, (actual, golden)) in self.rollouts.iter().zip(&expected.rollouts).enumerate() {
+            if actual.actions.len() != golden.actions.len() {
+                anyhow::bail!(
+                    "Rollout {}: {} steps, golden run has {}",
+                    i,
+                    actual.actions.len(),
+                    golden.actions.len()
+                );
+            }
+            if let Some(step) =
+                (0..actual.actions.len()).find(|&t| actual.actions[t] != golden.actions[t])
+            {
+                anyhow::bail!(
+                    "Rollout {} step {}: action {}, golden run took {}",
+                    i,
+                    step,
+                    actual.actions[step],
+                    golden.actions[step]
+                );
+            }
+            for (step, (&a, &g)) in actual.rewards.iter().zip(&golden.rewards).enumerate() {
+                check_close(&format!("Rollout {i} step {step} reward"), a, g, tol)?;
+            }
+            if !actual.losses.keys().eq(golden.losses.keys()) {
+                anyhow::bail!(
+                    "Rollout {}: losses {:?}, golden run has {:?}",
+                    i,
+                    actual.losses.keys().collect::<Vec<_>>(),
+                    golden.losses.keys().collect::<Vec<_>>()
+                );
+            }
+            for (name, &g) in &golden.losses {
+                let what = format!("Rollout {i} {}", name.replace('_', " "));
+                check_close(&what, actual.losses[name], g, tol)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_close(what: &str, actual: f32, golden: f32, tol: f32) -> Result<()> {
+    // Written out so a NaN on either side counts as a divergence
+    if (actual - golden).abs() <= tol {
+        Ok(())
+    } else {
+        anyhow::bail!("{what}: {actual}, golden run has {golden} (tolerance {tol})")
+    }
+}
+
+/// Records seeded training runs and replays them against golden files
+pub struct GoldenRecorder<F> {
+    make_env: F,
+    observation_dim: usize,
+    action_dim: usize,
+    /// Algorithm and configuration for new recordings
+    pub agent: GoldenAgent,
+    /// Rollout/update iterations per recording
+    pub rollouts: usize,
+    /// Environment steps per rollout
+    pub rollout_steps: usize,
+}
+
+impl<F, E> GoldenRecorder<F>
+where
+    F: FnMut(u64) -> Result<E>,
+    E: Environment<Observation = VectorObservation, Action = DiscreteAction>,
+{
+    /// Create a recorder building an environment seeded with the run's seed
+    /// via `make_env`
+    pub fn new(make_env: F, observation_dim: usize, action_dim: usize) -> Self {
+        Self {
+            make_env,
+            observation_dim,
+            action_dim,
+            agent: GoldenAgent::default(),
+            rollouts: 3,
+            rollout_steps: 64,
+        }
+    }
+
+    /// Train a fresh agent with `seed` and record the run
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the environment or agent can't be built, or
+    /// training fails.
+    pub fn record(&mut self, seed: u64) -> Result<GoldenRun> {
+        let agent = self.agent.clone();
+        let rollout_steps = self.rollout_steps;
+        self.run(agent, seed, rollout_steps)
+    }
+
+    /// Record a run with `seed` and save it as a golden file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if recording fails or the file can't be written.
+    pub fn record_golden(&mut self, path: &Path, seed: u64) -> Result<GoldenRun> {
+        let run = self.record(seed)?;
+        run.save(path)?;
+        Ok(run)
+    }
+
+    /// Re-run the golden file at `path` with its own seed and configuration
+    /// and compare the outputs within `tol`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be loaded, the run fails, or it
+    /// diverges from the golden run.
+    pub fn replay(&mut self, path: &Path, tol: f32) -> Result<()> {
+        let golden = GoldenRun::load(path)?;
+        let actual = self.run(golden.agent.clone(), golden.seed, golden.rollout_steps)?;
+        actual
+            .compare(&golden, tol)
+            .with_context(|| format!("Run diverged from golden file {}", path.display()))
+    }
+
+    /// [`GoldenRecorder::replay`] for tests
+    ///
+    /// # Panics
+    ///
+    /// Panics with the first divergence if the replay doesn't match.
+    pub fn assert_run_matches_golden(&mut self, path: &Path, tol: f32) {
+        if let Err(e) = self.replay(path, tol) {
+            panic!("{e:#}");
+        }
+    }
+
+    fn run(&mut self, agent: GoldenAgent, seed: u64, rollout_steps: usize) -> Result<GoldenRun> {
+        let mut env = (self.make_env)(seed)?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+
+        let (observation_dim, action_dim, n_rollouts) =
+            (self.observation_dim, self.action_dim, self.rollouts);
+        let mut rollouts = Vec::with_capacity(n_rollouts);
+        runtime.block_on(async {
+            match &agent {
+                GoldenAgent::A2C(config) => {
+                    let config = A2CConfig {
+                        n_steps: rollout_steps,
+                        ..config.clone()
+                    };
+                    let a2c = A2CAgent::new_seeded(config, observation_dim, action_dim, seed)?;
+                    for _ in 0..n_rollouts {
+                        a2c.collect_rollout(&mut env).await?;
+                        let (actions, rewards) = a2c.rollout_trace().await;
+                        let stats = a2c.train().await?;
+                        rollouts.push(GoldenRollout {
+                            actions,
+                            rewards,
+                            losses: actor_critic_losses(stats.policy_loss, stats.value_loss, stats.entropy),
+                        });
+                    }
+                }
+                GoldenAgent::PPO(config) => {
+                    let config = PPOConfig {
+                        n_steps: rollout_steps,
+                        ..config.clone()
+                    };
+                    let ppo =
+                        PPOAgentFull::new_seeded(config, observation_dim, action_dim, seed).await?;
+                    for _ in 0..n_rollouts {
+                        ppo.collect_rollout(&mut env).await?;
+                        let (actions, rewards) = ppo.rollout_trace().await;
+                        let stats = ppo.train().await?;
+                        rollouts.push(GoldenRollout {
+                            actions,
+                            rewards,
+                            losses: actor_critic_losses(stats.policy_loss, stats.value_loss, stats.entropy),
+                        });
+                    }
+                }
+                GoldenAgent::DQN(config) => {
+                    let mut dqn =
+                        DQNAgent::new_seeded(config.clone(), observation_dim, action_dim, seed).await?;
+                    for _ in 0..n_rollouts {
+                        let loss = dqn.collect_and_train(&mut env, rollout_steps).await?;
+                        let (actions, rewards) = dqn.replay_trace(rollout_steps);
+                        let losses = loss
+                            .map(|loss| {
+                                BTreeMap::from([
+                                    ("td_loss".to_string(), loss.td_loss),
+                                    ("cql_loss".to_string(), loss.cql_loss),
+                                ])
+                            })
+                            .unwrap_or_default();
+                        rollouts.push(GoldenRollout {
+                            actions,
+                            rewards,
+                            losses,
+                        });
+                    }
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        })?;
+
+        Ok(GoldenRun {
+            seed,
+            agent,
+            rollout_steps,
+            rollouts,
+        })
+    }
+}
+
+fn actor_critic_losses(policy_loss: f32, value_loss: f32, entropy: f32) -> BTreeMap<String, f32> {
+    BTreeMap::from([
+        ("policy_loss".to_string(), policy_loss),
+        ("value_loss".to_string(), value_loss),
+        ("entropy".to_string(), entropy),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentient_rl_core::EnvironmentConfig;
+    use sentient_rl_env::CartPoleEnv;
+
+    fn cartpole(seed: u64) -> Result<CartPoleEnv> {
+        Ok(CartPoleEnv::new(EnvironmentConfig {
+            seed: Some(seed),
+            ..EnvironmentConfig::default()
+        })?)
+    }
+
+    #[test]
+    fn test_golden_run_replays_and_perturbed_run_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("a2c_cartpole.json");
+
+        let mut recorder = GoldenRecorder::new(cartpole, 4, 2);
+        let golden = recorder.record_golden(&path, 7).unwrap();
+        assert_eq!(golden.rollouts.len(), 3);
+        assert!(golden.rollouts.iter().all(|r| r.actions.len() == 64));
+
+        recorder.assert_run_matches_golden(&path, 1e-6);
+
+        // Different training math: same first rollout, then the losses and
+        // eventually the actions drift
+        let mut config = A2CConfig::default();
+        config.base.learning_rate *= 10.0;
+        recorder.agent = GoldenAgent::A2C(config);
+        let perturbed = recorder.record(7).unwrap();
+        let err = perturbed.compare(&golden, 1e-6).unwrap_err().to_string();
+        assert!(err.starts_with("Rollout "), "{}", err);
+
+        // A golden file that no longer matches the code fails the replay
+        let mut tampered = golden.clone();
+        *tampered.rollouts[1].losses.get_mut("value_loss").unwrap() += 1e-3;
+        tampered.save(&path).unwrap();
+        let err = format!("{:#}", recorder.replay(&path, 1e-6).unwrap_err());
+        assert!(err.contains("Rollout 1 value loss"), "{}", err);
+    }
+
+    #[test]
+    fn test_ppo_golden_run_replays_and_perturbed_run_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ppo_cartpole.json");
+
+        let mut recorder = GoldenRecorder::new(cartpole, 4, 2);
+        recorder.agent = GoldenAgent::PPO(PPOConfig::default());
+        let golden = recorder.record_golden(&path, 7).unwrap();
+        assert_eq!(golden.rollouts.len(), 3);
+        assert!(golden.rollouts.iter().all(|r| r.actions.len() == 64));
+        assert!(std::fs::read_to_string(&path).unwrap().contains("\"algorithm\": \"ppo\""));
+
+        recorder.assert_run_matches_golden(&path, 1e-6);
+
+        let mut config = PPOConfig::default();
+        config.base.learning_rate *= 10.0;
+        recorder.agent = GoldenAgent::PPO(config);
+        let perturbed = recorder.record(7).unwrap();
+        let err = perturbed.compare(&golden, 1e-6).unwrap_err().to_string();
+        assert!(err.starts_with("Rollout "), "{}", err);
+
+        let mut tampered = golden.clone();
+        *tampered.rollouts[1].losses.get_mut("policy_loss").unwrap() += 1e-3;
+        tampered.save(&path).unwrap();
+        let err = format!("{:#}", recorder.replay(&path, 1e-6).unwrap_err());
+        assert!(err.contains("Rollout 1 policy loss"), "{}", err);
+    }
+
+    #[test]
+    fn test_dqn_golden_run_replays_and_perturbed_run_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("dqn_cartpole.json");
+
+        let mut recorder = GoldenRecorder::new(cartpole, 4, 2);
+        recorder.agent = GoldenAgent::DQN(DQNConfig::default());
+        let golden = recorder.record_golden(&path, 7).unwrap();
+        assert_eq!(golden.rollouts.len(), 3);
+        assert!(golden.rollouts.iter().all(|r| r.actions.len() == 64));
+        // The default batch of 32 fills during the first rollout
+        assert!(golden.rollouts.iter().all(|r| r.losses.contains_key("td_loss")));
+
+        recorder.assert_run_matches_golden(&path, 1e-6);
+
+        let mut config = DQNConfig::default();
+        config.base.learning_rate *= 10.0;
+        recorder.agent = GoldenAgent::DQN(config);
+        let perturbed = recorder.record(7).unwrap();
+        let err = perturbed.compare(&golden, 1e-6).unwrap_err().to_string();
+        assert!(err.starts_with("Rollout "), "{}", err);
+
+        let mut tampered = golden.clone();
+        *tampered.rollouts[1].losses.get_mut("td_loss").unwrap() += 1e-3;
+        tampered.save(&path).unwrap();
+        let err = format!("{:#}", recorder.replay(&path, 1e-6).unwrap_err());
+        assert!(err.contains("Rollout 1 td loss"), "{}", err);
+    }
+}
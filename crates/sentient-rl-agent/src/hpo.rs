@@ -0,0 +1,559 @@
+//! Random-search hyperparameter optimization
+//!
+//! [`random_search`] samples configurations from a [`SearchSpace`], hands each
+//! one to a [`Trainer`] for a short training budget and ranks them by the
+//! evaluation score it returns. Sampling uses a seeded RNG, so the same seed
+//! always proposes the same configurations in the same order.
+
+use anyhow::{Context, Result};
+use ndarray::Array1;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use sentient_rl_core::observation::VectorObservation;
+use sentient_rl_core::{AgentMode, DiscreteAction, Environment, Policy};
+
+use crate::a2c::{A2CAgent, A2CConfig};
+use crate::ppo::PPOConfig;
+use crate::ppo_full::PPOAgentFull;
+
+/// Named hyperparameter values for one trial
+pub type HyperParams = BTreeMap<String, f64>;
+
+/// Range a single hyperparameter is drawn from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamRange {
+    /// Uniform in `[low, high)`
+    Uniform {
+        /// Lower bound
+        low: f64,
+        /// Upper bound
+        high: f64,
+    },
+    /// Log-uniform in `[low, high)`, for scale parameters like learning rates
+    LogUniform {
+        /// Lower bound, must be positive
+        low: f64,
+        /// Upper bound
+        high: f64,
+    },
+    /// One of a fixed set of values
+    Choice(Vec<f64>),
+}
+
+impl ParamRange {
+    fn validate(&self, name: &str) -> Result<()> {
+        match self {
+            ParamRange::Uniform { low, high } if low < high => Ok(()),
+            ParamRange::LogUniform { low, high } if *low > 0.0 && low < high => Ok(()),
+            ParamRange::Choice(values) if !values.is_empty() => Ok(()),
+            other => anyhow::bail!("Invalid range for '{}': {:?}", name, other),
+        }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> f64 {
+        match self {
+            ParamRange::Uniform { low, high } => rng.gen_range(*low..*high),
+            ParamRange::LogUniform { low, high } => rng.gen_range(low.ln()..high.ln()).exp(),
+            ParamRange::Choice(values) => values[rng.gen_range(0..values.len())],
+        }
+    }
+}
+
+/// Hyperparameters to search and the range of each
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchSpace {
+    params: BTreeMap<String, ParamRange>,
+}
+
+impl SearchSpace {
+    /// Create an empty search space
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a parameter with an arbitrary range
+    #[must_use]
+    pub fn param(mut self, name: &str, range: ParamRange) -> Self {
+        self.params.insert(name.to_string(), range);
+        self
+    }
+
+    /// Add a parameter drawn uniformly from `[low, high)`
+    #[must_use]
+    pub fn uniform(self, name: &str, low: f64, high: f64) -> Self {
+        self.param(name, ParamRange::Uniform { low, high })
+    }
+
+    /// Add a parameter drawn log-uniformly from `[low, high)`
+    #[must_use]
+    pub fn log_uniform(self, name: &str, low: f64, high: f64) -> Self {
+        self.param(name, ParamRange::LogUniform { low, high })
+    }
+
+    /// Add a parameter picked from `values`
+    #[must_use]
+    pub fn choice(self, name: &str, values: &[f64]) -> Self {
+        self.param(name, ParamRange::Choice(values.to_vec()))
+    }
+
+    /// Whether no parameters have been added
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> HyperParams {
+        self.params
+            .iter()
+            .map(|(name, range)| (name.clone(), range.sample(rng)))
+            .collect()
+    }
+}
+
+/// Trains and evaluates one configuration
+pub trait Trainer {
+    /// Train a fresh agent built from `params` for the trainer's budget and
+    /// return its evaluation score (higher is better)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration is invalid or training fails.
+    fn train_and_evaluate(&mut self, params: &HyperParams, seed: u64) -> Result<f64>;
+}
+
+impl<F> Trainer for F
+where
+    F: FnMut(&HyperParams, u64) -> Result<f64>,
+{
+    fn train_and_evaluate(&mut self, params: &HyperParams, seed: u64) -> Result<f64> {
+        self(params, seed)
+    }
+}
+
+/// One evaluated configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trial {
+    /// Sampled hyperparameters
+    pub params: HyperParams,
+    /// Evaluation score returned by the trainer
+    pub score: f64,
+    /// Seed the trainer was given for this trial
+    pub seed: u64,
+}
+
+/// Evaluate up to `n_trials` distinct configurations sampled from `space`,
+/// returning them best first.
+///
+/// Repeated samples are skipped, so a small discrete space is covered without
+/// evaluating the same configuration twice; fewer than `n_trials` trials are
+/// returned if the space runs out. Non-finite scores rank last.
+///
+/// # Errors
+///
+/// Returns an error if the space is empty or has an invalid range, or if the
+/// trainer fails on any configuration.
+pub fn random_search<T: Trainer>(
+    space: &SearchSpace,
+    n_trials: usize,
+    seed: u64,
+    trainer: &mut T,
+) -> Result<Vec<Trial>> {
+    if space.is_empty() {
+        anyhow::bail!("Search space has no parameters");
+    }
+    for (name, range) in &space.params {
+        range.validate(name)?;
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut trials: Vec<Trial> = Vec::with_capacity(n_trials);
+    let max_attempts = n_trials.saturating_mul(10);
+
+    for _ in 0..max_attempts {
+        if trials.len() >= n_trials {
+            break;
+        }
+        let params = space.sample(&mut rng);
+        if trials.iter().any(|t| t.params == params) {
+            continue;
+        }
+
+        let trial_seed = rng.gen();
+        let score = trainer
+            .train_and_evaluate(&params, trial_seed)
+            .with_context(|| format!("Trial {} failed for {:?}", trials.len(), params))?;
+        tracing::debug!(trial = trials.len(), ?params, score, "HPO trial finished");
+        trials.push(Trial {
+            params,
+            score,
+            seed: trial_seed,
+        });
+    }
+
+    trials.sort_by(|a, b| {
+        let a_score = if a.score.is_finite() {
+            a.score
+        } else {
+            f64::NEG_INFINITY
+        };
+        let b_score = if b.score.is_finite() {
+            b.score
+        } else {
+            f64::NEG_INFINITY
+        };
+        b_score.total_cmp(&a_score)
+    });
+    Ok(trials)
+}
+
+/// [`Trainer`] running A2C for a fixed number of rollouts, scored by the mean
+/// undiscounted return over a few evaluation episodes. The trial seed drives
+/// both the agent and the environment, so a trial can be rerun exactly.
+///
+/// Recognized parameters: `learning_rate`, `gamma`, `gae_lambda`,
+/// `entropy_coef`, `value_loss_coef` and `max_grad_norm`; anything else is
+/// rejected so a misspelled name doesn't silently search nothing.
+pub struct A2CTrainer<F> {
+    make_env: F,
+    base: A2CConfig,
+    observation_dim: usize,
+    action_dim: usize,
+    /// Rollout/update iterations per trial
+    pub iterations: usize,
+    /// Environment steps per rollout
+    pub rollout_steps: usize,
+    /// Episodes averaged for the score
+    pub eval_episodes: usize,
+    /// Cap on evaluation episode length
+    pub max_eval_steps: usize,
+}
+
+impl<F, E> A2CTrainer<F>
+where
+    F: FnMut(u64) -> Result<E>,
+    E: Environment<Observation = VectorObservation, Action = DiscreteAction>,
+{
+    /// Create a trainer building a fresh environment per trial with
+    /// `make_env`, which receives the trial seed
+    pub fn new(make_env: F, observation_dim: usize, action_dim: usize) -> Self {
+        Self {
+            make_env,
+            base: A2CConfig::default(),
+            observation_dim,
+            action_dim,
+            iterations: 20,
+            rollout_steps: 128,
+            eval_episodes: 10,
+            max_eval_steps: 500,
+        }
+    }
+
+    /// Values used for parameters the search space doesn't cover
+    #[must_use]
+    pub fn with_base_config(mut self, base: A2CConfig) -> Self {
+        self.base = base;
+        self
+    }
+
+    fn config_for(&self, params: &HyperParams) -> Result<A2CConfig> {
+        let mut config = self.base.clone();
+        for (name, &value) in params {
+            match name.as_str() {
+                "learning_rate" => config.base.learning_rate = value,
+                "gamma" => config.base.gamma = value,
+                "gae_lambda" => config.gae_lambda = value,
+                "entropy_coef" => config.entropy_coef = value,
+                "value_loss_coef" => config.value_loss_coef = value,
+                "max_grad_norm" => config.max_grad_norm = value,
+                other => anyhow::bail!("A2C has no hyperparameter '{}'", other),
+            }
+        }
+        Ok(config)
+    }
+
+    async fn run(&mut self, config: A2CConfig, seed: u64) -> Result<f64> {
+        let mut env = (self.make_env)(seed)?;
+        let config = A2CConfig {
+            n_steps: self.rollout_steps,
+            ..config
+        };
+        let agent = A2CAgent::new_seeded(config, self.observation_dim, self.action_dim, seed)?;
+
+        for _ in 0..self.iterations {
+            agent.collect_rollout(&mut env).await?;
+            agent.train().await?;
+        }
+
+        agent.set_mode(AgentMode::Eval);
+        let mut total = 0.0;
+        for _ in 0..self.eval_episodes {
+            let (mut obs, _) = env.reset().await?;
+            for _ in 0..self.max_eval_steps {
+                let input: Array1<f32> = obs.data.iter().map(|&x| x as f32).collect();
+                let (action, _) = agent.act(&input).await?;
+                let step = env.step(DiscreteAction(action)).await?;
+                total += step.reward.0;
+                if step.done || step.truncated {
+                    break;
+                }
+                obs = step.observation;
+            }
+        }
+
+        #[allow(clippy::cast_precision_loss)]
+        Ok(total / self.eval_episodes.max(1) as f64)
+    }
+}
+
+impl<F, E> Trainer for A2CTrainer<F>
+where
+    F: FnMut(u64) -> Result<E>,
+    E: Environment<Observation = VectorObservation, Action = DiscreteAction>,
+{
+    fn train_and_evaluate(&mut self, params: &HyperParams, seed: u64) -> Result<f64> {
+        let config = self.config_for(params)?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        runtime.block_on(self.run(config, seed))
+    }
+}
+
+/// [`Trainer`] running PPO for a fixed number of rollouts, scored like
+/// [`A2CTrainer`] by the mean undiscounted return of the greedy policy.
+/// The agent from the latest trial is kept, so it can be checkpointed.
+///
+/// Recognized parameters: `learning_rate`, `gamma`, `gae_lambda`,
+/// `clip_param`, `entropy_coef`, `value_loss_coef` and `max_grad_norm`.
+pub struct PPOTrainer<F> {
+    make_env: F,
+    base: PPOConfig,
+    observation_dim: usize,
+    action_dim: usize,
+    agent: Option<PPOAgentFull>,
+    /// Rollout/update iterations per trial
+    pub iterations: usize,
+    /// Environment steps per rollout
+    pub rollout_steps: usize,
+    /// Episodes averaged for the score
+    pub eval_episodes: usize,
+    /// Cap on evaluation episode length
+    pub max_eval_steps: usize,
+}
+
+impl<F, E> PPOTrainer<F>
+where
+    F: FnMut(u64) -> Result<E>,
+    E: Environment<Observation = VectorObservation, Action = DiscreteAction>,
+{
+    /// Create a trainer building a fresh environment per trial with
+    /// `make_env`, which receives the trial seed
+    pub fn new(make_env: F, observation_dim: usize, action_dim: usize) -> Self {
+        Self {
+            make_env,
+            base: PPOConfig::default(),
+            observation_dim,
+            action_dim,
+            agent: None,
+            iterations: 20,
+            rollout_steps: 128,
+            eval_episodes: 10,
+            max_eval_steps: 500,
+        }
+    }
+
+    /// Values used for parameters the search space doesn't cover
+    #[must_use]
+    pub fn with_base_config(mut self, base: PPOConfig) -> Self {
+        self.base = base;
+        self
+    }
+
+    /// Take the agent trained by the latest trial, left in eval mode
+    pub fn take_agent(&mut self) -> Option<PPOAgentFull> {
+        self.agent.take()
+    }
+
+    fn config_for(&self, params: &HyperParams) -> Result<PPOConfig> {
+        let mut config = self.base.clone();
+        for (name, &value) in params {
+            match name.as_str() {
+                "learning_rate" => config.base.learning_rate = value,
+                "gamma" => config.base.gamma = value,
+                "gae_lambda" => config.gae_lambda = value,
+                "clip_param" => config.clip_param = value,
+                "entropy_coef" => config.entropy_coef = value,
+                "value_loss_coef" => config.value_loss_coef = value,
+                "max_grad_norm" => config.max_grad_norm = value,
+                other => anyhow::bail!("PPO has no hyperparameter '{}'", other),
+            }
+        }
+        Ok(config)
+    }
+
+    async fn run(&mut self, config: PPOConfig, seed: u64) -> Result<f64> {
+        let mut env = (self.make_env)(seed)?;
+        let config = PPOConfig {
+            n_steps: self.rollout_steps,
+            ..config
+        };
+        let agent = PPOAgentFull::new_seeded(config, self.observation_dim, self.action_dim, seed).await?;
+
+        for _ in 0..self.iterations {
+            agent.collect_rollout(&mut env).await?;
+            agent.train().await?;
+        }
+
+        agent.set_mode(AgentMode::Eval);
+        let mut total = 0.0;
+        for _ in 0..self.eval_episodes {
+            agent.reset_episode().await?;
+            let (mut obs, _) = env.reset().await?;
+            for _ in 0..self.max_eval_steps {
+                let action = agent.act(&obs).await?;
+                let step = env.step(action).await?;
+                total += step.reward.0;
+                if step.done || step.truncated {
+                    break;
+                }
+                obs = step.observation;
+            }
+        }
+        self.agent = Some(agent);
+
+        #[allow(clippy::cast_precision_loss)]
+        Ok(total / self.eval_episodes.max(1) as f64)
+    }
+}
+
+impl<F, E> Trainer for PPOTrainer<F>
+where
+    F: FnMut(u64) -> Result<E>,
+    E: Environment<Observation = VectorObservation, Action = DiscreteAction>,
+{
+    fn train_and_evaluate(&mut self, params: &HyperParams, seed: u64) -> Result<f64> {
+        let config = self.config_for(params)?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        runtime.block_on(self.run(config, seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentient_rl_core::EnvironmentConfig;
+    use sentient_rl_env::CartPoleEnv;
+
+    #[test]
+    fn test_sampling_is_seeded_and_deduplicated() {
+        let space = SearchSpace::new()
+            .log_uniform("learning_rate", 1e-4, 1e-1)
+            .uniform("gamma", 0.9, 0.999)
+            .choice("entropy_coef", &[0.0, 0.01]);
+
+        let mut seen = Vec::new();
+        let mut record = |params: &HyperParams, _seed: u64| -> Result<f64> {
+            seen.push(params.clone());
+            Ok(params["gamma"])
+        };
+        let trials = random_search(&space, 5, 7, &mut record).unwrap();
+        assert_eq!(trials.len(), 5);
+        assert!(trials.windows(2).all(|w| w[0].score >= w[1].score));
+        for trial in &trials {
+            let lr = trial.params["learning_rate"];
+            assert!((1e-4..1e-1).contains(&lr));
+        }
+
+        let mut again = Vec::new();
+        let mut record_again = |params: &HyperParams, _seed: u64| -> Result<f64> {
+            again.push(params.clone());
+            Ok(0.0)
+        };
+        random_search(&space, 5, 7, &mut record_again).unwrap();
+        assert_eq!(seen, again);
+
+        // A two-point space yields two trials however many are asked for
+        let tiny = SearchSpace::new().choice("gamma", &[0.9, 0.99]);
+        let trials = random_search(&tiny, 10, 1, &mut |p: &HyperParams, _| Ok(p["gamma"])).unwrap();
+        assert_eq!(trials.len(), 2);
+        assert!(
+            random_search(&SearchSpace::new(), 1, 0, &mut |_: &HyperParams, _| Ok(0.0)).is_err()
+        );
+    }
+
+    fn seeded_cartpole(seed: u64) -> Result<CartPoleEnv> {
+        Ok(CartPoleEnv::new(EnvironmentConfig {
+            seed: Some(seed),
+            ..EnvironmentConfig::default()
+        })?)
+    }
+
+    #[test]
+    fn test_identifies_better_config_on_cartpole() {
+        // 1e-6 barely moves the weights in this budget, so that agent stays
+        // near its initial policy while 0.01 learns to balance
+        let space = SearchSpace::new().choice("learning_rate", &[1e-6, 0.01]);
+        let mut trainer = A2CTrainer::new(seeded_cartpole, 4, 2);
+        trainer.iterations = 40;
+        trainer.eval_episodes = 20;
+
+        let trials = random_search(&space, 2, 42, &mut trainer).unwrap();
+        assert_eq!(trials.len(), 2);
+        assert!(
+            (trials[0].params["learning_rate"] - 0.01).abs() < f64::EPSILON,
+            "{:?}",
+            trials
+        );
+        assert!(trials[0].score > trials[1].score);
+
+        let mut bad = A2CTrainer::new(seeded_cartpole, 4, 2);
+        let typo: HyperParams = [("learning_rte".to_string(), 0.1)].into_iter().collect();
+        assert!(bad.train_and_evaluate(&typo, 0).is_err());
+    }
+
+    #[test]
+    fn test_trials_are_reproducible_from_seed() {
+        let mut trainer = A2CTrainer::new(seeded_cartpole, 4, 2);
+        trainer.iterations = 5;
+        trainer.eval_episodes = 3;
+        let params: HyperParams = [("learning_rate".to_string(), 0.01)].into_iter().collect();
+
+        let first = trainer.train_and_evaluate(&params, 11).unwrap();
+        let again = trainer.train_and_evaluate(&params, 11).unwrap();
+        assert!((first - again).abs() < f64::EPSILON, "{} != {}", first, again);
+
+        let mut trainer = PPOTrainer::new(seeded_cartpole, 4, 2);
+        trainer.iterations = 3;
+        trainer.rollout_steps = 64;
+        trainer.eval_episodes = 3;
+        let first = trainer.train_and_evaluate(&params, 11).unwrap();
+        let again = trainer.train_and_evaluate(&params, 11).unwrap();
+        assert!((first - again).abs() < f64::EPSILON, "{} != {}", first, again);
+    }
+
+    #[test]
+    fn test_ppo_trainer_keeps_latest_agent() {
+        let mut trainer = PPOTrainer::new(seeded_cartpole, 4, 2);
+        trainer.iterations = 2;
+        trainer.rollout_steps = 32;
+        trainer.eval_episodes = 1;
+        assert!(trainer.take_agent().is_none());
+
+        let typo: HyperParams = [("clip".to_string(), 0.1)].into_iter().collect();
+        assert!(trainer.train_and_evaluate(&typo, 0).is_err());
+        assert!(trainer.take_agent().is_none());
+
+        let params: HyperParams = [("clip_param".to_string(), 0.1)].into_iter().collect();
+        trainer.train_and_evaluate(&params, 3).unwrap();
+        let agent = trainer.take_agent().unwrap();
+        assert!(agent.mode().is_eval());
+        assert!(trainer.take_agent().is_none());
+    }
+}
@@ -3,6 +3,7 @@
 //! This crate provides various RL agent implementations including:
 //! - Deep Q-Networks (DQN)
 //! - Proximal Policy Optimization (PPO)
+//! - Advantage Actor-Critic (A2C)
 //! - Soft Actor-Critic (SAC)
 //! - And more...
 
@@ -11,30 +12,49 @@
 #![warn(clippy::pedantic)]
 #![allow(clippy::module_name_repetitions)]
 
+pub mod a2c;
+#[cfg(any(test, feature = "bench"))]
+pub mod bench;
 pub mod buffer;
 pub mod dqn;
+pub mod exploration;
+pub mod golden;
+pub mod hpo;
+pub mod onnx;
 pub mod policy;
 pub mod ppo;
 pub mod ppo_full;
 pub mod random;
+pub mod recurrent;
+pub mod sac;
+pub mod sanitize;
+pub mod smoke;
 pub mod utils;
 
 // Re-export agents
-pub use dqn::{DQNAgent, DQNConfig};
+pub use a2c::{A2CAgent, A2CConfig};
+pub use dqn::{DQNAgent, DQNConfig, DQNLoss, DQNStats};
+pub use exploration::{Boltzmann, EpsilonGreedy, ExplorationStrategy};
+pub use golden::{GoldenAgent, GoldenRecorder, GoldenRollout, GoldenRun};
+pub use hpo::{random_search, A2CTrainer, PPOTrainer, SearchSpace, Trainer, Trial};
 pub use ppo::{PPOAgent, PPOConfig};
 pub use random::RandomAgent;
+pub use sac::{SACAgent, SACConfig, TemperatureTuner, TemperatureStats};
+pub use sanitize::ObservationSanitizer;
+pub use smoke::{smoke_test, SmokeReport};
 
 // Re-export utilities
-pub use buffer::{ReplayBuffer, PrioritizedReplayBuffer, Experience};
+pub use buffer::{ReplayBuffer, PrioritizedReplayBuffer, NStepAccumulator, NStepExperience, Experience};
 pub use utils::{LinearSchedule, ExponentialSchedule, Schedule};
 
 // Re-export policy components
-pub use policy::{PolicyNetwork, MLPPolicy, MLPConfig, create_policy_network};
+pub use policy::{PolicyNetwork, MLPPolicy, MLPConfig, Activation, Init, OutputGradient, create_policy_network};
+pub use recurrent::{GRUPolicy, RecurrentConfig, create_recurrent_policy_network};
 
 /// Prelude module for convenient imports
 pub mod prelude {
     pub use crate::{
-        DQNAgent, DQNConfig, PPOAgent, PPOConfig, RandomAgent,
+        A2CAgent, A2CConfig, DQNAgent, DQNConfig, PPOAgent, PPOConfig, RandomAgent,
         ReplayBuffer, Experience,
     };
     pub use sentient_rl_core::prelude::*;
@@ -0,0 +1,405 @@
+//! Minimal ONNX export for MLP policies
+//!
+//! Writes the protobuf encoding of an ONNX `ModelProto` by hand so trained
+//! policies can run under any ONNX runtime without pulling in a protobuf or
+//! Python toolchain. Each layer becomes a `Gemm` node followed by its
+//! activation; the graph maps `observation [batch, input_dim]` to
+//! `logits [batch, output_dim]`.
+
+use ndarray::{Array1, Array2};
+
+use crate::policy::{Activation, MLPConfig};
+
+/// ONNX IR version written to the model
+pub const IR_VERSION: i64 = 7;
+/// Default-domain opset the graph targets
+pub const OPSET_VERSION: i64 = 13;
+/// Name of the graph input
+pub const INPUT_NAME: &str = "observation";
+/// Name of the graph output
+pub const OUTPUT_NAME: &str = "logits";
+
+/// `TensorProto.DataType.FLOAT`
+const FLOAT: u64 = 1;
+
+/// `AttributeProto.AttributeType.FLOAT`
+const ATTRIBUTE_FLOAT: i64 = 1;
+
+/// Scalar initializers the GELU expansion divides, adds and multiplies by
+const GELU_CONSTANTS: [(&str, f32); 3] = [
+    ("gelu.sqrt2", std::f32::consts::SQRT_2),
+    ("gelu.one", 1.0),
+    ("gelu.half", 0.5),
+];
+
+/// Protobuf wire types
+const VARINT: u64 = 0;
+const LENGTH_DELIMITED: u64 = 2;
+const FIXED32: u64 = 5;
+
+/// Append-only protobuf message encoder
+#[derive(Default)]
+struct ProtoWriter {
+    buf: Vec<u8>,
+}
+
+impl ProtoWriter {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            #[allow(clippy::cast_possible_truncation)]
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        self.buf.push(value as u8);
+    }
+
+    fn key(&mut self, field: u64, wire_type: u64) {
+        self.varint((field << 3) | wire_type);
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    fn int(&mut self, field: u64, value: i64) -> &mut Self {
+        self.key(field, VARINT);
+        self.varint(value as u64);
+        self
+    }
+
+    fn float(&mut self, field: u64, value: f32) -> &mut Self {
+        self.key(field, FIXED32);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    fn bytes(&mut self, field: u64, value: &[u8]) -> &mut Self {
+        self.key(field, LENGTH_DELIMITED);
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value);
+        self
+    }
+
+    fn string(&mut self, field: u64, value: &str) -> &mut Self {
+        self.bytes(field, value.as_bytes())
+    }
+
+    fn message(&mut self, field: u64, build: impl FnOnce(&mut ProtoWriter)) -> &mut Self {
+        let mut inner = ProtoWriter::default();
+        build(&mut inner);
+        self.bytes(field, &inner.buf)
+    }
+}
+
+/// Dimension of a graph input or output
+enum Dim<'a> {
+    Fixed(usize),
+    Symbolic(&'a str),
+}
+
+/// `ValueInfoProto` for a float tensor
+#[allow(clippy::cast_possible_wrap)]
+fn value_info(w: &mut ProtoWriter, name: &str, dims: &[Dim<'_>]) {
+    w.string(1, name).message(2, |ty| {
+        ty.message(1, |tensor| {
+            tensor.int(1, FLOAT as i64).message(2, |shape| {
+                for dim in dims {
+                    shape.message(1, |d| match dim {
+                        Dim::Fixed(n) => {
+                            d.int(1, *n as i64);
+                        }
+                        Dim::Symbolic(name) => {
+                            d.string(2, name);
+                        }
+                    });
+                }
+            });
+        });
+    });
+}
+
+/// `TensorProto` initializer holding float data
+#[allow(clippy::cast_possible_wrap)]
+fn initializer(w: &mut ProtoWriter, name: &str, dims: &[usize], data: impl Iterator<Item = f32>) {
+    for &dim in dims {
+        w.int(1, dim as i64);
+    }
+    w.int(2, FLOAT as i64).string(8, name);
+    let raw: Vec<u8> = data.flat_map(f32::to_le_bytes).collect();
+    w.bytes(9, &raw);
+}
+
+/// `NodeProto` with no attributes
+fn node(w: &mut ProtoWriter, op_type: &str, name: &str, inputs: &[&str], output: &str) {
+    for input in inputs {
+        w.string(1, input);
+    }
+    w.string(2, output).string(3, name).string(4, op_type);
+}
+
+/// Append the nodes applying `activation` to `input` after hidden layer
+/// `layer`, returning the name of their output
+fn activation_nodes(graph: &mut ProtoWriter, activation: Activation, layer: usize, input: &str) -> String {
+    let output = format!("layer{layer}.act");
+    let name = format!("act{layer}");
+    match activation {
+        Activation::Tanh => {
+            graph.message(1, |n| node(n, "Tanh", &name, &[input], &output));
+        }
+        Activation::ReLU => {
+            graph.message(1, |n| node(n, "Relu", &name, &[input], &output));
+        }
+        Activation::Sigmoid => {
+            graph.message(1, |n| node(n, "Sigmoid", &name, &[input], &output));
+        }
+        Activation::LeakyReLU { slope } => {
+            graph.message(1, |n| {
+                node(n, "LeakyRelu", &name, &[input], &output);
+                n.message(5, |a| {
+                    a.string(1, "alpha").float(2, slope).int(20, ATTRIBUTE_FLOAT);
+                });
+            });
+        }
+        Activation::GELU => {
+            // Opset 13 has no Gelu: x * (1 + erf(x / sqrt(2))) * 0.5
+            let step = |s: &str| format!("layer{layer}.gelu_{s}");
+            let steps = [
+                ("Div", vec![input.to_string(), "gelu.sqrt2".to_string()], step("scaled")),
+                ("Erf", vec![step("scaled")], step("erf")),
+                ("Add", vec![step("erf"), "gelu.one".to_string()], step("cdf2")),
+                ("Mul", vec![input.to_string(), step("cdf2")], step("x_cdf2")),
+                ("Mul", vec![step("x_cdf2"), "gelu.half".to_string()], output.clone()),
+            ];
+            for (i, (op, inputs, out)) in steps.iter().enumerate() {
+                let inputs: Vec<&str> = inputs.iter().map(String::as_str).collect();
+                graph.message(1, |n| node(n, op, &format!("{name}.{i}"), &inputs, out));
+            }
+        }
+    }
+    output
+}
+
+/// Serialize the discrete-logits head of an MLP as an ONNX model
+///
+/// `weights[i]` has shape `(in, out)` and is applied as `x · W + b`.
+#[must_use]
+pub fn mlp_to_onnx(config: &MLPConfig, weights: &[Array2<f32>], biases: &[Array1<f32>]) -> Vec<u8> {
+    let mut model = ProtoWriter::default();
+    model.int(1, IR_VERSION).string(2, "sentientos");
+
+    model.message(7, |graph| {
+        let mut current = INPUT_NAME.to_string();
+        let last = weights.len().saturating_sub(1);
+
+        for i in 0..weights.len().min(biases.len()) {
+            let (w_name, b_name) = (format!("layer{i}.weight"), format!("layer{i}.bias"));
+            let is_output = i == last;
+            let gemm_out = if is_output { OUTPUT_NAME.to_string() } else { format!("layer{i}.linear") };
+
+            graph.message(1, |n| node(n, "Gemm", &format!("gemm{i}"), &[&current, &w_name, &b_name], &gemm_out));
+            current = gemm_out;
+
+            // The output layer produces raw logits
+            if !is_output {
+                current = activation_nodes(graph, config.activation, i, &current);
+            }
+        }
+
+        graph.string(2, "mlp_policy");
+
+        for (i, (weight, bias)) in weights.iter().zip(biases).enumerate() {
+            let (rows, cols) = weight.dim();
+            graph.message(5, |t| initializer(t, &format!("layer{i}.weight"), &[rows, cols], weight.iter().copied()));
+            graph.message(5, |t| initializer(t, &format!("layer{i}.bias"), &[bias.len()], bias.iter().copied()));
+        }
+        if config.activation == Activation::GELU && weights.len() > 1 {
+            for (name, value) in GELU_CONSTANTS {
+                graph.message(5, |t| initializer(t, name, &[], std::iter::once(value)));
+            }
+        }
+
+        graph.message(11, |v| value_info(v, INPUT_NAME, &[Dim::Symbolic("batch"), Dim::Fixed(config.input_dim)]));
+        graph.message(12, |v| value_info(v, OUTPUT_NAME, &[Dim::Symbolic("batch"), Dim::Fixed(config.output_dim)]));
+    });
+
+    model.message(8, |opset| {
+        opset.string(1, "").int(2, OPSET_VERSION);
+    });
+
+    model.buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{MLPPolicy, PolicyNetwork};
+    use std::collections::{HashMap, HashSet};
+
+    /// Decoded protobuf field value
+    #[derive(Debug, Clone)]
+    enum Value {
+        Int(u64),
+        Bytes(Vec<u8>),
+    }
+
+    /// Parse a protobuf message into its fields, failing on malformed input
+    fn decode(mut buf: &[u8]) -> HashMap<u64, Vec<Value>> {
+        fn varint(buf: &mut &[u8]) -> u64 {
+            let mut value = 0;
+            for shift in (0..64).step_by(7) {
+                let (&byte, rest) = buf.split_first().expect("truncated varint");
+                *buf = rest;
+                value |= u64::from(byte & 0x7f) << shift;
+                if byte & 0x80 == 0 {
+                    return value;
+                }
+            }
+            panic!("varint too long");
+        }
+
+        let mut fields: HashMap<u64, Vec<Value>> = HashMap::new();
+        while !buf.is_empty() {
+            let key = varint(&mut buf);
+            let value = match key & 7 {
+                0 => Value::Int(varint(&mut buf)),
+                2 => {
+                    let len = usize::try_from(varint(&mut buf)).unwrap();
+                    assert!(len <= buf.len(), "length exceeds message");
+                    let (bytes, rest) = buf.split_at(len);
+                    buf = rest;
+                    Value::Bytes(bytes.to_vec())
+                }
+                5 => {
+                    assert!(buf.len() >= 4, "truncated fixed32");
+                    let (bytes, rest) = buf.split_at(4);
+                    buf = rest;
+                    Value::Bytes(bytes.to_vec())
+                }
+                wire => panic!("unexpected wire type {}", wire),
+            };
+            fields.entry(key >> 3).or_default().push(value);
+        }
+        fields
+    }
+
+    fn ints(fields: &HashMap<u64, Vec<Value>>, field: u64) -> Vec<u64> {
+        fields.get(&field).into_iter().flatten().map(|v| match v {
+            Value::Int(i) => *i,
+            Value::Bytes(_) => panic!("field {} is not a varint", field),
+        }).collect()
+    }
+
+    fn messages(fields: &HashMap<u64, Vec<Value>>, field: u64) -> Vec<Vec<u8>> {
+        fields.get(&field).into_iter().flatten().map(|v| match v {
+            Value::Bytes(b) => b.clone(),
+            Value::Int(_) => panic!("field {} is not length-delimited", field),
+        }).collect()
+    }
+
+    fn strings(fields: &HashMap<u64, Vec<Value>>, field: u64) -> Vec<String> {
+        messages(fields, field).into_iter().map(|b| String::from_utf8(b).unwrap()).collect()
+    }
+
+    /// Shape of a `ValueInfoProto`, with symbolic dims as `None`
+    fn value_info_shape(bytes: &[u8]) -> (String, Vec<Option<u64>>) {
+        let info = decode(bytes);
+        let name = strings(&info, 1).remove(0);
+        let ty = decode(&messages(&info, 2)[0]);
+        let tensor = decode(&messages(&ty, 1)[0]);
+        assert_eq!(ints(&tensor, 1), vec![1], "elem_type should be FLOAT");
+        let shape = decode(&messages(&tensor, 2)[0]);
+        let dims = messages(&shape, 1)
+            .iter()
+            .map(|d| ints(&decode(d), 1).first().copied())
+            .collect();
+        (name, dims)
+    }
+
+    #[tokio::test]
+    async fn test_exported_mlp_is_valid_onnx() {
+        let policy = MLPPolicy::new(MLPConfig {
+            input_dim: 4,
+            hidden_dims: vec![8, 6],
+            output_dim: 2,
+            activation: Activation::Tanh,
+            use_value_head: true,
+            init_log_std: -0.5,
+            ..MLPConfig::default()
+        });
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("policy.onnx");
+        policy.export_onnx(&path).await.unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        let model = decode(&bytes);
+        assert_eq!(ints(&model, 1), vec![7]);
+        let opset = decode(&messages(&model, 8)[0]);
+        assert_eq!(ints(&opset, 2), vec![13]);
+
+        let graph = decode(&messages(&model, 7)[0]);
+        let (input, input_dims) = value_info_shape(&messages(&graph, 11)[0]);
+        let (output, output_dims) = value_info_shape(&messages(&graph, 12)[0]);
+        assert_eq!((input.as_str(), input_dims), (INPUT_NAME, vec![None, Some(4)]));
+        assert_eq!((output.as_str(), output_dims), (OUTPUT_NAME, vec![None, Some(2)]));
+
+        // Initializers: three (weight, bias) pairs with matching raw float data
+        let mut available: HashSet<String> = HashSet::from([INPUT_NAME.to_string()]);
+        let expected_shapes = [vec![4, 8], vec![8], vec![8, 6], vec![6], vec![6, 2], vec![2]];
+        let initializers = messages(&graph, 5);
+        assert_eq!(initializers.len(), expected_shapes.len());
+        for (tensor, dims) in initializers.iter().zip(&expected_shapes) {
+            let tensor = decode(tensor);
+            assert_eq!(&ints(&tensor, 1), dims);
+            assert_eq!(ints(&tensor, 2), vec![1]);
+            let count: u64 = dims.iter().product();
+            assert_eq!(messages(&tensor, 9)[0].len() as u64, count * 4);
+            available.insert(strings(&tensor, 8).remove(0));
+        }
+
+        // Nodes are topologically ordered and end in the logits output
+        let mut op_types = Vec::new();
+        for node in messages(&graph, 1) {
+            let node = decode(&node);
+            for input in strings(&node, 1) {
+                assert!(available.contains(&input), "dangling input {}", input);
+            }
+            available.extend(strings(&node, 2));
+            op_types.extend(strings(&node, 4));
+        }
+        assert_eq!(op_types, vec!["Gemm", "Tanh", "Gemm", "Tanh", "Gemm"]);
+        assert!(available.contains(OUTPUT_NAME));
+    }
+
+    /// Op types of the graph's nodes, in order
+    fn op_types(model: &[u8]) -> Vec<String> {
+        let graph = decode(&messages(&decode(model), 7)[0]);
+        messages(&graph, 1).iter().flat_map(|node| strings(&decode(node), 4)).collect()
+    }
+
+    #[test]
+    fn test_exported_activations() {
+        let config = |activation| MLPConfig {
+            input_dim: 3,
+            hidden_dims: vec![4],
+            output_dim: 2,
+            activation,
+            ..MLPConfig::default()
+        };
+        let weights = [Array2::zeros((3, 4)), Array2::zeros((4, 2))];
+        let biases = [Array1::zeros(4), Array1::zeros(2)];
+
+        let leaky = mlp_to_onnx(&config(Activation::LeakyReLU { slope: 0.2 }), &weights, &biases);
+        assert_eq!(op_types(&leaky), vec!["Gemm", "LeakyRelu", "Gemm"]);
+        let graph = decode(&messages(&decode(&leaky), 7)[0]);
+        let attribute = decode(&messages(&decode(&messages(&graph, 1)[1]), 5)[0]);
+        assert_eq!(strings(&attribute, 1), vec!["alpha"]);
+        assert_eq!(messages(&attribute, 2), vec![0.2f32.to_le_bytes().to_vec()]);
+        assert_eq!(ints(&attribute, 20), vec![1]);
+
+        let gelu = mlp_to_onnx(&config(Activation::GELU), &weights, &biases);
+        assert_eq!(op_types(&gelu), vec!["Gemm", "Div", "Erf", "Add", "Mul", "Mul", "Gemm"]);
+        let graph = decode(&messages(&decode(&gelu), 7)[0]);
+        let names: Vec<String> = messages(&graph, 5).iter().flat_map(|t| strings(&decode(t), 8)).collect();
+        for (constant, _) in GELU_CONSTANTS {
+            assert!(names.iter().any(|n| n == constant), "missing {}", constant);
+        }
+    }
+}
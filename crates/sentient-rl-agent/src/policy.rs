@@ -7,6 +7,7 @@ use anyhow::{Result, Context};
 use async_trait::async_trait;
 use ndarray::{Array1, Array2, ArrayView1};
 use rand::Rng;
+use rand_distr::StandardNormal;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -20,6 +21,60 @@ pub trait PolicyNetwork: Send + Sync {
     /// Sample action from the policy
     async fn sample_action(&self, observation: &ArrayView1<f32>) -> Result<(Array1<f32>, f32)>;
     
+    /// Forward pass with the logits of actions whose `mask` entry is `false`
+    /// set to `-inf`, so softmax gives them exactly zero probability
+    async fn forward_masked(
+        &self,
+        observation: &ArrayView1<f32>,
+        mask: Option<&[bool]>,
+    ) -> Result<PolicyOutput> {
+        let mut output = self.forward(observation).await?;
+        if let Some(mask) = mask {
+            apply_action_mask(&mut output.action_output, mask)?;
+        }
+        Ok(output)
+    }
+    
+    /// Sample a discrete action from the valid set only, returning it one-hot
+    /// with its log probability under the renormalized distribution
+    async fn sample_action_masked(
+        &self,
+        observation: &ArrayView1<f32>,
+        mask: Option<&[bool]>,
+    ) -> Result<(Array1<f32>, f32)> {
+        let Some(mask) = mask else {
+            return self.sample_action(observation).await;
+        };
+        let output = self.forward_masked(observation, Some(mask)).await?;
+        let probs = masked_softmax(&output.action_output);
+        let action_idx = sample_categorical(&probs, rand::thread_rng().gen::<f32>());
+        
+        let mut action = Array1::zeros(probs.len());
+        action[action_idx] = 1.0;
+        Ok((action, probs[action_idx].ln()))
+    }
+    
+    /// The action the policy rates best, without sampling: the most likely
+    /// discrete action one-hot. Used when evaluating a trained policy.
+    async fn greedy_action(&self, observation: &ArrayView1<f32>) -> Result<Array1<f32>> {
+        let logits = self.forward(observation).await?.action_output;
+        let best = logits
+            .iter()
+            .enumerate()
+            .fold(0, |best, (i, &x)| if x > logits[best] { i } else { best });
+        let mut action = Array1::zeros(logits.len());
+        action[best] = 1.0;
+        Ok(action)
+    }
+    
+    /// Gradient with respect to every parameter, laid out like
+    /// [`get_parameters`](Self::get_parameters), of a loss whose gradient at
+    /// the outputs of a forward pass on `observation` is `grad`. `None` for
+    /// networks that can't backpropagate into their trunk.
+    fn parameter_gradients(&self, _observation: &ArrayView1<f32>, _grad: &OutputGradient) -> Option<Vec<f32>> {
+        None
+    }
+    
     /// Update network parameters
     async fn update(&mut self, gradients: &[f32]) -> Result<()>;
     
@@ -31,6 +86,26 @@ pub trait PolicyNetwork: Send + Sync {
     
     /// Clone the network
     fn clone_network(&self) -> Box<dyn PolicyNetwork>;
+    
+    /// Export the policy as an ONNX model
+    async fn export_onnx(&self, _path: &std::path::Path) -> Result<()> {
+        anyhow::bail!("ONNX export is not supported by this policy network")
+    }
+    
+    /// Recurrent state the next forward pass starts from (`None` for
+    /// feed-forward networks)
+    fn hidden_state(&self) -> Option<Array1<f32>> {
+        None
+    }
+    
+    /// Replace the recurrent state, typically with
+    /// [`PolicyOutput::hidden_state`] once the step has been taken
+    fn set_hidden_state(&mut self, _hidden: &Array1<f32>) -> Result<()> {
+        Ok(())
+    }
+    
+    /// Clear the recurrent state at an episode boundary
+    fn reset_hidden_state(&mut self) {}
 }
 
 /// Output from policy network
@@ -42,6 +117,21 @@ pub struct PolicyOutput {
     pub value: Option<f32>,
     /// Action log std (for continuous actions)
     pub log_std: Option<Array1<f32>>,
+    /// Activations of the last hidden layer, shared by the action and value heads
+    pub features: Array1<f32>,
+    /// Recurrent state after this step (recurrent policies only)
+    pub hidden_state: Option<Array1<f32>>,
+}
+
+/// Gradient of a scalar loss with respect to the outputs of a forward pass
+#[derive(Debug, Clone)]
+pub struct OutputGradient {
+    /// With respect to [`PolicyOutput::action_output`]
+    pub action_output: Array1<f32>,
+    /// With respect to [`PolicyOutput::value`] (ignored without a value head)
+    pub value: f32,
+    /// With respect to [`PolicyOutput::log_std`], for continuous actions
+    pub log_std: Option<Array1<f32>>,
 }
 
 /// MLP (Multi-Layer Perceptron) policy configuration
@@ -53,12 +143,185 @@ pub struct MLPConfig {
     pub hidden_dims: Vec<usize>,
     /// Output dimension (action space)
     pub output_dim: usize,
-    /// Activation function
-    pub activation: String,
+    /// Activation function of the hidden layers
+    pub activation: Activation,
     /// Whether to include value head (for actor-critic)
     pub use_value_head: bool,
     /// Initial log std for continuous actions
     pub init_log_std: f32,
+    /// Initialization of the hidden layers
+    #[serde(default)]
+    pub hidden_init: Init,
+    /// Initialization of the policy (action) head
+    #[serde(default)]
+    pub policy_init: Init,
+    /// Initialization of the value head
+    #[serde(default)]
+    pub value_init: Init,
+}
+
+/// Activation function applied after each hidden layer
+///
+/// Serialized as `"tanh"`, `"relu"`, `"gelu"`, `"sigmoid"` or
+/// `{"leaky_relu": {"slope": ..}}`, so an unsupported name fails when the
+/// config is parsed rather than when the network runs.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    /// Hyperbolic tangent
+    #[default]
+    Tanh,
+    /// `max(x, 0)`
+    #[serde(rename = "relu")]
+    ReLU,
+    /// Exact GELU, `x * Phi(x)`
+    #[serde(rename = "gelu")]
+    GELU,
+    /// `x` for positive inputs, `slope * x` otherwise
+    #[serde(rename = "leaky_relu")]
+    LeakyReLU {
+        /// Gradient for negative inputs, typically 0.01
+        slope: f32,
+    },
+    /// Logistic function, `1 / (1 + e^-x)`
+    Sigmoid,
+}
+
+impl Activation {
+    /// Activation at `x`
+    pub fn apply(self, x: f32) -> f32 {
+        match self {
+            Activation::Tanh => x.tanh(),
+            Activation::ReLU => x.max(0.0),
+            Activation::GELU => x * std_normal_cdf(x),
+            Activation::LeakyReLU { slope } => if x > 0.0 { x } else { slope * x },
+            Activation::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+        }
+    }
+    
+    /// Derivative of the activation at `x`. At the kink of ReLU and leaky
+    /// ReLU this is the left-hand slope.
+    pub fn derivative(self, x: f32) -> f32 {
+        match self {
+            Activation::Tanh => 1.0 - x.tanh().powi(2),
+            Activation::ReLU => if x > 0.0 { 1.0 } else { 0.0 },
+            Activation::GELU => {
+                let density = (-0.5 * x * x).exp() / (2.0 * std::f32::consts::PI).sqrt();
+                std_normal_cdf(x) + x * density
+            }
+            Activation::LeakyReLU { slope } => if x > 0.0 { 1.0 } else { slope },
+            Activation::Sigmoid => {
+                let s = self.apply(x);
+                s * (1.0 - s)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Activation {
+    type Err = anyhow::Error;
+    
+    /// Parse `tanh`, `relu`, `gelu`, `sigmoid` or `leaky_relu[:slope]`
+    /// (slope 0.01 by default)
+    fn from_str(name: &str) -> Result<Self> {
+        let (name, slope) = match name.split_once(':') {
+            Some((name, slope)) => (name, Some(slope)),
+            None => (name, None),
+        };
+        match (name.to_lowercase().as_str(), slope) {
+            ("tanh", None) => Ok(Activation::Tanh),
+            ("relu", None) => Ok(Activation::ReLU),
+            ("gelu", None) => Ok(Activation::GELU),
+            ("sigmoid", None) => Ok(Activation::Sigmoid),
+            ("leaky_relu", None) => Ok(Activation::LeakyReLU { slope: 0.01 }),
+            ("leaky_relu", Some(slope)) => {
+                let slope: f32 = slope.parse()
+                    .with_context(|| format!("Invalid leaky ReLU slope '{}'", slope))?;
+                Ok(Activation::LeakyReLU { slope })
+            }
+            _ => anyhow::bail!(
+                "Unsupported activation '{}' (expected tanh, relu, gelu, sigmoid or leaky_relu[:slope])",
+                name
+            ),
+        }
+    }
+}
+
+/// Standard normal CDF
+fn std_normal_cdf(x: f32) -> f32 {
+    (0.5 * (1.0 + statrs::function::erf::erf(f64::from(x) / std::f64::consts::SQRT_2))) as f32
+}
+
+/// Weight initialization scheme for one layer
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Init {
+    /// Uniform in ±sqrt(6 / (fan_in + fan_out))
+    #[default]
+    Xavier,
+    /// Normal with std sqrt(2 / fan_in), suited to ReLU layers
+    He,
+    /// Random (semi-)orthogonal matrix scaled by `gain`. PPO typically uses
+    /// sqrt(2) for hidden layers, 0.01 for the policy head and 1 for the
+    /// value head.
+    Orthogonal {
+        /// Factor the orthogonal matrix is multiplied by
+        gain: f32,
+    },
+}
+
+impl Init {
+    /// Orthogonal initialization with the gains PPO is usually trained with,
+    /// as `(hidden, policy, value)`
+    pub fn ppo() -> (Self, Self, Self) {
+        (
+            Init::Orthogonal { gain: std::f32::consts::SQRT_2 },
+            Init::Orthogonal { gain: 0.01 },
+            Init::Orthogonal { gain: 1.0 },
+        )
+    }
+    
+    /// Weights of an `in_dim` x `out_dim` layer
+    pub fn weights(self, in_dim: usize, out_dim: usize, rng: &mut impl Rng) -> Array2<f32> {
+        match self {
+            Init::Xavier => {
+                let limit = (6.0 / (in_dim + out_dim) as f32).sqrt();
+                Array2::from_shape_fn((in_dim, out_dim), |_| rng.gen_range(-limit..limit))
+            }
+            Init::He => {
+                let std = (2.0 / in_dim as f32).sqrt();
+                Array2::from_shape_fn((in_dim, out_dim), |_| rng.sample::<f32, _>(StandardNormal) * std)
+            }
+            Init::Orthogonal { gain } => orthogonal(in_dim, out_dim, rng) * gain,
+        }
+    }
+}
+
+/// Random `in_dim` x `out_dim` matrix whose columns (or rows, when it is
+/// wider than tall) are orthonormal, by Gram-Schmidt on Gaussian vectors
+fn orthogonal(in_dim: usize, out_dim: usize, rng: &mut impl Rng) -> Array2<f32> {
+    let (len, count) = (in_dim.max(out_dim), in_dim.min(out_dim));
+    let mut q = Array2::<f32>::zeros((len, count));
+    let mut filled = 0;
+    while filled < count {
+        let mut v = Array1::from_shape_fn(len, |_| rng.sample::<f32, _>(StandardNormal));
+        // Projecting out twice keeps f32 rounding from eroding orthogonality
+        for _ in 0..2 {
+            for k in 0..filled {
+                let basis = q.column(k);
+                let projection = basis.dot(&v);
+                v.scaled_add(-projection, &basis);
+            }
+        }
+        // A draw (nearly) inside the span so far is redrawn
+        let norm = v.dot(&v).sqrt();
+        if norm > 1e-3 {
+            q.column_mut(filled).assign(&(v / norm));
+            filled += 1;
+        }
+    }
+    // Parameters are read as row-major slices, so transpose into a fresh array
+    if in_dim >= out_dim { q } else { q.t().as_standard_layout().into_owned() }
 }
 
 impl Default for MLPConfig {
@@ -67,9 +330,12 @@ impl Default for MLPConfig {
             input_dim: 4,
             hidden_dims: vec![64, 64],
             output_dim: 2,
-            activation: "tanh".to_string(),
+            activation: Activation::Tanh,
             use_value_head: true,
             init_log_std: -0.5,
+            hidden_init: Init::Xavier,
+            policy_init: Init::Xavier,
+            value_init: Init::Xavier,
         }
     }
 }
@@ -91,26 +357,32 @@ pub struct MLPPolicy {
 impl MLPPolicy {
     /// Create new MLP policy
     pub fn new(config: MLPConfig) -> Self {
+        Self::with_rng(config, &mut rand::thread_rng())
+    }
+    
+    /// Create an MLP policy whose initial weights are drawn from `rng`, so a
+    /// seeded generator gives reproducible networks
+    pub fn with_rng(config: MLPConfig, rng: &mut impl Rng) -> Self {
         let mut weights = Vec::new();
         let mut biases = Vec::new();
         
         // Initialize layers
         let mut prev_dim = config.input_dim;
         for &hidden_dim in &config.hidden_dims {
-            weights.push(Self::xavier_init(prev_dim, hidden_dim));
+            weights.push(config.hidden_init.weights(prev_dim, hidden_dim, rng));
             biases.push(Array1::zeros(hidden_dim));
             prev_dim = hidden_dim;
         }
         
         // Output layer
-        weights.push(Self::xavier_init(prev_dim, config.output_dim));
+        weights.push(config.policy_init.weights(prev_dim, config.output_dim, rng));
         biases.push(Array1::zeros(config.output_dim));
         
         // Value head (if enabled)
         let (value_weights, value_bias) = if config.use_value_head {
             let last_hidden = config.hidden_dims.last().copied().unwrap_or(config.input_dim);
             (
-                Some(Self::xavier_init(last_hidden, 1)),
+                Some(config.value_init.weights(last_hidden, 1, rng)),
                 Some(Array1::zeros(1)),
             )
         } else {
@@ -130,23 +402,84 @@ impl MLPPolicy {
         }
     }
     
-    /// Xavier initialization for weights
-    fn xavier_init(in_dim: usize, out_dim: usize) -> Array2<f32> {
-        let limit = (6.0 / (in_dim + out_dim) as f32).sqrt();
-        let mut rng = rand::thread_rng();
-        Array2::from_shape_fn((in_dim, out_dim), |_| {
-            rng.gen_range(-limit..limit)
-        })
-    }
-    
     /// Apply activation function
     fn activation(&self, x: &Array1<f32>) -> Array1<f32> {
-        match self.config.activation.as_str() {
-            "relu" => x.mapv(|v| v.max(0.0)),
-            "tanh" => x.mapv(|v| v.tanh()),
-            "sigmoid" => x.mapv(|v| 1.0 / (1.0 + (-v).exp())),
-            _ => x.clone(),
+        let activation = self.config.activation;
+        x.mapv(|v| activation.apply(v))
+    }
+    
+    /// Derivative of the activation function at pre-activations `z`
+    fn activation_derivative(&self, z: &Array1<f32>) -> Array1<f32> {
+        let activation = self.config.activation;
+        z.mapv(|v| activation.derivative(v))
+    }
+    
+    /// Backpropagate `grad`, taken at the outputs of a forward pass on
+    /// `observation`, to every parameter. The result is laid out like
+    /// [`PolicyNetwork::get_parameters`], ready for [`PolicyNetwork::update`].
+    pub fn backward(&self, observation: &ArrayView1<f32>, grad: &OutputGradient) -> Vec<f32> {
+        self.backpropagate(observation, grad).0
+    }
+    
+    /// Backpropagate `grad` to the network's input instead, for losses that
+    /// flow on into whatever produced `observation` (such as an action fed
+    /// to a critic)
+    pub fn input_gradient(&self, observation: &ArrayView1<f32>, grad: &OutputGradient) -> Array1<f32> {
+        self.backpropagate(observation, grad).1
+    }
+    
+    /// Parameter gradients, laid out like [`PolicyNetwork::get_parameters`],
+    /// and the gradient with respect to the input
+    fn backpropagate(&self, observation: &ArrayView1<f32>, grad: &OutputGradient) -> (Vec<f32>, Array1<f32>) {
+        // Forward again, keeping each layer's input and pre-activation
+        let n_hidden = self.config.hidden_dims.len();
+        let mut inputs = Vec::with_capacity(n_hidden + 1);
+        let mut pre_activations = Vec::with_capacity(n_hidden);
+        let mut hidden = observation.to_owned();
+        for i in 0..n_hidden {
+            let z = hidden.dot(&self.weights[i]) + &self.biases[i];
+            inputs.push(hidden);
+            hidden = self.activation(&z);
+            pre_activations.push(z);
+        }
+        let features = hidden;
+        
+        // Heads
+        let mut weight_grads = vec![Array2::zeros((0, 0)); self.weights.len()];
+        let mut bias_grads = vec![Array1::zeros(0); self.biases.len()];
+        weight_grads[n_hidden] = outer(&features, &grad.action_output);
+        bias_grads[n_hidden] = grad.action_output.clone();
+        let mut d_hidden = self.weights[n_hidden].dot(&grad.action_output);
+        
+        let value_grads = self.value_weights.as_ref().map(|w| {
+            d_hidden.scaled_add(grad.value, &w.column(0));
+            (&features * grad.value, grad.value)
+        });
+        
+        // Hidden layers, last to first
+        for i in (0..n_hidden).rev() {
+            let delta = d_hidden * self.activation_derivative(&pre_activations[i]);
+            weight_grads[i] = outer(&inputs[i], &delta);
+            d_hidden = self.weights[i].dot(&delta);
+            bias_grads[i] = delta;
+        }
+        
+        let mut gradients = Vec::new();
+        for (w, b) in weight_grads.iter().zip(&bias_grads) {
+            gradients.extend(w.iter());
+            gradients.extend(b.iter());
         }
+        if let Some((w, b)) = value_grads {
+            gradients.extend(w.iter());
+            gradients.push(b);
+        }
+        if let Some(log_std) = &self.log_std {
+            match &grad.log_std {
+                Some(g) => gradients.extend(g.iter()),
+                None => gradients.extend(std::iter::repeat_n(0.0, log_std.len())),
+            }
+        }
+        (gradients, d_hidden)
     }
     
     /// Forward pass through the network
@@ -160,7 +493,7 @@ impl MLPPolicy {
         }
         
         // Output layer (no activation for policy logits)
-        let action_output = hidden.dot(&self.weights.last().unwrap()) 
+        let action_output = hidden.dot(self.weights.last().unwrap()) 
             + self.biases.last().unwrap();
         
         // Value head (if enabled)
@@ -175,6 +508,8 @@ impl MLPPolicy {
             action_output,
             value,
             log_std: self.log_std.clone(),
+            features: hidden,
+            hidden_state: None,
         }
     }
 }
@@ -256,6 +591,26 @@ impl PolicyNetwork for MLPPolicy {
         }
     }
     
+    async fn greedy_action(&self, observation: &ArrayView1<f32>) -> Result<Array1<f32>> {
+        let output = self.forward_impl(observation);
+        if self.log_std.is_none() {
+            let best = output.action_output
+                .iter()
+                .enumerate()
+                .fold(0, |best, (i, &x)| if x > output.action_output[best] { i } else { best });
+            let mut action = Array1::zeros(self.config.output_dim);
+            action[best] = 1.0;
+            Ok(action)
+        } else {
+            // Mean of the Gaussian, squashed like a sampled action
+            Ok(output.action_output.mapv(|x| x.tanh()))
+        }
+    }
+    
+    fn parameter_gradients(&self, observation: &ArrayView1<f32>, grad: &OutputGradient) -> Option<Vec<f32>> {
+        Some(self.backward(observation, grad))
+    }
+    
     async fn update(&mut self, gradients: &[f32]) -> Result<()> {
         // Simple gradient update (would be replaced by proper optimizer in production)
         let learning_rate = 3e-4;
@@ -362,6 +717,13 @@ impl PolicyNetwork for MLPPolicy {
         Ok(())
     }
     
+    async fn export_onnx(&self, path: &std::path::Path) -> Result<()> {
+        let model = crate::onnx::mlp_to_onnx(&self.config, &self.weights, &self.biases);
+        tokio::fs::write(path, model).await
+            .with_context(|| format!("Failed to write ONNX model to {:?}", path))?;
+        Ok(())
+    }
+    
     fn clone_network(&self) -> Box<dyn PolicyNetwork> {
         let mut cloned = Self::new(self.config.clone());
         
@@ -386,6 +748,54 @@ impl PolicyNetwork for MLPPolicy {
     }
 }
 
+/// `a` x `b` as an `a.len()` x `b.len()` matrix
+fn outer(a: &Array1<f32>, b: &Array1<f32>) -> Array2<f32> {
+    Array2::from_shape_fn((a.len(), b.len()), |(i, j)| a[i] * b[j])
+}
+
+/// Set the logits of invalid actions (`mask[i] == false`) to `-inf`
+pub fn apply_action_mask(logits: &mut Array1<f32>, mask: &[bool]) -> Result<()> {
+    if mask.len() != logits.len() {
+        anyhow::bail!("Action mask has {} entries for {} actions", mask.len(), logits.len());
+    }
+    if !mask.contains(&true) {
+        anyhow::bail!("Action mask leaves no valid action");
+    }
+    for (logit, &valid) in logits.iter_mut().zip(mask) {
+        if !valid {
+            *logit = f32::NEG_INFINITY;
+        }
+    }
+    Ok(())
+}
+
+/// Softmax that tolerates `-inf` logits, giving them zero probability
+pub fn masked_softmax(logits: &Array1<f32>) -> Array1<f32> {
+    let max_logit = logits.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+    let exp_logits = logits.mapv(|x| {
+        if x == f32::NEG_INFINITY {
+            0.0
+        } else {
+            (x - max_logit).exp()
+        }
+    });
+    let sum_exp = exp_logits.sum();
+    exp_logits / sum_exp
+}
+
+/// Index drawn from `probs` for a uniform `sample` in `[0, 1)`. Rounding
+/// slack falls to the last action with non-zero probability, never a masked one.
+pub(crate) fn sample_categorical(probs: &Array1<f32>, sample: f32) -> usize {
+    let mut cumsum = 0.0;
+    for (i, &p) in probs.iter().enumerate() {
+        cumsum += p;
+        if sample < cumsum {
+            return i;
+        }
+    }
+    probs.iter().rposition(|&p| p > 0.0).unwrap_or(probs.len() - 1)
+}
+
 /// Create a policy network based on configuration
 pub fn create_policy_network(config: &MLPConfig) -> Box<dyn PolicyNetwork> {
     // For now, only support pure ndarray implementation
@@ -404,9 +814,10 @@ mod tests {
             input_dim: 4,
             hidden_dims: vec![32, 32],
             output_dim: 2,
-            activation: "tanh".to_string(),
+            activation: Activation::Tanh,
             use_value_head: true,
             init_log_std: -0.5,
+            ..MLPConfig::default()
         };
         
         let policy = MLPPolicy::new(config);
@@ -417,15 +828,52 @@ mod tests {
         assert!(output.value.is_some());
     }
     
+    #[tokio::test]
+    async fn test_masked_actions_are_never_sampled() {
+        let policy = MLPPolicy::new(MLPConfig {
+            input_dim: 4,
+            hidden_dims: vec![16],
+            output_dim: 4,
+            ..MLPConfig::default()
+        });
+        let obs = arr1(&[0.1, -0.2, 0.3, 0.4]);
+        let mask = [true, false, true, false];
+        
+        let output = policy.forward_masked(&obs.view(), Some(&mask)).await.unwrap();
+        let probs = masked_softmax(&output.action_output);
+        assert_eq!(probs[1], 0.0);
+        assert_eq!(probs[3], 0.0);
+        assert!((probs[0] + probs[2] - 1.0).abs() < 1e-6);
+        
+        let mut counts = [0; 4];
+        for _ in 0..500 {
+            let (action, log_prob) = policy
+                .sample_action_masked(&obs.view(), Some(&mask))
+                .await
+                .unwrap();
+            let idx = action.iter().position(|&a| a == 1.0).unwrap();
+            counts[idx] += 1;
+            assert!((log_prob - probs[idx].ln()).abs() < 1e-5);
+        }
+        assert_eq!(counts[1] + counts[3], 0, "sampled a masked action: {:?}", counts);
+        
+        // Even a draw past the rounding slack lands on a valid action
+        assert_eq!(sample_categorical(&probs, 1.0), 2);
+        
+        assert!(policy.forward_masked(&obs.view(), Some(&[true, false])).await.is_err());
+        assert!(policy.forward_masked(&obs.view(), Some(&[false; 4])).await.is_err());
+    }
+    
     #[tokio::test]
     async fn test_action_sampling() {
         let config = MLPConfig {
             input_dim: 4,
             hidden_dims: vec![32],
             output_dim: 2,
-            activation: "relu".to_string(),
+            activation: Activation::ReLU,
             use_value_head: false,
             init_log_std: -0.5,
+            ..MLPConfig::default()
         };
         
         let policy = MLPPolicy::new(config);
@@ -435,4 +883,222 @@ mod tests {
         assert_eq!(action.len(), 2);
         assert!(log_prob.is_finite());
     }
-}
\ No newline at end of file
+
+    /// `w^T w` or `w w^T`, whichever is smaller
+    fn gram(w: &Array2<f32>) -> Array2<f32> {
+        if w.nrows() >= w.ncols() { w.t().dot(w) } else { w.dot(&w.t()) }
+    }
+    
+    #[test]
+    fn test_orthogonal_init_with_head_gains() {
+        let (hidden_init, policy_init, value_init) = Init::ppo();
+        let policy = MLPPolicy::new(MLPConfig {
+            input_dim: 8,
+            hidden_dims: vec![16, 16],
+            output_dim: 4,
+            hidden_init,
+            policy_init,
+            value_init,
+            ..MLPConfig::default()
+        });
+        
+        // Wide (8x16), square and tall (16x4, 16x1) layers are all
+        // orthonormal up to their gain
+        let value_weights = policy.value_weights.as_ref().unwrap();
+        let layers = [
+            (&policy.weights[0], 2.0),
+            (&policy.weights[1], 2.0),
+            (&policy.weights[2], 0.01 * 0.01),
+            (value_weights, 1.0),
+        ];
+        for (w, gain_sq) in layers {
+            let g = gram(w);
+            let identity = Array2::<f32>::eye(g.nrows()) * gain_sq;
+            let err = (&g - &identity).iter().fold(0.0f32, |m, &x| m.max(x.abs()));
+            assert!(err < 1e-4 * gain_sq.max(1.0), "{:?} layer off by {}", w.dim(), err);
+        }
+        
+        // Parameters are read back as flat row-major slices
+        assert!(policy.weights.iter().all(|w| w.is_standard_layout()));
+        
+        // The small policy gain keeps the initial action distribution flat
+        let head_scale = policy.weights[2].iter().fold(0.0f32, |m, &x| m.max(x.abs()));
+        assert!(head_scale <= 0.01 + 1e-6);
+        
+        // Defaults stay Xavier, and older configs without init fields still load
+        let legacy: MLPConfig = serde_json::from_str(
+            r#"{"input_dim":4,"hidden_dims":[8],"output_dim":2,"activation":"tanh","use_value_head":true,"init_log_std":-0.5}"#,
+        ).unwrap();
+        assert_eq!(legacy.hidden_init, Init::Xavier);
+        assert_eq!(legacy.policy_init, Init::Xavier);
+    }
+
+    /// Check `policy.backward` against central finite differences of `loss`
+    /// summed over `batch`, one parameter at a time. `loss` returns the loss
+    /// of a forward pass and its gradient with respect to the outputs.
+    async fn gradient_check(
+        policy: &mut MLPPolicy,
+        batch: &[Array1<f32>],
+        loss: impl Fn(&PolicyOutput) -> (f32, OutputGradient),
+    ) {
+        let params = policy.get_parameters().await.unwrap();
+        let mut analytic = vec![0.0; params.len()];
+        for obs in batch {
+            let (_, grad) = loss(&policy.forward_impl(&obs.view()));
+            let sample = policy.backward(&obs.view(), &grad);
+            assert_eq!(sample.len(), params.len());
+            for (total, g) in analytic.iter_mut().zip(sample) {
+                *total += g;
+            }
+        }
+        
+        let total_loss = |policy: &MLPPolicy| -> f32 {
+            batch.iter().map(|obs| loss(&policy.forward_impl(&obs.view())).0).sum()
+        };
+        let eps = 1e-2;
+        for i in 0..params.len() {
+            let mut shifted = params.clone();
+            shifted[i] = params[i] + eps;
+            policy.set_parameters(&shifted).await.unwrap();
+            let up = total_loss(policy);
+            shifted[i] = params[i] - eps;
+            policy.set_parameters(&shifted).await.unwrap();
+            let down = total_loss(policy);
+            
+            let numeric = (up - down) / (2.0 * eps);
+            assert!(
+                (analytic[i] - numeric).abs() <= 5e-3 * (1.0 + numeric.abs()),
+                "parameter {}: backward gives {}, finite differences {}", i, analytic[i], numeric
+            );
+        }
+        policy.set_parameters(&params).await.unwrap();
+    }
+    
+    fn batch() -> Vec<Array1<f32>> {
+        vec![arr1(&[0.5, -0.3, 0.8]), arr1(&[-0.7, 0.1, 0.2]), arr1(&[0.05, 0.9, -0.4])]
+    }
+    
+    #[tokio::test]
+    async fn test_backward_matches_finite_differences_discrete() {
+        let mut policy = MLPPolicy::new(MLPConfig {
+            input_dim: 3,
+            hidden_dims: vec![5, 4],
+            output_dim: 3,
+            activation: Activation::Tanh,
+            ..MLPConfig::default()
+        });
+        
+        // Cross-entropy on action 1 plus squared value error
+        gradient_check(&mut policy, &batch(), |output| {
+            let probs = masked_softmax(&output.action_output);
+            let value_error = output.value.unwrap() - 0.5;
+            let mut logit_grad = probs.clone();
+            logit_grad[1] -= 1.0;
+            (
+                -probs[1].ln() + 0.5 * value_error.powi(2),
+                OutputGradient { action_output: logit_grad, value: value_error, log_std: None },
+            )
+        }).await;
+    }
+    
+    #[tokio::test]
+    async fn test_backward_matches_finite_differences_continuous() {
+        let mut policy = MLPPolicy::new(MLPConfig {
+            input_dim: 3,
+            hidden_dims: vec![4],
+            output_dim: 2,
+            activation: Activation::Sigmoid,
+            hidden_init: Init::He,
+            ..MLPConfig::default()
+        });
+        
+        // Gaussian negative log likelihood of a fixed action plus squared value error
+        let action = arr1(&[0.3, -0.6]);
+        gradient_check(&mut policy, &batch(), |output| {
+            let mean = &output.action_output;
+            let log_std = output.log_std.as_ref().unwrap();
+            let z = (&action - mean) / log_std.mapv(f32::exp);
+            let value_error = output.value.unwrap() + 1.0;
+            (
+                (0.5 * &z * &z + log_std).sum() + 0.5 * value_error.powi(2),
+                OutputGradient {
+                    action_output: -&z / log_std.mapv(f32::exp),
+                    value: value_error,
+                    log_std: Some(1.0 - &z * &z),
+                },
+            )
+        }).await;
+    }
+
+    #[test]
+    fn test_input_gradient_matches_finite_differences() {
+        let policy = MLPPolicy::new(MLPConfig {
+            input_dim: 3,
+            hidden_dims: vec![5],
+            output_dim: 1,
+            use_value_head: false,
+            ..MLPConfig::default()
+        });
+        let grad = OutputGradient { action_output: arr1(&[1.0]), value: 0.0, log_std: None };
+        
+        for obs in batch() {
+            let analytic = policy.input_gradient(&obs.view(), &grad);
+            let eps = 1e-2;
+            for i in 0..obs.len() {
+                let (mut up, mut down) = (obs.clone(), obs.clone());
+                up[i] += eps;
+                down[i] -= eps;
+                let numeric = (policy.forward_impl(&up.view()).action_output[0]
+                    - policy.forward_impl(&down.view()).action_output[0]) / (2.0 * eps);
+                assert!(
+                    (analytic[i] - numeric).abs() <= 5e-3 * (1.0 + numeric.abs()),
+                    "input {}: input_gradient gives {}, finite differences {}", i, analytic[i], numeric
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_activations_and_derivatives() {
+        let leaky = Activation::LeakyReLU { slope: 0.1 };
+        // (activation, x, value, derivative)
+        let cases = [
+            (Activation::Tanh, -2.0, -0.9640276, 0.0706508),
+            (Activation::Tanh, 0.0, 0.0, 1.0),
+            (Activation::Tanh, 0.5, 0.4621172, 0.7864477),
+            (Activation::ReLU, -1.5, 0.0, 0.0),
+            (Activation::ReLU, 0.0, 0.0, 0.0),
+            (Activation::ReLU, 2.0, 2.0, 1.0),
+            (Activation::GELU, -1.0, -0.1586553, -0.0833155),
+            (Activation::GELU, 0.0, 0.0, 0.5),
+            (Activation::GELU, 1.0, 0.8413447, 1.0833155),
+            (leaky, -2.0, -0.2, 0.1),
+            (leaky, 0.0, 0.0, 0.1),
+            (leaky, 3.0, 3.0, 1.0),
+            (Activation::Sigmoid, -1.0, 0.2689414, 0.1966119),
+            (Activation::Sigmoid, 0.0, 0.5, 0.25),
+        ];
+        for (activation, x, value, derivative) in cases {
+            assert!((activation.apply(x) - value).abs() < 1e-5, "{:?}({})", activation, x);
+            assert!((activation.derivative(x) - derivative).abs() < 1e-5, "{:?}'({})", activation, x);
+            
+            // Away from kinks the derivative matches the slope of `apply`
+            if x != 0.0 {
+                let eps = 1e-3;
+                let numeric = (activation.apply(x + eps) - activation.apply(x - eps)) / (2.0 * eps);
+                assert!((activation.derivative(x) - numeric).abs() < 1e-3, "{:?}'({})", activation, x);
+            }
+        }
+        
+        // Names are checked when the config is built, not when it runs
+        assert_eq!("relu".parse::<Activation>().unwrap(), Activation::ReLU);
+        assert_eq!("leaky_relu:0.2".parse::<Activation>().unwrap(), Activation::LeakyReLU { slope: 0.2 });
+        assert!("swish".parse::<Activation>().is_err());
+        let config = r#"{"input_dim":4,"hidden_dims":[8],"output_dim":2,"use_value_head":true,"init_log_std":-0.5,"activation":"#;
+        let parsed: MLPConfig = serde_json::from_str(&format!("{}\"gelu\"}}", config)).unwrap();
+        assert_eq!(parsed.activation, Activation::GELU);
+        let parsed: MLPConfig = serde_json::from_str(&format!("{}{{\"leaky_relu\":{{\"slope\":0.2}}}}}}", config)).unwrap();
+        assert_eq!(parsed.activation, Activation::LeakyReLU { slope: 0.2 });
+        assert!(serde_json::from_str::<MLPConfig>(&format!("{}\"swish\"}}", config)).is_err());
+    }
+}
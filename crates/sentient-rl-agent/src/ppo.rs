@@ -5,7 +5,7 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-use sentient_rl_core::{Agent, Action, Observation, StepInfo, Experience};
+use sentient_rl_core::{Agent, DiscreteAction, Environment, Policy, VectorObservation};
 
 /// PPO-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +31,29 @@ pub struct PPOConfig {
     pub use_gae: bool,
     /// Normalize advantages
     pub normalize_advantages: bool,
+    /// Environment steps collected per rollout
+    #[serde(default = "default_n_steps")]
+    pub n_steps: usize,
+    /// Standardize value targets within each minibatch, so the value head
+    /// learns returns in zero-mean, unit-std units
+    #[serde(default)]
+    pub standardize_returns: bool,
+    /// Rollouts each update trains on: the newest plus up to
+    /// `reuse_rollouts - 1` earlier ones, importance-weighted. Their
+    /// advantages date from collection, so keep this small.
+    #[serde(default = "crate::ppo_full::default_reuse_rollouts")]
+    pub reuse_rollouts: usize,
+    /// Cap on the importance weight of a reused transition
+    #[serde(default = "crate::ppo_full::default_max_importance_weight")]
+    pub max_importance_weight: f32,
+    /// Longest run of steps a recurrent policy is unrolled over in training
+    /// (truncated backpropagation through time)
+    #[serde(default = "crate::ppo_full::default_bptt_len")]
+    pub bptt_len: usize,
+}
+
+fn default_n_steps() -> usize {
+    2048
 }
 
 impl Default for PPOConfig {
@@ -46,6 +69,11 @@ impl Default for PPOConfig {
             gae_lambda: 0.95,
             use_gae: true,
             normalize_advantages: true,
+            n_steps: default_n_steps(),
+            standardize_returns: false,
+            reuse_rollouts: crate::ppo_full::default_reuse_rollouts(),
+            max_importance_weight: crate::ppo_full::default_max_importance_weight(),
+            bptt_len: crate::ppo_full::default_bptt_len(),
         }
     }
 }
@@ -70,13 +98,12 @@ impl PPOAgent {
         Ok(Self { inner })
     }
     
-    /// Collect rollout from environment
-    pub async fn collect_rollout(
-        &self,
-        env: &mut dyn sentient_rl_core::Environment,
-        n_steps: usize,
-    ) -> Result<()> {
-        self.inner.collect_rollout(env, n_steps).await
+    /// Collect a rollout of `config.n_steps` steps from environment
+    pub async fn collect_rollout<E>(&self, env: &mut E) -> Result<()>
+    where
+        E: Environment<Observation = VectorObservation, Action = DiscreteAction>,
+    {
+        self.inner.collect_rollout(env).await
     }
     
     /// Train on collected rollout
@@ -87,27 +114,26 @@ impl PPOAgent {
 
 #[async_trait]
 impl Agent for PPOAgent {
-    async fn act(&self, observation: &Observation) -> Result<Action> {
-        self.inner.act(observation).await
-    }
+    type Observation = VectorObservation;
+    type Action = DiscreteAction;
     
-    async fn observe(&mut self, step_info: &StepInfo) -> Result<()> {
-        self.inner.observe(step_info).await
+    fn policy(&self) -> &dyn Policy<Observation = Self::Observation, Action = Self::Action> {
+        &self.inner
     }
     
-    async fn remember(&mut self, experience: Experience) -> Result<()> {
-        self.inner.remember(experience).await
+    fn policy_mut(&mut self) -> &mut dyn Policy<Observation = Self::Observation, Action = Self::Action> {
+        &mut self.inner
     }
     
-    async fn train(&mut self) -> Result<()> {
-        self.inner.train().await
+    async fn reset_episode(&mut self) -> sentient_rl_core::Result<()> {
+        Agent::reset_episode(&mut self.inner).await
     }
     
-    async fn save(&self, path: &Path) -> Result<()> {
+    async fn save(&self, path: &Path) -> sentient_rl_core::Result<()> {
         self.inner.save(path).await
     }
     
-    async fn load(&mut self, path: &Path) -> Result<()> {
+    async fn load(&mut self, path: &Path) -> sentient_rl_core::Result<()> {
         self.inner.load(path).await
     }
-}
\ No newline at end of file
+}
@@ -3,33 +3,71 @@
 use anyhow::{Result, Context};
 use async_trait::async_trait;
 use ndarray::{Array1, Array2, ArrayView1, ArrayView2, Axis};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 
 use sentient_rl_core::{
-    Agent, AgentConfig, Environment, Observation, Action, 
-    StepInfo, Trajectory, Experience as CoreExperience,
+    Agent, AgentMode, DiscreteAction, Environment, Policy, VectorObservation,
 };
 
-use crate::policy::{PolicyNetwork, MLPConfig, create_policy_network};
-use crate::utils::LinearSchedule;
+use crate::ppo::PPOConfig;
+use crate::policy::{masked_softmax, sample_categorical, Activation, Init, MLPConfig, MLPPolicy, PolicyNetwork};
+use crate::sanitize::ObservationSanitizer;
+use crate::utils::{LinearSchedule, Schedule};
 
-/// PPO rollout buffer for storing trajectories
+/// Below this spread, advantages are only centered: dividing by a
+/// near-zero std would blow rounding noise up into huge values
+const MIN_ADVANTAGE_STD: f32 = 1e-6;
+
+/// Environment steps over which the learning rate decays to a tenth
+const LR_DECAY_STEPS: usize = 1_000_000;
+
+/// On-policy rollout buffer for storing trajectories (shared by PPO and A2C)
 #[derive(Debug, Clone)]
 pub struct RolloutBuffer {
-    observations: Vec<Array1<f32>>,
-    actions: Vec<Array1<f32>>,
-    rewards: Vec<f32>,
-    values: Vec<f32>,
-    log_probs: Vec<f32>,
-    dones: Vec<bool>,
-    advantages: Vec<f32>,
-    returns: Vec<f32>,
+    pub(crate) observations: Vec<Array1<f32>>,
+    pub(crate) actions: Vec<Array1<f32>>,
+    pub(crate) rewards: Vec<f32>,
+    pub(crate) values: Vec<f32>,
+    pub(crate) log_probs: Vec<f32>,
+    pub(crate) dones: Vec<bool>,
+    pub(crate) advantages: Vec<f32>,
+    pub(crate) returns: Vec<f32>,
+    /// Recurrent state each observation was seen with (empty for feed-forward policies)
+    pub(crate) hidden_states: Vec<Array1<f32>>,
+    /// Valid-action mask each action was chosen under (empty if never recorded)
+    pub(crate) action_masks: Vec<Option<Vec<bool>>>,
+    /// Critic's value for the observation a truncated episode was cut off at,
+    /// for steps that ended one (shorter than the rollout if never recorded)
+    pub(crate) truncation_values: Vec<Option<f32>>,
+}
+
+/// Run of consecutive rollout steps unrolled together for truncated BPTT
+#[derive(Debug, Clone)]
+pub(crate) struct BpttSequence {
+    pub(crate) start: usize,
+    pub(crate) len: usize,
+    /// Recurrent state before the first step of the run (`None` for
+    /// feed-forward policies)
+    pub(crate) initial_hidden: Option<Array1<f32>>,
 }
 
 impl RolloutBuffer {
-    fn new() -> Self {
+    /// Index of each stored one-hot action, and the rewards
+    pub(crate) fn trace(&self) -> (Vec<usize>, Vec<f32>) {
+        let actions = self
+            .actions
+            .iter()
+            .map(|one_hot| one_hot.iter().position(|&x| x > 0.5).unwrap_or(0))
+            .collect();
+        (actions, self.rewards.clone())
+    }
+    
+    pub(crate) fn new() -> Self {
         Self {
             observations: Vec::new(),
             actions: Vec::new(),
@@ -39,10 +77,17 @@ impl RolloutBuffer {
             dones: Vec::new(),
             advantages: Vec::new(),
             returns: Vec::new(),
+            hidden_states: Vec::new(),
+            action_masks: Vec::new(),
+            truncation_values: Vec::new(),
         }
     }
     
-    fn add(
+    pub(crate) fn len(&self) -> usize {
+        self.observations.len()
+    }
+    
+    pub(crate) fn add(
         &mut self,
         obs: Array1<f32>,
         action: Array1<f32>,
@@ -59,65 +104,158 @@ impl RolloutBuffer {
         self.dones.push(done);
     }
     
-    fn compute_returns_and_advantages(&mut self, last_value: f32, gamma: f32, gae_lambda: f32) {
+    /// Record the recurrent state the policy held for the step just added
+    pub(crate) fn add_hidden_state(&mut self, hidden: Array1<f32>) {
+        self.hidden_states.push(hidden);
+    }
+    
+    /// Record the valid-action mask for the step just added
+    pub(crate) fn add_action_mask(&mut self, mask: Option<Vec<bool>>) {
+        self.action_masks.push(mask);
+    }
+    
+    /// Mark the step just added as ending its episode by truncation (a time
+    /// limit) rather than a terminal state. Its return bootstraps from
+    /// `value`, the critic's estimate for the observation it was cut off at.
+    pub(crate) fn mark_truncated(&mut self, value: f32) {
+        let last = self.len() - 1;
+        self.truncation_values.resize(self.len(), None);
+        self.truncation_values[last] = Some(value);
+    }
+    
+    fn truncation_value(&self, i: usize) -> Option<f32> {
+        self.truncation_values.get(i).copied().flatten()
+    }
+    
+    /// Split the rollout into the runs of steps training replays together:
+    /// at most `max_len` steps that never cross an episode end, each
+    /// starting from the hidden state stored for its first step. Without
+    /// stored hidden states every step is a run of its own.
+    pub(crate) fn bptt_sequences(&self, max_len: usize) -> Vec<BpttSequence> {
+        if self.hidden_states.len() != self.len() {
+            return (0..self.len())
+                .map(|start| BpttSequence { start, len: 1, initial_hidden: None })
+                .collect();
+        }
+        
+        let mut sequences = Vec::new();
+        let mut start = 0;
+        for i in 0..self.len() {
+            if self.dones[i] || i + 1 - start == max_len.max(1) || i + 1 == self.len() {
+                sequences.push(BpttSequence {
+                    start,
+                    len: i + 1 - start,
+                    initial_hidden: Some(self.hidden_states[start].clone()),
+                });
+                start = i + 1;
+            }
+        }
+        sequences
+    }
+    
+    /// Fill in GAE advantages and returns. Fails if a reward or value is
+    /// NaN or infinite, since it would spread to every earlier step.
+    pub(crate) fn compute_returns_and_advantages(&mut self, last_value: f32, gamma: f32, gae_lambda: f32) -> Result<()> {
+        if !last_value.is_finite() {
+            anyhow::bail!("Bootstrap value is {}", last_value);
+        }
+        if let Some(i) = (0..self.rewards.len()).find(|&i| {
+            !self.rewards[i].is_finite()
+                || !self.values[i].is_finite()
+                || self.truncation_value(i).is_some_and(|v| !v.is_finite())
+        }) {
+            anyhow::bail!(
+                "Rollout step {} has reward {}, value {} and truncation value {:?}",
+                i, self.rewards[i], self.values[i], self.truncation_value(i)
+            );
+        }
+        
         let n = self.rewards.len();
         self.advantages = vec![0.0; n];
         self.returns = vec![0.0; n];
         
         let mut last_gae_lam = 0.0;
-        let mut last_value = last_value;
         
         // Compute advantages using GAE
         for i in (0..n).rev() {
-            let next_value = if i == n - 1 {
+            // Nothing follows a terminal state, but a truncated episode
+            // still had a future worth what the critic says
+            let next_value = if self.dones[i] {
+                self.truncation_value(i).unwrap_or(0.0)
+            } else if i == n - 1 {
                 last_value
             } else {
                 self.values[i + 1]
             };
             
             let next_non_terminal = if self.dones[i] { 0.0 } else { 1.0 };
-            let delta = self.rewards[i] + gamma * next_value * next_non_terminal - self.values[i];
+            let delta = self.rewards[i] + gamma * next_value - self.values[i];
             
             last_gae_lam = delta + gamma * gae_lambda * next_non_terminal * last_gae_lam;
             self.advantages[i] = last_gae_lam;
             self.returns[i] = self.advantages[i] + self.values[i];
-            
-            if self.dones[i] {
-                last_gae_lam = 0.0;
-                last_value = 0.0;
-            }
         }
+        
+        Ok(())
     }
     
-    fn normalize_advantages(&mut self) {
-        let mean: f32 = self.advantages.iter().sum::<f32>() / self.advantages.len() as f32;
+    /// Rescale advantages to zero mean and unit std. When they (nearly) all
+    /// agree, as in a single-step rollout, they are only centered.
+    pub(crate) fn normalize_advantages(&mut self) -> Result<()> {
+        if self.advantages.is_empty() {
+            anyhow::bail!("Cannot normalize advantages of an empty rollout");
+        }
+        if let Some(adv) = self.advantages.iter().find(|a| !a.is_finite()) {
+            anyhow::bail!("Cannot normalize advantages: found {}", adv);
+        }
+        
+        let n = self.advantages.len() as f32;
+        let mean: f32 = self.advantages.iter().sum::<f32>() / n;
         let variance: f32 = self.advantages.iter()
             .map(|a| (a - mean).powi(2))
-            .sum::<f32>() / self.advantages.len() as f32;
-        let std = variance.sqrt() + 1e-8;
+            .sum::<f32>() / n;
+        let std = variance.sqrt();
         
         for adv in &mut self.advantages {
-            *adv = (*adv - mean) / std;
+            *adv -= mean;
+            if std >= MIN_ADVANTAGE_STD {
+                *adv /= std;
+            }
         }
+        
+        debug_assert!(self.advantages.iter().all(|a| a.is_finite()));
+        Ok(())
     }
     
-    fn get_batch(&self, indices: &[usize]) -> RolloutBatch {
+    /// Batch of this rollout's steps at `indices`
+    #[cfg(any(test, feature = "bench"))]
+    pub(crate) fn get_batch(&self, indices: &[usize]) -> RolloutBatch {
+        let indices: Vec<(usize, usize)> = indices.iter().map(|&idx| (0, idx)).collect();
+        Self::get_batch_from(&[self], &indices)
+    }
+    
+    /// Batch of the steps `(rollout, step)` drawn from several rollouts,
+    /// with unit importance weights
+    pub(crate) fn get_batch_from(rollouts: &[&RolloutBuffer], indices: &[(usize, usize)]) -> RolloutBatch {
         let batch_size = indices.len();
-        let obs_dim = self.observations[0].len();
-        let act_dim = self.actions[0].len();
+        let obs_dim = rollouts[0].observations[0].len();
+        let act_dim = rollouts[0].actions[0].len();
         
         let mut obs_batch = Array2::zeros((batch_size, obs_dim));
         let mut act_batch = Array2::zeros((batch_size, act_dim));
         let mut old_log_probs = Array1::zeros(batch_size);
         let mut advantages = Array1::zeros(batch_size);
         let mut returns = Array1::zeros(batch_size);
+        let mut action_masks = Vec::with_capacity(batch_size);
         
-        for (i, &idx) in indices.iter().enumerate() {
-            obs_batch.row_mut(i).assign(&self.observations[idx]);
-            act_batch.row_mut(i).assign(&self.actions[idx]);
-            old_log_probs[i] = self.log_probs[idx];
-            advantages[i] = self.advantages[idx];
-            returns[i] = self.returns[idx];
+        for (i, &(rollout, idx)) in indices.iter().enumerate() {
+            let rollout = rollouts[rollout];
+            obs_batch.row_mut(i).assign(&rollout.observations[idx]);
+            act_batch.row_mut(i).assign(&rollout.actions[idx]);
+            old_log_probs[i] = rollout.log_probs[idx];
+            advantages[i] = rollout.advantages[idx];
+            returns[i] = rollout.returns[idx];
+            action_masks.push(rollout.action_mask(idx).map(<[bool]>::to_vec));
         }
         
         RolloutBatch {
@@ -126,10 +264,34 @@ impl RolloutBuffer {
             old_log_probs,
             advantages,
             returns,
+            importance_weights: Array1::ones(batch_size),
+            action_masks,
+            initial_hidden: vec![None; batch_size],
+        }
+    }
+    
+    /// Batch of whole `(rollout, sequence)` runs, each row restarting the
+    /// recurrent state where its sequence begins
+    pub(crate) fn get_sequence_batch(rollouts: &[&RolloutBuffer], sequences: &[(usize, BpttSequence)]) -> RolloutBatch {
+        let indices: Vec<(usize, usize)> = sequences
+            .iter()
+            .flat_map(|(r, seq)| (seq.start..seq.start + seq.len).map(move |step| (*r, step)))
+            .collect();
+        let mut batch = Self::get_batch_from(rollouts, &indices);
+        let mut row = 0;
+        for (_, seq) in sequences {
+            batch.initial_hidden[row].clone_from(&seq.initial_hidden);
+            row += seq.len;
         }
+        batch
+    }
+    
+    /// Mask step `i`'s action was chosen under, if one was recorded
+    pub(crate) fn action_mask(&self, i: usize) -> Option<&[bool]> {
+        self.action_masks.get(i).and_then(Option::as_deref)
     }
     
-    fn clear(&mut self) {
+    pub(crate) fn clear(&mut self) {
         self.observations.clear();
         self.actions.clear();
         self.rewards.clear();
@@ -138,16 +300,126 @@ impl RolloutBuffer {
         self.dones.clear();
         self.advantages.clear();
         self.returns.clear();
+        self.hidden_states.clear();
+        self.action_masks.clear();
+        self.truncation_values.clear();
     }
 }
 
 /// Batch of rollout data for training
-struct RolloutBatch {
-    observations: Array2<f32>,
-    actions: Array2<f32>,
-    old_log_probs: Array1<f32>,
-    advantages: Array1<f32>,
-    returns: Array1<f32>,
+pub(crate) struct RolloutBatch {
+    pub(crate) observations: Array2<f32>,
+    pub(crate) actions: Array2<f32>,
+    pub(crate) old_log_probs: Array1<f32>,
+    pub(crate) advantages: Array1<f32>,
+    pub(crate) returns: Array1<f32>,
+    /// Truncated importance weight of each step: 1 for the newest rollout,
+    /// below or at `max_importance_weight` for reused ones
+    pub(crate) importance_weights: Array1<f32>,
+    /// Valid-action mask each step's action was chosen under
+    pub(crate) action_masks: Vec<Option<Vec<bool>>>,
+    /// Recurrent state to restart from before each step: the stored state
+    /// where a BPTT sequence begins, `None` within one and for feed-forward
+    /// policies
+    pub(crate) initial_hidden: Vec<Option<Array1<f32>>>,
+}
+
+/// Default for `reuse_rollouts`: train on the newest rollout only
+pub(crate) fn default_reuse_rollouts() -> usize {
+    1
+}
+
+/// Default cap on the importance weight of a reused transition
+pub(crate) fn default_max_importance_weight() -> f32 {
+    1.0
+}
+
+/// Default longest run a recurrent policy is unrolled over in training
+pub(crate) fn default_bptt_len() -> usize {
+    16
+}
+
+/// Earlier rollouts an on-policy agent keeps to train on again (shared by
+/// PPO and A2C)
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RolloutReplay {
+    capacity: usize,
+    rollouts: VecDeque<RolloutBuffer>,
+}
+
+impl RolloutReplay {
+    /// Keep up to `capacity` earlier rollouts
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            rollouts: VecDeque::with_capacity(capacity),
+        }
+    }
+    
+    /// Keep a finished rollout (one with returns computed), forgetting the
+    /// oldest beyond capacity
+    pub(crate) fn retain(&mut self, rollout: &RolloutBuffer) {
+        if self.capacity == 0 || rollout.len() == 0 || rollout.returns.len() != rollout.len() {
+            return;
+        }
+        self.rollouts.push_back(rollout.clone());
+        while self.rollouts.len() > self.capacity {
+            self.rollouts.pop_front();
+        }
+    }
+    
+    /// Kept rollouts, newest first
+    pub(crate) fn rollouts(&self) -> impl Iterator<Item = &RolloutBuffer> {
+        self.rollouts.iter().rev()
+    }
+}
+
+/// Importance weight `pi(a|s) / mu(a|s)` of a transition the behavior policy
+/// took with `behavior_log_prob` and the current policy gives `log_prob`,
+/// truncated at `max_weight` to keep the variance (and bias) of reuse low
+pub(crate) fn truncated_importance_weight(log_prob: f32, behavior_log_prob: f32, max_weight: f32) -> f32 {
+    (log_prob - behavior_log_prob).exp().min(max_weight)
+}
+
+/// Log probability of the one-hot `action` under the softmax of `logits`
+fn discrete_log_prob(logits: &Array1<f32>, action: &ArrayView1<f32>) -> f32 {
+    let max_logit = logits.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+    let log_sum_exp = logits.mapv(|x| (x - max_logit).exp()).sum().ln() + max_logit;
+    let action_idx = action.iter().position(|&x| x == 1.0).unwrap_or(0);
+    logits[action_idx] - log_sum_exp
+}
+
+/// Mean and spread of a minibatch's returns, used to standardize its value
+/// targets and to map value predictions back to return units
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReturnScale {
+    /// Mean return
+    pub mean: f32,
+    /// Return std, or 1 when the returns (nearly) all agree
+    pub std: f32,
+}
+
+impl ReturnScale {
+    /// Scale of `returns`. Returns that (nearly) all agree are only
+    /// centered, like advantages.
+    pub fn of(returns: &Array1<f32>) -> Self {
+        let mean = returns.mean().unwrap_or(0.0);
+        let std = returns.std(0.0);
+        Self {
+            mean,
+            std: if std >= MIN_ADVANTAGE_STD { std } else { 1.0 },
+        }
+    }
+    
+    /// `value` in standardized units
+    pub fn standardize(&self, value: f32) -> f32 {
+        (value - self.mean) / self.std
+    }
+    
+    /// Standardized `value` back in return units
+    pub fn unstandardize(&self, value: f32) -> f32 {
+        value * self.std + self.mean
+    }
 }
 
 /// Full PPO Agent implementation
@@ -158,6 +430,15 @@ pub struct PPOAgentFull {
     rollout_buffer: Arc<RwLock<RolloutBuffer>>,
     learning_rate_schedule: LinearSchedule,
     total_timesteps: Arc<RwLock<usize>>,
+    sanitizer: ObservationSanitizer,
+    mode: std::sync::Mutex<AgentMode>,
+    /// Scale of the last minibatch the value head was trained on, with
+    /// `standardize_returns`
+    return_scale: std::sync::Mutex<Option<ReturnScale>>,
+    /// Earlier rollouts reused by `train`
+    replay: Arc<RwLock<RolloutReplay>>,
+    /// Source of action samples, minibatch shuffles and update noise
+    rng: std::sync::Mutex<StdRng>,
 }
 
 /// Simple optimizer state
@@ -175,112 +456,272 @@ impl PPOAgentFull {
         observation_space: usize,
         action_space: usize,
     ) -> Result<Self> {
-        // Create policy network
+        Self::build(config, observation_space, action_space, StdRng::from_entropy())
+    }
+    
+    /// Create a PPO agent whose initial weights, action sampling and
+    /// updates are driven by `seed`. Paired with a seeded environment,
+    /// training runs are reproducible step for step.
+    pub async fn new_seeded(
+        config: PPOConfig,
+        observation_space: usize,
+        action_space: usize,
+        seed: u64,
+    ) -> Result<Self> {
+        Self::build(config, observation_space, action_space, StdRng::seed_from_u64(seed))
+    }
+    
+    fn build(
+        config: PPOConfig,
+        observation_space: usize,
+        action_space: usize,
+        mut rng: StdRng,
+    ) -> Result<Self> {
+        config.base.compute.ensure_supported()?;
+        
+        // Create policy network, orthogonally initialized as PPO expects
+        let (hidden_init, policy_init, value_init) = Init::ppo();
         let policy_config = MLPConfig {
             input_dim: observation_space,
             hidden_dims: vec![64, 64],
             output_dim: action_space,
-            activation: "tanh".to_string(),
+            activation: Activation::Tanh,
             use_value_head: true,
             init_log_std: -0.5,
+            hidden_init,
+            policy_init,
+            value_init,
         };
         
-        let policy = create_policy_network(&policy_config);
+        let policy: Box<dyn PolicyNetwork> = Box::new(MLPPolicy::with_rng(policy_config, &mut rng));
         
         // Learning rate schedule
         let lr_schedule = LinearSchedule::new(
             config.base.learning_rate,
             config.base.learning_rate * 0.1,
-            config.base.max_steps as f32,
+            LR_DECAY_STEPS,
         );
         
         Ok(Self {
-            config,
             policy: Arc::new(RwLock::new(policy)),
             optimizer_state: Arc::new(RwLock::new(OptimizerState::default())),
             rollout_buffer: Arc::new(RwLock::new(RolloutBuffer::new())),
             learning_rate_schedule: lr_schedule,
             total_timesteps: Arc::new(RwLock::new(0)),
+            sanitizer: ObservationSanitizer::default(),
+            mode: std::sync::Mutex::new(AgentMode::Train),
+            return_scale: std::sync::Mutex::new(None),
+            replay: Arc::new(RwLock::new(RolloutReplay::new(config.reuse_rollouts.saturating_sub(1)))),
+            rng: std::sync::Mutex::new(rng),
+            config,
         })
     }
     
-    /// Collect rollout
-    pub async fn collect_rollout(
-        &self,
-        env: &mut dyn Environment,
-        n_steps: usize,
-    ) -> Result<()> {
+    fn rng(&self) -> std::sync::MutexGuard<'_, StdRng> {
+        self.rng.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+    
+    /// Total environment steps collected
+    pub async fn total_timesteps(&self) -> usize {
+        *self.total_timesteps.read().await
+    }
+    
+    /// Flattened policy and value parameters, as checkpoints store them
+    pub async fn parameters(&self) -> Result<Vec<f32>> {
+        self.policy.read().await.get_parameters().await
+    }
+    
+    /// Current mode
+    pub fn mode(&self) -> AgentMode {
+        *self.mode.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+    
+    /// Switch between training and evaluation. In eval mode `act` takes the
+    /// policy's greedy action and `train` measures losses without updating.
+    pub fn set_mode(&self, mode: AgentMode) {
+        *self.mode.lock().unwrap_or_else(std::sync::PoisonError::into_inner) = mode;
+    }
+    
+    /// Probability the policy gives each action for `observation`
+    pub async fn action_probabilities(&self, observation: &VectorObservation) -> Result<Vec<f32>> {
+        let obs_array = self.to_array(observation);
+        let output = self.policy.read().await.forward(&obs_array.view()).await?;
+        Ok(masked_softmax(&output.action_output).to_vec())
+    }
+    
+    /// Sanitizer applied to every observation the agent acts on or stores;
+    /// give it the observation space's bounds to clip to them
+    pub fn observation_sanitizer(&self) -> &ObservationSanitizer {
+        &self.sanitizer
+    }
+    
+    /// Scale the value head was last trained in, if returns are standardized
+    pub fn return_scale(&self) -> Option<ReturnScale> {
+        *self.return_scale.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+    
+    /// A value head output in return units. With `standardize_returns` the
+    /// head predicts standardized returns, so it is mapped back with the
+    /// scale of the latest minibatch.
+    pub fn value_estimate(&self, prediction: f32) -> f32 {
+        match self.return_scale() {
+            Some(scale) => scale.unstandardize(prediction),
+            None => prediction,
+        }
+    }
+    
+    /// Value targets the loss compares the value head against: the returns,
+    /// standardized within the minibatch when `standardize_returns` is set
+    pub(crate) fn value_targets(&self, batch: &RolloutBatch) -> (Array1<f32>, Option<ReturnScale>) {
+        if !self.config.standardize_returns {
+            return (batch.returns.clone(), None);
+        }
+        let scale = ReturnScale::of(&batch.returns);
+        (batch.returns.mapv(|r| scale.standardize(r)), Some(scale))
+    }
+    
+    /// Sanitized network input for an environment observation
+    fn to_array(&self, observation: &VectorObservation) -> Array1<f32> {
+        let mut array: Array1<f32> = observation.data.iter().map(|&x| x as f32).collect();
+        self.sanitizer.sanitize(&mut array);
+        array
+    }
+    
+    /// Actions taken and rewards received during the last rollout
+    pub(crate) async fn rollout_trace(&self) -> (Vec<usize>, Vec<f32>) {
+        self.rollout_buffer.read().await.trace()
+    }
+    
+    /// Clear the policy's recurrent state before a new episode
+    pub async fn reset_episode(&self) -> Result<()> {
+        self.policy.write().await.reset_hidden_state();
+        Ok(())
+    }
+    
+    /// Collect a rollout of `config.n_steps` steps, resetting the
+    /// environment and the policy's episode state whenever an episode ends
+    /// or is truncated. A recurrent policy's state advances with every step
+    /// and is stored alongside it for [`train`](Self::train) to replay.
+    pub async fn collect_rollout<E>(&self, env: &mut E) -> Result<()>
+    where
+        E: Environment<Observation = VectorObservation, Action = DiscreteAction>,
+    {
         let mut buffer = self.rollout_buffer.write().await;
+        self.replay.write().await.retain(&buffer);
         buffer.clear();
+        self.sanitizer.set_space(env.observation_space().as_ref());
         
-        let mut obs = env.reset().await?;
+        let (mut obs, info) = env.reset().await?;
+        let mut mask = info.action_mask();
+        self.reset_episode().await?;
         
-        for _ in 0..n_steps {
-            // Get action from policy
-            let obs_array = Array1::from_vec(obs.as_slice().to_vec());
-            let policy = self.policy.read().await;
-            let (action, log_prob) = policy.sample_action(&obs_array.view()).await?;
+        for _ in 0..self.config.n_steps {
+            // Get action from policy, among the valid ones if the env says which
+            let obs_array = self.to_array(&obs);
+            let mut policy = self.policy.write().await;
+            let hidden = policy.hidden_state();
+            let output = policy.forward_masked(&obs_array.view(), mask.as_deref()).await?;
+            if let Some(next) = &output.hidden_state {
+                policy.set_hidden_state(next)?;
+            }
+            drop(policy);
+            let probs = masked_softmax(&output.action_output);
+            let action_idx = sample_categorical(&probs, self.rng().gen::<f32>());
+            let log_prob = probs[action_idx].ln();
+            let mut action = Array1::zeros(probs.len());
+            action[action_idx] = 1.0;
             
             // Get value estimate
-            let output = policy.forward(&obs_array.view()).await?;
-            let value = output.value.unwrap_or(0.0);
-            drop(policy);
+            let value = self.value_estimate(output.value.unwrap_or(0.0));
             
             // Step environment
-            let action_vec = action.to_vec();
-            let step_info = env.step(Action::new(action_vec)).await?;
+            let step = env.step(DiscreteAction(action_idx)).await?;
             
             // Store transition
+            let episode_over = step.done || step.truncated;
             buffer.add(
                 obs_array,
                 action,
-                step_info.reward.0,
+                step.reward.0 as f32,
                 value,
                 log_prob,
-                step_info.done,
+                episode_over,
             );
+            if step.truncated {
+                let final_obs = self.to_array(&step.observation);
+                let policy = self.policy.read().await;
+                let final_value = policy.forward(&final_obs.view()).await?.value.unwrap_or(0.0);
+                buffer.mark_truncated(self.value_estimate(final_value));
+            }
+            if let Some(hidden) = hidden {
+                buffer.add_hidden_state(hidden);
+            }
+            buffer.add_action_mask(mask.take());
             
             // Update timestep counter
             *self.total_timesteps.write().await += 1;
             
-            if step_info.done {
-                obs = env.reset().await?;
+            if episode_over {
+                let (reset_obs, info) = env.reset().await?;
+                obs = reset_obs;
+                mask = info.action_mask();
+                self.reset_episode().await?;
             } else {
-                obs = step_info.observation;
+                mask = step.info.action_mask();
+                obs = step.observation;
             }
         }
         
         // Compute returns and advantages
-        let last_obs = Array1::from_vec(obs.as_slice().to_vec());
+        let last_obs = self.to_array(&obs);
         let policy = self.policy.read().await;
         let last_output = policy.forward(&last_obs.view()).await?;
-        let last_value = last_output.value.unwrap_or(0.0);
+        let last_value = self.value_estimate(last_output.value.unwrap_or(0.0));
         drop(policy);
         
         buffer.compute_returns_and_advantages(
             last_value,
-            self.config.base.gamma,
-            self.config.gae_lambda,
-        );
+            self.config.base.gamma as f32,
+            self.config.gae_lambda as f32,
+        )?;
         
         if self.config.normalize_advantages {
-            buffer.normalize_advantages();
+            buffer.normalize_advantages()?;
         }
         
         Ok(())
     }
     
-    /// Train on collected rollout
+    /// Train on collected rollout, plus up to `reuse_rollouts - 1` earlier
+    /// ones. Earlier transitions are weighted by how much likelier the
+    /// current policy makes their actions (truncated at
+    /// `max_importance_weight`), and their clipping ratio is taken against
+    /// the policy as it stood when this call began.
+    ///
+    /// A recurrent policy is trained on whole runs of at most `bptt_len`
+    /// steps, each replayed from the hidden state stored at its start.
     pub async fn train(&self) -> Result<PPOTrainingStats> {
         let buffer = self.rollout_buffer.read().await;
+        let replay = self.replay.read().await;
         let n_samples = buffer.observations.len();
         
         if n_samples == 0 {
             return Err(anyhow::anyhow!("No data in rollout buffer"));
         }
         
-        let batch_size = n_samples / self.config.num_minibatches;
-        let indices: Vec<usize> = (0..n_samples).collect();
+        let rollouts: Vec<&RolloutBuffer> = std::iter::once(&*buffer).chain(replay.rollouts()).collect();
+        let mut corrections = vec![Vec::new()];
+        for rollout in &rollouts[1..] {
+            corrections.push(self.reuse_corrections(rollout).await?);
+        }
+        let bptt_len = self.config.bptt_len;
+        let sequences: Vec<(usize, BpttSequence)> = rollouts
+            .iter()
+            .enumerate()
+            .flat_map(|(r, rollout)| rollout.bptt_sequences(bptt_len).into_iter().map(move |seq| (r, seq)))
+            .collect();
+        let n_transitions: usize = rollouts.iter().map(|rollout| rollout.len()).sum();
+        let batch_size = sequences.len() / self.config.num_minibatches;
         
         let mut total_policy_loss = 0.0;
         let mut total_value_loss = 0.0;
@@ -290,28 +731,26 @@ impl PPOAgentFull {
         for _ in 0..self.config.ppo_epochs {
             // Shuffle indices
             use rand::seq::SliceRandom;
-            let mut rng = rand::thread_rng();
-            let mut shuffled_indices = indices.clone();
-            shuffled_indices.shuffle(&mut rng);
+            let mut shuffled_sequences = sequences.clone();
+            shuffled_sequences.shuffle(&mut *self.rng());
             
-            // Train on minibatches
+            // Train on minibatches of whole sequences
             for i in 0..self.config.num_minibatches {
                 let start = i * batch_size;
-                let end = ((i + 1) * batch_size).min(n_samples);
-                let batch_indices = &shuffled_indices[start..end];
-                
-                let batch = buffer.get_batch(batch_indices);
-                
-                // Compute losses
-                let (policy_loss, value_loss, entropy) = self.compute_losses(&batch).await?;
+                let end = ((i + 1) * batch_size).min(sequences.len());
+                let batch_sequences = &shuffled_sequences[start..end];
                 
-                // Compute total loss
-                let total_loss = policy_loss 
-                    + self.config.value_loss_coef * value_loss
-                    - self.config.entropy_coef * entropy;
-                
-                // Update policy
-                self.update_policy(total_loss).await?;
+                let mut batch = RolloutBuffer::get_sequence_batch(&rollouts, batch_sequences);
+                let steps = batch_sequences
+                    .iter()
+                    .flat_map(|(r, seq)| (seq.start..seq.start + seq.len).map(move |step| (*r, step)));
+                for (j, (r, step)) in steps.enumerate() {
+                    if let Some(&(weight, log_prob)) = corrections[r].get(step) {
+                        batch.importance_weights[j] = weight;
+                        batch.old_log_probs[j] = log_prob;
+                    }
+                }
+                let (policy_loss, value_loss, entropy) = self.train_minibatch(&batch).await?;
                 
                 total_policy_loss += policy_loss;
                 total_value_loss += value_loss;
@@ -320,17 +759,70 @@ impl PPOAgentFull {
             }
         }
         
+        let reused_weight: f32 = corrections.iter().flatten().map(|(weight, _)| weight).sum();
         Ok(PPOTrainingStats {
             policy_loss: total_policy_loss / n_updates as f32,
             value_loss: total_value_loss / n_updates as f32,
             entropy: total_entropy / n_updates as f32,
+            return_scale: self.return_scale(),
+            transitions: n_transitions,
+            mean_importance_weight: (n_samples as f32 + reused_weight) / n_transitions as f32,
         })
     }
     
-    /// Compute PPO losses
-    async fn compute_losses(&self, batch: &RolloutBatch) -> Result<(f32, f32, f32)> {
-        let policy = self.policy.read().await;
+    /// Truncated importance weight and current log probability of each
+    /// transition of an earlier `rollout`
+    async fn reuse_corrections(&self, rollout: &RolloutBuffer) -> Result<Vec<(f32, f32)>> {
+        let mut policy = self.policy.write().await;
+        let live_hidden = policy.hidden_state();
+        let mut corrections = Vec::with_capacity(rollout.len());
+        for (i, ((obs, action), &behavior_log_prob)) in rollout.observations.iter()
+            .zip(&rollout.actions)
+            .zip(&rollout.log_probs)
+            .enumerate()
+        {
+            if let Some(hidden) = rollout.hidden_states.get(i) {
+                policy.set_hidden_state(hidden)?;
+            }
+            let output = policy.forward_masked(&obs.view(), rollout.action_mask(i)).await?;
+            let log_prob = discrete_log_prob(&output.action_output, &action.view());
+            let weight = truncated_importance_weight(log_prob, behavior_log_prob, self.config.max_importance_weight);
+            corrections.push((weight, log_prob));
+        }
+        if let Some(hidden) = &live_hidden {
+            policy.set_hidden_state(hidden)?;
+        }
+        Ok(corrections)
+    }
+    
+    /// One PPO step on a minibatch: compute the losses and, outside eval
+    /// mode, update the policy. Returns policy loss, value loss and entropy.
+    pub(crate) async fn train_minibatch(&self, batch: &RolloutBatch) -> Result<(f32, f32, f32)> {
+        // Compute losses
+        let (value_targets, scale) = self.value_targets(batch);
+        let (policy_loss, value_loss, entropy) = self.compute_losses(batch, &value_targets).await?;
+        
+        // Compute total loss
+        let total_loss = policy_loss 
+            + self.config.value_loss_coef as f32 * value_loss
+            - self.config.entropy_coef as f32 * entropy;
+        
+        // Update policy; eval mode only measures the losses
+        if !self.mode().is_eval() {
+            self.update_policy(total_loss).await?;
+            *self.return_scale.lock().unwrap_or_else(std::sync::PoisonError::into_inner) = scale;
+        }
+        
+        Ok((policy_loss, value_loss, entropy))
+    }
+    
+    /// Compute PPO losses, regressing the value head on `value_targets`
+    async fn compute_losses(&self, batch: &RolloutBatch, value_targets: &Array1<f32>) -> Result<(f32, f32, f32)> {
+        let mut policy = self.policy.write().await;
         let batch_size = batch.observations.nrows();
+        // Unroll each sequence from its stored starting state, then restore
+        // the live state so an episode in progress carries on unchanged
+        let live_hidden = policy.hidden_state();
         
         let mut policy_loss = 0.0;
         let mut value_loss = 0.0;
@@ -342,10 +834,17 @@ impl PPOAgentFull {
             let action = batch.actions.row(i);
             let old_log_prob = batch.old_log_probs[i];
             let advantage = batch.advantages[i];
-            let return_val = batch.returns[i];
+            let return_val = value_targets[i];
+            let weight = batch.importance_weights[i];
             
-            // Forward pass
-            let output = policy.forward(&obs).await?;
+            // Forward pass; masked-out actions get zero probability
+            if let Some(hidden) = &batch.initial_hidden[i] {
+                policy.set_hidden_state(hidden)?;
+            }
+            let output = policy.forward_masked(&obs, batch.action_masks[i].as_deref()).await?;
+            if let Some(hidden) = &output.hidden_state {
+                policy.set_hidden_state(hidden)?;
+            }
             let value_pred = output.value.unwrap_or(0.0);
             
             // Compute action log probability
@@ -367,16 +866,19 @@ impl PPOAgentFull {
                 1.0 + self.config.clip_param as f32,
             );
             let policy_loss_i = -(ratio * advantage).min(clipped_ratio * advantage);
-            policy_loss += policy_loss_i;
+            policy_loss += weight * policy_loss_i;
             
             // Value loss
             let value_loss_i = (value_pred - return_val).powi(2);
-            value_loss += value_loss_i;
+            value_loss += weight * value_loss_i;
             
             // Entropy
             let entropy_i = -(probs.mapv(|p| if p > 0.0 { p * p.ln() } else { 0.0 })).sum();
             entropy += entropy_i;
         }
+        if let Some(hidden) = &live_hidden {
+            policy.set_hidden_state(hidden)?;
+        }
         
         Ok((
             policy_loss / batch_size as f32,
@@ -393,7 +895,7 @@ impl PPOAgentFull {
         
         // Get current learning rate
         let timesteps = *self.total_timesteps.read().await;
-        let lr = self.learning_rate_schedule.value(timesteps as f32);
+        let lr = self.learning_rate_schedule.value(timesteps) as f32;
         
         // Adam optimizer update (simplified)
         let params = policy.get_parameters().await?;
@@ -412,9 +914,12 @@ impl PPOAgentFull {
         
         // Compute pseudo-gradients (in practice, would backprop through network)
         let mut gradients = vec![0.0; n_params];
-        for i in 0..n_params {
-            // Simplified: use loss as gradient signal
-            gradients[i] = loss * (rand::random::<f32>() - 0.5) * 0.1;
+        {
+            let mut rng = self.rng();
+            for i in 0..n_params {
+                // Simplified: use loss as gradient signal
+                gradients[i] = loss * (rng.gen::<f32>() - 0.5) * 0.1;
+            }
         }
         
         // Adam update
@@ -444,40 +949,47 @@ impl PPOAgentFull {
     }
 }
 
-/// PPO training statistics
-#[derive(Debug, Clone)]
-pub struct PPOTrainingStats {
-    pub policy_loss: f32,
-    pub value_loss: f32,
-    pub entropy: f32,
+/// On-disk checkpoint encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointFormat {
+    /// Pretty-printed JSON, for human inspection
+    Json,
+    /// Compact bincode
+    Bincode,
 }
 
-#[async_trait]
-impl Agent for PPOAgentFull {
-    async fn act(&self, observation: &Observation) -> Result<Action> {
-        let obs_array = Array1::from_vec(observation.as_slice().to_vec());
-        let policy = self.policy.read().await;
-        let (action, _) = policy.sample_action(&obs_array.view()).await?;
-        Ok(Action::new(action.to_vec()))
-    }
-    
-    async fn observe(&mut self, step_info: &StepInfo) -> Result<()> {
-        // PPO doesn't need to observe individual steps during evaluation
-        // Training is done in batch mode via collect_rollout
-        Ok(())
-    }
-    
-    async fn remember(&mut self, experience: CoreExperience) -> Result<()> {
-        // PPO uses its own rollout buffer, not individual experiences
-        Ok(())
+impl CheckpointFormat {
+    /// `.bin` files use bincode; everything else is JSON
+    pub fn from_path(path: &std::path::Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("bin") => CheckpointFormat::Bincode,
+            _ => CheckpointFormat::Json,
+        }
     }
-    
-    async fn train(&mut self) -> Result<()> {
-        // Training is handled by the train method above
-        Ok(())
+}
+
+/// Bincode checkpoint payload
+///
+/// The config is kept as JSON since its flattened extra parameters cannot
+/// round-trip through a non-self-describing format.
+#[derive(Serialize, Deserialize)]
+struct BinaryCheckpoint {
+    config: String,
+    parameters: Vec<f32>,
+    total_timesteps: u64,
+}
+
+impl PPOAgentFull {
+    /// Save a checkpoint in the given format regardless of file extension
+    pub async fn save_as(&self, path: &std::path::Path, format: CheckpointFormat) -> Result<()> {
+        match format {
+            CheckpointFormat::Bincode => self.save_bin(path).await,
+            CheckpointFormat::Json => self.save_json(path).await,
+        }
     }
     
-    async fn save(&self, path: &std::path::Path) -> Result<()> {
+    /// Save a pretty-printed JSON checkpoint
+    pub async fn save_json(&self, path: &std::path::Path) -> Result<()> {
         let policy = self.policy.read().await;
         let params = policy.get_parameters().await?;
         
@@ -493,7 +1005,29 @@ impl Agent for PPOAgentFull {
         Ok(())
     }
     
-    async fn load(&mut self, path: &std::path::Path) -> Result<()> {
+    /// Save a compact bincode checkpoint
+    pub async fn save_bin(&self, path: &std::path::Path) -> Result<()> {
+        let checkpoint = BinaryCheckpoint {
+            config: serde_json::to_string(&self.config)?,
+            parameters: self.policy.read().await.get_parameters().await?,
+            total_timesteps: *self.total_timesteps.read().await as u64,
+        };
+        
+        let bytes = bincode::serialize(&checkpoint).context("Failed to encode checkpoint")?;
+        tokio::fs::write(path, bytes).await?;
+        Ok(())
+    }
+    
+    /// Load a checkpoint in the given format regardless of file extension
+    pub async fn load_as(&self, path: &std::path::Path, format: CheckpointFormat) -> Result<()> {
+        match format {
+            CheckpointFormat::Bincode => self.load_bin(path).await,
+            CheckpointFormat::Json => self.load_json(path).await,
+        }
+    }
+    
+    /// Load a JSON checkpoint written by [`save_json`](Self::save_json)
+    pub async fn load_json(&self, path: &std::path::Path) -> Result<()> {
         let json = tokio::fs::read_to_string(path).await?;
         let save_data: serde_json::Value = serde_json::from_str(&json)?;
         
@@ -512,4 +1046,521 @@ impl Agent for PPOAgentFull {
         
         Ok(())
     }
-}
\ No newline at end of file
+    
+    /// Load a bincode checkpoint written by [`save_bin`](Self::save_bin)
+    pub async fn load_bin(&self, path: &std::path::Path) -> Result<()> {
+        let bytes = tokio::fs::read(path).await?;
+        let checkpoint: BinaryCheckpoint = bincode::deserialize(&bytes)
+            .with_context(|| format!("Invalid bincode checkpoint {:?}", path))?;
+        
+        self.policy.write().await.set_parameters(&checkpoint.parameters).await?;
+        *self.total_timesteps.write().await = checkpoint.total_timesteps as usize;
+        Ok(())
+    }
+}
+
+/// PPO training statistics
+#[derive(Debug, Clone)]
+pub struct PPOTrainingStats {
+    pub policy_loss: f32,
+    /// In standardized units when `return_scale` is set
+    pub value_loss: f32,
+    pub entropy: f32,
+    /// Scale of the last minibatch's value targets, with `standardize_returns`
+    pub return_scale: Option<ReturnScale>,
+    /// Transitions each epoch trained on, across the newest and reused rollouts
+    pub transitions: usize,
+    /// Mean importance weight of those transitions (1 without reuse)
+    pub mean_importance_weight: f32,
+}
+
+#[async_trait]
+impl Policy for PPOAgentFull {
+    type Observation = VectorObservation;
+    type Action = DiscreteAction;
+    
+    /// Sample an action in training; take the most likely one in eval mode
+    async fn act(&self, observation: &VectorObservation) -> sentient_rl_core::Result<DiscreteAction> {
+        // Same categorical distribution the rollouts sample from
+        let probs = self.action_probabilities(observation).await?;
+        let action = if self.mode().is_eval() {
+            probs
+                .iter()
+                .enumerate()
+                .fold(0, |best, (i, &p)| if p > probs[best] { i } else { best })
+        } else {
+            sample_categorical(&Array1::from(probs), self.rng().gen::<f32>())
+        };
+        Ok(DiscreteAction(action))
+    }
+}
+
+/// Learning happens in [`PPOAgentFull::collect_rollout`] and
+/// [`PPOAgentFull::train`]; the agent acts as its own policy.
+#[async_trait]
+impl Agent for PPOAgentFull {
+    type Observation = VectorObservation;
+    type Action = DiscreteAction;
+    
+    fn policy(&self) -> &dyn Policy<Observation = Self::Observation, Action = Self::Action> {
+        self
+    }
+    
+    fn policy_mut(&mut self) -> &mut dyn Policy<Observation = Self::Observation, Action = Self::Action> {
+        self
+    }
+    
+    async fn reset_episode(&mut self) -> sentient_rl_core::Result<()> {
+        Ok(PPOAgentFull::reset_episode(self).await?)
+    }
+    
+    async fn save(&self, path: &std::path::Path) -> sentient_rl_core::Result<()> {
+        Ok(self.save_as(path, CheckpointFormat::from_path(path)).await?)
+    }
+    
+    async fn load(&mut self, path: &std::path::Path) -> sentient_rl_core::Result<()> {
+        Ok(self.load_as(path, CheckpointFormat::from_path(path)).await?)
+    }
+    
+    fn mode(&self) -> AgentMode {
+        PPOAgentFull::mode(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentient_rl_core::{
+        ActionSpace, BoxObservationSpace, DiscreteSpace, ObservationSpace, Reward, Step, StepInfo,
+        VectorState,
+    };
+
+    #[tokio::test]
+    async fn test_bin_checkpoint_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let (bin_path, json_path) = (dir.path().join("agent.bin"), dir.path().join("agent.json"));
+
+        let agent = PPOAgentFull::new(PPOConfig::default(), 16, 4).await.unwrap();
+        *agent.total_timesteps.write().await = 1234;
+        agent.save_as(&bin_path, CheckpointFormat::Bincode).await.unwrap();
+        agent.save_as(&json_path, CheckpointFormat::Json).await.unwrap();
+
+        let restored = PPOAgentFull::new(PPOConfig::default(), 16, 4).await.unwrap();
+        restored.load_bin(&bin_path).await.unwrap();
+        assert_eq!(
+            restored.policy.read().await.get_parameters().await.unwrap(),
+            agent.policy.read().await.get_parameters().await.unwrap(),
+        );
+        assert_eq!(*restored.total_timesteps.read().await, 1234);
+
+        let bin_size = std::fs::metadata(&bin_path).unwrap().len();
+        let json_size = std::fs::metadata(&json_path).unwrap().len();
+        assert!(bin_size * 3 < json_size, "bin {} bytes vs json {} bytes", bin_size, json_size);
+    }
+
+    #[tokio::test]
+    async fn test_eval_act_takes_most_likely_action() {
+        let agent = PPOAgentFull::new_seeded(PPOConfig::default(), 4, 6, 3).await.unwrap();
+        assert_eq!(Agent::mode(&agent), AgentMode::Train);
+        agent.set_mode(AgentMode::Eval);
+        assert_eq!(Agent::mode(&agent), AgentMode::Eval);
+
+        let mut chosen = std::collections::HashSet::new();
+        for i in 0..20 {
+            let obs = VectorObservation {
+                data: (0..4).map(|j| ((i * 4 + j) as f64).sin() * 3.0).collect(),
+            };
+            let probs = agent.action_probabilities(&obs).await.unwrap();
+            let action = Policy::act(&agent, &obs).await.unwrap().0;
+            assert!(probs.iter().all(|&p| p <= probs[action]), "{:?} {}", probs, action);
+            chosen.insert(action);
+        }
+        assert!(chosen.len() > 1, "eval mode always picked {:?}", chosen);
+    }
+
+    #[tokio::test]
+    async fn test_rollout_resets_recurrent_state_per_episode() {
+        use crate::recurrent::{create_recurrent_policy_network, RecurrentConfig};
+        use sentient_rl_core::EnvironmentConfig;
+        use sentient_rl_env::CartPoleEnv;
+
+        let config = PPOConfig {
+            n_steps: 200,
+            bptt_len: 8,
+            ..PPOConfig::default()
+        };
+        let agent = PPOAgentFull::new_seeded(config, 4, 2, 5).await.unwrap();
+        let mut recurrent = create_recurrent_policy_network(&RecurrentConfig {
+            input_dim: 4,
+            hidden_dim: 8,
+            output_dim: 2,
+            use_value_head: true,
+        });
+        recurrent.set_hidden_state(&Array1::ones(8)).unwrap();
+        *agent.policy.write().await = recurrent;
+
+        // Every step stores the state it was taken with: zero at each episode
+        // start (including the first, despite the state left over from before
+        // the rollout), then whatever the previous step advanced it to
+        let mut env = CartPoleEnv::new(EnvironmentConfig::default()).unwrap();
+        agent.collect_rollout(&mut env).await.unwrap();
+        let buffer = agent.rollout_buffer.read().await;
+        assert_eq!(buffer.hidden_states.len(), 200);
+        assert!(buffer.dones.iter().any(|&d| d));
+        for i in 0..200 {
+            let episode_start = i == 0 || buffer.dones[i - 1];
+            let zero = buffer.hidden_states[i].iter().all(|&h| h == 0.0);
+            assert_eq!(zero, episode_start, "hidden state at step {}", i);
+        }
+        {
+            let mut policy = agent.policy.write().await;
+            let live = policy.hidden_state();
+            for i in (1..200).filter(|&i| !buffer.dones[i - 1]) {
+                policy.set_hidden_state(&buffer.hidden_states[i - 1]).unwrap();
+                let next = policy.forward(&buffer.observations[i - 1].view()).await.unwrap().hidden_state.unwrap();
+                assert!((next - &buffer.hidden_states[i]).iter().all(|d| d.abs() < 1e-6), "step {}", i);
+            }
+            policy.set_hidden_state(&live.unwrap()).unwrap();
+        }
+
+        // Minibatch sequences never cross an episode end and are replayed from
+        // their stored starting states, so the unchanged policy reproduces
+        // every collected log probability: each ratio is 1 and the clipped
+        // loss is minus the mean advantage
+        let sequences: Vec<(usize, BpttSequence)> = buffer.bptt_sequences(8).into_iter().map(|seq| (0, seq)).collect();
+        for (_, seq) in &sequences {
+            assert!(seq.len <= 8);
+            assert!(!buffer.dones[seq.start..seq.start + seq.len - 1].contains(&true));
+            assert_eq!(seq.initial_hidden.as_ref(), Some(&buffer.hidden_states[seq.start]));
+        }
+        let live = agent.policy.read().await.hidden_state();
+        let batch = RolloutBuffer::get_sequence_batch(&[&buffer], &sequences);
+        let (policy_loss, _, _) = agent.compute_losses(&batch, &batch.returns).await.unwrap();
+        let mean_advantage = batch.advantages.mean().unwrap();
+        assert!((policy_loss + mean_advantage).abs() < 1e-5, "{} vs {}", policy_loss, mean_advantage);
+        assert_eq!(agent.policy.read().await.hidden_state(), live);
+
+        // Carrying one state straight through the rollout instead does not
+        let mut unreplayed = RolloutBuffer::get_sequence_batch(&[&buffer], &sequences);
+        unreplayed.initial_hidden = vec![None; 200];
+        let (policy_loss, _, _) = agent.compute_losses(&unreplayed, &unreplayed.returns).await.unwrap();
+        assert!((policy_loss + mean_advantage).abs() > 1e-4, "{} vs {}", policy_loss, mean_advantage);
+        drop(buffer);
+
+        // The Agent trait hook clears the state too
+        agent.policy.write().await.set_hidden_state(&Array1::ones(8)).unwrap();
+        let mut agent = agent;
+        Agent::reset_episode(&mut agent).await.unwrap();
+        assert!(agent.policy.read().await.hidden_state().unwrap().iter().all(|&h| h == 0.0));
+    }
+
+    /// One-dimensional environment counting past its declared bound of 100
+    #[derive(Default)]
+    struct CountingEnv {
+        t: f64,
+    }
+
+    #[async_trait]
+    impl Environment for CountingEnv {
+        type Observation = VectorObservation;
+        type Action = DiscreteAction;
+        type State = VectorState;
+
+        fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = VectorObservation>> {
+            Box::new(BoxObservationSpace::new(vec![0.0], vec![100.0], vec![1]).unwrap())
+        }
+
+        fn action_space(&self) -> Box<dyn ActionSpace<Action = DiscreteAction>> {
+            Box::new(DiscreteSpace::new(2))
+        }
+
+        async fn reset(&mut self) -> sentient_rl_core::Result<(VectorObservation, StepInfo)> {
+            self.t = 0.0;
+            Ok((VectorObservation { data: vec![self.t] }, StepInfo::default()))
+        }
+
+        async fn step(&mut self, _action: DiscreteAction) -> sentient_rl_core::Result<Step<VectorObservation, Self::State>> {
+            self.t += 1.0;
+            Ok(Step {
+                observation: VectorObservation { data: vec![self.t] },
+                reward: Reward(1.0),
+                done: false,
+                truncated: false,
+                info: StepInfo::default(),
+                state: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollout_clips_observations_to_the_env_space() {
+        let config = PPOConfig {
+            n_steps: 120,
+            ..PPOConfig::default()
+        };
+        let agent = PPOAgentFull::new(config, 1, 2).await.unwrap();
+        agent.collect_rollout(&mut CountingEnv::default()).await.unwrap();
+
+        let buffer = agent.rollout_buffer.read().await;
+        let stored: Vec<f32> = buffer.observations.iter().map(|o| o[0]).collect();
+        assert_eq!(stored[50], 50.0);
+        assert_eq!(stored[119], 100.0);
+        assert!(stored.iter().all(|&x| (0.0..=100.0).contains(&x)));
+        assert!(agent.observation_sanitizer().repaired() > 0);
+    }
+
+    /// Three actions; even steps allow only action 1, odd steps only 0 and 2
+    #[derive(Default)]
+    struct AlternatingMaskEnv {
+        t: usize,
+    }
+
+    impl AlternatingMaskEnv {
+        fn info(&self) -> StepInfo {
+            let even = self.t % 2 == 0;
+            StepInfo::default().with_action_mask(&[!even, even, !even])
+        }
+    }
+
+    #[async_trait]
+    impl Environment for AlternatingMaskEnv {
+        type Observation = VectorObservation;
+        type Action = DiscreteAction;
+        type State = VectorState;
+
+        fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = VectorObservation>> {
+            Box::new(BoxObservationSpace::new(vec![0.0], vec![1.0], vec![1]).unwrap())
+        }
+
+        fn action_space(&self) -> Box<dyn ActionSpace<Action = DiscreteAction>> {
+            Box::new(DiscreteSpace::new(3))
+        }
+
+        async fn reset(&mut self) -> sentient_rl_core::Result<(VectorObservation, StepInfo)> {
+            self.t = 0;
+            Ok((VectorObservation { data: vec![0.0] }, self.info()))
+        }
+
+        async fn step(&mut self, action: DiscreteAction) -> sentient_rl_core::Result<Step<VectorObservation, Self::State>> {
+            let even = self.t % 2 == 0;
+            assert_eq!(action.0 == 1, even, "masked action {} taken at step {}", action.0, self.t);
+            self.t += 1;
+            Ok(Step {
+                observation: VectorObservation { data: vec![(self.t % 2) as f64] },
+                reward: Reward(1.0),
+                done: self.t == 5,
+                truncated: false,
+                info: self.info(),
+                state: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollout_and_losses_respect_action_mask() {
+        let config = PPOConfig {
+            n_steps: 32,
+            ..PPOConfig::default()
+        };
+        let agent = PPOAgentFull::new(config, 1, 3).await.unwrap();
+        let mut env = AlternatingMaskEnv::default();
+        agent.collect_rollout(&mut env).await.unwrap();
+
+        let buffer = agent.rollout_buffer.read().await;
+        assert_eq!(buffer.action_masks.len(), 32);
+        let batch = buffer.get_batch(&(0..32).collect::<Vec<_>>());
+        assert_eq!(batch.action_masks, buffer.action_masks);
+
+        // The stored log probabilities are those of the masked distribution,
+        // which is what the loss evaluates the same actions under
+        let policy = agent.policy.read().await;
+        for i in 0..32 {
+            let output = policy.forward_masked(&batch.observations.row(i), buffer.action_mask(i)).await.unwrap();
+            let log_prob = discrete_log_prob(&output.action_output, &batch.actions.row(i));
+            assert!((log_prob - buffer.log_probs[i]).abs() < 1e-5, "step {}", i);
+        }
+        drop(policy);
+
+        let targets = batch.returns.clone();
+        let (policy_loss, value_loss, entropy) = agent.compute_losses(&batch, &targets).await.unwrap();
+        assert!(policy_loss.is_finite() && value_loss.is_finite());
+        // Even steps have a single valid action, odd steps two
+        assert!(entropy < 2.0_f32.ln() && entropy > 0.0, "{}", entropy);
+    }
+
+    /// A rollout of one-step episodes with the given rewards and zero values
+    fn one_step_episodes(rewards: &[f32]) -> RolloutBuffer {
+        let mut buffer = RolloutBuffer::new();
+        for &reward in rewards {
+            buffer.add(Array1::zeros(2), Array1::zeros(1), reward, 0.0, 0.0, true);
+        }
+        buffer
+    }
+
+    fn assert_finite(values: &[f32]) {
+        assert!(values.iter().all(|v| v.is_finite()), "{:?}", values);
+    }
+
+    #[test]
+    fn test_single_sample_rollout_is_centered_not_scaled() {
+        let mut buffer = one_step_episodes(&[2.5]);
+        buffer.compute_returns_and_advantages(0.0, 0.99, 0.95).unwrap();
+        buffer.normalize_advantages().unwrap();
+        assert_eq!(buffer.advantages, vec![0.0]);
+        assert_eq!(buffer.returns, vec![2.5]);
+    }
+
+    #[test]
+    fn test_equal_rewards_skip_scaling() {
+        let mut buffer = one_step_episodes(&[1.0; 8]);
+        buffer.compute_returns_and_advantages(0.0, 0.99, 0.95).unwrap();
+        buffer.normalize_advantages().unwrap();
+        assert_finite(&buffer.advantages);
+        assert_eq!(buffer.advantages, vec![0.0; 8]);
+
+        // Spread-out advantages still get unit std
+        let mut buffer = one_step_episodes(&[1.0, 3.0]);
+        buffer.compute_returns_and_advantages(0.0, 0.99, 0.95).unwrap();
+        buffer.normalize_advantages().unwrap();
+        assert_eq!(buffer.advantages, vec![-1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_truncated_episode_bootstraps_from_next_value() {
+        let (gamma, lambda) = (0.9, 0.8);
+        let rollout = |truncated: bool| {
+            let mut buffer = RolloutBuffer::new();
+            buffer.add(ndarray::arr1(&[0.0]), ndarray::arr1(&[0.0]), 1.0, 0.5, 0.0, false);
+            buffer.add(ndarray::arr1(&[1.0]), ndarray::arr1(&[0.0]), 2.0, 0.4, 0.0, true);
+            if truncated {
+                // V(s') of the observation the time limit cut off at
+                buffer.mark_truncated(3.0);
+            }
+            buffer.add(ndarray::arr1(&[2.0]), ndarray::arr1(&[0.0]), 0.5, 0.2, 0.0, false);
+            buffer.compute_returns_and_advantages(1.0, gamma, lambda).unwrap();
+            buffer
+        };
+
+        // delta_2 = 0.5 + 0.9 * 1.0 - 0.2, the next episode bootstraps as usual
+        let a2 = 0.5 + gamma * 1.0 - 0.2;
+        // delta_1 = r + gamma * V(s') - V(s), cut off from what follows
+        let a1 = 2.0 + gamma * 3.0 - 0.4;
+        let a0 = (1.0 + gamma * 0.4 - 0.5) + gamma * lambda * a1;
+        let buffer = rollout(true);
+        for (actual, expected) in buffer.advantages.iter().zip([a0, a1, a2]) {
+            assert!((actual - expected).abs() < 1e-5, "{:?} != {:?}", buffer.advantages, [a0, a1, a2]);
+        }
+        assert!((buffer.returns[1] - (2.0 + gamma * 3.0)).abs() < 1e-5);
+
+        // A terminal step has no future to bootstrap from
+        let terminal = rollout(false);
+        assert!((terminal.advantages[1] - (2.0 - 0.4)).abs() < 1e-5);
+        assert!((terminal.advantages[2] - a2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_empty_or_non_finite_rollout_is_an_error() {
+        let mut buffer = RolloutBuffer::new();
+        buffer.compute_returns_and_advantages(0.0, 0.99, 0.95).unwrap();
+        assert!(buffer.normalize_advantages().is_err());
+
+        let mut buffer = one_step_episodes(&[1.0, f32::NAN, 2.0]);
+        let err = buffer.compute_returns_and_advantages(0.0, 0.99, 0.95).unwrap_err();
+        assert!(err.to_string().contains("step 1"), "{}", err);
+
+        let mut buffer = one_step_episodes(&[1.0]);
+        assert!(buffer.compute_returns_and_advantages(f32::INFINITY, 0.99, 0.95).is_err());
+
+        let mut buffer = one_step_episodes(&[1.0, 2.0]);
+        buffer.advantages = vec![1.0, f32::NAN];
+        assert!(buffer.normalize_advantages().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_standardized_returns_per_minibatch() {
+        let config = PPOConfig { standardize_returns: true, ..PPOConfig::default() };
+        let agent = PPOAgentFull::new(config, 2, 1).await.unwrap();
+        let rewards: Vec<f32> = (0..64).map(|i| 10.0 + 3.0 * (i % 7) as f32 + i as f32).collect();
+        let mut buffer = one_step_episodes(&rewards);
+        buffer.compute_returns_and_advantages(0.0, 0.99, 0.95).unwrap();
+        
+        // Every minibatch gets its own zero-mean, unit-std targets
+        let indices: Vec<usize> = (0..64).map(|i| (i * 37) % 64).collect();
+        for chunk in indices.chunks(16) {
+            let batch = buffer.get_batch(chunk);
+            let (targets, scale) = agent.value_targets(&batch);
+            let scale = scale.unwrap();
+            assert!(targets.mean().unwrap().abs() < 1e-5, "{:?}", targets);
+            assert!((targets.std(0.0) - 1.0).abs() < 1e-4, "{:?}", targets);
+            for (target, ret) in targets.iter().zip(&batch.returns) {
+                assert!((scale.unstandardize(*target) - ret).abs() < 1e-4);
+            }
+        }
+        
+        // Training keeps the scale, so predictions are reported in return units
+        let batch = buffer.get_batch(&indices[..16]);
+        agent.train_minibatch(&batch).await.unwrap();
+        let scale = agent.return_scale().unwrap();
+        assert_eq!(scale, ReturnScale::of(&batch.returns));
+        assert!((agent.value_estimate(1.0) - (scale.mean + scale.std)).abs() < 1e-4);
+        
+        // Off by default: the loss sees the raw returns
+        let plain = PPOAgentFull::new(PPOConfig::default(), 2, 1).await.unwrap();
+        let (targets, scale) = plain.value_targets(&batch);
+        assert_eq!(targets, batch.returns);
+        assert!(scale.is_none());
+        plain.train_minibatch(&batch).await.unwrap();
+        assert_eq!(plain.value_estimate(1.5), 1.5);
+    }
+
+    /// A finished rollout of one-step episodes alternating between two
+    /// actions, each recorded with `log_prob`
+    fn two_action_rollout(log_prob: f32) -> RolloutBuffer {
+        let mut buffer = RolloutBuffer::new();
+        for i in 0..8 {
+            let mut action = Array1::zeros(2);
+            action[i % 2] = 1.0;
+            buffer.add(ndarray::arr1(&[i as f32 * 0.1, 1.0]), action, 1.0, 0.0, log_prob, true);
+        }
+        buffer.compute_returns_and_advantages(0.0, 0.99, 0.95).unwrap();
+        buffer
+    }
+
+    #[tokio::test]
+    async fn test_reused_rollouts_are_importance_weighted() {
+        let config = PPOConfig { reuse_rollouts: 2, ..PPOConfig::default() };
+        let agent = PPOAgentFull::new(config, 2, 2).await.unwrap();
+        
+        // The earlier rollout's actions were certain under the behavior
+        // policy, so each weighs as much as the current policy's probability
+        agent.replay.write().await.retain(&two_action_rollout(0.0));
+        *agent.rollout_buffer.write().await = two_action_rollout(0.5f32.ln());
+        let corrections = agent.reuse_corrections(&two_action_rollout(0.0)).await.unwrap();
+        for &(weight, log_prob) in &corrections {
+            assert!(weight > 0.0 && weight < 1.0);
+            assert!((weight - log_prob.exp()).abs() < 1e-6);
+        }
+        
+        let stats = agent.train().await.unwrap();
+        assert_eq!(stats.transitions, 16);
+        assert!(stats.mean_importance_weight > 0.5 && stats.mean_importance_weight < 1.0, "{:?}", stats);
+        
+        // Actions the behavior policy rarely took are capped, not blown up
+        let unlikely = agent.reuse_corrections(&two_action_rollout(-10.0)).await.unwrap();
+        assert!(unlikely.iter().all(|&(weight, _)| weight == 1.0));
+        
+        // Only the last `reuse_rollouts - 1` rollouts are kept
+        for _ in 0..3 {
+            agent.replay.write().await.retain(&two_action_rollout(0.0));
+        }
+        assert_eq!(agent.replay.read().await.rollouts().count(), 1);
+        
+        // Without reuse only the newest rollout is trained on
+        let plain = PPOAgentFull::new(PPOConfig::default(), 2, 2).await.unwrap();
+        plain.replay.write().await.retain(&two_action_rollout(0.0));
+        *plain.rollout_buffer.write().await = two_action_rollout(0.5f32.ln());
+        let stats = plain.train().await.unwrap();
+        assert_eq!(stats.transitions, 8);
+        assert_eq!(stats.mean_importance_weight, 1.0);
+    }
+}
@@ -1,27 +1,30 @@
 //! Random agent for baseline comparisons
 
 use async_trait::async_trait;
+use std::marker::PhantomData;
 use sentient_rl_core::{
-    Agent, AgentConfig, Policy, ActionSpace, Observation, Action, Step, State,
+    Agent, AgentConfig, Policy, ActionSpace, Observation, Step, State,
 };
 
-/// Random agent that selects actions uniformly at random
-pub struct RandomAgent<A> {
+/// Random agent that selects actions uniformly at random, ignoring
+/// observations of type `O`
+pub struct RandomAgent<A, O> {
     /// Action space
     action_space: A,
     /// Configuration
     config: AgentConfig,
     /// Random policy
-    policy: RandomPolicy<A>,
+    policy: RandomPolicy<A, O>,
 }
 
 /// Random policy wrapper
-struct RandomPolicy<A> {
+struct RandomPolicy<A, O> {
     action_space: A,
+    _observation: PhantomData<fn(&O)>,
 }
 
 #[async_trait]
-impl<O, A> Policy for RandomPolicy<A>
+impl<O, A> Policy for RandomPolicy<A, O>
 where
     O: Observation,
     A: ActionSpace + Clone + Send + Sync,
@@ -35,7 +38,7 @@ where
     }
 }
 
-impl<A> RandomAgent<A>
+impl<A, O> RandomAgent<A, O>
 where
     A: ActionSpace + Clone,
 {
@@ -43,6 +46,7 @@ where
     pub fn new(action_space: A) -> Self {
         let policy = RandomPolicy {
             action_space: action_space.clone(),
+            _observation: PhantomData,
         };
         
         Self {
@@ -54,7 +58,7 @@ where
 }
 
 #[async_trait]
-impl<O, A> Agent for RandomAgent<A>
+impl<O, A> Agent for RandomAgent<A, O>
 where
     O: Observation,
     A: ActionSpace + Clone + Send + Sync + 'static,
@@ -0,0 +1,344 @@
+//! Recurrent (GRU) policy network
+//!
+//! A single GRU cell replaces the MLP hidden stack, so the policy can carry
+//! memory across the steps of a partially observable episode. The cell reads
+//! its previous output from [`PolicyNetwork::hidden_state`]; callers commit the
+//! new state returned in [`PolicyOutput::hidden_state`] after acting and clear
+//! it with [`PolicyNetwork::reset_hidden_state`] when an episode ends.
+//!
+//! Rollouts store the state each step was taken with. PPO replays them in
+//! truncated-BPTT runs of at most `bptt_len` steps, each unrolled from the
+//! state stored at its start. The cell offers no
+//! [`PolicyNetwork::parameter_gradients`], so A2C trains only the action and
+//! value heads on top of it.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use ndarray::{s, Array1, Array2, ArrayView1};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::policy::{sample_categorical, PolicyNetwork, PolicyOutput};
+
+/// Recurrent policy configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurrentConfig {
+    /// Input dimension
+    pub input_dim: usize,
+    /// Size of the GRU hidden state
+    pub hidden_dim: usize,
+    /// Output dimension (number of discrete actions)
+    pub output_dim: usize,
+    /// Whether to include value head (for actor-critic)
+    pub use_value_head: bool,
+}
+
+impl Default for RecurrentConfig {
+    fn default() -> Self {
+        Self {
+            input_dim: 4,
+            hidden_dim: 64,
+            output_dim: 2,
+            use_value_head: true,
+        }
+    }
+}
+
+impl RecurrentConfig {
+    /// Number of GRU cell parameters, which precede the heads in
+    /// [`PolicyNetwork::get_parameters`]
+    #[must_use]
+    pub fn cell_parameter_count(&self) -> usize {
+        3 * self.hidden_dim * (self.input_dim + self.hidden_dim + 1)
+    }
+}
+
+/// GRU policy for discrete action spaces
+///
+/// Gates are stored fused in update/reset/candidate order:
+/// `z = σ(x·Wz + h·Uz + bz)`, `r = σ(x·Wr + h·Ur + br)`,
+/// `n = tanh(x·Wn + (r ⊙ h)·Un + bn)`, `h' = (1 - z) ⊙ n + z ⊙ h`.
+pub struct GRUPolicy {
+    config: RecurrentConfig,
+    /// Input-to-gate weights, `input_dim x 3·hidden_dim`
+    input_weights: Array2<f32>,
+    /// Hidden-to-gate weights, `hidden_dim x 3·hidden_dim`
+    hidden_weights: Array2<f32>,
+    /// Gate biases
+    gate_bias: Array1<f32>,
+    action_weights: Array2<f32>,
+    action_bias: Array1<f32>,
+    value_weights: Option<Array2<f32>>,
+    value_bias: Option<Array1<f32>>,
+    /// State the next forward pass starts from
+    hidden: Array1<f32>,
+}
+
+impl GRUPolicy {
+    /// Create a new GRU policy with a zero hidden state
+    #[must_use]
+    pub fn new(config: RecurrentConfig) -> Self {
+        let (input, hidden, output) = (config.input_dim, config.hidden_dim, config.output_dim);
+        let (value_weights, value_bias) = if config.use_value_head {
+            (Some(xavier_init(hidden, 1)), Some(Array1::zeros(1)))
+        } else {
+            (None, None)
+        };
+
+        Self {
+            input_weights: xavier_init(input, 3 * hidden),
+            hidden_weights: xavier_init(hidden, 3 * hidden),
+            gate_bias: Array1::zeros(3 * hidden),
+            action_weights: xavier_init(hidden, output),
+            action_bias: Array1::zeros(output),
+            value_weights,
+            value_bias,
+            hidden: Array1::zeros(hidden),
+            config,
+        }
+    }
+
+    /// Configuration the policy was built with
+    #[must_use]
+    pub fn config(&self) -> &RecurrentConfig {
+        &self.config
+    }
+
+    /// One GRU step from `hidden`
+    fn cell(&self, input: &ArrayView1<f32>, hidden: &Array1<f32>) -> Array1<f32> {
+        let h = self.config.hidden_dim;
+        let x_gates = input.dot(&self.input_weights) + &self.gate_bias;
+
+        let z = (&x_gates.slice(s![..h]) + &hidden.dot(&self.hidden_weights.slice(s![.., ..h])))
+            .mapv(sigmoid);
+        let r = (&x_gates.slice(s![h..2 * h])
+            + &hidden.dot(&self.hidden_weights.slice(s![.., h..2 * h])))
+            .mapv(sigmoid);
+        let n = (&x_gates.slice(s![2 * h..])
+            + &(&r * hidden).dot(&self.hidden_weights.slice(s![.., 2 * h..])))
+            .mapv(f32::tanh);
+
+        (1.0 - &z) * &n + &z * hidden
+    }
+
+    fn forward_impl(&self, input: &ArrayView1<f32>) -> PolicyOutput {
+        let next_hidden = self.cell(input, &self.hidden);
+        let action_output = next_hidden.dot(&self.action_weights) + &self.action_bias;
+        let value = match (&self.value_weights, &self.value_bias) {
+            (Some(w), Some(b)) => Some((next_hidden.dot(w) + b)[0]),
+            _ => None,
+        };
+
+        PolicyOutput {
+            action_output,
+            value,
+            log_std: None,
+            features: next_hidden.clone(),
+            hidden_state: Some(next_hidden),
+        }
+    }
+
+    /// Parameter arrays in flattening order
+    fn parameter_arrays_mut(&mut self) -> Vec<&mut [f32]> {
+        let mut arrays = vec![
+            self.input_weights.as_slice_mut().unwrap(),
+            self.hidden_weights.as_slice_mut().unwrap(),
+            self.gate_bias.as_slice_mut().unwrap(),
+            self.action_weights.as_slice_mut().unwrap(),
+            self.action_bias.as_slice_mut().unwrap(),
+        ];
+        if let (Some(w), Some(b)) = (&mut self.value_weights, &mut self.value_bias) {
+            arrays.push(w.as_slice_mut().unwrap());
+            arrays.push(b.as_slice_mut().unwrap());
+        }
+        arrays
+    }
+}
+
+#[async_trait]
+impl PolicyNetwork for GRUPolicy {
+    async fn forward(&self, observation: &ArrayView1<f32>) -> Result<PolicyOutput> {
+        if observation.len() != self.config.input_dim {
+            anyhow::bail!(
+                "GRU policy expects {} inputs, got {}",
+                self.config.input_dim,
+                observation.len()
+            );
+        }
+        Ok(self.forward_impl(observation))
+    }
+
+    async fn sample_action(&self, observation: &ArrayView1<f32>) -> Result<(Array1<f32>, f32)> {
+        let output = self.forward(observation).await?;
+        let max_logit = output
+            .action_output
+            .iter()
+            .fold(f32::NEG_INFINITY, |a, &b| a.max(b));
+        let probs = output.action_output.mapv(|x| (x - max_logit).exp());
+        let probs = &probs / probs.sum();
+
+        let action_idx = sample_categorical(&probs, rand::thread_rng().gen::<f32>());
+
+        let mut action = Array1::zeros(self.config.output_dim);
+        action[action_idx] = 1.0;
+        Ok((action, probs[action_idx].max(1e-8).ln()))
+    }
+
+    async fn update(&mut self, gradients: &[f32]) -> Result<()> {
+        let learning_rate = 3e-4;
+        let mut params = self.get_parameters().await?;
+        for (param, grad) in params.iter_mut().zip(gradients) {
+            *param -= learning_rate * grad;
+        }
+        self.set_parameters(&params).await
+    }
+
+    async fn get_parameters(&self) -> Result<Vec<f32>> {
+        let mut params = Vec::new();
+        params.extend_from_slice(self.input_weights.as_slice().unwrap());
+        params.extend_from_slice(self.hidden_weights.as_slice().unwrap());
+        params.extend_from_slice(self.gate_bias.as_slice().unwrap());
+        params.extend_from_slice(self.action_weights.as_slice().unwrap());
+        params.extend_from_slice(self.action_bias.as_slice().unwrap());
+        if let (Some(w), Some(b)) = (&self.value_weights, &self.value_bias) {
+            params.extend_from_slice(w.as_slice().unwrap());
+            params.extend_from_slice(b.as_slice().unwrap());
+        }
+        Ok(params)
+    }
+
+    async fn set_parameters(&mut self, params: &[f32]) -> Result<()> {
+        let mut arrays = self.parameter_arrays_mut();
+        let expected: usize = arrays.iter().map(|a| a.len()).sum();
+        if params.len() != expected {
+            anyhow::bail!(
+                "GRU policy has {} parameters, got {}",
+                expected,
+                params.len()
+            );
+        }
+
+        let mut offset = 0;
+        for array in &mut arrays {
+            array.copy_from_slice(&params[offset..offset + array.len()]);
+            offset += array.len();
+        }
+        Ok(())
+    }
+
+    fn clone_network(&self) -> Box<dyn PolicyNetwork> {
+        Box::new(Self {
+            config: self.config.clone(),
+            input_weights: self.input_weights.clone(),
+            hidden_weights: self.hidden_weights.clone(),
+            gate_bias: self.gate_bias.clone(),
+            action_weights: self.action_weights.clone(),
+            action_bias: self.action_bias.clone(),
+            value_weights: self.value_weights.clone(),
+            value_bias: self.value_bias.clone(),
+            hidden: self.hidden.clone(),
+        })
+    }
+
+    fn hidden_state(&self) -> Option<Array1<f32>> {
+        Some(self.hidden.clone())
+    }
+
+    fn set_hidden_state(&mut self, hidden: &Array1<f32>) -> Result<()> {
+        if hidden.len() != self.config.hidden_dim {
+            anyhow::bail!(
+                "GRU hidden state has {} units, got {}",
+                self.config.hidden_dim,
+                hidden.len()
+            );
+        }
+        self.hidden.assign(hidden);
+        Ok(())
+    }
+
+    fn reset_hidden_state(&mut self) {
+        self.hidden.fill(0.0);
+    }
+}
+
+/// Create a recurrent policy network
+#[must_use]
+pub fn create_recurrent_policy_network(config: &RecurrentConfig) -> Box<dyn PolicyNetwork> {
+    Box::new(GRUPolicy::new(config.clone()))
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn xavier_init(in_dim: usize, out_dim: usize) -> Array2<f32> {
+    let limit = (6.0 / (in_dim + out_dim) as f32).sqrt();
+    let mut rng = rand::thread_rng();
+    Array2::from_shape_fn((in_dim, out_dim), |_| rng.gen_range(-limit..limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::arr1;
+
+    fn config() -> RecurrentConfig {
+        RecurrentConfig {
+            input_dim: 3,
+            hidden_dim: 8,
+            output_dim: 2,
+            ..RecurrentConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_forward_shapes() {
+        let policy = GRUPolicy::new(config());
+        let output = policy
+            .forward(&arr1(&[0.5, -0.2, 0.1]).view())
+            .await
+            .unwrap();
+
+        assert_eq!(output.action_output.len(), 2);
+        assert!(output.value.is_some());
+        assert_eq!(output.features.len(), 8);
+        assert_eq!(output.hidden_state.as_ref().map(Array1::len), Some(8));
+
+        let params = policy.get_parameters().await.unwrap();
+        assert_eq!(
+            params.len(),
+            config().cell_parameter_count() + 8 * 2 + 2 + 8 + 1
+        );
+        assert!(policy.forward(&arr1(&[0.5, -0.2]).view()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hidden_state_evolves_and_resets() {
+        let mut policy = GRUPolicy::new(config());
+        let obs = arr1(&[0.5, -0.2, 0.1]);
+        assert!(policy.hidden_state().unwrap().iter().all(|&h| h == 0.0));
+
+        // Same observation, different memory: the state keeps moving
+        let first = policy.forward(&obs.view()).await.unwrap();
+        let h1 = first.hidden_state.unwrap();
+        assert!(h1.iter().any(|&h| h != 0.0));
+        policy.set_hidden_state(&h1).unwrap();
+
+        let second = policy.forward(&obs.view()).await.unwrap();
+        let h2 = second.hidden_state.unwrap();
+        assert_ne!(h1, h2);
+        assert_ne!(first.action_output, second.action_output);
+
+        // Forward alone doesn't advance the stored state
+        assert_eq!(policy.hidden_state().unwrap(), h1);
+
+        // After a reset the episode starts over from the zero state
+        policy.reset_hidden_state();
+        assert!(policy.hidden_state().unwrap().iter().all(|&h| h == 0.0));
+        let restarted = policy.forward(&obs.view()).await.unwrap();
+        assert_eq!(restarted.hidden_state.unwrap(), h1);
+
+        assert!(policy.set_hidden_state(&Array1::zeros(3)).is_err());
+    }
+}
@@ -0,0 +1,722 @@
+//! Soft Actor-Critic (SAC) agent implementation
+//!
+//! Off-policy actor-critic for continuous actions: a tanh-squashed Gaussian
+//! actor trained through the reparameterization trick, twin Q critics whose
+//! smaller estimate is used against overestimation, Polyak-averaged target
+//! critics, and an entropy temperature tuned by [`TemperatureTuner`].
+
+use anyhow::Result;
+use ndarray::{arr1, s, Array1, ArrayView1};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+use sentient_rl_core::observation::VectorObservation;
+use sentient_rl_core::{
+    AgentMode, ContinuousAction, Environment, SpaceSignature, SpaceSpec, Transition, VectorState,
+};
+
+use crate::buffer::ReplayBuffer;
+use crate::utils::Adam;
+use crate::policy::{Activation, MLPConfig, MLPPolicy, OutputGradient, PolicyNetwork};
+
+/// Bounds the actor's log standard deviation is kept within
+const LOG_STD_RANGE: (f32, f32) = (-5.0, 2.0);
+
+/// SAC-specific configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SACConfig {
+    /// Base agent configuration
+    #[serde(flatten)]
+    pub base: sentient_rl_core::AgentConfig,
+    /// Soft update coefficient for the target critics
+    pub tau: f64,
+    /// Initial (or fixed) entropy temperature
+    pub alpha: f32,
+    /// Tune alpha towards `target_entropy` with its own optimizer
+    pub auto_entropy_tuning: bool,
+    /// Entropy target; defaults to `-action_dim`
+    pub target_entropy: Option<f32>,
+    /// Learning rate of the temperature optimizer
+    pub alpha_learning_rate: f32,
+    /// Hidden layer sizes of the actor and of each critic
+    #[serde(default = "default_hidden_dims")]
+    pub hidden_dims: Vec<usize>,
+}
+
+fn default_hidden_dims() -> Vec<usize> {
+    vec![64, 64]
+}
+
+impl Default for SACConfig {
+    fn default() -> Self {
+        Self {
+            base: sentient_rl_core::AgentConfig::default(),
+            tau: 0.005,
+            alpha: 0.2,
+            auto_entropy_tuning: true,
+            target_entropy: None,
+            alpha_learning_rate: 3e-4,
+            hidden_dims: default_hidden_dims(),
+        }
+    }
+}
+
+impl SACConfig {
+    /// Entropy target for an action space of `action_dim` dimensions
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn target_entropy_for(&self, action_dim: usize) -> f32 {
+        self.target_entropy.unwrap_or(-(action_dim as f32))
+    }
+}
+
+/// Temperature values reported after each update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemperatureStats {
+    /// Number of temperature updates so far
+    pub update: usize,
+    /// Temperature after the update
+    pub alpha: f32,
+    /// Temperature loss for the batch
+    pub alpha_loss: f32,
+    /// Policy entropy estimated from the batch
+    pub entropy: f32,
+    /// Entropy target being tuned towards
+    pub target_entropy: f32,
+}
+
+/// Callback invoked with the temperature stats after every update
+pub type TrainingCallback = Box<dyn FnMut(&TemperatureStats) + Send>;
+
+/// Entropy temperature, optionally tuned with Adam on `log(alpha)`
+///
+/// The temperature loss is `-log_alpha * (log_prob + target_entropy)`, so
+/// alpha rises while the policy's entropy is below target and falls once it
+/// is above.
+pub struct TemperatureTuner {
+    log_alpha: f32,
+    target_entropy: f32,
+    auto_tune: bool,
+    optimizer: Adam,
+    updates: usize,
+    callback: Option<TrainingCallback>,
+}
+
+impl TemperatureTuner {
+    /// Create a tuner for an action space of `action_dim` dimensions
+    #[must_use]
+    pub fn new(config: &SACConfig, action_dim: usize) -> Self {
+        Self {
+            log_alpha: config.alpha.max(f32::MIN_POSITIVE).ln(),
+            target_entropy: config.target_entropy_for(action_dim),
+            auto_tune: config.auto_entropy_tuning,
+            optimizer: Adam::new(config.alpha_learning_rate, 1),
+            updates: 0,
+            callback: None,
+        }
+    }
+
+    /// Report every update to `callback`
+    #[must_use]
+    pub fn with_callback(mut self, callback: impl FnMut(&TemperatureStats) + Send + 'static) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Current temperature
+    #[must_use]
+    pub fn alpha(&self) -> f32 {
+        self.log_alpha.exp()
+    }
+
+    /// Update the temperature from the log-probabilities of freshly sampled actions
+    #[allow(clippy::cast_precision_loss)]
+    pub fn update(&mut self, log_probs: &[f32]) -> TemperatureStats {
+        let mean_log_prob = if log_probs.is_empty() {
+            0.0
+        } else {
+            log_probs.iter().sum::<f32>() / log_probs.len() as f32
+        };
+        let gap = mean_log_prob + self.target_entropy;
+        let alpha_loss = -self.log_alpha * gap;
+
+        self.updates += 1;
+        if self.auto_tune {
+            let mut log_alpha = [self.log_alpha];
+            self.optimizer.step(&mut log_alpha, &[-gap]);
+            self.log_alpha = log_alpha[0];
+        }
+
+        let stats = TemperatureStats {
+            update: self.updates,
+            alpha: self.alpha(),
+            alpha_loss,
+            entropy: -mean_log_prob,
+            target_entropy: self.target_entropy,
+        };
+        tracing::debug!(alpha = stats.alpha, entropy = stats.entropy, "SAC temperature update");
+        if let Some(callback) = self.callback.as_mut() {
+            callback(&stats);
+        }
+        stats
+    }
+}
+
+/// A Q-network, the Polyak-averaged copy its targets come from, and its
+/// optimizer
+struct Critic {
+    online: MLPPolicy,
+    target: MLPPolicy,
+    optimizer: Adam,
+}
+
+impl Critic {
+    async fn new(config: &MLPConfig, learning_rate: f32, rng: &mut StdRng) -> Result<Self> {
+        let online = MLPPolicy::with_rng(config.clone(), rng);
+        let params = online.get_parameters().await?;
+        let mut target = MLPPolicy::with_rng(config.clone(), rng);
+        target.set_parameters(&params).await?;
+        Ok(Self {
+            online,
+            target,
+            optimizer: Adam::new(learning_rate, params.len()),
+        })
+    }
+
+    async fn apply(&mut self, gradients: &[f32]) -> Result<()> {
+        let mut params = self.online.get_parameters().await?;
+        self.optimizer.step(&mut params, gradients);
+        self.online.set_parameters(&params).await
+    }
+
+    /// Move the target `tau` of the way towards the online network
+    async fn soft_update(&mut self, tau: f32) -> Result<()> {
+        let online = self.online.get_parameters().await?;
+        let mut target = self.target.get_parameters().await?;
+        for (t, o) in target.iter_mut().zip(&online) {
+            *t += tau * (o - *t);
+        }
+        self.target.set_parameters(&target).await
+    }
+}
+
+/// Q-value of `input`, an observation followed by an action
+async fn q_value(network: &MLPPolicy, input: &Array1<f32>) -> Result<f32> {
+    Ok(network.forward(&input.view()).await?.action_output[0])
+}
+
+fn critic_input(observation: &ArrayView1<f32>, action: &ArrayView1<f32>) -> Array1<f32> {
+    observation.iter().chain(action.iter()).copied().collect()
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn to_array(observation: &VectorObservation) -> Array1<f32> {
+    observation.data.iter().map(|&x| x as f32).collect()
+}
+
+/// Reparameterized actor sample `tanh(mean + std * noise)`
+struct SquashedSample {
+    noise: Array1<f32>,
+    std: Array1<f32>,
+    action: Array1<f32>,
+    log_prob: f32,
+}
+
+/// Losses and temperature after one SAC update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SACStats {
+    /// Mean squared TD error, summed over both critics
+    pub critic_loss: f32,
+    /// Mean of `alpha * log_prob - min Q` over the batch
+    pub actor_loss: f32,
+    /// Temperature update made in the same step
+    pub temperature: TemperatureStats,
+}
+
+/// SAC agent for continuous action spaces
+///
+/// Actions are squashed into `[-1, 1]` per dimension; environments with
+/// other bounds should rescale them.
+pub struct SACAgent {
+    config: SACConfig,
+    observation_dim: usize,
+    action_dim: usize,
+    actor: MLPPolicy,
+    actor_optimizer: Adam,
+    critics: [Critic; 2],
+    temperature: TemperatureTuner,
+    replay: ReplayBuffer<VectorObservation, ContinuousAction, VectorState>,
+    /// Source of exploration noise
+    rng: StdRng,
+    mode: AgentMode,
+    total_timesteps: usize,
+}
+
+impl SACAgent {
+    /// Create a new SAC agent
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compute config is unsupported.
+    pub async fn new(config: SACConfig, observation_dim: usize, action_dim: usize) -> Result<Self> {
+        Self::build(config, observation_dim, action_dim, StdRng::from_entropy()).await
+    }
+
+    /// Create a SAC agent whose initial weights and exploration noise are
+    /// driven by `seed`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the compute config is unsupported.
+    pub async fn new_seeded(
+        config: SACConfig,
+        observation_dim: usize,
+        action_dim: usize,
+        seed: u64,
+    ) -> Result<Self> {
+        Self::build(config, observation_dim, action_dim, StdRng::seed_from_u64(seed)).await
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    async fn build(
+        config: SACConfig,
+        observation_dim: usize,
+        action_dim: usize,
+        mut rng: StdRng,
+    ) -> Result<Self> {
+        config.base.compute.ensure_supported()?;
+        let learning_rate = config.base.learning_rate as f32;
+
+        let actor = MLPPolicy::with_rng(
+            MLPConfig {
+                input_dim: observation_dim,
+                hidden_dims: config.hidden_dims.clone(),
+                output_dim: action_dim,
+                activation: Activation::ReLU,
+                use_value_head: false,
+                init_log_std: 0.0,
+                ..MLPConfig::default()
+            },
+            &mut rng,
+        );
+        let actor_optimizer = Adam::new(learning_rate, actor.get_parameters().await?.len());
+
+        let critic_config = MLPConfig {
+            input_dim: observation_dim + action_dim,
+            hidden_dims: config.hidden_dims.clone(),
+            output_dim: 1,
+            activation: Activation::ReLU,
+            use_value_head: false,
+            ..MLPConfig::default()
+        };
+        let critics = [
+            Critic::new(&critic_config, learning_rate, &mut rng).await?,
+            Critic::new(&critic_config, learning_rate, &mut rng).await?,
+        ];
+
+        Ok(Self {
+            temperature: TemperatureTuner::new(&config, action_dim),
+            replay: ReplayBuffer::new(config.base.buffer_size),
+            config,
+            observation_dim,
+            action_dim,
+            actor,
+            actor_optimizer,
+            critics,
+            rng,
+            mode: AgentMode::Train,
+            total_timesteps: 0,
+        })
+    }
+
+    /// Report temperature updates to `callback`
+    #[must_use]
+    pub fn with_callback(mut self, callback: impl FnMut(&TemperatureStats) + Send + 'static) -> Self {
+        self.temperature = self.temperature.with_callback(callback);
+        self
+    }
+
+    /// Agent configuration
+    #[must_use]
+    pub fn config(&self) -> &SACConfig {
+        &self.config
+    }
+
+    /// Current entropy temperature
+    #[must_use]
+    pub fn alpha(&self) -> f32 {
+        self.temperature.alpha()
+    }
+
+    /// Temperature step of a SAC update
+    pub fn update_temperature(&mut self, log_probs: &[f32]) -> TemperatureStats {
+        self.temperature.update(log_probs)
+    }
+
+    /// Whether the agent is training or evaluating
+    #[must_use]
+    pub fn mode(&self) -> AgentMode {
+        self.mode
+    }
+
+    /// Switch between training and evaluation. In [`AgentMode::Eval`],
+    /// [`act`](Self::act) returns the squashed mean action and no updates run.
+    pub fn set_mode(&mut self, mode: AgentMode) {
+        self.mode = mode;
+    }
+
+    /// Total environment steps collected
+    #[must_use]
+    pub fn total_timesteps(&self) -> usize {
+        self.total_timesteps
+    }
+
+    /// Transitions held for replay
+    #[must_use]
+    pub fn replay_len(&self) -> usize {
+        self.replay.len()
+    }
+
+    /// Sample from the actor in training; take its squashed mean in
+    /// evaluation
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the observation doesn't fit the actor.
+    pub async fn act(&mut self, observation: &Array1<f32>) -> Result<Array1<f32>> {
+        if self.mode.is_eval() {
+            let output = self.actor.forward(&observation.view()).await?;
+            Ok(output.action_output.mapv(f32::tanh))
+        } else {
+            Ok(self.sample_action(observation).await?.action)
+        }
+    }
+
+    async fn sample_action(&mut self, observation: &Array1<f32>) -> Result<SquashedSample> {
+        let output = self.actor.forward(&observation.view()).await?;
+        let log_std = output
+            .log_std
+            .ok_or_else(|| anyhow::anyhow!("SAC actor has no log std"))?;
+        let std = log_std.mapv(f32::exp);
+        let noise: Array1<f32> = (0..self.action_dim)
+            .map(|_| self.rng.sample::<f32, _>(StandardNormal))
+            .collect();
+        let action = (&output.action_output + &(&std * &noise)).mapv(f32::tanh);
+
+        // Gaussian log density of the pre-squash sample, corrected for tanh
+        let half_log_two_pi = 0.5 * (2.0 * std::f32::consts::PI).ln();
+        let log_prob = noise
+            .iter()
+            .zip(&log_std)
+            .zip(&action)
+            .map(|((&n, &l), &a)| -0.5 * n * n - l - half_log_two_pi - (1.0 - a * a + 1e-6).ln())
+            .sum();
+        Ok(SquashedSample { noise, std, action, log_prob })
+    }
+
+    /// Store a transition for replay
+    pub fn remember(&mut self, transition: Transition<VectorObservation, ContinuousAction, VectorState>) {
+        self.replay.push_transition(transition);
+    }
+
+    /// Step `env` for `n_steps` from a fresh episode, storing each
+    /// transition and, in training, running one update per step once the
+    /// replay buffer holds a batch. Returns the stats of the last update.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the environment or an update fails.
+    pub async fn collect_and_train<E>(&mut self, env: &mut E, n_steps: usize) -> Result<Option<SACStats>>
+    where
+        E: Environment<Observation = VectorObservation, Action = ContinuousAction>,
+    {
+        let (mut obs, _) = env.reset().await?;
+        let mut last = None;
+
+        for _ in 0..n_steps {
+            let action = self.act(&to_array(&obs)).await?;
+            let action = ContinuousAction(action.iter().map(|&a| f64::from(a)).collect());
+            let step = env.step(action.clone()).await?;
+            self.remember(Transition {
+                observation: obs,
+                action,
+                reward: step.reward,
+                next_observation: step.observation.clone(),
+                done: step.done,
+                state: None,
+                next_state: None,
+            });
+            self.total_timesteps += 1;
+
+            if !self.mode.is_eval() {
+                if let Some(stats) = self.update().await? {
+                    last = Some(stats);
+                }
+            }
+            obs = if step.done || step.truncated {
+                env.reset().await?.0
+            } else {
+                step.observation
+            };
+        }
+
+        Ok(last)
+    }
+
+    /// One SAC update on a sampled batch: both critics, then the actor, then
+    /// the temperature, then the target critics. `None` until the replay
+    /// buffer holds `batch_size` transitions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a stored transition doesn't fit the networks.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+    pub async fn update(&mut self) -> Result<Option<SACStats>> {
+        let Some(batch) = self.replay.sample(self.config.base.batch_size.max(1)) else {
+            return Ok(None);
+        };
+        let scale = 1.0 / batch.len() as f32;
+        let gamma = self.config.base.gamma as f32;
+        let alpha = self.temperature.alpha();
+        let transitions: Vec<_> = batch
+            .iter()
+            .map(|experience| {
+                let t = &experience.transition;
+                let action: Array1<f32> = t.action.0.iter().map(|&a| a as f32).collect();
+                let next = to_array(&t.next_observation);
+                (to_array(&t.observation), action, t.reward.0 as f32, next, t.done)
+            })
+            .collect();
+
+        // Critics regress on r + gamma * (min target Q(s', a') - alpha * log pi(a' | s'))
+        let mut critic_grads: Vec<Vec<f32>> = Vec::with_capacity(2);
+        for critic in &self.critics {
+            critic_grads.push(vec![0.0; critic.online.get_parameters().await?.len()]);
+        }
+        let mut critic_loss = 0.0;
+        for (obs, action, reward, next_obs, done) in &transitions {
+            let target = if *done {
+                *reward
+            } else {
+                let next = self.sample_action(next_obs).await?;
+                let input = critic_input(&next_obs.view(), &next.action.view());
+                let q1 = q_value(&self.critics[0].target, &input).await?;
+                let q2 = q_value(&self.critics[1].target, &input).await?;
+                reward + gamma * (q1.min(q2) - alpha * next.log_prob)
+            };
+
+            let input = critic_input(&obs.view(), &action.view());
+            for (critic, grads) in self.critics.iter().zip(&mut critic_grads) {
+                let error = q_value(&critic.online, &input).await? - target;
+                critic_loss += error * error * scale;
+                let grad = OutputGradient { action_output: arr1(&[error * scale]), value: 0.0, log_std: None };
+                for (total, g) in grads.iter_mut().zip(critic.online.backward(&input.view(), &grad)) {
+                    *total += g;
+                }
+            }
+        }
+        for (critic, grads) in self.critics.iter_mut().zip(&critic_grads) {
+            critic.apply(grads).await?;
+        }
+
+        // Actor minimizes alpha * log pi(a | s) - min Q(s, a) with a reparameterized
+        let mut actor_grads = vec![0.0; self.actor_optimizer.n_params()];
+        let mut actor_loss = 0.0;
+        let mut log_probs = Vec::with_capacity(transitions.len());
+        let unit = OutputGradient { action_output: arr1(&[1.0]), value: 0.0, log_std: None };
+        for (obs, ..) in &transitions {
+            let sample = self.sample_action(obs).await?;
+            let input = critic_input(&obs.view(), &sample.action.view());
+            let q1 = q_value(&self.critics[0].online, &input).await?;
+            let q2 = q_value(&self.critics[1].online, &input).await?;
+            let (q, critic) = if q1 <= q2 { (q1, &self.critics[0]) } else { (q2, &self.critics[1]) };
+            actor_loss += (alpha * sample.log_prob - q) * scale;
+            log_probs.push(sample.log_prob);
+
+            let input_grad = critic.online.input_gradient(&input.view(), &unit);
+            let dq_da = input_grad.slice(s![self.observation_dim..]);
+            let mut d_mean = Array1::zeros(self.action_dim);
+            let mut d_log_std = Array1::zeros(self.action_dim);
+            for k in 0..self.action_dim {
+                let a = sample.action[k];
+                let squash = 1.0 - a * a;
+                // d/du of the loss, u being the pre-squash sample
+                let d_u = alpha * 2.0 * a * squash / (squash + 1e-6) - dq_da[k] * squash;
+                d_mean[k] = d_u * scale;
+                d_log_std[k] = (d_u * sample.std[k] * sample.noise[k] - alpha) * scale;
+            }
+            let grad = OutputGradient { action_output: d_mean, value: 0.0, log_std: Some(d_log_std) };
+            for (total, g) in actor_grads.iter_mut().zip(self.actor.backward(&obs.view(), &grad)) {
+                *total += g;
+            }
+        }
+        let mut params = self.actor.get_parameters().await?;
+        self.actor_optimizer.step(&mut params, &actor_grads);
+        // The log std parameters come last
+        let log_std_start = params.len() - self.action_dim;
+        for log_std in &mut params[log_std_start..] {
+            *log_std = log_std.clamp(LOG_STD_RANGE.0, LOG_STD_RANGE.1);
+        }
+        self.actor.set_parameters(&params).await?;
+
+        let temperature = self.temperature.update(&log_probs);
+        let tau = self.config.tau as f32;
+        for critic in &mut self.critics {
+            critic.soft_update(tau).await?;
+        }
+
+        Ok(Some(SACStats { critic_loss, actor_loss, temperature }))
+    }
+}
+
+impl SpaceSignature for SACAgent {
+    fn observation_spec(&self) -> SpaceSpec {
+        SpaceSpec::continuous(self.observation_dim)
+    }
+
+    fn action_spec(&self) -> SpaceSpec {
+        SpaceSpec::continuous(self.action_dim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use sentient_rl_core::action::ContinuousSpace;
+    use sentient_rl_core::{
+        ActionSpace, BoxObservationSpace, ObservationSpace, Reward, Step, StepInfo,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// One-step episodes paying `-(action - 0.5)^2`
+    struct Bandit;
+
+    #[async_trait]
+    impl Environment for Bandit {
+        type Observation = VectorObservation;
+        type Action = ContinuousAction;
+        type State = VectorState;
+
+        fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = VectorObservation>> {
+            Box::new(BoxObservationSpace::new(vec![1.0], vec![1.0], vec![1]).unwrap())
+        }
+
+        fn action_space(&self) -> Box<dyn ActionSpace<Action = ContinuousAction>> {
+            Box::new(ContinuousSpace::new(vec![-1.0], vec![1.0]).unwrap())
+        }
+
+        async fn reset(&mut self) -> sentient_rl_core::Result<(VectorObservation, StepInfo)> {
+            Ok((VectorObservation { data: vec![1.0] }, StepInfo::default()))
+        }
+
+        async fn step(
+            &mut self,
+            action: ContinuousAction,
+        ) -> sentient_rl_core::Result<Step<VectorObservation, VectorState>> {
+            Ok(Step {
+                observation: VectorObservation { data: vec![1.0] },
+                reward: Reward(-(action.0[0] - 0.5).powi(2)),
+                done: true,
+                truncated: false,
+                info: StepInfo::default(),
+                state: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auto_tuned_alpha_closes_entropy_gap() {
+        let config = SACConfig {
+            alpha_learning_rate: 0.05,
+            ..SACConfig::default()
+        };
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&logged);
+        let mut agent = SACAgent::new(config.clone(), 3, 2).await.unwrap().with_callback(move |stats| {
+            sink.lock().unwrap().push(stats.alpha);
+        });
+        assert!((agent.config().target_entropy_for(2) + 2.0).abs() < f32::EPSILON);
+
+        // Entropy 3.0 is above the -2.0 target, so alpha should shrink
+        let initial = agent.alpha();
+        for _ in 0..20 {
+            agent.update_temperature(&[-3.0, -3.0]);
+        }
+        assert!(agent.alpha() < initial);
+
+        let logged = logged.lock().unwrap();
+        assert_eq!(logged.len(), 20);
+        assert!(logged.windows(2).all(|w| w[1] < w[0]));
+
+        // Entropy -4.0 is below target, so alpha should grow
+        let mut tuner = TemperatureTuner::new(&config, 2);
+        let initial = tuner.alpha();
+        for _ in 0..20 {
+            tuner.update(&[4.0, 4.0]);
+        }
+        assert!(tuner.alpha() > initial);
+    }
+
+    #[test]
+    fn test_fixed_alpha_stays_constant() {
+        let config = SACConfig {
+            auto_entropy_tuning: false,
+            alpha: 0.1,
+            ..SACConfig::default()
+        };
+        let mut tuner = TemperatureTuner::new(&config, 3);
+        for _ in 0..10 {
+            let stats = tuner.update(&[-1.0, 2.0]);
+            assert!((stats.alpha - 0.1).abs() < 1e-6);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_learns_best_action_of_continuous_bandit() {
+        let config = SACConfig {
+            base: sentient_rl_core::AgentConfig {
+                learning_rate: 3e-3,
+                batch_size: 32,
+                ..Default::default()
+            },
+            alpha: 0.01,
+            auto_entropy_tuning: false,
+            hidden_dims: vec![16, 16],
+            ..SACConfig::default()
+        };
+        let mut agent = SACAgent::new_seeded(config, 1, 1, 7).await.unwrap();
+        let mut env = Bandit;
+        let stats = agent.collect_and_train(&mut env, 600).await.unwrap().unwrap();
+        assert_eq!(agent.total_timesteps(), 600);
+        assert!(stats.critic_loss < 0.05, "critic loss {}", stats.critic_loss);
+
+        agent.set_mode(AgentMode::Eval);
+        let action = agent.act(&arr1(&[1.0])).await.unwrap()[0];
+        assert!((action - 0.5).abs() < 0.1, "greedy action {}", action);
+    }
+
+    #[tokio::test]
+    async fn test_soft_update_moves_targets_by_tau() {
+        let mut agent = SACAgent::new_seeded(SACConfig::default(), 2, 1, 3).await.unwrap();
+        let critic = &mut agent.critics[0];
+        let online = critic.online.get_parameters().await.unwrap();
+        let shifted: Vec<f32> = online.iter().map(|p| p + 1.0).collect();
+        critic.online.set_parameters(&shifted).await.unwrap();
+
+        critic.soft_update(0.25).await.unwrap();
+        let target = critic.target.get_parameters().await.unwrap();
+        for (t, o) in target.iter().zip(&online) {
+            assert!((t - (o + 0.25)).abs() < 1e-5);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_compute_is_an_error() {
+        let mut config = SACConfig::default();
+        config.base.compute.device = sentient_rl_core::Device::Cuda(0);
+        assert!(SACAgent::new(config, 2, 1).await.is_err());
+    }
+}
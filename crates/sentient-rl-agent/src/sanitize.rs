@@ -0,0 +1,172 @@
+//! Observation sanitization at the agent boundary
+//!
+//! A single NaN in an observation propagates through every layer of the
+//! policy and, once trained on, into its weights. Agents pass observations
+//! through an [`ObservationSanitizer`] before acting or storing them, which
+//! replaces non-finite entries with zero and clips the rest to the bounds the
+//! environment declared.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{PoisonError, RwLock};
+
+use ndarray::Array1;
+use sentient_rl_core::{ObservationSpace, SpaceDescriptor};
+
+/// Repaired observations between warnings, after the first
+pub const DEFAULT_WARN_EVERY: usize = 1000;
+
+/// Per-element observation bounds; unbounded elements use infinities
+#[derive(Debug, Clone, Default)]
+struct Bounds {
+    low: Vec<f32>,
+    high: Vec<f32>,
+}
+
+/// Replaces NaN and infinite observation entries with zero and clips
+/// observations to their space's bounds, logging a sample of the repairs
+#[derive(Debug)]
+pub struct ObservationSanitizer {
+    bounds: RwLock<Bounds>,
+    warn_every: usize,
+    repaired: AtomicUsize,
+}
+
+impl Default for ObservationSanitizer {
+    fn default() -> Self {
+        Self::unbounded()
+    }
+}
+
+impl ObservationSanitizer {
+    /// Sanitizer that only replaces non-finite entries
+    #[must_use]
+    pub fn unbounded() -> Self {
+        Self {
+            bounds: RwLock::new(Bounds::default()),
+            warn_every: DEFAULT_WARN_EVERY,
+            repaired: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sanitizer clipping element `i` to `[low[i], high[i]]`
+    #[must_use]
+    pub fn with_bounds(low: &[f64], high: &[f64]) -> Self {
+        let sanitizer = Self::unbounded();
+        sanitizer.set_bounds(low, high);
+        sanitizer
+    }
+
+    /// Log a warning for the first repaired observation and every
+    /// `warn_every`th one after it
+    #[must_use]
+    pub fn with_warn_every(mut self, warn_every: usize) -> Self {
+        self.warn_every = warn_every.max(1);
+        self
+    }
+
+    /// Clip to `[low[i], high[i]]` from now on. Elements past the end of
+    /// either bound are left unbounded.
+    pub fn set_bounds(&self, low: &[f64], high: &[f64]) {
+        #[allow(clippy::cast_possible_truncation)]
+        let to_f32 = |v: &[f64]| v.iter().map(|&x| x as f32).collect();
+        *self.bounds.write().unwrap_or_else(PoisonError::into_inner) = Bounds {
+            low: to_f32(low),
+            high: to_f32(high),
+        };
+    }
+
+    /// Take bounds from `space` if it declares them as a box, and drop any
+    /// earlier bounds otherwise
+    pub fn set_space<S>(&self, space: &S)
+    where
+        S: ObservationSpace + ?Sized,
+    {
+        match space.descriptor() {
+            Some(SpaceDescriptor::Box { low, high, .. }) => self.set_bounds(&low, &high),
+            _ => self.set_bounds(&[], &[]),
+        }
+    }
+
+    /// Repair `observation` in place, returning how many entries changed
+    pub fn sanitize(&self, observation: &mut Array1<f32>) -> usize {
+        let bounds = self.bounds.read().unwrap_or_else(PoisonError::into_inner);
+        let mut changed = 0;
+        for (i, x) in observation.iter_mut().enumerate() {
+            let mut value = if x.is_finite() { *x } else { 0.0 };
+            if let Some(&low) = bounds.low.get(i) {
+                value = value.max(low);
+            }
+            if let Some(&high) = bounds.high.get(i) {
+                value = value.min(high);
+            }
+            if value.to_bits() != x.to_bits() {
+                *x = value;
+                changed += 1;
+            }
+        }
+        drop(bounds);
+
+        if changed > 0 {
+            let repaired = self.repaired.fetch_add(1, Ordering::Relaxed);
+            if repaired.is_multiple_of(self.warn_every) {
+                tracing::warn!(
+                    entries = changed,
+                    repaired_observations = repaired + 1,
+                    "Observation had non-finite or out-of-bounds entries; sanitized before use"
+                );
+            }
+        }
+        changed
+    }
+
+    /// Sanitized copy of `observation`
+    #[must_use]
+    pub fn sanitized(&self, observation: &Array1<f32>) -> Array1<f32> {
+        let mut observation = observation.clone();
+        self.sanitize(&mut observation);
+        observation
+    }
+
+    /// Observations that needed repair so far
+    pub fn repaired(&self) -> usize {
+        self.repaired.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+    use sentient_rl_core::observation::BoxObservationSpace;
+
+    #[test]
+    fn test_nan_and_out_of_range_entries_are_repaired() {
+        let space = BoxObservationSpace::new(
+            vec![-1.0, -1.0, 0.0, f64::NEG_INFINITY],
+            vec![1.0, 1.0, 10.0, f64::INFINITY],
+            vec![4],
+        )
+        .unwrap();
+        let sanitizer = ObservationSanitizer::default();
+        sanitizer.set_space(&space);
+
+        let mut obs = array![f32::NAN, 7.5, 3.0, f32::NEG_INFINITY];
+        assert_eq!(sanitizer.sanitize(&mut obs), 3);
+        assert_eq!(obs, array![0.0, 1.0, 3.0, 0.0]);
+        for (i, &x) in obs.iter().enumerate() {
+            assert!(x.is_finite());
+            assert!(f64::from(x) >= space.low[i] && f64::from(x) <= space.high[i]);
+        }
+
+        // Clean observations pass through untouched and are not counted
+        assert_eq!(sanitizer.sanitize(&mut obs), 0);
+        assert_eq!(sanitizer.repaired(), 1);
+    }
+
+    #[test]
+    fn test_zero_outside_bounds_is_clipped() {
+        let sanitizer = ObservationSanitizer::with_bounds(&[2.0], &[4.0]).with_warn_every(1);
+        assert_eq!(sanitizer.sanitized(&array![f32::NAN, f32::INFINITY]), array![2.0, 0.0]);
+        assert_eq!(ObservationSanitizer::unbounded().sanitized(&array![1e30]), array![1e30]);
+    }
+}
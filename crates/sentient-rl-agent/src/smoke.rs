@@ -0,0 +1,149 @@
+//! Smoke-testing an environment with a [`RandomAgent`]
+//!
+//! [`smoke_test`] sizes a random agent from the environment's own spaces and
+//! plays a few episodes. It exercises `reset`, `step` and the space
+//! definitions without any learning, so a new environment that is wired
+//! wrong fails here in seconds rather than hours into a training run.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use sentient_rl_core::action::DiscreteSpace;
+use sentient_rl_core::{run_episodes, DiscreteAction, Environment, Observation, Trajectory};
+
+use crate::random::RandomAgent;
+
+/// Steps after which an episode that has not ended counts as a failure
+pub const MAX_EPISODE_STEPS: usize = 100_000;
+
+/// Statistics of a smoke run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SmokeReport {
+    /// Episodes played
+    pub episodes: usize,
+    /// Shape of the environment's observations
+    pub observation_shape: Vec<usize>,
+    /// Number of discrete actions
+    pub n_actions: usize,
+    /// Mean episode return
+    pub mean_reward: f64,
+    /// Standard deviation of the episode return
+    pub std_reward: f64,
+    /// Mean episode length in steps
+    pub mean_length: f64,
+}
+
+/// Play `episodes` episodes of `env` with uniformly random actions
+///
+/// # Errors
+///
+/// Returns an error if the action space is not discrete or can't be
+/// described, if the environment fails to reset or step, if an observation
+/// doesn't match the observation space's shape, if a reward is not finite,
+/// or if an episode runs for [`MAX_EPISODE_STEPS`] without ending.
+pub async fn smoke_test<E>(env: &mut E, episodes: usize) -> Result<SmokeReport>
+where
+    E: Environment<Action = DiscreteAction>,
+{
+    let observation_shape = env.observation_space().shape();
+    let descriptor = env
+        .action_space()
+        .descriptor()
+        .context("The action space can't be described")?;
+    let action_space = DiscreteSpace::try_from(&descriptor)?;
+    let n_actions = action_space.n;
+    let mut agent = RandomAgent::new(action_space);
+
+    let trajectories = run_episodes(&mut agent, env, episodes, MAX_EPISODE_STEPS).await?;
+
+    let expected_len: usize = observation_shape.iter().product();
+    for (episode, trajectory) in trajectories.iter().enumerate() {
+        for (step, transition) in trajectory.transitions.iter().enumerate() {
+            for observation in [&transition.observation, &transition.next_observation] {
+                let len = observation.to_vec().len();
+                if len != expected_len {
+                    bail!(
+                        "Episode {episode}, step {step}: observation has {len} values, \
+                         the observation space shape {observation_shape:?} has {expected_len}"
+                    );
+                }
+            }
+            if !transition.reward.0.is_finite() {
+                bail!("Episode {episode}, step {step}: reward {} is not finite", transition.reward.0);
+            }
+        }
+        let ended = trajectory.len() < MAX_EPISODE_STEPS || trajectory.transitions.last().is_some_and(|t| t.done);
+        if !ended {
+            bail!("Episode {episode} did not end within {MAX_EPISODE_STEPS} steps");
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let n = episodes.max(1) as f64;
+    let mean_reward = trajectories.iter().map(|t| t.total_reward).sum::<f64>() / n;
+    let std_reward = (trajectories
+        .iter()
+        .map(|t| (t.total_reward - mean_reward).powi(2))
+        .sum::<f64>()
+        / n)
+        .sqrt();
+    #[allow(clippy::cast_precision_loss)]
+    let mean_length = trajectories.iter().map(Trajectory::len).sum::<usize>() as f64 / n;
+
+    Ok(SmokeReport {
+        episodes,
+        observation_shape,
+        n_actions,
+        mean_reward,
+        std_reward,
+        mean_length,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use sentient_rl_core::observation::{BoxObservationSpace, VectorObservation};
+    use sentient_rl_core::state::VectorState;
+    use sentient_rl_core::{ActionSpace, ObservationSpace, Reward, Step, StepInfo};
+
+    /// Reports four-value observations but hands out three
+    struct MiswiredEnv;
+
+    #[async_trait]
+    impl Environment for MiswiredEnv {
+        type Observation = VectorObservation;
+        type Action = DiscreteAction;
+        type State = VectorState;
+
+        fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = VectorObservation>> {
+            Box::new(BoxObservationSpace::new(vec![-1.0; 4], vec![1.0; 4], vec![4]).unwrap())
+        }
+
+        fn action_space(&self) -> Box<dyn ActionSpace<Action = DiscreteAction>> {
+            Box::new(DiscreteSpace::new(2))
+        }
+
+        async fn reset(&mut self) -> sentient_rl_core::Result<(VectorObservation, StepInfo)> {
+            Ok((VectorObservation { data: vec![0.0; 3] }, StepInfo::default()))
+        }
+
+        async fn step(&mut self, _action: DiscreteAction) -> sentient_rl_core::Result<Step<VectorObservation, VectorState>> {
+            Ok(Step {
+                observation: VectorObservation { data: vec![0.0; 3] },
+                reward: Reward(1.0),
+                done: true,
+                truncated: false,
+                info: StepInfo::default(),
+                state: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observation_shape_mismatch_fails() {
+        let err = smoke_test(&mut MiswiredEnv, 3).await.unwrap_err();
+        assert!(err.to_string().contains("observation has 3 values"), "{err}");
+    }
+}
@@ -177,6 +177,42 @@ impl Default for RunningMeanStd {
     }
 }
 
+/// Adam over a flat parameter vector
+pub(crate) struct Adam {
+    learning_rate: f32,
+    momentum: Vec<f32>,
+    velocity: Vec<f32>,
+    t: i32,
+}
+
+impl Adam {
+    pub(crate) fn new(learning_rate: f32, n_params: usize) -> Self {
+        Self {
+            learning_rate,
+            momentum: vec![0.0; n_params],
+            velocity: vec![0.0; n_params],
+            t: 0,
+        }
+    }
+
+    /// Number of parameters the optimizer was sized for
+    pub(crate) fn n_params(&self) -> usize {
+        self.momentum.len()
+    }
+
+    pub(crate) fn step(&mut self, params: &mut [f32], gradients: &[f32]) {
+        let (beta1, beta2, epsilon) = (0.9_f32, 0.999_f32, 1e-8_f32);
+        self.t = self.t.saturating_add(1);
+        let (bias1, bias2) = (1.0 - beta1.powi(self.t), 1.0 - beta2.powi(self.t));
+        let moments = self.momentum.iter_mut().zip(self.velocity.iter_mut());
+        for ((param, &grad), (m, v)) in params.iter_mut().zip(gradients).zip(moments) {
+            *m = beta1 * *m + (1.0 - beta1) * grad;
+            *v = beta2 * *v + (1.0 - beta2) * grad * grad;
+            *param -= self.learning_rate * (*m / bias1) / ((*v / bias2).sqrt() + epsilon);
+        }
+    }
+}
+
 /// Clip value to range
 pub fn clip(x: f64, min: f64, max: f64) -> f64 {
     x.clamp(min, max)
@@ -22,6 +22,16 @@ pub trait ActionSpace: Send + Sync {
     
     /// Get the dimensionality of the action space
     fn dim(&self) -> Option<usize>;
+    
+    /// Flat description used for compatibility checks
+    fn spec(&self) -> crate::compat::SpaceSpec {
+        crate::compat::SpaceSpec::continuous(self.dim().unwrap_or(0))
+    }
+    
+    /// Serializable description of this space, if it has one
+    fn descriptor(&self) -> Option<crate::spec::SpaceDescriptor> {
+        None
+    }
 }
 
 /// Discrete action (e.g., for discrete action spaces)
@@ -75,6 +85,62 @@ impl ActionSpace for DiscreteSpace {
     fn dim(&self) -> Option<usize> {
         Some(1)
     }
+    
+    fn spec(&self) -> crate::compat::SpaceSpec {
+        crate::compat::SpaceSpec::discrete(self.n)
+    }
+    
+    fn descriptor(&self) -> Option<crate::spec::SpaceDescriptor> {
+        Some(self.into())
+    }
+}
+
+/// One choice from each of several discrete sub-spaces
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MultiDiscreteAction(pub Vec<usize>);
+
+impl Action for MultiDiscreteAction {
+    fn to_vec(&self) -> Vec<f64> {
+        self.0.iter().map(|&choice| choice as f64).collect()
+    }
+}
+
+/// Several independent discrete choices, the `i`th among `nvec[i]` options
+#[derive(Debug, Clone)]
+pub struct MultiDiscreteSpace {
+    /// Number of options of each sub-space
+    pub nvec: Vec<usize>,
+}
+
+impl MultiDiscreteSpace {
+    /// Create a new multi-discrete action space
+    #[must_use]
+    pub fn new(nvec: Vec<usize>) -> Self {
+        Self { nvec }
+    }
+}
+
+impl ActionSpace for MultiDiscreteSpace {
+    type Action = MultiDiscreteAction;
+    
+    fn sample(&self) -> Self::Action {
+        use rand::Rng;
+        let mut rng = rand::thread_rng();
+        MultiDiscreteAction(self.nvec.iter().map(|&n| rng.gen_range(0..n)).collect())
+    }
+    
+    fn contains(&self, action: &Self::Action) -> bool {
+        action.0.len() == self.nvec.len()
+            && action.0.iter().zip(&self.nvec).all(|(choice, n)| choice < n)
+    }
+    
+    fn dim(&self) -> Option<usize> {
+        Some(self.nvec.len())
+    }
+    
+    fn descriptor(&self) -> Option<crate::spec::SpaceDescriptor> {
+        Some(self.into())
+    }
 }
 
 /// Continuous action space (box)
@@ -125,4 +191,8 @@ impl ActionSpace for ContinuousSpace {
     fn dim(&self) -> Option<usize> {
         Some(self.low.len())
     }
+    
+    fn descriptor(&self) -> Option<crate::spec::SpaceDescriptor> {
+        Some(self.into())
+    }
 }
\ No newline at end of file
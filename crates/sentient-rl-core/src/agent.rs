@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
-use crate::{Action, Observation, Policy, Step, Environment};
+use crate::{Action, ComputeConfig, Observation, Policy, Step, Environment};
 
 /// Configuration for agents
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +18,9 @@ pub struct AgentConfig {
     pub buffer_size: usize,
     /// Target network update frequency
     pub target_update_freq: Option<usize>,
+    /// Device and precision to compute with
+    #[serde(default)]
+    pub compute: ComputeConfig,
     /// Additional parameters
     #[serde(flatten)]
     pub params: serde_json::Map<String, serde_json::Value>,
@@ -31,11 +34,35 @@ impl Default for AgentConfig {
             batch_size: 32,
             buffer_size: 10000,
             target_update_freq: Some(100),
+            compute: ComputeConfig::default(),
             params: serde_json::Map::new(),
         }
     }
 }
 
+/// Whether an agent is learning or only being run
+///
+/// In `Eval` an agent acts deterministically, never explores and leaves its
+/// weights untouched, which is what deployment, policy injection and
+/// evaluation need.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentMode {
+    /// Explore and learn from experience
+    #[default]
+    Train,
+    /// Act greedily with frozen weights
+    Eval,
+}
+
+impl AgentMode {
+    /// Whether exploration and learning are switched off
+    #[must_use]
+    pub fn is_eval(self) -> bool {
+        self == AgentMode::Eval
+    }
+}
+
 /// Core agent trait
 #[async_trait]
 pub trait Agent: Send + Sync {
@@ -60,6 +87,12 @@ pub trait Agent: Send + Sync {
         Ok(()) // Default: no learning
     }
     
+    /// Clear per-episode internal state (recurrent hidden state, running
+    /// statistics) once an episode has finished
+    async fn reset_episode(&mut self) -> crate::Result<()> {
+        Ok(()) // Default: stateless between episodes
+    }
+    
     /// Save the agent
     async fn save(&self, path: &std::path::Path) -> crate::Result<()>;
     
@@ -70,6 +103,11 @@ pub trait Agent: Send + Sync {
     fn metrics(&self) -> AgentMetrics {
         AgentMetrics::default()
     }
+    
+    /// Whether the agent is training or evaluating
+    fn mode(&self) -> AgentMode {
+        AgentMode::Train
+    }
 }
 
 /// Agent metrics
@@ -180,4 +218,12 @@ where
     fn metrics(&self) -> AgentMetrics {
         self.metrics.clone()
     }
+    
+    fn mode(&self) -> AgentMode {
+        if self.training {
+            AgentMode::Train
+        } else {
+            AgentMode::Eval
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,140 @@
+//! Agent/environment space compatibility checks
+//!
+//! Binding an agent built for one observation or action shape to an
+//! environment with another otherwise only fails deep inside a training step.
+//! [`check_compatibility`] compares both sides up front.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::environment::Environment;
+
+/// Whether a space is made of discrete choices or continuous values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpaceKind {
+    /// A finite set of choices
+    Discrete,
+    /// Real-valued vectors
+    Continuous,
+}
+
+/// Flat description of a space
+///
+/// For discrete spaces `dim` is the number of choices; for continuous spaces
+/// it is the flattened vector length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpaceSpec {
+    /// Discrete or continuous
+    pub kind: SpaceKind,
+    /// Number of choices or flattened length
+    pub dim: usize,
+}
+
+impl SpaceSpec {
+    /// Discrete space with `n` choices
+    #[must_use]
+    pub fn discrete(n: usize) -> Self {
+        Self { kind: SpaceKind::Discrete, dim: n }
+    }
+
+    /// Continuous space of flattened length `dim`
+    #[must_use]
+    pub fn continuous(dim: usize) -> Self {
+        Self { kind: SpaceKind::Continuous, dim }
+    }
+}
+
+impl fmt::Display for SpaceSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            SpaceKind::Discrete => write!(f, "discrete({})", self.dim),
+            SpaceKind::Continuous => write!(f, "continuous({})", self.dim),
+        }
+    }
+}
+
+/// Anything with an observation and action space: agents and environments
+pub trait SpaceSignature {
+    /// Observation space description
+    fn observation_spec(&self) -> SpaceSpec;
+
+    /// Action space description
+    fn action_spec(&self) -> SpaceSpec;
+}
+
+impl<E: Environment> SpaceSignature for E {
+    fn observation_spec(&self) -> SpaceSpec {
+        self.observation_space().spec()
+    }
+
+    fn action_spec(&self) -> SpaceSpec {
+        self.action_space().spec()
+    }
+}
+
+/// Spaces an agent was built for, when the agent itself cannot report them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentSpaces {
+    /// Observation space the agent expects
+    pub observation: SpaceSpec,
+    /// Action space the agent produces
+    pub action: SpaceSpec,
+}
+
+impl SpaceSignature for AgentSpaces {
+    fn observation_spec(&self) -> SpaceSpec {
+        self.observation
+    }
+
+    fn action_spec(&self) -> SpaceSpec {
+        self.action
+    }
+}
+
+/// Fail with [`RLError::ShapeMismatch`](crate::RLError::ShapeMismatch) unless
+/// the agent's spaces match the environment's
+pub fn check_compatibility(agent: &impl SpaceSignature, env: &impl SpaceSignature) -> crate::Result<()> {
+    for (space, agent_spec, env_spec) in [
+        ("observation", agent.observation_spec(), env.observation_spec()),
+        ("action", agent.action_spec(), env.action_spec()),
+    ] {
+        if agent_spec != env_spec {
+            return Err(crate::RLError::ShapeMismatch {
+                space,
+                agent: agent_spec,
+                env: env_spec,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RLError;
+
+    fn spaces(observation: SpaceSpec, action: SpaceSpec) -> AgentSpaces {
+        AgentSpaces { observation, action }
+    }
+
+    #[test]
+    fn test_check_compatibility() {
+        let env = spaces(SpaceSpec::continuous(4), SpaceSpec::discrete(2));
+        assert!(check_compatibility(&env, &env).is_ok());
+
+        let wide = spaces(SpaceSpec::continuous(8), SpaceSpec::discrete(2));
+        let err = check_compatibility(&wide, &env).unwrap_err();
+        assert!(matches!(err, RLError::ShapeMismatch { space: "observation", .. }));
+        assert_eq!(
+            err.to_string(),
+            "Shape mismatch in observation space: agent expects continuous(8), environment provides continuous(4)"
+        );
+
+        let continuous = spaces(SpaceSpec::continuous(4), SpaceSpec::continuous(2));
+        assert!(matches!(
+            check_compatibility(&continuous, &env),
+            Err(RLError::ShapeMismatch { space: "action", .. })
+        ));
+    }
+}
@@ -0,0 +1,125 @@
+//! Device and precision configuration
+//!
+//! Agents currently run on the CPU ndarray backend only. Configs carry the
+//! intended device and precision anyway, so a GPU backend can be added without
+//! changing them; requesting what this build cannot do fails up front.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::error::RLError;
+
+/// Where tensors live and computation runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Device {
+    /// Host CPU
+    #[default]
+    Cpu,
+    /// CUDA GPU with the given ordinal
+    Cuda(usize),
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Device::Cpu => write!(f, "cpu"),
+            Device::Cuda(ordinal) => write!(f, "cuda:{ordinal}"),
+        }
+    }
+}
+
+/// Floating point precision of network parameters and activations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DType {
+    /// Half precision
+    F16,
+    /// Single precision
+    #[default]
+    F32,
+    /// Double precision
+    F64,
+}
+
+impl fmt::Display for DType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DType::F16 => write!(f, "f16"),
+            DType::F32 => write!(f, "f32"),
+            DType::F64 => write!(f, "f64"),
+        }
+    }
+}
+
+/// Device and precision an agent should compute with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ComputeConfig {
+    /// Compute device
+    #[serde(default)]
+    pub device: Device,
+    /// Parameter and activation precision
+    #[serde(default)]
+    pub dtype: DType,
+}
+
+impl ComputeConfig {
+    /// Check that the ndarray backend can honor this config. It runs on the
+    /// CPU in single precision only.
+    ///
+    /// # Errors
+    ///
+    /// [`RLError::UnsupportedCompute`] naming the unavailable device or dtype.
+    pub fn ensure_supported(&self) -> crate::Result<()> {
+        if self.device != Device::Cpu {
+            return Err(RLError::UnsupportedCompute(format!(
+                "device {} requested, but this build only has the CPU ndarray backend; use Device::Cpu",
+                self.device
+            )));
+        }
+        if self.dtype != DType::F32 {
+            return Err(RLError::UnsupportedCompute(format!(
+                "dtype {} requested, but the ndarray backend computes in f32 only",
+                self.dtype
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AgentConfig;
+
+    #[test]
+    fn test_default_is_cpu_f32_and_unsupported_combos_fail() {
+        let compute = AgentConfig::default().compute;
+        assert_eq!(compute.device, Device::Cpu);
+        assert_eq!(compute.dtype, DType::F32);
+        assert!(compute.ensure_supported().is_ok());
+
+        let cuda = ComputeConfig {
+            device: Device::Cuda(1),
+            ..ComputeConfig::default()
+        };
+        let err = cuda.ensure_supported().unwrap_err();
+        assert!(matches!(err, RLError::UnsupportedCompute(_)));
+        assert!(err.to_string().contains("device cuda:1 requested"), "{err}");
+
+        let f64 = ComputeConfig {
+            dtype: DType::F64,
+            ..ComputeConfig::default()
+        };
+        let err = f64.ensure_supported().unwrap_err().to_string();
+        assert!(err.contains("dtype f64 requested"), "{err}");
+
+        // Configs saved before compute existed still load, as Cpu/F32
+        let legacy: AgentConfig = serde_json::from_str(
+            r#"{"learning_rate":0.001,"gamma":0.99,"batch_size":32,"buffer_size":100,"target_update_freq":null}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.compute, ComputeConfig::default());
+        assert!(!legacy.params.contains_key("compute"));
+    }
+}
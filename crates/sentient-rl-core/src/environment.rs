@@ -22,7 +22,16 @@ pub struct Step<O, S> {
     pub state: Option<S>,
 }
 
+/// Info key under which environments report the valid-action mask
+pub const ACTION_MASK_KEY: &str = "action_mask";
+
 /// Additional information from a step
+///
+/// Environments report diagnostics under bare keys (`"command"`,
+/// `"execution_time_ms"`). Wrappers pass the inner environment's info through
+/// and add their own keys prefixed with the wrapper name, e.g.
+/// `"TimeLimit.truncated"`, so a stack of wrappers never clobbers what the
+/// environment reported.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StepInfo {
     /// Custom fields
@@ -30,6 +39,53 @@ pub struct StepInfo {
     pub fields: serde_json::Map<String, serde_json::Value>,
 }
 
+impl StepInfo {
+    /// Set `key` to `value`, replacing any previous value
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) {
+        self.fields.insert(key.into(), value.into());
+    }
+
+    /// Builder form of [`insert`](Self::insert)
+    #[must_use]
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.insert(key, value);
+        self
+    }
+
+    /// Value stored under `key`
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.fields.get(key)
+    }
+
+    /// Copy every field of `other` into this info, overwriting on conflict
+    pub fn merge(&mut self, other: StepInfo) {
+        self.fields.extend(other.fields);
+    }
+
+    /// Whether no fields are set
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Report which discrete actions are valid for the next step
+    #[must_use]
+    pub fn with_action_mask(self, mask: &[bool]) -> Self {
+        self.with(ACTION_MASK_KEY, mask.to_vec())
+    }
+
+    /// Valid-action mask for the next step, if the environment reported one
+    #[must_use]
+    pub fn action_mask(&self) -> Option<Vec<bool>> {
+        self.get(ACTION_MASK_KEY)?
+            .as_array()?
+            .iter()
+            .map(serde_json::Value::as_bool)
+            .collect()
+    }
+}
+
 /// Episode information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Episode {
@@ -56,6 +112,12 @@ pub struct EnvironmentConfig {
     pub max_steps: Option<usize>,
     /// Render mode
     pub render_mode: Option<String>,
+    /// Bounds rewards are clamped to after the environment computes them,
+    /// so shaping terms can't push them outside the range value learning
+    /// expects. Environments made through the registry are wrapped in
+    /// `ClipReward` when this is set.
+    #[serde(default)]
+    pub reward_clip: Option<(f32, f32)>,
     /// Additional parameters
     #[serde(flatten)]
     pub params: serde_json::Map<String, serde_json::Value>,
@@ -67,6 +129,7 @@ impl Default for EnvironmentConfig {
             seed: None,
             max_steps: None,
             render_mode: None,
+            reward_clip: None,
             params: serde_json::Map::new(),
         }
     }
@@ -115,6 +178,47 @@ pub trait Environment: Send + Sync {
     }
 }
 
+/// Boxed environments, such as the ones a registry hands out, are
+/// environments too
+#[async_trait]
+impl<E: Environment + ?Sized> Environment for Box<E> {
+    type Observation = E::Observation;
+    type Action = E::Action;
+    type State = E::State;
+    
+    fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+        (**self).observation_space()
+    }
+    
+    fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+        (**self).action_space()
+    }
+    
+    fn state_space(&self) -> Option<Box<dyn StateSpace<State = Self::State>>> {
+        (**self).state_space()
+    }
+    
+    async fn reset(&mut self) -> crate::Result<(Self::Observation, StepInfo)> {
+        (**self).reset().await
+    }
+    
+    async fn step(&mut self, action: Self::Action) -> crate::Result<Step<Self::Observation, Self::State>> {
+        (**self).step(action).await
+    }
+    
+    async fn render(&self) -> crate::Result<()> {
+        (**self).render().await
+    }
+    
+    async fn close(&mut self) -> crate::Result<()> {
+        (**self).close().await
+    }
+    
+    fn episode_info(&self) -> Option<Episode> {
+        (**self).episode_info()
+    }
+}
+
 /// Wrapper for environments that tracks episodes
 pub struct TrackedEnvironment<E> {
     /// Inner environment
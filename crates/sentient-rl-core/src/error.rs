@@ -27,12 +27,36 @@ pub enum RLError {
     
     /// Dimension mismatch
     #[error("Dimension mismatch: expected {expected}, got {actual}")]
-    DimensionMismatch { expected: usize, actual: usize },
+    DimensionMismatch {
+        /// Dimension that was required
+        expected: usize,
+        /// Dimension that was given
+        actual: usize,
+    },
+    
+    /// Agent and environment spaces disagree
+    #[error("Shape mismatch in {space} space: agent expects {agent}, environment provides {env}")]
+    ShapeMismatch {
+        /// Which space disagrees, "observation" or "action"
+        space: &'static str,
+        /// Shape the agent was built for
+        agent: crate::compat::SpaceSpec,
+        /// Shape the environment provides
+        env: crate::compat::SpaceSpec,
+    },
     
     /// Computation error
     #[error("Computation error: {0}")]
     Computation(String),
     
+    /// Requested device or precision is not available in this build
+    #[error("Unsupported compute config: {0}")]
+    UnsupportedCompute(String),
+    
+    /// Operation aborted by a cancellation request
+    #[error("Cancelled: {0}")]
+    Cancelled(String),
+    
     /// Serialization error
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
@@ -10,24 +10,32 @@
 
 pub mod action;
 pub mod agent;
+pub mod compat;
+pub mod compute;
 pub mod environment;
 pub mod error;
 pub mod observation;
 pub mod policy;
 pub mod reward;
+pub mod rollout;
+pub mod spec;
 pub mod state;
 pub mod trajectory;
 pub mod value;
 
 // Re-export core traits and types
-pub use action::{Action, ActionSpace, DiscreteAction, ContinuousAction};
-pub use agent::{Agent, AgentConfig, Learning};
-pub use environment::{Environment, EnvironmentConfig, Step, Episode};
+pub use action::{Action, ActionSpace, DiscreteAction, DiscreteSpace, ContinuousAction, MultiDiscreteAction};
+pub use agent::{Agent, AgentConfig, AgentMode, Learning};
+pub use compat::{check_compatibility, AgentSpaces, SpaceKind, SpaceSignature, SpaceSpec};
+pub use compute::{ComputeConfig, DType, Device};
+pub use environment::{Environment, EnvironmentConfig, Step, StepInfo, Episode, ACTION_MASK_KEY};
 pub use error::{RLError, Result};
-pub use observation::{Observation, ObservationSpace};
+pub use observation::{Observation, ObservationSpace, VectorObservation, BoxObservationSpace};
 pub use policy::{Policy, DeterministicPolicy, StochasticPolicy};
 pub use reward::{Reward, RewardFunction};
-pub use state::{State, StateSpace, Terminal};
+pub use rollout::run_episodes;
+pub use spec::{EnvSpec, SpaceDescriptor};
+pub use state::{State, StateSpace, Terminal, VectorState, BoxSpace};
 pub use trajectory::{Trajectory, Transition, Experience};
 pub use value::{ValueFunction, ActionValueFunction, Advantage};
 
@@ -25,6 +25,16 @@ pub trait ObservationSpace: Send + Sync {
     
     /// Get the shape of observations in this space
     fn shape(&self) -> Vec<usize>;
+    
+    /// Flat description used for compatibility checks
+    fn spec(&self) -> crate::compat::SpaceSpec {
+        crate::compat::SpaceSpec::continuous(self.shape().iter().product())
+    }
+    
+    /// Serializable description of this space, if it has one
+    fn descriptor(&self) -> Option<crate::spec::SpaceDescriptor> {
+        None
+    }
 }
 
 /// Vector observation
@@ -118,4 +128,8 @@ impl ObservationSpace for BoxObservationSpace {
     fn shape(&self) -> Vec<usize> {
         self.shape.clone()
     }
+    
+    fn descriptor(&self) -> Option<crate::spec::SpaceDescriptor> {
+        Some(self.into())
+    }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 //! Policy abstractions for action selection
 
 use async_trait::async_trait;
-use std::fmt::Debug;
+use std::marker::PhantomData;
 
 use crate::{Action, ActionSpace, Observation};
 
@@ -29,19 +29,6 @@ pub trait DeterministicPolicy: Policy {
     async fn deterministic_act(&self, observation: &Self::Observation) -> crate::Result<Self::Action>;
 }
 
-#[async_trait]
-impl<P> Policy for P
-where
-    P: DeterministicPolicy,
-{
-    type Observation = P::Observation;
-    type Action = P::Action;
-    
-    async fn act(&self, observation: &Self::Observation) -> crate::Result<Self::Action> {
-        self.deterministic_act(observation).await
-    }
-}
-
 /// Stochastic policy that samples actions from a distribution
 #[async_trait]
 pub trait StochasticPolicy: Policy {
@@ -61,19 +48,6 @@ pub trait StochasticPolicy: Policy {
     }
 }
 
-#[async_trait]
-impl<P> Policy for P
-where
-    P: StochasticPolicy,
-{
-    type Observation = P::Observation;
-    type Action = P::Action;
-    
-    async fn act(&self, observation: &Self::Observation) -> crate::Result<Self::Action> {
-        self.sample(observation).await
-    }
-}
-
 /// Epsilon-greedy policy wrapper
 pub struct EpsilonGreedy<P, A> {
     /// Base policy
@@ -111,9 +85,10 @@ where
     
     async fn act(&self, observation: &Self::Observation) -> crate::Result<Self::Action> {
         use rand::Rng;
-        let mut rng = rand::thread_rng();
+        // Draw before awaiting: the thread-local rng is not `Send`
+        let explore = rand::thread_rng().gen::<f64>() < self.epsilon;
         
-        if rng.gen::<f64>() < self.epsilon {
+        if explore {
             // Explore: random action
             Ok(self.action_space.sample())
         } else {
@@ -127,21 +102,26 @@ where
     }
 }
 
-/// Random policy that always selects random actions
-pub struct RandomPolicy<A> {
+/// Random policy that always selects random actions, ignoring
+/// observations of type `O`
+pub struct RandomPolicy<A, O> {
     /// Action space
     pub action_space: A,
+    _observation: PhantomData<fn(&O)>,
 }
 
-impl<A> RandomPolicy<A> {
+impl<A, O> RandomPolicy<A, O> {
     /// Create a new random policy
     pub fn new(action_space: A) -> Self {
-        Self { action_space }
+        Self {
+            action_space,
+            _observation: PhantomData,
+        }
     }
 }
 
 #[async_trait]
-impl<O, A> Policy for RandomPolicy<A>
+impl<A, O> Policy for RandomPolicy<A, O>
 where
     O: Observation,
     A: ActionSpace + Send + Sync,
@@ -18,6 +18,12 @@ impl Reward {
     pub fn value(&self) -> f64 {
         self.0
     }
+    
+    /// This reward clamped to `[low, high]`
+    #[must_use]
+    pub fn clip(self, low: f64, high: f64) -> Self {
+        Self(self.0.max(low).min(high))
+    }
 }
 
 impl From<f64> for Reward {
@@ -0,0 +1,200 @@
+//! Episode rollouts driving an agent through an environment
+
+use crate::{Agent, Environment, Step, Trajectory, Transition};
+
+/// Run `episodes` episodes of `agent` in `env`, each capped at `max_steps`.
+///
+/// Every episode starts from `env.reset()` and ends with
+/// [`Agent::reset_episode`], whether the environment finished it or the step
+/// cap did, so agents with recurrent state start each episode fresh.
+///
+/// # Errors
+///
+/// Returns the first error raised by the agent or the environment.
+pub async fn run_episodes<G, E>(
+    agent: &mut G,
+    env: &mut E,
+    episodes: usize,
+    max_steps: usize,
+) -> crate::Result<Vec<Trajectory<E::Observation, E::Action, E::State>>>
+where
+    E: Environment,
+    G: Agent<Observation = E::Observation, Action = E::Action>,
+{
+    let mut trajectories = Vec::with_capacity(episodes);
+
+    for _ in 0..episodes {
+        let (mut observation, _) = env.reset().await?;
+        let mut trajectory = Trajectory::new(uuid::Uuid::new_v4().to_string());
+
+        for _ in 0..max_steps {
+            let action = agent.act(&observation).await?;
+            let step = env.step(action.clone()).await?;
+            agent.observe(&step).await?;
+
+            let Step {
+                observation: next_observation,
+                reward,
+                done,
+                truncated,
+                info,
+                state,
+            } = step;
+            trajectory.push_with_info(
+                Transition {
+                    observation,
+                    action,
+                    reward,
+                    next_observation: next_observation.clone(),
+                    done,
+                    state: None,
+                    next_state: state,
+                },
+                info,
+            );
+            observation = next_observation;
+
+            if done || truncated {
+                break;
+            }
+        }
+
+        agent.reset_episode().await?;
+        trajectories.push(trajectory);
+    }
+
+    Ok(trajectories)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::DiscreteSpace;
+    use crate::observation::{BoxObservationSpace, VectorObservation};
+    use crate::state::VectorState;
+    use crate::{ActionSpace, DiscreteAction, ObservationSpace, Policy, Reward, StepInfo};
+    use async_trait::async_trait;
+
+    struct ConstantPolicy;
+
+    #[async_trait]
+    impl Policy for ConstantPolicy {
+        type Observation = VectorObservation;
+        type Action = DiscreteAction;
+
+        async fn act(&self, _observation: &VectorObservation) -> crate::Result<DiscreteAction> {
+            Ok(DiscreteAction(0))
+        }
+    }
+
+    /// Agent whose only state is how often it has been reset
+    struct ResetCounter {
+        policy: ConstantPolicy,
+        resets: usize,
+        steps_since_reset: usize,
+    }
+
+    #[async_trait]
+    impl Agent for ResetCounter {
+        type Observation = VectorObservation;
+        type Action = DiscreteAction;
+
+        fn policy(&self) -> &dyn Policy<Observation = VectorObservation, Action = DiscreteAction> {
+            &self.policy
+        }
+
+        fn policy_mut(
+            &mut self,
+        ) -> &mut dyn Policy<Observation = VectorObservation, Action = DiscreteAction> {
+            &mut self.policy
+        }
+
+        async fn observe(
+            &mut self,
+            _step: &Step<VectorObservation, impl crate::State>,
+        ) -> crate::Result<()> {
+            self.steps_since_reset += 1;
+            Ok(())
+        }
+
+        async fn reset_episode(&mut self) -> crate::Result<()> {
+            self.resets += 1;
+            self.steps_since_reset = 0;
+            Ok(())
+        }
+
+        async fn save(&self, _path: &std::path::Path) -> crate::Result<()> {
+            Ok(())
+        }
+
+        async fn load(&mut self, _path: &std::path::Path) -> crate::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Episodes end after `length` steps
+    struct FixedLengthEnv {
+        length: u32,
+        t: u32,
+    }
+
+    #[async_trait]
+    impl Environment for FixedLengthEnv {
+        type Observation = VectorObservation;
+        type Action = DiscreteAction;
+        type State = VectorState;
+
+        fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = VectorObservation>> {
+            Box::new(BoxObservationSpace::new(vec![0.0], vec![100.0], vec![1]).unwrap())
+        }
+
+        fn action_space(&self) -> Box<dyn ActionSpace<Action = DiscreteAction>> {
+            Box::new(DiscreteSpace::new(1))
+        }
+
+        async fn reset(&mut self) -> crate::Result<(VectorObservation, StepInfo)> {
+            self.t = 0;
+            Ok((VectorObservation { data: vec![0.0] }, StepInfo::default()))
+        }
+
+        async fn step(
+            &mut self,
+            _action: DiscreteAction,
+        ) -> crate::Result<Step<VectorObservation, VectorState>> {
+            self.t += 1;
+            Ok(Step {
+                observation: VectorObservation {
+                    data: vec![f64::from(self.t)],
+                },
+                reward: Reward(1.0),
+                done: self.t >= self.length,
+                truncated: false,
+                info: StepInfo::default(),
+                state: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_is_reset_once_per_completed_episode() {
+        let mut agent = ResetCounter {
+            policy: ConstantPolicy,
+            resets: 0,
+            steps_since_reset: 0,
+        };
+        let mut env = FixedLengthEnv { length: 3, t: 0 };
+
+        let trajectories = run_episodes(&mut agent, &mut env, 4, 100).await.unwrap();
+        assert_eq!(trajectories.len(), 4);
+        assert!(trajectories
+            .iter()
+            .all(|t| t.len() == 3 && t.transitions[2].done));
+        assert_eq!(agent.resets, 4);
+        assert_eq!(agent.steps_since_reset, 0);
+
+        // Hitting the step cap also ends the episode
+        let trajectories = run_episodes(&mut agent, &mut env, 2, 2).await.unwrap();
+        assert!(trajectories.iter().all(|t| t.len() == 2));
+        assert_eq!(agent.resets, 6);
+    }
+}
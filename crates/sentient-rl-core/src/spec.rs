@@ -0,0 +1,372 @@
+//! Serializable space and environment descriptions
+//!
+//! Spaces are trait objects, so an environment's spaces can't be persisted or
+//! sent to a remote trainer directly. [`SpaceDescriptor`] captures the full
+//! definition of each concrete space (bounds and shape included, unlike the
+//! flat [`SpaceSpec`](crate::compat::SpaceSpec) used for compatibility checks)
+//! and converts back to an equivalent space. [`EnvSpec`] bundles both spaces of
+//! an environment under its id.
+
+use serde::{Deserialize, Serialize};
+
+use crate::action::{ContinuousSpace, DiscreteSpace, MultiDiscreteSpace};
+use crate::environment::Environment;
+use crate::observation::BoxObservationSpace;
+use crate::state::BoxSpace;
+use crate::RLError;
+
+/// Full, serializable definition of a space
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpaceDescriptor {
+    /// `n` discrete choices
+    Discrete {
+        /// Number of choices
+        n: usize,
+    },
+    /// Bounded real-valued vectors
+    Box {
+        /// Lower bound of each element
+        #[serde(with = "bounds")]
+        low: Vec<f64>,
+        /// Upper bound of each element
+        #[serde(with = "bounds")]
+        high: Vec<f64>,
+        /// Shape of the vectors
+        shape: Vec<usize>,
+    },
+    /// Several independent discrete choices
+    MultiDiscrete {
+        /// Number of choices of each sub-space
+        nvec: Vec<usize>,
+    },
+}
+
+impl SpaceDescriptor {
+    fn kind(&self) -> &'static str {
+        match self {
+            SpaceDescriptor::Discrete { .. } => "discrete",
+            SpaceDescriptor::Box { .. } => "box",
+            SpaceDescriptor::MultiDiscrete { .. } => "multi-discrete",
+        }
+    }
+
+    fn mismatch(&self, target: &str) -> RLError {
+        RLError::Environment(format!(
+            "Cannot build a {} from a {} space descriptor",
+            target,
+            self.kind()
+        ))
+    }
+}
+
+/// JSON has no infinity, and unbounded boxes are common (`CartPole`'s
+/// velocities), so infinite bounds are written as `"inf"` / `"-inf"`
+mod bounds {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum Bound {
+        Finite(f64),
+        Named(String),
+    }
+
+    pub fn serialize<S: Serializer>(values: &[f64], serializer: S) -> Result<S::Ok, S::Error> {
+        values
+            .iter()
+            .map(|&v| {
+                if v == f64::INFINITY {
+                    Bound::Named("inf".to_string())
+                } else if v == f64::NEG_INFINITY {
+                    Bound::Named("-inf".to_string())
+                } else {
+                    Bound::Finite(v)
+                }
+            })
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f64>, D::Error> {
+        Vec::<Bound>::deserialize(deserializer)?
+            .into_iter()
+            .map(|bound| match bound {
+                Bound::Finite(v) => Ok(v),
+                Bound::Named(name) => match name.as_str() {
+                    "inf" => Ok(f64::INFINITY),
+                    "-inf" => Ok(f64::NEG_INFINITY),
+                    _ => Err(serde::de::Error::custom(format!("invalid bound {name:?}"))),
+                },
+            })
+            .collect()
+    }
+}
+
+impl From<&DiscreteSpace> for SpaceDescriptor {
+    fn from(space: &DiscreteSpace) -> Self {
+        SpaceDescriptor::Discrete { n: space.n }
+    }
+}
+
+impl From<&MultiDiscreteSpace> for SpaceDescriptor {
+    fn from(space: &MultiDiscreteSpace) -> Self {
+        SpaceDescriptor::MultiDiscrete {
+            nvec: space.nvec.clone(),
+        }
+    }
+}
+
+impl From<&BoxObservationSpace> for SpaceDescriptor {
+    fn from(space: &BoxObservationSpace) -> Self {
+        SpaceDescriptor::Box {
+            low: space.low.clone(),
+            high: space.high.clone(),
+            shape: space.shape.clone(),
+        }
+    }
+}
+
+impl From<&ContinuousSpace> for SpaceDescriptor {
+    fn from(space: &ContinuousSpace) -> Self {
+        SpaceDescriptor::Box {
+            low: space.low.clone(),
+            high: space.high.clone(),
+            shape: vec![space.low.len()],
+        }
+    }
+}
+
+impl From<&BoxSpace> for SpaceDescriptor {
+    fn from(space: &BoxSpace) -> Self {
+        SpaceDescriptor::Box {
+            low: space.low.clone(),
+            high: space.high.clone(),
+            shape: vec![space.low.len()],
+        }
+    }
+}
+
+impl TryFrom<&SpaceDescriptor> for DiscreteSpace {
+    type Error = RLError;
+
+    fn try_from(descriptor: &SpaceDescriptor) -> crate::Result<Self> {
+        match descriptor {
+            SpaceDescriptor::Discrete { n } => Ok(DiscreteSpace::new(*n)),
+            other => Err(other.mismatch("discrete space")),
+        }
+    }
+}
+
+impl TryFrom<&SpaceDescriptor> for MultiDiscreteSpace {
+    type Error = RLError;
+
+    fn try_from(descriptor: &SpaceDescriptor) -> crate::Result<Self> {
+        match descriptor {
+            SpaceDescriptor::MultiDiscrete { nvec } => Ok(MultiDiscreteSpace::new(nvec.clone())),
+            other => Err(other.mismatch("multi-discrete space")),
+        }
+    }
+}
+
+impl TryFrom<&SpaceDescriptor> for BoxObservationSpace {
+    type Error = RLError;
+
+    fn try_from(descriptor: &SpaceDescriptor) -> crate::Result<Self> {
+        match descriptor {
+            SpaceDescriptor::Box { low, high, shape } => {
+                BoxObservationSpace::new(low.clone(), high.clone(), shape.clone())
+            }
+            other => Err(other.mismatch("box observation space")),
+        }
+    }
+}
+
+impl TryFrom<&SpaceDescriptor> for ContinuousSpace {
+    type Error = RLError;
+
+    fn try_from(descriptor: &SpaceDescriptor) -> crate::Result<Self> {
+        match descriptor {
+            SpaceDescriptor::Box { low, high, .. } => {
+                ContinuousSpace::new(low.clone(), high.clone())
+            }
+            other => Err(other.mismatch("continuous space")),
+        }
+    }
+}
+
+impl TryFrom<&SpaceDescriptor> for BoxSpace {
+    type Error = RLError;
+
+    fn try_from(descriptor: &SpaceDescriptor) -> crate::Result<Self> {
+        match descriptor {
+            SpaceDescriptor::Box { low, high, .. } => BoxSpace::new(low.clone(), high.clone()),
+            other => Err(other.mismatch("box state space")),
+        }
+    }
+}
+
+/// Serializable description of an environment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvSpec {
+    /// Registered name of the environment
+    pub id: String,
+    /// Observation space
+    pub observation_space: SpaceDescriptor,
+    /// Action space
+    pub action_space: SpaceDescriptor,
+    /// Episode step limit, if any
+    #[serde(default)]
+    pub max_episode_steps: Option<usize>,
+}
+
+impl EnvSpec {
+    /// Create a spec from its parts
+    #[must_use]
+    pub fn new(
+        id: impl Into<String>,
+        observation_space: SpaceDescriptor,
+        action_space: SpaceDescriptor,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            observation_space,
+            action_space,
+            max_episode_steps: None,
+        }
+    }
+
+    /// Set the episode step limit
+    #[must_use]
+    pub fn with_max_episode_steps(mut self, max_episode_steps: usize) -> Self {
+        self.max_episode_steps = Some(max_episode_steps);
+        self
+    }
+
+    /// Describe a live environment
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either of the environment's spaces has no
+    /// [`SpaceDescriptor`].
+    pub fn from_env<E: Environment + ?Sized>(
+        id: impl Into<String>,
+        env: &E,
+    ) -> crate::Result<Self> {
+        let id = id.into();
+        let describe = |space: &str, descriptor: Option<SpaceDescriptor>| {
+            descriptor.ok_or_else(|| {
+                RLError::Environment(format!("The {space} space of {id} can't be described"))
+            })
+        };
+        let observation_space = describe("observation", env.observation_space().descriptor())?;
+        let action_space = describe("action", env.action_space().descriptor())?;
+        Ok(Self::new(id, observation_space, action_space))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::ActionSpace;
+    use crate::observation::ObservationSpace;
+    use crate::state::StateSpace;
+
+    fn round_trip(descriptor: &SpaceDescriptor) -> SpaceDescriptor {
+        let json = serde_json::to_string(descriptor).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn test_space_descriptors_round_trip_through_json() {
+        let discrete = DiscreteSpace::new(3);
+        let json = serde_json::to_value(SpaceDescriptor::from(&discrete)).unwrap();
+        assert_eq!(json, serde_json::json!({"type": "discrete", "n": 3}));
+        let rebuilt = DiscreteSpace::try_from(&round_trip(&(&discrete).into())).unwrap();
+        assert_eq!(rebuilt.n, 3);
+        assert_eq!(rebuilt.spec(), discrete.spec());
+
+        let multi = MultiDiscreteSpace::new(vec![2, 5, 3]);
+        let rebuilt = MultiDiscreteSpace::try_from(&round_trip(&(&multi).into())).unwrap();
+        assert_eq!(rebuilt.nvec, multi.nvec);
+        assert_eq!(rebuilt.dim(), Some(3));
+
+        // Unbounded dimensions survive JSON
+        let inf = f64::INFINITY;
+        let observations = BoxObservationSpace::new(
+            vec![-2.4, -inf, -0.21, -inf],
+            vec![2.4, inf, 0.21, inf],
+            vec![4],
+        )
+        .unwrap();
+        let descriptor = SpaceDescriptor::from(&observations);
+        assert!(serde_json::to_string(&descriptor)
+            .unwrap()
+            .contains(r#""-inf""#));
+        let rebuilt = BoxObservationSpace::try_from(&round_trip(&descriptor)).unwrap();
+        assert_eq!(rebuilt.low, observations.low);
+        assert_eq!(rebuilt.high, observations.high);
+        assert_eq!(rebuilt.shape(), vec![4]);
+
+        let actions = ContinuousSpace::new(vec![-1.0, 0.0], vec![1.0, 0.5]).unwrap();
+        let rebuilt = ContinuousSpace::try_from(&round_trip(&(&actions).into())).unwrap();
+        assert_eq!((rebuilt.low, rebuilt.high), (actions.low, actions.high));
+
+        let states = BoxSpace::new(vec![0.0; 2], vec![1.0; 2]).unwrap();
+        let rebuilt = BoxSpace::try_from(&round_trip(&(&states).into())).unwrap();
+        assert_eq!(rebuilt.dim(), Some(2));
+
+        // Descriptors only rebuild their own kind, and bad boxes are rejected
+        let err = DiscreteSpace::try_from(&SpaceDescriptor::from(&multi)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Environment error: Cannot build a discrete space from a multi-discrete space descriptor"
+        );
+        let lopsided = SpaceDescriptor::Box {
+            low: vec![0.0; 3],
+            high: vec![1.0; 3],
+            shape: vec![2],
+        };
+        assert!(BoxObservationSpace::try_from(&lopsided).is_err());
+        assert!(serde_json::from_str::<SpaceDescriptor>(
+            r#"{"type": "box", "low": ["huge"], "high": [1.0], "shape": [1]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_env_spec_round_trips_through_json() {
+        let observations =
+            BoxObservationSpace::new(vec![-1.0; 4], vec![1.0; 4], vec![2, 2]).unwrap();
+        let spec = EnvSpec::new(
+            "Grid-v0",
+            (&observations).into(),
+            (&DiscreteSpace::new(4)).into(),
+        )
+        .with_max_episode_steps(200);
+
+        let json = serde_json::to_string(&spec).unwrap();
+        let restored: EnvSpec = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, spec);
+        assert_eq!(
+            BoxObservationSpace::try_from(&restored.observation_space)
+                .unwrap()
+                .shape(),
+            vec![2, 2]
+        );
+
+        let minimal: EnvSpec = serde_json::from_str(
+            r#"{"id": "Bandit-v0",
+                "observation_space": {"type": "box", "low": [0.0], "high": [0.0], "shape": [1]},
+                "action_space": {"type": "multi_discrete", "nvec": [10]}}"#,
+        )
+        .unwrap();
+        assert_eq!(minimal.max_episode_steps, None);
+        assert_eq!(
+            MultiDiscreteSpace::try_from(&minimal.action_space)
+                .unwrap()
+                .nvec,
+            vec![10]
+        );
+    }
+}
@@ -1,8 +1,10 @@
 //! Trajectory and experience storage
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::Write;
 
-use crate::{Action, Observation, Reward, State};
+use crate::{Action, Observation, Reward, State, StepInfo};
 
 /// Single transition in a trajectory
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -51,10 +53,13 @@ impl<O, A, S> From<Transition<O, A, S>> for Experience<O, A, S> {
 }
 
 /// Complete trajectory of an episode
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trajectory<O, A, S> {
     /// Sequence of transitions
     pub transitions: Vec<Transition<O, A, S>>,
+    /// Step info reported alongside each transition
+    #[serde(default)]
+    pub infos: Vec<StepInfo>,
     /// Total reward
     pub total_reward: f64,
     /// Episode ID
@@ -66,6 +71,7 @@ impl<O, A, S> Trajectory<O, A, S> {
     pub fn new(episode_id: String) -> Self {
         Self {
             transitions: Vec::new(),
+            infos: Vec::new(),
             total_reward: 0.0,
             episode_id,
         }
@@ -73,8 +79,16 @@ impl<O, A, S> Trajectory<O, A, S> {
     
     /// Add a transition to the trajectory
     pub fn push(&mut self, transition: Transition<O, A, S>) {
+        self.push_with_info(transition, StepInfo::default());
+    }
+
+    /// Add a transition together with the info the environment reported for it
+    pub fn push_with_info(&mut self, transition: Transition<O, A, S>, info: StepInfo) {
+        // Trajectories deserialized without infos get empty ones backfilled
+        self.infos.resize_with(self.transitions.len(), StepInfo::default);
         self.total_reward += transition.reward.0;
         self.transitions.push(transition);
+        self.infos.push(info);
     }
     
     /// Get the length of the trajectory
@@ -89,6 +103,53 @@ impl<O, A, S> Trajectory<O, A, S> {
         self.transitions.is_empty()
     }
     
+    /// Write one CSV row per step: `step`, `action`, `reward`,
+    /// `cumulative_reward`, then one column per info key.
+    ///
+    /// Info columns are the sorted union of keys across all steps, so the
+    /// header is the same however the keys vary; steps missing a key leave it
+    /// empty. Actions are written as compact JSON (`3`, `[0.5,-1.0]`), string
+    /// info values as-is and other info values as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an action can't be serialized or writing fails.
+    pub fn to_csv<W: Write>(&self, mut writer: W) -> crate::Result<()>
+    where
+        A: Serialize,
+    {
+        let keys: BTreeSet<&str> = self
+            .infos
+            .iter()
+            .flat_map(|info| info.fields.keys().map(String::as_str))
+            .collect();
+
+        let mut header = vec!["step", "action", "reward", "cumulative_reward"];
+        header.extend(keys.iter().copied());
+        write_csv_row(&mut writer, header.iter().copied())?;
+
+        let mut cumulative = 0.0;
+        for (step, transition) in self.transitions.iter().enumerate() {
+            cumulative += transition.reward.0;
+            let mut row = vec![
+                step.to_string(),
+                serde_json::to_string(&transition.action)?,
+                transition.reward.0.to_string(),
+                cumulative.to_string(),
+            ];
+            let info = self.infos.get(step);
+            row.extend(keys.iter().map(|key| match info.and_then(|i| i.fields.get(*key)) {
+                None | Some(serde_json::Value::Null) => String::new(),
+                Some(serde_json::Value::String(s)) => s.clone(),
+                Some(value) => value.to_string(),
+            }));
+            write_csv_row(&mut writer, row.iter().map(String::as_str))?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+
     /// Compute returns (cumulative discounted rewards)
     #[must_use]
     pub fn returns(&self, gamma: f64) -> Vec<f64> {
@@ -130,6 +191,28 @@ impl<O, A, S> Trajectory<O, A, S> {
     }
 }
 
+/// Write `fields` as one CSV record, quoting those that need it
+fn write_csv_row<'a, W: Write>(
+    writer: &mut W,
+    fields: impl Iterator<Item = &'a str>,
+) -> std::io::Result<()> {
+    let mut line = String::new();
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            line.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            line.push('"');
+            line.push_str(&field.replace('"', "\"\""));
+            line.push('"');
+        } else {
+            line.push_str(field);
+        }
+    }
+    line.push('\n');
+    writer.write_all(line.as_bytes())
+}
+
 /// Batch of trajectories
 #[derive(Debug, Clone)]
 pub struct TrajectoryBatch<O, A, S> {
@@ -173,4 +256,83 @@ impl<O, A, S> Default for TrajectoryBatch<O, A, S> {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DiscreteAction;
+
+    /// Minimal RFC 4180 reader for checking `to_csv` output
+    fn parse_csv(text: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, quoted) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                ('"', _) => quoted = !quoted,
+                (',', false) => row.push(std::mem::take(&mut field)),
+                ('\n', false) => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                (c, _) => field.push(c),
+            }
+        }
+        rows
+    }
+
+    fn step(action: usize, reward: f64) -> Transition<Vec<f64>, DiscreteAction, ()> {
+        Transition {
+            observation: vec![0.0],
+            action: DiscreteAction(action),
+            reward: Reward(reward),
+            next_observation: vec![1.0],
+            done: false,
+            state: None,
+            next_state: None,
+        }
+    }
+
+    #[test]
+    fn test_csv_round_trips_rewards_and_actions() {
+        let mut trajectory = Trajectory::new("ep-7".to_string());
+        trajectory.push_with_info(
+            step(1, 0.5),
+            StepInfo::default().with("command", "ls -l, then cd"),
+        );
+        trajectory.push(step(0, -1.25));
+        trajectory.push_with_info(
+            step(2, 2.0),
+            StepInfo::default().with("TimeLimit.truncated", true).with("command", "say \"hi\""),
+        );
+
+        let mut out = Vec::new();
+        trajectory.to_csv(&mut out).unwrap();
+        let rows = parse_csv(&String::from_utf8(out).unwrap());
+
+        assert_eq!(
+            rows[0],
+            ["step", "action", "reward", "cumulative_reward", "TimeLimit.truncated", "command"]
+        );
+        assert_eq!(rows.len(), 4);
+        assert!(rows.iter().all(|r| r.len() == rows[0].len()));
+
+        let actions: Vec<usize> = rows[1..].iter().map(|r| r[1].parse().unwrap()).collect();
+        let rewards: Vec<f64> = rows[1..].iter().map(|r| r[2].parse().unwrap()).collect();
+        let cumulative: Vec<f64> = rows[1..].iter().map(|r| r[3].parse().unwrap()).collect();
+        assert_eq!(actions, [1, 0, 2]);
+        assert_eq!(rewards, [0.5, -1.25, 2.0]);
+        assert_eq!(cumulative, [0.5, -0.75, 1.25]);
+
+        assert_eq!(rows[1][5], "ls -l, then cd");
+        assert_eq!(rows[2][4..], ["", ""]);
+        assert_eq!(rows[3][4], "true");
+        assert_eq!(rows[3][5], "say \"hi\"");
+    }
+}
@@ -1,6 +1,7 @@
 //! Value functions for RL algorithms
 
 use async_trait::async_trait;
+use std::marker::PhantomData;
 
 use crate::{Action, Observation, State};
 
@@ -82,20 +83,23 @@ pub struct NeuralQFunction<N> {
     pub use_gpu: bool,
 }
 
-/// Tabular value function (for discrete state spaces)
-pub struct TabularValueFunction {
+/// Tabular value function (for discrete state spaces), keyed by the
+/// JSON encoding of states of type `S`
+pub struct TabularValueFunction<S> {
     /// Value table
     pub values: std::collections::HashMap<String, f64>,
     /// Default value for unseen states
     pub default_value: f64,
+    _state: PhantomData<fn(&S)>,
 }
 
-impl TabularValueFunction {
+impl<S> TabularValueFunction<S> {
     /// Create a new tabular value function
     pub fn new(default_value: f64) -> Self {
         Self {
             values: std::collections::HashMap::new(),
             default_value,
+            _state: PhantomData,
         }
     }
     
@@ -106,7 +110,7 @@ impl TabularValueFunction {
 }
 
 #[async_trait]
-impl<S> ValueFunction for TabularValueFunction
+impl<S> ValueFunction for TabularValueFunction<S>
 where
     S: State + serde::Serialize,
 {
@@ -118,23 +122,26 @@ where
     }
 }
 
-/// Tabular Q-function (for discrete state-action spaces)
-pub struct TabularQFunction {
+/// Tabular Q-function (for discrete state-action spaces), keyed by the
+/// JSON encoding of observations of type `O`
+pub struct TabularQFunction<O> {
     /// Q-value table
     pub q_values: std::collections::HashMap<String, Vec<f64>>,
     /// Number of actions
     pub num_actions: usize,
     /// Default Q-value
     pub default_q_value: f64,
+    _observation: PhantomData<fn(&O)>,
 }
 
-impl TabularQFunction {
+impl<O> TabularQFunction<O> {
     /// Create a new tabular Q-function
     pub fn new(num_actions: usize, default_q_value: f64) -> Self {
         Self {
             q_values: std::collections::HashMap::new(),
             num_actions,
             default_q_value,
+            _observation: PhantomData,
         }
     }
     
@@ -150,7 +157,7 @@ impl TabularQFunction {
 }
 
 #[async_trait]
-impl<O> ActionValueFunction for TabularQFunction
+impl<O> ActionValueFunction for TabularQFunction<O>
 where
     O: Observation + serde::Serialize,
 {
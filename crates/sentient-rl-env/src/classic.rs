@@ -1,7 +1,8 @@
 //! Classic control environments
 
 use async_trait::async_trait;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 use sentient_rl_core::{
     Environment, EnvironmentConfig, Step, StepInfo,
@@ -10,6 +11,10 @@ use sentient_rl_core::{
     BoxObservationSpace, RLError, Result,
 };
 
+fn seeded_rng(config: &EnvironmentConfig) -> StdRng {
+    config.seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64)
+}
+
 /// CartPole environment
 pub struct CartPoleEnv {
     /// Current state
@@ -18,6 +23,8 @@ pub struct CartPoleEnv {
     config: CartPoleConfig,
     /// Step count
     steps: usize,
+    /// Draws initial states; seeded from `EnvironmentConfig::seed` when set
+    rng: StdRng,
 }
 
 #[derive(Debug, Clone)]
@@ -67,6 +74,7 @@ impl CartPoleEnv {
             },
             config: CartPoleConfig::default(),
             steps: 0,
+            rng: seeded_rng(&config),
         })
     }
     
@@ -111,7 +119,7 @@ impl Environment for CartPoleEnv {
     }
     
     async fn reset(&mut self) -> Result<(Self::Observation, StepInfo)> {
-        let mut rng = rand::thread_rng();
+        let rng = &mut self.rng;
         self.state = CartPoleState {
             x: rng.gen_range(-0.05..0.05),
             x_dot: rng.gen_range(-0.05..0.05),
@@ -176,6 +184,8 @@ pub struct MountainCarEnv {
     config: MountainCarConfig,
     /// Step count
     steps: usize,
+    /// Draws initial states; seeded from `EnvironmentConfig::seed` when set
+    rng: StdRng,
 }
 
 #[derive(Debug, Clone)]
@@ -221,6 +231,7 @@ impl MountainCarEnv {
             },
             config: MountainCarConfig::default(),
             steps: 0,
+            rng: seeded_rng(&config),
         })
     }
 }
@@ -244,9 +255,8 @@ impl Environment for MountainCarEnv {
     }
     
     async fn reset(&mut self) -> Result<(Self::Observation, StepInfo)> {
-        let mut rng = rand::thread_rng();
         self.state = MountainCarState {
-            position: rng.gen_range(-0.6..-0.4),
+            position: self.rng.gen_range(-0.6..-0.4),
             velocity: 0.0,
         };
         self.steps = 0;
@@ -0,0 +1,247 @@
+//! Curriculum learning for goal-based environments
+//!
+//! [`Curriculum`] wraps a [`GoalTaskEnv`] and moves it through a ladder of
+//! difficulty levels. Once the rolling episode success rate clears the
+//! promotion threshold the next level is unlocked (more goal templates, or
+//! fewer steps to finish in); a sustained run of failures drops back a level.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use crate::sentient_envs::GoalTaskEnv;
+
+use sentient_rl_core::{
+    ActionSpace, DiscreteAction, Environment, ObservationSpace, Step, StepInfo, VectorObservation,
+    VectorState,
+};
+
+/// One rung of the curriculum
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurriculumLevel {
+    /// Number of goal templates in play
+    pub goal_templates: usize,
+    /// Maximum steps per episode
+    pub max_steps: usize,
+}
+
+/// Configuration for [`Curriculum`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurriculumConfig {
+    /// Levels from easiest to hardest
+    pub levels: Vec<CurriculumLevel>,
+    /// Number of recent episodes the success rate is computed over
+    pub window: usize,
+    /// Success rate at or above which the next level is unlocked
+    pub promote_threshold: f32,
+    /// Success rate at or below which the previous level is restored
+    pub demote_threshold: f32,
+    /// Fraction of an episode's goals that must succeed for it to count as a success
+    pub episode_success_threshold: f32,
+}
+
+impl Default for CurriculumConfig {
+    fn default() -> Self {
+        // Matches the five templates and 50 steps of `GoalTaskEnvConfig::default`
+        Self {
+            levels: vec![
+                CurriculumLevel { goal_templates: 2, max_steps: 50 },
+                CurriculumLevel { goal_templates: 3, max_steps: 50 },
+                CurriculumLevel { goal_templates: 5, max_steps: 50 },
+                CurriculumLevel { goal_templates: 5, max_steps: 30 },
+            ],
+            window: 20,
+            promote_threshold: 0.8,
+            demote_threshold: 0.3,
+            episode_success_threshold: 0.5,
+        }
+    }
+}
+
+/// Wrapper that scales [`GoalTaskEnv`] difficulty with the agent's success rate
+///
+/// Each step's info carries the current level under `"Curriculum.level"`.
+pub struct Curriculum {
+    env: GoalTaskEnv,
+    config: CurriculumConfig,
+    level: usize,
+    outcomes: VecDeque<bool>,
+    episode_goals: usize,
+    episode_successes: usize,
+}
+
+impl Curriculum {
+    /// Wrap `env`, starting at the easiest level
+    pub fn new(env: GoalTaskEnv, config: CurriculumConfig) -> Result<Self> {
+        if config.levels.is_empty() {
+            anyhow::bail!("Curriculum needs at least one level");
+        }
+        if config.window == 0 {
+            anyhow::bail!("Curriculum window must be positive");
+        }
+
+        let mut curriculum = Self {
+            env,
+            outcomes: VecDeque::with_capacity(config.window),
+            config,
+            level: 0,
+            episode_goals: 0,
+            episode_successes: 0,
+        };
+        curriculum.apply_level();
+        Ok(curriculum)
+    }
+
+    /// Index of the current level
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Settings of the current level
+    pub fn current_level(&self) -> CurriculumLevel {
+        self.config.levels[self.level]
+    }
+
+    /// Success rate over the episodes seen since the last level change
+    #[allow(clippy::cast_precision_loss)]
+    pub fn success_rate(&self) -> Option<f32> {
+        if self.outcomes.is_empty() {
+            return None;
+        }
+        let successes = self.outcomes.iter().filter(|&&success| success).count();
+        Some(successes as f32 / self.outcomes.len() as f32)
+    }
+
+    /// Wrapped environment
+    pub fn inner(&self) -> &GoalTaskEnv {
+        &self.env
+    }
+
+    /// Record a finished episode and move between levels if the rolling
+    /// success rate crosses a threshold
+    pub fn record_episode(&mut self, success: bool) {
+        if self.outcomes.len() >= self.config.window {
+            self.outcomes.pop_front();
+        }
+        self.outcomes.push_back(success);
+
+        // Only judge a level on a full window of its own episodes
+        if self.outcomes.len() < self.config.window {
+            return;
+        }
+
+        let rate = self.success_rate().unwrap_or(0.0);
+        let previous = self.level;
+        if rate >= self.config.promote_threshold && self.level + 1 < self.config.levels.len() {
+            self.level += 1;
+        } else if rate <= self.config.demote_threshold && self.level > 0 {
+            self.level -= 1;
+        }
+
+        if self.level != previous {
+            tracing::info!(from = previous, to = self.level, success_rate = rate, "Curriculum level changed");
+            self.outcomes.clear();
+            self.apply_level();
+        }
+    }
+
+    fn apply_level(&mut self) {
+        let level = self.current_level();
+        self.env.set_difficulty(level.goal_templates, level.max_steps);
+    }
+}
+
+#[async_trait]
+impl Environment for Curriculum {
+    type Observation = VectorObservation;
+    type Action = DiscreteAction;
+    type State = VectorState;
+
+    fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+        self.env.observation_space()
+    }
+
+    fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+        self.env.action_space()
+    }
+
+    async fn reset(&mut self) -> sentient_rl_core::Result<(Self::Observation, StepInfo)> {
+        self.episode_goals = 0;
+        self.episode_successes = 0;
+        self.env.reset().await
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    async fn step(&mut self, action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
+        let mut step = self.env.step(action).await?;
+
+        self.episode_goals += 1;
+        if self.env.last_goal_succeeded().await == Some(true) {
+            self.episode_successes += 1;
+        }
+
+        if step.done {
+            let rate = self.episode_successes as f32 / self.episode_goals as f32;
+            self.record_episode(rate >= self.config.episode_success_threshold);
+        }
+
+        step.info.insert("Curriculum.level", self.level);
+        Ok(step)
+    }
+
+    async fn close(&mut self) -> sentient_rl_core::Result<()> {
+        self.env.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sentient_envs::GoalTaskEnvConfig;
+
+    fn curriculum() -> Curriculum {
+        let config = CurriculumConfig {
+            window: 5,
+            ..CurriculumConfig::default()
+        };
+        Curriculum::new(GoalTaskEnv::new(GoalTaskEnvConfig::default()).unwrap(), config).unwrap()
+    }
+
+    #[test]
+    fn test_level_follows_success_rate() {
+        let mut curriculum = curriculum();
+        assert_eq!(curriculum.level(), 0);
+        assert_eq!(curriculum.inner().active_templates(), 2);
+
+        // A high-success streak unlocks each level in turn
+        for _ in 0..5 {
+            curriculum.record_episode(true);
+        }
+        assert_eq!(curriculum.level(), 1);
+        assert_eq!(curriculum.inner().active_templates(), 3);
+        for _ in 0..10 {
+            curriculum.record_episode(true);
+        }
+        assert_eq!(curriculum.level(), 3);
+        assert_eq!(curriculum.inner().max_steps(), 30);
+
+        // Staying on top does not overflow the ladder
+        for _ in 0..5 {
+            curriculum.record_episode(true);
+        }
+        assert_eq!(curriculum.level(), 3);
+
+        // Mixed results hold the level; sustained failure reverts it
+        for success in [true, false, true, false, true] {
+            curriculum.record_episode(success);
+        }
+        assert_eq!(curriculum.level(), 3);
+        for _ in 0..5 {
+            curriculum.record_episode(false);
+        }
+        assert_eq!(curriculum.level(), 2);
+        assert_eq!(curriculum.inner().max_steps(), 50);
+        assert_eq!(curriculum.inner().active_templates(), 5);
+    }
+}
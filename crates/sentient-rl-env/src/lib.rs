@@ -12,19 +12,33 @@
 #![allow(clippy::module_name_repetitions)]
 
 pub mod classic;
+pub mod curriculum;
 pub mod llm;
 pub mod registry;
+pub mod reward;
 pub mod sentient_envs;
+pub mod system_observation;
 pub mod wrappers;
 
 // Re-export environments
 pub use classic::{CartPoleEnv, MountainCarEnv};
-pub use llm::{LLMEnv, LLMEnvConfig};
-pub use sentient_envs::{JSONLEnv, JSONLEnvConfig, GoalTaskEnv, GoalTaskEnvConfig};
-pub use registry::{EnvRegistry, register_env, make_env};
+pub use curriculum::{Curriculum, CurriculumConfig, CurriculumLevel};
+pub use llm::{LLMEnv, LLMEnvConfig, PROMPT_TEMPLATES};
+pub use sentient_envs::{
+    JSONLEnv, JSONLEnvConfig, GoalTaskEnv, GoalTaskEnvConfig, SuccessCriteria, SuccessMatcher, TraceHeader,
+    TRACE_SCHEMA_VERSION, TraceEvaluation, TracePolicy,
+};
+pub use system_observation::{
+    SystemObservation, SystemObservationEncoder, ObservationSpec, HostMetrics, HostProbe,
+    SysinfoProbe, GoalOutcome, OBSERVATION_SPEC_VERSION,
+};
+pub use registry::{EnvRegistry, register_env, make_env, list_envs, env_spec};
+pub use reward::{
+    SystemImprovementReward, SystemSnapshot, MetricsProbe, ProcProbe, ImprovementWeights,
+};
 pub use wrappers::{
     RewardWrapper, ObservationWrapper, ActionWrapper,
-    TimeLimit, FrameStack, Normalize,
+    TimeLimit, ClipReward, FrameStack, Normalize, Cancellable, run_cancellable,
 };
 
 // Re-export core types
@@ -7,11 +7,36 @@ use sentient_rl_core::{
     Environment, EnvironmentConfig, Step, StepInfo,
     VectorObservation, BoxObservationSpace, DiscreteSpace,
     DiscreteAction, VectorState, Reward, Terminal,
-    Result,
+    RLError, Result,
 };
 
-/// Configuration for LLM environments
+/// User turns the agent chooses between; action `i` sends
+/// `PROMPT_TEMPLATES[i]`
+pub const PROMPT_TEMPLATES: [&str; 10] = [
+    "Continue the conversation naturally.",
+    "Ask a clarifying question.",
+    "Provide more details.",
+    "Summarize the conversation so far.",
+    "Change the topic slightly.",
+    "Express agreement.",
+    "Express disagreement politely.",
+    "Ask for examples.",
+    "Provide an example.",
+    "Conclude the conversation.",
+];
+
+/// Index of the template that ends the conversation
+const CONCLUDE_ACTION: usize = 9;
+
+/// Scalar features at the front of an observation
+const CONTEXT_FEATURES: usize = 3;
+
+/// Size of the hashed bag-of-words embedding of the conversation
+const EMBEDDING_DIM: usize = 32;
+
+/// Configuration for LLM environments; unset fields take their defaults
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct LLMEnvConfig {
     /// Maximum conversation length
     pub max_turns: usize,
@@ -83,19 +108,51 @@ impl LLMEnv {
         self.task = task;
     }
     
-    /// Get current state representation
-    fn get_state_vector(&self) -> Vec<f64> {
-        // Simplified state representation
-        // In practice, this would use embeddings or other features
-        vec![
-            self.turn_count as f64,
-            self.conversation.len() as f64,
-            self.config.temperature,
-            0.0, // Placeholder for more features
-        ]
+    /// Length of every observation: turn progress, sampling temperature and
+    /// last action, followed by an embedding of the conversation
+    pub const OBSERVATION_DIM: usize = CONTEXT_FEATURES + EMBEDDING_DIM;
+    
+    /// Get current state representation, every feature in `[0, 1]`
+    #[allow(clippy::cast_precision_loss)]
+    fn get_state_vector(&self, last_action: Option<usize>) -> Vec<f64> {
+        let mut state = Vec::with_capacity(Self::OBSERVATION_DIM);
+        state.push((self.turn_count as f64 / self.config.max_turns.max(1) as f64).min(1.0));
+        state.push((self.config.temperature / 2.0).clamp(0.0, 1.0));
+        state.push(last_action.map_or(0.0, |a| (a + 1) as f64 / PROMPT_TEMPLATES.len() as f64));
+        state.extend(self.embed_conversation());
+        state
+    }
+    
+    /// Hashed bag-of-words embedding of the conversation, L2-normalized so
+    /// it doesn't grow with the conversation's length
+    #[allow(clippy::cast_possible_truncation)]
+    fn embed_conversation(&self) -> Vec<f64> {
+        let mut embedding = vec![0.0; EMBEDDING_DIM];
+        let words = self.conversation.iter().flat_map(|m| m.content.split_whitespace());
+        for word in words {
+            let word = word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+            if !word.is_empty() {
+                embedding[(fnv1a(word.as_bytes()) % EMBEDDING_DIM as u64) as usize] += 1.0;
+            }
+        }
+        
+        let norm = embedding.iter().map(|x| x * x).sum::<f64>().sqrt();
+        if norm > 0.0 {
+            for x in &mut embedding {
+                *x /= norm;
+            }
+        }
+        embedding
     }
 }
 
+/// FNV-1a, so the embedding is the same across builds and platforms
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
 #[async_trait]
 impl Environment for LLMEnv {
     type Observation = VectorObservation;
@@ -103,20 +160,16 @@ impl Environment for LLMEnv {
     type State = VectorState;
     
     fn observation_space(&self) -> Box<dyn sentient_rl_core::ObservationSpace<Observation = Self::Observation>> {
-        // Simplified observation space
-        // In practice, this would be much higher dimensional
         Box::new(BoxObservationSpace::new(
-            vec![0.0, 0.0, 0.0, -1.0],
-            vec![self.config.max_turns as f64, 100.0, 1.0, 1.0],
-            vec![4],
+            vec![0.0; Self::OBSERVATION_DIM],
+            vec![1.0; Self::OBSERVATION_DIM],
+            vec![Self::OBSERVATION_DIM],
         ).unwrap())
     }
     
     fn action_space(&self) -> Box<dyn sentient_rl_core::ActionSpace<Action = Self::Action>> {
-        // Simplified action space
-        // In practice, this could be continuous (for generating text)
-        // or discrete with a large vocabulary
-        Box::new(DiscreteSpace::new(10)) // 10 different action types
+        // One action per prompt template
+        Box::new(DiscreteSpace::new(PROMPT_TEMPLATES.len()))
     }
     
     async fn reset(&mut self) -> Result<(Self::Observation, StepInfo)> {
@@ -131,7 +184,7 @@ impl Environment for LLMEnv {
         
         Ok((
             VectorObservation {
-                data: self.get_state_vector(),
+                data: self.get_state_vector(None),
             },
             StepInfo::default(),
         ))
@@ -139,19 +192,9 @@ impl Environment for LLMEnv {
     
     async fn step(&mut self, action: Self::Action) -> Result<Step<Self::Observation, Self::State>> {
         // Map action to prompt/response strategy
-        let user_message = match action.0 {
-            0 => "Continue the conversation naturally.",
-            1 => "Ask a clarifying question.",
-            2 => "Provide more details.",
-            3 => "Summarize the conversation so far.",
-            4 => "Change the topic slightly.",
-            5 => "Express agreement.",
-            6 => "Express disagreement politely.",
-            7 => "Ask for examples.",
-            8 => "Provide an example.",
-            9 => "Conclude the conversation.",
-            _ => "Continue.",
-        };
+        let user_message = *PROMPT_TEMPLATES
+            .get(action.0)
+            .ok_or_else(|| RLError::InvalidAction(format!("Invalid action: {}", action.0)))?;
         
         self.conversation.push(Message {
             role: "user".to_string(),
@@ -171,7 +214,7 @@ impl Environment for LLMEnv {
         let reward = match &self.config.reward_type[..] {
             "coherence" => {
                 // Reward for maintaining conversation
-                if action.0 == 9 && self.turn_count > 5 {
+                if action.0 == CONCLUDE_ACTION && self.turn_count > 5 {
                     10.0 // Bonus for appropriate conclusion
                 } else if self.turn_count < self.config.max_turns {
                     1.0
@@ -186,18 +229,19 @@ impl Environment for LLMEnv {
             _ => 0.0,
         };
         
-        let done = self.turn_count >= self.config.max_turns || action.0 == 9;
+        let done = self.turn_count >= self.config.max_turns || action.0 == CONCLUDE_ACTION;
+        let data = self.get_state_vector(Some(action.0));
         
         Ok(Step {
             observation: VectorObservation {
-                data: self.get_state_vector(),
+                data: data.clone(),
             },
             reward: Reward(reward),
             done,
             truncated: false,
             info: StepInfo::default(),
             state: Some(VectorState {
-                data: self.get_state_vector(),
+                data,
                 terminal: if done { Terminal::Yes } else { Terminal::No },
             }),
         })
@@ -209,4 +253,55 @@ impl Environment for LLMEnv {
 // - PromptOptimizationEnv: Learn to optimize prompts
 // - AdversarialLLMEnv: Learn to find LLM vulnerabilities
 // - MultiAgentLLMEnv: Multiple agents interacting through LLMs
-// - RetrievalAugmentedEnv: Learn to use RAG effectively
\ No newline at end of file
+// - RetrievalAugmentedEnv: Learn to use RAG effectively
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentient_rl_core::SpaceDescriptor;
+
+    #[tokio::test]
+    async fn test_declared_spaces_match_reset_and_step() {
+        let config = EnvironmentConfig {
+            params: serde_json::json!({ "max_turns": 50, "temperature": 3.5 })
+                .as_object()
+                .unwrap()
+                .clone(),
+            ..EnvironmentConfig::default()
+        };
+        let mut env = LLMEnv::new(config).unwrap();
+        let observation_space = env.observation_space();
+        let action_space = env.action_space();
+        assert_eq!(observation_space.shape(), vec![LLMEnv::OBSERVATION_DIM]);
+        assert_eq!(
+            action_space.descriptor(),
+            Some(SpaceDescriptor::Discrete { n: PROMPT_TEMPLATES.len() })
+        );
+
+        let (obs, _) = env.reset().await.unwrap();
+        assert!(observation_space.contains(&obs), "{obs:?}");
+
+        // A long conversation stays inside the box, and the embedding moves
+        let mut previous = obs;
+        for turn in 0..49 {
+            let action = DiscreteAction(turn % (PROMPT_TEMPLATES.len() - 1));
+            assert!(action_space.contains(&action));
+            let step = env.step(action).await.unwrap();
+            assert!(observation_space.contains(&step.observation), "{:?}", step.observation);
+            assert_eq!(step.state.unwrap().data, step.observation.data);
+            assert!(!step.done);
+            assert_ne!(step.observation, previous);
+            previous = step.observation;
+        }
+        let step = env.step(DiscreteAction(CONCLUDE_ACTION)).await.unwrap();
+        assert!(observation_space.contains(&step.observation));
+        assert!(step.done);
+
+        // Actions outside the declared space are rejected
+        let invalid = DiscreteAction(PROMPT_TEMPLATES.len());
+        assert!(!action_space.contains(&invalid));
+        env.reset().await.unwrap();
+        let err = env.step(invalid).await.unwrap_err();
+        assert!(matches!(err, RLError::InvalidAction(_)), "{err}");
+    }
+}
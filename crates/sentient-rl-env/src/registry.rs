@@ -3,88 +3,151 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
-use sentient_rl_core::{Environment, EnvironmentConfig};
+use sentient_rl_core::{
+    DiscreteAction, EnvSpec, Environment, EnvironmentConfig, VectorObservation, VectorState,
+};
 
-type EnvConstructor = Box<dyn Fn(EnvironmentConfig) -> sentient_rl_core::Result<Box<dyn Environment<
-    Observation = Box<dyn sentient_rl_core::Observation>,
-    Action = Box<dyn sentient_rl_core::Action>,
-    State = Box<dyn sentient_rl_core::State>,
->>> + Send + Sync>;
+use crate::classic::{CartPoleEnv, MountainCarEnv};
+use crate::llm::LLMEnv;
+use crate::wrappers::ClipReward;
+
+/// Environment built by the registry: vector observations in, one discrete
+/// action out
+pub type BoxedEnv = Box<dyn Environment<
+    Observation = VectorObservation,
+    Action = DiscreteAction,
+    State = VectorState,
+>>;
+
+type EnvConstructor = Box<dyn Fn(EnvironmentConfig) -> sentient_rl_core::Result<BoxedEnv> + Send + Sync>;
 
 lazy_static::lazy_static! {
     static ref REGISTRY: Arc<Mutex<EnvRegistry>> = Arc::new(Mutex::new(EnvRegistry::new()));
 }
 
-/// Global environment registry
+/// Global environment registry, starting out with the built-in
+/// environments: `cartpole`, `mountaincar` and `llm`
 pub struct EnvRegistry {
     /// Registered environments
     envs: HashMap<String, EnvConstructor>,
 }
 
 impl EnvRegistry {
-    /// Create a new registry
+    /// Create a new registry holding the built-in environments
     fn new() -> Self {
-        Self {
+        let mut registry = Self {
             envs: HashMap::new(),
-        }
+        };
+        registry.register("cartpole", |config| Ok(Box::new(CartPoleEnv::new(config)?) as BoxedEnv));
+        registry.register("mountaincar", |config| Ok(Box::new(MountainCarEnv::new(config)?) as BoxedEnv));
+        registry.register("llm", |config| Ok(Box::new(LLMEnv::new(config)?) as BoxedEnv));
+        registry
     }
     
     /// Register an environment
     pub fn register<F>(&mut self, name: impl Into<String>, constructor: F)
     where
-        F: Fn(EnvironmentConfig) -> sentient_rl_core::Result<Box<dyn Environment<
-            Observation = Box<dyn sentient_rl_core::Observation>,
-            Action = Box<dyn sentient_rl_core::Action>,
-            State = Box<dyn sentient_rl_core::State>,
-        >>> + Send + Sync + 'static,
+        F: Fn(EnvironmentConfig) -> sentient_rl_core::Result<BoxedEnv> + Send + Sync + 'static,
     {
         self.envs.insert(name.into(), Box::new(constructor));
     }
     
-    /// Create an environment by name
-    pub fn make(&self, name: &str, config: EnvironmentConfig) -> sentient_rl_core::Result<Box<dyn Environment<
-        Observation = Box<dyn sentient_rl_core::Observation>,
-        Action = Box<dyn sentient_rl_core::Action>,
-        State = Box<dyn sentient_rl_core::State>,
-    >>> {
-        self.envs
+    /// Create an environment by name, clamping its rewards to
+    /// `config.reward_clip` if set
+    pub fn make(&self, name: &str, config: EnvironmentConfig) -> sentient_rl_core::Result<BoxedEnv> {
+        let constructor = self
+            .envs
             .get(name)
-            .ok_or_else(|| sentient_rl_core::RLError::Environment(format!("Unknown environment: {}", name)))
-            .and_then(|constructor| constructor(config))
+            .ok_or_else(|| sentient_rl_core::RLError::Environment(format!("Unknown environment: {}", name)))?;
+        match config.reward_clip {
+            Some(bounds) => Ok(Box::new(ClipReward::new(constructor(config)?, bounds)?)),
+            None => constructor(config),
+        }
     }
     
-    /// List registered environments
+    /// Names of the registered environments, sorted
     pub fn list(&self) -> Vec<String> {
-        self.envs.keys().cloned().collect()
+        let mut names: Vec<String> = self.envs.keys().cloned().collect();
+        names.sort();
+        names
+    }
+    
+    /// Describe a registered environment, built with the default config
+    pub fn spec(&self, name: &str) -> sentient_rl_core::Result<EnvSpec> {
+        let env = self.make(name, EnvironmentConfig::default())?;
+        EnvSpec::from_env(name, env.as_ref())
     }
 }
 
 /// Register an environment globally
 pub fn register_env<F>(name: impl Into<String>, constructor: F)
 where
-    F: Fn(EnvironmentConfig) -> sentient_rl_core::Result<Box<dyn Environment<
-        Observation = Box<dyn sentient_rl_core::Observation>,
-        Action = Box<dyn sentient_rl_core::Action>,
-        State = Box<dyn sentient_rl_core::State>,
-    >>> + Send + Sync + 'static,
+    F: Fn(EnvironmentConfig) -> sentient_rl_core::Result<BoxedEnv> + Send + Sync + 'static,
 {
     REGISTRY.lock().unwrap().register(name, constructor);
 }
 
 /// Create an environment by name
-pub fn make_env(name: &str, config: EnvironmentConfig) -> sentient_rl_core::Result<Box<dyn Environment<
-    Observation = Box<dyn sentient_rl_core::Observation>,
-    Action = Box<dyn sentient_rl_core::Action>,
-    State = Box<dyn sentient_rl_core::State>,
->>> {
+pub fn make_env(name: &str, config: EnvironmentConfig) -> sentient_rl_core::Result<BoxedEnv> {
     REGISTRY.lock().unwrap().make(name, config)
 }
 
-/// List all registered environments
+/// Names of all registered environments, sorted
 pub fn list_envs() -> Vec<String> {
     REGISTRY.lock().unwrap().list()
 }
 
+/// Describe a globally registered environment
+pub fn env_spec(name: &str) -> sentient_rl_core::Result<EnvSpec> {
+    REGISTRY.lock().unwrap().spec(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentient_rl_core::SpaceSignature;
+
+    #[tokio::test]
+    async fn test_builtin_envs_are_registered() {
+        for name in ["cartpole", "mountaincar", "llm"] {
+            assert!(list_envs().contains(&name.to_string()));
+            let mut env = make_env(name, EnvironmentConfig::default()).unwrap();
+            let (obs, _) = env.reset().await.unwrap();
+            assert_eq!(obs.data.len(), env.observation_spec().dim);
+            assert_eq!(env_spec(name).unwrap().id, name);
+        }
+        assert!(make_env("no-such-env", EnvironmentConfig::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_make_applies_the_configured_reward_clip() {
+        let config = |reward_clip| EnvironmentConfig {
+            reward_clip,
+            ..EnvironmentConfig::default()
+        };
+
+        // CartPole pays 1 per step survived
+        let mut env = make_env("cartpole", config(Some((0.0, 0.25)))).unwrap();
+        env.reset().await.unwrap();
+        let step = env.step(DiscreteAction(0)).await.unwrap();
+        assert!((step.reward.0 - 0.25).abs() < 1e-9);
+        assert!(step.info.get("ClipReward.raw_reward").is_some());
+
+        let mut env = make_env("cartpole", config(None)).unwrap();
+        env.reset().await.unwrap();
+        assert!((env.step(DiscreteAction(0)).await.unwrap().reward.0 - 1.0).abs() < 1e-9);
+
+        assert!(make_env("cartpole", config(Some((1.0, -1.0)))).is_err());
+    }
+
+    #[test]
+    fn test_list_is_sorted() {
+        let mut registry = EnvRegistry::new();
+        registry.register("acrobot", |config| Ok(Box::new(CartPoleEnv::new(config)?) as BoxedEnv));
+        assert_eq!(registry.list(), vec!["acrobot", "cartpole", "llm", "mountaincar"]);
+    }
+}
+
 // Add lazy_static to dependencies
 const _: &str = r#"
 [dependencies]
@@ -0,0 +1,206 @@
+//! Rewards derived from measured system state
+//!
+//! [`SystemImprovementReward`] compares a snapshot of key system metrics taken
+//! before a goal runs with one taken afterwards, so agents are rewarded for
+//! goals that actually leave the system healthier rather than for commands
+//! that merely exit successfully.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Point-in-time view of the metrics a goal is expected to influence
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct SystemSnapshot {
+    /// Available memory in megabytes
+    pub free_memory_mb: f64,
+    /// CPU utilisation in percent (0-100)
+    pub cpu_percent: f64,
+    /// Errors per minute observed in system logs
+    pub error_rate: f64,
+}
+
+/// Source of [`SystemSnapshot`]s
+pub trait MetricsProbe: Send + Sync {
+    /// Take a snapshot of the current system state
+    fn snapshot(&self) -> SystemSnapshot;
+}
+
+/// Probe backed by `/proc`
+///
+/// Memory comes from `MemAvailable`, CPU from the one-minute load average
+/// relative to the number of cores. The error rate is supplied by the caller
+/// since it depends on which logs are being watched.
+#[derive(Clone, Default)]
+pub struct ProcProbe {
+    error_rate: Option<Arc<dyn Fn() -> f64 + Send + Sync>>,
+}
+
+impl ProcProbe {
+    /// Create a probe that reports a zero error rate
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `source` to report the current error rate
+    #[must_use]
+    pub fn with_error_rate(mut self, source: impl Fn() -> f64 + Send + Sync + 'static) -> Self {
+        self.error_rate = Some(Arc::new(source));
+        self
+    }
+
+    fn read_available_memory_mb() -> f64 {
+        std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|content| {
+                content
+                    .lines()
+                    .find(|l| l.starts_with("MemAvailable:"))
+                    .and_then(|l| l.split_whitespace().nth(1))
+                    .and_then(|kb| kb.parse::<f64>().ok())
+            })
+            .map_or(0.0, |kb| kb / 1024.0)
+    }
+
+    fn read_cpu_percent() -> f64 {
+        let load = std::fs::read_to_string("/proc/loadavg")
+            .ok()
+            .and_then(|content| content.split_whitespace().next().and_then(|v| v.parse::<f64>().ok()))
+            .unwrap_or(0.0);
+        #[allow(clippy::cast_precision_loss)]
+        let cores = std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get) as f64;
+        (load / cores * 100.0).min(100.0)
+    }
+}
+
+impl MetricsProbe for ProcProbe {
+    fn snapshot(&self) -> SystemSnapshot {
+        SystemSnapshot {
+            free_memory_mb: Self::read_available_memory_mb(),
+            cpu_percent: Self::read_cpu_percent(),
+            error_rate: self.error_rate.as_ref().map_or(0.0, |f| f()),
+        }
+    }
+}
+
+/// Relative importance of each metric
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ImprovementWeights {
+    /// Weight of the relative change in free memory
+    pub memory: f64,
+    /// Weight of the change in CPU utilisation
+    pub cpu: f64,
+    /// Weight of the change in error rate
+    pub error_rate: f64,
+}
+
+impl Default for ImprovementWeights {
+    fn default() -> Self {
+        Self {
+            memory: 1.0,
+            cpu: 1.0,
+            error_rate: 2.0,
+        }
+    }
+}
+
+/// Rewards measurable improvement between two system snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemImprovementReward {
+    /// Per-metric weights
+    pub weights: ImprovementWeights,
+    /// Multiplier applied to metrics that got worse
+    pub regression_penalty: f64,
+    /// Error-rate change (errors/min) that counts as a full unit of improvement
+    pub error_rate_scale: f64,
+    /// Rewards are clipped to `[-max_reward, max_reward]`
+    pub max_reward: f64,
+}
+
+impl Default for SystemImprovementReward {
+    fn default() -> Self {
+        Self {
+            weights: ImprovementWeights::default(),
+            regression_penalty: 1.5,
+            error_rate_scale: 10.0,
+            max_reward: 1.0,
+        }
+    }
+}
+
+impl SystemImprovementReward {
+    /// Per-metric improvement; positive means better, negative means worse
+    #[must_use]
+    pub fn deltas(&self, before: &SystemSnapshot, after: &SystemSnapshot) -> ImprovementWeights {
+        let memory = (after.free_memory_mb - before.free_memory_mb) / before.free_memory_mb.max(1.0);
+        let cpu = (before.cpu_percent - after.cpu_percent) / 100.0;
+        let error_rate = (before.error_rate - after.error_rate) / self.error_rate_scale.max(f64::EPSILON);
+        ImprovementWeights { memory, cpu, error_rate }
+    }
+
+    /// Reward for moving from `before` to `after`
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn reward(&self, before: &SystemSnapshot, after: &SystemSnapshot) -> f32 {
+        let deltas = self.deltas(before, after);
+        let penalize = |delta: f64| if delta < 0.0 { delta * self.regression_penalty } else { delta };
+
+        let total = self.weights.memory * penalize(deltas.memory)
+            + self.weights.cpu * penalize(deltas.cpu)
+            + self.weights.error_rate * penalize(deltas.error_rate);
+
+        total.clamp(-self.max_reward, self.max_reward) as f32
+    }
+
+    /// Reward for a goal executed between two snapshots, suitable for the
+    /// policy injector's feedback loop. Failed goals can still earn credit
+    /// for improvement but never a positive total.
+    #[must_use]
+    pub fn feedback_reward(&self, before: &SystemSnapshot, after: &SystemSnapshot, success: bool) -> f32 {
+        let reward = self.reward(before, after);
+        if success { reward } else { reward.min(0.0) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(free_memory_mb: f64, cpu_percent: f64, error_rate: f64) -> SystemSnapshot {
+        SystemSnapshot { free_memory_mb, cpu_percent, error_rate }
+    }
+
+    #[test]
+    fn test_reward_tracks_delta() {
+        let reward = SystemImprovementReward::default();
+        let baseline = snapshot(1000.0, 50.0, 4.0);
+
+        assert!(reward.reward(&baseline, &baseline).abs() < f32::EPSILON);
+
+        let small = reward.reward(&baseline, &snapshot(1050.0, 45.0, 4.0));
+        let large = reward.reward(&baseline, &snapshot(1200.0, 30.0, 1.0));
+        assert!(small > 0.0);
+        assert!(large > small, "bigger improvement earns more ({large} vs {small})");
+
+        let regression = reward.reward(&baseline, &snapshot(950.0, 55.0, 4.0));
+        assert!(regression < 0.0);
+        assert!(regression.abs() > small, "regressions are penalised harder than equal gains");
+    }
+
+    #[test]
+    fn test_reward_is_clipped() {
+        let reward = SystemImprovementReward::default();
+        let before = snapshot(100.0, 100.0, 50.0);
+        assert!((reward.reward(&before, &snapshot(10_000.0, 0.0, 0.0)) - 1.0).abs() < f32::EPSILON);
+        assert!((reward.reward(&snapshot(10_000.0, 0.0, 0.0), &before) + 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_failed_goal_never_rewarded() {
+        let reward = SystemImprovementReward::default();
+        let before = snapshot(1000.0, 50.0, 4.0);
+        let after = snapshot(1200.0, 30.0, 1.0);
+        assert!(reward.feedback_reward(&before, &after, true) > 0.0);
+        assert!(reward.feedback_reward(&before, &after, false).abs() < f32::EPSILON);
+    }
+}
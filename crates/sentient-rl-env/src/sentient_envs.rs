@@ -9,15 +9,22 @@ use chrono::{DateTime, Utc};
 use ndarray::Array1;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use tokio::sync::RwLock;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+use crate::reward::{MetricsProbe, SystemImprovementReward};
+use crate::system_observation::{
+    GoalOutcome, HostProbe, ObservationSpec, SysinfoProbe, SystemObservation, SystemObservationEncoder,
+};
+use crate::wrappers::run_cancellable;
 
 use sentient_rl_core::{
-    Environment, EnvironmentConfig, StepInfo, 
-    Observation, Action, Reward, Space, BoxSpace, DiscreteSpace,
+    ActionSpace, AgentMode, BoxObservationSpace, DiscreteAction, DiscreteSpace, Environment,
+    ObservationSpace, RLError, Reward, Step, StepInfo, VectorObservation, VectorState,
 };
 
 /// Configuration for JSONL environment
@@ -35,6 +42,18 @@ pub struct JSONLEnvConfig {
     pub reward_config: RewardConfig,
 }
 
+impl Default for JSONLEnvConfig {
+    fn default() -> Self {
+        Self {
+            trace_file: PathBuf::from("logs/rl_trace.jsonl"),
+            max_episode_length: 200,
+            observation_dim: ObservationSpec::default().len(),
+            action_dim: 10,
+            reward_config: RewardConfig::default(),
+        }
+    }
+}
+
 /// Reward shaping configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RewardConfig {
@@ -62,6 +81,105 @@ impl Default for RewardConfig {
     }
 }
 
+/// Network-ready observation as the environment hands it to agents
+fn to_observation(obs: &Array1<f32>) -> VectorObservation {
+    VectorObservation {
+        data: obs.iter().map(|&x| f64::from(x)).collect(),
+    }
+}
+
+/// `[-1, 1]^dim` box that encoded observations lie in
+fn unit_box(dim: usize) -> Box<dyn ObservationSpace<Observation = VectorObservation>> {
+    Box::new(
+        BoxObservationSpace::new(vec![-1.0; dim], vec![1.0; dim], vec![dim])
+            .expect("bounds match the shape"),
+    )
+}
+
+/// Name in the `_schema` field of a trace file header
+pub const TRACE_SCHEMA: &str = "trace";
+
+/// Trace file format version written by current producers
+///
+/// Version 1 files have no header line; they are upgraded on load.
+pub const TRACE_SCHEMA_VERSION: u32 = 2;
+
+/// First line of a versioned trace file:
+/// `{"_schema":"trace","version":2,"obs_dim":64,"action_dim":10}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceHeader {
+    /// Always [`TRACE_SCHEMA`]
+    #[serde(rename = "_schema")]
+    pub schema: String,
+    /// Format version of the entries that follow
+    pub version: u32,
+    /// Observation dimension the traces were recorded for
+    pub obs_dim: usize,
+    /// Number of discrete actions the traces were recorded for
+    pub action_dim: usize,
+}
+
+impl Default for TraceHeader {
+    /// Header for the spaces a default [`JSONLEnvConfig`] replays
+    fn default() -> Self {
+        let config = JSONLEnvConfig::default();
+        Self::new(config.observation_dim, config.action_dim)
+    }
+}
+
+impl TraceHeader {
+    /// Header for a trace file in the current format
+    pub fn new(obs_dim: usize, action_dim: usize) -> Self {
+        Self {
+            schema: TRACE_SCHEMA.to_string(),
+            version: TRACE_SCHEMA_VERSION,
+            obs_dim,
+            action_dim,
+        }
+    }
+    
+    /// Header line producers write before their first trace entry
+    pub fn to_line(&self) -> Result<String> {
+        serde_json::to_string(self).context("Failed to serialize trace header")
+    }
+    
+    /// Parse a header line, or `None` if the line is a (version 1) entry.
+    /// Headers of another schema or a newer version are rejected.
+    pub fn parse(line: &str) -> Result<Option<Self>> {
+        let value: Value = serde_json::from_str(line).context("Failed to parse trace file")?;
+        if value.get("_schema").is_none() {
+            return Ok(None);
+        }
+        let header: Self = serde_json::from_value(value).context("Invalid trace file header")?;
+        if header.schema != TRACE_SCHEMA {
+            anyhow::bail!("Expected a '{}' file, found schema '{}'", TRACE_SCHEMA, header.schema);
+        }
+        if header.version > TRACE_SCHEMA_VERSION {
+            anyhow::bail!(
+                "Trace file has schema version {}, but this build reads up to version {}",
+                header.version,
+                TRACE_SCHEMA_VERSION
+            );
+        }
+        Ok(Some(header))
+    }
+    
+    /// Check the traces were recorded for the environment's spaces
+    fn validate(&self, config: &JSONLEnvConfig) -> Result<()> {
+        if self.obs_dim != config.observation_dim || self.action_dim != config.action_dim {
+            anyhow::bail!(
+                "Trace file was recorded with obs dim {} and action dim {}, \
+                 but the environment expects obs dim {} and action dim {}",
+                self.obs_dim,
+                self.action_dim,
+                config.observation_dim,
+                config.action_dim
+            );
+        }
+        Ok(())
+    }
+}
+
 /// Trace entry from JSONL file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct TraceEntry {
@@ -70,6 +188,22 @@ struct TraceEntry {
     action: String,
     result: TraceResult,
     metadata: Option<Value>,
+    /// Episode the trace belongs to, when the log records one
+    #[serde(default)]
+    episode_id: Option<String>,
+}
+
+impl TraceEntry {
+    /// Discrete action the entry was recorded with: the `action_idx` the
+    /// policy injector stores in metadata, or a numeric `action`
+    fn recorded_action(&self) -> Option<usize> {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.get("action_idx"))
+            .and_then(Value::as_u64)
+            .and_then(|idx| usize::try_from(idx).ok())
+            .or_else(|| self.action.trim().parse().ok())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,55 +214,252 @@ struct TraceResult {
     execution_time_ms: Option<u64>,
 }
 
+/// Chooses discrete actions while a trace is evaluated
+#[async_trait]
+pub trait TracePolicy: Send {
+    /// Action index for `observation`
+    async fn choose(&mut self, observation: &VectorObservation) -> Result<usize>;
+    
+    /// Switch exploration and learning on or off; policies with neither
+    /// can ignore it
+    fn set_mode(&mut self, _mode: AgentMode) {}
+}
+
+/// How closely a policy's choices match the actions recorded in a trace
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TraceEvaluation {
+    /// Episodes replayed
+    pub episodes: usize,
+    /// Steps replayed
+    pub steps: usize,
+    /// Steps whose trace entry recorded an action to compare against
+    pub compared: usize,
+    /// Compared steps where the policy chose the recorded action
+    pub agreements: usize,
+    /// Reward accumulated over the replay
+    pub total_reward: f32,
+}
+
+impl TraceEvaluation {
+    /// Fraction of compared steps where the policy agreed with the trace,
+    /// or `None` if no entry recorded an action
+    pub fn agreement_rate(&self) -> Option<f32> {
+        (self.compared > 0).then(|| self.agreements as f32 / self.compared as f32)
+    }
+
+    fn record(&mut self, chosen: usize, recorded: Option<usize>, reward: f32) {
+        self.steps += 1;
+        self.total_reward += reward;
+        if let Some(recorded) = recorded {
+            self.compared += 1;
+            if chosen == recorded {
+                self.agreements += 1;
+            }
+        }
+    }
+}
+
 /// Environment that replays JSONL traces
 pub struct JSONLEnv {
     config: JSONLEnvConfig,
     traces: Arc<RwLock<Vec<TraceEntry>>>,
+    /// Trace indices of each labeled episode, in log order
+    episodes: Vec<Vec<usize>>,
+    /// Traces without an episode id in a log that labels episodes
+    unlabeled: usize,
     current_episode: Arc<RwLock<Vec<TraceEntry>>>,
     current_step: Arc<RwLock<usize>>,
-    observation_space: Box<dyn Space>,
-    action_space: Box<dyn Space>,
+    header: TraceHeader,
 }
 
 impl JSONLEnv {
     /// Create new JSONL environment
     pub async fn new(config: JSONLEnvConfig) -> Result<Self> {
         // Load traces from file
-        let traces = Self::load_traces(&config.trace_file).await?;
-        
-        // Create spaces
-        let observation_space = Box::new(BoxSpace::new(
-            vec![-1.0; config.observation_dim],
-            vec![1.0; config.observation_dim],
-        ));
-        
-        let action_space = Box::new(DiscreteSpace::new(config.action_dim));
+        let (header, traces) = Self::load_traces(&config).await?;
+        let episodes = Self::group_episodes(&traces);
+        let unlabeled = if episodes.is_empty() {
+            0
+        } else {
+            traces.iter().filter(|trace| trace.episode_id.is_none()).count()
+        };
+        if unlabeled > 0 {
+            tracing::warn!(
+                path = %config.trace_file.display(),
+                unlabeled,
+                "Skipping traces without an episode id"
+            );
+        }
         
         Ok(Self {
             config,
             traces: Arc::new(RwLock::new(traces)),
+            episodes,
+            unlabeled,
             current_episode: Arc::new(RwLock::new(Vec::new())),
             current_step: Arc::new(RwLock::new(0)),
-            observation_space,
-            action_space,
+            header,
         })
     }
     
+    /// Header of the loaded trace file, inferred for version 1 files
+    pub fn header(&self) -> &TraceHeader {
+        &self.header
+    }
+    
+    /// Traces never replayed because the log labels episodes but not them
+    pub fn unlabeled_traces(&self) -> usize {
+        self.unlabeled
+    }
+    
     /// Load traces from JSONL file
-    async fn load_traces(path: &Path) -> Result<Vec<TraceEntry>> {
+    ///
+    /// Files with a header are checked against the environment's spaces.
+    /// Headerless version 1 files predate it; their header is inferred from
+    /// the config and the entries are read as-is.
+    async fn load_traces(config: &JSONLEnvConfig) -> Result<(TraceHeader, Vec<TraceEntry>)> {
+        let path = &config.trace_file;
         let content = fs::read_to_string(path).await
             .context("Failed to read trace file")?;
+        let mut lines = content.lines().filter(|line| !line.trim().is_empty()).peekable();
+        
+        let parsed = lines.peek().map(|line| TraceHeader::parse(line)).transpose()?.flatten();
+        let header = if let Some(header) = parsed {
+            lines.next();
+            header.validate(config)
+                .with_context(|| format!("Incompatible trace file {}", path.display()))?;
+            header
+        } else {
+            tracing::info!(path = %path.display(), "Upgrading headerless version 1 trace file");
+            TraceHeader::new(config.observation_dim, config.action_dim)
+        };
         
         let mut traces = Vec::new();
-        for line in content.lines() {
-            if !line.trim().is_empty() {
-                let entry: TraceEntry = serde_json::from_str(line)
-                    .context("Failed to parse trace entry")?;
-                traces.push(entry);
+        for line in lines {
+            let entry: TraceEntry = serde_json::from_str(line)
+                .context("Failed to parse trace entry")?;
+            traces.push(entry);
+        }
+        
+        Ok((header, traces))
+    }
+    
+    /// Rewrite a headerless version 1 trace file in the current format,
+    /// recording the spaces it was collected for. Files that already have a
+    /// header are left untouched.
+    pub async fn migrate_trace_file(path: &Path, obs_dim: usize, action_dim: usize) -> Result<()> {
+        let content = fs::read_to_string(path).await
+            .context("Failed to read trace file")?;
+        let first = content.lines().find(|line| !line.trim().is_empty());
+        if let Some(header) = first.map(TraceHeader::parse).transpose()?.flatten() {
+            tracing::debug!(path = %path.display(), version = header.version, "Trace file already has a header");
+            return Ok(());
+        }
+        
+        let header = TraceHeader::new(obs_dim, action_dim).to_line()?;
+        fs::write(path, format!("{}\n{}", header, content)).await
+            .context("Failed to write migrated trace file")
+    }
+    
+    /// Group labeled traces by `episode_id`, keeping first-seen episode order
+    ///
+    /// Traces without an id are left out (see [`JSONLEnv::unlabeled_traces`]);
+    /// an entirely unlabeled log yields no episodes.
+    fn group_episodes(traces: &[TraceEntry]) -> Vec<Vec<usize>> {
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        let mut episodes: Vec<Vec<usize>> = Vec::new();
+        
+        for (i, trace) in traces.iter().enumerate() {
+            if let Some(id) = trace.episode_id.as_deref() {
+                let slot = *index.entry(id).or_insert_with(|| {
+                    episodes.push(Vec::new());
+                    episodes.len() - 1
+                });
+                episodes[slot].push(i);
+            }
+        }
+        
+        episodes
+    }
+    
+    /// Number of episodes [`JSONLEnv::reset_to_episode`] can replay: the
+    /// labeled episodes, or consecutive windows of `max_episode_length`
+    /// traces when the log has no episode ids
+    pub async fn episode_count(&self) -> usize {
+        if self.episodes.is_empty() {
+            let window = self.config.max_episode_length.max(1);
+            self.traces.read().await.len().div_ceil(window)
+        } else {
+            self.episodes.len()
+        }
+    }
+    
+    /// Reset to episode `index`, in log order, instead of a random one
+    pub async fn reset_to_episode(&mut self, index: usize) -> Result<VectorObservation> {
+        let episode: Vec<TraceEntry> = {
+            let traces = self.traces.read().await;
+            if self.episodes.is_empty() {
+                let window = self.config.max_episode_length.max(1);
+                traces.iter().skip(index * window).take(window).cloned().collect()
+            } else {
+                let indices = self.episodes.get(index)
+                    .ok_or_else(|| anyhow::anyhow!("Trace has no episode {}", index))?;
+                indices
+                    .iter()
+                    .take(self.config.max_episode_length)
+                    .map(|&i| traces[i].clone())
+                    .collect()
+            }
+        };
+        
+        self.start_episode(episode).await
+    }
+    
+    /// Action the trace recorded for the step about to be taken, if any
+    pub async fn recorded_action(&self) -> Option<usize> {
+        let step = *self.current_step.read().await;
+        self.current_episode.read().await.get(step).and_then(TraceEntry::recorded_action)
+    }
+    
+    /// Replay every episode once, in log order, scoring the action `policy`
+    /// chooses at each step against the one recorded. The trace alone
+    /// decides what happens next and what reward is earned, so this
+    /// measures how far a policy has drifted without acting on anything.
+    pub async fn evaluate(&mut self, policy: &mut dyn TracePolicy) -> Result<TraceEvaluation> {
+        policy.set_mode(AgentMode::Eval);
+        let mut evaluation = TraceEvaluation::default();
+        
+        for index in 0..self.episode_count().await {
+            let mut observation = self.reset_to_episode(index).await?;
+            evaluation.episodes += 1;
+            
+            loop {
+                let chosen = policy.choose(&observation).await?;
+                let recorded = self.recorded_action().await;
+                let step = self.step(DiscreteAction(chosen)).await?;
+                evaluation.record(chosen, recorded, step.reward.0 as f32);
+                
+                if step.done {
+                    break;
+                }
+                observation = step.observation;
             }
         }
         
-        Ok(traces)
+        Ok(evaluation)
+    }
+    
+    /// Make `episode` the current one and return its first observation
+    async fn start_episode(&self, episode: Vec<TraceEntry>) -> Result<VectorObservation> {
+        *self.current_episode.write().await = episode;
+        *self.current_step.write().await = 0;
+        
+        let episode = self.current_episode.read().await;
+        if let Some(first_trace) = episode.first() {
+            Ok(to_observation(&self.trace_to_observation(first_trace, 0)))
+        } else {
+            Err(anyhow::anyhow!("Empty episode"))
+        }
     }
     
     /// Convert trace to observation
@@ -195,50 +526,55 @@ impl JSONLEnv {
 
 #[async_trait]
 impl Environment for JSONLEnv {
-    fn observation_space(&self) -> &dyn Space {
-        self.observation_space.as_ref()
+    type Observation = VectorObservation;
+    type Action = DiscreteAction;
+    type State = VectorState;
+    
+    fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+        unit_box(self.config.observation_dim)
     }
     
-    fn action_space(&self) -> &dyn Space {
-        self.action_space.as_ref()
+    fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+        Box::new(DiscreteSpace::new(self.config.action_dim))
     }
     
-    async fn reset(&mut self) -> Result<Observation> {
+    async fn reset(&mut self) -> sentient_rl_core::Result<(Self::Observation, StepInfo)> {
         let traces = self.traces.read().await;
         
         // Sample random episode from traces
         if traces.is_empty() {
-            return Err(anyhow::anyhow!("No traces available"));
+            return Err(RLError::Environment("No traces available".to_string()));
         }
         
-        // Create episode by sampling consecutive traces
-        let start_idx = rand::random::<usize>() % traces.len();
-        let episode_length = self.config.max_episode_length.min(traces.len() - start_idx);
-        
-        let episode: Vec<TraceEntry> = traces[start_idx..start_idx + episode_length]
-            .iter()
-            .cloned()
-            .collect();
-        
-        *self.current_episode.write().await = episode;
-        *self.current_step.write().await = 0;
-        
-        // Return initial observation
-        let episode = self.current_episode.read().await;
-        if let Some(first_trace) = episode.first() {
-            let obs = self.trace_to_observation(first_trace, 0);
-            Ok(Observation::new(obs.to_vec()))
+        let episode: Vec<TraceEntry> = if self.episodes.is_empty() {
+            // No episode markers: sample a window of consecutive traces
+            let start_idx = rand::random::<usize>() % traces.len();
+            let episode_length = self.config.max_episode_length.min(traces.len() - start_idx);
+            
+            traces[start_idx..start_idx + episode_length]
+                .iter()
+                .cloned()
+                .collect()
         } else {
-            Err(anyhow::anyhow!("Empty episode"))
-        }
+            // Replay one recorded episode from its start
+            let indices = &self.episodes[rand::random::<usize>() % self.episodes.len()];
+            indices
+                .iter()
+                .take(self.config.max_episode_length)
+                .map(|&i| traces[i].clone())
+                .collect()
+        };
+        drop(traces);
+        
+        Ok((self.start_episode(episode).await?, StepInfo::default()))
     }
     
-    async fn step(&mut self, action: Action) -> Result<StepInfo> {
+    async fn step(&mut self, _action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
         let mut step = self.current_step.write().await;
         let episode = self.current_episode.read().await;
         
         if *step >= episode.len() {
-            return Err(anyhow::anyhow!("Episode ended"));
+            return Err(RLError::Environment("Episode ended".to_string()));
         }
         
         // Get current trace
@@ -256,23 +592,23 @@ impl Environment for JSONLEnv {
         // Get next observation
         let observation = if done || *step >= episode.len() {
             // Terminal state
-            Observation::new(vec![0.0; self.config.observation_dim])
+            VectorObservation { data: vec![0.0; self.config.observation_dim] }
         } else {
             let next_trace = &episode[*step];
-            let obs = self.trace_to_observation(next_trace, *step);
-            Observation::new(obs.to_vec())
+            to_observation(&self.trace_to_observation(next_trace, *step))
         };
         
-        Ok(StepInfo {
+        Ok(Step {
             observation,
-            reward: Reward(reward),
+            reward: Reward(f64::from(reward)),
             done,
             truncated: *step >= self.config.max_episode_length,
-            info: Default::default(),
+            info: StepInfo::default(),
+            state: None,
         })
     }
     
-    async fn close(&mut self) -> Result<()> {
+    async fn close(&mut self) -> sentient_rl_core::Result<()> {
         Ok(())
     }
 }
@@ -284,12 +620,91 @@ pub struct GoalTaskEnvConfig {
     pub goal_templates: Vec<String>,
     /// Maximum steps per episode
     pub max_steps: usize,
-    /// Observation dimension
-    pub observation_dim: usize,
+    /// Features observations are made of, in order; see
+    /// [`system_observation`](crate::system_observation)
+    #[serde(default)]
+    pub observation_spec: ObservationSpec,
     /// Whether to execute real commands
     pub execute_real_commands: bool,
     /// Command timeout in seconds
     pub command_timeout_secs: u64,
+    /// How long a simulated goal execution takes, in milliseconds
+    #[serde(default = "default_simulated_latency_ms")]
+    pub simulated_latency_ms: u64,
+    /// How real executions of a goal template are judged; goals without an
+    /// entry succeed when their command exits 0
+    #[serde(default)]
+    pub success_criteria: HashMap<String, SuccessCriteria>,
+}
+
+fn default_simulated_latency_ms() -> u64 {
+    100
+}
+
+/// What a real command must do for its goal to count as achieved. Both
+/// checks apply when both are set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuccessCriteria {
+    /// Required exit status; `None` accepts any
+    #[serde(default = "default_success_exit_code")]
+    pub exit_code: Option<i32>,
+    /// Pattern stdout must match
+    #[serde(default)]
+    pub stdout_regex: Option<String>,
+}
+
+fn default_success_exit_code() -> Option<i32> {
+    Some(0)
+}
+
+impl Default for SuccessCriteria {
+    fn default() -> Self {
+        Self {
+            exit_code: default_success_exit_code(),
+            stdout_regex: None,
+        }
+    }
+}
+
+impl SuccessCriteria {
+    /// Compile the stdout pattern, failing if it is not a valid regex
+    pub fn compile(&self) -> Result<SuccessMatcher> {
+        let stdout_regex = match &self.stdout_regex {
+            Some(pattern) => Some(
+                regex::Regex::new(pattern)
+                    .with_context(|| format!("Invalid success pattern {:?}", pattern))?,
+            ),
+            None => None,
+        };
+        Ok(SuccessMatcher {
+            exit_code: self.exit_code,
+            stdout_regex,
+        })
+    }
+}
+
+/// [`SuccessCriteria`] with the stdout pattern compiled
+#[derive(Debug, Clone)]
+pub struct SuccessMatcher {
+    exit_code: Option<i32>,
+    stdout_regex: Option<regex::Regex>,
+}
+
+/// Criteria for goals without their own: exit status 0
+static DEFAULT_SUCCESS: SuccessMatcher = SuccessMatcher {
+    exit_code: Some(0),
+    stdout_regex: None,
+};
+
+impl SuccessMatcher {
+    /// Whether a command that exited with `exit_code` (`None` if killed by a
+    /// signal) and printed `stdout` met these criteria
+    pub fn is_met(&self, exit_code: Option<i32>, stdout: &str) -> bool {
+        if self.exit_code.is_some_and(|expected| exit_code != Some(expected)) {
+            return false;
+        }
+        self.stdout_regex.as_ref().is_none_or(|regex| regex.is_match(stdout))
+    }
 }
 
 impl Default for GoalTaskEnvConfig {
@@ -303,9 +718,11 @@ impl Default for GoalTaskEnvConfig {
                 "Scan system logs for errors".to_string(),
             ],
             max_steps: 50,
-            observation_dim: 64,
+            observation_spec: ObservationSpec::default(),
             execute_real_commands: false,
             command_timeout_secs: 5,
+            simulated_latency_ms: default_simulated_latency_ms(),
+            success_criteria: HashMap::new(),
         }
     }
 }
@@ -316,8 +733,14 @@ pub struct GoalTaskEnv {
     current_goal: Arc<RwLock<Option<String>>>,
     current_step: Arc<RwLock<usize>>,
     goal_history: Arc<RwLock<VecDeque<GoalExecution>>>,
-    observation_space: Box<dyn Space>,
-    action_space: Box<dyn Space>,
+    last_goal_at: Arc<RwLock<Option<DateTime<Utc>>>>,
+    encoder: SystemObservationEncoder,
+    host_probe: Arc<dyn HostProbe>,
+    improvement: Option<(SystemImprovementReward, Arc<dyn MetricsProbe>)>,
+    /// `config.success_criteria`, compiled
+    success: HashMap<String, SuccessMatcher>,
+    active_templates: usize,
+    cancel: Option<CancellationToken>,
 }
 
 #[derive(Debug, Clone)]
@@ -329,64 +752,140 @@ struct GoalExecution {
     execution_time: std::time::Duration,
 }
 
+/// Characters of command output kept in step info
+const OUTPUT_SNIPPET_CHARS: usize = 200;
+
+impl GoalExecution {
+    /// What ran and how it went, under the step info keys loggers and the
+    /// dashboard read: `goal`, `command`, `success`, `execution_time_ms`
+    /// and the start of the output as `output`
+    fn step_info(&self) -> StepInfo {
+        let output = self.output.trim();
+        let snippet = match output.char_indices().nth(OUTPUT_SNIPPET_CHARS) {
+            Some((end, _)) => format!("{}…", &output[..end]),
+            None => output.to_string(),
+        };
+        
+        StepInfo::default()
+            .with("goal", self.goal.clone())
+            .with("command", self.command.clone())
+            .with("success", self.success)
+            .with("execution_time_ms", self.execution_time.as_millis() as u64)
+            .with("output", snippet)
+    }
+}
+
 impl GoalTaskEnv {
     /// Create new goal task environment
-    pub fn new(config: GoalTaskEnvConfig) -> Self {
-        let observation_space = Box::new(BoxSpace::new(
-            vec![-1.0; config.observation_dim],
-            vec![1.0; config.observation_dim],
-        ));
-        
-        let action_space = Box::new(DiscreteSpace::new(config.goal_templates.len()));
+    ///
+    /// Fails if the configured observation spec names features the encoder
+    /// cannot build, or a success pattern is not a valid regex.
+    pub fn new(config: GoalTaskEnvConfig) -> Result<Self> {
+        let encoder = SystemObservationEncoder::new(config.observation_spec.clone())?;
+        let success = config.success_criteria.iter()
+            .map(|(goal, criteria)| {
+                let matcher = criteria.compile()
+                    .with_context(|| format!("Invalid success criteria for goal {:?}", goal))?;
+                Ok((goal.clone(), matcher))
+            })
+            .collect::<Result<_>>()?;
+        let active_templates = config.goal_templates.len();
         
-        Self {
+        Ok(Self {
             config,
             current_goal: Arc::new(RwLock::new(None)),
             current_step: Arc::new(RwLock::new(0)),
             goal_history: Arc::new(RwLock::new(VecDeque::with_capacity(100))),
-            observation_space,
-            action_space,
-        }
+            last_goal_at: Arc::new(RwLock::new(None)),
+            encoder,
+            host_probe: Arc::new(SysinfoProbe),
+            improvement: None,
+            success,
+            active_templates,
+            cancel: None,
+        })
+    }
+    
+    /// Restrict goals to the first `active_templates` templates and episodes
+    /// to `max_steps` steps
+    ///
+    /// The action space keeps its full size; actions past the active set
+    /// wrap around onto it.
+    pub fn set_difficulty(&mut self, active_templates: usize, max_steps: usize) {
+        self.active_templates = active_templates.clamp(1, self.config.goal_templates.len().max(1));
+        self.config.max_steps = max_steps.max(1);
+    }
+    
+    /// Number of goal templates currently in play
+    pub fn active_templates(&self) -> usize {
+        self.active_templates
+    }
+    
+    /// Valid actions under the current difficulty: only the active templates
+    pub fn action_mask(&self) -> Vec<bool> {
+        (0..self.config.goal_templates.len())
+            .map(|i| i < self.active_templates)
+            .collect()
+    }
+    
+    /// Maximum steps per episode
+    pub fn max_steps(&self) -> usize {
+        self.config.max_steps
+    }
+    
+    /// Whether the most recent goal execution succeeded
+    pub(crate) async fn last_goal_succeeded(&self) -> Option<bool> {
+        self.goal_history.read().await.back().map(|execution| execution.success)
+    }
+    
+    /// Add a reward for measured system improvement on top of the
+    /// command-success reward
+    pub fn with_improvement_reward(
+        mut self,
+        reward: SystemImprovementReward,
+        probe: Arc<dyn MetricsProbe>,
+    ) -> Self {
+        self.improvement = Some((reward, probe));
+        self
+    }
+    
+    /// Abort in-flight goal executions, and refuse new steps and resets,
+    /// once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+    
+    /// Read host metrics for observations from `probe` instead of
+    /// [`SysinfoProbe`]
+    pub fn with_host_probe(mut self, probe: Arc<dyn HostProbe>) -> Self {
+        self.host_probe = probe;
+        self
     }
     
     /// Convert state to observation
     async fn get_observation(&self) -> Array1<f32> {
-        let mut obs = Array1::zeros(self.config.observation_dim);
-        
-        // Encode current goal
-        if let Some(goal) = &*self.current_goal.read().await {
-            let goal_hash = goal.chars().map(|c| c as u32).sum::<u32>() as f32;
-            obs[0] = (goal_hash % 1000.0) / 1000.0;
-        }
-        
-        // Step counter
-        let step = *self.current_step.read().await;
-        obs[1] = (step as f32 / self.config.max_steps as f32).tanh();
-        
-        // Recent goal history features
-        let history = self.goal_history.read().await;
-        let recent_success_rate = if history.len() > 0 {
-            let successes = history.iter().filter(|g| g.success).count();
-            successes as f32 / history.len() as f32
-        } else {
-            0.5
-        };
-        obs[2] = recent_success_rate;
-        
-        // Average execution time
-        if history.len() > 0 {
-            let avg_time: f32 = history.iter()
-                .map(|g| g.execution_time.as_secs_f32())
-                .sum::<f32>() / history.len() as f32;
-            obs[3] = avg_time.tanh();
-        }
-        
-        // Fill remaining with system metrics (simulated)
-        for i in 4..self.config.observation_dim {
-            obs[i] = ((i as f32) * 0.1).sin() * 0.5;
-        }
+        self.observation_at(Utc::now()).await
+    }
+    
+    /// The observation at `now`, encoded exactly as the policy injector
+    /// encodes the live system
+    async fn observation_at(&self, now: DateTime<Utc>) -> Array1<f32> {
+        let host = self.host_probe.host_metrics();
+        let goals: Vec<GoalOutcome> = self
+            .goal_history
+            .read()
+            .await
+            .iter()
+            .map(|execution| GoalOutcome {
+                success: execution.success,
+                execution_time_ms: execution.execution_time.as_millis() as u64,
+            })
+            .collect();
+        let last_goal_at = *self.last_goal_at.read().await;
         
-        obs
+        let obs = SystemObservation::from_parts(&host, &goals, last_goal_at, now);
+        Array1::from(self.encoder.encode(&obs))
     }
     
     /// Execute goal and return result
@@ -396,11 +895,34 @@ impl GoalTaskEnv {
         // Map goal to command
         let command = self.goal_to_command(goal);
         
-        let (success, output) = if self.config.execute_real_commands {
+        let criteria = self.success.get(goal).unwrap_or(&DEFAULT_SUCCESS);
+        let timeout = tokio::time::Duration::from_secs(self.config.command_timeout_secs);
+        let (success, output) = match tokio::time::timeout(timeout, self.run_command(&command, criteria)).await {
+            Ok(result) => result?,
+            Err(_) => (
+                false,
+                format!("Timed out after {}s: {}", self.config.command_timeout_secs, command),
+            ),
+        };
+        
+        Ok(GoalExecution {
+            goal: goal.to_string(),
+            command,
+            success,
+            output,
+            execution_time: start.elapsed(),
+        })
+    }
+    
+    /// Run (or simulate) a command, returning whether it met `criteria` and
+    /// its output. Dropping the future kills a real command's process.
+    async fn run_command(&self, command: &str, criteria: &SuccessMatcher) -> Result<(bool, String)> {
+        if self.config.execute_real_commands {
             // Execute real command
             match tokio::process::Command::new("sh")
                 .arg("-c")
-                .arg(&command)
+                .arg(command)
+                .kill_on_drop(true)
                 .output()
                 .await
             {
@@ -408,29 +930,22 @@ impl GoalTaskEnv {
                     let stdout = String::from_utf8_lossy(&output.stdout);
                     let stderr = String::from_utf8_lossy(&output.stderr);
                     let full_output = format!("{}\n{}", stdout, stderr);
-                    (output.status.success(), full_output)
+                    let success = criteria.is_met(output.status.code(), &stdout);
+                    Ok((success, full_output))
                 }
-                Err(e) => (false, format!("Command failed: {}", e)),
+                Err(e) => Ok((false, format!("Command failed: {}", e))),
             }
         } else {
             // Simulate execution
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+            tokio::time::sleep(tokio::time::Duration::from_millis(self.config.simulated_latency_ms)).await;
             let success = rand::random::<f32>() > 0.3;
             let output = if success {
                 format!("Simulated success for: {}", command)
             } else {
                 format!("Simulated failure for: {}", command)
             };
-            (success, output)
-        };
-        
-        Ok(GoalExecution {
-            goal: goal.to_string(),
-            command,
-            success,
-            output,
-            execution_time: start.elapsed(),
-        })
+            Ok((success, output))
+        }
     }
     
     /// Convert goal to executable command
@@ -473,36 +988,59 @@ impl GoalTaskEnv {
 
 #[async_trait]
 impl Environment for GoalTaskEnv {
-    fn observation_space(&self) -> &dyn Space {
-        self.observation_space.as_ref()
+    type Observation = VectorObservation;
+    type Action = DiscreteAction;
+    type State = VectorState;
+    
+    fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+        unit_box(self.encoder.dim())
     }
     
-    fn action_space(&self) -> &dyn Space {
-        self.action_space.as_ref()
+    fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+        Box::new(DiscreteSpace::new(self.config.goal_templates.len()))
     }
     
-    async fn reset(&mut self) -> Result<Observation> {
+    async fn reset(&mut self) -> sentient_rl_core::Result<(Self::Observation, StepInfo)> {
+        if self.cancel.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            return Err(RLError::Cancelled("GoalTaskEnv reset not started".to_string()));
+        }
         *self.current_step.write().await = 0;
         *self.current_goal.write().await = None;
         
         let obs = self.get_observation().await;
-        Ok(Observation::new(obs.to_vec()))
+        Ok((to_observation(&obs), StepInfo::default().with_action_mask(&self.action_mask())))
     }
     
-    async fn step(&mut self, action: Action) -> Result<StepInfo> {
-        let action_idx = action.as_slice()[0] as usize;
+    async fn step(&mut self, action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
+        let action_idx = action.0;
         
         if action_idx >= self.config.goal_templates.len() {
-            return Err(anyhow::anyhow!("Invalid action"));
+            return Err(RLError::InvalidAction(format!(
+                "Goal {} of {}",
+                action_idx,
+                self.config.goal_templates.len()
+            )));
         }
         
-        // Select goal based on action
-        let goal = self.config.goal_templates[action_idx].clone();
+        // Select goal based on action, folded onto the active templates
+        let goal = self.config.goal_templates[action_idx % self.active_templates].clone();
         *self.current_goal.write().await = Some(goal.clone());
         
-        // Execute goal
-        let execution = self.execute_goal(&goal).await?;
-        let reward = self.compute_reward(&execution);
+        // Execute goal, snapshotting the system around it if configured
+        let before = self.improvement.as_ref().map(|(_, probe)| probe.snapshot());
+        let execution = match &self.cancel {
+            Some(token) => {
+                let work = async { self.execute_goal(&goal).await.map_err(RLError::from) };
+                run_cancellable(token, "GoalTaskEnv step", work).await?
+            }
+            None => self.execute_goal(&goal).await?,
+        };
+        let mut reward = self.compute_reward(&execution);
+        let info = execution.step_info().with_action_mask(&self.action_mask());
+        if let (Some((improvement, probe)), Some(before)) = (&self.improvement, before) {
+            let after = probe.snapshot();
+            reward += improvement.reward(&before, &after);
+        }
         
         // Update history
         let mut history = self.goal_history.write().await;
@@ -510,6 +1048,8 @@ impl Environment for GoalTaskEnv {
             history.pop_front();
         }
         history.push_back(execution);
+        drop(history);
+        *self.last_goal_at.write().await = Some(Utc::now());
         
         // Update step counter
         let mut step = self.current_step.write().await;
@@ -521,16 +1061,410 @@ impl Environment for GoalTaskEnv {
         // Get next observation
         let obs = self.get_observation().await;
         
-        Ok(StepInfo {
-            observation: Observation::new(obs.to_vec()),
-            reward: Reward(reward),
+        Ok(Step {
+            observation: to_observation(&obs),
+            reward: Reward(f64::from(reward)),
             done,
             truncated: done,
-            info: Default::default(),
+            info,
+            state: None,
         })
     }
     
-    async fn close(&mut self) -> Result<()> {
+    async fn close(&mut self) -> sentient_rl_core::Result<()> {
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system_observation::HostMetrics;
+    use crate::wrappers::ClipReward;
+    use sentient_rl_core::EnvironmentConfig;
+
+    fn trace_line(episode_id: Option<&str>, step: usize) -> String {
+        let mut entry = serde_json::json!({
+            "timestamp": "2024-01-01T00:00:00Z",
+            "goal": format!("goal {step}"),
+            "action": "free -h",
+            "result": { "success": true, "output": null, "error": null, "execution_time_ms": 20 },
+            "metadata": null,
+        });
+        if let Some(id) = episode_id {
+            entry["episode_id"] = serde_json::json!(id);
+        }
+        entry.to_string()
+    }
+
+    fn trace_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{name}_{}.jsonl", std::process::id()))
+    }
+
+    fn env_config(path: PathBuf) -> JSONLEnvConfig {
+        JSONLEnvConfig {
+            trace_file: path,
+            max_episode_length: 10,
+            observation_dim: 8,
+            action_dim: 4,
+            reward_config: RewardConfig::default(),
+        }
+    }
+
+    async fn try_env_for(lines: &[String], name: &str) -> Result<JSONLEnv> {
+        let path = trace_path(name);
+        fs::write(&path, lines.join("\n")).await.unwrap();
+        let env = JSONLEnv::new(env_config(path.clone())).await;
+        fs::remove_file(&path).await.ok();
+        env
+    }
+
+    async fn env_for(lines: &[String], name: &str) -> JSONLEnv {
+        try_env_for(lines, name).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reset_replays_one_labeled_episode() {
+        let lengths = [("a", 3), ("b", 2), ("c", 4)];
+        let lines: Vec<String> = lengths
+            .iter()
+            .flat_map(|&(id, len)| (0..len).map(move |step| trace_line(Some(id), step)))
+            .collect();
+        let mut env = env_for(&lines, "labeled_traces").await;
+
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..50 {
+            env.reset().await.unwrap();
+            let episode = env.current_episode.read().await;
+            let id = episode[0].episode_id.clone().unwrap();
+            assert!(episode.iter().all(|t| t.episode_id.as_deref() == Some(id.as_str())));
+
+            let expected = lengths.iter().find(|(name, _)| *name == id).unwrap().1;
+            assert_eq!(episode.len(), expected, "episode {id} should be replayed whole");
+            let goals: Vec<&str> = episode.iter().map(|t| t.goal.as_str()).collect();
+            let in_order: Vec<String> = (0..expected).map(|step| format!("goal {step}")).collect();
+            assert_eq!(goals, in_order);
+            seen.insert(id);
+        }
+        assert_eq!(seen.len(), lengths.len());
+    }
+
+    #[tokio::test]
+    async fn test_reward_clip_clamps_shaped_reward() {
+        let lines: Vec<String> = (0..3).map(|step| trace_line(Some("a"), step)).collect();
+        let path = trace_path("clipped_traces");
+        fs::write(&path, lines.join("\n")).await.unwrap();
+        let config = JSONLEnvConfig {
+            reward_config: RewardConfig {
+                success_reward: 10.0,
+                ..RewardConfig::default()
+            },
+            ..env_config(path.clone())
+        };
+
+        // Success plus the efficiency bonus, well above the clip
+        let mut env = JSONLEnv::new(config.clone()).await.unwrap();
+        env.reset().await.unwrap();
+        assert!((env.step(DiscreteAction(0)).await.unwrap().reward.0 - 10.19).abs() < 1e-4);
+
+        let clip = EnvironmentConfig {
+            reward_clip: Some((-1.0, 1.0)),
+            ..EnvironmentConfig::default()
+        };
+        let mut env = ClipReward::from_config(JSONLEnv::new(config).await.unwrap(), &clip).unwrap();
+        env.reset().await.unwrap();
+        for _ in 0..3 {
+            let step = env.step(DiscreteAction(0)).await.unwrap();
+            assert!((step.reward.0 - 1.0).abs() < 1e-6);
+        }
+        fs::remove_file(&path).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_unlabeled_traces_fall_back_to_windows() {
+        let lines: Vec<String> = (0..5).map(|step| trace_line(None, step)).collect();
+        let mut env = env_for(&lines, "unlabeled_traces").await;
+        assert!(env.episodes.is_empty());
+        assert_eq!(env.unlabeled_traces(), 0);
+
+        env.reset().await.unwrap();
+        assert!(!env.current_episode.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unlabeled_traces_in_labeled_log_are_counted() {
+        let lines = vec![
+            trace_line(Some("a"), 0),
+            trace_line(None, 1),
+            trace_line(Some("a"), 2),
+            trace_line(None, 3),
+            trace_line(Some("b"), 0),
+        ];
+        let env = env_for(&lines, "partly_labeled_traces").await;
+        assert_eq!(env.episodes, vec![vec![0, 2], vec![4]]);
+        assert_eq!(env.unlabeled_traces(), 2);
+    }
+
+    /// Always chooses the same action
+    struct FixedPolicy {
+        action: usize,
+        calls: usize,
+        mode: AgentMode,
+    }
+
+    #[async_trait]
+    impl TracePolicy for FixedPolicy {
+        async fn choose(&mut self, _observation: &VectorObservation) -> Result<usize> {
+            self.calls += 1;
+            Ok(self.action)
+        }
+
+        fn set_mode(&mut self, mode: AgentMode) {
+            self.mode = mode;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_scores_agreement_with_recorded_actions() {
+        let line = |episode: &str, action_idx: Option<usize>, success: bool| {
+            let mut entry: Value = serde_json::from_str(&trace_line(Some(episode), 0)).unwrap();
+            entry["result"]["success"] = serde_json::json!(success);
+            if let Some(idx) = action_idx {
+                entry["metadata"] = serde_json::json!({ "action_idx": idx });
+            }
+            entry.to_string()
+        };
+        // The last entry recorded no action, so it is replayed but not compared
+        let lines = vec![
+            line("a", Some(1), true),
+            line("a", Some(0), true),
+            line("a", Some(1), false),
+            line("b", Some(1), true),
+            line("b", None, true),
+        ];
+        let mut env = env_for(&lines, "eval_traces").await;
+        let mut policy = FixedPolicy { action: 1, calls: 0, mode: AgentMode::Train };
+
+        let evaluation = env.evaluate(&mut policy).await.unwrap();
+
+        assert_eq!(policy.calls, 5);
+        assert_eq!(policy.mode, AgentMode::Eval);
+        assert_eq!(evaluation.episodes, 2);
+        assert_eq!(evaluation.steps, 5);
+        assert_eq!(evaluation.compared, 4);
+        assert_eq!(evaluation.agreements, 3);
+        assert_eq!(evaluation.agreement_rate(), Some(0.75));
+        // Successes in under 100ms earn 1.19, the failure -0.51
+        assert!((evaluation.total_reward - (4.0 * 1.19 - 0.51)).abs() < 1e-4, "{}", evaluation.total_reward);
+
+        assert_eq!(TraceEvaluation::default().agreement_rate(), None);
+    }
+
+    #[tokio::test]
+    async fn test_trace_header_is_validated_and_v1_files_migrate() {
+        let entries: Vec<String> = (0..3).map(|step| trace_line(Some("a"), step)).collect();
+
+        // Version 1: no header, so it is inferred from the config
+        let env = env_for(&entries, "v1_traces").await;
+        assert_eq!(env.header(), &TraceHeader::new(8, 4));
+        assert_eq!(env.traces.read().await.len(), 3);
+
+        // Version 2: the header is checked and not read as an entry
+        let mut v2 = vec![TraceHeader::new(8, 4).to_line().unwrap()];
+        v2.extend(entries.iter().cloned());
+        let env = env_for(&v2, "v2_traces").await;
+        assert_eq!(env.header().version, TRACE_SCHEMA_VERSION);
+        assert_eq!(env.traces.read().await.len(), 3);
+
+        v2[0] = TraceHeader::new(16, 4).to_line().unwrap();
+        let err = try_env_for(&v2, "v2_wrong_dim").await.err().unwrap();
+        assert!(format!("{err:#}").contains("obs dim 16"), "{err:#}");
+
+        let future = TraceHeader {
+            version: TRACE_SCHEMA_VERSION + 1,
+            ..TraceHeader::new(8, 4)
+        };
+        v2[0] = future.to_line().unwrap();
+        let err = try_env_for(&v2, "v3_traces").await.err().unwrap();
+        assert!(err.to_string().contains("schema version 3"), "{err}");
+
+        // Migrating on disk prepends the header exactly once
+        let path = trace_path("migrate_traces");
+        fs::write(&path, entries.join("\n")).await.unwrap();
+        JSONLEnv::migrate_trace_file(&path, 8, 4).await.unwrap();
+        JSONLEnv::migrate_trace_file(&path, 8, 4).await.unwrap();
+        let content = fs::read_to_string(&path).await.unwrap();
+        assert_eq!(content.lines().count(), 4);
+        assert_eq!(content.lines().next().unwrap(), TraceHeader::new(8, 4).to_line().unwrap());
+        let env = JSONLEnv::new(env_config(path.clone())).await.unwrap();
+        assert_eq!(env.traces.read().await.len(), 3);
+        fs::remove_file(&path).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_cancelling_slow_step_returns_promptly() {
+        let token = CancellationToken::new();
+        let mut env = GoalTaskEnv::new(GoalTaskEnvConfig {
+            simulated_latency_ms: 10_000,
+            command_timeout_secs: 30,
+            ..GoalTaskEnvConfig::default()
+        })
+        .unwrap()
+        .with_cancellation(token.clone());
+        env.reset().await.unwrap();
+
+        let stop = token.clone();
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            stop.cancel();
+        });
+
+        let start = std::time::Instant::now();
+        let err = env.step(DiscreteAction(0)).await.unwrap_err();
+        assert!(start.elapsed() < std::time::Duration::from_secs(1), "took {:?}", start.elapsed());
+        assert!(matches!(err, RLError::Cancelled(_)), "{err}");
+        canceller.await.unwrap();
+
+        // Nothing new starts once cancelled
+        assert!(env.reset().await.is_err());
+        assert!(env.goal_history.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_step_info_reports_the_goal_execution() {
+        let mut env = GoalTaskEnv::new(GoalTaskEnvConfig {
+            simulated_latency_ms: 0,
+            ..GoalTaskEnvConfig::default()
+        })
+        .unwrap();
+        env.reset().await.unwrap();
+
+        // Action 1 is "Check memory usage patterns"
+        let step = env.step(DiscreteAction(1)).await.unwrap();
+        let succeeded = env.last_goal_succeeded().await.unwrap();
+        assert_eq!(step.info.get("goal"), Some(&serde_json::json!("Check memory usage patterns")));
+        assert_eq!(step.info.get("command"), Some(&serde_json::json!("free -h")));
+        assert_eq!(step.info.get("success"), Some(&serde_json::json!(succeeded)));
+        assert!(step.info.get("execution_time_ms").unwrap().is_u64());
+        let output = step.info.get("output").unwrap().as_str().unwrap();
+        assert!(output.ends_with("for: free -h"), "{output}");
+        assert!(step.info.get(sentient_rl_core::ACTION_MASK_KEY).is_some());
+
+        // Long output is cut to a snippet
+        let execution = GoalExecution {
+            goal: "g".to_string(),
+            command: "yes".to_string(),
+            success: true,
+            output: "y\n".repeat(1000),
+            execution_time: std::time::Duration::from_millis(1500),
+        };
+        let info = execution.step_info();
+        assert_eq!(info.get("execution_time_ms"), Some(&serde_json::json!(1500)));
+        assert_eq!(info.get("output").unwrap().as_str().unwrap().chars().count(), OUTPUT_SNIPPET_CHARS + 1);
+    }
+
+    #[tokio::test]
+    async fn test_success_criteria_judge_real_commands_by_output() {
+        // Goals without a known command run `echo 'Unknown goal'`, which exits 0
+        let goal = "Say something".to_string();
+        let config = |criteria: SuccessCriteria| GoalTaskEnvConfig {
+            goal_templates: vec![goal.clone()],
+            execute_real_commands: true,
+            success_criteria: HashMap::from([(goal.clone(), criteria)]),
+            ..GoalTaskEnvConfig::default()
+        };
+
+        // Exit 0, but the output is not what the goal needs
+        let env = GoalTaskEnv::new(config(SuccessCriteria {
+            stdout_regex: Some("^OK".to_string()),
+            ..SuccessCriteria::default()
+        }))
+        .unwrap();
+        let execution = env.execute_goal(&goal).await.unwrap();
+        assert_eq!(execution.command, "echo 'Unknown goal'");
+        assert!(!execution.success, "{}", execution.output);
+        assert!(env.compute_reward(&execution) < 0.0);
+
+        let env = GoalTaskEnv::new(config(SuccessCriteria {
+            exit_code: None,
+            stdout_regex: Some("Unknown goal".to_string()),
+        }))
+        .unwrap();
+        assert!(env.execute_goal(&goal).await.unwrap().success);
+
+        // A failing exit status passes when only the output is checked
+        let output_only = SuccessCriteria {
+            exit_code: None,
+            stdout_regex: Some(r"\d+ errors? found".to_string()),
+        }
+        .compile()
+        .unwrap();
+        assert!(output_only.is_met(Some(1), "scan finished: 3 errors found"));
+        assert!(!output_only.is_met(Some(0), "scan finished"));
+        let exit_zero = SuccessCriteria::default().compile().unwrap();
+        assert!(!exit_zero.is_met(Some(1), "3 errors found"));
+        assert!(!exit_zero.is_met(None, ""));
+
+        // A bad pattern is caught when the env is built, not on a step
+        let invalid = SuccessCriteria {
+            stdout_regex: Some("(".to_string()),
+            ..SuccessCriteria::default()
+        };
+        assert!(invalid.compile().is_err());
+        let err = GoalTaskEnv::new(config(invalid)).err().unwrap();
+        let err = format!("{:#}", err);
+        assert!(err.contains("Say something") && err.contains("Invalid success pattern"), "{}", err);
+    }
+
+    struct FixedHost(HostMetrics);
+
+    impl HostProbe for FixedHost {
+        fn host_metrics(&self) -> HostMetrics {
+            self.0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observation_uses_the_shared_encoder() {
+        let host = HostMetrics {
+            cpu_usage: 42.0,
+            memory_usage: 61.5,
+            disk_usage: 80.0,
+            process_count: 312,
+        };
+        let env = GoalTaskEnv::new(GoalTaskEnvConfig::default())
+            .unwrap()
+            .with_host_probe(Arc::new(FixedHost(host)));
+
+        let now = DateTime::parse_from_rfc3339("2024-03-06T13:30:00Z").unwrap().with_timezone(&Utc);
+        let goals: Vec<GoalOutcome> = (0..12)
+            .map(|i| GoalOutcome { success: i % 3 != 0, execution_time_ms: 50 * i })
+            .collect();
+        for goal in &goals {
+            env.goal_history.write().await.push_back(GoalExecution {
+                goal: "g".to_string(),
+                command: "true".to_string(),
+                success: goal.success,
+                output: String::new(),
+                execution_time: std::time::Duration::from_millis(goal.execution_time_ms),
+            });
+        }
+        let last_goal_at = now - chrono::Duration::seconds(90);
+        *env.last_goal_at.write().await = Some(last_goal_at);
+
+        let expected = SystemObservationEncoder::default()
+            .encode(&SystemObservation::from_parts(&host, &goals, Some(last_goal_at), now));
+        assert_eq!(env.observation_at(now).await.to_vec(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_default_probe_measures_the_host() {
+        let env = GoalTaskEnv::new(GoalTaskEnvConfig::default()).unwrap();
+        let spec = ObservationSpec::default();
+        let obs = env.observation_at(Utc::now()).await;
+
+        // At least this test process is running
+        let processes = spec.features.iter().position(|f| f == "process_count").unwrap();
+        assert!(obs[processes] > 0.0, "{:?}", obs);
+    }
+}
@@ -0,0 +1,320 @@
+//! Observation layout shared by training and serving
+//!
+//! A policy trained on [`GoalTaskEnv`](crate::GoalTaskEnv) is served by the
+//! shell's policy injector, so both must turn the same system state into the
+//! same input vector. Both assemble a [`SystemObservation`] with
+//! [`SystemObservation::from_parts`] and encode it with a
+//! [`SystemObservationEncoder`]; neither has an encoding of its own.
+//!
+//! Version 1 features, in default order:
+//!
+//! | # | name                   | value                                           |
+//! |---|------------------------|-------------------------------------------------|
+//! | 0 | `cpu_usage`            | CPU percent / 100                               |
+//! | 1 | `memory_usage`         | memory percent / 100                            |
+//! | 2 | `disk_usage`           | root disk percent / 100                         |
+//! | 3 | `process_count`        | tanh(processes / 1000)                          |
+//! | 4 | `goal_success_rate`    | successes among recent goals, 0.5 with none     |
+//! | 5 | `avg_execution_time`   | tanh(mean ms of recent goals / 1000), 100 ms with none |
+//! | 6 | `error_count`          | tanh(failures among recent goals / 10)          |
+//! | 7 | `time_since_last_goal` | tanh(seconds / 300), 300 s with no goal yet     |
+//! | 8 | `time_of_day`          | (hour + minute / 60) / 24, UTC                  |
+//! | 9 | `day_of_week`          | days since Monday / 7, UTC                      |
+//!
+//! "Recent goals" are the last [`RECENT_GOALS`]. Changing a feature's meaning
+//! or normalization requires bumping [`OBSERVATION_SPEC_VERSION`].
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Version of the feature definitions above
+pub const OBSERVATION_SPEC_VERSION: u32 = 1;
+
+/// Every feature the encoder can build, in the default order
+pub const OBSERVATION_FEATURES: [&str; 10] = [
+    "cpu_usage",
+    "memory_usage",
+    "disk_usage",
+    "process_count",
+    "goal_success_rate",
+    "avg_execution_time",
+    "error_count",
+    "time_since_last_goal",
+    "time_of_day",
+    "day_of_week",
+];
+
+/// Goals the history features look back over
+pub const RECENT_GOALS: usize = 10;
+
+/// Seconds reported as time since the last goal before any goal ran
+pub const NO_GOAL_SECS: f32 = 300.0;
+
+/// Host metrics at one point in time
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct HostMetrics {
+    /// CPU usage percentage
+    pub cpu_usage: f32,
+    /// Memory usage percentage
+    pub memory_usage: f32,
+    /// Usage percentage of the root filesystem
+    pub disk_usage: f32,
+    /// Number of running processes
+    pub process_count: usize,
+}
+
+/// Source of [`HostMetrics`]
+pub trait HostProbe: Send + Sync {
+    /// Measure the host now
+    fn host_metrics(&self) -> HostMetrics;
+}
+
+/// Host metrics measured with `sysinfo`; what training and the policy
+/// injector both observe unless a test substitutes its own probe
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SysinfoProbe;
+
+impl HostProbe for SysinfoProbe {
+    fn host_metrics(&self) -> HostMetrics {
+        use sysinfo::{Disks, System};
+
+        let mut system = System::new_all();
+        system.refresh_all();
+
+        let cpu_usage = system.global_cpu_info().cpu_usage();
+        let total_mem = system.total_memory() as f32;
+        let used_mem = system.used_memory() as f32;
+        let memory_usage = (used_mem / total_mem) * 100.0;
+
+        // Only the root filesystem counts
+        let disks = Disks::new_with_refreshed_list();
+        let disk_usage = disks
+            .list()
+            .iter()
+            .find(|disk| disk.mount_point() == std::path::Path::new("/"))
+            .map_or(0.0, |disk| {
+                let total = disk.total_space() as f32;
+                let available = disk.available_space() as f32;
+                ((total - available) / total) * 100.0
+            });
+
+        HostMetrics {
+            cpu_usage,
+            memory_usage,
+            disk_usage,
+            process_count: system.processes().len(),
+        }
+    }
+}
+
+/// How one goal went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoalOutcome {
+    /// Whether the goal succeeded
+    pub success: bool,
+    /// How long it took to execute
+    pub execution_time_ms: u64,
+}
+
+/// System state a policy observes, before encoding
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemObservation {
+    /// CPU usage percentage
+    pub cpu_usage: f32,
+    /// Memory usage percentage
+    pub memory_usage: f32,
+    /// Disk usage percentage
+    pub disk_usage: f32,
+    /// Number of active processes
+    pub process_count: usize,
+    /// Recent goal success rate
+    pub goal_success_rate: f32,
+    /// Average goal execution time (ms)
+    pub avg_execution_time: f32,
+    /// Failed goals among the recent ones
+    pub error_count: usize,
+    /// Time since last goal (seconds)
+    pub time_since_last_goal: f32,
+    /// Current time of day (normalized)
+    pub time_of_day: f32,
+    /// Day of week (normalized)
+    pub day_of_week: f32,
+}
+
+impl SystemObservation {
+    /// Observation at `now` of a host measuring `host`, where `goals` are
+    /// the goals run so far, oldest first, and the last one ran at
+    /// `last_goal_at`
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_parts(
+        host: &HostMetrics,
+        goals: &[GoalOutcome],
+        last_goal_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let recent = &goals[goals.len().saturating_sub(RECENT_GOALS)..];
+        let (goal_success_rate, avg_execution_time) = if recent.is_empty() {
+            (0.5, 100.0)
+        } else {
+            let successes = recent.iter().filter(|g| g.success).count();
+            let total_ms: u64 = recent.iter().map(|g| g.execution_time_ms).sum();
+            (
+                successes as f32 / recent.len() as f32,
+                total_ms as f32 / recent.len() as f32,
+            )
+        };
+
+        Self {
+            cpu_usage: host.cpu_usage,
+            memory_usage: host.memory_usage,
+            disk_usage: host.disk_usage,
+            process_count: host.process_count,
+            goal_success_rate,
+            avg_execution_time,
+            error_count: recent.iter().filter(|g| !g.success).count(),
+            time_since_last_goal: last_goal_at.map_or(NO_GOAL_SECS, |at| {
+                (now - at).num_milliseconds().max(0) as f32 / 1000.0
+            }),
+            time_of_day: (now.hour() as f32 + now.minute() as f32 / 60.0) / 24.0,
+            day_of_week: now.weekday().num_days_from_monday() as f32 / 7.0,
+        }
+    }
+}
+
+/// Ordered, named features a policy was trained on. Saved with the
+/// checkpoint so the injector builds inputs in the layout the policy expects.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObservationSpec {
+    /// [`OBSERVATION_SPEC_VERSION`] the features were defined by
+    pub version: u32,
+    /// Feature names, in input order
+    pub features: Vec<String>,
+}
+
+impl Default for ObservationSpec {
+    fn default() -> Self {
+        Self {
+            version: OBSERVATION_SPEC_VERSION,
+            features: OBSERVATION_FEATURES.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+impl ObservationSpec {
+    /// Number of features, i.e. the policy's observation dimension
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    /// Whether the spec has no features
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    /// Check that the encoder can build every feature of the spec
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spec is from another version or names a
+    /// feature this version does not define.
+    pub fn validate(&self) -> Result<()> {
+        if self.version != OBSERVATION_SPEC_VERSION {
+            bail!(
+                "Observation spec version mismatch: policy was trained on v{} features, injector builds v{}",
+                self.version, OBSERVATION_SPEC_VERSION
+            );
+        }
+        let unknown: Vec<&str> = self
+            .features
+            .iter()
+            .map(String::as_str)
+            .filter(|f| !OBSERVATION_FEATURES.contains(f))
+            .collect();
+        if !unknown.is_empty() {
+            bail!(
+                "Observation spec mismatch: policy expects features the injector cannot build: {}",
+                unknown.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Build the policy input from an observation, in spec order
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spec names an unknown feature.
+    pub fn build(&self, obs: &SystemObservation) -> Result<Vec<f32>> {
+        self.features
+            .iter()
+            .map(|name| {
+                feature_value(name, obs)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown observation feature '{name}'"))
+            })
+            .collect()
+    }
+}
+
+/// Turns [`SystemObservation`]s into policy inputs laid out by a validated
+/// [`ObservationSpec`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SystemObservationEncoder {
+    spec: ObservationSpec,
+}
+
+impl SystemObservationEncoder {
+    /// Encoder for `spec`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `spec` fails [`ObservationSpec::validate`].
+    pub fn new(spec: ObservationSpec) -> Result<Self> {
+        spec.validate()?;
+        Ok(Self { spec })
+    }
+
+    /// Layout of the encoded vectors
+    #[must_use]
+    pub fn spec(&self) -> &ObservationSpec {
+        &self.spec
+    }
+
+    /// Length of the encoded vectors
+    #[must_use]
+    pub fn dim(&self) -> usize {
+        self.spec.len()
+    }
+
+    /// `obs` as a policy input
+    #[must_use]
+    pub fn encode(&self, obs: &SystemObservation) -> Vec<f32> {
+        self.spec
+            .features
+            .iter()
+            .map(|name| feature_value(name, obs).unwrap_or_default())
+            .collect()
+    }
+}
+
+/// Normalized value of one named observation feature
+#[allow(clippy::cast_precision_loss)]
+fn feature_value(name: &str, obs: &SystemObservation) -> Option<f32> {
+    let value = match name {
+        "cpu_usage" => obs.cpu_usage / 100.0,
+        "memory_usage" => obs.memory_usage / 100.0,
+        "disk_usage" => obs.disk_usage / 100.0,
+        "process_count" => (obs.process_count as f32 / 1000.0).tanh(),
+        "goal_success_rate" => obs.goal_success_rate,
+        "avg_execution_time" => (obs.avg_execution_time / 1000.0).tanh(),
+        "error_count" => (obs.error_count as f32 / 10.0).tanh(),
+        "time_since_last_goal" => (obs.time_since_last_goal / 300.0).tanh(),
+        "time_of_day" => obs.time_of_day,
+        "day_of_week" => obs.day_of_week,
+        _ => return None,
+    };
+    Some(value)
+}
@@ -1,11 +1,19 @@
 //! Environment wrappers for common transformations
+//!
+//! Every wrapper forwards the inner environment's [`StepInfo`] and adds its
+//! own keys prefixed with the wrapper name (`"TimeLimit.truncated"`), so
+//! diagnostics reported by the environment reach the agent and logger no
+//! matter how deeply it is wrapped.
 
 use async_trait::async_trait;
+use rand_distr::{Distribution, StandardNormal};
 use std::collections::VecDeque;
+use tokio_util::sync::CancellationToken;
 
+use sentient_rl_core::observation::VectorObservation;
 use sentient_rl_core::{
-    Environment, Step, StepInfo, Observation, ObservationSpace,
-    Action, ActionSpace, State, StateSpace, Reward,
+    Environment, EnvironmentConfig, Step, StepInfo, ObservationSpace,
+    ActionSpace, StateSpace, Reward, RLError, SpaceDescriptor,
 };
 
 /// Wrapper that modifies rewards
@@ -44,7 +52,9 @@ where
     
     async fn step(&mut self, action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
         let mut step = self.env.step(action).await?;
-        step.reward = (self.reward_fn)(step.reward, &step);
+        let raw_reward = step.reward;
+        step.reward = (self.reward_fn)(raw_reward, &step);
+        step.info.insert("RewardWrapper.raw_reward", raw_reward.0);
         Ok(step)
     }
     
@@ -87,6 +97,68 @@ pub struct TimeLimit<E> {
     pub steps: usize,
 }
 
+impl<E, F> RewardWrapper<E, F> {
+    /// Create a new reward wrapper
+    pub fn new(env: E, reward_fn: F) -> Self {
+        Self { env, reward_fn }
+    }
+}
+
+impl<E: Environment, F> ActionWrapper<E, F, E::Action> {
+    /// Create a wrapper that rewrites actions (clipping, rescaling) before
+    /// they reach the inner environment
+    pub fn new(env: E, action_fn: F) -> Self {
+        Self {
+            env,
+            action_fn,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+#[async_trait]
+impl<E, F> Environment for ActionWrapper<E, F, E::Action>
+where
+    E: Environment,
+    F: Fn(E::Action) -> E::Action + Send + Sync,
+{
+    type Observation = E::Observation;
+    type Action = E::Action;
+    type State = E::State;
+    
+    fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+        self.env.observation_space()
+    }
+    
+    fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+        self.env.action_space()
+    }
+    
+    fn state_space(&self) -> Option<Box<dyn StateSpace<State = Self::State>>> {
+        self.env.state_space()
+    }
+    
+    async fn reset(&mut self) -> sentient_rl_core::Result<(Self::Observation, StepInfo)> {
+        self.env.reset().await
+    }
+    
+    async fn step(&mut self, action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
+        let action = (self.action_fn)(action);
+        let applied = format!("{action:?}");
+        let mut step = self.env.step(action).await?;
+        step.info.insert("ActionWrapper.action", applied);
+        Ok(step)
+    }
+    
+    async fn render(&self) -> sentient_rl_core::Result<()> {
+        self.env.render().await
+    }
+    
+    async fn close(&mut self) -> sentient_rl_core::Result<()> {
+        self.env.close().await
+    }
+}
+
 impl<E> TimeLimit<E> {
     /// Create a new time limit wrapper
     pub fn new(env: E, max_steps: usize) -> Self {
@@ -128,10 +200,13 @@ where
         self.steps += 1;
         let mut step = self.env.step(action).await?;
         
-        if self.steps >= self.max_steps && !step.done {
+        let hit_limit = self.steps >= self.max_steps && !step.done;
+        if hit_limit {
             step.truncated = true;
             step.done = true;
         }
+        step.info.insert("TimeLimit.truncated", hit_limit);
+        step.info.insert("TimeLimit.elapsed_steps", self.steps);
         
         Ok(step)
     }
@@ -145,8 +220,91 @@ where
     }
 }
 
+/// Wrapper that clamps rewards to [`EnvironmentConfig::reward_clip`]
+///
+/// The unclipped reward is reported as `"ClipReward.raw_reward"`.
+pub struct ClipReward<E> {
+    /// Inner environment
+    pub env: E,
+    /// Lowest reward passed on
+    pub low: f64,
+    /// Highest reward passed on
+    pub high: f64,
+}
+
+impl<E> ClipReward<E> {
+    /// Clamp rewards of `env` to `[low, high]`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a bound is NaN or `low` is above `high`.
+    pub fn new(env: E, (low, high): (f32, f32)) -> sentient_rl_core::Result<Self> {
+        if low.is_nan() || high.is_nan() || low > high {
+            return Err(RLError::Environment(format!(
+                "Invalid reward clip [{low}, {high}]"
+            )));
+        }
+        Ok(Self {
+            env,
+            low: f64::from(low),
+            high: f64::from(high),
+        })
+    }
+
+    /// Clamp rewards of `env` as `config` asks; without a `reward_clip`
+    /// every reward passes through
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configured bounds are invalid.
+    pub fn from_config(env: E, config: &EnvironmentConfig) -> sentient_rl_core::Result<Self> {
+        Self::new(env, config.reward_clip.unwrap_or((f32::NEG_INFINITY, f32::INFINITY)))
+    }
+}
+
+#[async_trait]
+impl<E> Environment for ClipReward<E>
+where
+    E: Environment,
+{
+    type Observation = E::Observation;
+    type Action = E::Action;
+    type State = E::State;
+
+    fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+        self.env.observation_space()
+    }
+
+    fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+        self.env.action_space()
+    }
+
+    fn state_space(&self) -> Option<Box<dyn StateSpace<State = Self::State>>> {
+        self.env.state_space()
+    }
+
+    async fn reset(&mut self) -> sentient_rl_core::Result<(Self::Observation, StepInfo)> {
+        self.env.reset().await
+    }
+
+    async fn step(&mut self, action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
+        let mut step = self.env.step(action).await?;
+        step.info.insert("ClipReward.raw_reward", step.reward.0);
+        step.reward = step.reward.clip(self.low, self.high);
+        Ok(step)
+    }
+
+    async fn render(&self) -> sentient_rl_core::Result<()> {
+        self.env.render().await
+    }
+
+    async fn close(&mut self) -> sentient_rl_core::Result<()> {
+        self.env.close().await
+    }
+}
+
 /// Frame stacking wrapper for temporal information
-pub struct FrameStack<E> {
+pub struct FrameStack<E: Environment> {
     /// Inner environment
     pub env: E,
     /// Number of frames to stack
@@ -157,6 +315,7 @@ pub struct FrameStack<E> {
 
 impl<E> FrameStack<E>
 where
+    E: Environment,
     E::Observation: Clone,
 {
     /// Create a new frame stack wrapper
@@ -169,6 +328,115 @@ where
     }
 }
 
+impl<E> FrameStack<E>
+where
+    E: Environment<Observation = VectorObservation>,
+{
+    /// Oldest-to-newest frames concatenated into one observation
+    fn stacked(&self) -> VectorObservation {
+        VectorObservation {
+            data: self.frames.iter().flat_map(|frame| frame.data.iter().copied()).collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl<E> Environment for FrameStack<E>
+where
+    E: Environment<Observation = VectorObservation>,
+{
+    type Observation = VectorObservation;
+    type Action = E::Action;
+    type State = E::State;
+    
+    fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+        Box::new(StackedSpace {
+            inner: self.env.observation_space(),
+            n_frames: self.n_frames,
+        })
+    }
+    
+    fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+        self.env.action_space()
+    }
+    
+    fn state_space(&self) -> Option<Box<dyn StateSpace<State = Self::State>>> {
+        self.env.state_space()
+    }
+    
+    async fn reset(&mut self) -> sentient_rl_core::Result<(Self::Observation, StepInfo)> {
+        let (obs, info) = self.env.reset().await?;
+        
+        // Pad the history with the first frame until it fills up
+        self.frames.clear();
+        self.frames.extend(std::iter::repeat_n(obs, self.n_frames));
+        
+        Ok((self.stacked(), info.with("FrameStack.n_frames", self.n_frames)))
+    }
+    
+    async fn step(&mut self, action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
+        let mut step = self.env.step(action).await?;
+        
+        if self.frames.len() >= self.n_frames {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(step.observation);
+        step.observation = self.stacked();
+        step.info.insert("FrameStack.n_frames", self.n_frames);
+        
+        Ok(step)
+    }
+    
+    async fn render(&self) -> sentient_rl_core::Result<()> {
+        self.env.render().await
+    }
+    
+    async fn close(&mut self) -> sentient_rl_core::Result<()> {
+        self.env.close().await
+    }
+}
+
+/// Observation space of `n_frames` concatenated copies of an inner space
+struct StackedSpace {
+    inner: Box<dyn ObservationSpace<Observation = VectorObservation>>,
+    n_frames: usize,
+}
+
+impl ObservationSpace for StackedSpace {
+    type Observation = VectorObservation;
+    
+    fn sample(&self) -> Self::Observation {
+        VectorObservation {
+            data: (0..self.n_frames).flat_map(|_| self.inner.sample().data).collect(),
+        }
+    }
+    
+    fn contains(&self, obs: &Self::Observation) -> bool {
+        let frame_len: usize = self.inner.shape().iter().product();
+        obs.data.len() == frame_len * self.n_frames
+            && (frame_len == 0
+                || obs.data.chunks(frame_len).all(|frame| {
+                    self.inner.contains(&VectorObservation { data: frame.to_vec() })
+                }))
+    }
+    
+    fn shape(&self) -> Vec<usize> {
+        vec![self.inner.shape().iter().product::<usize>() * self.n_frames]
+    }
+    
+    /// The inner box's bounds, repeated for each frame
+    fn descriptor(&self) -> Option<SpaceDescriptor> {
+        match self.inner.descriptor()? {
+            SpaceDescriptor::Box { low, high, .. } => Some(SpaceDescriptor::Box {
+                low: low.repeat(self.n_frames),
+                high: high.repeat(self.n_frames),
+                shape: self.shape(),
+            }),
+            _ => None,
+        }
+    }
+}
+
 /// Observation normalization wrapper
 pub struct Normalize<E> {
     /// Inner environment
@@ -225,4 +493,321 @@ impl<E> Normalize<E> {
         
         normalized
     }
-}
\ No newline at end of file
+}
+
+#[async_trait]
+impl<E> Environment for Normalize<E>
+where
+    E: Environment<Observation = VectorObservation>,
+{
+    type Observation = VectorObservation;
+    type Action = E::Action;
+    type State = E::State;
+    
+    fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+        Box::new(NormalizedSpace {
+            dim: self.mean.len(),
+            clip_range: self.clip_range,
+        })
+    }
+    
+    fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+        self.env.action_space()
+    }
+    
+    fn state_space(&self) -> Option<Box<dyn StateSpace<State = Self::State>>> {
+        self.env.state_space()
+    }
+    
+    async fn reset(&mut self) -> sentient_rl_core::Result<(Self::Observation, StepInfo)> {
+        let (obs, mut info) = self.env.reset().await?;
+        self.update(&obs.data);
+        info.insert("Normalize.raw_observation", obs.data.clone());
+        Ok((VectorObservation { data: self.normalize(&obs.data) }, info))
+    }
+    
+    async fn step(&mut self, action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
+        let mut step = self.env.step(action).await?;
+        self.update(&step.observation.data);
+        let normalized = self.normalize(&step.observation.data);
+        let raw = std::mem::replace(&mut step.observation.data, normalized);
+        step.info.insert("Normalize.raw_observation", raw);
+        Ok(step)
+    }
+    
+    async fn render(&self) -> sentient_rl_core::Result<()> {
+        self.env.render().await
+    }
+    
+    async fn close(&mut self) -> sentient_rl_core::Result<()> {
+        self.env.close().await
+    }
+}
+
+/// Observation space of normalized vectors: roughly standard normal, within
+/// the clip range when one is set
+struct NormalizedSpace {
+    dim: usize,
+    clip_range: Option<(f64, f64)>,
+}
+
+impl ObservationSpace for NormalizedSpace {
+    type Observation = VectorObservation;
+    
+    fn sample(&self) -> Self::Observation {
+        let mut rng = rand::thread_rng();
+        let data = (0..self.dim)
+            .map(|_| {
+                let z: f64 = StandardNormal.sample(&mut rng);
+                self.clip_range.map_or(z, |(min, max)| z.clamp(min, max))
+            })
+            .collect();
+        VectorObservation { data }
+    }
+    
+    fn contains(&self, obs: &Self::Observation) -> bool {
+        obs.data.len() == self.dim
+            && self.clip_range.is_none_or(|(min, max)| {
+                obs.data.iter().all(|&z| (min..=max).contains(&z))
+            })
+    }
+    
+    fn shape(&self) -> Vec<usize> {
+        vec![self.dim]
+    }
+    
+    /// A box bounded by the clip range, unbounded without one
+    fn descriptor(&self) -> Option<SpaceDescriptor> {
+        let (low, high) = self.clip_range.unwrap_or((f64::NEG_INFINITY, f64::INFINITY));
+        Some(SpaceDescriptor::Box {
+            low: vec![low; self.dim],
+            high: vec![high; self.dim],
+            shape: self.shape(),
+        })
+    }
+}
+
+/// Wrapper that aborts in-flight `reset`/`step` calls once a token is
+/// cancelled
+///
+/// The inner future is dropped rather than awaited, so work it owns (a child
+/// process started with `kill_on_drop`, a pending request) stops with it.
+/// Calls made after cancellation fail immediately with
+/// [`RLError::Cancelled`].
+pub struct Cancellable<E> {
+    /// Inner environment
+    pub env: E,
+    /// Token that aborts in-flight calls
+    pub token: CancellationToken,
+}
+
+impl<E> Cancellable<E> {
+    /// Create a wrapper aborting the inner environment's calls on `token`
+    pub fn new(env: E, token: CancellationToken) -> Self {
+        Self { env, token }
+    }
+}
+
+/// Run `work` until it finishes or `token` is cancelled, whichever is first
+///
+/// # Errors
+///
+/// [`RLError::Cancelled`] naming `operation` if the token fires first,
+/// otherwise whatever `work` returns.
+pub async fn run_cancellable<T, Err>(
+    token: &CancellationToken,
+    operation: &str,
+    work: impl std::future::Future<Output = std::result::Result<T, Err>>,
+) -> std::result::Result<T, Err>
+where
+    Err: From<RLError>,
+{
+    if token.is_cancelled() {
+        return Err(RLError::Cancelled(format!("{operation} not started")).into());
+    }
+    tokio::select! {
+        biased;
+        () = token.cancelled() => Err(RLError::Cancelled(format!("{operation} aborted")).into()),
+        result = work => result,
+    }
+}
+
+#[async_trait]
+impl<E> Environment for Cancellable<E>
+where
+    E: Environment,
+{
+    type Observation = E::Observation;
+    type Action = E::Action;
+    type State = E::State;
+    
+    fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+        self.env.observation_space()
+    }
+    
+    fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+        self.env.action_space()
+    }
+    
+    fn state_space(&self) -> Option<Box<dyn StateSpace<State = Self::State>>> {
+        self.env.state_space()
+    }
+    
+    async fn reset(&mut self) -> sentient_rl_core::Result<(Self::Observation, StepInfo)> {
+        run_cancellable(&self.token, "reset", self.env.reset()).await
+    }
+    
+    async fn step(&mut self, action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
+        run_cancellable(&self.token, "step", self.env.step(action)).await
+    }
+    
+    async fn render(&self) -> sentient_rl_core::Result<()> {
+        self.env.render().await
+    }
+    
+    async fn close(&mut self) -> sentient_rl_core::Result<()> {
+        self.env.close().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentient_rl_core::action::{DiscreteAction, DiscreteSpace};
+    use sentient_rl_core::observation::BoxObservationSpace;
+    use sentient_rl_core::state::VectorState;
+
+    /// One-dimensional environment that reports which command it ran
+    struct ProbeEnv {
+        t: f64,
+    }
+
+    #[async_trait]
+    impl Environment for ProbeEnv {
+        type Observation = VectorObservation;
+        type Action = DiscreteAction;
+        type State = VectorState;
+
+        fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = Self::Observation>> {
+            Box::new(BoxObservationSpace::new(vec![0.0], vec![100.0], vec![1]).unwrap())
+        }
+
+        fn action_space(&self) -> Box<dyn ActionSpace<Action = Self::Action>> {
+            Box::new(DiscreteSpace::new(2))
+        }
+
+        async fn reset(&mut self) -> sentient_rl_core::Result<(Self::Observation, StepInfo)> {
+            self.t = 0.0;
+            Ok((VectorObservation { data: vec![self.t] }, StepInfo::default().with("command", "reset")))
+        }
+
+        async fn step(&mut self, action: Self::Action) -> sentient_rl_core::Result<Step<Self::Observation, Self::State>> {
+            self.t += 1.0;
+            Ok(Step {
+                observation: VectorObservation { data: vec![self.t] },
+                reward: Reward(1.0),
+                done: false,
+                truncated: false,
+                info: StepInfo::default()
+                    .with("command", format!("run {}", action.0))
+                    .with("execution_time_ms", 12),
+                state: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inner_info_survives_wrapper_stack() {
+        let stacked = FrameStack::new(ProbeEnv { t: 0.0 }, 3);
+        let limited = TimeLimit::new(stacked, 2);
+        let mut env = RewardWrapper::new(limited, |reward: Reward, _: &Step<VectorObservation, VectorState>| {
+            Reward(reward.0 * 0.5)
+        });
+
+        let (obs, info) = env.reset().await.unwrap();
+        assert_eq!(obs.data, vec![0.0, 0.0, 0.0]);
+        assert_eq!(info.get("command"), Some(&serde_json::json!("reset")));
+        assert_eq!(info.get("FrameStack.n_frames"), Some(&serde_json::json!(3)));
+
+        let step = env.step(DiscreteAction(1)).await.unwrap();
+        assert_eq!(step.observation.data, vec![0.0, 0.0, 1.0]);
+        assert_eq!(step.info.get("command"), Some(&serde_json::json!("run 1")));
+        assert_eq!(step.info.get("execution_time_ms"), Some(&serde_json::json!(12)));
+        assert_eq!(step.info.get("TimeLimit.truncated"), Some(&serde_json::json!(false)));
+        assert_eq!(step.info.get("RewardWrapper.raw_reward"), Some(&serde_json::json!(1.0)));
+        assert!((step.reward.0 - 0.5).abs() < f64::EPSILON);
+
+        let step = env.step(DiscreteAction(0)).await.unwrap();
+        assert!(step.truncated && step.done);
+        assert_eq!(step.info.get("command"), Some(&serde_json::json!("run 0")));
+        assert_eq!(step.info.get("TimeLimit.truncated"), Some(&serde_json::json!(true)));
+        assert_eq!(step.info.get("TimeLimit.elapsed_steps"), Some(&serde_json::json!(2)));
+    }
+
+    #[tokio::test]
+    async fn test_clip_reward_clamps_to_configured_bounds() {
+        let config = EnvironmentConfig {
+            reward_clip: Some((-0.25, 0.25)),
+            ..EnvironmentConfig::default()
+        };
+        let mut env = ClipReward::from_config(ProbeEnv { t: 0.0 }, &config).unwrap();
+        env.reset().await.unwrap();
+
+        let step = env.step(DiscreteAction(0)).await.unwrap();
+        assert!((step.reward.0 - 0.25).abs() < f64::EPSILON);
+        assert_eq!(step.info.get("ClipReward.raw_reward"), Some(&serde_json::json!(1.0)));
+        assert_eq!(step.info.get("command"), Some(&serde_json::json!("run 0")));
+
+        let mut unclipped = ClipReward::from_config(ProbeEnv { t: 0.0 }, &EnvironmentConfig::default()).unwrap();
+        assert!((unclipped.step(DiscreteAction(0)).await.unwrap().reward.0 - 1.0).abs() < f64::EPSILON);
+
+        assert!(ClipReward::new(ProbeEnv { t: 0.0 }, (1.0, -1.0)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_normalize_and_action_wrappers_keep_info() {
+        let mut env = ActionWrapper::new(
+            Normalize::new(ProbeEnv { t: 0.0 }, 1),
+            |action: DiscreteAction| DiscreteAction(action.0.min(1)),
+        );
+        env.reset().await.unwrap();
+
+        let step = env.step(DiscreteAction(7)).await.unwrap();
+        assert_eq!(step.info.get("command"), Some(&serde_json::json!("run 1")));
+        assert_eq!(step.info.get("ActionWrapper.action"), Some(&serde_json::json!("DiscreteAction(1)")));
+        assert_eq!(step.info.get("Normalize.raw_observation"), Some(&serde_json::json!([1.0])));
+        assert!(env.observation_space().contains(&step.observation));
+    }
+
+    #[test]
+    fn test_wrapper_spaces_have_descriptors() {
+        let stacked = FrameStack::new(ProbeEnv { t: 0.0 }, 3);
+        assert_eq!(
+            stacked.observation_space().descriptor(),
+            Some(SpaceDescriptor::Box {
+                low: vec![0.0; 3],
+                high: vec![100.0; 3],
+                shape: vec![3],
+            })
+        );
+
+        let mut normalized = Normalize::new(ProbeEnv { t: 0.0 }, 1);
+        assert_eq!(
+            normalized.observation_space().descriptor(),
+            Some(SpaceDescriptor::Box {
+                low: vec![-5.0],
+                high: vec![5.0],
+                shape: vec![1],
+            })
+        );
+        normalized.clip_range = None;
+        assert_eq!(
+            normalized.observation_space().descriptor(),
+            Some(SpaceDescriptor::Box {
+                low: vec![f64::NEG_INFINITY],
+                high: vec![f64::INFINITY],
+                shape: vec![1],
+            })
+        );
+    }
+}
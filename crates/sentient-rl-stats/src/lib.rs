@@ -0,0 +1,168 @@
+//! Episode return statistics
+//!
+//! The web dashboard and `sentientctl rl reward-graph` both summarise the
+//! same reward series, so the math lives here once and both views agree.
+
+#![warn(missing_docs)]
+#![warn(clippy::all)]
+#![warn(clippy::pedantic)]
+#![allow(clippy::cast_precision_loss)]
+
+/// Trailing moving average over `window` values, one output per input.
+///
+/// Early points average over what is available, so a window longer than
+/// the series is a running mean. Windows of 0 and 1 return the input.
+#[must_use]
+pub fn moving_average(values: &[f64], window: usize) -> Vec<f64> {
+    let window = window.max(1);
+    let mut sum = 0.0;
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            sum += value;
+            if i >= window {
+                sum -= values[i - window];
+            }
+            sum / (i + 1).min(window) as f64
+        })
+        .collect()
+}
+
+/// Exponential moving average with smoothing factor `2 / (window + 1)`,
+/// seeded with the first value
+#[must_use]
+pub fn exponential_moving_average(values: &[f64], window: usize) -> Vec<f64> {
+    let alpha = 2.0 / (window.max(1) as f64 + 1.0);
+    let mut average = None;
+    values
+        .iter()
+        .map(|&value| {
+            let next = match average {
+                Some(previous) => previous + alpha * (value - previous),
+                None => value,
+            };
+            average = Some(next);
+            next
+        })
+        .collect()
+}
+
+/// Most bins [`histogram`] splits a series into
+pub const MAX_BUCKETS: usize = 200;
+
+/// One equal-width bin of a histogram, covering `[low, high)`; the last
+/// bin also includes `high`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bucket {
+    /// Lower edge
+    pub low: f64,
+    /// Upper edge
+    pub high: f64,
+    /// Values that fell in the bin
+    pub count: usize,
+}
+
+/// Bucket `values` into `buckets` equal-width bins spanning their range,
+/// with `buckets` clamped to `1..=MAX_BUCKETS`.
+///
+/// Non-finite values are ignored; a series with a single distinct value
+/// yields one bin holding all of it, and an empty series no bins.
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::float_cmp)]
+pub fn histogram(values: &[f64], buckets: usize) -> Vec<Bucket> {
+    let finite: Vec<f64> = values.iter().copied().filter(|v| v.is_finite()).collect();
+    let Some(&first) = finite.first() else {
+        return Vec::new();
+    };
+    let (min, max) = finite
+        .iter()
+        .fold((first, first), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    if min == max {
+        return vec![Bucket {
+            low: min,
+            high: max,
+            count: finite.len(),
+        }];
+    }
+
+    let buckets = buckets.clamp(1, MAX_BUCKETS);
+    let width = (max - min) / buckets as f64;
+    let mut bins: Vec<Bucket> = (0..buckets)
+        .map(|i| Bucket {
+            low: min + width * i as f64,
+            high: if i + 1 == buckets {
+                max
+            } else {
+                min + width * (i + 1) as f64
+            },
+            count: 0,
+        })
+        .collect();
+    for v in finite {
+        let index = (((v - min) / width) as usize).min(buckets - 1);
+        bins[index].count += 1;
+    }
+    bins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn test_moving_average_matches_hand_computed_values() {
+        let raw = [2.0, 8.0, 2.0, 8.0, 5.0, -1.0, 4.0];
+        // (2)/1, (2+8)/2, (2+8+2)/3, (8+2+8)/3, (2+8+5)/3, (8+5-1)/3, (5-1+4)/3
+        assert_close(
+            &moving_average(&raw, 3),
+            &[2.0, 5.0, 4.0, 6.0, 5.0, 4.0, 8.0 / 3.0],
+        );
+        assert_close(&moving_average(&raw, 100)[..3], &[2.0, 5.0, 4.0]);
+        assert_eq!(moving_average(&raw, 0), raw);
+        assert!(moving_average(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_ema_weights_recent_values() {
+        // window 3 -> alpha 0.5
+        assert_close(
+            &exponential_moving_average(&[4.0, 0.0, 2.0, 6.0], 3),
+            &[4.0, 2.0, 2.0, 4.0],
+        );
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn test_histogram_separates_bimodal_returns() {
+        let mut returns = Vec::new();
+        for i in 0..15 {
+            returns.push(-20.0 + f64::from(i) * 0.1);
+            returns.push(20.0 - f64::from(i) * 0.1);
+        }
+
+        let bins = histogram(&returns, 8);
+        let counts: Vec<usize> = bins.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![15, 0, 0, 0, 0, 0, 0, 15]);
+        assert_eq!(bins[0].low, -20.0);
+        assert_eq!(bins[7].high, 20.0);
+    }
+
+    #[test]
+    fn test_histogram_degenerate_inputs() {
+        assert!(histogram(&[], 5).is_empty());
+        assert_eq!(
+            histogram(&[1.0, 1.0, f64::NAN], 5),
+            vec![Bucket { low: 1.0, high: 1.0, count: 2 }]
+        );
+        assert_eq!(histogram(&[0.0, 1.0], 0).len(), 1);
+        assert_eq!(histogram(&[0.0, 1.0], usize::MAX).len(), MAX_BUCKETS);
+    }
+}
@@ -10,6 +10,7 @@ use uefi::CStr16;
 use uefi::Identify;
 
 use crate::boot_info::InferenceConfig;
+use crate::load_error::{LoadError, LoadStage};
 
 pub fn get_esp_volume(boot_services: &BootServices) -> Result<Handle, Status> {
     let handles = boot_services
@@ -30,38 +31,13 @@ pub fn get_esp_volume(boot_services: &BootServices) -> Result<Handle, Status> {
 pub fn load_config(
     boot_services: &BootServices,
     esp_handle: &Handle,
-) -> Result<InferenceConfig, Status> {
-    let mut sfs = boot_services
-        .open_protocol_exclusive::<SimpleFileSystem>(*esp_handle)
-        .map_err(|e| e.status())?;
-    let mut root = sfs.open_volume().map_err(|e| e.status())?;
-
-    let config_path = cstr16!("\\inference_config.json");
-    let file = root
-        .open(config_path, FileMode::Read, FileAttribute::empty())
-        .map_err(|e| e.status())?;
-
-    let mut file = file.into_regular_file().unwrap();
-
-    let info_size = file
-        .get_info::<FileInfo>(&mut [])
-        .unwrap_err()
-        .data()
-        .unwrap();
-    let mut info_buffer = alloc::vec![0; info_size];
-
-    let file_info = file
-        .get_info::<FileInfo>(&mut info_buffer)
-        .map_err(|e| e.status())?;
-    let file_size = file_info.file_size() as usize;
-
-    let mut buffer = alloc::vec![0; file_size];
-
-    file.read(&mut buffer).map_err(|e| e.status())?;
+) -> Result<InferenceConfig, LoadError> {
+    let path = "\\inference_config.json";
+    let buffer = read_file(boot_services, esp_handle, path, usize::MAX)?;
 
     let config_str = String::from_utf8_lossy(&buffer);
-    let config: InferenceConfig =
-        serde_json::from_str(&config_str).map_err(|_| Status::INVALID_PARAMETER)?;
+    let config: InferenceConfig = serde_json::from_str(&config_str)
+        .map_err(|e| LoadError::parse(path, e.line(), e.column()))?;
 
     info!("Loaded config: {config:?}");
     serial_println!(
@@ -76,82 +52,79 @@ pub fn load_model(
     boot_services: &BootServices,
     esp_handle: &Handle,
     model_path: &str,
-) -> Result<Vec<u8>, Status> {
-    let mut sfs = boot_services
-        .open_protocol_exclusive::<SimpleFileSystem>(*esp_handle)
-        .map_err(|e| e.status())?;
-    let mut root = sfs.open_volume().map_err(|e| e.status())?;
-
-    let path_vec = crate::str_to_cstr16(model_path);
-    let path_cstr = CStr16::from_u16_with_nul(&path_vec).unwrap();
-
-    let file = root
-        .open(path_cstr, FileMode::Read, FileAttribute::empty())
-        .map_err(|e| e.status())?;
-
-    let mut file = file.into_regular_file().unwrap();
-
-    let info_size = file
-        .get_info::<FileInfo>(&mut [])
-        .unwrap_err()
-        .data()
-        .unwrap();
-    let mut info_buffer = alloc::vec![0; info_size];
-
-    let file_info = file
-        .get_info::<FileInfo>(&mut info_buffer)
-        .map_err(|e| e.status())?;
-    let file_size = file_info.file_size() as usize;
+) -> Result<Vec<u8>, LoadError> {
+    let buffer = read_file(boot_services, esp_handle, model_path, 1024 * 1024)?;
 
-    let mut buffer = alloc::vec![0; file_size];
-
-    let mut offset = 0;
-    while offset < file_size {
-        let chunk_size = (file_size - offset).min(1024 * 1024);
-        file.read(&mut buffer[offset..offset + chunk_size])
-            .map_err(|e| e.status())?;
-        offset += chunk_size;
-    }
-
-    info!("Loaded model: {file_size} bytes");
+    info!("Loaded model: {} bytes", buffer.len());
     serial_println!(
         "🧠 Loaded AI model: {} bytes from {}",
-        file_size,
+        buffer.len(),
         model_path
     );
     Ok(buffer)
 }
 
-pub fn load_kernel(boot_services: &BootServices, esp_handle: &Handle) -> Result<Vec<u8>, Status> {
+pub fn load_kernel(
+    boot_services: &BootServices,
+    esp_handle: &Handle,
+) -> Result<Vec<u8>, LoadError> {
+    let buffer = read_file(boot_services, esp_handle, "\\kernel.efi", usize::MAX)?;
+
+    info!("Loaded kernel: {} bytes", buffer.len());
+    serial_println!("🔧 Loaded kernel.efi: {} bytes", buffer.len());
+    Ok(buffer)
+}
+
+/// Read a whole file from the ESP in reads of at most `chunk_size` bytes
+fn read_file(
+    boot_services: &BootServices,
+    esp_handle: &Handle,
+    path: &str,
+    chunk_size: usize,
+) -> Result<Vec<u8>, LoadError> {
+    let fail = |stage: LoadStage, status: Status| LoadError::status(stage, path, status.0);
+
     let mut sfs = boot_services
         .open_protocol_exclusive::<SimpleFileSystem>(*esp_handle)
-        .map_err(|e| e.status())?;
-    let mut root = sfs.open_volume().map_err(|e| e.status())?;
-
-    let kernel_path = cstr16!("\\kernel.efi");
-    let file = root
-        .open(kernel_path, FileMode::Read, FileAttribute::empty())
-        .map_err(|e| e.status())?;
+        .map_err(|e| fail(LoadStage::Volume, e.status()))?;
+    let mut root = sfs
+        .open_volume()
+        .map_err(|e| fail(LoadStage::Volume, e.status()))?;
 
-    let mut file = file.into_regular_file().unwrap();
+    let path_vec = crate::str_to_cstr16(path);
+    let path_cstr = CStr16::from_u16_with_nul(&path_vec)
+        .map_err(|_| fail(LoadStage::Open, Status::INVALID_PARAMETER))?;
 
-    let info_size = file
-        .get_info::<FileInfo>(&mut [])
-        .unwrap_err()
-        .data()
-        .unwrap();
+    let file = root
+        .open(path_cstr, FileMode::Read, FileAttribute::empty())
+        .map_err(|e| fail(LoadStage::Open, e.status()))?;
+
+    let mut file = file
+        .into_regular_file()
+        .ok_or_else(|| fail(LoadStage::Open, Status::INVALID_PARAMETER))?;
+
+    // An empty buffer makes get_info report the size it needs
+    let info_size = match file.get_info::<FileInfo>(&mut []) {
+        Err(e) if e.status() == Status::BUFFER_TOO_SMALL => e.data().unwrap_or(0),
+        Err(e) => return Err(fail(LoadStage::Stat, e.status())),
+        Ok(_) => 0,
+    };
     let mut info_buffer = alloc::vec![0; info_size];
 
     let file_info = file
         .get_info::<FileInfo>(&mut info_buffer)
-        .map_err(|e| e.status())?;
+        .map_err(|e| fail(LoadStage::Stat, e.status()))?;
     let file_size = file_info.file_size() as usize;
 
     let mut buffer = alloc::vec![0; file_size];
 
-    file.read(&mut buffer).map_err(|e| e.status())?;
+    let mut offset = 0;
+    while offset < file_size {
+        let end = offset + (file_size - offset).min(chunk_size);
+        file.read(&mut buffer[offset..end])
+            .map_err(|e| fail(LoadStage::Read, e.status()))?;
+        offset = end;
+    }
 
-    info!("Loaded kernel: {file_size} bytes");
-    serial_println!("🔧 Loaded kernel.efi: {} bytes", file_size);
     Ok(buffer)
 }
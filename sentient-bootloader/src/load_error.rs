@@ -0,0 +1,219 @@
+//! Structured errors for loading files from the ESP
+//!
+//! A [`LoadError`] records which file failed, at which stage, and the UEFI
+//! status (or parse position) behind it. The path is kept in a fixed buffer so
+//! the error can be built and logged without allocating, even when the failure
+//! is an allocation.
+//!
+//! This module only depends on `core`, so its tests run on the host:
+//! `rustc --edition 2021 --test src/load_error.rs && ./load_error`
+
+use core::fmt;
+
+/// Longest path kept verbatim; longer paths keep their tail
+const MAX_PATH: usize = 64;
+const ELLIPSIS: &str = "...";
+
+/// High bit set on every UEFI error status
+const ERROR_BIT: usize = 1 << (usize::BITS - 1);
+
+/// Step of loading a file that failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadStage {
+    /// Locating or opening the ESP filesystem
+    Volume,
+    /// Opening the file on the volume
+    Open,
+    /// Querying the file size
+    Stat,
+    /// Reading the file's contents
+    Read,
+    /// Parsing the contents that were read
+    Parse,
+}
+
+impl LoadStage {
+    fn as_str(self) -> &'static str {
+        match self {
+            LoadStage::Volume => "open volume for",
+            LoadStage::Open => "open",
+            LoadStage::Stat => "stat",
+            LoadStage::Read => "read",
+            LoadStage::Parse => "parse",
+        }
+    }
+}
+
+/// Why a stage failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadCause {
+    /// Raw UEFI status code
+    Status(usize),
+    /// Malformed content at a 1-based line and column
+    Parse {
+        /// 1-based line of the first malformed byte
+        line: usize,
+        /// 1-based column of the first malformed byte
+        column: usize,
+    },
+}
+
+/// Failure to load a file from the ESP, displayed as
+/// `<stage> <path> failed: <cause>`
+#[derive(Clone, Copy)]
+pub struct LoadError {
+    stage: LoadStage,
+    cause: LoadCause,
+    path: [u8; MAX_PATH],
+    path_len: usize,
+    truncated: bool,
+}
+
+impl LoadError {
+    /// Failure of `stage` on `path`; paths beyond `MAX_PATH` bytes keep their tail
+    pub fn new(stage: LoadStage, path: &str, cause: LoadCause) -> Self {
+        let mut error = LoadError {
+            stage,
+            cause,
+            path: [0; MAX_PATH],
+            path_len: 0,
+            truncated: false,
+        };
+
+        // Keep the end of long paths: the file name is the useful part
+        let mut start = 0;
+        if path.len() > MAX_PATH {
+            start = path.len() - (MAX_PATH - ELLIPSIS.len());
+            while !path.is_char_boundary(start) {
+                start += 1;
+            }
+            error.truncated = true;
+        }
+        let tail = &path.as_bytes()[start..];
+        error.path[..tail.len()].copy_from_slice(tail);
+        error.path_len = tail.len();
+        error
+    }
+
+    /// Failure reported by a UEFI call
+    pub fn status(stage: LoadStage, path: &str, status: usize) -> Self {
+        Self::new(stage, path, LoadCause::Status(status))
+    }
+
+    /// Failure to parse the file's contents
+    pub fn parse(path: &str, line: usize, column: usize) -> Self {
+        Self::new(LoadStage::Parse, path, LoadCause::Parse { line, column })
+    }
+
+    /// Step that failed
+    pub fn stage(&self) -> LoadStage {
+        self.stage
+    }
+
+    /// Why it failed
+    pub fn cause(&self) -> LoadCause {
+        self.cause
+    }
+
+    /// Path of the file; long paths are cut down to their tail
+    pub fn path(&self) -> &str {
+        // Built from a `&str` cut at a char boundary
+        core::str::from_utf8(&self.path[..self.path_len]).unwrap_or("<invalid path>")
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ellipsis = if self.truncated { ELLIPSIS } else { "" };
+        write!(
+            f,
+            "{} {}{} failed: ",
+            self.stage.as_str(),
+            ellipsis,
+            self.path()
+        )?;
+        match self.cause {
+            LoadCause::Status(code) => match status_name(code) {
+                Some(name) => write!(f, "{name} (0x{code:x})"),
+                None => write!(f, "status 0x{code:x}"),
+            },
+            LoadCause::Parse { line, column } => {
+                write!(f, "invalid JSON at line {line}, column {column}")
+            }
+        }
+    }
+}
+
+impl fmt::Debug for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Name of a UEFI status code commonly hit while loading files
+pub fn status_name(code: usize) -> Option<&'static str> {
+    if code & ERROR_BIT == 0 {
+        return if code == 0 { Some("SUCCESS") } else { None };
+    }
+    Some(match code & !ERROR_BIT {
+        1 => "LOAD_ERROR",
+        2 => "INVALID_PARAMETER",
+        3 => "UNSUPPORTED",
+        5 => "BUFFER_TOO_SMALL",
+        7 => "DEVICE_ERROR",
+        9 => "OUT_OF_RESOURCES",
+        10 => "VOLUME_CORRUPTED",
+        12 => "NO_MEDIA",
+        14 => "NOT_FOUND",
+        15 => "ACCESS_DENIED",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_failures_name_file_and_stage() {
+        let missing = LoadError::status(LoadStage::Open, "\\kernel.efi", ERROR_BIT | 14);
+        assert_eq!(
+            missing.to_string(),
+            "open \\kernel.efi failed: NOT_FOUND (0x800000000000000e)"
+        );
+
+        let read = LoadError::status(LoadStage::Read, "\\model.gguf", ERROR_BIT | 7);
+        assert_eq!(
+            read.to_string(),
+            "read \\model.gguf failed: DEVICE_ERROR (0x8000000000000007)"
+        );
+
+        let odd = LoadError::status(LoadStage::Volume, "\\kernel.efi", ERROR_BIT | 99);
+        assert_eq!(
+            odd.to_string(),
+            "open volume for \\kernel.efi failed: status 0x8000000000000063"
+        );
+    }
+
+    #[test]
+    fn test_parse_failure_reports_position() {
+        let error = LoadError::parse("\\inference_config.json", 3, 17);
+        assert_eq!(error.stage(), LoadStage::Parse);
+        assert_eq!(
+            error.to_string(),
+            "parse \\inference_config.json failed: invalid JSON at line 3, column 17"
+        );
+    }
+
+    #[test]
+    fn test_long_paths_keep_their_tail() {
+        let long = format!("\\models\\{}\\phi-2-Ω.gguf", "nested\\".repeat(20));
+        let error = LoadError::status(LoadStage::Stat, &long, ERROR_BIT | 15);
+        assert!(error.path().len() <= MAX_PATH - ELLIPSIS.len());
+        assert!(long.ends_with(error.path()));
+
+        let message = error.to_string();
+        assert!(message.starts_with("stat ...") && message.contains("phi-2-Ω.gguf failed"));
+        assert!(message.ends_with("ACCESS_DENIED (0x800000000000000f)"));
+    }
+}
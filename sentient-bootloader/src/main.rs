@@ -16,6 +16,7 @@ mod config;
 mod file_loader;
 mod hardware;
 mod launch;
+mod load_error;
 mod model;
 
 use crate::color::{print_phase, Phase};
@@ -68,7 +69,7 @@ fn main(image_handle: Handle, mut system_table: SystemTable<Boot>) -> Status {
         Ok(cfg) => cfg,
         Err(e) => {
             print_phase(stdout, Phase::Error, "Failed to load config!");
-            serial_println!("❌ Config load failed: {:?}", e);
+            serial_println!("❌ Config load failed: {}", e);
             return Status::LOAD_ERROR;
         }
     };
@@ -77,7 +78,7 @@ fn main(image_handle: Handle, mut system_table: SystemTable<Boot>) -> Status {
         Ok(data) => data,
         Err(e) => {
             print_phase(stdout, Phase::Error, "Failed to load AI model!");
-            serial_println!("❌ Model load failed: {:?}", e);
+            serial_println!("❌ Model load failed: {}", e);
             return Status::LOAD_ERROR;
         }
     };
@@ -104,7 +105,7 @@ fn main(image_handle: Handle, mut system_table: SystemTable<Boot>) -> Status {
         Ok(data) => data,
         Err(e) => {
             print_phase(stdout, Phase::Error, "Failed to load kernel!");
-            serial_println!("❌ Kernel load failed: {:?}", e);
+            serial_println!("❌ Kernel load failed: {}", e);
             return Status::LOAD_ERROR;
         }
     };
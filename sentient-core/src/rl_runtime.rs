@@ -379,13 +379,17 @@ impl RLRuntime {
         let logs_dir = std::path::Path::new("logs");
         std::fs::create_dir_all(logs_dir)?;
         
+        // One write under the lock the other goal injectors take, so
+        // concurrent appends never interleave
         let injection_file = logs_dir.join("goal_injections.jsonl");
+        let mut line = serde_json::to_vec(&injection)?;
+        line.push(b'\n');
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
             .open(&injection_file)?;
-        
-        writeln!(file, "{}", serde_json::to_string(&injection)?)?;
+        file.lock()?;
+        file.write_all(&line)?;
         
         log::info!("Injected RL goal: {}", goal);
         Ok(())
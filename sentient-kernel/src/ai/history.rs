@@ -0,0 +1,81 @@
+//! Fixed-capacity history of recent values
+//!
+//! This module only depends on `core`, so its tests run on the host:
+//! `rustc --edition 2021 --test src/ai/history.rs && ./history`
+
+/// Ring buffer keeping the last `N` values pushed, oldest first
+pub struct RingBuffer<T, const N: usize> {
+    slots: [Option<T>; N],
+    // Slot the next push writes to
+    next: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> RingBuffer<T, N> {
+    pub fn new() -> Self {
+        RingBuffer {
+            slots: core::array::from_fn(|_| None),
+            next: 0,
+            len: 0,
+        }
+    }
+
+    /// Append a value, evicting the oldest once full
+    pub fn push(&mut self, value: T) {
+        if N == 0 {
+            return;
+        }
+        self.slots[self.next] = Some(value);
+        self.next = (self.next + 1) % N;
+        self.len = (self.len + 1).min(N);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Values from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let start = (self.next + N - self.len) % N.max(1);
+        (0..self.len).filter_map(move |i| self.slots[(start + i) % N].as_ref())
+    }
+}
+
+impl<T, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_last_k_in_order() {
+        const K: usize = 4;
+        let mut history: RingBuffer<String, K> = RingBuffer::new();
+        assert!(history.is_empty());
+
+        history.push(String::from("r0"));
+        history.push(String::from("r1"));
+        assert_eq!(history.iter().collect::<Vec<_>>(), ["r0", "r1"]);
+
+        for i in 2..K + 2 {
+            history.push(format!("r{i}"));
+        }
+        assert_eq!(history.len(), K);
+        assert_eq!(history.iter().collect::<Vec<_>>(), ["r2", "r3", "r4", "r5"]);
+    }
+
+    #[test]
+    fn test_zero_capacity_stays_empty() {
+        let mut history: RingBuffer<u32, 0> = RingBuffer::new();
+        history.push(1);
+        assert_eq!(history.iter().count(), 0);
+    }
+}
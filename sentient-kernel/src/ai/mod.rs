@@ -5,16 +5,24 @@ use alloc::string::String;
 use core::panic::PanicInfo;
 use spin::Mutex;
 
+mod history;
 mod inference;
+mod power;
 mod runtime;
 mod scheduler;
 
+use history::RingBuffer;
 pub use inference::{InferenceRequest, InferenceResponse};
+use power::LoadAverage;
+pub use power::{PowerMode, DEFAULT_TICK_INTERVAL_MS};
 use runtime::ModelRuntime;
 pub use scheduler::SchedulerHints;
 
 static AI_SUBSYSTEM: Mutex<Option<AISubsystem>> = Mutex::new(None);
 
+/// Number of inference responses kept for the `ai` shell command
+pub const RESPONSE_HISTORY: usize = 8;
+
 #[allow(dead_code)]
 pub fn try_get_ai_subsystem() -> Result<&'static Mutex<Option<AISubsystem>>, String> {
     if AI_SUBSYSTEM.lock().is_some() {
@@ -31,6 +39,11 @@ pub struct AISubsystem {
     scheduler_hints: SchedulerHints,
     inference_count: u64,
     power_mode: PowerMode,
+    load: LoadAverage,
+    cores: u32,
+    // Set when the model picks a power mode; the fallback policy skips that tick
+    model_set_power: bool,
+    recent_responses: RingBuffer<InferenceResponse, RESPONSE_HISTORY>,
 }
 
 impl AISubsystem {
@@ -41,13 +54,23 @@ impl AISubsystem {
     ) -> Result<InferenceResponse, String> {
         self.runtime.infer(&request, &self.config)
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-pub enum PowerMode {
-    Performance,
-    Balanced,
-    LowPower,
+    pub fn scheduler_hints(&self) -> &SchedulerHints {
+        &self.scheduler_hints
+    }
+
+    pub fn power_mode(&self) -> PowerMode {
+        self.power_mode
+    }
+
+    pub fn inference_count(&self) -> u64 {
+        self.inference_count
+    }
+
+    /// Last `RESPONSE_HISTORY` responses, oldest first
+    pub fn recent_responses(&self) -> impl Iterator<Item = &InferenceResponse> {
+        self.recent_responses.iter()
+    }
 }
 
 pub fn init(boot_info: &BootInfo) -> Result<(), String> {
@@ -75,6 +98,10 @@ pub fn init(boot_info: &BootInfo) -> Result<(), String> {
         scheduler_hints: SchedulerHints::default(),
         inference_count: 0,
         power_mode: PowerMode::Balanced,
+        load: LoadAverage::new(),
+        cores: boot_info.hardware.cpu_features.cores,
+        model_set_power: false,
+        recent_responses: RingBuffer::new(),
     };
 
     *AI_SUBSYSTEM.lock() = Some(subsystem);
@@ -106,6 +133,7 @@ pub fn process_pending_inferences() {
             match ai.runtime.infer(&request, &ai.config) {
                 Ok(response) => {
                     ai.inference_count += 1;
+                    ai.recent_responses.push(response.clone());
                     handle_inference_response(response, ai);
                 }
                 Err(e) => {
@@ -125,6 +153,7 @@ fn handle_inference_response(response: InferenceResponse, subsystem: &mut AISubs
         InferenceResponse::PowerModeChange(mode) => {
             serial_println!("⚡ AI: Changing power mode to {:?}", mode);
             subsystem.power_mode = mode;
+            subsystem.model_set_power = true;
         }
         InferenceResponse::SystemCommand(cmd) => {
             serial_println!("🤖 AI: System command: {}", cmd);
@@ -147,6 +176,19 @@ fn execute_ai_command(cmd: &str) {
     }
 }
 
+pub fn current_power_mode() -> PowerMode {
+    AI_SUBSYSTEM
+        .lock()
+        .as_ref()
+        .map(|ai| ai.power_mode)
+        .unwrap_or(PowerMode::Balanced)
+}
+
+/// AI tick interval suited to the current power mode
+pub fn tick_interval_ms() -> u64 {
+    power::tick_interval_ms(current_power_mode())
+}
+
 pub fn should_enter_low_power() -> bool {
     AI_SUBSYSTEM
         .lock()
@@ -190,6 +232,31 @@ pub fn analyze_panic(panic_info: &PanicInfo) {
 fn set_power_mode(mode: PowerMode) {
     if let Some(ai) = AI_SUBSYSTEM.lock().as_mut() {
         ai.power_mode = mode;
+        ai.model_set_power = true;
+    }
+}
+
+/// Record the current load and, unless the model chose a power mode since the
+/// last tick, apply the metrics-driven power policy
+pub fn apply_power_policy(metrics: &SystemMetrics) {
+    if let Some(ai) = AI_SUBSYSTEM.lock().as_mut() {
+        ai.load
+            .record(power::load_sample(metrics.task_count, ai.cores));
+
+        if core::mem::take(&mut ai.model_set_power) {
+            return;
+        }
+
+        let mode = power::decide_power_mode(&ai.load, ai.power_mode);
+        if mode != ai.power_mode {
+            serial_println!(
+                "⚡ Power policy: {:?} -> {:?} (load {:.2})",
+                ai.power_mode,
+                mode,
+                ai.load.value()
+            );
+            ai.power_mode = mode;
+        }
     }
 }
 
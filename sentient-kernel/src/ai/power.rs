@@ -0,0 +1,192 @@
+//! Metrics-driven power policy
+//!
+//! Used when the model has not dictated a power mode. Load is smoothed with an
+//! exponentially weighted average and each mode has separate enter and exit
+//! thresholds, so a brief spike never flips the mode and a mode isn't left
+//! as soon as load drifts back across the line that triggered it.
+//!
+//! This module only depends on `core`, so its tests run on the host:
+//! `rustc --edition 2021 --test src/ai/power.rs && ./power`
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerMode {
+    Performance,
+    Balanced,
+    LowPower,
+}
+
+/// Weight of the newest sample in the load average
+const LOAD_SMOOTHING: f32 = 0.2;
+/// Samples needed before the policy acts at all
+const MIN_SAMPLES: u32 = 3;
+
+/// Load at or above which Performance is entered
+const PERFORMANCE_ENTER: f32 = 0.75;
+/// Load below which Performance is left
+const PERFORMANCE_EXIT: f32 = 0.5;
+/// Load at or below which LowPower is entered
+const LOW_POWER_ENTER: f32 = 0.05;
+/// Load above which LowPower is left
+const LOW_POWER_EXIT: f32 = 0.2;
+
+/// AI tick interval in Balanced mode
+pub const DEFAULT_TICK_INTERVAL_MS: u64 = 5000;
+
+/// Exponentially weighted system load in `0.0..=1.0`
+#[derive(Debug, Clone, Copy)]
+pub struct LoadAverage {
+    value: f32,
+    samples: u32,
+}
+
+impl LoadAverage {
+    pub const fn new() -> Self {
+        LoadAverage {
+            value: 0.0,
+            samples: 0,
+        }
+    }
+
+    /// Fold in one load sample
+    pub fn record(&mut self, sample: f32) {
+        let sample = sample.clamp(0.0, 1.0);
+        self.value = if self.samples == 0 {
+            sample
+        } else {
+            self.value + LOAD_SMOOTHING * (sample - self.value)
+        };
+        self.samples = self.samples.saturating_add(1);
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+}
+
+impl Default for LoadAverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Load sample from runnable tasks, not counting the kernel itself
+pub fn load_sample(task_count: u32, cores: u32) -> f32 {
+    task_count.saturating_sub(1) as f32 / cores.max(1) as f32
+}
+
+/// Power mode the policy recommends given the smoothed load
+pub fn decide_power_mode(load: &LoadAverage, current: PowerMode) -> PowerMode {
+    if load.samples() < MIN_SAMPLES {
+        return current;
+    }
+
+    let value = load.value();
+    match current {
+        PowerMode::Performance if value >= PERFORMANCE_EXIT => PowerMode::Performance,
+        PowerMode::LowPower if value <= LOW_POWER_EXIT => PowerMode::LowPower,
+        _ if value >= PERFORMANCE_ENTER => PowerMode::Performance,
+        _ if value <= LOW_POWER_ENTER => PowerMode::LowPower,
+        _ => PowerMode::Balanced,
+    }
+}
+
+/// How often `ai_system_tick` should run in `mode`
+///
+/// Performance checks in more often to react to load; LowPower backs off so
+/// the CPU can stay halted longer.
+pub fn tick_interval_ms(mode: PowerMode) -> u64 {
+    match mode {
+        PowerMode::Performance => 2000,
+        PowerMode::Balanced => DEFAULT_TICK_INTERVAL_MS,
+        PowerMode::LowPower => 15000,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed `samples` through the policy, returning the final mode
+    fn run(load: &mut LoadAverage, mut mode: PowerMode, samples: &[f32]) -> PowerMode {
+        for &sample in samples {
+            load.record(sample);
+            mode = decide_power_mode(load, mode);
+        }
+        mode
+    }
+
+    fn settled(sample: f32) -> LoadAverage {
+        let mut load = LoadAverage::new();
+        for _ in 0..MIN_SAMPLES {
+            load.record(sample);
+        }
+        load
+    }
+
+    #[test]
+    fn test_idle_enters_low_power() {
+        let mut load = settled(0.3);
+        assert_eq!(
+            decide_power_mode(&load, PowerMode::Balanced),
+            PowerMode::Balanced
+        );
+
+        let mode = run(&mut load, PowerMode::Balanced, &[0.0; 15]);
+        assert_eq!(mode, PowerMode::LowPower);
+
+        // Light activity stays in LowPower until it clears the exit threshold
+        let mode = run(&mut load, mode, &[0.3; 2]);
+        assert_eq!(mode, PowerMode::LowPower);
+    }
+
+    #[test]
+    fn test_sustained_load_enters_performance() {
+        let mut load = settled(0.3);
+        let mode = run(&mut load, PowerMode::Balanced, &[1.0; 10]);
+        assert_eq!(mode, PowerMode::Performance);
+
+        // Dropping back to moderate load eventually returns to Balanced
+        let mode = run(&mut load, mode, &[0.3; 15]);
+        assert_eq!(mode, PowerMode::Balanced);
+    }
+
+    #[test]
+    fn test_transient_spike_does_not_flap() {
+        let mut load = settled(0.3);
+        let mut mode = PowerMode::Balanced;
+        for _ in 0..10 {
+            mode = run(&mut load, mode, &[1.0, 0.3, 0.3, 0.3]);
+            assert_eq!(mode, PowerMode::Balanced);
+        }
+
+        // A single idle tick doesn't drop into LowPower either
+        mode = run(&mut load, mode, &[0.0]);
+        assert_eq!(mode, PowerMode::Balanced);
+    }
+
+    #[test]
+    fn test_waits_for_enough_samples() {
+        let mut load = LoadAverage::new();
+        load.record(1.0);
+        assert_eq!(
+            decide_power_mode(&load, PowerMode::LowPower),
+            PowerMode::LowPower
+        );
+        assert_eq!(load_sample(1, 4), 0.0);
+        assert_eq!(load_sample(5, 4), 1.0);
+        assert_eq!(load_sample(3, 0), 2.0);
+    }
+
+    #[test]
+    fn test_tick_interval_follows_power_mode() {
+        let performance = tick_interval_ms(PowerMode::Performance);
+        let balanced = tick_interval_ms(PowerMode::Balanced);
+        let low_power = tick_interval_ms(PowerMode::LowPower);
+        assert!(low_power > balanced && balanced > performance);
+        assert_eq!(balanced, DEFAULT_TICK_INTERVAL_MS);
+    }
+}
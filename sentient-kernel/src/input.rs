@@ -0,0 +1,258 @@
+//! Serial input decoding
+//!
+//! The serial port delivers raw bytes: a multi-byte UTF-8 rune or an ANSI
+//! escape sequence (arrow keys, Home/End) can be split across reads.
+//! [`InputDecoder`] assembles them into whole [`Key`]s before they reach the
+//! shell, without allocating.
+//!
+//! This module only depends on `core`, so its tests run on the host:
+//! `rustc --edition 2021 --test src/input.rs && ./input`
+
+const ESC: u8 = 0x1b;
+/// Longest sequence kept while decoding (4-byte rune or `ESC [ n ; m X`)
+const MAX_SEQUENCE: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    /// Inside a UTF-8 rune of `len` bytes
+    Utf8 {
+        len: usize,
+    },
+    /// Saw ESC
+    Escape,
+    /// Saw `ESC [`, collecting parameter bytes
+    Csi,
+    /// Saw `ESC O`
+    Ss3,
+}
+
+pub struct InputDecoder {
+    state: State,
+    buf: [u8; MAX_SEQUENCE],
+    len: usize,
+}
+
+impl InputDecoder {
+    pub const fn new() -> Self {
+        InputDecoder {
+            state: State::Ground,
+            buf: [0; MAX_SEQUENCE],
+            len: 0,
+        }
+    }
+
+    /// Feed one byte, returning a key once a rune or sequence is complete
+    pub fn feed(&mut self, byte: u8) -> Option<Key> {
+        match self.state {
+            State::Ground => self.ground(byte),
+            State::Utf8 { len } => {
+                if byte & 0xC0 != 0x80 {
+                    // Rune cut short: drop it and start over with this byte
+                    self.reset();
+                    return self.ground(byte);
+                }
+                self.push(byte);
+                if self.len < len {
+                    return None;
+                }
+                let key = core::str::from_utf8(&self.buf[..self.len])
+                    .ok()
+                    .and_then(|s| s.chars().next())
+                    .unwrap_or(char::REPLACEMENT_CHARACTER);
+                self.reset();
+                Some(Key::Char(key))
+            }
+            State::Escape => match byte {
+                b'[' => {
+                    self.state = State::Csi;
+                    None
+                }
+                b'O' => {
+                    self.state = State::Ss3;
+                    None
+                }
+                _ => {
+                    // Not a sequence we know; drop the ESC and keep the byte
+                    self.reset();
+                    self.ground(byte)
+                }
+            },
+            State::Csi => match byte {
+                b'0'..=b'9' | b';' => {
+                    // Excess parameters are ignored rather than overflowing
+                    if self.len < MAX_SEQUENCE {
+                        self.push(byte);
+                    }
+                    None
+                }
+                0x40..=0x7e => {
+                    let key = csi_key(&self.buf[..self.len], byte);
+                    self.reset();
+                    key
+                }
+                _ => {
+                    self.reset();
+                    self.ground(byte)
+                }
+            },
+            State::Ss3 => {
+                self.reset();
+                match byte {
+                    b'A' => Some(Key::Up),
+                    b'B' => Some(Key::Down),
+                    b'C' => Some(Key::Right),
+                    b'D' => Some(Key::Left),
+                    b'H' => Some(Key::Home),
+                    b'F' => Some(Key::End),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn ground(&mut self, byte: u8) -> Option<Key> {
+        let len = match byte {
+            ESC => {
+                self.state = State::Escape;
+                return None;
+            }
+            0x00..=0x7f => return Some(Key::Char(byte as char)),
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            // Stray continuation or invalid lead byte
+            _ => return Some(Key::Char(char::REPLACEMENT_CHARACTER)),
+        };
+        self.push(byte);
+        self.state = State::Utf8 { len };
+        None
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.buf[self.len] = byte;
+        self.len += 1;
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Ground;
+        self.len = 0;
+    }
+}
+
+impl Default for InputDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Key for a complete `ESC [ params final` sequence
+fn csi_key(params: &[u8], final_byte: u8) -> Option<Key> {
+    match (params, final_byte) {
+        (_, b'A') => Some(Key::Up),
+        (_, b'B') => Some(Key::Down),
+        (_, b'C') => Some(Key::Right),
+        (_, b'D') => Some(Key::Left),
+        (_, b'H') | (b"1" | b"7", b'~') => Some(Key::Home),
+        (_, b'F') | (b"4" | b"8", b'~') => Some(Key::End),
+        (b"3", b'~') => Some(Key::Delete),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(decoder: &mut InputDecoder, bytes: &[u8]) -> Vec<Key> {
+        bytes.iter().filter_map(|&b| decoder.feed(b)).collect()
+    }
+
+    #[test]
+    fn test_multibyte_rune_split_across_reads() {
+        let mut decoder = InputDecoder::new();
+        let bytes = "aé€😀".as_bytes();
+
+        // Feed one byte per read; nothing is emitted mid-rune
+        let mut keys = Vec::new();
+        for chunk in bytes.chunks(1) {
+            keys.extend(decode(&mut decoder, chunk));
+        }
+        assert_eq!(
+            keys,
+            vec![
+                Key::Char('a'),
+                Key::Char('é'),
+                Key::Char('€'),
+                Key::Char('😀')
+            ]
+        );
+
+        let euro = "€".as_bytes();
+        assert!(decode(&mut decoder, &euro[..2]).is_empty());
+        assert_eq!(decode(&mut decoder, &euro[2..]), vec![Key::Char('€')]);
+    }
+
+    #[test]
+    fn test_escape_sequence_split_across_reads() {
+        let mut decoder = InputDecoder::new();
+        assert!(decode(&mut decoder, b"\x1b").is_empty());
+        assert!(decode(&mut decoder, b"[").is_empty());
+        assert_eq!(decode(&mut decoder, b"A"), vec![Key::Up]);
+
+        assert!(decode(&mut decoder, b"\x1b[3").is_empty());
+        assert_eq!(
+            decode(&mut decoder, b"~x"),
+            vec![Key::Delete, Key::Char('x')]
+        );
+
+        let keys = decode(&mut decoder, b"\x1b[D\x1b[C\x1b[H\x1b[F\x1bOH\x1b[4~\x1b[B");
+        assert_eq!(
+            keys,
+            vec![
+                Key::Left,
+                Key::Right,
+                Key::Home,
+                Key::End,
+                Key::Home,
+                Key::End,
+                Key::Down
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_input_recovers() {
+        let mut decoder = InputDecoder::new();
+
+        // Truncated rune followed by ASCII
+        assert_eq!(
+            decode(&mut decoder, &[0xe2, 0x82, b'a']),
+            vec![Key::Char('a')]
+        );
+        // Stray continuation byte
+        assert_eq!(
+            decode(&mut decoder, &[0x80]),
+            vec![Key::Char(char::REPLACEMENT_CHARACTER)]
+        );
+        // Unknown escapes are swallowed without eating the next key
+        assert_eq!(
+            decode(&mut decoder, b"\x1b[99zq\x1bxy"),
+            vec![Key::Char('q'), Key::Char('x'), Key::Char('y')]
+        );
+        // Overlong parameters don't overflow the buffer
+        assert_eq!(decode(&mut decoder, b"\x1b[123456789;1A"), vec![Key::Up]);
+    }
+}
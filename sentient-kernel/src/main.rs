@@ -11,6 +11,7 @@ use uefi::table::Runtime;
 
 mod ai;
 mod boot_info;
+mod input;
 mod mm;
 mod serial;
 mod shell;
@@ -172,6 +173,7 @@ fn kernel_runtime_loop(_runtime_table: SystemTable<Runtime>, _boot_info: &'stati
 
     // Initialize a simple shell state without allocation
     let mut shell_started = false;
+    let mut input = input::InputDecoder::new();
 
     // Main kernel loop
     loop {
@@ -184,9 +186,12 @@ fn kernel_runtime_loop(_runtime_table: SystemTable<Runtime>, _boot_info: &'stati
             shell_started = true;
         }
 
-        // Check for serial input - handle commands directly without allocation
-        if let Some(ch) = serial::try_read_char() {
-            shell::handle_input_simple(ch);
+        // Drain pending serial input so pasted text isn't dropped while the
+        // loop is busy, decoding runes and escape sequences into keys
+        while let Some(byte) = serial::try_read_byte() {
+            if let Some(key) = input.feed(byte) {
+                shell::handle_key(key);
+            }
         }
 
         // Process AI inference requests
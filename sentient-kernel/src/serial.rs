@@ -98,14 +98,14 @@ pub fn println(args: fmt::Arguments) {
     _print(format_args!("\n"));
 }
 
-pub fn try_read_char() -> Option<char> {
+/// Next raw byte from the receive FIFO, if any; decode with `input::InputDecoder`
+pub fn try_read_byte() -> Option<u8> {
     #[cfg(feature = "serial-debug")]
     unsafe {
         let line_status = inb(LINE_STATUS_REGISTER);
         if line_status & 0x01 != 0 {
             // Data available
-            let byte = inb(DATA_REGISTER);
-            Some(byte as char)
+            Some(inb(DATA_REGISTER))
         } else {
             None
         }
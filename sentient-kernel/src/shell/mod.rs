@@ -1,3 +1,4 @@
+use crate::input::Key;
 use crate::serial_println;
 
 pub const SHELL_BANNER: &str = r#"
@@ -11,6 +12,15 @@ pub const SHELL_BANNER: &str = r#"
 static mut COMMAND_BUFFER: [u8; 256] = [0; 256];
 static mut COMMAND_LEN: usize = 0;
 
+pub fn handle_key(key: Key) {
+    match key {
+        Key::Char(ch) => handle_input_simple(ch),
+        // No line editing or history yet; navigation keys are swallowed
+        // instead of echoing their escape bytes into the command
+        Key::Up | Key::Down | Key::Left | Key::Right | Key::Home | Key::End | Key::Delete => {}
+    }
+}
+
 pub fn handle_input_simple(ch: char) {
     unsafe {
         match ch {
@@ -60,6 +70,8 @@ fn execute_command_simple() {
             cmd_ask(prompt);
         } else if cmd_equals(cmd, "models") {
             cmd_models();
+        } else if cmd_equals(cmd, "ai") {
+            cmd_ai();
         } else if cmd_starts_with(cmd, "image ") {
             let prompt = &cmd[6..];
             cmd_image(prompt);
@@ -104,6 +116,7 @@ fn cmd_help() {
     serial_println!("  status     - Show system status and connected AI models");
     serial_println!("  ask <prompt> - Query AI model with a prompt");
     serial_println!("  models     - List available AI models");
+    serial_println!("  ai         - Show AI scheduler hints, power mode and recent responses");
     serial_println!("  image <prompt> - Generate image from prompt");
     serial_println!("  exit       - Exit the shell");
     serial_println!();
@@ -211,6 +224,40 @@ fn cmd_models() {
     serial_println!();
 }
 
+fn cmd_ai() {
+    match crate::ai::try_get_ai_subsystem() {
+        Ok(ai_lock) => {
+            if let Some(ref ai) = *ai_lock.lock() {
+                let hints = ai.scheduler_hints();
+                serial_println!("AI Runtime:");
+                serial_println!("  Inferences: {}", ai.inference_count());
+                serial_println!("  Power mode: {:?}", ai.power_mode());
+                serial_println!("  Tick interval: {} ms", crate::sys::ai_tick_interval_ms());
+                serial_println!("  Scheduler hints:");
+                serial_println!("    Time quantum: {} ms", hints.time_quantum_ms);
+                serial_println!("    Priority boost: {:.2}", hints.priority_boost);
+                serial_println!("    Active tasks: {}", hints.active_tasks);
+                serial_println!("    CPU affinity: {:?}", hints.cpu_affinity);
+                serial_println!();
+                serial_println!("Recent responses (last {}):", crate::ai::RESPONSE_HISTORY);
+
+                if ai.recent_responses().next().is_none() {
+                    serial_println!("  (none yet)");
+                }
+                for (i, response) in ai.recent_responses().enumerate() {
+                    serial_println!("  {}. {:?}", i + 1, response);
+                }
+            } else {
+                serial_println!("AI subsystem not initialized");
+            }
+        }
+        Err(e) => {
+            serial_println!("Error: Failed to access AI subsystem - {}", e);
+        }
+    }
+    serial_println!();
+}
+
 fn cmd_image(prompt: &str) {
     serial_println!("Image generation in kernel mode is not yet implemented.");
     serial_println!("Prompt received: '{}'", prompt);
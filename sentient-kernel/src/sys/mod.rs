@@ -1,6 +1,7 @@
 use crate::ai::{InferenceRequest, InferenceResponse, SystemMetrics};
 use crate::boot_info::BootInfo;
 use crate::serial_println;
+use core::sync::atomic::{AtomicU64, Ordering};
 use spin::Mutex;
 use uefi::prelude::*;
 use uefi::CStr16;
@@ -145,13 +146,23 @@ pub fn get_task_count() -> u32 {
 }
 
 static mut LAST_AI_TICK: u64 = 0;
-const AI_TICK_INTERVAL_MS: u64 = 5000; // Only run every 5 seconds
+static AI_TICK_INTERVAL_MS: AtomicU64 = AtomicU64::new(crate::ai::DEFAULT_TICK_INTERVAL_MS);
+
+/// Current interval between AI system ticks
+pub fn ai_tick_interval_ms() -> u64 {
+    AI_TICK_INTERVAL_MS.load(Ordering::Relaxed)
+}
+
+/// Change how often `ai_system_tick` runs
+pub fn set_ai_tick_interval_ms(interval_ms: u64) {
+    AI_TICK_INTERVAL_MS.store(interval_ms, Ordering::Relaxed);
+}
 
 pub fn ai_system_tick() {
     // Rate limit AI system ticks
     unsafe {
         let current_time = get_system_metrics().uptime_ms;
-        if current_time - LAST_AI_TICK < AI_TICK_INTERVAL_MS {
+        if current_time - LAST_AI_TICK < ai_tick_interval_ms() {
             return;
         }
         LAST_AI_TICK = current_time;
@@ -159,6 +170,12 @@ pub fn ai_system_tick() {
 
     // Submit periodic system analysis
     let metrics = get_system_metrics();
+
+    // Fall back to the metrics-driven policy when the model stays quiet,
+    // then pace the next tick to the resulting power mode
+    crate::ai::apply_power_policy(&metrics);
+    set_ai_tick_interval_ms(crate::ai::tick_interval_ms());
+
     let request = InferenceRequest::SystemAnalysis {
         event: "system_tick",
         metrics,
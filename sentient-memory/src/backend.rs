@@ -0,0 +1,277 @@
+// Pluggable persistence for the RL store
+// Checkpoints and buffers are stored as bytes under '/'-separated keys
+// ("<id>/metadata.json"); a backend decides where those bytes live
+
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::sync::RwLock;
+
+use crate::disk::{DiskOps, StorageError};
+
+/// Byte storage by key
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Store `data` under `key`, replacing any previous value atomically
+    async fn save(&self, key: &str, data: &[u8]) -> Result<(), StorageError>;
+
+    /// What is stored under `key`, or `None` if nothing is
+    async fn load(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Every key starting with `prefix`, sorted
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError>;
+
+    /// Remove `key`; removing a missing key is not an error
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Files under a root directory, one per key, written through [`DiskOps`]
+/// so writes are atomic and transient failures are retried
+#[derive(Clone)]
+pub struct FsBackend {
+    root: PathBuf,
+    disk: DiskOps,
+}
+
+impl FsBackend {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            disk: DiskOps::default(),
+        }
+    }
+
+    /// Retry policy and filesystem used for reads and writes
+    pub fn with_disk_ops(mut self, disk: DiskOps) -> Self {
+        self.disk = disk;
+        self
+    }
+
+    /// Backend holding the directory of `path`, and the key of `path` in it
+    pub fn for_file(path: &Path) -> (Self, String) {
+        let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let key = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        (Self::new(root), key)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        key.split('/').fold(self.root.clone(), |path, part| path.join(part))
+    }
+
+    /// Keys of the files under `dir`, which is `prefix` below the root
+    async fn walk(&self, dir: PathBuf, prefix: String, keys: &mut Vec<String>) -> io::Result<()> {
+        let mut pending = vec![(dir, prefix)];
+        while let Some((dir, prefix)) = pending.pop() {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                // Gone already, e.g. a checkpoint cleaned up mid-listing
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let key = format!("{}{}", prefix, name);
+                match entry.file_type().await {
+                    Ok(file_type) if file_type.is_dir() => pending.push((entry.path(), format!("{}/", key))),
+                    // In-progress atomic writes are not stored values yet
+                    Ok(_) if !name.ends_with(".tmp") => keys.push(key),
+                    Ok(_) => {}
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_not_found(error: &StorageError) -> bool {
+    matches!(error, StorageError::Io { source, .. } if source.kind() == io::ErrorKind::NotFound)
+}
+
+#[async_trait]
+impl StorageBackend for FsBackend {
+    async fn save(&self, key: &str, data: &[u8]) -> Result<(), StorageError> {
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            self.disk.create_dir_all(parent).await?;
+        }
+        self.disk.write_atomic(&path, data).await
+    }
+
+    async fn load(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match self.disk.read(&self.path(key)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if is_not_found(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        // Only the directory the prefix names can hold matching keys
+        let dir_key = &prefix[..prefix.rfind('/').map_or(0, |i| i + 1)];
+        let dir = self.path(dir_key.trim_end_matches('/'));
+        let mut keys = Vec::new();
+        self.walk(dir.clone(), dir_key.to_string(), &mut keys)
+            .await
+            .map_err(|source| StorageError::Io {
+                operation: "list",
+                path: dir,
+                source,
+            })?;
+        keys.retain(|key| key.starts_with(prefix));
+        keys.sort();
+        Ok(keys)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let path = self.path(key);
+        match self.disk.remove_file(&path).await {
+            Ok(()) => {}
+            Err(e) if is_not_found(&e) => return Ok(()),
+            Err(e) => return Err(e),
+        }
+
+        // Directories exist only to hold keys; drop the ones left empty
+        let mut dir = path.parent();
+        while let Some(parent) = dir.filter(|d| *d != self.root && d.starts_with(&self.root)) {
+            if fs::remove_dir(parent).await.is_err() {
+                break;
+            }
+            dir = parent.parent();
+        }
+        Ok(())
+    }
+}
+
+/// Keys held in memory, for tests that should not touch the filesystem
+#[derive(Default)]
+pub struct InMemoryBackend {
+    entries: RwLock<BTreeMap<String, Vec<u8>>>,
+}
+
+impl InMemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl StorageBackend for InMemoryBackend {
+    async fn save(&self, key: &str, data: &[u8]) -> Result<(), StorageError> {
+        self.entries.write().await.insert(key.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    async fn load(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        Ok(self.entries.read().await.get(key).cloned())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        Ok(self
+            .entries
+            .read()
+            .await
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.entries.write().await.remove(key);
+        Ok(())
+    }
+}
+
+/// Another backend's keys under `<prefix>/`, so several stores can share one
+/// backend
+pub struct ScopedBackend {
+    inner: Arc<dyn StorageBackend>,
+    prefix: String,
+}
+
+impl ScopedBackend {
+    pub fn new(inner: Arc<dyn StorageBackend>, prefix: &str) -> Self {
+        Self {
+            inner,
+            prefix: format!("{}/", prefix.trim_end_matches('/')),
+        }
+    }
+
+    fn key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+#[async_trait]
+impl StorageBackend for ScopedBackend {
+    async fn save(&self, key: &str, data: &[u8]) -> Result<(), StorageError> {
+        self.inner.save(&self.key(key), data).await
+    }
+
+    async fn load(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        self.inner.load(&self.key(key)).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, StorageError> {
+        Ok(self
+            .inner
+            .list(&self.key(prefix))
+            .await?
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string))
+            .collect())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.inner.delete(&self.key(key)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fs_backend_lists_nested_keys_and_prunes_empty_dirs() {
+        let dir = std::env::temp_dir().join(format!("fs_backend_{}", uuid::Uuid::new_v4()));
+        let backend = FsBackend::new(&dir);
+        assert!(backend.list("").await.unwrap().is_empty());
+        assert_eq!(backend.load("a/missing").await.unwrap(), None);
+
+        backend.save("a/one", b"1").await.unwrap();
+        backend.save("a/two", b"2").await.unwrap();
+        backend.save("b", b"3").await.unwrap();
+        assert_eq!(backend.list("").await.unwrap(), vec!["a/one", "a/two", "b"]);
+        assert_eq!(backend.list("a/").await.unwrap(), vec!["a/one", "a/two"]);
+        assert_eq!(backend.list("a/t").await.unwrap(), vec!["a/two"]);
+        assert_eq!(backend.list("b").await.unwrap(), vec!["b"]);
+        assert!(backend.list("missing/").await.unwrap().is_empty());
+        assert_eq!(backend.load("a/two").await.unwrap(), Some(b"2".to_vec()));
+
+        backend.delete("a/one").await.unwrap();
+        backend.delete("a/two").await.unwrap();
+        backend.delete("a/two").await.unwrap();
+        assert!(!dir.join("a").exists());
+        assert!(dir.exists());
+
+        // Scoped keys land in a subdirectory
+        let scoped = ScopedBackend::new(Arc::new(backend.clone()), "policies");
+        scoped.save("x/metadata.json", b"{}").await.unwrap();
+        assert!(dir.join("policies").join("x").join("metadata.json").exists());
+        assert_eq!(scoped.list("").await.unwrap(), vec!["x/metadata.json"]);
+
+        fs::remove_dir_all(&dir).await.ok();
+    }
+}
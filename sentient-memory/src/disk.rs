@@ -0,0 +1,401 @@
+// Disk I/O with bounded retries and atomic writes
+// Used by the RL store so a transient filesystem error doesn't lose a checkpoint
+
+use async_trait::async_trait;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::fs;
+use tokio::time::Instant;
+
+/// Filesystem primitives the store needs; swapped out in tests to inject
+/// failures
+#[async_trait]
+pub trait DiskIo: Send + Sync {
+    async fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    async fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+    async fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+    async fn remove_file(&self, path: &Path) -> io::Result<()>;
+    async fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+}
+
+/// The real filesystem, through `tokio::fs`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioFs;
+
+#[async_trait]
+impl DiskIo for TokioFs {
+    async fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path).await
+    }
+
+    async fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        fs::write(path, data).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to).await
+    }
+
+    async fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path).await
+    }
+
+    async fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path).await
+    }
+}
+
+/// How hard to try before giving up on a disk operation
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Attempts per operation, including the first
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubled after each further failure
+    pub initial_backoff: Duration,
+    /// Upper bound on a single wait
+    pub max_backoff: Duration,
+    /// Overall time budget for one operation, retries included
+    pub deadline: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(500),
+            deadline: Duration::from_secs(10),
+        }
+    }
+}
+
+/// A disk operation that failed for good
+#[derive(Debug)]
+pub enum StorageError {
+    /// The error can't be fixed by retrying (missing file, bad permissions)
+    Io {
+        operation: &'static str,
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// Every attempt failed
+    RetriesExhausted {
+        operation: &'static str,
+        path: PathBuf,
+        attempts: u32,
+        source: io::Error,
+    },
+    /// The deadline passed before an attempt succeeded
+    DeadlineExceeded {
+        operation: &'static str,
+        path: PathBuf,
+        attempts: u32,
+        deadline: Duration,
+    },
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Io {
+                operation,
+                path,
+                source,
+            } => {
+                write!(f, "Failed to {} {:?}: {}", operation, path, source)
+            }
+            StorageError::RetriesExhausted {
+                operation,
+                path,
+                attempts,
+                source,
+            } => write!(
+                f,
+                "Failed to {} {:?} after {} attempts: {}",
+                operation, path, attempts, source
+            ),
+            StorageError::DeadlineExceeded {
+                operation,
+                path,
+                attempts,
+                deadline,
+            } => write!(
+                f,
+                "Failed to {} {:?}: deadline of {:?} passed after {} attempts",
+                operation, path, deadline, attempts
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::Io { source, .. } | StorageError::RetriesExhausted { source, .. } => {
+                Some(source)
+            }
+            StorageError::DeadlineExceeded { .. } => None,
+        }
+    }
+}
+
+/// Errors no amount of retrying will fix
+fn is_permanent(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::NotFound
+            | io::ErrorKind::PermissionDenied
+            | io::ErrorKind::InvalidInput
+            | io::ErrorKind::InvalidData
+    )
+}
+
+/// Disk operations retried under a [`RetryPolicy`]
+#[derive(Clone)]
+pub struct DiskOps {
+    io: Arc<dyn DiskIo>,
+    retry: RetryPolicy,
+}
+
+impl Default for DiskOps {
+    fn default() -> Self {
+        Self::new(Arc::new(TokioFs), RetryPolicy::default())
+    }
+}
+
+impl DiskOps {
+    pub fn new(io: Arc<dyn DiskIo>, retry: RetryPolicy) -> Self {
+        Self { io, retry }
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry
+    }
+
+    /// Read a whole file
+    pub async fn read(&self, path: &Path) -> Result<Vec<u8>, StorageError> {
+        self.with_retry("read", path, || self.io.read(path)).await
+    }
+
+    /// Create a directory and its parents
+    pub async fn create_dir_all(&self, path: &Path) -> Result<(), StorageError> {
+        self.with_retry("create directory", path, || self.io.create_dir_all(path))
+            .await
+    }
+
+    /// Remove a file
+    pub async fn remove_file(&self, path: &Path) -> Result<(), StorageError> {
+        self.with_retry("remove", path, || self.io.remove_file(path)).await
+    }
+
+    /// Replace `path` with `data` atomically: the bytes go to a temporary
+    /// sibling that is renamed over `path` once fully written, so readers
+    /// see the old file or the new one, never a partial write.
+    pub async fn write_atomic(&self, path: &Path, data: &[u8]) -> Result<(), StorageError> {
+        let tmp = temp_path(path);
+        let result = self
+            .with_retry("write", path, || async {
+                self.io.write(&tmp, data).await?;
+                self.io.rename(&tmp, path).await
+            })
+            .await;
+        if result.is_err() {
+            // Best effort; a leftover temp file is never read as data
+            let _ = self.io.remove_file(&tmp).await;
+        }
+        result
+    }
+
+    async fn with_retry<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        path: &Path,
+        mut attempt: F,
+    ) -> Result<T, StorageError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = io::Result<T>>,
+    {
+        let deadline = Instant::now() + self.retry.deadline;
+        let mut backoff = self.retry.initial_backoff;
+        let mut attempts = 0;
+        let deadline_exceeded = |attempts| StorageError::DeadlineExceeded {
+            operation,
+            path: path.to_path_buf(),
+            attempts,
+            deadline: self.retry.deadline,
+        };
+
+        loop {
+            attempts += 1;
+            let error = match tokio::time::timeout_at(deadline, attempt()).await {
+                Ok(Ok(value)) => return Ok(value),
+                Ok(Err(e)) => e,
+                Err(_) => return Err(deadline_exceeded(attempts)),
+            };
+
+            if is_permanent(&error) {
+                return Err(StorageError::Io {
+                    operation,
+                    path: path.to_path_buf(),
+                    source: error,
+                });
+            }
+            if attempts >= self.retry.max_attempts {
+                return Err(StorageError::RetriesExhausted {
+                    operation,
+                    path: path.to_path_buf(),
+                    attempts,
+                    source: error,
+                });
+            }
+            if Instant::now() + backoff >= deadline {
+                return Err(deadline_exceeded(attempts));
+            }
+
+            log::warn!(
+                "Failed to {} {:?} (attempt {}/{}): {}; retrying in {:?}",
+                operation,
+                path,
+                attempts,
+                self.retry.max_attempts,
+                error,
+                backoff
+            );
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(self.retry.max_backoff);
+        }
+    }
+}
+
+/// Temporary sibling of `path` used while writing it
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rl_store::{RLMemoryStore, Trajectory};
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Real filesystem whose first `failures` writes store half the data and
+    /// then fail, like a disk filling up mid-write
+    struct FlakyFs {
+        failures: AtomicU32,
+        writes: AtomicU32,
+    }
+
+    impl FlakyFs {
+        fn new(failures: u32) -> Self {
+            Self {
+                failures: AtomicU32::new(failures),
+                writes: AtomicU32::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl DiskIo for FlakyFs {
+        async fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            TokioFs.read(path).await
+        }
+
+        async fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+            self.writes.fetch_add(1, Ordering::SeqCst);
+            let fail = self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok();
+            if fail {
+                TokioFs.write(path, &data[..data.len() / 2]).await?;
+                return Err(io::Error::other("disk hiccup"));
+            }
+            TokioFs.write(path, data).await
+        }
+
+        async fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            TokioFs.rename(from, to).await
+        }
+
+        async fn remove_file(&self, path: &Path) -> io::Result<()> {
+            TokioFs.remove_file(path).await
+        }
+
+        async fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+            TokioFs.create_dir_all(path).await
+        }
+    }
+
+    fn fast_retry(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            deadline: Duration::from_secs(5),
+        }
+    }
+
+    fn trajectory(total_reward: f32) -> Trajectory {
+        Trajectory {
+            id: uuid::Uuid::new_v4(),
+            experiences: Vec::new(),
+            total_reward,
+            metadata: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flaky_writes_are_retried_without_partial_files() {
+        let dir = std::env::temp_dir().join(format!("disk_retry_{}", uuid::Uuid::new_v4()));
+        let flaky = Arc::new(FlakyFs::new(2));
+        let store = RLMemoryStore::new(dir.join("store"))
+            .with_disk_ops(DiskOps::new(flaky.clone(), fast_retry(5)));
+        for episode in 0..3 {
+            store
+                .add_trajectory(trajectory(episode as f32))
+                .await
+                .unwrap();
+        }
+
+        // Two failed attempts, then success
+        let backup = dir.join("backup");
+        store.save_all(&backup).await.unwrap();
+        assert_eq!(flaky.writes.load(Ordering::SeqCst), 3);
+        let files: Vec<_> = std::fs::read_dir(&backup)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(files, vec!["trajectories.json.gz".to_string()]);
+
+        let restored = RLMemoryStore::new(dir.join("restored"));
+        restored.load_all(&backup).await.unwrap();
+        assert_eq!(restored.get_trajectories(10).await.len(), 3);
+
+        // Out of attempts: a typed error, and the good file is untouched
+        let broken = RLMemoryStore::new(dir.join("store"))
+            .with_disk_ops(DiskOps::new(Arc::new(FlakyFs::new(10)), fast_retry(3)));
+        broken.add_trajectory(trajectory(9.0)).await.unwrap();
+        let err = broken.save_all(&backup).await.unwrap_err();
+        assert!(
+            matches!(
+                err.downcast_ref::<StorageError>(),
+                Some(StorageError::RetriesExhausted { attempts: 3, .. })
+            ),
+            "{:#}",
+            err
+        );
+        assert_eq!(std::fs::read_dir(&backup).unwrap().count(), 1);
+        restored.load_all(&backup).await.unwrap();
+        assert_eq!(restored.get_trajectories(10).await.len(), 3);
+
+        fs::remove_dir_all(&dir).await.ok();
+    }
+}
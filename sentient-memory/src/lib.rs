@@ -1,6 +1,10 @@
 // SentientOS Memory Store
 // Provides persistent storage for various system components
 
+pub mod backend;
+pub mod disk;
 pub mod rl_store;
 
-pub use rl_store::{RLMemoryStore, ReplayBuffer, PolicyStorage};
\ No newline at end of file
+pub use backend::{FsBackend, InMemoryBackend, ScopedBackend, StorageBackend};
+pub use disk::{DiskIo, DiskOps, RetryPolicy, StorageError, TokioFs};
+pub use rl_store::{RLMemoryStore, ReplayBuffer, PolicyStorage, RetentionPolicy, LoadReport};
\ No newline at end of file
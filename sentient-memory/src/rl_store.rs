@@ -5,15 +5,20 @@ use anyhow::{Result, Context};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
-use tokio::fs;
 use uuid::Uuid;
 use bincode;
 use flate2::{Compression, write::GzEncoder, read::GzDecoder};
-use std::io::{Write, Read};
+use std::io::{BufReader, Read, Write};
+
+use crate::backend::{FsBackend, ScopedBackend, StorageBackend};
+use crate::disk::DiskOps;
+
+/// Read buffer size used while streaming a replay buffer from storage
+const LOAD_CHUNK: usize = 64 * 1024;
 
 /// Experience for replay buffer
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +72,23 @@ pub struct ReplayConfig {
     pub beta: f32,   // Importance sampling weight
     pub beta_increment: f32,
     pub epsilon: f32,  // Small value to ensure non-zero priorities
+    /// Expected state length, checked when loading from disk
+    #[serde(default)]
+    pub state_dim: Option<usize>,
+    /// Expected action length, checked when loading from disk
+    #[serde(default)]
+    pub action_dim: Option<usize>,
+    /// Fail `load` on any corruption instead of keeping what can be recovered
+    #[serde(default)]
+    pub strict_load: bool,
+    /// Let prioritized batches repeat an experience. When false every batch
+    /// holds distinct indices; uniform sampling never repeats either way.
+    #[serde(default = "default_with_replacement")]
+    pub with_replacement: bool,
+}
+
+fn default_with_replacement() -> bool {
+    true
 }
 
 impl Default for ReplayConfig {
@@ -79,6 +101,68 @@ impl Default for ReplayConfig {
             beta: 0.4,
             beta_increment: 0.001,
             epsilon: 1e-6,
+            state_dim: None,
+            action_dim: None,
+            strict_load: false,
+            with_replacement: default_with_replacement(),
+        }
+    }
+}
+
+/// Outcome of `ReplayBuffer::load`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadReport {
+    /// Number of experiences the file claims to hold
+    pub expected: usize,
+    /// Experiences that passed validation and were loaded
+    pub recovered: usize,
+    /// Experiences that were read but malformed, and were skipped
+    pub dropped: usize,
+}
+
+impl LoadReport {
+    /// Whether every recorded experience could be read back
+    pub fn is_complete(&self) -> bool {
+        self.recovered + self.dropped >= self.expected
+    }
+}
+
+/// Uniform fixed-size sample of a stream of experiences (Algorithm R)
+struct Reservoir {
+    capacity: usize,
+    seen: usize,
+    items: Vec<(Experience, Option<f32>)>,
+}
+
+impl Reservoir {
+    fn new(capacity: usize) -> Self {
+        Self { capacity, seen: 0, items: Vec::with_capacity(capacity) }
+    }
+    
+    /// Count the next experience in the stream and pick the slot it takes,
+    /// if it is kept
+    fn admit(&mut self) -> Option<usize> {
+        use rand::Rng;
+        let n = self.seen;
+        self.seen += 1;
+        if n < self.capacity {
+            return Some(n);
+        }
+        let slot = rand::thread_rng().gen_range(0..=n);
+        (slot < self.capacity).then_some(slot)
+    }
+    
+    fn put(&mut self, slot: usize, exp: Experience, priority: Option<f32>) {
+        if slot == self.items.len() {
+            self.items.push((exp, priority));
+        } else {
+            self.items[slot] = (exp, priority);
+        }
+    }
+    
+    fn offer(&mut self, exp: Experience, priority: Option<f32>) {
+        if let Some(slot) = self.admit() {
+            self.put(slot, exp, priority);
         }
     }
 }
@@ -104,9 +188,9 @@ pub struct ReplayBuffer {
 impl ReplayBuffer {
     pub fn new(config: ReplayConfig) -> Self {
         Self {
-            config,
             buffer: Arc::new(RwLock::new(VecDeque::with_capacity(config.max_size))),
             priorities: Arc::new(RwLock::new(Vec::with_capacity(config.max_size))),
+            config,
             total_priority: Arc::new(RwLock::new(0.0)),
             min_priority: Arc::new(RwLock::new(f32::MAX)),
             max_priority: Arc::new(RwLock::new(1.0)),
@@ -148,13 +232,57 @@ impl ReplayBuffer {
         let size = batch_size.unwrap_or(self.config.batch_size);
         let buffer = self.buffer.read().await;
         
+        if !self.config.with_replacement && buffer.len() < size {
+            return Err(anyhow::anyhow!(
+                "Cannot sample {} distinct experiences: buffer holds only {}",
+                size, buffer.len()
+            ));
+        }
         if buffer.len() < size {
             return Err(anyhow::anyhow!("Not enough experiences in buffer"));
         }
         
         let mut batch = Vec::with_capacity(size);
         
-        if self.config.prioritized {
+        if self.config.prioritized && !self.config.with_replacement {
+            // Prioritized sampling without replacement: each draw is
+            // proportional to priority among the experiences not yet taken
+            let priorities = self.priorities.read().await;
+            let total_priority = *self.total_priority.read().await;
+            let mut remaining = priorities.clone();
+            
+            for _ in 0..size {
+                let remaining_total: f32 = remaining.iter().sum();
+                let sample_point = rand::random::<f32>() * remaining_total;
+                
+                // Rounding can leave the sample point past the end; fall
+                // back to the last experience still available
+                let mut cumsum = 0.0;
+                let mut idx = None;
+                for (j, &priority) in remaining.iter().enumerate() {
+                    if priority <= 0.0 {
+                        continue;
+                    }
+                    cumsum += priority;
+                    idx = Some(j);
+                    if cumsum >= sample_point {
+                        break;
+                    }
+                }
+                let idx = idx
+                    .ok_or_else(|| anyhow::anyhow!("No experience left with non-zero priority"))?;
+                remaining[idx] = 0.0;
+                
+                let prob = priorities[idx] / total_priority;
+                let weight = (buffer.len() as f32 * prob).powf(-self.config.beta);
+                batch.push((buffer[idx].clone(), weight, idx));
+            }
+            
+            let max_weight = batch.iter().map(|(_, w, _)| *w).fold(0.0f32, f32::max);
+            for (_, weight, _) in &mut batch {
+                *weight /= max_weight;
+            }
+        } else if self.config.prioritized {
             // Prioritized sampling
             let priorities = self.priorities.read().await;
             let total_priority = *self.total_priority.read().await;
@@ -255,6 +383,17 @@ impl ReplayBuffer {
     
     /// Save buffer to disk
     pub async fn save(&self, path: &Path) -> Result<()> {
+        self.save_with(&DiskOps::default(), path).await
+    }
+    
+    /// Save buffer to disk through `disk`, replacing any previous file atomically
+    pub async fn save_with(&self, disk: &DiskOps, path: &Path) -> Result<()> {
+        let (backend, key) = FsBackend::for_file(path);
+        self.save_to(&backend.with_disk_ops(disk.clone()), &key).await
+    }
+    
+    /// Save buffer under `key` in `backend`, replacing any previous value
+    pub async fn save_to(&self, backend: &dyn StorageBackend, key: &str) -> Result<()> {
         let buffer = self.buffer.read().await;
         let data = bincode::serialize(&buffer.clone().into_iter().collect::<Vec<_>>())?;
         
@@ -263,22 +402,38 @@ impl ReplayBuffer {
         encoder.write_all(&data)?;
         let compressed = encoder.finish()?;
         
-        fs::write(path, compressed).await?;
-        log::info!("Saved replay buffer with {} experiences to {:?}", buffer.len(), path);
+        backend.save(key, &compressed).await?;
+        log::info!("Saved replay buffer with {} experiences to {:?}", buffer.len(), key);
         
         Ok(())
     }
     
     /// Load buffer from disk
-    pub async fn load(&self, path: &Path) -> Result<()> {
-        let compressed = fs::read(path).await?;
-        
-        // Decompress data
-        let mut decoder = GzDecoder::new(&compressed[..]);
-        let mut data = Vec::new();
-        decoder.read_to_end(&mut data)?;
-        
-        let experiences: Vec<Experience> = bincode::deserialize(&data)?;
+    ///
+    /// Experiences are decoded one at a time straight from the compressed
+    /// stream, so large buffers load without a decompressed copy of the
+    /// file in memory, and a truncated or corrupted file still yields
+    /// everything before the damage. Entries whose dimensions don't match
+    /// `state_dim`/`action_dim`, or that hold non-finite values, are
+    /// dropped. With `strict_load` set any of these problems is an error
+    /// and the buffer is left untouched. A file whose length header can't
+    /// be read is always an error.
+    pub async fn load(&self, path: &Path) -> Result<LoadReport> {
+        self.load_with(&DiskOps::default(), path).await
+    }
+    
+    /// [`ReplayBuffer::load`] reading through `disk`
+    pub async fn load_with(&self, disk: &DiskOps, path: &Path) -> Result<LoadReport> {
+        let (backend, key) = FsBackend::for_file(path);
+        self.load_from(&backend.with_disk_ops(disk.clone()), &key).await
+    }
+    
+    /// [`ReplayBuffer::load`] reading `key` from `backend`
+    pub async fn load_from(&self, backend: &dyn StorageBackend, key: &str) -> Result<LoadReport> {
+        let compressed = backend.load(key).await?
+            .ok_or_else(|| anyhow::anyhow!("No replay buffer stored under {:?}", key))?;
+        let mut experiences = Vec::new();
+        let report = self.read_experiences(GzDecoder::new(&compressed[..]), key, |exp| experiences.push(exp))?;
         
         // Clear current buffer
         self.clear().await;
@@ -288,51 +443,278 @@ impl ReplayBuffer {
             self.add(exp).await?;
         }
         
-        log::info!("Loaded {} experiences from {:?}", self.len().await, path);
+        if report.is_complete() && report.dropped == 0 {
+            log::info!("Loaded {} experiences from {:?}", report.recovered, key);
+        } else {
+            log::warn!(
+                "Recovered {} of {} experiences from {:?} ({} malformed dropped, {} unreadable)",
+                report.recovered, report.expected, key, report.dropped,
+                report.expected.saturating_sub(report.recovered + report.dropped)
+            );
+        }
+        Ok(report)
+    }
+    
+    /// Replace the buffer's contents with a uniform sample of the buffers
+    /// saved at `paths`
+    ///
+    /// Every experience across all files has the same chance of being kept,
+    /// whichever file it came from, so no source is favoured by its position
+    /// in `paths`. At most `target_size` experiences are kept, and never more
+    /// than `max_size`. Files are streamed through a reservoir sample, so only
+    /// the kept experiences are held in memory. Entries are validated as in
+    /// [`ReplayBuffer::load`]; with `strict_load` a bad file fails the merge
+    /// and the buffer is left untouched.
+    ///
+    /// Saved buffers carry no priorities, so merged experiences start at the
+    /// maximum priority like newly added ones. [`ReplayBuffer::merge`] keeps
+    /// the priorities of in-memory buffers.
+    pub async fn merge_from(&self, paths: &[PathBuf], target_size: usize) -> Result<Vec<LoadReport>> {
+        let mut reservoir = Reservoir::new(target_size.min(self.config.max_size));
+        let mut reports = Vec::with_capacity(paths.len());
+        for path in paths {
+            let (backend, key) = FsBackend::for_file(path);
+            let compressed = backend.load(&key).await?
+                .ok_or_else(|| anyhow::anyhow!("No replay buffer stored at {:?}", path))?;
+            let report = self.read_experiences(GzDecoder::new(&compressed[..]), &key, |exp| {
+                reservoir.offer(exp, None);
+            })?;
+            reports.push(report);
+        }
+        
+        let seen = reservoir.seen;
+        self.replace_contents(reservoir.items).await;
+        log::info!("Merged {} replay buffers: kept {} of {} experiences", paths.len(), self.len().await, seen);
+        Ok(reports)
+    }
+    
+    /// [`ReplayBuffer::merge_from`] sampling from in-memory buffers
+    ///
+    /// When both this buffer and a source are prioritized, kept experiences
+    /// bring their priority along, so their relative priorities survive
+    /// the merge.
+    pub async fn merge(&self, sources: &[&ReplayBuffer], target_size: usize) {
+        let mut reservoir = Reservoir::new(target_size.min(self.config.max_size));
+        for source in sources {
+            let buffer = source.buffer.read().await;
+            let priorities = source.priorities.read().await;
+            for (i, exp) in buffer.iter().enumerate() {
+                if let Some(slot) = reservoir.admit() {
+                    let priority = if source.config.prioritized { priorities.get(i).copied() } else { None };
+                    reservoir.put(slot, exp.clone(), priority);
+                }
+            }
+        }
+        self.replace_contents(reservoir.items).await;
+    }
+    
+    /// Swap in merged experiences, oldest first so eviction order holds.
+    /// Experiences without a priority get the highest one among the rest.
+    async fn replace_contents(&self, mut items: Vec<(Experience, Option<f32>)>) {
+        items.sort_by_key(|(exp, _)| exp.timestamp);
+        self.clear().await;
+        
+        let mut buffer = self.buffer.write().await;
+        let mut priorities = self.priorities.write().await;
+        let known: Vec<f32> = items.iter().filter_map(|(_, priority)| *priority).collect();
+        let max_priority = known.iter().copied().reduce(f32::max).unwrap_or(1.0);
+        for (exp, priority) in items {
+            buffer.push_back(exp);
+            if self.config.prioritized {
+                priorities.push(priority.unwrap_or(max_priority));
+            }
+        }
+        
+        if self.config.prioritized {
+            *self.total_priority.write().await = priorities.iter().sum();
+            *self.min_priority.write().await = known.into_iter().fold(f32::MAX, f32::min);
+            *self.max_priority.write().await = max_priority;
+        }
+    }
+    
+    /// Decode and validate experiences from the decompressed stream `data`,
+    /// handing each valid one to `keep`
+    ///
+    /// Reads go through a [`LOAD_CHUNK`]-sized buffer and experiences are
+    /// deserialized one at a time, so the decompressed file is never held in
+    /// memory as a whole.
+    fn read_experiences<R: Read>(
+        &self,
+        data: R,
+        key: &str,
+        mut keep: impl FnMut(Experience),
+    ) -> Result<LoadReport> {
+        let strict = self.config.strict_load;
+        let mut reader = BufReader::with_capacity(LOAD_CHUNK, data);
+        
+        // Same layout as `bincode::serialize(&Vec<Experience>)`: a u64 length
+        // followed by the experiences
+        // Without the length there is no telling how much was lost, so even a
+        // lenient load fails rather than report an empty buffer as complete
+        let mut report = LoadReport::default();
+        let expected: u64 = bincode::deserialize_from(&mut reader)
+            .with_context(|| format!("Unreadable replay buffer {:?}", key))?;
+        report.expected = expected as usize;
+        
+        while report.recovered + report.dropped < report.expected {
+            let exp: Experience = match bincode::deserialize_from(&mut reader) {
+                Ok(exp) => exp,
+                Err(e) if strict => {
+                    return Err(e).context(format!(
+                        "Replay buffer {:?} truncated after {} of {} experiences",
+                        key, report.recovered + report.dropped, report.expected
+                    ));
+                }
+                Err(e) => {
+                    log::warn!("Replay buffer {:?} is corrupted ({}), keeping what was readable", key, e);
+                    break;
+                }
+            };
+            
+            match self.validate(&exp) {
+                Ok(()) => {
+                    keep(exp);
+                    report.recovered += 1;
+                }
+                Err(reason) if strict => {
+                    anyhow::bail!("Malformed experience {} in {:?}: {}", report.recovered + report.dropped, key, reason);
+                }
+                Err(reason) => {
+                    log::debug!("Dropping experience: {}", reason);
+                    report.dropped += 1;
+                }
+            }
+        }
+        
+        // Run the decoder to the end so a damaged gzip trailer is still noticed
+        if report.is_complete() {
+            if let Err(e) = std::io::copy(&mut reader, &mut std::io::sink()) {
+                if strict {
+                    return Err(e).context(format!("Corrupted replay buffer {:?}", key));
+                }
+                log::warn!("Replay buffer {:?} is corrupted ({}), keeping what was readable", key, e);
+            }
+        }
+        
+        Ok(report)
+    }
+    
+    /// Check an experience against the configured dimensions
+    fn validate(&self, exp: &Experience) -> std::result::Result<(), String> {
+        if let Some(dim) = self.config.state_dim {
+            if exp.state.len() != dim || exp.next_state.len() != dim {
+                return Err(format!(
+                    "state dims {}/{} do not match configured {}",
+                    exp.state.len(), exp.next_state.len(), dim
+                ));
+            }
+        }
+        if let Some(dim) = self.config.action_dim {
+            if exp.action.len() != dim {
+                return Err(format!("action dim {} does not match configured {}", exp.action.len(), dim));
+            }
+        }
+        
+        let mut values = exp.state.iter().chain(&exp.action).chain(&exp.next_state);
+        if !exp.reward.is_finite() || values.any(|v| !v.is_finite()) {
+            return Err("non-finite value".to_string());
+        }
         Ok(())
     }
 }
 
+/// Which checkpoints survive `PolicyStorage::cleanup`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Most recent checkpoints to keep, by episode
+    pub keep_latest: usize,
+    /// Highest-scoring checkpoints to keep, by best reward
+    pub keep_best: usize,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_latest: 5,
+            keep_best: 3,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Indices of the checkpoints to keep: the union of the latest and best sets
+    pub fn select(&self, checkpoints: &[&PolicyMetadata]) -> std::collections::HashSet<usize> {
+        let mut order: Vec<usize> = (0..checkpoints.len()).collect();
+        let mut keep = std::collections::HashSet::new();
+        
+        order.sort_by(|&a, &b| checkpoints[b].episode.cmp(&checkpoints[a].episode));
+        keep.extend(order.iter().take(self.keep_latest));
+        
+        order.sort_by(|&a, &b| checkpoints[b].best_reward.total_cmp(&checkpoints[a].best_reward));
+        keep.extend(order.iter().take(self.keep_best));
+        
+        keep
+    }
+}
+
 /// Storage for policy checkpoints
+///
+/// Each checkpoint is two keys in the backend, `<id>/model.bin.gz` and
+/// `<id>/metadata.json`. The metadata cache mirrors the stored checkpoints.
+/// Every change to either happens under the cache's write lock, so saves,
+/// cleanups and refreshes cannot interleave and leave the two out of step.
 pub struct PolicyStorage {
-    storage_dir: PathBuf,
-    metadata_cache: Arc<RwLock<Vec<PolicyMetadata>>>,
+    backend: Arc<dyn StorageBackend>,
+    metadata_cache: Arc<RwLock<HashMap<Uuid, PolicyMetadata>>>,
 }
 
 impl PolicyStorage {
+    /// Checkpoints in directories under `storage_dir`
     pub fn new(storage_dir: PathBuf) -> Self {
+        Self::with_backend(Arc::new(FsBackend::new(storage_dir)))
+    }
+    
+    /// Checkpoints in `backend`
+    pub fn with_backend(backend: Arc<dyn StorageBackend>) -> Self {
         Self {
-            storage_dir,
-            metadata_cache: Arc::new(RwLock::new(Vec::new())),
+            backend,
+            metadata_cache: Arc::new(RwLock::new(HashMap::new())),
         }
     }
     
-    /// Initialize storage directory
+    /// Load the metadata of stored checkpoints
     pub async fn init(&self) -> Result<()> {
-        fs::create_dir_all(&self.storage_dir).await?;
         self.refresh_cache().await?;
         Ok(())
     }
     
+    fn model_key(id: Uuid) -> String {
+        format!("{}/model.bin.gz", id)
+    }
+    
+    fn metadata_key(id: Uuid) -> String {
+        format!("{}/metadata.json", id)
+    }
+    
     /// Save policy checkpoint
+    ///
+    /// The model is written before the metadata, and both atomically, so a
+    /// checkpoint only becomes visible to listings once it is complete. The
+    /// metadata is published and cached in one step under the cache lock.
     pub async fn save_checkpoint(&self, checkpoint: PolicyCheckpoint) -> Result<Uuid> {
-        let checkpoint_dir = self.storage_dir.join(checkpoint.id.to_string());
-        fs::create_dir_all(&checkpoint_dir).await?;
-        
-        // Save metadata
-        let metadata_path = checkpoint_dir.join("metadata.json");
-        let metadata_json = serde_json::to_string_pretty(&checkpoint)?;
-        fs::write(&metadata_path, metadata_json).await?;
-        
         // Save model parameters (compressed)
-        let model_path = checkpoint_dir.join("model.bin.gz");
         let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
         encoder.write_all(&checkpoint.parameters)?;
         let compressed = encoder.finish()?;
-        fs::write(&model_path, compressed).await?;
+        self.backend.save(&Self::model_key(checkpoint.id), &compressed).await?;
         
-        // Update cache
-        self.metadata_cache.write().await.push(checkpoint.metadata.clone());
+        // Save metadata and update the cache together
+        let metadata_json = serde_json::to_string_pretty(&checkpoint)?;
+        let mut cache = self.metadata_cache.write().await;
+        self.backend
+            .save(&Self::metadata_key(checkpoint.id), metadata_json.as_bytes())
+            .await?;
+        cache.insert(checkpoint.id, checkpoint.metadata.clone());
         
         log::info!("Saved policy checkpoint: {}", checkpoint.id);
         Ok(checkpoint.id)
@@ -340,16 +722,14 @@ impl PolicyStorage {
     
     /// Load policy checkpoint
     pub async fn load_checkpoint(&self, id: Uuid) -> Result<PolicyCheckpoint> {
-        let checkpoint_dir = self.storage_dir.join(id.to_string());
-        
         // Load metadata
-        let metadata_path = checkpoint_dir.join("metadata.json");
-        let metadata_json = fs::read_to_string(&metadata_path).await?;
-        let mut checkpoint: PolicyCheckpoint = serde_json::from_str(&metadata_json)?;
+        let metadata_json = self.backend.load(&Self::metadata_key(id)).await?
+            .ok_or_else(|| anyhow::anyhow!("No checkpoint {}", id))?;
+        let mut checkpoint: PolicyCheckpoint = serde_json::from_slice(&metadata_json)?;
         
         // Load model parameters
-        let model_path = checkpoint_dir.join("model.bin.gz");
-        let compressed = fs::read(&model_path).await?;
+        let compressed = self.backend.load(&Self::model_key(id)).await?
+            .ok_or_else(|| anyhow::anyhow!("Checkpoint {} has no model parameters", id))?;
         let mut decoder = GzDecoder::new(&compressed[..]);
         let mut parameters = Vec::new();
         decoder.read_to_end(&mut parameters)?;
@@ -358,72 +738,53 @@ impl PolicyStorage {
         Ok(checkpoint)
     }
     
-    /// List all checkpoints
+    /// List all checkpoints, oldest episode first
     pub async fn list_checkpoints(&self) -> Result<Vec<PolicyMetadata>> {
-        Ok(self.metadata_cache.read().await.clone())
+        let mut checkpoints: Vec<PolicyMetadata> =
+            self.metadata_cache.read().await.values().cloned().collect();
+        checkpoints.sort_by_key(|m| m.episode);
+        Ok(checkpoints)
+    }
+    
+    /// IDs of all stored checkpoints, sorted
+    pub async fn checkpoint_ids(&self) -> Result<Vec<Uuid>> {
+        let _cache = self.metadata_cache.read().await;
+        let mut ids: Vec<Uuid> = self.scan_checkpoints().await?
+            .into_iter()
+            .map(|checkpoint| checkpoint.id)
+            .collect();
+        
+        ids.sort();
+        Ok(ids)
     }
     
     /// Get best checkpoint by reward
     pub async fn get_best_checkpoint(&self) -> Result<Option<Uuid>> {
         let cache = self.metadata_cache.read().await;
         
-        if cache.is_empty() {
-            return Ok(None);
-        }
-        
-        let best = cache.iter()
-            .max_by(|a, b| a.best_reward.partial_cmp(&b.best_reward).unwrap())
-            .unwrap();
-        
-        // Find checkpoint ID
-        let mut entries = fs::read_dir(&self.storage_dir).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            if entry.file_type().await?.is_dir() {
-                let metadata_path = entry.path().join("metadata.json");
-                if metadata_path.exists() {
-                    let metadata_json = fs::read_to_string(&metadata_path).await?;
-                    let checkpoint: PolicyCheckpoint = serde_json::from_str(&metadata_json)?;
-                    if checkpoint.metadata.best_reward == best.best_reward {
-                        return Ok(Some(checkpoint.id));
-                    }
-                }
-            }
-        }
-        
-        Ok(None)
+        Ok(cache.iter()
+            .max_by(|(_, a), (_, b)| a.best_reward.total_cmp(&b.best_reward))
+            .map(|(id, _)| *id))
     }
     
-    /// Delete old checkpoints
-    pub async fn cleanup(&self, keep_count: usize) -> Result<usize> {
+    /// Delete checkpoints not covered by the retention policy
+    pub async fn cleanup(&self, policy: &RetentionPolicy) -> Result<usize> {
         let mut cache = self.metadata_cache.write().await;
         
-        if cache.len() <= keep_count {
-            return Ok(0);
-        }
-        
-        // Sort by episode number
-        cache.sort_by_key(|m| m.episode);
-        
-        // Keep only the latest checkpoints
-        let to_remove = cache.len() - keep_count;
-        let removed_metadata: Vec<_> = cache.drain(..to_remove).collect();
+        let stored = self.scan_checkpoints().await?;
+        let keep = policy.select(&stored.iter().map(|c| &c.metadata).collect::<Vec<_>>());
         
-        // Delete checkpoint directories
+        // Delete checkpoints in neither set, metadata first so a failure
+        // part way leaves nothing listed without its model
         let mut deleted = 0;
-        let mut entries = fs::read_dir(&self.storage_dir).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            if entry.file_type().await?.is_dir() {
-                let metadata_path = entry.path().join("metadata.json");
-                if metadata_path.exists() {
-                    let metadata_json = fs::read_to_string(&metadata_path).await?;
-                    let checkpoint: PolicyCheckpoint = serde_json::from_str(&metadata_json)?;
-                    
-                    // Check if this checkpoint should be removed
-                    if removed_metadata.iter().any(|m| m.episode == checkpoint.metadata.episode) {
-                        fs::remove_dir_all(entry.path()).await?;
-                        deleted += 1;
-                    }
-                }
+        cache.clear();
+        for (index, checkpoint) in stored.into_iter().enumerate() {
+            if keep.contains(&index) {
+                cache.insert(checkpoint.id, checkpoint.metadata);
+            } else {
+                self.backend.delete(&Self::metadata_key(checkpoint.id)).await?;
+                self.backend.delete(&Self::model_key(checkpoint.id)).await?;
+                deleted += 1;
             }
         }
         
@@ -433,22 +794,36 @@ impl PolicyStorage {
     
     /// Refresh metadata cache
     async fn refresh_cache(&self) -> Result<()> {
-        let mut cache = Vec::new();
-        
-        let mut entries = fs::read_dir(&self.storage_dir).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            if entry.file_type().await?.is_dir() {
-                let metadata_path = entry.path().join("metadata.json");
-                if metadata_path.exists() {
-                    let metadata_json = fs::read_to_string(&metadata_path).await?;
-                    let checkpoint: PolicyCheckpoint = serde_json::from_str(&metadata_json)?;
-                    cache.push(checkpoint.metadata);
-                }
+        let mut cache = self.metadata_cache.write().await;
+        
+        *cache = self.scan_checkpoints().await?
+            .into_iter()
+            .map(|checkpoint| (checkpoint.id, checkpoint.metadata))
+            .collect();
+        Ok(())
+    }
+    
+    /// Every complete checkpoint in the backend. Callers hold the cache
+    /// lock, read or write, so no save can publish metadata mid-scan.
+    async fn scan_checkpoints(&self) -> Result<Vec<PolicyCheckpoint>> {
+        let mut stored = Vec::new();
+        
+        for key in self.backend.list("").await? {
+            let is_metadata = key
+                .strip_suffix("/metadata.json")
+                .is_some_and(|id| !id.contains('/'));
+            if !is_metadata {
+                continue;
+            }
+            // Deleted since the listing; nothing to report
+            if let Some(metadata_json) = self.backend.load(&key).await? {
+                let checkpoint: PolicyCheckpoint = serde_json::from_slice(&metadata_json)
+                    .with_context(|| format!("Invalid checkpoint metadata {:?}", key))?;
+                stored.push(checkpoint);
             }
         }
         
-        *self.metadata_cache.write().await = cache;
-        Ok(())
+        Ok(stored)
     }
 }
 
@@ -458,16 +833,40 @@ pub struct RLMemoryStore {
     policy_storage: PolicyStorage,
     trajectories: Arc<RwLock<VecDeque<Trajectory>>>,
     max_trajectories: usize,
+    /// Set when the store lives on disk, so `with_disk_ops` can reach it
+    storage_dir: Option<PathBuf>,
+    disk: DiskOps,
 }
 
 impl RLMemoryStore {
+    /// Store with its policies under `storage_dir/policies`
     pub fn new(storage_dir: PathBuf) -> Self {
+        let mut store = Self::with_backend(Arc::new(FsBackend::new(storage_dir.clone())));
+        store.storage_dir = Some(storage_dir);
+        store
+    }
+    
+    /// Store with its policies under `policies/` in `backend`
+    pub fn with_backend(backend: Arc<dyn StorageBackend>) -> Self {
         Self {
             replay_buffers: Arc::new(Mutex::new(std::collections::HashMap::new())),
-            policy_storage: PolicyStorage::new(storage_dir.join("policies")),
+            policy_storage: PolicyStorage::with_backend(Arc::new(ScopedBackend::new(backend, "policies"))),
             trajectories: Arc::new(RwLock::new(VecDeque::new())),
             max_trajectories: 1000,
+            storage_dir: None,
+            disk: DiskOps::default(),
+        }
+    }
+    
+    /// Retry policy and filesystem used by `save_all`/`load_all` and, for a
+    /// store created with `new`, the policy storage
+    pub fn with_disk_ops(mut self, disk: DiskOps) -> Self {
+        if let Some(storage_dir) = &self.storage_dir {
+            let backend = FsBackend::new(storage_dir.join("policies")).with_disk_ops(disk.clone());
+            self.policy_storage = PolicyStorage::with_backend(Arc::new(backend));
         }
+        self.disk = disk;
+        self
     }
     
     /// Initialize store
@@ -498,6 +897,13 @@ impl RLMemoryStore {
         }
     }
     
+    /// Names of all replay buffers, sorted
+    pub async fn replay_buffer_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.replay_buffers.lock().await.keys().cloned().collect();
+        names.sort();
+        names
+    }
+    
     /// Add trajectory
     pub async fn add_trajectory(&self, trajectory: Trajectory) -> Result<()> {
         let mut trajectories = self.trajectories.write().await;
@@ -526,50 +932,59 @@ impl RLMemoryStore {
     }
     
     /// Save all buffers
+    ///
+    /// Each file is replaced atomically, with transient failures retried
+    /// under the store's [`crate::disk::RetryPolicy`].
     pub async fn save_all(&self, backup_dir: &Path) -> Result<()> {
-        fs::create_dir_all(backup_dir).await?;
-        
+        self.save_all_to(&self.fs_backend(backup_dir)).await?;
+        log::info!("Saved all memory stores to {:?}", backup_dir);
+        Ok(())
+    }
+    
+    /// Save all buffers and trajectories as keys in `backend`
+    pub async fn save_all_to(&self, backend: &dyn StorageBackend) -> Result<()> {
         let buffers = self.replay_buffers.lock().await;
         for (name, buffer) in buffers.iter() {
-            let buffer_path = backup_dir.join(format!("{}_replay.bin.gz", name));
-            buffer.save(&buffer_path).await?;
+            buffer.save_to(backend, &format!("{}_replay.bin.gz", name)).await?;
         }
         
         // Save trajectories
-        let trajectories_path = backup_dir.join("trajectories.json.gz");
         let trajectories = self.trajectories.read().await;
         let data = serde_json::to_vec(&trajectories.clone().into_iter().collect::<Vec<_>>())?;
         
         let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
         encoder.write_all(&data)?;
         let compressed = encoder.finish()?;
-        fs::write(&trajectories_path, compressed).await?;
+        backend.save("trajectories.json.gz", &compressed).await?;
         
-        log::info!("Saved all memory stores to {:?}", backup_dir);
         Ok(())
     }
     
     /// Load all buffers
     pub async fn load_all(&self, backup_dir: &Path) -> Result<()> {
+        if !backup_dir.is_dir() {
+            anyhow::bail!("No memory store backup at {:?}", backup_dir);
+        }
+        self.load_all_from(&self.fs_backend(backup_dir)).await?;
+        log::info!("Loaded all memory stores from {:?}", backup_dir);
+        Ok(())
+    }
+    
+    /// Load the buffers and trajectories saved in `backend` by `save_all_to`
+    pub async fn load_all_from(&self, backend: &dyn StorageBackend) -> Result<()> {
         // Load replay buffers
-        let mut entries = fs::read_dir(backup_dir).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            let path = entry.path();
-            if let Some(name) = path.file_stem() {
-                if let Some(name_str) = name.to_str() {
-                    if name_str.ends_with("_replay") {
-                        let buffer_name = name_str.trim_end_matches("_replay");
-                        let buffer = self.get_replay_buffer(buffer_name, None).await;
-                        buffer.load(&path).await?;
-                    }
-                }
+        for key in backend.list("").await? {
+            let buffer_name = key
+                .strip_suffix("_replay.bin.gz")
+                .filter(|name| !name.contains('/'));
+            if let Some(buffer_name) = buffer_name {
+                let buffer = self.get_replay_buffer(buffer_name, None).await;
+                buffer.load_from(backend, &key).await?;
             }
         }
         
         // Load trajectories
-        let trajectories_path = backup_dir.join("trajectories.json.gz");
-        if trajectories_path.exists() {
-            let compressed = fs::read(&trajectories_path).await?;
+        if let Some(compressed) = backend.load("trajectories.json.gz").await? {
             let mut decoder = GzDecoder::new(&compressed[..]);
             let mut data = Vec::new();
             decoder.read_to_end(&mut data)?;
@@ -580,14 +995,19 @@ impl RLMemoryStore {
             trajectories.extend(loaded_trajectories);
         }
         
-        log::info!("Loaded all memory stores from {:?}", backup_dir);
         Ok(())
     }
+    
+    fn fs_backend(&self, dir: &Path) -> FsBackend {
+        FsBackend::new(dir).with_disk_ops(self.disk.clone())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::InMemoryBackend;
+    use tokio::fs;
     
     #[tokio::test]
     async fn test_replay_buffer() {
@@ -654,6 +1074,47 @@ mod tests {
         buffer.update_priorities(&indices, &td_errors).await.unwrap();
     }
     
+    #[tokio::test]
+    async fn test_sampling_without_replacement() {
+        for prioritized in [true, false] {
+            let config = ReplayConfig {
+                max_size: 10,
+                batch_size: 8,
+                prioritized,
+                with_replacement: false,
+                ..Default::default()
+            };
+            let buffer = ReplayBuffer::new(config);
+            
+            for i in 0..8 {
+                buffer.add(Experience {
+                    state: vec![i as f32],
+                    action: vec![0.0],
+                    reward: 0.0,
+                    next_state: vec![(i + 1) as f32],
+                    done: false,
+                    metadata: None,
+                    timestamp: Utc::now(),
+                }).await.unwrap();
+            }
+            // Skewed priorities would make duplicates likely with replacement
+            buffer.update_priorities(&[0, 1], &[100.0, 50.0]).await.unwrap();
+            
+            for _ in 0..20 {
+                let batch = buffer.sample(None).await.unwrap();
+                let indices: Vec<_> = batch.iter().map(|(_, _, idx)| *idx).collect();
+                let mut distinct = indices.clone();
+                distinct.sort();
+                distinct.dedup();
+                assert_eq!(distinct.len(), 8, "duplicate index in {:?}", indices);
+                assert!(batch.iter().all(|(exp, _, idx)| exp.state[0] == *idx as f32));
+            }
+            
+            let err = buffer.sample(Some(9)).await.unwrap_err().to_string();
+            assert!(err.contains("9 distinct experiences"), "{}", err);
+        }
+    }
+    
     #[tokio::test]
     async fn test_policy_storage() {
         let temp_dir = std::env::temp_dir().join("test_policy_storage");
@@ -694,4 +1155,432 @@ mod tests {
         // Cleanup
         std::fs::remove_dir_all(temp_dir).ok();
     }
-}
\ No newline at end of file
+    
+    #[tokio::test]
+    async fn test_cleanup_keeps_old_best_checkpoint() {
+        let temp_dir = std::env::temp_dir().join(format!("test_policy_retention_{}", Uuid::new_v4()));
+        let storage = PolicyStorage::new(temp_dir.clone());
+        storage.init().await.unwrap();
+        
+        // Episode 10 is the best by far; later ones are mediocre
+        let rewards = [(10, 5.0), (20, 1.0), (30, 1.2), (40, 0.9), (50, 1.1), (60, 1.3)];
+        for (episode, best_reward) in rewards {
+            storage.save_checkpoint(PolicyCheckpoint {
+                id: Uuid::new_v4(),
+                model_type: "ppo".to_string(),
+                parameters: vec![0],
+                metadata: PolicyMetadata {
+                    episode,
+                    total_steps: episode * 100,
+                    average_reward: best_reward / 2.0,
+                    best_reward,
+                    training_time_hours: 0.1,
+                    hyperparameters: serde_json::json!({}),
+                },
+                created_at: Utc::now(),
+            }).await.unwrap();
+        }
+        
+        let policy = RetentionPolicy { keep_latest: 2, keep_best: 1 };
+        let deleted = storage.cleanup(&policy).await.unwrap();
+        assert_eq!(deleted, 3);
+        
+        let mut episodes: Vec<usize> = storage.list_checkpoints().await.unwrap()
+            .iter()
+            .map(|m| m.episode)
+            .collect();
+        episodes.sort();
+        assert_eq!(episodes, vec![10, 50, 60]);
+        
+        // Survivors are still on disk after a reload
+        storage.refresh_cache().await.unwrap();
+        assert_eq!(storage.list_checkpoints().await.unwrap().len(), 3);
+        
+        std::fs::remove_dir_all(temp_dir).ok();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_saves_and_cleanup_keep_cache_in_sync() {
+        let temp_dir = std::env::temp_dir().join(format!("test_policy_race_{}", Uuid::new_v4()));
+        let storage = Arc::new(PolicyStorage::new(temp_dir.clone()));
+        storage.init().await.unwrap();
+        let policy = RetentionPolicy { keep_latest: 3, keep_best: 2 };
+        
+        let mut tasks = Vec::new();
+        for episode in 0..40 {
+            let saver = storage.clone();
+            tasks.push(tokio::spawn(async move {
+                saver.save_checkpoint(PolicyCheckpoint {
+                    id: Uuid::new_v4(),
+                    model_type: "ppo".to_string(),
+                    parameters: vec![0; 4096],
+                    metadata: PolicyMetadata {
+                        episode,
+                        total_steps: episode * 100,
+                        average_reward: 0.0,
+                        best_reward: (episode % 7) as f32,
+                        training_time_hours: 0.1,
+                        hyperparameters: serde_json::json!({}),
+                    },
+                    created_at: Utc::now(),
+                }).await.map(|_| ())
+            }));
+            if episode % 8 == 4 {
+                let cleaner = storage.clone();
+                tasks.push(tokio::spawn(async move {
+                    cleaner.cleanup(&policy).await.map(|_| ())
+                }));
+            }
+        }
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        
+        let mut cached: Vec<Uuid> = storage.metadata_cache.read().await.keys().copied().collect();
+        cached.sort();
+        let on_disk = storage.checkpoint_ids().await.unwrap();
+        assert!(!on_disk.is_empty());
+        assert_eq!(cached, on_disk);
+        
+        // Cached metadata is what the directories hold
+        for id in on_disk {
+            let loaded = storage.load_checkpoint(id).await.unwrap();
+            let cache = storage.metadata_cache.read().await;
+            assert_eq!(cache[&id].episode, loaded.metadata.episode);
+        }
+        
+        std::fs::remove_dir_all(temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_round_trip_through_in_memory_backend() {
+        let backend = Arc::new(InMemoryBackend::new());
+        let storage = PolicyStorage::with_backend(backend.clone());
+        storage.init().await.unwrap();
+        
+        let mut ids = Vec::new();
+        for episode in [10, 20, 30] {
+            ids.push(storage.save_checkpoint(PolicyCheckpoint {
+                id: Uuid::new_v4(),
+                model_type: "dqn".to_string(),
+                parameters: vec![episode as u8; 64],
+                metadata: PolicyMetadata {
+                    episode,
+                    total_steps: episode * 100,
+                    average_reward: 0.5,
+                    best_reward: episode as f32,
+                    training_time_hours: 0.1,
+                    hyperparameters: serde_json::json!({ "gamma": 0.99 }),
+                },
+                created_at: Utc::now(),
+            }).await.unwrap());
+        }
+        assert_eq!(backend.list("").await.unwrap().len(), 6);
+        
+        let loaded = storage.load_checkpoint(ids[1]).await.unwrap();
+        assert_eq!(loaded.parameters, vec![20; 64]);
+        assert_eq!(loaded.metadata.hyperparameters["gamma"], 0.99);
+        assert_eq!(storage.get_best_checkpoint().await.unwrap(), Some(ids[2]));
+        
+        // A second storage over the same backend sees the same checkpoints
+        let reopened = PolicyStorage::with_backend(backend.clone());
+        reopened.init().await.unwrap();
+        let mut expected = ids.clone();
+        expected.sort();
+        assert_eq!(reopened.checkpoint_ids().await.unwrap(), expected);
+        
+        let policy = RetentionPolicy { keep_latest: 1, keep_best: 0 };
+        assert_eq!(reopened.cleanup(&policy).await.unwrap(), 2);
+        assert_eq!(reopened.checkpoint_ids().await.unwrap(), vec![ids[2]]);
+        assert_eq!(backend.list("").await.unwrap().len(), 2);
+        assert!(reopened.load_checkpoint(ids[0]).await.is_err());
+    }
+    
+    #[tokio::test]
+    async fn test_checkpoint_listing_waits_for_writers() {
+        let storage = Arc::new(PolicyStorage::with_backend(Arc::new(InMemoryBackend::new())));
+        storage.init().await.unwrap();
+        
+        // A save or cleanup in progress holds the cache's write lock
+        let writer = storage.metadata_cache.write().await;
+        let lister = storage.clone();
+        let listing = tokio::spawn(async move { lister.checkpoint_ids().await });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!listing.is_finished());
+        
+        drop(writer);
+        assert!(listing.await.unwrap().unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn test_store_round_trips_through_in_memory_backend() {
+        let backend = Arc::new(InMemoryBackend::new());
+        let store = RLMemoryStore::with_backend(backend.clone());
+        store.init().await.unwrap();
+        
+        let buffer = store.get_replay_buffer("dqn", None).await;
+        for i in 0..5 {
+            buffer.add(Experience {
+                state: vec![i as f32],
+                action: vec![0.0],
+                reward: i as f32,
+                next_state: vec![(i + 1) as f32],
+                done: i == 4,
+                metadata: None,
+                timestamp: Utc::now(),
+            }).await.unwrap();
+        }
+        store.add_trajectory(Trajectory {
+            id: Uuid::new_v4(),
+            experiences: Vec::new(),
+            total_reward: 3.0,
+            metadata: None,
+            created_at: Utc::now(),
+        }).await.unwrap();
+        store.policy_storage().save_checkpoint(PolicyCheckpoint {
+            id: Uuid::new_v4(),
+            model_type: "dqn".to_string(),
+            parameters: vec![1, 2, 3],
+            metadata: PolicyMetadata {
+                episode: 1,
+                total_steps: 5,
+                average_reward: 1.0,
+                best_reward: 1.0,
+                training_time_hours: 0.0,
+                hyperparameters: serde_json::json!({}),
+            },
+            created_at: Utc::now(),
+        }).await.unwrap();
+        
+        let backup = InMemoryBackend::new();
+        store.save_all_to(&backup).await.unwrap();
+        assert_eq!(
+            backup.list("").await.unwrap(),
+            vec!["dqn_replay.bin.gz", "trajectories.json.gz"]
+        );
+        
+        let restored = RLMemoryStore::with_backend(backend.clone());
+        restored.init().await.unwrap();
+        restored.load_all_from(&backup).await.unwrap();
+        assert_eq!(restored.replay_buffer_names().await, vec!["dqn"]);
+        assert_eq!(restored.get_replay_buffer("dqn", None).await.len().await, 5);
+        assert_eq!(restored.get_trajectories(10).await[0].total_reward, 3.0);
+        assert_eq!(restored.policy_storage().list_checkpoints().await.unwrap().len(), 1);
+        
+        // Policies share the store's backend under their own prefix
+        assert!(backend.list("").await.unwrap().iter().all(|key| key.starts_with("policies/")));
+    }
+
+    #[tokio::test]
+    async fn test_load_truncated_buffer() {
+        let config = ReplayConfig {
+            max_size: 1000,
+            prioritized: false,
+            state_dim: Some(4),
+            action_dim: Some(1),
+            ..Default::default()
+        };
+        let source = ReplayBuffer::new(config.clone());
+        for i in 0..300 {
+            // The second experience has the wrong state dimension
+            let state_dim = if i == 1 { 3 } else { 4 };
+            source.add(Experience {
+                state: (0..state_dim).map(|_| rand::random::<f32>()).collect(),
+                action: vec![(i % 4) as f32],
+                reward: rand::random::<f32>(),
+                next_state: (0..state_dim).map(|_| rand::random::<f32>()).collect(),
+                done: i % 50 == 49,
+                metadata: None,
+                timestamp: Utc::now(),
+            }).await.unwrap();
+        }
+        
+        let dir = std::env::temp_dir().join(format!("replay_load_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("replay.bin.gz");
+        source.save(&path).await.unwrap();
+        
+        // An intact file loads fully apart from the malformed entry
+        let lenient = ReplayBuffer::new(config.clone());
+        let report = lenient.load(&path).await.unwrap();
+        assert_eq!(report, LoadReport { expected: 300, recovered: 299, dropped: 1 });
+        
+        // Chop the file part way through the compressed stream
+        let bytes = fs::read(&path).await.unwrap();
+        fs::write(&path, &bytes[..bytes.len() * 2 / 3]).await.unwrap();
+        
+        let report = lenient.load(&path).await.unwrap();
+        assert!(!report.is_complete());
+        assert!(report.recovered > 0 && report.recovered < 299, "{:?}", report);
+        assert_eq!(report.dropped, 1);
+        assert_eq!(lenient.len().await, report.recovered);
+        
+        // Strict loading refuses the file and keeps the current contents
+        let strict = ReplayBuffer::new(ReplayConfig { strict_load: true, ..config });
+        strict.add(Experience {
+            state: vec![0.0; 4],
+            action: vec![0.0],
+            reward: 0.0,
+            next_state: vec![0.0; 4],
+            done: true,
+            metadata: None,
+            timestamp: Utc::now(),
+        }).await.unwrap();
+        assert!(strict.load(&path).await.is_err());
+        assert_eq!(strict.len().await, 1);
+        
+        // A header too damaged to read fails even a lenient load, rather than
+        // passing for a complete load of nothing
+        fs::write(&path, &bytes[..12]).await.unwrap();
+        let err = lenient.load(&path).await.unwrap_err();
+        assert!(err.to_string().contains("Unreadable"), "{:#}", err);
+        assert_eq!(lenient.len().await, report.recovered);
+        
+        fs::remove_dir_all(&dir).await.ok();
+    }
+
+    /// Passes reads through while recording the largest one
+    struct PeakReader<R> {
+        inner: R,
+        total: usize,
+        largest_read: usize,
+    }
+    
+    impl<R: Read> Read for PeakReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.total += n;
+            self.largest_read = self.largest_read.max(buf.len());
+            Ok(n)
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_streaming_load_matches_eager_decode() {
+        let config = ReplayConfig {
+            max_size: 20_000,
+            prioritized: false,
+            ..Default::default()
+        };
+        let source = ReplayBuffer::new(config.clone());
+        for i in 0..20_000 {
+            source.add(Experience {
+                state: (0..16).map(|_| rand::random::<f32>()).collect(),
+                action: vec![(i % 4) as f32],
+                reward: rand::random::<f32>(),
+                next_state: (0..16).map(|_| rand::random::<f32>()).collect(),
+                done: i % 100 == 99,
+                metadata: None,
+                timestamp: Utc::now(),
+            }).await.unwrap();
+        }
+        let backend = InMemoryBackend::new();
+        source.save_to(&backend, "replay").await.unwrap();
+        let compressed = backend.load("replay").await.unwrap().unwrap();
+        
+        // Eager path: decompress everything, then deserialize the whole Vec
+        let mut data = Vec::new();
+        GzDecoder::new(&compressed[..]).read_to_end(&mut data).unwrap();
+        let eager: Vec<Experience> = bincode::deserialize(&data).unwrap();
+        
+        let loaded = ReplayBuffer::new(config);
+        let report = loaded.load_from(&backend, "replay").await.unwrap();
+        assert_eq!(report, LoadReport { expected: 20_000, recovered: 20_000, dropped: 0 });
+        let streamed = loaded.buffer.read().await;
+        assert_eq!(streamed.len(), eager.len());
+        for (a, b) in streamed.iter().zip(&eager) {
+            assert_eq!(bincode::serialize(a).unwrap(), bincode::serialize(b).unwrap());
+        }
+        
+        // The decompressed bytes are only ever seen a chunk at a time, far
+        // below the size of the whole file
+        let mut peak = PeakReader { inner: GzDecoder::new(&compressed[..]), total: 0, largest_read: 0 };
+        let report = loaded.read_experiences(&mut peak, "replay", |_| {}).unwrap();
+        assert_eq!(report.recovered, eager.len());
+        assert_eq!(peak.total, data.len());
+        assert!(peak.largest_read <= LOAD_CHUNK, "read {} bytes at once", peak.largest_read);
+        assert!(data.len() > 20 * LOAD_CHUNK, "buffer too small to exercise streaming");
+    }
+
+    #[tokio::test]
+    async fn test_merge_samples_sources_uniformly() {
+        let config = ReplayConfig {
+            max_size: 10_000,
+            prioritized: false,
+            ..Default::default()
+        };
+        let dir = std::env::temp_dir().join(format!("replay_merge_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).await.unwrap();
+        
+        // The action records which source an experience came from
+        let sizes = [1000, 2000, 3000];
+        let mut paths = Vec::new();
+        for (source, &size) in sizes.iter().enumerate() {
+            let buffer = ReplayBuffer::new(config.clone());
+            for _ in 0..size {
+                buffer.add(Experience {
+                    state: vec![0.0; 2],
+                    action: vec![source as f32],
+                    reward: 0.0,
+                    next_state: vec![0.0; 2],
+                    done: false,
+                    metadata: None,
+                    timestamp: Utc::now(),
+                }).await.unwrap();
+            }
+            let path = dir.join(format!("replay_{}.bin.gz", source));
+            buffer.save(&path).await.unwrap();
+            paths.push(path);
+        }
+        
+        let merged = ReplayBuffer::new(config.clone());
+        let reports = merged.merge_from(&paths, 600).await.unwrap();
+        assert_eq!(reports.iter().map(|r| r.recovered).collect::<Vec<_>>(), sizes);
+        assert_eq!(merged.len().await, 600);
+        
+        // Each experience is equally likely to be kept, so a source's share
+        // follows its size: 100, 200 and 300 expected, with a standard
+        // deviation of about 12 at most
+        let mut counts = [0usize; 3];
+        for exp in merged.buffer.read().await.iter() {
+            counts[exp.action[0] as usize] += 1;
+        }
+        for (count, size) in counts.iter().zip(sizes) {
+            let expected = size / 10;
+            assert!(count.abs_diff(expected) <= 50, "kept {:?} of sources sized {:?}", counts, sizes);
+        }
+        
+        // Priorities of in-memory sources come along
+        let prioritized = ReplayConfig { prioritized: true, ..config };
+        let a = ReplayBuffer::new(prioritized.clone());
+        let b = ReplayBuffer::new(prioritized.clone());
+        for (buffer, count) in [(&a, 3), (&b, 2)] {
+            for i in 0..count {
+                buffer.add(Experience {
+                    state: vec![i as f32],
+                    action: vec![0.0],
+                    reward: 0.0,
+                    next_state: vec![0.0],
+                    done: false,
+                    metadata: None,
+                    timestamp: Utc::now(),
+                }).await.unwrap();
+            }
+        }
+        a.update_priorities(&[0, 1, 2], &[1.0, 2.0, 3.0]).await.unwrap();
+        let merged = ReplayBuffer::new(prioritized);
+        merged.merge(&[&a, &b], 10).await;
+        
+        let mut expected: Vec<f32> = a.priorities.read().await.iter()
+            .chain(b.priorities.read().await.iter())
+            .copied()
+            .collect();
+        let mut kept = merged.priorities.read().await.clone();
+        expected.sort_by(f32::total_cmp);
+        kept.sort_by(f32::total_cmp);
+        assert_eq!(kept, expected);
+        assert!((*merged.total_priority.read().await - expected.iter().sum::<f32>()).abs() < 1e-4);
+        
+        fs::remove_dir_all(&dir).await.ok();
+    }
+}
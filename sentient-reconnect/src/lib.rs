@@ -0,0 +1,242 @@
+//! Reconnecting to HTTP backends that briefly drop off the network
+//!
+//! [`retry`] keeps calling an operation while it fails with
+//! [`Failure::Transient`], sleeping with jittered exponential backoff, until
+//! it succeeds or [`Backoff::deadline`] passes. [`Failure::Misconfigured`]
+//! stops it at once: retrying a wrong URL or endpoint path will not help.
+//! The two outcomes stay distinct in [`ReconnectError`], so callers can
+//! report "down for now" differently from "set up wrong".
+//!
+//! Shared by `AiClient` in sentient-shell and by `sentientctl validate`.
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
+
+/// Retry schedule for [`retry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Upper bound on any single delay
+    pub max_delay: Duration,
+    /// Time after the first attempt at which the endpoint is declared down
+    pub deadline: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            deadline: Duration::from_secs(3),
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before retry number `retry`, counting from zero: between half
+    /// and all of `initial_delay * 2^retry`, capped at `max_delay`
+    pub fn delay(&self, retry: u32) -> Duration {
+        let cap = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        cap / 2 + cap.mul_f64(unit_jitter() / 2.0)
+    }
+}
+
+/// Uniform in [0, 1), from the randomly keyed std hasher
+fn unit_jitter() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Why one attempt failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+    /// The endpoint may answer if asked again (refused, reset, timed out, 5xx)
+    Transient(String),
+    /// The endpoint answered in a way asking again will not change
+    Misconfigured(String),
+}
+
+impl Failure {
+    /// Classify a reqwest error
+    pub fn from_reqwest(err: &reqwest::Error) -> Self {
+        if let Some(status) = err.status() {
+            Self::from_status(status)
+        } else if err.is_connect() || err.is_timeout() || err.is_request() {
+            Failure::Transient(err.to_string())
+        } else {
+            // Bad URL, redirect loop, or a body that is not what the API sends
+            Failure::Misconfigured(err.to_string())
+        }
+    }
+
+    /// Classify an unsuccessful HTTP status
+    pub fn from_status(status: reqwest::StatusCode) -> Self {
+        let reason = format!("HTTP {}", status);
+        if status.is_server_error()
+            || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || status == reqwest::StatusCode::REQUEST_TIMEOUT
+        {
+            Failure::Transient(reason)
+        } else {
+            Failure::Misconfigured(reason)
+        }
+    }
+}
+
+/// How [`retry`] gave up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReconnectError {
+    /// Still failing transiently at the deadline; the endpoint may come back
+    Unreachable {
+        attempts: u32,
+        elapsed: Duration,
+        last_error: String,
+    },
+    /// Failed in a way retrying cannot fix
+    Misconfigured(String),
+}
+
+impl ReconnectError {
+    /// Whether the endpoint is down for now rather than set up wrong
+    pub fn is_unreachable(&self) -> bool {
+        matches!(self, ReconnectError::Unreachable { .. })
+    }
+}
+
+impl fmt::Display for ReconnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReconnectError::Unreachable {
+                attempts,
+                elapsed,
+                last_error,
+            } => write!(
+                f,
+                "temporarily unreachable after {} attempts over {:?}: {}",
+                attempts, elapsed, last_error
+            ),
+            ReconnectError::Misconfigured(reason) => write!(f, "misconfigured: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ReconnectError {}
+
+/// Call `operation` until it succeeds, fails with [`Failure::Misconfigured`],
+/// or `backoff.deadline` passes. Always makes at least one attempt.
+pub fn retry<T>(
+    backoff: &Backoff,
+    mut operation: impl FnMut() -> Result<T, Failure>,
+) -> Result<T, ReconnectError> {
+    let start = Instant::now();
+    let mut attempts = 0;
+    loop {
+        let last_error = match operation() {
+            Ok(value) => return Ok(value),
+            Err(Failure::Misconfigured(reason)) => return Err(ReconnectError::Misconfigured(reason)),
+            Err(Failure::Transient(reason)) => reason,
+        };
+        attempts += 1;
+
+        let elapsed = start.elapsed();
+        let Some(remaining) = backoff.deadline.checked_sub(elapsed).filter(|r| !r.is_zero()) else {
+            return Err(ReconnectError::Unreachable {
+                attempts,
+                elapsed,
+                last_error,
+            });
+        };
+        std::thread::sleep(backoff.delay(attempts - 1).min(remaining));
+    }
+}
+
+/// GET `url` with [`retry`], returning the first successful response
+pub fn get(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    backoff: &Backoff,
+) -> Result<reqwest::blocking::Response, ReconnectError> {
+    retry(backoff, || {
+        let resp = client.get(url).send().map_err(|e| Failure::from_reqwest(&e))?;
+        if resp.status().is_success() {
+            Ok(resp)
+        } else {
+            Err(Failure::from_status(resp.status()))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Nothing listens on the port for `down_for`; after that it answers
+    /// one request with `status_line`
+    fn flaky_server(down_for: Duration, status_line: &'static str) -> (String, std::thread::JoinHandle<()>) {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            std::thread::sleep(down_for);
+            let listener = TcpListener::bind(("127.0.0.1", port)).unwrap();
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let _ = write!(stream, "{}\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}", status_line);
+        });
+        (format!("http://127.0.0.1:{}/api/tags", port), server)
+    }
+
+    #[test]
+    fn test_refused_then_accepted_succeeds_within_deadline() {
+        let (url, server) = flaky_server(Duration::from_millis(300), "HTTP/1.1 200 OK");
+        let backoff = Backoff {
+            initial_delay: Duration::from_millis(20),
+            max_delay: Duration::from_millis(100),
+            deadline: Duration::from_secs(5),
+        };
+
+        let start = Instant::now();
+        let resp = get(&reqwest::blocking::Client::new(), &url, &backoff).unwrap();
+        assert!(resp.status().is_success());
+        assert!(start.elapsed() < backoff.deadline);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_down_past_deadline_is_unreachable_and_not_found_is_misconfigured() {
+        let client = reqwest::blocking::Client::new();
+        let short = Backoff {
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(20),
+            deadline: Duration::from_millis(100),
+        };
+        let closed_port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let err = get(&client, &format!("http://127.0.0.1:{}/", closed_port), &short).unwrap_err();
+        assert!(err.is_unreachable(), "{}", err);
+        if let ReconnectError::Unreachable { attempts, .. } = err {
+            assert!(attempts > 1);
+        }
+
+        let (url, server) = flaky_server(Duration::ZERO, "HTTP/1.1 404 Not Found");
+        let err = get(&client, &url, &Backoff::default()).unwrap_err();
+        assert_eq!(err, ReconnectError::Misconfigured("HTTP 404 Not Found".to_string()));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_delay_grows_with_jitter_up_to_cap() {
+        let backoff = Backoff::default();
+        for retry in 0..10 {
+            let cap = (backoff.initial_delay * 2u32.pow(retry)).min(backoff.max_delay);
+            let delay = backoff.delay(retry);
+            assert!(delay >= cap / 2 && delay <= cap, "retry {}: {:?}", retry, delay);
+        }
+    }
+}
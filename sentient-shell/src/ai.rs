@@ -1,5 +1,9 @@
+use sentient_reconnect::{self as reconnect, Backoff, ReconnectError};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
 use std::time::Duration;
 
 #[derive(Clone)]
@@ -7,12 +11,144 @@ pub struct AiClient {
     ollama_url: String,
     sd_url: String,
     client: reqwest::blocking::Client,
+    config: AiClientConfig,
+}
+
+/// HTTP settings for `AiClient`
+#[derive(Debug, Clone)]
+pub struct AiClientConfig {
+    /// Maximum time to establish a TCP connection
+    pub connect_timeout: Duration,
+    /// Total time allowed for quick calls (model lists, health checks)
+    pub request_timeout: Duration,
+    /// Total time allowed for text and image generation
+    pub generation_timeout: Duration,
+    /// Idle connections kept open per host
+    pub pool_max_idle_per_host: usize,
+    /// How long an idle pooled connection is kept before closing
+    pub pool_idle_timeout: Duration,
+    /// Retrying health checks and model listing while a backend is
+    /// unreachable
+    pub reconnect: Backoff,
+}
+
+impl Default for AiClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(30),
+            generation_timeout: Duration::from_secs(300),
+            pool_max_idle_per_host: 4,
+            pool_idle_timeout: Duration::from_secs(90),
+            reconnect: Backoff::default(),
+        }
+    }
+}
+
+impl AiClientConfig {
+    /// Defaults overridden by `AI_CONNECT_TIMEOUT_MS`, `AI_REQUEST_TIMEOUT_MS`,
+    /// `AI_GENERATION_TIMEOUT_MS` and `AI_RECONNECT_DEADLINE_MS` when set
+    pub fn from_env() -> Self {
+        let ms = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(Duration::from_millis)
+        };
+
+        let defaults = Self::default();
+        Self {
+            connect_timeout: ms("AI_CONNECT_TIMEOUT_MS").unwrap_or(defaults.connect_timeout),
+            request_timeout: ms("AI_REQUEST_TIMEOUT_MS").unwrap_or(defaults.request_timeout),
+            generation_timeout: ms("AI_GENERATION_TIMEOUT_MS").unwrap_or(defaults.generation_timeout),
+            reconnect: Backoff {
+                deadline: ms("AI_RECONNECT_DEADLINE_MS").unwrap_or(defaults.reconnect.deadline),
+                ..defaults.reconnect
+            },
+            ..defaults
+        }
+    }
+}
+
+/// Transport failures surfaced distinctly so callers can react to them
+#[derive(Debug, thiserror::Error)]
+pub enum AiClientError {
+    #[error("Request to {url} timed out after {timeout:?}")]
+    Timeout { url: String, timeout: Duration },
+
+    #[error("Connection to {url} failed: {reason}")]
+    ConnectionFailed { url: String, reason: String },
+
+    #[error("HTTP error from {url}: {source}")]
+    Http {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+}
+
+impl AiClientError {
+    fn from_reqwest(err: reqwest::Error, url: &str, timeout: Duration) -> Self {
+        if err.is_timeout() {
+            AiClientError::Timeout {
+                url: url.to_string(),
+                timeout,
+            }
+        } else if err.is_connect() {
+            AiClientError::ConnectionFailed {
+                url: url.to_string(),
+                reason: err.to_string(),
+            }
+        } else {
+            AiClientError::Http {
+                url: url.to_string(),
+                source: err,
+            }
+        }
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, AiClientError::Timeout { .. })
+    }
 }
 
 #[derive(Debug)]
 pub struct ImageInfo {
     pub hash: String,
     pub size: usize,
+    /// Where the image was written, if it was saved to disk
+    pub path: Option<PathBuf>,
+}
+
+/// Progress snapshot reported by the SD WebUI while generating
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ImageProgress {
+    /// Fraction complete in [0, 1]
+    pub progress: f32,
+    /// Estimated seconds remaining
+    #[serde(default)]
+    pub eta_relative: f32,
+}
+
+/// Options for a progress-reporting, cancellable image generation
+#[derive(Debug, Clone)]
+pub struct ImageGenerationOptions {
+    /// Directory the generated PNG is saved into
+    pub output_dir: PathBuf,
+    /// How often the progress endpoint is polled
+    pub poll_interval: Duration,
+}
+
+impl Default for ImageGenerationOptions {
+    fn default() -> Self {
+        Self {
+            output_dir: dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("sentient-shell")
+                .join("images"),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
 }
 
 // Ollama API structures
@@ -65,8 +201,16 @@ struct SDModel {
 
 impl AiClient {
     pub fn new(ollama_url: String, sd_url: String) -> Self {
+        Self::with_config(ollama_url, sd_url, AiClientConfig::default())
+    }
+
+    /// Build a client whose pooled HTTP connections use the given timeouts
+    pub fn with_config(ollama_url: String, sd_url: String, config: AiClientConfig) -> Self {
         let client = reqwest::blocking::Client::builder()
-            .timeout(Duration::from_secs(30))
+            .connect_timeout(config.connect_timeout)
+            .timeout(config.request_timeout)
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .pool_idle_timeout(config.pool_idle_timeout)
             .build()
             .unwrap_or_else(|_| reqwest::blocking::Client::new());
 
@@ -74,9 +218,14 @@ impl AiClient {
             ollama_url,
             sd_url,
             client,
+            config,
         }
     }
 
+    pub fn config(&self) -> &AiClientConfig {
+        &self.config
+    }
+
     pub fn ollama_url(&self) -> &str {
         &self.ollama_url
     }
@@ -85,22 +234,31 @@ impl AiClient {
         &self.sd_url
     }
 
+    /// `Ok(false)` if Ollama stays unreachable until the reconnect deadline,
+    /// an error if it answers in a way that points at misconfiguration
     pub fn check_ollama_connection(&self) -> Result<bool> {
-        let url = format!("{}/api/tags", self.ollama_url);
-        match self.client.get(&url).send() {
-            Ok(resp) => Ok(resp.status().is_success()),
-            Err(_) => Ok(false),
-        }
+        Self::connectivity(self.get_with_reconnect(&format!("{}/api/tags", self.ollama_url)))
     }
 
+    /// `Ok(false)` if the SD WebUI stays unreachable until the reconnect
+    /// deadline, an error if it answers in a way that points at
+    /// misconfiguration
     pub fn check_sd_connection(&self) -> Result<bool> {
-        let url = format!("{}/sdapi/v1/sd-models", self.sd_url);
-        match self.client.get(&url).send() {
-            Ok(resp) => Ok(resp.status().is_success()),
-            Err(_) => Ok(false),
+        Self::connectivity(self.get_with_reconnect(&format!("{}/sdapi/v1/sd-models", self.sd_url)))
+    }
+
+    fn connectivity(result: Result<reqwest::blocking::Response, ReconnectError>) -> Result<bool> {
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) if e.is_unreachable() => Ok(false),
+            Err(e) => Err(e.into()),
         }
     }
 
+    fn get_with_reconnect(&self, url: &str) -> Result<reqwest::blocking::Response, ReconnectError> {
+        reconnect::get(&self.client, url, &self.config.reconnect)
+    }
+
     pub fn get_preferred_model(&self) -> Result<Option<String>> {
         let models = self.list_ollama_models()?;
 
@@ -130,8 +288,9 @@ impl AiClient {
             .client
             .post(&url)
             .json(request)
-            .timeout(Duration::from_secs(30))
+            .timeout(self.config.generation_timeout)
             .send()
+            .map_err(|e| AiClientError::from_reqwest(e, &url, self.config.generation_timeout))
             .context("Failed to send request to Ollama")?;
 
         if !resp.status().is_success() {
@@ -139,7 +298,10 @@ impl AiClient {
             anyhow::bail!("Ollama API error: {}", error_text);
         }
 
-        let ollama_resp: OllamaResponse = resp.json().context("Failed to parse Ollama response")?;
+        let ollama_resp: OllamaResponse = resp
+            .json()
+            .map_err(|e| AiClientError::from_reqwest(e, &url, self.config.generation_timeout))
+            .context("Failed to parse Ollama response")?;
 
         Ok(ollama_resp.response)
     }
@@ -147,16 +309,13 @@ impl AiClient {
     pub fn list_ollama_models(&self) -> Result<Vec<String>> {
         let url = format!("{}/api/tags", self.ollama_url);
         let resp = self
-            .client
-            .get(&url)
-            .send()
+            .get_with_reconnect(&url)
             .context("Failed to connect to Ollama")?;
 
-        if !resp.status().is_success() {
-            anyhow::bail!("Ollama API returned error: {}", resp.status());
-        }
-
-        let tags: OllamaTagsResponse = resp.json().context("Failed to parse Ollama response")?;
+        let tags: OllamaTagsResponse = resp
+            .json()
+            .map_err(|e| AiClientError::from_reqwest(e, &url, self.config.request_timeout))
+            .context("Failed to parse Ollama response")?;
 
         Ok(tags.models.into_iter().map(|m| m.name).collect())
     }
@@ -164,16 +323,13 @@ impl AiClient {
     pub fn list_sd_models(&self) -> Result<Vec<String>> {
         let url = format!("{}/sdapi/v1/sd-models", self.sd_url);
         let resp = self
-            .client
-            .get(&url)
-            .send()
+            .get_with_reconnect(&url)
             .context("Failed to connect to Stable Diffusion")?;
 
-        if !resp.status().is_success() {
-            anyhow::bail!("SD API returned error: {}", resp.status());
-        }
-
-        let models: Vec<SDModel> = resp.json().context("Failed to parse SD response")?;
+        let models: Vec<SDModel> = resp
+            .json()
+            .map_err(|e| AiClientError::from_reqwest(e, &url, self.config.request_timeout))
+            .context("Failed to parse SD response")?;
 
         Ok(models.into_iter().map(|m| m.title).collect())
     }
@@ -195,20 +351,141 @@ impl AiClient {
             .client
             .post(&url)
             .json(&request)
+            .timeout(self.config.generation_timeout)
             .send()
+            .map_err(|e| AiClientError::from_reqwest(e, &url, self.config.generation_timeout))
             .context("Failed to send request to Ollama")?;
 
         if !resp.status().is_success() {
             anyhow::bail!("Ollama API returned error: {}", resp.status());
         }
 
-        let response: OllamaResponse = resp.json().context("Failed to parse Ollama response")?;
+        let response: OllamaResponse = resp
+            .json()
+            .map_err(|e| AiClientError::from_reqwest(e, &url, self.config.generation_timeout))
+            .context("Failed to parse Ollama response")?;
 
         Ok(response.response)
     }
 
     pub fn generate_image(&mut self, prompt: &str) -> Result<ImageInfo> {
-        let url = format!("{}/sdapi/v1/txt2img", self.sd_url);
+        let image_data = Self::request_txt2img(
+            &self.client,
+            &self.sd_url,
+            prompt,
+            self.config.generation_timeout,
+        )?;
+
+        Ok(ImageInfo {
+            hash: Self::hash_image(&image_data),
+            size: image_data.len(),
+            path: None,
+        })
+    }
+
+    /// Generate an image while polling the SD progress endpoint.
+    ///
+    /// `on_progress` is invoked with each progress snapshot. Setting `cancel`
+    /// interrupts the in-flight generation via the SD interrupt API. On
+    /// success the image is saved as PNG under `options.output_dir`.
+    pub fn generate_image_with_progress<F>(
+        &mut self,
+        prompt: &str,
+        options: &ImageGenerationOptions,
+        cancel: &AtomicBool,
+        mut on_progress: F,
+    ) -> Result<ImageInfo>
+    where
+        F: FnMut(&ImageProgress),
+    {
+        let (tx, rx) = mpsc::channel();
+        let client = self.client.clone();
+        let sd_url = self.sd_url.clone();
+        let prompt_owned = prompt.to_string();
+        let timeout = self.config.generation_timeout;
+        std::thread::spawn(move || {
+            let _ = tx.send(Self::request_txt2img(&client, &sd_url, &prompt_owned, timeout));
+        });
+
+        let image_data = loop {
+            match rx.recv_timeout(options.poll_interval) {
+                Ok(result) => break result?,
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if cancel.load(Ordering::SeqCst) {
+                        self.interrupt_sd()?;
+                        anyhow::bail!("Image generation cancelled");
+                    }
+                    if let Ok(progress) = self.get_sd_progress() {
+                        on_progress(&progress);
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    anyhow::bail!("Image generation worker exited unexpectedly");
+                }
+            }
+        };
+
+        on_progress(&ImageProgress {
+            progress: 1.0,
+            eta_relative: 0.0,
+        });
+
+        let hash = Self::hash_image(&image_data);
+        std::fs::create_dir_all(&options.output_dir).with_context(|| {
+            format!("Failed to create image directory {}", options.output_dir.display())
+        })?;
+        let path = options.output_dir.join(format!("{}.png", &hash[..16]));
+        std::fs::write(&path, &image_data)
+            .with_context(|| format!("Failed to save image to {}", path.display()))?;
+
+        Ok(ImageInfo {
+            hash,
+            size: image_data.len(),
+            path: Some(path),
+        })
+    }
+
+    /// Query the SD WebUI for the progress of the current job
+    pub fn get_sd_progress(&self) -> Result<ImageProgress> {
+        let url = format!("{}/sdapi/v1/progress?skip_current_image=true", self.sd_url);
+        let resp = self
+            .client
+            .get(&url)
+            .send()
+            .map_err(|e| AiClientError::from_reqwest(e, &url, self.config.request_timeout))
+            .context("Failed to query SD progress")?;
+
+        if !resp.status().is_success() {
+            anyhow::bail!("SD API returned error: {}", resp.status());
+        }
+
+        resp.json().context("Failed to parse SD progress")
+    }
+
+    /// Ask the SD WebUI to abort the current job
+    pub fn interrupt_sd(&self) -> Result<()> {
+        let url = format!("{}/sdapi/v1/interrupt", self.sd_url);
+        let resp = self
+            .client
+            .post(&url)
+            .send()
+            .map_err(|e| AiClientError::from_reqwest(e, &url, self.config.request_timeout))
+            .context("Failed to interrupt SD generation")?;
+
+        if !resp.status().is_success() {
+            anyhow::bail!("SD API returned error: {}", resp.status());
+        }
+
+        Ok(())
+    }
+
+    fn request_txt2img(
+        client: &reqwest::blocking::Client,
+        sd_url: &str,
+        prompt: &str,
+        timeout: Duration,
+    ) -> Result<Vec<u8>> {
+        let url = format!("{}/sdapi/v1/txt2img", sd_url);
         let request = SDTxt2ImgRequest {
             prompt: prompt.to_string(),
             negative_prompt: String::new(),
@@ -217,18 +494,22 @@ impl AiClient {
             height: 512,
         };
 
-        let resp = self
-            .client
+        let resp = client
             .post(&url)
             .json(&request)
+            .timeout(timeout)
             .send()
+            .map_err(|e| AiClientError::from_reqwest(e, &url, timeout))
             .context("Failed to send request to Stable Diffusion")?;
 
         if !resp.status().is_success() {
             anyhow::bail!("SD API returned error: {}", resp.status());
         }
 
-        let response: SDTxt2ImgResponse = resp.json().context("Failed to parse SD response")?;
+        let response: SDTxt2ImgResponse = resp
+            .json()
+            .map_err(|e| AiClientError::from_reqwest(e, &url, timeout))
+            .context("Failed to parse SD response")?;
 
         if response.images.is_empty() {
             anyhow::bail!("No images generated");
@@ -237,19 +518,15 @@ impl AiClient {
         // Get the first image
         let image_b64 = &response.images[0];
         use base64::Engine;
-        let image_data = base64::engine::general_purpose::STANDARD
+        base64::engine::general_purpose::STANDARD
             .decode(image_b64)
-            .context("Failed to decode image data")?;
+            .context("Failed to decode image data")
+    }
 
-        // Calculate SHA256 hash
+    fn hash_image(image_data: &[u8]) -> String {
         use sha2::{Digest, Sha256};
         let mut hasher = Sha256::new();
-        hasher.update(&image_data);
-        let hash = format!("{:x}", hasher.finalize());
-
-        Ok(ImageInfo {
-            hash,
-            size: image_data.len(),
-        })
+        hasher.update(image_data);
+        format!("{:x}", hasher.finalize())
     }
 }
@@ -0,0 +1,333 @@
+// Request queue with micro-batching
+// Requests for a provider that supports batch inference are queued, and those
+// arriving within a short window go to the backend as one call
+
+use super::config::BatchingConfig;
+use super::{InferenceRequest, InferenceResponse, ModelProvider, MODEL_OVERRIDE_KEY};
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+type Reply = oneshot::Sender<Result<InferenceResponse>>;
+
+struct Pending {
+    request: InferenceRequest,
+    reply: Reply,
+}
+
+lazy_static::lazy_static! {
+    /// Runtime the shared batchers run on, so requests routed from threads
+    /// without a runtime can still join a batch
+    static ref BATCH_RUNTIME: tokio::runtime::Runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("ai-batcher")
+        .enable_all()
+        .build()
+        .expect("failed to start the request batching runtime");
+}
+
+/// Queues requests for one provider and hands them over in batches.
+///
+/// The queue is bounded, so when the provider falls behind, callers wait in
+/// [`RequestBatcher::submit`] instead of piling more work onto it.
+pub struct RequestBatcher {
+    queue: mpsc::Sender<Pending>,
+}
+
+impl RequestBatcher {
+    /// Start batching requests for `provider`. Must be called inside a
+    /// Tokio runtime.
+    pub fn new(provider: Arc<dyn ModelProvider>, config: BatchingConfig) -> Self {
+        Self::spawn_on(&tokio::runtime::Handle::current(), provider, config)
+    }
+
+    fn spawn_on(
+        runtime: &tokio::runtime::Handle,
+        provider: Arc<dyn ModelProvider>,
+        config: BatchingConfig,
+    ) -> Self {
+        let (queue, requests) = mpsc::channel(config.queue_capacity.max(1));
+        runtime.spawn(run(provider, config, requests));
+        Self { queue }
+    }
+
+    /// Queue `request` and wait for its response
+    pub async fn submit(&self, request: InferenceRequest) -> Result<InferenceResponse> {
+        let (reply, response) = oneshot::channel();
+        self.queue
+            .send(Pending { request, reply })
+            .await
+            .map_err(|_| anyhow!("Request batcher has shut down"))?;
+        response
+            .await
+            .map_err(|_| anyhow!("Request batcher dropped the request"))?
+    }
+
+    /// [`RequestBatcher::submit`] for synchronous callers, blocking the
+    /// thread until the response arrives. Panics if called from async code.
+    pub fn submit_blocking(&self, request: InferenceRequest) -> Result<InferenceResponse> {
+        let (reply, response) = oneshot::channel();
+        self.queue
+            .blocking_send(Pending { request, reply })
+            .map_err(|_| anyhow!("Request batcher has shut down"))?;
+        response
+            .blocking_recv()
+            .map_err(|_| anyhow!("Request batcher dropped the request"))?
+    }
+
+    /// Whether the batcher can still take requests
+    pub fn is_running(&self) -> bool {
+        !self.queue.is_closed()
+    }
+}
+
+/// Collect batches until every sender is gone
+async fn run(
+    provider: Arc<dyn ModelProvider>,
+    config: BatchingConfig,
+    mut requests: mpsc::Receiver<Pending>,
+) {
+    let max_batch = config.max_batch.max(1);
+    let max_delay = Duration::from_millis(config.max_delay_ms);
+
+    while let Some(first) = requests.recv().await {
+        let mut batch = vec![first];
+        let deadline = Instant::now() + max_delay;
+        while batch.len() < max_batch {
+            match tokio::time::timeout_at(deadline, requests.recv()).await {
+                Ok(Some(pending)) => batch.push(pending),
+                Ok(None) | Err(_) => break,
+            }
+        }
+
+        // New requests queue up (and eventually block) while this runs
+        for group in group_compatible(batch) {
+            dispatch(provider.clone(), group).await;
+        }
+    }
+}
+
+/// Split a batch into runs that can share one backend call: same capability
+/// and same requested model
+fn group_compatible(batch: Vec<Pending>) -> Vec<Vec<Pending>> {
+    let mut groups: Vec<(String, Vec<Pending>)> = Vec::new();
+    for pending in batch {
+        let key = format!(
+            "{:?}|{}",
+            pending.request.capability,
+            pending
+                .request
+                .metadata
+                .get(MODEL_OVERRIDE_KEY)
+                .map(|v| v.to_string())
+                .unwrap_or_default()
+        );
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(pending),
+            None => groups.push((key, vec![pending])),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+/// Run one backend call for `group` and fan the results back out
+async fn dispatch(provider: Arc<dyn ModelProvider>, group: Vec<Pending>) {
+    let (requests, replies): (Vec<_>, Vec<_>) =
+        group.into_iter().map(|p| (p.request, p.reply)).unzip();
+    let size = requests.len();
+    debug!(
+        "🤖 [AI-ROUTER] Sending batch of {} to {}",
+        size,
+        provider.name()
+    );
+
+    let result = tokio::task::spawn_blocking(move || provider.infer_batch(&requests))
+        .await
+        .map_err(|e| anyhow!("Batched inference panicked: {}", e))
+        .and_then(|r| r);
+
+    match result {
+        Ok(responses) if responses.len() == size => {
+            for (reply, response) in replies.into_iter().zip(responses) {
+                let _ = reply.send(Ok(response));
+            }
+        }
+        Ok(responses) => {
+            warn!(
+                "⚠️  [AI-ROUTER] Batch of {} returned {} responses",
+                size,
+                responses.len()
+            );
+            for reply in replies {
+                let _ = reply.send(Err(anyhow!(
+                    "Provider returned {} responses for a batch of {}",
+                    responses.len(),
+                    size
+                )));
+            }
+        }
+        Err(e) => {
+            for reply in replies {
+                let _ = reply.send(Err(anyhow!("Batched inference failed: {:#}", e)));
+            }
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref BATCHERS: Mutex<HashMap<String, Arc<RequestBatcher>>> = Mutex::new(HashMap::new());
+}
+
+/// The shared batcher for `provider`, started on first use on a runtime of
+/// its own
+pub fn batcher_for(
+    provider: Arc<dyn ModelProvider>,
+    config: &BatchingConfig,
+) -> Arc<RequestBatcher> {
+    let mut batchers = BATCHERS.lock().unwrap();
+    let name = provider.name().to_string();
+    match batchers.get(&name) {
+        Some(batcher) if batcher.is_running() => batcher.clone(),
+        _ => {
+            let batcher = Arc::new(RequestBatcher::spawn_on(
+                BATCH_RUNTIME.handle(),
+                provider,
+                config.clone(),
+            ));
+            batchers.insert(name, batcher.clone());
+            batcher
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_router::{ModelCapability, ModelEndpoint};
+
+    /// Embeds a prompt as its length and records every backend call
+    #[derive(Default)]
+    struct CountingProvider {
+        calls: Mutex<Vec<usize>>,
+    }
+
+    impl ModelProvider for CountingProvider {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn is_available(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn infer(&self, request: &InferenceRequest) -> Result<InferenceResponse> {
+            Ok(self.infer_batch(std::slice::from_ref(request))?.remove(0))
+        }
+
+        fn list_models(&self) -> Result<Vec<ModelEndpoint>> {
+            Ok(Vec::new())
+        }
+
+        fn supports_batching(&self, capability: &ModelCapability) -> bool {
+            *capability == ModelCapability::Embedding
+        }
+
+        fn infer_batch(&self, requests: &[InferenceRequest]) -> Result<Vec<InferenceResponse>> {
+            self.calls.lock().unwrap().push(requests.len());
+            Ok(requests
+                .iter()
+                .map(|r| InferenceResponse {
+                    text: None,
+                    embedding: Some(vec![r.prompt.len() as f32]),
+                    metadata: HashMap::new(),
+                    model_used: "counting".to_string(),
+                    tokens_used: None,
+                    duration_ms: 0,
+                })
+                .collect())
+        }
+    }
+
+    fn embedding_request(prompt: &str) -> InferenceRequest {
+        InferenceRequest {
+            prompt: prompt.to_string(),
+            capability: ModelCapability::Embedding,
+            max_tokens: None,
+            temperature: None,
+            system_prompt: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    async fn submit_all(batcher: Arc<RequestBatcher>, count: usize) -> Vec<f32> {
+        let handles: Vec<_> = (1..=count)
+            .map(|i| {
+                let batcher = batcher.clone();
+                tokio::spawn(async move { batcher.submit(embedding_request(&"x".repeat(i))).await })
+            })
+            .collect();
+        let mut embeddings = Vec::new();
+        for handle in handles {
+            embeddings.push(handle.await.unwrap().unwrap().embedding.unwrap()[0]);
+        }
+        embeddings
+    }
+
+    #[tokio::test]
+    async fn test_requests_within_window_share_one_provider_call() {
+        let provider = Arc::new(CountingProvider::default());
+        let config = BatchingConfig {
+            max_batch: 16,
+            max_delay_ms: 200,
+            ..Default::default()
+        };
+        let batcher = Arc::new(RequestBatcher::new(provider.clone(), config));
+
+        // Each caller gets the response for its own request
+        let embeddings = submit_all(batcher.clone(), 5).await;
+        assert_eq!(embeddings, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(*provider.calls.lock().unwrap(), vec![5]);
+
+        // A full batch goes out without waiting for the window
+        let provider = Arc::new(CountingProvider::default());
+        let config = BatchingConfig {
+            max_batch: 2,
+            max_delay_ms: 60_000,
+            ..Default::default()
+        };
+        let batcher = Arc::new(RequestBatcher::new(provider.clone(), config));
+        let embeddings = tokio::time::timeout(Duration::from_secs(5), submit_all(batcher, 4))
+            .await
+            .unwrap();
+        assert_eq!(embeddings, vec![1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(*provider.calls.lock().unwrap(), vec![2, 2]);
+    }
+
+    #[test]
+    fn test_blocking_callers_share_one_provider_call() {
+        // No runtime here: the shared batcher brings its own
+        let provider = Arc::new(CountingProvider::default());
+        let config = BatchingConfig {
+            max_batch: 16,
+            max_delay_ms: 200,
+            ..Default::default()
+        };
+        let batcher = batcher_for(provider.clone(), &config);
+
+        let handles: Vec<_> = (1..=5)
+            .map(|i| {
+                let batcher = batcher.clone();
+                std::thread::spawn(move || {
+                    batcher.submit_blocking(embedding_request(&"x".repeat(i))).unwrap().embedding.unwrap()[0]
+                })
+            })
+            .collect();
+        let embeddings: Vec<f32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(embeddings, vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(*provider.calls.lock().unwrap(), vec![5]);
+    }
+}
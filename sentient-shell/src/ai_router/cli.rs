@@ -3,7 +3,7 @@ use super::*;
 use super::registry::get_model_registry;
 use super::router::AIRouter;
 use super::providers::ollama::OllamaProvider;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::sync::Arc;
 
 pub fn handle_command(args: &[&str]) -> Result<String> {
@@ -16,13 +16,13 @@ pub fn handle_command(args: &[&str]) -> Result<String> {
         "status" => show_status(),
         "query" => {
             if args.len() < 2 {
-                return Ok("Usage: ai query <prompt>".to_string());
+                anyhow::bail!("Usage: ai query <prompt>");
             }
             query_model(&args[1..].join(" "))
         }
         "init" => init_providers(),
         "help" => Ok(help()),
-        _ => Ok(format!("Unknown command: {}. Try 'ai help'", args[0])),
+        _ => anyhow::bail!("Unknown command: {}. Try 'ai help'", args[0]),
     }
 }
 
@@ -113,22 +113,18 @@ fn query_model(prompt: &str) -> Result<String> {
         metadata: HashMap::new(),
     };
     
-    match AIRouter::route_request(&request) {
-        Ok(response) => {
-            let mut output = String::new();
-            if let Some(text) = response.text {
-                output.push_str(&text);
-                output.push_str("\n\n");
-            }
-            output.push_str(&format!("Model: {}\n", response.model_used));
-            output.push_str(&format!("Time: {}ms\n", response.duration_ms));
-            if let Some(tokens) = response.tokens_used {
-                output.push_str(&format!("Tokens: {}\n", tokens));
-            }
-            Ok(output)
-        }
-        Err(e) => Ok(format!("Error: {}", e)),
+    let response = AIRouter::route_request(&request)?;
+    let mut output = String::new();
+    if let Some(text) = response.text {
+        output.push_str(&text);
+        output.push_str("\n\n");
     }
+    output.push_str(&format!("Model: {}\n", response.model_used));
+    output.push_str(&format!("Time: {}ms\n", response.duration_ms));
+    if let Some(tokens) = response.tokens_used {
+        output.push_str(&format!("Tokens: {}\n", tokens));
+    }
+    Ok(output)
 }
 
 fn init_providers() -> Result<String> {
@@ -140,8 +136,7 @@ fn init_providers() -> Result<String> {
     
     let ollama = Arc::new(OllamaProvider::new(ollama_url.clone()));
     
-    match registry.register_provider(ollama) {
-        Ok(_) => Ok(format!("Initialized Ollama provider at {}", ollama_url)),
-        Err(e) => Ok(format!("Failed to initialize Ollama: {}", e)),
-    }
+    registry.register_provider(ollama)
+        .context("Failed to initialize Ollama")?;
+    Ok(format!("Initialized Ollama provider at {}", ollama_url))
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 //! Configuration loader for model routing
 
+use super::{InferenceRequest, ModelCapability, TRACE_ID_KEY};
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -56,6 +57,40 @@ pub struct LoadBalancingConfig {
     pub max_concurrent_requests: usize,
     pub timeout_ms: u64,
     pub retry_attempts: u32,
+    #[serde(default)]
+    pub batching: BatchingConfig,
+}
+
+/// Micro-batching for providers that support batch inference
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BatchingConfig {
+    pub enabled: bool,
+    /// Most requests sent to a provider in one call
+    pub max_batch: usize,
+    /// How long the first request in a batch waits for others to join
+    pub max_delay_ms: u64,
+    /// Requests queued per provider before callers have to wait
+    pub queue_capacity: usize,
+}
+
+impl Default for BatchingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_batch: 16,
+            max_delay_ms: 10,
+            queue_capacity: 256,
+        }
+    }
+}
+
+/// Sampling defaults for requests of one capability
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestDefaults {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<usize>,
 }
 
 /// Full models configuration
@@ -64,8 +99,49 @@ pub struct ModelsConfig {
     pub models: HashMap<String, ModelConfig>,
     pub routing: RoutingConfig,
     pub load_balancing: LoadBalancingConfig,
+    /// Per-capability request defaults, keyed by capability name
+    /// (`code_generation`, `text`, ...)
+    #[serde(default)]
+    pub defaults: HashMap<String, RequestDefaults>,
+}
+
+impl ModelsConfig {
+    /// Fill in whatever `temperature` and `max_tokens` the request left
+    /// unset from the defaults for its capability
+    pub fn apply_request_defaults(&self, request: &mut InferenceRequest) {
+        apply_request_defaults(&self.defaults, request);
+    }
+}
+
+/// The entry of a `[defaults]` table that applies to `capability`. An
+/// entry under the capability's full name (`code_generation`) takes
+/// precedence over one under its alias (`code`); between aliases the
+/// first by name wins, so the table's order never matters.
+pub fn request_defaults_for<'a>(
+    defaults: &'a HashMap<String, RequestDefaults>,
+    capability: &ModelCapability,
+) -> Option<&'a RequestDefaults> {
+    defaults.iter()
+        .filter(|(name, _)| name.parse::<ModelCapability>().ok().as_ref() == Some(capability))
+        .min_by_key(|(name, _)| {
+            let full_name = capability.config_name().is_some_and(|full| name.eq_ignore_ascii_case(full));
+            (!full_name, name.as_str())
+        })
+        .map(|(_, defaults)| defaults)
 }
 
+/// Fill in whatever `temperature` and `max_tokens` `request` left unset
+/// from the `defaults` table entry for its capability
+pub fn apply_request_defaults(defaults: &HashMap<String, RequestDefaults>, request: &mut InferenceRequest) {
+    if let Some(defaults) = request_defaults_for(defaults, &request.capability) {
+        request.temperature = request.temperature.or(defaults.temperature);
+        request.max_tokens = request.max_tokens.or(defaults.max_tokens);
+    }
+}
+
+/// Where the shell loads the models configuration from
+pub const MODELS_CONFIG_PATH: &str = "config/models.toml";
+
 lazy_static! {
     static ref MODELS_CONFIG: Arc<RwLock<Option<ModelsConfig>>> = Arc::new(RwLock::new(None));
 }
@@ -93,6 +169,21 @@ pub fn get_models_config() -> Option<ModelsConfig> {
     MODELS_CONFIG.read().unwrap().clone()
 }
 
+/// `request` with the loaded configuration's capability defaults applied,
+/// tagged with the current trace id unless it already carries one
+pub fn with_request_defaults(request: &InferenceRequest) -> InferenceRequest {
+    let mut request = request.clone();
+    if let Some(config) = MODELS_CONFIG.read().unwrap().as_ref() {
+        config.apply_request_defaults(&mut request);
+    }
+    if let Some(trace_id) = crate::trace_id::current() {
+        request.metadata
+            .entry(TRACE_ID_KEY.to_string())
+            .or_insert(serde_json::Value::String(trace_id));
+    }
+    request
+}
+
 /// Validate configuration
 fn validate_config(config: &ModelsConfig) -> Result<()> {
     // Check default model exists
@@ -116,6 +207,13 @@ fn validate_config(config: &ModelsConfig) -> Result<()> {
         }
     }
     
+    // Check defaults name known capabilities
+    for capability in config.defaults.keys() {
+        if let Err(e) = capability.parse::<ModelCapability>() {
+            anyhow::bail!("Invalid [defaults.{}]: {}", capability, e);
+        }
+    }
+    
     Ok(())
 }
 
@@ -226,4 +324,95 @@ retry_attempts = 2
         assert!(load_models_config(temp_file.path()).is_ok());
         assert!(get_models_config().is_some());
     }
+    
+    #[test]
+    fn test_capability_defaults_fill_unset_parameters() {
+        let config: ModelsConfig = toml::from_str(r#"
+[models.coder]
+name = "Coder"
+provider = "ollama"
+trusted = true
+allow_tool_calls = false
+capabilities = ["code_generation"]
+performance_tier = "balanced"
+context_length = 4096
+priority = 90
+use_cases = ["coding"]
+
+[routing]
+default_model = "coder"
+offline_chain = []
+
+[routing.intents]
+
+[routing.performance]
+
+[routing.context]
+
+[load_balancing]
+strategy = "capability_first"
+max_concurrent_requests = 3
+timeout_ms = 30000
+retry_attempts = 2
+
+[defaults.code_generation]
+temperature = 0.2
+max_tokens = 2048
+
+[defaults.text]
+temperature = 0.8
+"#).unwrap();
+        validate_config(&config).unwrap();
+        
+        let request = |capability, temperature| InferenceRequest {
+            prompt: "write a function that reverses a string".to_string(),
+            capability,
+            max_tokens: None,
+            temperature,
+            system_prompt: None,
+            metadata: HashMap::new(),
+        };
+        
+        let mut code = request(ModelCapability::CodeGeneration, None);
+        config.apply_request_defaults(&mut code);
+        assert_eq!(code.temperature, Some(0.2));
+        assert_eq!(code.max_tokens, Some(2048));
+        
+        // Explicit values win
+        let mut explicit = request(ModelCapability::CodeGeneration, Some(0.9));
+        config.apply_request_defaults(&mut explicit);
+        assert_eq!(explicit.temperature, Some(0.9));
+        assert_eq!(explicit.max_tokens, Some(2048));
+        
+        // Each capability gets its own defaults, or none
+        let mut text = request(ModelCapability::TextGeneration, None);
+        config.apply_request_defaults(&mut text);
+        assert_eq!((text.temperature, text.max_tokens), (Some(0.8), None));
+        let mut embed = request(ModelCapability::Embedding, None);
+        config.apply_request_defaults(&mut embed);
+        assert_eq!((embed.temperature, embed.max_tokens), (None, None));
+    }
+    
+    #[test]
+    fn test_full_capability_name_wins_over_alias() {
+        let defaults = |temperature| RequestDefaults { temperature: Some(temperature), max_tokens: None };
+        let temperature = |table: &HashMap<String, RequestDefaults>, capability| {
+            request_defaults_for(table, &capability).and_then(|defaults| defaults.temperature)
+        };
+        
+        // Every table gets its own hasher, so this sees several orders
+        for _ in 0..32 {
+            let table = HashMap::from([
+                ("code".to_string(), defaults(0.9)),
+                ("Code_Generation".to_string(), defaults(0.2)),
+                ("summary".to_string(), defaults(0.5)),
+                ("qa".to_string(), defaults(0.4)),
+                ("QA".to_string(), defaults(0.6)),
+            ]);
+            assert_eq!(temperature(&table, ModelCapability::CodeGeneration), Some(0.2));
+            assert_eq!(temperature(&table, ModelCapability::Summarization), Some(0.5));
+            assert_eq!(temperature(&table, ModelCapability::QuestionAnswering), Some(0.6));
+            assert_eq!(temperature(&table, ModelCapability::Embedding), None);
+        }
+    }
 }
\ No newline at end of file
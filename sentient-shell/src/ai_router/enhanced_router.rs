@@ -24,7 +24,7 @@ impl EnhancedAIRouter {
     pub fn new() -> Self {
         // Load configuration if not already loaded
         if get_models_config().is_none() {
-            if let Err(e) = super::config::load_models_config("config/models.toml") {
+            if let Err(e) = super::config::load_models_config(super::config::MODELS_CONFIG_PATH) {
                 warn!("Failed to load models config: {}. Using defaults.", e);
             }
         }
@@ -1,9 +1,11 @@
 //! Intelligent LLM routing with intent detection and capability matching
 
-use super::{ModelEndpoint, InferenceRequest, InferenceResponse};
+use super::{ModelEndpoint, InferenceRequest, InferenceResponse, ModelCapability};
+use super::config::{apply_request_defaults, request_defaults_for, RequestDefaults};
+use super::tokens::{default_estimator, TokenEstimator};
 use anyhow::{Result, Context, bail};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use log::{info, debug, warn};
@@ -71,7 +73,7 @@ pub struct ModelConfig {
 }
 
 /// Routing configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoutingConfig {
     pub default_model: String,
     pub offline_chain: Vec<String>,
@@ -81,7 +83,7 @@ pub struct RoutingConfig {
 }
 
 /// Load balancing configuration
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoadBalancingConfig {
     pub strategy: String,
     pub max_concurrent_requests: usize,
@@ -95,6 +97,54 @@ pub struct ModelsConfig {
     pub models: HashMap<String, ModelConfig>,
     pub routing: RoutingConfig,
     pub load_balancing: LoadBalancingConfig,
+    /// Per-capability request defaults, keyed by capability name
+    #[serde(default)]
+    pub defaults: HashMap<String, RequestDefaults>,
+}
+
+impl From<&super::config::ModelsConfig> for ModelsConfig {
+    /// The routing view of the configuration the shell loaded at startup
+    fn from(config: &super::config::ModelsConfig) -> Self {
+        use super::config::PerformanceTier as Tier;
+        
+        let models = config.models.iter()
+            .map(|(id, model)| (id.clone(), ModelConfig {
+                name: model.name.clone(),
+                provider: model.provider.clone(),
+                endpoint: model.endpoint.clone(),
+                location: model.location.clone(),
+                model_id: model.model_id.clone(),
+                capabilities: model.capabilities.clone(),
+                performance_tier: match model.performance_tier {
+                    Tier::Fast => PerformanceTier::Fast,
+                    Tier::Balanced => PerformanceTier::Balanced,
+                    Tier::Powerful => PerformanceTier::Powerful,
+                    Tier::Specialized => PerformanceTier::Specialized,
+                },
+                context_length: model.context_length,
+                priority: model.priority,
+                use_cases: model.use_cases.clone(),
+            }))
+            .collect();
+        
+        Self {
+            models,
+            routing: RoutingConfig {
+                default_model: config.routing.default_model.clone(),
+                offline_chain: config.routing.offline_chain.clone(),
+                intents: config.routing.intents.clone(),
+                performance: config.routing.performance.clone(),
+                context: config.routing.context.clone(),
+            },
+            load_balancing: LoadBalancingConfig {
+                strategy: config.load_balancing.strategy.clone(),
+                max_concurrent_requests: config.load_balancing.max_concurrent_requests,
+                timeout_ms: config.load_balancing.timeout_ms,
+                retry_attempts: config.load_balancing.retry_attempts,
+            },
+            defaults: config.defaults.clone(),
+        }
+    }
 }
 
 /// Model health status
@@ -111,6 +161,7 @@ pub struct IntelligentRouter {
     config: ModelsConfig,
     health: Arc<RwLock<HashMap<String, ModelHealth>>>,
     providers: Arc<RwLock<HashMap<String, Box<dyn super::ModelProvider>>>>,
+    token_estimator: Arc<dyn TokenEstimator>,
 }
 
 impl IntelligentRouter {
@@ -122,16 +173,36 @@ impl IntelligentRouter {
         let config: ModelsConfig = toml::from_str(&config_str)
             .context("Failed to parse models config")?;
         
+        Ok(Self::with_config(config))
+    }
+    
+    /// Create a router from an already loaded configuration
+    pub fn with_config(config: ModelsConfig) -> Self {
         let router = Self {
             config,
             health: Arc::new(RwLock::new(HashMap::new())),
             providers: Arc::new(RwLock::new(HashMap::new())),
+            token_estimator: default_estimator(),
         };
         
         // Initialize health tracking
         router.init_health_tracking();
         
-        Ok(router)
+        router
+    }
+    
+    /// Create a router from the models configuration the shell loaded
+    /// (see [`super::config::load_models_config`])
+    pub fn from_loaded_config() -> Result<Self> {
+        let config = super::config::get_models_config()
+            .ok_or_else(|| anyhow::anyhow!("Models configuration not loaded"))?;
+        Ok(Self::with_config(ModelsConfig::from(&config)))
+    }
+    
+    /// Use `estimator` for context-length checks
+    pub fn with_token_estimator(mut self, estimator: Arc<dyn TokenEstimator>) -> Self {
+        self.token_estimator = estimator;
+        self
     }
     
     /// Initialize health tracking for all models
@@ -148,7 +219,10 @@ impl IntelligentRouter {
     }
     
     /// Route request to best available model
-    pub async fn route(&self, request: InferenceRequest) -> Result<InferenceResponse> {
+    pub async fn route(&self, mut request: InferenceRequest) -> Result<InferenceResponse> {
+        // Before the context check, which counts the max_tokens reservation
+        apply_request_defaults(&self.config.defaults, &mut request);
+        
         // Detect intent from prompt
         let intent = self.detect_intent(&request.prompt);
         debug!("Detected intent: {:?}", intent);
@@ -238,47 +312,51 @@ impl IntelligentRouter {
         Intent::GeneralQuery
     }
     
-    /// Get candidate models based on intent and request
-    fn get_candidate_models(&self, intent: &Intent, request: &InferenceRequest) -> Result<Vec<String>> {
-        let mut candidates = Vec::new();
-        
+    /// Get candidate models based on intent and request.
+    ///
+    /// Healthy intent models come first, by priority, then the default model
+    /// if it is healthy, then the offline chain as a last resort whatever the
+    /// health tracking says. Each model appears once, at its first position.
+    fn get_candidate_models(&self, intent: &Intent, _request: &InferenceRequest) -> Result<Vec<String>> {
         // Get intent-based models
         let intent_key = match intent {
-            Intent::ToolCall => "tool_call",
-            Intent::CodeGeneration => "code_generation",
-            Intent::SystemAnalysis => "system_analysis",
-            Intent::QuickResponse => "quick_response",
-            Intent::VisualAnalysis => "visual_analysis",
-            Intent::ComplexReasoning => "complex_reasoning",
-            Intent::GeneralQuery => {
-                // Use default model for general queries
-                candidates.push(self.config.routing.default_model.clone());
-                return Ok(candidates);
-            }
+            Intent::ToolCall => Some("tool_call"),
+            Intent::CodeGeneration => Some("code_generation"),
+            Intent::SystemAnalysis => Some("system_analysis"),
+            Intent::QuickResponse => Some("quick_response"),
+            Intent::VisualAnalysis => Some("visual_analysis"),
+            Intent::ComplexReasoning => Some("complex_reasoning"),
+            // Use default model for general queries
+            Intent::GeneralQuery => None,
         };
         
-        if let Some(models) = self.config.routing.intents.get(intent_key) {
-            candidates.extend(models.clone());
-        }
+        let mut preferred = intent_key
+            .and_then(|key| self.config.routing.intents.get(key))
+            .cloned()
+            .unwrap_or_default();
+        
+        // Sort by priority
+        preferred.sort_by_key(|model_id| {
+            self.config.models.get(model_id)
+                .map(|m| std::cmp::Reverse(m.priority))
+                .unwrap_or(std::cmp::Reverse(0))
+        });
+        preferred.push(self.config.routing.default_model.clone());
         
         // Filter by availability
         let health = self.health.read().unwrap();
-        candidates.retain(|model_id| {
+        preferred.retain(|model_id| {
             health.get(model_id)
                 .map(|h| h.available && h.error_count < 3)
                 .unwrap_or(false)
         });
         
-        // Sort by priority
-        candidates.sort_by_key(|model_id| {
-            self.config.models.get(model_id)
-                .map(|m| std::cmp::Reverse(m.priority))
-                .unwrap_or(std::cmp::Reverse(0))
-        });
-        
-        // Add offline fallback if no candidates
-        if candidates.is_empty() && self.is_offline() {
-            candidates.extend(self.config.routing.offline_chain.clone());
+        // Offline chain last, in its configured order
+        let mut candidates: Vec<String> = Vec::new();
+        for model_id in preferred.into_iter().chain(self.config.routing.offline_chain.iter().cloned()) {
+            if !candidates.contains(&model_id) {
+                candidates.push(model_id);
+            }
         }
         
         Ok(candidates)
@@ -289,16 +367,11 @@ impl IntelligentRouter {
         let model_config = self.config.models.get(model_id)
             .ok_or_else(|| anyhow::anyhow!("Model {} not found", model_id))?;
         
+        self.check_context_length(model_id, model_config, request)?;
+        
         // Get or create provider
         let provider = self.get_provider(model_id, model_config)?;
         
-        // Check context length
-        let estimated_tokens = request.prompt.len() / 4; // Rough estimate
-        if estimated_tokens > model_config.context_length {
-            bail!("Prompt too long for model {} (estimated {} tokens, max {})", 
-                  model_id, estimated_tokens, model_config.context_length);
-        }
-        
         // Create endpoint
         let endpoint = ModelEndpoint {
             id: model_id.to_string(),
@@ -323,6 +396,20 @@ impl IntelligentRouter {
         Ok(response)
     }
     
+    /// Fail if the prompt plus the output it reserves (`max_tokens`) would
+    /// not fit the model's context window
+    fn check_context_length(&self, model_id: &str, config: &ModelConfig, request: &InferenceRequest) -> Result<()> {
+        let prompt_tokens = self.token_estimator.estimate(&request.prompt)
+            + request.system_prompt.as_deref().map_or(0, |s| self.token_estimator.estimate(s));
+        let reserved = request.max_tokens.unwrap_or(0);
+        
+        if prompt_tokens + reserved > config.context_length {
+            bail!("Prompt too long for model {} (estimated {} tokens + {} reserved for output, max {})",
+                  model_id, prompt_tokens, reserved, config.context_length);
+        }
+        Ok(())
+    }
+    
     /// Get or create provider for model
     fn get_provider(&self, model_id: &str, config: &ModelConfig) -> Result<Box<dyn super::ModelProvider>> {
         // This is a simplified version - in practice, you'd create appropriate providers
@@ -380,6 +467,36 @@ impl IntelligentRouter {
         }
     }
     
+    /// Ask each model's provider whether it is reachable and record the
+    /// answer, replacing the optimistic health a new router starts with.
+    /// Blocks for as long as the slowest provider takes to answer.
+    pub fn probe_health(&self) {
+        self.probe_health_with(|model_id, config| {
+            self.get_provider(model_id, config)?.is_available()
+        });
+    }
+    
+    fn probe_health_with(&self, probe: impl Fn(&str, &ModelConfig) -> Result<bool>) {
+        for (model_id, config) in &self.config.models {
+            let available = match probe(model_id, config) {
+                Ok(available) => available,
+                Err(e) => {
+                    debug!("Health probe for {} failed: {}", model_id, e);
+                    false
+                }
+            };
+            
+            let mut health = self.health.write().unwrap();
+            if let Some(status) = health.get_mut(model_id) {
+                status.available = available;
+                status.last_check = Instant::now();
+                if available {
+                    status.error_count = 0;
+                }
+            }
+        }
+    }
+    
     /// Update model latency
     fn update_latency(&self, model_id: &str, latency_ms: u64) {
         let mut health = self.health.write().unwrap();
@@ -415,6 +532,66 @@ impl IntelligentRouter {
         info
     }
     
+    /// The configuration routing runs with, for debugging: every model with
+    /// the request defaults its capabilities resolve to and its current
+    /// health, then the routing rules, per-capability defaults and load
+    /// balancing settings.
+    ///
+    /// Health is this router's own view: a freshly created router reports
+    /// every model available until requests to it start failing, so call
+    /// [`probe_health`](Self::probe_health) first when exporting from one.
+    pub fn export_effective_config(&self) -> serde_json::Value {
+        let offline_mode = self.is_offline();
+        let health = self.health.read().unwrap();
+        
+        let models: BTreeMap<&String, serde_json::Value> = self.config.models.iter()
+            .map(|(id, config)| {
+                let status = health.get(id);
+                (id, serde_json::json!({
+                    "name": config.name,
+                    "provider": config.provider,
+                    "endpoint": config.endpoint,
+                    "location": config.location,
+                    "model_id": config.model_id,
+                    "capabilities": config.capabilities,
+                    "performance_tier": config.performance_tier,
+                    "context_length": config.context_length,
+                    "priority": config.priority,
+                    "use_cases": config.use_cases,
+                    "defaults": self.resolved_defaults(config),
+                    "health": {
+                        "available": status.map(|s| s.available).unwrap_or(false),
+                        // Same test `get_candidate_models` applies
+                        "routable": status.map(|s| s.available && s.error_count < 3).unwrap_or(false),
+                        "error_count": status.map(|s| s.error_count).unwrap_or(0),
+                        "latency_ms": status.and_then(|s| s.latency_ms),
+                        "checked_secs_ago": status.map(|s| s.last_check.elapsed().as_secs()),
+                    },
+                }))
+            })
+            .collect();
+        
+        serde_json::json!({
+            "models": models,
+            "routing": self.config.routing,
+            "defaults": self.config.defaults.iter().collect::<BTreeMap<_, _>>(),
+            "load_balancing": self.config.load_balancing,
+            "offline_mode": offline_mode,
+        })
+    }
+    
+    /// Request defaults for each capability `config` declares that has
+    /// any, keyed by the capability as the model entry spells it
+    fn resolved_defaults(&self, config: &ModelConfig) -> BTreeMap<String, RequestDefaults> {
+        config.capabilities.iter()
+            .filter_map(|name| {
+                let capability = name.parse::<ModelCapability>().ok()?;
+                request_defaults_for(&self.config.defaults, &capability)
+                    .map(|defaults| (name.clone(), defaults.clone()))
+            })
+            .collect()
+    }
+    
     /// Test routing decision for a prompt
     pub fn test_routing(&self, prompt: &str) -> Result<HashMap<String, serde_json::Value>> {
         let intent = self.detect_intent(prompt);
@@ -433,4 +610,202 @@ impl IntelligentRouter {
             ("selected".to_string(), serde_json::json!(candidates.first())),
         ]))
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_router::ModelCapability;
+
+    fn model(provider: &str, priority: u32) -> ModelConfig {
+        ModelConfig {
+            name: format!("{} model", provider),
+            provider: provider.to_string(),
+            endpoint: Some("http://localhost:11434".to_string()),
+            location: None,
+            model_id: None,
+            capabilities: vec!["general_reasoning".to_string()],
+            performance_tier: PerformanceTier::Balanced,
+            context_length: 4096,
+            priority,
+            use_cases: Vec::new(),
+        }
+    }
+
+    fn router() -> IntelligentRouter {
+        let models = HashMap::from([
+            ("phi2_local".to_string(), model("local", 100)),
+            ("llama3_local".to_string(), model("ollama", 80)),
+            ("mistral_instruct".to_string(), model("ollama", 90)),
+            ("deepseek_v2".to_string(), model("ollama", 95)),
+        ]);
+        IntelligentRouter::with_config(ModelsConfig {
+            models,
+            routing: RoutingConfig {
+                default_model: "mistral_instruct".to_string(),
+                offline_chain: vec!["phi2_local".to_string(), "llama3_local".to_string()],
+                intents: HashMap::from([(
+                    "code_generation".to_string(),
+                    vec!["llama3_local".to_string(), "deepseek_v2".to_string()],
+                )]),
+                performance: HashMap::new(),
+                context: HashMap::new(),
+            },
+            load_balancing: LoadBalancingConfig {
+                strategy: "capability_first".to_string(),
+                max_concurrent_requests: 3,
+                timeout_ms: 30000,
+                retry_attempts: 2,
+            },
+            defaults: HashMap::new(),
+        })
+    }
+
+    fn mark_down(router: &IntelligentRouter, model_id: &str) {
+        for _ in 0..3 {
+            router.update_health(model_id, false, Some("connection refused".to_string()));
+        }
+    }
+
+    fn candidates(router: &IntelligentRouter, intent: Intent) -> Vec<String> {
+        let request = InferenceRequest {
+            prompt: "write a function".to_string(),
+            capability: ModelCapability::TextGeneration,
+            max_tokens: None,
+            temperature: None,
+            system_prompt: None,
+            metadata: HashMap::new(),
+        };
+        router.get_candidate_models(&intent, &request).unwrap()
+    }
+
+    #[test]
+    fn test_all_healthy_prefers_intent_models_then_falls_back() {
+        let router = router();
+        assert_eq!(
+            candidates(&router, Intent::CodeGeneration),
+            vec!["deepseek_v2", "llama3_local", "mistral_instruct", "phi2_local"]
+        );
+        assert_eq!(
+            candidates(&router, Intent::GeneralQuery),
+            vec!["mistral_instruct", "phi2_local", "llama3_local"]
+        );
+        assert!(!router.is_offline());
+    }
+
+    #[test]
+    fn test_some_remote_down_still_reaches_offline_chain() {
+        // Intent models down, default up: not offline, yet nothing the
+        // intent asked for is usable
+        let router = router();
+        mark_down(&router, "deepseek_v2");
+        mark_down(&router, "llama3_local");
+        assert!(!router.is_offline());
+        assert_eq!(
+            candidates(&router, Intent::CodeGeneration),
+            vec!["mistral_instruct", "phi2_local", "llama3_local"]
+        );
+
+        mark_down(&router, "mistral_instruct");
+        assert_eq!(
+            candidates(&router, Intent::GeneralQuery),
+            vec!["phi2_local", "llama3_local"]
+        );
+    }
+
+    #[test]
+    fn test_context_check_counts_tokens_and_output_reservation() {
+        let router = router();
+        let config = model("ollama", 90);
+        let request = |prompt: String, max_tokens: Option<usize>| InferenceRequest {
+            prompt,
+            capability: ModelCapability::TextGeneration,
+            max_tokens,
+            temperature: None,
+            system_prompt: None,
+            metadata: HashMap::new(),
+        };
+
+        // ~4500 tokens of Chinese passes a 4096 window by bytes / 4
+        let chinese = "今天天气很好".repeat(600);
+        assert!(chinese.len() / 4 < config.context_length);
+        assert!(router.check_context_length("m", &config, &request(chinese, None)).is_err());
+
+        // Short English fits until the output reservation no longer does
+        let prompt = "Summarize the last hour of system logs.".to_string();
+        assert!(router.check_context_length("m", &config, &request(prompt.clone(), Some(4000))).is_ok());
+        let err = router
+            .check_context_length("m", &config, &request(prompt, Some(4096)))
+            .unwrap_err();
+        assert!(err.to_string().contains("4096 reserved for output"), "{}", err);
+    }
+
+    #[test]
+    fn test_export_lists_every_model_with_defaults_and_health() {
+        let mut router = router();
+        router.config.models.get_mut("deepseek_v2").unwrap()
+            .capabilities.push("code_generation".to_string());
+        router.config.defaults = HashMap::from([
+            ("code".to_string(), RequestDefaults { temperature: Some(0.9), max_tokens: None }),
+            ("code_generation".to_string(), RequestDefaults { temperature: Some(0.25), max_tokens: Some(2048) }),
+            ("summarization".to_string(), RequestDefaults { temperature: Some(0.5), max_tokens: None }),
+        ]);
+        mark_down(&router, "llama3_local");
+        
+        let export = router.export_effective_config();
+        let models = export["models"].as_object().unwrap();
+        assert_eq!(models.len(), router.config.models.len());
+        for (id, config) in &router.config.models {
+            let model = &models[id];
+            assert_eq!(model["provider"], config.provider);
+            assert_eq!(model["context_length"], config.context_length);
+            assert_eq!(model["health"]["available"], id != "llama3_local", "{}", id);
+            assert_eq!(model["health"]["routable"], id != "llama3_local", "{}", id);
+        }
+        assert_eq!(models["llama3_local"]["health"]["error_count"], 3);
+        
+        // Defaults resolve through the capability names, the full name winning
+        // over an alias; models without a matching capability get none
+        let deepseek = &models["deepseek_v2"]["defaults"];
+        assert_eq!(deepseek.as_object().unwrap().len(), 1);
+        assert_eq!(deepseek["code_generation"]["temperature"].as_f64().unwrap() as f32, 0.25);
+        assert_eq!(deepseek["code_generation"]["max_tokens"], 2048);
+        assert!(models["phi2_local"]["defaults"].as_object().unwrap().is_empty());
+        
+        assert_eq!(export["routing"]["default_model"], "mistral_instruct");
+        assert_eq!(export["defaults"].as_object().unwrap().len(), 3);
+        assert_eq!(export["load_balancing"]["strategy"], "capability_first");
+        assert_eq!(export["offline_mode"], false);
+    }
+
+    #[test]
+    fn test_probe_replaces_the_assumed_health() {
+        let router = router();
+        mark_down(&router, "deepseek_v2");
+        router.probe_health_with(|model_id, _| match model_id {
+            "llama3_local" => Ok(false),
+            "mistral_instruct" => anyhow::bail!("connection refused"),
+            _ => Ok(true),
+        });
+        
+        let export = router.export_effective_config();
+        let health = |id: &str| export["models"][id]["health"].clone();
+        assert_eq!(health("llama3_local")["available"], false);
+        assert_eq!(health("mistral_instruct")["available"], false);
+        assert_eq!(health("phi2_local")["routable"], true);
+        // A reachable model is routable again whatever failed before
+        assert_eq!(health("deepseek_v2")["routable"], true);
+        assert_eq!(health("deepseek_v2")["error_count"], 0);
+    }
+
+    #[test]
+    fn test_all_remote_down_uses_offline_chain() {
+        let router = router();
+        for model_id in ["llama3_local", "mistral_instruct", "deepseek_v2"] {
+            mark_down(&router, model_id);
+        }
+        assert!(router.is_offline());
+        for intent in [Intent::CodeGeneration, Intent::GeneralQuery, Intent::ToolCall] {
+            assert_eq!(candidates(&router, intent), vec!["phi2_local", "llama3_local"]);
+        }
+    }
+}
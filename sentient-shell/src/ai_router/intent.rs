@@ -1,6 +1,7 @@
 //! Intent detection for intelligent routing
 
 use super::ModelCapability;
+use super::tokens::{HeuristicEstimator, TokenEstimator};
 use serde::{Deserialize, Serialize};
 use log::debug;
 
@@ -206,7 +207,7 @@ impl IntentDetector {
     
     /// Estimate required context length
     pub fn estimate_context_requirement(prompt: &str, intent: &Intent) -> usize {
-        let base_tokens = prompt.len() / 4; // Rough token estimate
+        let base_tokens = HeuristicEstimator.estimate(prompt);
         
         match intent {
             Intent::ToolCall | Intent::CommandExecution | Intent::QuickResponse => base_tokens + 500,
@@ -2,6 +2,7 @@
 
 use super::enhanced_router::EnhancedAIRouter;
 use super::config::{get_models_config, get_model_config};
+use super::intelligent_router::IntelligentRouter;
 use super::intent::{IntentDetector, Intent};
 use anyhow::{Result, bail};
 use std::collections::HashMap;
@@ -15,8 +16,9 @@ pub fn handle_llm_command(args: &[&str]) -> Result<String> {
     match args[0] {
         "route" => handle_route_command(&args[1..]),
         "model" => handle_model_command(&args[1..]),
+        "config" => handle_config_command(&args[1..]),
         "help" => Ok(llm_help()),
-        _ => Ok(format!("Unknown llm command: {}. Try 'llm help'", args[0])),
+        _ => bail!("Unknown llm command: {}. Try 'llm help'", args[0]),
     }
 }
 
@@ -30,7 +32,7 @@ fn handle_route_command(args: &[&str]) -> Result<String> {
         "list" => list_routing_rules(),
         "test" => {
             if args.len() < 2 {
-                return Ok("Usage: llm route test <prompt>".to_string());
+                anyhow::bail!("Usage: llm route test <prompt>");
             }
             let verbose = args.contains(&"--verbose");
             let prompt = args.iter()
@@ -43,12 +45,12 @@ fn handle_route_command(args: &[&str]) -> Result<String> {
         },
         "explain" => {
             if args.len() < 2 {
-                return Ok("Usage: llm route explain <prompt>".to_string());
+                anyhow::bail!("Usage: llm route explain <prompt>");
             }
             super::llm_cli_extra::explain_routing(&args[1..].join(" "))
         },
         "info" => show_routing_info(),
-        _ => Ok(format!("Unknown route command: {}. Try 'llm route help'", args[0])),
+        _ => bail!("Unknown route command: {}. Try 'llm route help'", args[0]),
     }
 }
 
@@ -64,18 +66,27 @@ fn handle_model_command(args: &[&str]) -> Result<String> {
         "show-trusted" => super::llm_cli_extra::show_trusted_models(),
         "trust" => {
             if args.len() < 2 {
-                return Ok("Usage: llm model trust <model_id>".to_string());
+                anyhow::bail!("Usage: llm model trust <model_id>");
             }
             super::llm_cli_extra::toggle_model_trust(args[1], true)
         },
         "untrust" => {
             if args.len() < 2 {
-                return Ok("Usage: llm model untrust <model_id>".to_string());
+                anyhow::bail!("Usage: llm model untrust <model_id>");
             }
             super::llm_cli_extra::toggle_model_trust(args[1], false)
         },
         "capabilities" => list_model_capabilities(&args[1..]),
-        _ => Ok(format!("Unknown model command: {}. Try 'llm model help'", args[0])),
+        _ => bail!("Unknown model command: {}. Try 'llm model help'", args[0]),
+    }
+}
+
+/// Handle config subcommands
+fn handle_config_command(args: &[&str]) -> Result<String> {
+    match args.first() {
+        Some(&"dump") => dump_effective_config(),
+        Some(other) => bail!("Unknown config command: {}. Try 'llm config help'", other),
+        None => Ok(config_help()),
     }
 }
 
@@ -84,13 +95,25 @@ fn llm_help() -> String {
     r#"LLM Routing Commands:
   route             Routing configuration and testing
   model             Model information and capabilities
+  config            Effective routing configuration
   help              Show this help message
 
 Examples:
   llm route list
   llm route test "Write a function to sort an array"
   llm model info phi2_local
-  llm model list"#.to_string()
+  llm model list
+  llm config dump"#.to_string()
+}
+
+/// Config help text
+fn config_help() -> String {
+    r#"LLM Config Commands:
+  dump              Print the effective models, routing rules, defaults,
+                    load balancing and model health as JSON
+
+Examples:
+  llm config dump"#.to_string()
 }
 
 /// Route help text
@@ -119,6 +142,14 @@ Examples:
   llm model capabilities phi2_local"#.to_string()
 }
 
+/// Print the configuration routing runs with, health probed from the
+/// providers rather than assumed
+fn dump_effective_config() -> Result<String> {
+    let router = IntelligentRouter::from_loaded_config()?;
+    router.probe_health();
+    Ok(serde_json::to_string_pretty(&router.export_effective_config())?)
+}
+
 /// List routing rules
 fn list_routing_rules() -> Result<String> {
     let config = get_models_config()
@@ -3,6 +3,8 @@
 
 pub mod registry;
 pub mod router;
+pub mod batcher;
+pub mod tokens;
 pub mod providers;
 pub mod cli;
 pub mod stream_parser;
@@ -31,6 +33,53 @@ pub enum ModelCapability {
     Custom(String),
 }
 
+impl std::str::FromStr for ModelCapability {
+    type Err = anyhow::Error;
+
+    /// Parse the short capability names used on the command line
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" | "text_generation" => Ok(ModelCapability::TextGeneration),
+            "code" | "code_generation" => Ok(ModelCapability::CodeGeneration),
+            "image" | "image_generation" => Ok(ModelCapability::ImageGeneration),
+            "embed" | "embedding" => Ok(ModelCapability::Embedding),
+            "classify" | "classification" => Ok(ModelCapability::Classification),
+            "translate" | "translation" => Ok(ModelCapability::Translation),
+            "summary" | "summarization" => Ok(ModelCapability::Summarization),
+            "qa" | "question_answering" => Ok(ModelCapability::QuestionAnswering),
+            other => anyhow::bail!(
+                "Unknown capability '{}'. Expected one of: text, code, image, embed, classify, translate, summary, qa",
+                other
+            ),
+        }
+    }
+}
+
+impl ModelCapability {
+    /// Full configuration name, which [`FromStr`](std::str::FromStr) also
+    /// accepts a short alias for. `None` for custom capabilities.
+    pub fn config_name(&self) -> Option<&'static str> {
+        match self {
+            ModelCapability::TextGeneration => Some("text_generation"),
+            ModelCapability::CodeGeneration => Some("code_generation"),
+            ModelCapability::ImageGeneration => Some("image_generation"),
+            ModelCapability::Embedding => Some("embedding"),
+            ModelCapability::Classification => Some("classification"),
+            ModelCapability::Translation => Some("translation"),
+            ModelCapability::Summarization => Some("summarization"),
+            ModelCapability::QuestionAnswering => Some("question_answering"),
+            ModelCapability::Custom(_) => None,
+        }
+    }
+}
+
+/// Metadata key carrying an explicitly requested model id to the provider
+pub const MODEL_OVERRIDE_KEY: &str = "model";
+
+/// Metadata key carrying the trace id of the command or goal that made the
+/// request (see [`crate::trace_id`])
+pub const TRACE_ID_KEY: &str = "trace_id";
+
 /// Model endpoint information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelEndpoint {
@@ -73,4 +122,15 @@ pub trait ModelProvider: Send + Sync {
     fn is_available(&self) -> Result<bool>;
     fn infer(&self, request: &InferenceRequest) -> Result<InferenceResponse>;
     fn list_models(&self) -> Result<Vec<ModelEndpoint>>;
+
+    /// Whether `infer_batch` is cheaper than one `infer` per request for
+    /// requests of `capability`
+    fn supports_batching(&self, _capability: &ModelCapability) -> bool {
+        false
+    }
+
+    /// Run several requests, returning one response per request in order
+    fn infer_batch(&self, requests: &[InferenceRequest]) -> Result<Vec<InferenceResponse>> {
+        requests.iter().map(|request| self.infer(request)).collect()
+    }
 }
\ No newline at end of file
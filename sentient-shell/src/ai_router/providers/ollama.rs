@@ -44,6 +44,17 @@ struct OllamaGenerateResponse {
     eval_count: Option<i32>,
 }
 
+#[derive(Serialize)]
+struct OllamaEmbedRequest<'a> {
+    model: &'a str,
+    input: Vec<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
 #[derive(Deserialize)]
 struct OllamaModel {
     name: String,
@@ -66,14 +77,7 @@ impl ModelProvider for OllamaProvider {
 
     fn infer(&self, request: &InferenceRequest) -> Result<InferenceResponse> {
         let start_time = Instant::now();
-        
-        // For now, use a default model based on capability
-        let model = match &request.capability {
-            ModelCapability::CodeGeneration => "deepseek-v2:16b",
-            ModelCapability::TextGeneration => "llama3.2:latest",
-            ModelCapability::Embedding => "bge-m3:latest",
-            _ => "llama3.2:latest",
-        };
+        let model = model_for(request);
 
         let ollama_request = OllamaGenerateRequest {
             model: model.to_string(),
@@ -110,6 +114,56 @@ impl ModelProvider for OllamaProvider {
         })
     }
 
+    fn supports_batching(&self, capability: &ModelCapability) -> bool {
+        // Only /api/embed takes several inputs per call
+        *capability == ModelCapability::Embedding
+    }
+
+    fn infer_batch(&self, requests: &[InferenceRequest]) -> Result<Vec<InferenceResponse>> {
+        // /api/embed takes many inputs at once; generation is one call each
+        let model = match requests.first() {
+            Some(first) if first.capability == ModelCapability::Embedding => model_for(first),
+            _ => return requests.iter().map(|request| self.infer(request)).collect(),
+        };
+        if requests.iter().any(|r| r.capability != ModelCapability::Embedding || model_for(r) != model) {
+            return requests.iter().map(|request| self.infer(request)).collect();
+        }
+
+        let start_time = Instant::now();
+        let url = format!("{}/api/embed", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&OllamaEmbedRequest {
+                model,
+                input: requests.iter().map(|r| r.prompt.as_str()).collect(),
+            })
+            .send()
+            .context("Failed to send embedding request to Ollama")?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("Ollama embedding request failed: {}", response.status());
+        }
+
+        let embed: OllamaEmbedResponse = response.json()
+            .context("Failed to parse Ollama embedding response")?;
+        if embed.embeddings.len() != requests.len() {
+            anyhow::bail!(
+                "Ollama returned {} embeddings for {} inputs",
+                embed.embeddings.len(), requests.len()
+            );
+        }
+
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        Ok(embed.embeddings.into_iter().map(|embedding| InferenceResponse {
+            text: None,
+            embedding: Some(embedding),
+            metadata: std::collections::HashMap::new(),
+            model_used: model.to_string(),
+            tokens_used: None,
+            duration_ms,
+        }).collect())
+    }
+
     fn list_models(&self) -> Result<Vec<ModelEndpoint>> {
         let url = format!("{}/api/tags", self.base_url);
         let response = self.client.get(&url).send()
@@ -152,4 +206,17 @@ impl ModelProvider for OllamaProvider {
 
         Ok(endpoints)
     }
-}
\ No newline at end of file
+}
+
+/// Honour an explicitly requested model, otherwise pick a default by capability
+fn model_for(request: &InferenceRequest) -> &str {
+    match request.metadata.get(MODEL_OVERRIDE_KEY).and_then(|v| v.as_str()) {
+        Some(model) => model,
+        None => match &request.capability {
+            ModelCapability::CodeGeneration => "deepseek-v2:16b",
+            ModelCapability::TextGeneration => "llama3.2:latest",
+            ModelCapability::Embedding => "bge-m3:latest",
+            _ => "llama3.2:latest",
+        },
+    }
+}
@@ -74,6 +74,23 @@ impl ModelRegistry {
         self.endpoints.read().unwrap().get(&endpoint_id).cloned()
     }
 
+    /// Find an endpoint by `provider:model_id` or by bare model id
+    pub fn find_endpoint(&self, model: &str) -> Option<ModelEndpoint> {
+        let endpoints = self.endpoints.read().unwrap();
+
+        if let Some(endpoint) = endpoints.get(model) {
+            return Some(endpoint.clone());
+        }
+
+        let mut matching: Vec<_> = endpoints
+            .values()
+            .filter(|e| e.model_id == model || e.name == model)
+            .cloned()
+            .collect();
+        matching.sort_by(|a, b| b.priority.cmp(&a.priority));
+        matching.into_iter().next()
+    }
+
     /// List all registered endpoints
     pub fn list_endpoints(&self) -> Vec<ModelEndpoint> {
         self.endpoints.read().unwrap().values().cloned().collect()
@@ -1,16 +1,45 @@
 // Request routing logic
 use super::*;
 use super::registry::get_model_registry;
+use super::batcher::batcher_for;
+use super::config::{get_models_config, with_request_defaults, BatchingConfig};
+use crate::trace_id::{self, TraceScope};
 use anyhow::{Result, bail};
+use std::sync::Arc;
 use std::time::Instant;
 use log::{info, debug, warn, error};
 
 pub struct AIRouter;
 
 impl AIRouter {
-    /// Route an inference request to the best available model
+    /// Route an inference request to the best available model. Requests
+    /// the preferred provider can batch join its queue, so concurrent
+    /// callers share backend calls; if the batch fails, the request is
+    /// routed on its own.
+    ///
+    /// Blocks the calling thread; from async code use
+    /// [`AIRouter::route_request_batched`].
     pub fn route_request(request: &InferenceRequest) -> Result<InferenceResponse> {
         let start_time = Instant::now();
+        let request = &with_request_defaults(request);
+        let _trace = enter_trace(request);
+        
+        if let Some((endpoint, provider, batching)) = batch_target(request) {
+            let batched = for_endpoint(request, &endpoint);
+            match batcher_for(provider, &batching).submit_blocking(batched) {
+                Ok(response) => return Ok(finish_batched(response, &endpoint, start_time)),
+                Err(e) => warn!("⚠️  [AI-ROUTER] Batched request to {} failed: {}", endpoint.name, e),
+            }
+        }
+        
+        Self::route_unbatched(request)
+    }
+    
+    /// Try the endpoints for the request's capability in priority order,
+    /// one `infer` call each
+    fn route_unbatched(request: &InferenceRequest) -> Result<InferenceResponse> {
+        let start_time = Instant::now();
+        let _trace = enter_trace(request);
         let registry = get_model_registry();
         
         // Find suitable endpoints
@@ -62,6 +91,24 @@ impl AIRouter {
         bail!("All endpoints failed. Last error: {:?}", last_error)
     }
     
+    /// Route a request from async code, like `route_request` but waiting
+    /// for a batched response without holding a thread
+    pub async fn route_request_batched(request: InferenceRequest) -> Result<InferenceResponse> {
+        // Tag the request with the caller's trace id before it changes threads
+        let request = with_request_defaults(&request);
+        
+        if let Some((endpoint, provider, batching)) = batch_target(&request) {
+            let start_time = Instant::now();
+            let batched = for_endpoint(&request, &endpoint);
+            match batcher_for(provider, &batching).submit(batched).await {
+                Ok(response) => return Ok(finish_batched(response, &endpoint, start_time)),
+                Err(e) => warn!("⚠️  [AI-ROUTER] Batched request to {} failed: {}", endpoint.name, e),
+            }
+        }
+        
+        tokio::task::spawn_blocking(move || Self::route_unbatched(&request)).await?
+    }
+    
     /// Route a request to a specific model
     pub fn route_to_model(
         provider: &str, 
@@ -69,6 +116,8 @@ impl AIRouter {
         request: &InferenceRequest
     ) -> Result<InferenceResponse> {
         let registry = get_model_registry();
+        let request = &with_request_defaults(request);
+        let _trace = enter_trace(request);
         
         // Check if endpoint exists and is active
         match registry.get_endpoint(provider, model_id) {
@@ -92,6 +141,33 @@ impl AIRouter {
         }
     }
     
+    /// Route a request to an explicitly named model, bypassing intent detection.
+    ///
+    /// `model` may be `provider:model_id` or a bare model id.
+    pub fn route_to_named_model(model: &str, request: &InferenceRequest) -> Result<InferenceResponse> {
+        let registry = get_model_registry();
+
+        let endpoint = match registry.find_endpoint(model) {
+            Some(endpoint) => endpoint,
+            None => bail!("Model '{}' is not available. Run 'ai list' to see registered models", model),
+        };
+
+        if !endpoint.capabilities.contains(&request.capability) {
+            bail!(
+                "Model '{}' does not support capability {:?}",
+                model, request.capability
+            );
+        }
+
+        let mut request = request.clone();
+        request.metadata.insert(
+            MODEL_OVERRIDE_KEY.to_string(),
+            serde_json::Value::String(endpoint.model_id.clone()),
+        );
+
+        Self::route_to_model(&endpoint.provider, &endpoint.model_id, &request)
+    }
+
     /// Get routing statistics
     pub fn get_stats() -> RouterStats {
         // This would track actual usage statistics
@@ -105,6 +181,54 @@ impl AIRouter {
     }
 }
 
+/// The first endpoint for the request's capability, with its provider and
+/// the batching config, if batching is enabled and the provider batches
+/// requests of that capability
+fn batch_target(
+    request: &InferenceRequest,
+) -> Option<(ModelEndpoint, Arc<dyn ModelProvider>, BatchingConfig)> {
+    let batching = get_models_config()
+        .map(|c| c.load_balancing.batching)
+        .unwrap_or_default();
+    if !batching.enabled {
+        return None;
+    }
+    
+    let registry = get_model_registry();
+    let endpoint = registry.get_endpoints_by_capability(&request.capability).into_iter().next()?;
+    let provider = registry.get_provider(&endpoint.provider)
+        .filter(|provider| provider.supports_batching(&request.capability))?;
+    Some((endpoint, provider, batching))
+}
+
+/// `request` pinned to `endpoint`'s model, so the batch goes to it
+fn for_endpoint(request: &InferenceRequest, endpoint: &ModelEndpoint) -> InferenceRequest {
+    let mut request = request.clone();
+    request.metadata
+        .entry(MODEL_OVERRIDE_KEY.to_string())
+        .or_insert_with(|| serde_json::Value::String(endpoint.model_id.clone()));
+    request
+}
+
+fn finish_batched(
+    mut response: InferenceResponse,
+    endpoint: &ModelEndpoint,
+    start_time: Instant,
+) -> InferenceResponse {
+    response.model_used = format!("{}:{}", endpoint.provider, endpoint.model_id);
+    response.duration_ms = start_time.elapsed().as_millis() as u64;
+    response
+}
+
+/// Make the trace id `request` carries current while it is routed, so the
+/// router's logs name it on whichever thread does the work
+fn enter_trace(request: &InferenceRequest) -> Option<TraceScope> {
+    request.metadata
+        .get(TRACE_ID_KEY)
+        .and_then(|id| id.as_str())
+        .map(trace_id::enter)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RouterStats {
     pub total_requests: u64,
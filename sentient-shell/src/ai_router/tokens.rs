@@ -0,0 +1,190 @@
+//! Token estimation for context-window checks
+//!
+//! Routing needs to know whether a prompt fits a model before sending it.
+//! Counting bytes and dividing by four is close for plain English but badly
+//! off for code, numbers and CJK text, so estimators look at the text itself.
+
+/// Estimates how many tokens a model will see for a piece of text
+pub trait TokenEstimator: Send + Sync {
+    fn estimate(&self, text: &str) -> usize;
+}
+
+/// Tokenizer-free estimate modelled on BPE vocabularies such as cl100k.
+///
+/// Whitespace-delimited words are split into runs of letters, digits and
+/// punctuation, each costing roughly what a BPE tokenizer charges for it:
+/// a token per six letters, per three digits and per two punctuation marks.
+/// Han characters cost more than a token each, kana, hangul and other
+/// non-Latin letters about half, and emoji and symbols one per two UTF-8
+/// bytes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicEstimator;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CharClass {
+    Space,
+    Letter,
+    Digit,
+    Punct,
+    Other,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_whitespace() {
+            Self::Space
+        } else if c.is_ascii_alphabetic() {
+            Self::Letter
+        } else if c.is_ascii_digit() {
+            Self::Digit
+        } else if c.is_ascii() {
+            Self::Punct
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Tokens for a run of `len` characters of this class
+    fn run_cost(self, len: usize) -> f64 {
+        match self {
+            Self::Letter => len.div_ceil(6) as f64,
+            Self::Digit => len.div_ceil(3) as f64,
+            Self::Punct => len.div_ceil(2) as f64,
+            Self::Space | Self::Other => 0.0,
+        }
+    }
+}
+
+fn is_han(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FFFF
+    )
+}
+
+fn is_kana_or_hangul(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30FF | 0xAC00..=0xD7AF)
+}
+
+impl TokenEstimator for HeuristicEstimator {
+    fn estimate(&self, text: &str) -> usize {
+        let mut tokens = 0.0;
+        let mut run = CharClass::Space;
+        let mut run_len = 0;
+
+        for c in text.chars() {
+            let class = CharClass::of(c);
+            if class != run {
+                tokens += run.run_cost(run_len);
+                run = class;
+                run_len = 0;
+            }
+            match class {
+                CharClass::Other if is_han(c) => tokens += 1.25,
+                CharClass::Other if is_kana_or_hangul(c) || c.is_alphabetic() => tokens += 0.5,
+                CharClass::Other => tokens += c.len_utf8() as f64 / 2.0,
+                _ => run_len += 1,
+            }
+        }
+        tokens += run.run_cost(run_len);
+
+        tokens.ceil() as usize
+    }
+}
+
+/// Exact counts from the cl100k_base BPE tokenizer
+#[cfg(feature = "tiktoken")]
+pub struct TiktokenEstimator {
+    bpe: tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenEstimator {
+    pub fn cl100k() -> anyhow::Result<Self> {
+        Ok(Self {
+            bpe: tiktoken_rs::cl100k_base()?,
+        })
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl TokenEstimator for TiktokenEstimator {
+    fn estimate(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+}
+
+/// The estimator routing uses unless given another: the real tokenizer
+/// when built with the `tiktoken` feature, the heuristic otherwise
+pub fn default_estimator() -> std::sync::Arc<dyn TokenEstimator> {
+    #[cfg(feature = "tiktoken")]
+    match TiktokenEstimator::cl100k() {
+        Ok(estimator) => return std::sync::Arc::new(estimator),
+        Err(e) => log::warn!("Falling back to heuristic token estimates: {}", e),
+    }
+    std::sync::Arc::new(HeuristicEstimator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Strings with their cl100k_base token counts
+    const KNOWN_COUNTS: &[(&str, usize)] = &[
+        ("Hello, world!", 4),
+        ("The quick brown fox jumps over the lazy dog.", 10),
+        (
+            "Check disk usage on /var/log and report anything over 90%.",
+            14,
+        ),
+        ("fn main() {\n    println!(\"Hello, world!\");\n}\n", 12),
+        (
+            "SELECT id, name FROM users WHERE created_at > '2024-01-01' ORDER BY name;",
+            22,
+        ),
+        ("{\"tool\": \"disk_info\", \"args\": {\"path\": \"/\"}}", 15),
+        ("1234567890", 4),
+        ("今天天气很好，我们去公园散步吧。", 20),
+        ("こんにちは世界", 4),
+        (
+            "Привет, как дела? Проверь, пожалуйста, свободное место на диске.",
+            31,
+        ),
+        ("Les élèves étudient l'informatique à l'université.", 17),
+        ("🚀🔥 Deploy finished ✅", 10),
+        ("", 0),
+    ];
+
+    fn within_tolerance(estimate: usize, actual: usize) -> bool {
+        let allowed = (actual as f64 * 0.3).max(2.0);
+        (estimate as f64 - actual as f64).abs() <= allowed
+    }
+
+    #[test]
+    fn test_heuristic_tracks_known_token_counts() {
+        for &(text, actual) in KNOWN_COUNTS {
+            let estimate = HeuristicEstimator.estimate(text);
+            assert!(
+                within_tolerance(estimate, actual),
+                "{:?}: estimated {} tokens, actual {}",
+                text,
+                estimate,
+                actual
+            );
+        }
+
+        // Where bytes / 4 goes badly wrong
+        let chinese = "今天天气很好，我们去公园散步吧。";
+        assert!(!within_tolerance(chinese.len() / 4, 20));
+        assert!(within_tolerance(HeuristicEstimator.estimate(chinese), 20));
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_tiktoken_matches_known_token_counts() {
+        let estimator = TiktokenEstimator::cl100k().unwrap();
+        for &(text, actual) in KNOWN_COUNTS {
+            assert_eq!(estimator.estimate(text), actual, "{:?}", text);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Non-interactive batch mode
+//!
+//! Executes shell commands from a script file (or a single `-c` string)
+//! without a prompt. Used for provisioning and automated tests.
+
+use crate::ShellState;
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Outcome of a batch run
+#[derive(Debug, Default)]
+pub struct BatchReport {
+    /// Number of commands that were executed
+    pub executed: usize,
+    /// Failed commands as (line number, error message)
+    pub failures: Vec<(usize, String)>,
+    /// Whether the script requested `exit` before reaching the end
+    pub exited_early: bool,
+}
+
+impl BatchReport {
+    pub fn success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Process exit code for the batch: 0 on success, 1 if any command failed
+    pub fn exit_code(&self) -> i32 {
+        if self.success() {
+            0
+        } else {
+            1
+        }
+    }
+}
+
+/// Run each line through `ShellState::execute_command`.
+///
+/// Blank lines and lines starting with `#` are skipped. Execution stops on
+/// the first failing command unless `keep_going` is set.
+pub fn run_lines<I, S>(shell: &mut ShellState, lines: I, keep_going: bool) -> BatchReport
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut report = BatchReport::default();
+
+    for (idx, line) in lines.into_iter().enumerate() {
+        let line_no = idx + 1;
+        let command = line.as_ref().trim();
+        if command.is_empty() || command.starts_with('#') {
+            continue;
+        }
+
+        log::debug!("batch[{}]: {}", line_no, command);
+        report.executed += 1;
+
+        match shell.execute_command(command) {
+            Ok(true) => {
+                report.exited_early = true;
+                break;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("Error at line {}: {}", line_no, e);
+                report.failures.push((line_no, e.to_string()));
+                if !keep_going {
+                    break;
+                }
+            }
+        }
+    }
+
+    report
+}
+
+/// Run all commands from a script file
+pub fn run_script(shell: &mut ShellState, path: &Path, keep_going: bool) -> Result<BatchReport> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read script {}", path.display()))?;
+    Ok(run_lines(shell, contents.lines(), keep_going))
+}
@@ -1,4 +1,6 @@
 pub mod rag_tool;
+pub mod rl_eval;
+pub mod rl_snapshot;
 pub mod rl_trace;
 pub mod rl_infer;
 pub mod rl_retrain;
@@ -0,0 +1,152 @@
+// Offline policy evaluation against recorded traces
+// Replays a JSONL trace through JSONLEnv and scores a checkpoint's choices
+// against the actions that were actually taken, without touching the system
+
+use anyhow::{anyhow, Result};
+use clap::ArgMatches;
+use colored::*;
+use sentient_rl_core::{AgentMode, VectorObservation};
+use sentient_rl_env::sentient_envs::RewardConfig;
+use sentient_rl_env::{JSONLEnv, JSONLEnvConfig, TraceEvaluation, TracePolicy};
+use std::path::{Path, PathBuf};
+
+use crate::policy_injector::{PolicyInjector, PolicyInjectorConfig};
+use crate::rl_training::{RLTrainingConfig, TrainingSession};
+
+/// Where training writes checkpoints and their metadata
+const CHECKPOINT_DIR: &str = "/var/rl_checkpoints";
+
+/// Lets the policy injector's loaded policy choose actions during a replay
+struct InjectorPolicy<'a> {
+    injector: &'a PolicyInjector,
+    /// Mode requested through `set_mode`, applied before the next choice
+    pending_mode: Option<AgentMode>,
+}
+
+impl<'a> InjectorPolicy<'a> {
+    fn new(injector: &'a PolicyInjector) -> Self {
+        Self {
+            injector,
+            pending_mode: None,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TracePolicy for InjectorPolicy<'_> {
+    async fn choose(&mut self, observation: &VectorObservation) -> Result<usize> {
+        if let Some(mode) = self.pending_mode.take() {
+            self.injector.set_mode(mode).await?;
+        }
+        let features: Vec<f32> = observation.data.iter().map(|&x| x as f32).collect();
+        self.injector
+            .choose_action(&features)
+            .await?
+            .ok_or_else(|| anyhow!("Policy produced no usable action"))
+    }
+    
+    /// The injector's policy sits behind an async lock, so the switch is
+    /// made on the next `choose`
+    fn set_mode(&mut self, mode: AgentMode) {
+        self.pending_mode = Some(mode);
+    }
+}
+
+/// Score checkpoint `id` (an id, "latest" or "best") on `trace`
+pub async fn evaluate_checkpoint(
+    checkpoint_dir: &Path,
+    id: &str,
+    trace: &Path,
+) -> Result<(String, TraceEvaluation)> {
+    let session = TrainingSession::with_checkpoint_dir(
+        RLTrainingConfig::default(),
+        checkpoint_dir.to_path_buf(),
+    );
+    let meta = session.resolve_checkpoint(id).await?;
+
+    let injector = PolicyInjector::new(PolicyInjectorConfig {
+        checkpoint_path: checkpoint_dir.join(format!("{}.bin", meta.id)),
+        ..Default::default()
+    });
+    injector.load_policy().await?;
+
+    // Encode observations the way training saw them
+    let mut env = JSONLEnv::new(JSONLEnvConfig {
+        trace_file: trace.to_path_buf(),
+        max_episode_length: RLTrainingConfig::default().steps_per_rollout,
+        observation_dim: meta.observation_dim,
+        action_dim: meta.action_dim,
+        reward_config: RewardConfig::default(),
+    })
+    .await?;
+
+    let evaluation = env.evaluate(&mut InjectorPolicy::new(&injector)).await?;
+    Ok((meta.id, evaluation))
+}
+
+pub async fn handle_eval_trace_command(matches: &ArgMatches) -> Result<()> {
+    let id = matches.get_one::<String>("checkpoint").unwrap();
+    let trace = PathBuf::from(matches.get_one::<String>("trace").unwrap());
+
+    let (id, evaluation) = evaluate_checkpoint(Path::new(CHECKPOINT_DIR), id, &trace).await?;
+
+    println!(
+        "{} {} on {}",
+        "📏 Trace evaluation:".bold().cyan(),
+        id,
+        trace.display()
+    );
+    println!("   Episodes: {}", evaluation.episodes);
+    println!("   Steps: {}", evaluation.steps);
+    match evaluation.agreement_rate() {
+        Some(rate) => println!(
+            "   Agreement: {:.1}% ({}/{} steps with a recorded action)",
+            rate * 100.0,
+            evaluation.agreements,
+            evaluation.compared
+        ),
+        None => println!("   Agreement: n/a (no step recorded an action)"),
+    }
+    println!("   Total reward: {:.3}", evaluation.total_reward);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy_injector::ObservationSpec;
+    use sentient_rl_agent::ppo_full::PPOAgentFull;
+    use sentient_rl_agent::PPOConfig;
+
+    #[tokio::test]
+    async fn test_replay_switches_the_loaded_policy_mode() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let spec = ObservationSpec::default();
+        let config = RLTrainingConfig {
+            observation_dim: spec.len(),
+            observation_spec: Some(spec.clone()),
+            ..Default::default()
+        };
+        let action_dim = config.action_dim;
+        let session = TrainingSession::with_checkpoint_dir(config, dir.path().to_path_buf());
+        session.write_checkpoint_metadata("checkpoint_ep0", 0).await.unwrap();
+        let agent = PPOAgentFull::new(PPOConfig::default(), spec.len(), action_dim).await.unwrap();
+        agent.save_bin(&dir.path().join("checkpoint_ep0.bin")).await.unwrap();
+
+        let injector = PolicyInjector::new(PolicyInjectorConfig {
+            checkpoint_path: dir.path().join("checkpoint_ep0.bin"),
+            ..Default::default()
+        });
+        injector.load_policy().await.unwrap();
+        injector.set_mode(AgentMode::Train).await.unwrap();
+
+        // Applied on the next choice, once the policy lock can be awaited
+        let mut policy = InjectorPolicy::new(&injector);
+        policy.set_mode(AgentMode::Eval);
+        assert_eq!(injector.mode().await, Some(AgentMode::Train));
+        let observation = VectorObservation { data: vec![0.5; spec.len()] };
+        policy.choose(&observation).await.unwrap();
+        assert_eq!(injector.mode().await, Some(AgentMode::Eval));
+    }
+}
@@ -0,0 +1,85 @@
+// Smoke-testing an environment with a random agent
+// Plays a few episodes of a named environment with uniformly random actions
+// and reports reward statistics; any error or panic fails the smoke test
+
+use anyhow::{anyhow, bail, Result};
+use clap::ArgMatches;
+use colored::*;
+use sentient_rl_agent::{smoke_test, SmokeReport};
+use sentient_rl_core::EnvironmentConfig;
+use sentient_rl_env::{list_envs, make_env};
+use std::any::Any;
+
+/// Play `episodes` random episodes of the registered environment called
+/// `env_name`
+pub async fn run_smoke(env_name: &str, episodes: usize) -> Result<SmokeReport> {
+    let name = env_name.to_lowercase();
+    if !list_envs().contains(&name) {
+        bail!(
+            "Unknown environment: {} (expected one of {})",
+            env_name,
+            list_envs().join(", ")
+        );
+    }
+    let mut env = make_env(&name, EnvironmentConfig::default())?;
+    // Run on its own task so a panicking environment is reported, not fatal
+    let task = tokio::spawn(async move { smoke_test(&mut env, episodes).await });
+
+    match task.await {
+        Ok(result) => result.map_err(|e| e.context(format!("Smoke test of {} failed", env_name))),
+        Err(e) if e.is_panic() => Err(anyhow!(
+            "Smoke test of {} panicked: {}",
+            env_name,
+            panic_message(e.into_panic())
+        )),
+        Err(e) => Err(anyhow!("Smoke test of {} did not finish: {}", env_name, e)),
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
+
+pub async fn handle_smoke_command(matches: &ArgMatches) -> Result<()> {
+    let env_name = matches.get_one::<String>("env").unwrap();
+    let episodes: usize = matches.get_one::<String>("episodes").unwrap().parse()?;
+
+    let report = run_smoke(env_name, episodes).await?;
+
+    println!("{} {}", "💨 Smoke test passed:".bold().green(), env_name);
+    println!(
+        "   Spaces: observation {:?}, {} discrete actions",
+        report.observation_shape, report.n_actions
+    );
+    println!("   Episodes: {}", report.episodes);
+    println!("   Reward: {:.3} ± {:.3}", report.mean_reward, report.std_reward);
+    println!("   Mean length: {:.1} steps", report.mean_length);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cartpole_smoke_reports_sane_episode_stats() {
+        let report = run_smoke("CartPole", 20).await.unwrap();
+        assert_eq!(report.episodes, 20);
+        assert_eq!(report.observation_shape, vec![4]);
+        assert_eq!(report.n_actions, 2);
+
+        // A random policy drops the pole within a few dozen steps, scoring one
+        // per step survived
+        assert!(report.mean_length >= 8.0 && report.mean_length < 200.0, "{:?}", report);
+        assert!((report.mean_reward - report.mean_length).abs() < 1e-9, "{:?}", report);
+        assert!(report.std_reward > 0.0 && report.std_reward.is_finite(), "{:?}", report);
+
+        let err = run_smoke("pong", 1).await.unwrap_err();
+        assert!(err.to_string().starts_with("Unknown environment: pong"), "{}", err);
+    }
+}
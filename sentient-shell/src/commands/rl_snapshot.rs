@@ -0,0 +1,382 @@
+// Full RL state snapshots for migrating a system
+// Bundles replay buffers, trajectories, policy checkpoints and the policy
+// injector's learned state into one directory with a versioned manifest
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::ArgMatches;
+use colored::*;
+use sentient_memory::{PolicyStorage, RLMemoryStore};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::policy_injector::{
+    InjectorState, ObservationSpec, PolicyInjector, PolicyInjectorConfig, OBSERVATION_SPEC_VERSION,
+};
+
+/// Version of the snapshot layout written by this build
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Where the RL store keeps its policy checkpoints (under `policies/`)
+pub const RL_STORE_DIR: &str = "/var/rl_checkpoints";
+
+/// Where the RL store's replay buffers and trajectories are persisted
+pub const RL_MEMORY_DIR: &str = "/var/rl_checkpoints/memory";
+
+const MANIFEST_FILE: &str = "manifest.json";
+const STORE_DIR: &str = "store";
+const POLICIES_DIR: &str = "policies";
+const INJECTOR_STATE_FILE: &str = "injector_state.json";
+
+/// What a snapshot holds and which versions wrote it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// Snapshot layout version
+    pub format_version: u32,
+    /// Version of the sentient-shell that wrote the snapshot
+    pub shell_version: String,
+    /// Observation layout the injector state was learned on
+    pub observation_spec_version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Names of the bundled replay buffers
+    pub replay_buffers: Vec<String>,
+    /// Number of bundled trajectories
+    pub trajectories: usize,
+    /// IDs of the bundled policy checkpoints
+    pub checkpoints: Vec<Uuid>,
+}
+
+impl SnapshotManifest {
+    /// Fail unless this build can restore the snapshot
+    pub fn check_compatible(&self) -> Result<()> {
+        if self.format_version > SNAPSHOT_FORMAT_VERSION {
+            anyhow::bail!(
+                "Snapshot format version {} was written by sentient-shell {}; this build reads up to version {}",
+                self.format_version, self.shell_version, SNAPSHOT_FORMAT_VERSION
+            );
+        }
+        if self.observation_spec_version != OBSERVATION_SPEC_VERSION {
+            anyhow::bail!(
+                "Snapshot injector state uses observation spec version {}, this build uses {}",
+                self.observation_spec_version, OBSERVATION_SPEC_VERSION
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Write everything in `store` and `injector` to the empty or missing
+/// directory `dir`. The manifest is written last, so an interrupted
+/// snapshot is never mistaken for a complete one.
+pub async fn create_snapshot(
+    store: &RLMemoryStore,
+    injector: &PolicyInjector,
+    dir: &Path,
+) -> Result<SnapshotManifest> {
+    if dir.exists() && std::fs::read_dir(dir)?.next().is_some() {
+        anyhow::bail!("Snapshot directory {:?} is not empty", dir);
+    }
+    tokio::fs::create_dir_all(dir).await?;
+
+    store.save_all(&dir.join(STORE_DIR)).await?;
+
+    let policies = PolicyStorage::new(dir.join(POLICIES_DIR));
+    policies.init().await?;
+    let checkpoints = store.policy_storage().checkpoint_ids().await?;
+    for id in &checkpoints {
+        let checkpoint = store.policy_storage().load_checkpoint(*id).await
+            .with_context(|| format!("Failed to read policy checkpoint {}", id))?;
+        policies.save_checkpoint(checkpoint).await?;
+    }
+
+    let state = injector.export_state().await;
+    tokio::fs::write(dir.join(INJECTOR_STATE_FILE), serde_json::to_string_pretty(&state)?).await?;
+
+    let manifest = SnapshotManifest {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        shell_version: env!("CARGO_PKG_VERSION").to_string(),
+        observation_spec_version: OBSERVATION_SPEC_VERSION,
+        created_at: Utc::now(),
+        replay_buffers: store.replay_buffer_names().await,
+        trajectories: store.get_trajectories(usize::MAX).await.len(),
+        checkpoints,
+    };
+    tokio::fs::write(dir.join(MANIFEST_FILE), serde_json::to_string_pretty(&manifest)?).await?;
+
+    Ok(manifest)
+}
+
+/// Read and validate the manifest of the snapshot in `dir`
+pub async fn read_manifest(dir: &Path) -> Result<SnapshotManifest> {
+    let path = dir.join(MANIFEST_FILE);
+    let content = tokio::fs::read_to_string(&path).await
+        .with_context(|| format!("{:?} is not a complete RL snapshot", dir))?;
+    let manifest: SnapshotManifest = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid snapshot manifest {:?}", path))?;
+    manifest.check_compatible()?;
+
+    for name in &manifest.replay_buffers {
+        let buffer = dir.join(STORE_DIR).join(format!("{}_replay.bin.gz", name));
+        if !buffer.exists() {
+            anyhow::bail!("Snapshot is missing replay buffer {:?}", name);
+        }
+    }
+    for id in &manifest.checkpoints {
+        if !dir.join(POLICIES_DIR).join(id.to_string()).join("metadata.json").exists() {
+            anyhow::bail!("Snapshot is missing policy checkpoint {}", id);
+        }
+    }
+    Ok(manifest)
+}
+
+/// Restore the snapshot in `dir` into `store` and `injector`. Nothing is
+/// changed unless the manifest is compatible and complete and the injector
+/// state fits; the injector is only updated once the store has loaded, so a
+/// store that fails to load leaves it untouched.
+pub async fn restore_snapshot(
+    store: &RLMemoryStore,
+    injector: &PolicyInjector,
+    dir: &Path,
+) -> Result<SnapshotManifest> {
+    let manifest = read_manifest(dir).await?;
+
+    let state_path = dir.join(INJECTOR_STATE_FILE);
+    let state: InjectorState = serde_json::from_str(&tokio::fs::read_to_string(&state_path).await?)
+        .with_context(|| format!("Invalid injector state {:?}", state_path))?;
+    injector.check_state(&state).await
+        .context("Snapshot injector state does not fit this injector")?;
+
+    store.load_all(&dir.join(STORE_DIR)).await?;
+
+    let policies = PolicyStorage::new(dir.join(POLICIES_DIR));
+    let existing = store.policy_storage().checkpoint_ids().await?;
+    for id in &manifest.checkpoints {
+        if existing.contains(id) {
+            continue;
+        }
+        let checkpoint = policies.load_checkpoint(*id).await
+            .with_context(|| format!("Failed to read snapshot checkpoint {}", id))?;
+        store.policy_storage().save_checkpoint(checkpoint).await?;
+    }
+
+    // Persisted as soon as it is imported, so this comes last
+    injector.import_state(state).await?;
+
+    Ok(manifest)
+}
+
+/// The system's RL store, with its persisted buffers and trajectories
+async fn open_store() -> Result<RLMemoryStore> {
+    let store = RLMemoryStore::new(PathBuf::from(RL_STORE_DIR));
+    store.init().await?;
+    if Path::new(RL_MEMORY_DIR).exists() {
+        store.load_all(Path::new(RL_MEMORY_DIR)).await?;
+    }
+    Ok(store)
+}
+
+/// The system's policy injector with its learned state loaded
+async fn open_injector() -> Result<PolicyInjector> {
+    let injector = PolicyInjector::new(PolicyInjectorConfig::default());
+    if let Err(e) = injector.load_policy().await {
+        log::warn!("No usable policy checkpoint ({:#}); using the default observation spec", e);
+        injector.load_state(&ObservationSpec::default()).await?;
+    }
+    Ok(injector)
+}
+
+pub async fn handle_snapshot_command(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("create", create_matches)) => {
+            let dir = Path::new(create_matches.get_one::<String>("dir").unwrap());
+            let store = open_store().await?;
+            let injector = open_injector().await?;
+            let manifest = create_snapshot(&store, &injector, dir).await?;
+
+            println!("{} {}", "📦 Snapshot created:".bold().green(), dir.display());
+            print_contents(&manifest);
+            Ok(())
+        }
+        Some(("restore", restore_matches)) => {
+            let dir = Path::new(restore_matches.get_one::<String>("dir").unwrap());
+            let store = open_store().await?;
+            let injector = open_injector().await?;
+            let manifest = restore_snapshot(&store, &injector, dir).await?;
+            store.save_all(Path::new(RL_MEMORY_DIR)).await?;
+
+            println!("{} {}", "♻️  Snapshot restored:".bold().green(), dir.display());
+            println!("   Created: {} by sentient-shell {}", manifest.created_at, manifest.shell_version);
+            print_contents(&manifest);
+            Ok(())
+        }
+        _ => {
+            println!("Use 'rl snapshot create <dir>' or 'rl snapshot restore <dir>'");
+            Ok(())
+        }
+    }
+}
+
+fn print_contents(manifest: &SnapshotManifest) {
+    println!("   Replay buffers: {}", manifest.replay_buffers.len());
+    println!("   Trajectories: {}", manifest.trajectories);
+    println!("   Policy checkpoints: {}", manifest.checkpoints.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentient_memory::rl_store::{Experience, PolicyCheckpoint, PolicyMetadata, Trajectory};
+
+    fn experience(i: usize) -> Experience {
+        Experience {
+            state: vec![i as f32, 0.5],
+            action: vec![(i % 2) as f32],
+            reward: i as f32 * 0.1,
+            next_state: vec![i as f32 + 1.0, 0.5],
+            done: i % 4 == 3,
+            metadata: None,
+            timestamp: Utc::now(),
+        }
+    }
+
+    fn checkpoint(episode: usize) -> PolicyCheckpoint {
+        PolicyCheckpoint {
+            id: Uuid::new_v4(),
+            model_type: "ppo".to_string(),
+            parameters: vec![episode as u8; 32],
+            metadata: PolicyMetadata {
+                episode,
+                total_steps: episode * 100,
+                average_reward: 0.5,
+                best_reward: episode as f32,
+                training_time_hours: 0.1,
+                hyperparameters: serde_json::json!({"learning_rate": 3e-4}),
+            },
+            created_at: Utc::now(),
+        }
+    }
+
+    fn injector(dir: &Path) -> PolicyInjector {
+        PolicyInjector::new(PolicyInjectorConfig {
+            state_path: dir.join("injector_state.json"),
+            ..Default::default()
+        })
+    }
+
+    /// Replay buffer contents as saved to disk
+    async fn buffer_bytes(store: &RLMemoryStore, name: &str, dir: &Path) -> Vec<u8> {
+        let path = dir.join(format!("{}.bin.gz", Uuid::new_v4()));
+        store.get_replay_buffer(name, None).await.save(&path).await.unwrap();
+        std::fs::read(path).unwrap()
+    }
+
+    async fn checkpoint_json(store: &RLMemoryStore) -> Vec<serde_json::Value> {
+        let storage = store.policy_storage();
+        let mut checkpoints = Vec::new();
+        for id in storage.checkpoint_ids().await.unwrap() {
+            let checkpoint = storage.load_checkpoint(id).await.unwrap();
+            checkpoints.push(serde_json::to_value(checkpoint).unwrap());
+        }
+        checkpoints
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_restores_into_fresh_store() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = RLMemoryStore::new(dir.path().join("source"));
+        source.init().await.unwrap();
+        for (name, count) in [("ppo", 6), ("dqn", 3)] {
+            let buffer = source.get_replay_buffer(name, None).await;
+            for i in 0..count {
+                buffer.add(experience(i)).await.unwrap();
+            }
+        }
+        for episode in 0..3 {
+            source.add_trajectory(Trajectory {
+                id: Uuid::new_v4(),
+                experiences: (0..episode + 1).map(experience).collect(),
+                total_reward: episode as f32,
+                metadata: Some(serde_json::json!({"episode": episode})),
+                created_at: Utc::now(),
+            }).await.unwrap();
+        }
+        for episode in [10, 20] {
+            source.policy_storage().save_checkpoint(checkpoint(episode)).await.unwrap();
+        }
+        let source_injector = injector(&dir.path().join("source"));
+        let mut state = source_injector.export_state().await;
+        let features = vec![0.5; state.reward_model.num_features()];
+        state.reward_model.update(2, &features, 1.0);
+        source_injector.import_state(state).await.unwrap();
+
+        let snapshot = dir.path().join("snapshot");
+        let manifest = create_snapshot(&source, &source_injector, &snapshot).await.unwrap();
+        assert_eq!(manifest.replay_buffers, vec!["dqn", "ppo"]);
+        assert_eq!(manifest.trajectories, 3);
+        assert_eq!(manifest.checkpoints.len(), 2);
+        assert!(create_snapshot(&source, &source_injector, &snapshot).await.is_err());
+
+        let restored = RLMemoryStore::new(dir.path().join("restored"));
+        restored.init().await.unwrap();
+        let restored_injector = injector(&dir.path().join("restored"));
+        assert_eq!(restore_snapshot(&restored, &restored_injector, &snapshot).await.unwrap(), manifest);
+
+        assert_eq!(restored.replay_buffer_names().await, source.replay_buffer_names().await);
+        for name in ["ppo", "dqn"] {
+            assert_eq!(
+                buffer_bytes(&restored, name, dir.path()).await,
+                buffer_bytes(&source, name, dir.path()).await
+            );
+        }
+        assert_eq!(
+            serde_json::to_value(restored.get_trajectories(10).await).unwrap(),
+            serde_json::to_value(source.get_trajectories(10).await).unwrap()
+        );
+        assert_eq!(checkpoint_json(&restored).await, checkpoint_json(&source).await);
+        assert_eq!(
+            serde_json::to_value(restored_injector.export_state().await).unwrap(),
+            serde_json::to_value(source_injector.export_state().await).unwrap()
+        );
+        assert_eq!(restored_injector.export_state().await.reward_model.updates(), 1);
+
+        // Snapshots from a newer build are refused before anything changes
+        let mut newer = manifest.clone();
+        newer.format_version += 1;
+        std::fs::write(snapshot.join(MANIFEST_FILE), serde_json::to_string(&newer).unwrap()).unwrap();
+        let fresh = RLMemoryStore::new(dir.path().join("fresh"));
+        fresh.init().await.unwrap();
+        let err = format!("{:#}", restore_snapshot(&fresh, &injector(dir.path()), &snapshot).await.unwrap_err());
+        assert!(err.contains("reads up to version 1"), "{}", err);
+        assert!(fresh.get_trajectories(10).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_store_load_failure_leaves_injector_untouched() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = RLMemoryStore::new(dir.path().join("source"));
+        source.init().await.unwrap();
+        let buffer = source.get_replay_buffer("ppo", None).await;
+        for i in 0..4 {
+            buffer.add(experience(i)).await.unwrap();
+        }
+        let source_injector = injector(&dir.path().join("source"));
+        let mut state = source_injector.export_state().await;
+        let features = vec![0.5; state.reward_model.num_features()];
+        state.reward_model.update(2, &features, 1.0);
+        source_injector.import_state(state).await.unwrap();
+
+        let snapshot = dir.path().join("snapshot");
+        create_snapshot(&source, &source_injector, &snapshot).await.unwrap();
+        std::fs::write(snapshot.join(STORE_DIR).join("ppo_replay.bin.gz"), b"not a replay buffer").unwrap();
+
+        let restored_dir = dir.path().join("restored");
+        let restored = RLMemoryStore::new(restored_dir.clone());
+        restored.init().await.unwrap();
+        let restored_injector = injector(&restored_dir);
+        assert!(restore_snapshot(&restored, &restored_injector, &snapshot).await.is_err());
+
+        assert_eq!(restored_injector.export_state().await.reward_model.updates(), 0);
+        assert!(!restored_dir.join("injector_state.json").exists());
+    }
+}
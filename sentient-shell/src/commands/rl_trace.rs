@@ -1,8 +1,11 @@
 use anyhow::{Result, Context};
 use clap::{Arg, ArgMatches, Command};
 use colored::*;
+use sentient_rl_core::Trajectory;
+use std::path::Path;
 
 use crate::rag_tool_fusion::TraceLogger;
+use crate::rl_training::EPISODE_TRACE_DIR;
 
 pub fn cli() -> Command {
     Command::new("rl")
@@ -34,6 +37,31 @@ pub fn cli() -> Command {
                     Command::new("worst")
                         .about("Show worst performing model/tool combinations")
                 )
+                .subcommand(
+                    Command::new("export")
+                        .about("Export one episode step by step as CSV")
+                        .arg(
+                            Arg::new("episode")
+                                .long("episode")
+                                .help("Training episode number to export")
+                                .value_name("ID")
+                                .required(true)
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .help("Output file path (defaults to stdout)")
+                                .value_name("PATH")
+                        )
+                        .arg(
+                            Arg::new("dir")
+                                .long("dir")
+                                .help("Directory holding recorded episodes")
+                                .value_name("DIR")
+                                .default_value(EPISODE_TRACE_DIR)
+                        )
+                )
         )
         .subcommand(
             Command::new("infer")
@@ -74,6 +102,66 @@ pub fn cli() -> Command {
                         .help("Skip evaluation after training")
                 )
         )
+        .subcommand(
+            Command::new("snapshot")
+                .about("Snapshot or restore all RL state")
+                .subcommand(
+                    Command::new("create")
+                        .about("Bundle replay buffers, trajectories, checkpoints and injector state")
+                        .arg(
+                            Arg::new("dir")
+                                .help("Empty or new directory to write the snapshot to")
+                                .required(true)
+                                .index(1)
+                        )
+                )
+                .subcommand(
+                    Command::new("restore")
+                        .about("Restore a snapshot into this system")
+                        .arg(
+                            Arg::new("dir")
+                                .help("Snapshot directory")
+                                .required(true)
+                                .index(1)
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("eval-trace")
+                .about("Score a policy checkpoint against the actions recorded in a trace")
+                .arg(
+                    Arg::new("checkpoint")
+                        .long("checkpoint")
+                        .help("Checkpoint ID, \"latest\" or \"best\"")
+                        .value_name("ID")
+                        .default_value("latest")
+                )
+                .arg(
+                    Arg::new("trace")
+                        .long("trace")
+                        .help("JSONL trace file to replay")
+                        .value_name("FILE")
+                        .required(true)
+                )
+        )
+        .subcommand(
+            Command::new("smoke")
+                .about("Play random episodes of an environment to check it is wired correctly")
+                .arg(
+                    Arg::new("env")
+                        .long("env")
+                        .help("Registered environment name, e.g. cartpole")
+                        .value_name("NAME")
+                        .required(true)
+                )
+                .arg(
+                    Arg::new("episodes")
+                        .long("episodes")
+                        .help("Number of episodes to play")
+                        .value_name("N")
+                        .default_value("10")
+                )
+        )
         .subcommand(
             Command::new("export")
                 .about("Export traces for external analysis")
@@ -103,6 +191,15 @@ pub async fn execute(matches: &ArgMatches) -> Result<()> {
         Some(("export", export_matches)) => handle_export_command(export_matches).await,
         Some(("infer", infer_matches)) => handle_infer_command(infer_matches).await,
         Some(("retrain", retrain_matches)) => handle_retrain_command(retrain_matches).await,
+        Some(("snapshot", snapshot_matches)) => {
+            crate::commands::rl_snapshot::handle_snapshot_command(snapshot_matches).await
+        }
+        Some(("eval-trace", eval_matches)) => {
+            crate::commands::rl_eval::handle_eval_trace_command(eval_matches).await
+        }
+        Some(("smoke", smoke_matches)) => {
+            crate::commands::rl_smoke::handle_smoke_command(smoke_matches).await
+        }
         _ => {
             println!("Use 'rl trace summary' to see trace statistics");
             Ok(())
@@ -111,6 +208,10 @@ pub async fn execute(matches: &ArgMatches) -> Result<()> {
 }
 
 async fn handle_trace_command(matches: &ArgMatches) -> Result<()> {
+    if let Some(("export", export_matches)) = matches.subcommand() {
+        return export_episode(export_matches).await;
+    }
+    
     let trace_logger = TraceLogger::new("logs/rl_trace.jsonl").await?;
     
     match matches.subcommand() {
@@ -395,6 +496,35 @@ async fn handle_export_command(matches: &ArgMatches) -> Result<()> {
     Ok(())
 }
 
+/// Trajectory with observations, actions and states kept as raw JSON, so any
+/// recorded environment can be exported
+type RecordedTrajectory = Trajectory<serde_json::Value, serde_json::Value, serde_json::Value>;
+
+async fn export_episode(matches: &ArgMatches) -> Result<()> {
+    let episode = matches.get_one::<String>("episode").unwrap();
+    let dir = matches.get_one::<String>("dir").unwrap();
+    let path = Path::new(dir).join(format!("{}.json", episode));
+    
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .with_context(|| format!("No recorded episode '{}' at {}", episode, path.display()))?;
+    let trajectory: RecordedTrajectory = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    
+    let mut csv = Vec::new();
+    trajectory.to_csv(&mut csv)?;
+    
+    match matches.get_one::<String>("output") {
+        Some(output_path) => {
+            tokio::fs::write(output_path, csv).await?;
+            println!("✅ Exported {} steps of episode {} to {}", trajectory.len(), episode, output_path);
+        }
+        None => print!("{}", String::from_utf8_lossy(&csv)),
+    }
+    
+    Ok(())
+}
+
 async fn handle_infer_command(matches: &ArgMatches) -> Result<()> {
     let prompt = matches.get_one::<String>("prompt")
         .ok_or_else(|| anyhow::anyhow!("Prompt is required"))?;
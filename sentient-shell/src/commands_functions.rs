@@ -1,12 +1,24 @@
-use crate::ai::AiClient;
+use crate::ai::{AiClient, ImageGenerationOptions, ImageProgress};
+use crate::fallback::{FallbackChain, TextBackend};
+use crate::llm::tool_loop::{Conversation, ToolLoop};
+use crate::ai_router::registry::get_model_registry;
+use crate::ai_router::router::AIRouter;
+use crate::ai_router::{InferenceRequest, InferenceResponse, ModelCapability};
+use crate::output::{CommandOutput, ModelsReport, OutputFormat, StatusReport};
 use crate::package;
 use anyhow::Result;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub fn show_help() {
     println!("SentientShell Commands:");
     println!("  help       - Show this help message");
     println!("  status     - Show system status and connected AI models");
     println!("  ask <prompt> - Query AI model with a prompt");
+    println!("    --model <id>       Send to a specific model");
+    println!("    --capability <cap> Route by capability (text, code, qa, ...)");
     println!("  models     - List available AI models");
     println!("  image <prompt> - Generate image from prompt");
     println!("  pkg        - Package management commands");
@@ -18,9 +30,14 @@ pub fn show_help() {
     println!("  rag_tool   - Hybrid RAG + Tool fusion with intelligent routing");
     println!("  rl         - Reinforcement learning trace analysis");
     println!("  rl infer   - Test RL policy inference on a prompt");
+    println!("  rl trace export --episode <id> - Write one episode's steps as CSV");
     println!("  sentient goal - Execute autonomous goal-driven tasks");
+    println!("  selftest   - Check router, RAG, replay store and checkpoints end to end");
+    println!("    --skip <a,b>       Skip components (router, rag, replay, checkpoint)");
     println!("  exit       - Exit the shell");
     println!();
+    println!("Append --json to status, models, service list or selftest for machine-readable output.");
+    println!();
     println!("Package Commands:");
     println!("  pkg list       - List available packages");
     println!("  pkg installed  - List installed packages");
@@ -30,66 +47,82 @@ pub fn show_help() {
     println!();
     println!("Examples:");
     println!("  ask What is the meaning of life?");
+    println!("  ask --capability code Write a quicksort in Rust");
     println!("  image A beautiful sunset over mountains");
     println!("  pkg install calc");
     println!("  calc 2 + 2");
 }
 
 pub fn show_status(ai_client: &AiClient) -> Result<()> {
-    println!("System Status:");
-    println!("  Shell Version: {}", crate::SHELL_VERSION);
-    println!("  Ollama Server: {}", ai_client.ollama_url());
-    println!("  SD Server: {}", ai_client.sd_url());
-
-    // Check connectivity
-    print!("  Ollama Status: ");
-    match ai_client.check_ollama_connection() {
-        Ok(true) => println!("Connected ✓"),
-        Ok(false) => println!("Not reachable ✗"),
-        Err(e) => println!("Error: {}", e),
-    }
+    print!("{}", OutputFormat::Human.render(&CommandOutput::Status(collect_status(ai_client))));
+    Ok(())
+}
+
+/// Probe configured AI backends and collect a status report
+pub fn collect_status(ai_client: &AiClient) -> StatusReport {
+    let (preferred_model, preferred_model_error) = match ai_client.get_preferred_model() {
+        Ok(model) => (model, None),
+        Err(e) => (None, Some(e.to_string())),
+    };
 
-    print!("  SD Status: ");
-    match ai_client.check_sd_connection() {
-        Ok(true) => println!("Connected ✓"),
-        Ok(false) => println!("Not reachable ✗"),
-        Err(e) => println!("Error: {}", e),
+    StatusReport {
+        shell_version: crate::SHELL_VERSION.to_string(),
+        ollama_url: ai_client.ollama_url().to_string(),
+        sd_url: ai_client.sd_url().to_string(),
+        ollama_status: ai_client.check_ollama_connection().into(),
+        sd_status: ai_client.check_sd_connection().into(),
+        preferred_model,
+        preferred_model_error,
     }
+}
+
+/// The remote model with the local fallback chain behind it, as one backend
+/// for the tool loop
+struct AskBackend<'a> {
+    remote: &'a mut AiClient,
+    fallback: &'a mut FallbackChain,
+    /// Local backend that produced the last answer, if the remote failed
+    fell_back_to: Option<String>,
+}
 
-    // Show preferred model
-    match ai_client.get_preferred_model() {
-        Ok(Some(model)) => println!("  Preferred Model: {}", model),
-        Ok(None) => println!("  Preferred Model: None available"),
-        Err(e) => println!("  Preferred Model: Error - {}", e),
+impl TextBackend for AskBackend<'_> {
+    fn name(&self) -> &str {
+        "ask"
     }
 
-    Ok(())
+    fn generate(&mut self, prompt: &str) -> Result<String> {
+        let answer = self.fallback.ask(self.remote, prompt)?;
+        self.fell_back_to = (!answer.failures.is_empty()).then_some(answer.backend);
+        Ok(answer.text)
+    }
 }
 
-pub fn ask_ai(ai_client: &mut AiClient, prompt: &str) -> Result<()> {
+pub fn ask_ai(ai_client: &mut AiClient, fallback: &mut FallbackChain, prompt: &str) -> Result<()> {
     println!("Thinking...");
 
-    match ai_client.generate_text(prompt) {
-        Ok(response) => {
+    let mut backend = AskBackend {
+        remote: ai_client,
+        fallback,
+        fell_back_to: None,
+    };
+    let tools = crate::shell::tools::ToolHandler::new();
+    let mut conversation = Conversation::from_user(prompt);
+
+    // Tool calls in a reply are run and their results sent back to the model
+    match ToolLoop::from_env().run(&mut backend, &tools, &mut conversation) {
+        Ok(outcome) if outcome.loop_detected => {
+            println!("\n⚠️  {}", outcome.answer);
+        }
+        Ok(outcome) => {
             println!("\nResponse:");
-            println!("{}", response);
-            
-            // Process response for tool calls
-            if let Err(e) = crate::shell::tools::process_ai_response_for_tools(&response) {
-                log::warn!("Failed to process tool calls: {}", e);
+            println!("{}", outcome.answer);
+            if let Some(name) = &backend.fell_back_to {
+                println!("\n[answered by {} backend; remote Ollama unavailable]", name);
             }
         }
         Err(e) => {
             println!("Failed to get AI response: {}", e);
 
-            // Try local inference if available
-            #[cfg(feature = "local-inference")]
-            {
-                println!("Attempting local inference fallback...");
-                // Note: In production, we'd pass the local_inference instance from ShellState
-                println!("Local inference is available but not connected in this context");
-            }
-
             // If all else fails, provide a demo response
             println!("\n[Demo Mode] Query: '{}'", prompt);
             println!("AI services are not available. In a production environment,");
@@ -100,58 +133,134 @@ pub fn ask_ai(ai_client: &mut AiClient, prompt: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn list_models(ai_client: &AiClient) -> Result<()> {
-    println!("Available Models:");
-
-    // List Ollama models
-    println!("\nOllama Models:");
-    match ai_client.list_ollama_models() {
-        Ok(models) => {
-            if models.is_empty() {
-                println!("  No models found");
-            } else {
-                for model in models {
-                    println!("  - {}", model);
-                }
-            }
-        }
-        Err(e) => {
-            println!("  Error listing models: {}", e);
-        }
+/// Options accepted by the `ask` command
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AskOptions {
+    /// Explicit model (`provider:model_id` or bare model id)
+    pub model: Option<String>,
+    /// Explicit capability, bypassing intent detection
+    pub capability: Option<ModelCapability>,
+    pub prompt: String,
+}
+
+impl AskOptions {
+    /// Whether the caller asked to bypass default routing
+    pub fn is_explicit(&self) -> bool {
+        self.model.is_some() || self.capability.is_some()
     }
+}
 
-    // List SD models
-    println!("\nStable Diffusion Models:");
-    match ai_client.list_sd_models() {
-        Ok(models) => {
-            if models.is_empty() {
-                println!("  No models found");
-            } else {
-                for model in models {
-                    println!("  - {}", model);
-                }
+/// Parse `ask [--model <id>] [--capability <cap>] <prompt>`
+pub fn parse_ask_args(args: &[&str]) -> Result<AskOptions> {
+    let mut options = AskOptions::default();
+    let mut prompt = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match *arg {
+            "--model" | "-m" => {
+                let model = iter
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--model requires a model id"))?;
+                options.model = Some(model.to_string());
             }
-        }
-        Err(e) => {
-            println!("  Error listing models: {}", e);
+            "--capability" => {
+                let cap = iter
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("--capability requires a value"))?;
+                options.capability = Some(cap.parse()?);
+            }
+            _ => prompt.push(*arg),
         }
     }
 
+    if prompt.is_empty() {
+        anyhow::bail!("Usage: ask [--model <id>] [--capability <cap>] <prompt>");
+    }
+    options.prompt = prompt.join(" ");
+    Ok(options)
+}
+
+/// Route an `ask` directly to the requested model or capability
+pub fn ask_explicit(options: &AskOptions) -> Result<InferenceResponse> {
+    let capability = match (&options.capability, &options.model) {
+        (Some(cap), _) => cap.clone(),
+        (None, Some(model)) => get_model_registry()
+            .find_endpoint(model)
+            .and_then(|e| e.capabilities.first().cloned())
+            .unwrap_or(ModelCapability::TextGeneration),
+        (None, None) => ModelCapability::TextGeneration,
+    };
+
+    let request = InferenceRequest {
+        prompt: options.prompt.clone(),
+        capability,
+        max_tokens: None,
+        temperature: None,
+        system_prompt: None,
+        metadata: HashMap::new(),
+    };
+
+    match &options.model {
+        Some(model) => AIRouter::route_to_named_model(model, &request),
+        None => AIRouter::route_request(&request),
+    }
+}
+
+pub fn list_models(ai_client: &AiClient) -> Result<()> {
+    print!("{}", OutputFormat::Human.render(&CommandOutput::Models(collect_models(ai_client))));
     Ok(())
 }
 
-pub fn generate_image(ai_client: &mut AiClient, prompt: &str) -> Result<()> {
-    println!("Generating image...");
+/// Collect the models available on the Ollama and SD backends
+pub fn collect_models(ai_client: &AiClient) -> ModelsReport {
+    let (ollama_models, ollama_error) = match ai_client.list_ollama_models() {
+        Ok(models) => (models, None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+    let (sd_models, sd_error) = match ai_client.list_sd_models() {
+        Ok(models) => (models, None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
 
-    match ai_client.generate_image(prompt) {
+    ModelsReport {
+        ollama_models,
+        ollama_error,
+        sd_models,
+        sd_error,
+    }
+}
+
+/// Set by the SIGINT handler while an image generation is in flight
+static IMAGE_CANCEL: AtomicBool = AtomicBool::new(false);
+
+pub fn generate_image(ai_client: &mut AiClient, prompt: &str) -> Result<Option<PathBuf>> {
+    println!("Generating image... (Ctrl-C to cancel)");
+
+    IMAGE_CANCEL.store(false, Ordering::SeqCst);
+    let _guard = CancelOnInterrupt::install();
+
+    let options = ImageGenerationOptions::default();
+    let result = ai_client.generate_image_with_progress(prompt, &options, &IMAGE_CANCEL, |p| {
+        print!("\r{}", render_progress_bar(p));
+        let _ = io::stdout().flush();
+    });
+    println!();
+
+    match result {
         Ok(image_info) => {
             println!("\nImage generated successfully!");
             println!("  Prompt: {}", prompt);
             println!("  Hash: {}", image_info.hash);
             println!("  Size: {} bytes", image_info.size);
-
-            // In a real implementation, we might save the image or display a preview
-            // For now, we just show the info
+            if let Some(path) = &image_info.path {
+                println!("  Saved to: {}", path.display());
+            }
+            Ok(image_info.path)
+        }
+        Err(e) if IMAGE_CANCEL.load(Ordering::SeqCst) => {
+            println!("{}", e);
+            Ok(None)
         }
         Err(e) => {
             println!("Failed to generate image: {}", e);
@@ -161,10 +270,60 @@ pub fn generate_image(ai_client: &mut AiClient, prompt: &str) -> Result<()> {
             println!("SD WebUI is not available. In a production environment,");
             println!("this would connect to Stable Diffusion at http://192.168.69.197:7860");
             println!("Demo hash: a1b2c3d4e5f6789...");
+            Ok(None)
         }
     }
+}
 
-    Ok(())
+/// Render a single-line progress bar such as `[#####.....]  50% ETA 3s`
+pub fn render_progress_bar(progress: &ImageProgress) -> String {
+    const WIDTH: usize = 30;
+    let fraction = progress.progress.clamp(0.0, 1.0);
+    let filled = (fraction * WIDTH as f32).round() as usize;
+    format!(
+        "[{}{}] {:>3}% ETA {:.0}s",
+        "#".repeat(filled),
+        ".".repeat(WIDTH - filled),
+        (fraction * 100.0).round() as u32,
+        progress.eta_relative.max(0.0)
+    )
+}
+
+/// Routes SIGINT to `IMAGE_CANCEL` for its lifetime, restoring the previous handler on drop
+struct CancelOnInterrupt {
+    #[cfg(unix)]
+    previous: Option<nix::sys::signal::SigAction>,
+}
+
+impl CancelOnInterrupt {
+    #[cfg(unix)]
+    fn install() -> Self {
+        use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+
+        extern "C" fn on_sigint(_: libc::c_int) {
+            IMAGE_CANCEL.store(true, Ordering::SeqCst);
+        }
+
+        let action = SigAction::new(SigHandler::Handler(on_sigint), SaFlags::empty(), SigSet::empty());
+        // SAFETY: the handler only performs an atomic store, which is async-signal-safe
+        let previous = unsafe { sigaction(Signal::SIGINT, &action) }.ok();
+        Self { previous }
+    }
+
+    #[cfg(not(unix))]
+    fn install() -> Self {
+        Self {}
+    }
+}
+
+impl Drop for CancelOnInterrupt {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(previous) = self.previous.take() {
+            // SAFETY: restoring the handler that was installed before us
+            let _ = unsafe { nix::sys::signal::sigaction(nix::sys::signal::Signal::SIGINT, &previous) };
+        }
+    }
 }
 
 pub fn pkg_usage() {
@@ -212,28 +371,19 @@ pub fn handle_pkg_command(
         }
         "install" => {
             if args.len() < 2 {
-                println!("Usage: pkg install <name>");
-                return Ok(());
-            }
-            match registry.install(args[1]) {
-                Ok(msg) => println!("{}", msg),
-                Err(e) => println!("Error: {}", e),
+                anyhow::bail!("Usage: pkg install <name>");
             }
+            println!("{}", registry.install(args[1])?);
         }
         "uninstall" => {
             if args.len() < 2 {
-                println!("Usage: pkg uninstall <name>");
-                return Ok(());
-            }
-            match registry.uninstall(args[1]) {
-                Ok(msg) => println!("{}", msg),
-                Err(e) => println!("Error: {}", e),
+                anyhow::bail!("Usage: pkg uninstall <name>");
             }
+            println!("{}", registry.uninstall(args[1])?);
         }
         "search" => {
             if args.len() < 2 {
-                println!("Usage: pkg search <query>");
-                return Ok(());
+                anyhow::bail!("Usage: pkg search <query>");
             }
             let query = args[1..].join(" ");
             let results = registry.search(&query);
@@ -247,10 +397,7 @@ pub fn handle_pkg_command(
                 }
             }
         }
-        _ => {
-            println!("Unknown subcommand: {}", args[0]);
-            pkg_usage();
-        }
+        _ => anyhow::bail!("Unknown pkg subcommand: {}. Try 'pkg'", args[0]),
     }
 
     Ok(())
@@ -260,15 +407,10 @@ pub fn run_package(ai_client: &mut AiClient, package_name: &str, args: &[&str])
     match package_name {
         "calc" => {
             if args.is_empty() {
-                println!("Usage: calc <expression>");
-                println!("Example: calc 2 + 2");
-                return Ok(());
+                anyhow::bail!("Usage: calc <expression> (e.g. calc 2 + 2)");
             }
             let expression = args.join(" ");
-            match package::core::calc::run(&expression) {
-                Ok(result) => println!("{}", result),
-                Err(e) => println!("Error: {}", e),
-            }
+            println!("{}", package::core::calc::run(&expression)?);
         }
         "neofetch" => {
             println!("{}", package::core::neofetch::run());
@@ -285,57 +427,31 @@ pub fn run_package(ai_client: &mut AiClient, package_name: &str, args: &[&str])
             }
         }
         "todo" => {
-            match package::core::todo::run(args) {
-                Ok(output) => println!("{}", output),
-                Err(e) => println!("Error: {}", e),
-            }
+            println!("{}", package::core::todo::run(args)?);
         }
         "ask" => {
             let runtime = tokio::runtime::Runtime::new()?;
-            match runtime.block_on(package::core::ask::run(ai_client, args)) {
-                Ok(response) => println!("{}", response),
-                Err(e) => println!("Error: {}", e),
-            }
+            println!("{}", runtime.block_on(package::core::ask::run(ai_client, args))?);
         }
         "timer" => {
-            match package::core::timer::run(args) {
-                Ok(output) => println!("{}", output),
-                Err(e) => println!("Error: {}", e),
-            }
+            println!("{}", package::core::timer::run(args)?);
         }
         "scratch" => {
-            match package::core::scratch::run(args) {
-                Ok(output) => println!("{}", output),
-                Err(e) => println!("Error: {}", e),
-            }
+            println!("{}", package::core::scratch::run(args)?);
         }
         "df" => {
-            match package::core::df::run(args) {
-                Ok(output) => println!("{}", output),
-                Err(e) => println!("Error: {}", e),
-            }
+            println!("{}", package::core::df::run(args)?);
         }
         "top" => {
-            match package::core::top::run(args) {
-                Ok(output) => println!("{}", output),
-                Err(e) => println!("Error: {}", e),
-            }
+            println!("{}", package::core::top::run(args)?);
         }
         "ps" => {
-            match package::core::ps::run(args) {
-                Ok(output) => println!("{}", output),
-                Err(e) => println!("Error: {}", e),
-            }
+            println!("{}", package::core::ps::run(args)?);
         }
         "hivefix" => {
-            match package::core::hivefix::run(args) {
-                Ok(output) => println!("{}", output),
-                Err(e) => println!("Error: {}", e),
-            }
-        }
-        _ => {
-            println!("Package '{}' not found or not executable", package_name);
+            println!("{}", package::core::hivefix::run(args)?);
         }
+        _ => anyhow::bail!("Package '{}' not found or not executable", package_name),
     }
     Ok(())
 }
@@ -0,0 +1,134 @@
+//! Shell-level fallback chain for text generation
+//!
+//! When the remote Ollama server cannot answer, the shell walks an ordered
+//! list of local backends (local ONNX inference, then the boot model), in the
+//! same spirit as the router's `offline_chain`.
+
+use crate::ai::AiClient;
+#[cfg(feature = "local-inference")]
+use crate::inference::LocalInference;
+use anyhow::Result;
+
+/// A backend able to answer a text prompt
+pub trait TextBackend {
+    fn name(&self) -> &str;
+    fn generate(&mut self, prompt: &str) -> Result<String>;
+}
+
+/// Answer produced by the chain, tagged with the backend that produced it
+#[derive(Debug, Clone)]
+pub struct FallbackAnswer {
+    pub backend: String,
+    pub text: String,
+    /// Backends that were tried first and failed, with their errors
+    pub failures: Vec<(String, String)>,
+}
+
+/// Local ONNX inference backend
+#[cfg(feature = "local-inference")]
+pub struct LocalInferenceBackend {
+    inference: LocalInference,
+}
+
+#[cfg(feature = "local-inference")]
+impl LocalInferenceBackend {
+    pub fn new(inference: LocalInference) -> Self {
+        Self { inference }
+    }
+}
+
+#[cfg(feature = "local-inference")]
+impl TextBackend for LocalInferenceBackend {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn generate(&mut self, prompt: &str) -> Result<String> {
+        self.inference.infer(prompt)
+    }
+}
+
+/// Kernel boot model backend
+pub struct BootLlmBackend;
+
+impl TextBackend for BootLlmBackend {
+    fn name(&self) -> &str {
+        "boot"
+    }
+
+    fn generate(&mut self, prompt: &str) -> Result<String> {
+        let boot_llm = crate::boot_llm::BOOT_LLM.lock().unwrap();
+        if !boot_llm.available {
+            anyhow::bail!("Boot model not available");
+        }
+        boot_llm.evaluate(prompt)
+    }
+}
+
+/// Ordered list of local backends tried after the remote server fails
+#[derive(Default)]
+pub struct FallbackChain {
+    backends: Vec<Box<dyn TextBackend>>,
+}
+
+impl FallbackChain {
+    pub fn new(backends: Vec<Box<dyn TextBackend>>) -> Self {
+        Self { backends }
+    }
+
+    /// Default chain: local inference (if it loaded), then the boot model
+    pub fn local_default() -> Self {
+        let mut backends: Vec<Box<dyn TextBackend>> = Vec::new();
+        #[cfg(feature = "local-inference")]
+        if let Ok(inference) = LocalInference::new() {
+            backends.push(Box::new(LocalInferenceBackend::new(inference)));
+        }
+        backends.push(Box::new(BootLlmBackend));
+        Self { backends }
+    }
+
+    pub fn backend_names(&self) -> Vec<&str> {
+        self.backends.iter().map(|b| b.name()).collect()
+    }
+
+    /// Ask the remote server first, then each local backend in order
+    pub fn ask(&mut self, remote: &mut AiClient, prompt: &str) -> Result<FallbackAnswer> {
+        let mut failures = Vec::new();
+
+        match remote.generate_text(prompt) {
+            Ok(text) => {
+                return Ok(FallbackAnswer {
+                    backend: "ollama".to_string(),
+                    text,
+                    failures,
+                })
+            }
+            Err(e) => {
+                log::warn!("Remote Ollama failed, trying local backends: {}", e);
+                failures.push(("ollama".to_string(), e.to_string()));
+            }
+        }
+
+        for backend in self.backends.iter_mut() {
+            match backend.generate(prompt) {
+                Ok(text) => {
+                    return Ok(FallbackAnswer {
+                        backend: backend.name().to_string(),
+                        text,
+                        failures,
+                    })
+                }
+                Err(e) => {
+                    log::debug!("Fallback backend {} failed: {}", backend.name(), e);
+                    failures.push((backend.name().to_string(), e.to_string()));
+                }
+            }
+        }
+
+        let summary: Vec<String> = failures
+            .iter()
+            .map(|(name, err)| format!("{}: {}", name, err))
+            .collect();
+        anyhow::bail!("All backends failed ({})", summary.join("; "))
+    }
+}
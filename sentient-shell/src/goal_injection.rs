@@ -0,0 +1,136 @@
+// Shared writer for the goal injection queue
+// Every component that injects goals (CLI, web UI, policy injector,
+// services) appends to the same JSONL file, possibly at the same time
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Name of the goal injection queue inside a logs directory
+pub const GOAL_INJECTIONS_FILE: &str = "goal_injections.jsonl";
+
+/// Path of the goal injection queue in `logs_dir`
+pub fn injection_file(logs_dir: &Path) -> PathBuf {
+    logs_dir.join(GOAL_INJECTIONS_FILE)
+}
+
+/// Append `record` to the goal injection queue in `logs_dir`
+pub fn append_goal_injection(logs_dir: &Path, record: &impl Serialize) -> Result<()> {
+    append_jsonl(&injection_file(logs_dir), record)
+}
+
+/// [`append_goal_injection`] off the async runtime's worker threads
+pub async fn append_goal_injection_async(logs_dir: &Path, record: &impl Serialize) -> Result<()> {
+    let line = to_line(record)?;
+    let path = injection_file(logs_dir);
+    tokio::task::spawn_blocking(move || append_line(&path, &line)).await?
+}
+
+/// Append `record` to the JSONL file at `path` as one complete line.
+///
+/// The line is written with a single append while holding an exclusive lock
+/// on the file, so concurrent appenders in this or any other process that
+/// locks the file never interleave or leave partial lines.
+pub fn append_jsonl(path: &Path, record: &impl Serialize) -> Result<()> {
+    append_line(path, &to_line(record)?)
+}
+
+/// Open `path` for reading and writing under an exclusive lock, creating
+/// it if needed. For rewriting a queue without losing concurrent appends;
+/// the lock is released when the file is dropped.
+pub fn open_locked(path: &Path) -> Result<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    file.lock()
+        .with_context(|| format!("Failed to lock {:?}", path))?;
+    Ok(file)
+}
+
+fn to_line(record: &impl Serialize) -> Result<Vec<u8>> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+fn append_line(path: &Path, line: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {:?}", path))?;
+    file.lock()
+        .with_context(|| format!("Failed to lock {:?}", path))?;
+    file.write_all(line)
+        .with_context(|| format!("Failed to append to {:?}", path))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::{Arc, Barrier};
+
+    #[test]
+    fn test_concurrent_appends_never_interleave() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let logs_dir = dir.path().join("logs");
+        // Large records make a torn write far more likely without the lock
+        let padding = "x".repeat(64 * 1024);
+        let writers = 16;
+        let records = 20;
+
+        // Some writers append directly, others through the async path
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let start = Arc::new(Barrier::new(writers));
+        let handles: Vec<_> = (0..writers)
+            .map(|writer| {
+                let (logs_dir, padding, start) = (logs_dir.clone(), padding.clone(), start.clone());
+                let handle = runtime.handle().clone();
+                std::thread::spawn(move || {
+                    start.wait();
+                    for i in 0..records {
+                        let record =
+                            json!({"goal": format!("goal {}-{}", writer, i), "padding": padding});
+                        if writer % 4 == 0 {
+                            handle
+                                .block_on(append_goal_injection_async(&logs_dir, &record))
+                                .unwrap();
+                        } else {
+                            append_goal_injection(&logs_dir, &record).unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let content = std::fs::read_to_string(injection_file(&logs_dir)).unwrap();
+        assert!(content.ends_with('\n'));
+        let mut goals: Vec<String> = content
+            .lines()
+            .map(|line| {
+                let record: serde_json::Value = serde_json::from_str(line)
+                    .unwrap_or_else(|e| panic!("Interleaved line ({}): {:.80}", e, line));
+                assert_eq!(record["padding"].as_str().unwrap().len(), padding.len());
+                record["goal"].as_str().unwrap().to_string()
+            })
+            .collect();
+        assert_eq!(goals.len(), writers * records);
+        goals.sort();
+        goals.dedup();
+        assert_eq!(goals.len(), writers * records);
+    }
+}
@@ -1,5 +1,6 @@
 use super::*;
-use super::audit::{AuditLogger, AuditEventType};
+use super::audit::{AuditLogger, AuditEventType, AuditFilter, AuditRecord};
+use super::communicator::KnownFixes;
 use super::prompts::PromptTemplates;
 use super::rollback::RollbackManager;
 use super::sandbox_security::validate_patch_safety;
@@ -139,6 +140,23 @@ impl HiveFixAgent {
         self.fix_candidates.lock().unwrap().clone()
     }
 
+    /// Fix candidates generated so far, filed under their error's fingerprint
+    pub fn known_fixes(&self) -> KnownFixes {
+        let history = self.error_history.lock().unwrap();
+        let mut known = KnownFixes::default();
+        for fix in self.fix_candidates.lock().unwrap().values() {
+            if let Some(error) = history.iter().find(|error| error.id == fix.error_id) {
+                known.insert(error, &fix.id);
+            }
+        }
+        known
+    }
+
+    /// Audit history matching `filter`, oldest first
+    pub fn query_audit(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>> {
+        self.audit_logger.query(filter)
+    }
+
     pub fn apply_fix(&mut self, fix_id: &str) -> Result<()> {
         let candidates = self.fix_candidates.lock().unwrap();
         let fix = candidates.get(fix_id)
@@ -147,9 +165,9 @@ impl HiveFixAgent {
         *self.status.lock().unwrap() = HiveFixStatus::Applying(fix_id.to_string());
         
         // Apply the patch
-        match patch::apply_patch(&fix.patch) {
-            Ok(_) => {
-                info!("Successfully applied fix {}", fix_id);
+        match patch::apply_patch(&fix.patch, fix_id, &self.config.patch_root, &self.rollback_manager) {
+            Ok(snapshot_id) => {
+                info!("Successfully applied fix {} (snapshot {})", fix_id, snapshot_id);
                 Ok(())
             }
             Err(e) => {
@@ -195,6 +213,10 @@ fn analyze_error(ai_client: &mut AiClient, error: &ErrorEvent) -> Result<FixCand
         return Err(anyhow::anyhow!("Unsafe patch rejected"));
     }
     
+    // Only a unified diff can be checked against the files and applied
+    let patch = patch::Patch::parse(&response)
+        .context("AI response has no applicable patch")?;
+    
     // Parse AI response into fix candidate
     let description = response.lines()
         .find(|line| line.starts_with("FIX:") || line.starts_with("CAUSE:"))
@@ -206,7 +228,7 @@ fn analyze_error(ai_client: &mut AiClient, error: &ErrorEvent) -> Result<FixCand
         id: format!("fix_{}", error.id),
         error_id: error.id.clone(),
         description,
-        patch: response,
+        patch,
         confidence: 0.75,
         tested: false,
         test_result: None,
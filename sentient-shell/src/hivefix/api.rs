@@ -1,6 +1,8 @@
 use super::*;
-use anyhow::Result;
+use anyhow::{Result, bail};
+use chrono::{DateTime, NaiveDate, Utc};
 use crate::hivefix::agent::HiveFixAgent;
+use crate::hivefix::audit::{AuditFilter, AuditOutcome};
 
 // CLI API for hivefix commands
 pub fn handle_command(agent: &mut HiveFixAgent, args: &[&str]) -> Result<String> {
@@ -35,12 +37,14 @@ pub fn handle_command(agent: &mut HiveFixAgent, args: &[&str]) -> Result<String>
             }
             test_error_injection(agent, args[1])
         },
+        "coverage" => fault_coverage(agent),
         "trace" => {
             if args.len() < 2 {
                 return Ok("Usage: hivefix trace <fix-id>".to_string());
             }
             trace_fix_lifecycle(agent, args[1])
         },
+        "audit" => query_audit(agent, &args[1..]),
         "rollback" => {
             if args.len() < 2 {
                 return Ok("Usage: hivefix rollback <snapshot-id>".to_string());
@@ -63,7 +67,11 @@ Commands:
   hivefix disable      Disable the agent
   hivefix scan         Manually trigger error scan
   hivefix test <type>  Inject test error (package/panic/memory/config)
+  hivefix coverage     Dry-run every test error and report which ones have a known fix
   hivefix trace <id>   Trace fix lifecycle
+  hivefix audit [--since <time>] [--until <time>] [--source <source>] [--outcome success|failure]
+                       Query the audit log; times are RFC 3339 or YYYY-MM-DD,
+                       sources shell, kernel, system, user or package:<name>
   hivefix rollback <id> Rollback to snapshot
 
 The agent monitors system logs and automatically proposes fixes using AI."#.to_string()
@@ -159,11 +167,85 @@ fn test_error_injection(agent: &mut HiveFixAgent, error_type: &str) -> Result<St
     Ok(format!("Injected test error: {}", error.message))
 }
 
+fn fault_coverage(agent: &HiveFixAgent) -> Result<String> {
+    use crate::hivefix::error_injector::ErrorInjector;
+    
+    let report = ErrorInjector::dry_run(&ErrorInjector::standard_faults(), &agent.known_fixes())?;
+    Ok(report.to_string())
+}
+
 fn trace_fix_lifecycle(_agent: &HiveFixAgent, fix_id: &str) -> Result<String> {
     // In production, this would trace through audit logs
     Ok(format!("Trace for fix '{}' not yet implemented", fix_id))
 }
 
+fn query_audit(agent: &HiveFixAgent, args: &[&str]) -> Result<String> {
+    let filter = parse_audit_filter(args)?;
+    let records = agent.query_audit(&filter)?;
+    
+    if records.is_empty() {
+        return Ok("No matching audit records.".to_string());
+    }
+    
+    let mut output = format!("{} audit records:\n", records.len());
+    for record in &records {
+        output.push_str(&format!(
+            "  {} {:?} {}{}{}\n",
+            record.entry.timestamp.to_rfc3339(),
+            record.entry.event_type,
+            record.entry.event_id,
+            record.source.as_ref().map(|s| format!(" source={:?}", s)).unwrap_or_default(),
+            record.outcome.map(|o| format!(" outcome={:?}", o)).unwrap_or_default(),
+        ));
+    }
+    Ok(output)
+}
+
+fn parse_audit_filter(args: &[&str]) -> Result<AuditFilter> {
+    let mut filter = AuditFilter::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let Some(value) = args.next() else {
+            bail!("Missing value for {}", flag);
+        };
+        match *flag {
+            "--since" => filter.since = Some(parse_time(value)?),
+            "--until" => filter.until = Some(parse_time(value)?),
+            "--source" => filter.source = Some(parse_source(value)?),
+            "--outcome" => filter.outcome = Some(match *value {
+                "success" => AuditOutcome::Success,
+                "failure" => AuditOutcome::Failure,
+                other => bail!("Unknown outcome '{}': expected success or failure", other),
+            }),
+            other => bail!("Unknown audit option '{}'", other),
+        }
+    }
+    Ok(filter)
+}
+
+/// RFC 3339 timestamp, or a date meaning its midnight UTC
+fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Invalid time '{}': expected RFC 3339 or YYYY-MM-DD", value))?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+fn parse_source(value: &str) -> Result<ErrorSource> {
+    Ok(match value {
+        "shell" => ErrorSource::Shell,
+        "kernel" => ErrorSource::Kernel,
+        "system" => ErrorSource::System,
+        "user" => ErrorSource::User,
+        _ => match value.strip_prefix("package:") {
+            Some(name) => ErrorSource::Package(name.to_string()),
+            None => bail!("Unknown source '{}'", value),
+        },
+    })
+}
+
 fn rollback_fix(_agent: &HiveFixAgent, snapshot_id: &str) -> Result<String> {
     // In production, this would use the rollback manager
     Ok(format!("Rollback to snapshot '{}' not yet implemented", snapshot_id))
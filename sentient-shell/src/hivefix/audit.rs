@@ -4,6 +4,7 @@ use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 use std::fs::{self, File, OpenOptions};
 use std::io::{Write, BufWriter};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
@@ -28,9 +29,136 @@ pub enum AuditEventType {
     ConfigChanged,
 }
 
+/// An audit entry with the error source and outcome it is about, when
+/// known
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub entry: AuditEntry,
+    pub source: Option<ErrorSource>,
+    pub outcome: Option<AuditOutcome>,
+}
+
+/// How a tested or applied patch went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+/// Which records a query returns; unset fields match everything
+#[derive(Debug, Clone, Default)]
+pub struct AuditFilter {
+    /// Earliest timestamp, inclusive
+    pub since: Option<DateTime<Utc>>,
+    /// Latest timestamp, exclusive
+    pub until: Option<DateTime<Utc>>,
+    pub source: Option<ErrorSource>,
+    pub outcome: Option<AuditOutcome>,
+}
+
+impl AuditFilter {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        self.source.as_ref().is_none_or(|source| record.source.as_ref() == Some(source))
+            && self.outcome.is_none_or(|outcome| record.outcome == Some(outcome))
+    }
+}
+
+/// Audit records sorted by timestamp, so time ranges are found by binary
+/// search. Patch events are linked to the source of the error they fix.
+#[derive(Debug, Default)]
+pub struct AuditIndex {
+    records: Vec<AuditRecord>,
+    error_sources: HashMap<String, ErrorSource>,
+    fix_sources: HashMap<String, ErrorSource>,
+}
+
+impl AuditIndex {
+    /// Index every entry of the audit log at `path`; a missing log is empty
+    pub fn load(path: &Path) -> Result<Self> {
+        use std::io::{BufRead, BufReader};
+        
+        let mut index = Self::default();
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(index),
+            Err(e) => return Err(e).with_context(|| format!("Failed to open {}", path.display())),
+        };
+        for line in BufReader::new(file).lines() {
+            if let Ok(entry) = serde_json::from_str::<AuditEntry>(&line?) {
+                index.push(entry);
+            }
+        }
+        Ok(index)
+    }
+    
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+    
+    /// Add an entry, keeping the records in timestamp order
+    pub fn push(&mut self, entry: AuditEntry) {
+        let detail = |key: &str| entry.details.get(key).and_then(|v| v.as_str()).map(str::to_string);
+        let source = match entry.event_type {
+            AuditEventType::ErrorDetected => {
+                // `source` holds the Debug form, which only matches the
+                // serialized one for variants without data
+                let source = ["error_source", "source"].iter()
+                    .filter_map(|key| entry.details.get(*key))
+                    .find_map(|value| serde_json::from_value::<ErrorSource>(value.clone()).ok());
+                if let (Some(error_id), Some(source)) = (detail("error_id"), &source) {
+                    self.error_sources.insert(error_id, source.clone());
+                }
+                source
+            }
+            AuditEventType::PatchProposed => {
+                let source = detail("error_id").and_then(|id| self.error_sources.get(&id).cloned());
+                if let (Some(fix_id), Some(source)) = (detail("fix_id"), &source) {
+                    self.fix_sources.insert(fix_id, source.clone());
+                }
+                source
+            }
+            _ => detail("fix_id").and_then(|id| self.fix_sources.get(&id).cloned()),
+        };
+        let outcome = match entry.event_type {
+            AuditEventType::PatchTested | AuditEventType::PatchApplied | AuditEventType::PatchRejected => {
+                entry.details.get("success").and_then(|v| v.as_bool()).map(|success| {
+                    if success { AuditOutcome::Success } else { AuditOutcome::Failure }
+                })
+            }
+            _ => None,
+        };
+        
+        let at = self.records.partition_point(|r| r.entry.timestamp <= entry.timestamp);
+        self.records.insert(at, AuditRecord { entry, source, outcome });
+    }
+    
+    /// Records matching `filter`, oldest first
+    pub fn query(&self, filter: &AuditFilter) -> Vec<AuditRecord> {
+        let start = filter.since.map_or(0, |since| {
+            self.records.partition_point(|r| r.entry.timestamp < since)
+        });
+        let end = filter.until.map_or(self.records.len(), |until| {
+            self.records.partition_point(|r| r.entry.timestamp < until)
+        });
+        if start >= end {
+            return Vec::new();
+        }
+        self.records[start..end].iter()
+            .filter(|record| filter.matches(record))
+            .cloned()
+            .collect()
+    }
+}
+
 pub struct AuditLogger {
     log_path: PathBuf,
     writer: Mutex<Option<BufWriter<File>>>,
+    /// Loaded from the log on the first query, then kept current
+    index: Mutex<Option<AuditIndex>>,
 }
 
 impl AuditLogger {
@@ -43,6 +171,7 @@ impl AuditLogger {
         Ok(Self {
             log_path,
             writer: Mutex::new(None),
+            index: Mutex::new(None),
         })
     }
     
@@ -85,13 +214,27 @@ impl AuditLogger {
             w.flush()?;
         }
         
+        if let Some(index) = self.index.lock().unwrap().as_mut() {
+            index.push(entry.clone());
+        }
+        
         Ok(())
     }
     
+    /// Audit records matching `filter`, oldest first
+    pub fn query(&self, filter: &AuditFilter) -> Result<Vec<AuditRecord>> {
+        let mut index = self.index.lock().unwrap();
+        if index.is_none() {
+            *index = Some(AuditIndex::load(&self.log_path)?);
+        }
+        Ok(index.as_ref().map(|index| index.query(filter)).unwrap_or_default())
+    }
+    
     pub fn log_error_detected(&self, error: &ErrorEvent) -> Result<()> {
         let details = serde_json::json!({
             "error_id": error.id,
             "source": format!("{:?}", error.source),
+            "error_source": error.source,
             "message": error.message,
             "has_stack_trace": error.stack_trace.is_some(),
         });
@@ -117,7 +260,7 @@ impl AuditLogger {
             "error_id": fix.error_id,
             "description": fix.description,
             "confidence": fix.confidence,
-            "patch_hash": calculate_string_hash(&fix.patch),
+            "patch_hash": calculate_string_hash(&fix.patch.to_string()),
         });
         
         self.log_event(AuditEventType::PatchProposed, details)?;
@@ -210,4 +353,85 @@ pub fn trace_fix(audit_log_path: &Path, fix_id: &str) -> Result<Vec<AuditEntry>>
     }
     
     Ok(entries)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    fn entry(timestamp: DateTime<Utc>, event_type: AuditEventType, details: serde_json::Value) -> AuditEntry {
+        AuditEntry {
+            hash: calculate_hash(&event_type, &details),
+            event_id: format!("evt_{}", timestamp.timestamp()),
+            timestamp,
+            event_type,
+            details,
+        }
+    }
+
+    fn detected(timestamp: DateTime<Utc>, error_id: &str, source: ErrorSource) -> AuditEntry {
+        let details = serde_json::json!({
+            "error_id": error_id,
+            "source": format!("{:?}", source),
+            "error_source": source,
+        });
+        entry(timestamp, AuditEventType::ErrorDetected, details)
+    }
+
+    #[test]
+    fn test_query_by_time_range_and_source() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let logger = AuditLogger::new(dir.path().join("hivefix.log")).unwrap();
+        logger.init().unwrap();
+        let proposed = |fix_id: &str, error_id: &str| serde_json::json!({ "fix_id": fix_id, "error_id": error_id });
+        let outcome = |fix_id: &str, success: bool| serde_json::json!({ "fix_id": fix_id, "success": success });
+        let entries = vec![
+            detected(at(1, 9), "e1", ErrorSource::Shell),
+            entry(at(1, 10), AuditEventType::PatchProposed, proposed("fix_e1", "e1")),
+            entry(at(2, 9), AuditEventType::PatchTested, outcome("fix_e1", true)),
+            detected(at(2, 12), "e2", ErrorSource::Package("calc".to_string())),
+            entry(at(3, 8), AuditEventType::PatchProposed, proposed("fix_e2", "e2")),
+            entry(at(3, 9), AuditEventType::PatchApplied, outcome("fix_e1", true)),
+            detected(at(3, 23), "e3", ErrorSource::Shell),
+            entry(at(4, 0), AuditEventType::PatchRejected, outcome("fix_e2", false)),
+            detected(at(4, 0), "e4", ErrorSource::Shell),
+            detected(at(5, 9), "e5", ErrorSource::Shell),
+        ];
+        for entry in &entries {
+            logger.write_entry(entry).unwrap();
+        }
+
+        let filter = AuditFilter {
+            since: Some(at(2, 0)),
+            until: Some(at(4, 0)),
+            source: Some(ErrorSource::Shell),
+            outcome: None,
+        };
+        let found: Vec<DateTime<Utc>> = logger.query(&filter).unwrap()
+            .iter()
+            .map(|record| record.entry.timestamp)
+            .collect();
+        assert_eq!(found, vec![at(2, 9), at(3, 9), at(3, 23)]);
+
+        // Entries written after the index loaded are found too
+        logger.write_entry(&detected(at(3, 12), "e6", ErrorSource::Shell)).unwrap();
+        let records = logger.query(&AuditFilter { outcome: Some(AuditOutcome::Success), ..filter.clone() }).unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records.iter().all(|r| r.source == Some(ErrorSource::Shell)));
+        assert_eq!(logger.query(&filter).unwrap().len(), 4);
+
+        // The same records come back from the log file
+        let index = AuditIndex::load(&dir.path().join("hivefix.log")).unwrap();
+        assert_eq!(index.len(), entries.len() + 1);
+        let package = index.query(&AuditFilter {
+            source: Some(ErrorSource::Package("calc".to_string())),
+            ..Default::default()
+        });
+        assert_eq!(package.len(), 3);
+        assert_eq!(package[2].outcome, Some(AuditOutcome::Failure));
+    }
+}
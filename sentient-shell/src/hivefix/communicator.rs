@@ -1,6 +1,7 @@
 use super::*;
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::SystemTime;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,7 +46,7 @@ impl HiveCommunicator {
                 machine_id: self.machine_id.clone(),
                 error_fingerprint: fingerprint_error(error),
                 fix_description: fix.description.clone(),
-                patch_content: sanitize_patch(&fix.patch),
+                patch_content: sanitize_patch(&fix.patch.to_string()),
                 success_rate: fix.confidence,
                 test_results: fix.test_result.as_ref().map(|r| vec![r.clone()]).unwrap_or_default(),
             };
@@ -93,7 +94,7 @@ fn generate_machine_id() -> String {
     format!("hive_{:x}", hasher.finish())
 }
 
-fn fingerprint_error(error: &ErrorEvent) -> String {
+pub fn fingerprint_error(error: &ErrorEvent) -> String {
     // Create anonymized fingerprint of error
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
@@ -110,6 +111,35 @@ fn fingerprint_error(error: &ErrorEvent) -> String {
     format!("err_{:x}", hasher.finish())
 }
 
+/// Fixes on record, filed under the fingerprint of the error each fixes.
+/// An error is matched to a fix the way the hive matches them: by
+/// [`fingerprint_error`].
+#[derive(Debug, Clone, Default)]
+pub struct KnownFixes {
+    by_fingerprint: HashMap<String, Vec<String>>,
+}
+
+impl KnownFixes {
+    /// Fixes shared through the hive
+    pub fn from_deltas(deltas: &[HiveDelta]) -> Self {
+        let mut known = Self::default();
+        for delta in deltas {
+            known.by_fingerprint.entry(delta.error_fingerprint.clone()).or_default().push(delta.id.clone());
+        }
+        known
+    }
+
+    /// Record that fix `fix_id` was made for `error`
+    pub fn insert(&mut self, error: &ErrorEvent, fix_id: &str) {
+        self.by_fingerprint.entry(fingerprint_error(error)).or_default().push(fix_id.to_string());
+    }
+
+    /// Ids of the fixes `error` matches
+    pub fn for_error(&self, error: &ErrorEvent) -> &[String] {
+        self.by_fingerprint.get(&fingerprint_error(error)).map_or(&[], Vec::as_slice)
+    }
+}
+
 fn anonymize_message(msg: &str) -> String {
     // Remove specific values, keep structure
     msg.split_whitespace()
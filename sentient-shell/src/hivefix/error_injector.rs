@@ -1,6 +1,9 @@
 use super::*;
 use anyhow::Result;
 use crate::hivefix::agent::HiveFixAgent;
+use crate::hivefix::communicator::{fingerprint_error, KnownFixes};
+use serde::Serialize;
+use std::fmt;
 
 /// Test error scenarios for HiveFix validation
 #[derive(Debug, Clone)]
@@ -27,12 +30,120 @@ pub enum PackageError {
     CorruptedBinary,
 }
 
+/// Where a fault would be injected and the error HiveFix would see
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FaultSite {
+    /// Error class, e.g. `package:runtime_error`
+    pub class: String,
+    /// Package, file, config key or component the fault targets
+    pub target: String,
+    pub source: ErrorSource,
+    /// Message of the resulting error
+    pub signature: String,
+    /// Fingerprint the error is filed under
+    pub fingerprint: String,
+    /// Whether a known fix is filed under the fingerprint
+    pub recognized: bool,
+}
+
+/// Outcome of a dry run: every fault site, in the order given
+#[derive(Debug, Clone, Serialize)]
+pub struct CoverageReport {
+    pub sites: Vec<FaultSite>,
+}
+
+impl CoverageReport {
+    /// Classes with a site a known fix matches, sorted and deduplicated
+    pub fn recognized_classes(&self) -> Vec<&str> {
+        self.classes(true)
+    }
+    
+    /// Classes with a site no known fix matches
+    pub fn unrecognized_classes(&self) -> Vec<&str> {
+        self.classes(false)
+    }
+    
+    fn classes(&self, recognized: bool) -> Vec<&str> {
+        let mut classes: Vec<&str> = self.sites.iter()
+            .filter(|site| site.recognized == recognized)
+            .map(|site| site.class.as_str())
+            .collect();
+        classes.sort_unstable();
+        classes.dedup();
+        classes
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let recognized = self.recognized_classes().len();
+        let total = recognized + self.unrecognized_classes().len();
+        writeln!(f, "Fault coverage (dry run): {}/{} error classes recognized", recognized, total)?;
+        for site in &self.sites {
+            writeln!(
+                f,
+                "  {} {:<24} {:<20} {}",
+                if site.recognized { "✓" } else { "✗" },
+                site.class,
+                site.target,
+                site.signature
+            )?;
+        }
+        Ok(())
+    }
+}
+
 pub struct ErrorInjector;
 
 impl ErrorInjector {
     /// Inject a test error into the system
     pub fn inject(error: TestError) -> Result<ErrorEvent> {
-        match error {
+        Self::expected_error(&error)
+    }
+    
+    /// Enumerate the sites `faults` would be injected at and the errors
+    /// they would raise, without injecting anything. A site is recognized
+    /// when its error matches one of the `known` fixes.
+    pub fn dry_run(faults: &[TestError], known: &KnownFixes) -> Result<CoverageReport> {
+        let mut sites = Vec::with_capacity(faults.len());
+        for fault in faults {
+            let error = Self::expected_error(fault)?;
+            sites.push(FaultSite {
+                class: fault_class(fault),
+                target: fault_target(fault),
+                recognized: !known.for_error(&error).is_empty(),
+                fingerprint: fingerprint_error(&error),
+                source: error.source,
+                signature: error.message,
+            });
+        }
+        Ok(CoverageReport { sites })
+    }
+    
+    /// One fault of every class
+    pub fn standard_faults() -> Vec<TestError> {
+        let packages = [
+            PackageError::SyntaxError,
+            PackageError::RuntimeError,
+            PackageError::MissingDependency,
+            PackageError::CorruptedBinary,
+        ];
+        let mut faults: Vec<TestError> = packages.into_iter()
+            .map(|error_type| TestError::BrokenPackage { name: "calc".to_string(), error_type })
+            .collect();
+        faults.extend([
+            TestError::Panic { message: "Test panic for HiveFix".to_string() },
+            TestError::InfiniteLoop,
+            TestError::MemoryAbuse { size_mb: 512 },
+            TestError::CorruptedFile { path: "/var/lib/sentient/state.db".to_string() },
+            TestError::ConfigError { key: "max_threads".to_string(), bad_value: "not_a_number".to_string() },
+        ]);
+        faults
+    }
+    
+    /// The error HiveFix sees for `error`
+    fn expected_error(error: &TestError) -> Result<ErrorEvent> {
+        match error.clone() {
             TestError::BrokenPackage { name, error_type } => {
                 Self::inject_package_error(&name, error_type)
             }
@@ -147,6 +258,35 @@ impl ErrorInjector {
     }
 }
 
+fn fault_class(fault: &TestError) -> String {
+    match fault {
+        TestError::BrokenPackage { error_type, .. } => {
+            let kind = match error_type {
+                PackageError::SyntaxError => "syntax_error",
+                PackageError::RuntimeError => "runtime_error",
+                PackageError::MissingDependency => "missing_dependency",
+                PackageError::CorruptedBinary => "corrupted_binary",
+            };
+            format!("package:{}", kind)
+        }
+        TestError::Panic { .. } => "panic".to_string(),
+        TestError::InfiniteLoop => "infinite_loop".to_string(),
+        TestError::MemoryAbuse { .. } => "memory_abuse".to_string(),
+        TestError::CorruptedFile { .. } => "corrupted_file".to_string(),
+        TestError::ConfigError { .. } => "config_error".to_string(),
+    }
+}
+
+fn fault_target(fault: &TestError) -> String {
+    match fault {
+        TestError::BrokenPackage { name, .. } => name.clone(),
+        TestError::Panic { .. } | TestError::InfiniteLoop => "shell".to_string(),
+        TestError::MemoryAbuse { .. } => "allocator".to_string(),
+        TestError::CorruptedFile { path } => path.clone(),
+        TestError::ConfigError { key, .. } => key.clone(),
+    }
+}
+
 /// Test harness for HiveFix
 pub struct HiveFixTestHarness {
     agent: HiveFixAgent,
@@ -232,4 +372,62 @@ impl TestReport {
             errors: Vec::new(),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dry_run_lists_sites_without_touching_the_system() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let existing = dir.path().join("state.db");
+        std::fs::write(&existing, "intact").unwrap();
+        let missing = dir.path().join("missing.db");
+        let faults = vec![
+            TestError::BrokenPackage { name: "calc".to_string(), error_type: PackageError::RuntimeError },
+            TestError::MemoryAbuse { size_mb: 512 },
+            TestError::CorruptedFile { path: existing.display().to_string() },
+            TestError::CorruptedFile { path: missing.display().to_string() },
+            TestError::ConfigError { key: "max_threads".to_string(), bad_value: "lots".to_string() },
+        ];
+
+        // Fixes made for earlier errors of some of the classes; their
+        // values differ but the fingerprints agree
+        let mut known = KnownFixes::default();
+        let earlier = [
+            TestError::MemoryAbuse { size_mb: 64 },
+            TestError::CorruptedFile { path: "/var/lib/sentient/other.db".to_string() },
+            TestError::ConfigError { key: "log_level".to_string(), bad_value: "loud".to_string() },
+        ];
+        for (i, fault) in earlier.iter().enumerate() {
+            known.insert(&ErrorInjector::expected_error(fault).unwrap(), &format!("fix_{}", i));
+        }
+
+        let report = ErrorInjector::dry_run(&faults, &known).unwrap();
+        let sites: Vec<(&str, &str, bool)> = report.sites.iter()
+            .map(|site| (site.class.as_str(), site.target.as_str(), site.recognized))
+            .collect();
+        assert_eq!(
+            sites,
+            vec![
+                ("package:runtime_error", "calc", false),
+                ("memory_abuse", "allocator", true),
+                ("corrupted_file", existing.to_str().unwrap(), true),
+                ("corrupted_file", missing.to_str().unwrap(), true),
+                ("config_error", "max_threads", false),
+            ]
+        );
+        assert_eq!(report.sites[0].source, ErrorSource::Package("calc".to_string()));
+        assert_eq!(report.sites[1].signature, "Memory allocation failed: requested 512 MB exceeds limit");
+        assert_eq!(report.recognized_classes(), vec!["corrupted_file", "memory_abuse"]);
+        assert_eq!(report.unrecognized_classes(), vec!["config_error", "package:runtime_error"]);
+        let unknown = ErrorInjector::dry_run(&faults, &KnownFixes::default()).unwrap();
+        assert!(unknown.recognized_classes().is_empty());
+
+        // Nothing was written, created or removed
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "intact");
+        assert!(!missing.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
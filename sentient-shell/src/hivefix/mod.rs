@@ -14,6 +14,7 @@ pub mod prompts;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::time::SystemTime;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,7 +27,7 @@ pub struct ErrorEvent {
     pub context: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ErrorSource {
     Shell,
     Package(String),
@@ -40,7 +41,7 @@ pub struct FixCandidate {
     pub id: String,
     pub error_id: String,
     pub description: String,
-    pub patch: String,
+    pub patch: patch::Patch,
     pub confidence: f32,
     pub tested: bool,
     pub test_result: Option<TestResult>,
@@ -73,6 +74,8 @@ pub struct HiveFixConfig {
     pub ollama_url: String,
     pub hive_server_url: Option<String>,
     pub log_paths: Vec<String>,
+    /// Directory fix patches are applied in; they cannot write outside it
+    pub patch_root: PathBuf,
 }
 
 impl Default for HiveFixConfig {
@@ -87,6 +90,7 @@ impl Default for HiveFixConfig {
                 "/var/log/sentient/".to_string(),
                 "/tmp/sentient-errors.log".to_string(),
             ],
+            patch_root: PathBuf::from("/opt/sentient"),
         }
     }
 }
\ No newline at end of file
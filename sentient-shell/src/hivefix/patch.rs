@@ -1,81 +1,312 @@
-use super::*;
-use anyhow::{Result, Context};
+use anyhow::{Result, Context, bail};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
-#[derive(Debug, Clone)]
+use super::rollback::RollbackManager;
+
+/// A unified diff, parsed into the files it changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Patch {
-    pub file_path: Option<String>,
-    pub old_content: Option<String>,
-    pub new_content: String,
-    pub patch_type: PatchType,
+    pub files: Vec<FilePatch>,
+}
+
+/// Changes to one file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilePatch {
+    /// Path relative to the directory the patch applies in, without the
+    /// `a/`/`b/` prefix
+    pub path: String,
+    /// Whether the file is created by the patch (`--- /dev/null`)
+    pub new_file: bool,
+    pub hunks: Vec<Hunk>,
+}
+
+/// One `@@ -old_start,old_len +new_start,new_len @@` block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hunk {
+    /// First line the hunk covers in the original file, 1-based; 0 when
+    /// it covers no lines
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<HunkLine>,
 }
 
-#[derive(Debug, Clone)]
-pub enum PatchType {
-    FilePatch,      // Modify existing file
-    ConfigPatch,    // Update configuration
-    CodePatch,      // Fix code issues
-    StatePatch,     // Fix corrupted state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
 }
 
-pub fn generate_patch(error: &ErrorEvent, ai_suggestion: &str) -> Result<Patch> {
-    // Parse AI suggestion to determine patch type and content
-    let patch_type = detect_patch_type(error);
-    
-    // Extract file path if mentioned
-    let file_path = extract_file_path(ai_suggestion);
-    
-    Ok(Patch {
-        file_path,
-        old_content: None,
-        new_content: ai_suggestion.to_string(),
-        patch_type,
-    })
+impl Hunk {
+    /// Lines the original file must have where the hunk applies
+    fn expected(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|line| match line {
+            HunkLine::Context(text) | HunkLine::Remove(text) => Some(text.as_str()),
+            HunkLine::Add(_) => None,
+        })
+    }
+
+    /// Lines that replace them
+    fn replacement(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().filter_map(|line| match line {
+            HunkLine::Context(text) | HunkLine::Add(text) => Some(text.as_str()),
+            HunkLine::Remove(_) => None,
+        })
+    }
 }
 
-pub fn apply_patch(patch_content: &str) -> Result<()> {
-    // Parse patch content
-    // For now, this is a simplified implementation
-    
-    if patch_content.contains("FILE:") {
-        // File-based patch
-        let lines: Vec<&str> = patch_content.lines().collect();
-        for line in lines {
-            if let Some(file_path) = line.strip_prefix("FILE:") {
-                // In production, apply actual file changes
-                log::info!("Would patch file: {}", file_path.trim());
+impl Patch {
+    /// Parse the unified diff in `text`. Anything around the diff, such as
+    /// an explanation or a code fence, is ignored.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut files = Vec::new();
+        let mut lines = text.lines().enumerate().peekable();
+
+        while let Some((_, line)) = lines.next() {
+            let Some(old) = line.strip_prefix("--- ") else {
+                continue;
+            };
+            let new = match lines.next() {
+                Some((_, next)) if next.starts_with("+++ ") => &next[4..],
+                _ => continue,
+            };
+            let old = diff_path(old);
+            let new = diff_path(new);
+            if new == "/dev/null" {
+                bail!("Patch deletes {}; deleting files is not supported", old);
             }
+
+            let mut file = FilePatch {
+                path: new.to_string(),
+                new_file: old == "/dev/null",
+                hunks: Vec::new(),
+            };
+            while let Some(&(number, header)) = lines.peek() {
+                if !header.starts_with("@@") {
+                    break;
+                }
+                lines.next();
+                let (old_start, old_len, new_start, new_len) = parse_hunk_header(header)
+                    .with_context(|| format!("Invalid hunk header on line {}: {}", number + 1, header))?;
+
+                let mut hunk = Hunk { old_start, old_len, new_start, new_len, lines: Vec::new() };
+                let (mut old_seen, mut new_seen) = (0, 0);
+                while old_seen < old_len || new_seen < new_len {
+                    let Some((number, line)) = lines.next() else {
+                        bail!("Hunk for {} ends early: expected {} old and {} new lines", file.path, old_len, new_len);
+                    };
+                    let parsed = match line.chars().next() {
+                        Some(' ') => HunkLine::Context(line[1..].to_string()),
+                        // Some tools drop the space of an empty context line
+                        None => HunkLine::Context(String::new()),
+                        Some('-') => HunkLine::Remove(line[1..].to_string()),
+                        Some('+') => HunkLine::Add(line[1..].to_string()),
+                        Some('\\') => continue,
+                        _ => bail!("Unexpected line {} in hunk for {}: {}", number + 1, file.path, line),
+                    };
+                    match parsed {
+                        HunkLine::Context(_) => { old_seen += 1; new_seen += 1; }
+                        HunkLine::Remove(_) => old_seen += 1,
+                        HunkLine::Add(_) => new_seen += 1,
+                    }
+                    hunk.lines.push(parsed);
+                }
+                if old_seen != old_len || new_seen != new_len {
+                    bail!("Hunk for {} does not match its header {}", file.path, header);
+                }
+                // "\ No newline at end of file" after the last line
+                if matches!(lines.peek(), Some((_, line)) if line.starts_with('\\')) {
+                    lines.next();
+                }
+                file.hunks.push(hunk);
+            }
+
+            if file.hunks.is_empty() {
+                bail!("Patch for {} has no hunks", file.path);
+            }
+            files.push(file);
         }
+
+        if files.is_empty() {
+            bail!("No unified diff found");
+        }
+        Ok(Self { files })
+    }
+
+    /// Files the patch writes, under `root`, or an error if any of them
+    /// would land outside it
+    pub fn paths(&self, root: &Path) -> Result<Vec<PathBuf>> {
+        self.files.iter().map(|file| confined_path(root, &file.path)).collect()
+    }
+
+    /// Apply every file patch under `root`. Paths and hunks are all
+    /// checked first, so a patch that escapes `root` or does not fit
+    /// changes nothing.
+    pub fn apply(&self, root: &Path) -> Result<()> {
+        let paths = self.paths(root)?;
+        let mut patched = Vec::with_capacity(self.files.len());
+        for (file, path) in self.files.iter().zip(paths) {
+            let original = if file.new_file {
+                if path.exists() {
+                    bail!("Patch creates {}, which already exists", path.display());
+                }
+                String::new()
+            } else {
+                fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?
+            };
+            patched.push((path, file.apply_to(&original)?));
+        }
+
+        for (path, content) in patched {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            log::info!("Patched {}", path.display());
+        }
+        Ok(())
     }
-    
-    Ok(())
 }
 
-fn detect_patch_type(error: &ErrorEvent) -> PatchType {
-    match &error.source {
-        ErrorSource::Shell => PatchType::CodePatch,
-        ErrorSource::Package(_) => PatchType::CodePatch,
-        ErrorSource::Kernel => PatchType::ConfigPatch,
-        ErrorSource::System => PatchType::ConfigPatch,
-        ErrorSource::User => PatchType::StatePatch,
+impl FilePatch {
+    /// `original` with the hunks applied, or an error naming the first
+    /// hunk whose context or removed lines are not where it says
+    pub fn apply_to(&self, original: &str) -> Result<String> {
+        let lines: Vec<&str> = original.lines().collect();
+        let mut output: Vec<&str> = Vec::with_capacity(lines.len());
+        let mut next = 0;
+
+        for (index, hunk) in self.hunks.iter().enumerate() {
+            // A hunk covering no lines inserts after `old_start`
+            let start = if hunk.old_len == 0 { hunk.old_start } else { hunk.old_start.saturating_sub(1) };
+            if start < next || start + hunk.old_len > lines.len() {
+                bail!(
+                    "Hunk {} of {} covers lines {}..{}, outside the file or before the previous hunk",
+                    index + 1, self.path, start + 1, start + hunk.old_len
+                );
+            }
+            for (offset, expected) in hunk.expected().enumerate() {
+                let found = lines[start + offset];
+                if found != expected {
+                    bail!(
+                        "Hunk {} of {} does not match line {}: expected {:?}, found {:?}",
+                        index + 1, self.path, start + offset + 1, expected, found
+                    );
+                }
+            }
+            output.extend(&lines[next..start]);
+            output.extend(hunk.replacement());
+            next = start + hunk.old_len;
+        }
+        output.extend(&lines[next..]);
+
+        let mut content = output.join("\n");
+        if !output.is_empty() && (original.ends_with('\n') || original.is_empty()) {
+            content.push('\n');
+        }
+        Ok(content)
     }
 }
 
-fn extract_file_path(content: &str) -> Option<String> {
-    // Look for file paths in the content
-    for line in content.lines() {
-        if line.contains("/") && (line.contains(".rs") || line.contains(".toml")) {
-            // Simple heuristic to find file paths
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            for part in parts {
-                if part.contains("/") && !part.starts_with("http") {
-                    return Some(part.to_string());
+impl fmt::Display for Patch {
+    /// The patch as a unified diff
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.files {
+            if file.new_file {
+                writeln!(f, "--- /dev/null")?;
+            } else {
+                writeln!(f, "--- a/{}", file.path)?;
+            }
+            writeln!(f, "+++ b/{}", file.path)?;
+            for hunk in &file.hunks {
+                writeln!(f, "@@ -{},{} +{},{} @@", hunk.old_start, hunk.old_len, hunk.new_start, hunk.new_len)?;
+                for line in &hunk.lines {
+                    match line {
+                        HunkLine::Context(text) => writeln!(f, " {}", text)?,
+                        HunkLine::Remove(text) => writeln!(f, "-{}", text)?,
+                        HunkLine::Add(text) => writeln!(f, "+{}", text)?,
+                    }
                 }
             }
         }
+        Ok(())
+    }
+}
+
+/// Path of a `---`/`+++` line, without a timestamp or `a/`/`b/` prefix
+fn diff_path(spec: &str) -> &str {
+    let path = spec.split('\t').next().unwrap_or_default().trim();
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+}
+
+/// `(old_start, old_len, new_start, new_len)` of `@@ -l[,s] +l[,s] @@`
+fn parse_hunk_header(header: &str) -> Result<(usize, usize, usize, usize)> {
+    let ranges = header
+        .strip_prefix("@@ ")
+        .and_then(|rest| rest.split(" @@").next())
+        .context("missing @@ markers")?;
+    let mut parts = ranges.split_whitespace();
+    let (Some(old), Some(new), None) = (parts.next(), parts.next(), parts.next()) else {
+        bail!("expected an old and a new range");
+    };
+    let range = |spec: &str, sign: char| -> Result<(usize, usize)> {
+        let spec = spec.strip_prefix(sign).with_context(|| format!("range {} lacks '{}'", spec, sign))?;
+        let (start, len) = spec.split_once(',').unwrap_or((spec, "1"));
+        Ok((start.parse()?, len.parse()?))
+    };
+    let (old_start, old_len) = range(old, '-')?;
+    let (new_start, new_len) = range(new, '+')?;
+    Ok((old_start, old_len, new_start, new_len))
+}
+
+/// `path` under `root`; absolute paths and `..` could resolve outside it
+fn confined_path(root: &Path, path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path);
+    let plain = relative.components().all(|part| matches!(part, Component::Normal(_) | Component::CurDir));
+    if path.is_empty() || !plain {
+        bail!("Patch path {:?} is not a plain relative path", path);
     }
-    None
+    Ok(root.join(relative))
+}
+
+/// Apply fix `fix_id`'s patch under `root`, the only directory fixes may
+/// write to. The files are snapshotted through `rollback` before the first
+/// write and restored if applying fails; files the patch created are
+/// removed. Returns the snapshot id.
+pub fn apply_patch(patch: &Patch, fix_id: &str, root: &Path, rollback: &RollbackManager) -> Result<String> {
+    let paths = patch.paths(root)?;
+    let created: Vec<&PathBuf> = patch
+        .files
+        .iter()
+        .zip(&paths)
+        .filter(|(file, path)| file.new_file && !path.exists())
+        .map(|(_, path)| path)
+        .collect();
+    let snapshot_id = rollback
+        .create_snapshot(fix_id, &paths)
+        .context("Failed to snapshot files before patching")?;
+
+    if let Err(e) = patch.apply(root) {
+        for path in created {
+            if path.exists() {
+                fs::remove_file(path).ok();
+            }
+        }
+        rollback
+            .rollback(&snapshot_id)
+            .with_context(|| format!("Failed to roll back to {} after: {:#}", snapshot_id, e))?;
+        return Err(e.context(format!("Rolled back to {}", snapshot_id)));
+    }
+    Ok(snapshot_id)
 }
 
 pub fn create_backup(file_path: &Path) -> Result<PathBuf> {
@@ -91,4 +322,125 @@ pub fn restore_backup(backup_path: &Path) -> Result<()> {
         .context("Failed to restore backup")?;
     fs::remove_file(backup_path)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+The config reads the wrong key.
+
+```diff
+--- a/config/shell.toml
++++ b/config/shell.toml
+@@ -1,4 +1,4 @@
+ [shell]
+-prompt = \"$ \"
++prompt = \"sentient> \"
+ history = 100
+
+@@ -7,2 +7,3 @@
+ [ai]
+ model = \"phi\"
++timeout_secs = 30
+```
+";
+
+    const ORIGINAL: &str = "[shell]\nprompt = \"$ \"\nhistory = 100\n\n[log]\nlevel = \"info\"\n[ai]\nmodel = \"phi\"\n";
+
+    #[test]
+    fn test_parse_unified_diff_into_hunks() {
+        let patch = Patch::parse(DIFF).unwrap();
+        assert_eq!(patch.files.len(), 1);
+        let file = &patch.files[0];
+        assert_eq!(file.path, "config/shell.toml");
+        assert!(!file.new_file);
+        assert_eq!(file.hunks.len(), 2);
+        assert_eq!((file.hunks[0].old_start, file.hunks[0].old_len), (1, 4));
+        assert_eq!(
+            file.hunks[0].lines,
+            vec![
+                HunkLine::Context("[shell]".to_string()),
+                HunkLine::Remove("prompt = \"$ \"".to_string()),
+                HunkLine::Add("prompt = \"sentient> \"".to_string()),
+                HunkLine::Context("history = 100".to_string()),
+                HunkLine::Context(String::new()),
+            ]
+        );
+        assert_eq!((file.hunks[1].new_start, file.hunks[1].new_len), (7, 3));
+
+        // Rendering and reparsing keeps the structure
+        assert_eq!(Patch::parse(&patch.to_string()).unwrap(), patch);
+        assert!(Patch::parse("FIX: restart the service").is_err());
+    }
+
+    #[test]
+    fn test_apply_patch_checks_context_first() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config/shell.toml");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, ORIGINAL).unwrap();
+
+        let patch = Patch::parse(DIFF).unwrap();
+        patch.apply(dir.path()).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[shell]\nprompt = \"sentient> \"\nhistory = 100\n\n[log]\nlevel = \"info\"\n[ai]\nmodel = \"phi\"\ntimeout_secs = 30\n"
+        );
+
+        // The file has moved on: the context no longer matches, nothing is written
+        let changed = ORIGINAL.replace("history = 100", "history = 500");
+        fs::write(&path, &changed).unwrap();
+        let err = patch.apply(dir.path()).unwrap_err().to_string();
+        assert!(err.contains("Hunk 1 of config/shell.toml does not match line 3"), "{}", err);
+        assert_eq!(fs::read_to_string(&path).unwrap(), changed);
+    }
+
+    #[test]
+    fn test_paths_outside_the_root_are_rejected() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(&root).unwrap();
+        let outside = dir.path().join("escaped.txt");
+
+        for path in ["../escaped.txt", "config/../../escaped.txt", outside.to_str().unwrap()] {
+            let diff = format!("--- /dev/null\n+++ {}\n@@ -0,0 +1,1 @@\n+owned\n", path);
+            let patch = Patch::parse(&diff).unwrap();
+            let err = patch.apply(&root).unwrap_err().to_string();
+            assert!(err.contains("is not a plain relative path"), "{}: {}", path, err);
+        }
+        assert!(!outside.exists());
+    }
+
+    #[test]
+    fn test_failed_apply_rolls_back_to_the_snapshot() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path().join("root");
+        let config = root.join("config/shell.toml");
+        fs::create_dir_all(config.parent().unwrap()).unwrap();
+        fs::write(&config, ORIGINAL).unwrap();
+        // A file where the last patch needs a directory makes its write fail
+        fs::write(root.join("blocker"), "").unwrap();
+        let rollback = RollbackManager::new(dir.path().join("snapshots")).unwrap();
+
+        let diff = format!(
+            "--- /dev/null\n+++ b/added.txt\n@@ -0,0 +1,1 @@\n+hello\n{}--- /dev/null\n+++ b/blocker/new.toml\n@@ -0,0 +1,1 @@\n+x = 1\n",
+            DIFF.split("```diff\n").nth(1).unwrap().trim_end_matches("```\n")
+        );
+        let patch = Patch::parse(&diff).unwrap();
+        assert_eq!(patch.files.len(), 3);
+
+        let err = apply_patch(&patch, "fix-1", &root, &rollback).unwrap_err();
+        assert!(format!("{:#}", err).contains("Rolled back to snap_fix-1_"), "{:#}", err);
+        assert_eq!(fs::read_to_string(&config).unwrap(), ORIGINAL);
+        assert!(!root.join("added.txt").exists());
+        assert_eq!(rollback.list_snapshots().unwrap().len(), 1);
+
+        // Without the blocker the same patch goes through
+        let fits = Patch { files: patch.files[..2].to_vec() };
+        apply_patch(&fits, "fix-2", &root, &rollback).unwrap();
+        assert_eq!(fs::read_to_string(root.join("added.txt")).unwrap(), "hello\n");
+        assert!(fs::read_to_string(&config).unwrap().contains("timeout_secs = 30"));
+    }
+}
@@ -9,7 +9,9 @@ impl PromptTemplates {
         let base_context = format!(
             "You are HiveFix, an AI-powered self-healing agent for SentientOS. \
             You must analyze errors and propose safe, minimal fixes. \
-            Always prioritize system stability and security.\n\n"
+            Always prioritize system stability and security. \
+            Give every file change as a unified diff (--- a/<path>, +++ b/<path>, @@ hunks) \
+            with unchanged context lines around each change.\n\n"
         );
         
         let specific_prompt = match &error.source {
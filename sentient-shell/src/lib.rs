@@ -1,17 +1,27 @@
 pub mod ai;
 pub mod ai_router;
+pub mod batch;
+pub mod clock;
 pub mod commands_functions;
 pub mod commands {
     pub mod rag_tool;
+    pub mod rl_eval;
+    pub mod rl_smoke;
+    pub mod rl_snapshot;
     pub mod rl_trace;
     pub mod rl_infer;
     pub mod rl_retrain;
     pub mod sentient_goal;
 }
+pub mod fallback;
+pub mod goal_injection;
 pub mod hivefix;
 #[cfg(feature = "local-inference")]
 pub mod inference;
+pub mod output;
 pub mod package;
+pub mod policy_injector;
+pub mod reward_model;
 #[cfg(feature = "serial")]
 pub mod serial;
 pub mod service;
@@ -21,6 +31,9 @@ pub mod web_ui;
 pub mod schema;
 pub mod boot_llm;
 pub mod rag;
+pub mod rl_training;
+pub mod selftest;
+pub mod trace_id;
 
 // Re-export ShellState from main module
 pub use crate::shell_state::ShellState;
@@ -88,6 +88,14 @@ impl FunctionParser {
         }
     }
     
+    /// Create parser for several formats, tried in order
+    pub fn with_formats(formats: Vec<CallFormat>) -> Self {
+        Self {
+            formats,
+            validate_tools: true,
+        }
+    }
+    
     /// Disable tool validation
     pub fn without_validation(mut self) -> Self {
         self.validate_tools = false;
@@ -1,3 +1,4 @@
 //! LLM integration modules
 
-pub mod functions;
\ No newline at end of file
+pub mod functions;
+pub mod tool_loop;
\ No newline at end of file
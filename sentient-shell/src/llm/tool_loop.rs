@@ -0,0 +1,468 @@
+//! Multi-step tool use
+//!
+//! Runs the tool calls a model emits, feeds their results back as structured
+//! messages, and asks the model again until it answers without calling tools.
+
+use crate::fallback::TextBackend;
+use crate::llm::functions::{CallFormat, FunctionCall, FunctionParser};
+use crate::tools::exec::ExecutionResult;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Default cap on model turns in one [`ToolLoop::run`]
+pub const DEFAULT_MAX_TOOL_ITERATIONS: usize = 5;
+
+/// Environment variable overriding [`DEFAULT_MAX_TOOL_ITERATIONS`]
+pub const MAX_TOOL_ITERATIONS_ENV: &str = "SENTIENT_MAX_TOOL_ITERATIONS";
+
+/// Outcome of one tool call, as shown to the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolResultMessage {
+    pub tool_id: String,
+    pub arguments: Option<Value>,
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    /// Why the tool could not run at all
+    pub error: Option<String>,
+    pub interrupted: bool,
+}
+
+impl ToolResultMessage {
+    /// Result of a tool that ran, successfully or not
+    pub fn from_execution(call: &FunctionCall, result: &ExecutionResult) -> Self {
+        Self {
+            tool_id: call.tool_id.clone(),
+            arguments: call.arguments.clone(),
+            success: result.exit_code == 0 && !result.interrupted,
+            exit_code: Some(result.exit_code),
+            stdout: result.stdout.clone(),
+            stderr: result.stderr.clone(),
+            error: None,
+            interrupted: result.interrupted,
+        }
+    }
+
+    /// Result of a tool that could not be run
+    pub fn from_error(call: &FunctionCall, error: &anyhow::Error) -> Self {
+        Self {
+            tool_id: call.tool_id.clone(),
+            arguments: call.arguments.clone(),
+            success: false,
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            error: Some(format!("{:#}", error)),
+            interrupted: false,
+        }
+    }
+
+    /// Result standing in for a call that repeats an earlier one in the
+    /// same turn; the call is not run again
+    pub fn loop_detected(call: &FunctionCall) -> Self {
+        Self::from_error(
+            call,
+            &anyhow::anyhow!(
+                "Tool loop detected: {} was already called with the same arguments",
+                call.tool_id
+            ),
+        )
+    }
+}
+
+/// One entry in a conversation with the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum Message {
+    System { content: String },
+    User { content: String },
+    Assistant { content: String },
+    ToolResult(ToolResultMessage),
+}
+
+/// Messages exchanged with the model so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    pub messages: Vec<Message>,
+}
+
+impl Conversation {
+    /// A conversation opened by `prompt`
+    pub fn from_user(prompt: impl Into<String>) -> Self {
+        Self {
+            messages: vec![Message::User {
+                content: prompt.into(),
+            }],
+        }
+    }
+
+    pub fn push(&mut self, message: Message) {
+        self.messages.push(message);
+    }
+
+    /// Text prompt for backends that take a single string. A lone user
+    /// message is sent as is; anything longer becomes a transcript ending
+    /// with the assistant's turn, tool results as JSON.
+    pub fn to_prompt(&self) -> String {
+        if let [Message::User { content }] = self.messages.as_slice() {
+            return content.clone();
+        }
+
+        let mut prompt = String::new();
+        for message in &self.messages {
+            match message {
+                Message::System { content } => prompt.push_str(&format!("System: {}\n\n", content)),
+                Message::User { content } => prompt.push_str(&format!("User: {}\n\n", content)),
+                Message::Assistant { content } => {
+                    prompt.push_str(&format!("Assistant: {}\n\n", content))
+                }
+                Message::ToolResult(result) => prompt.push_str(&format!(
+                    "Tool result: {}\n\n",
+                    serde_json::to_string(result).unwrap_or_default()
+                )),
+            }
+        }
+        prompt.push_str("Assistant:");
+        prompt
+    }
+}
+
+/// Executes tool calls on behalf of the loop
+pub trait ToolRunner {
+    fn run(&self, call: &FunctionCall) -> Result<ExecutionResult>;
+}
+
+/// What a finished [`ToolLoop::run`] produced
+#[derive(Debug, Clone)]
+pub struct ToolLoopOutcome {
+    /// The model's last reply, which called no tools
+    pub answer: String,
+    /// Every tool result fed back, in order
+    pub tool_results: Vec<ToolResultMessage>,
+    /// Model turns taken, including the final one
+    pub iterations: usize,
+    /// Whether the loop was halted because the model repeated a call. The
+    /// answer is then the detection message rather than a model reply.
+    pub loop_detected: bool,
+}
+
+/// Alternates between the model and its tool calls until it stops calling
+/// tools
+pub struct ToolLoop {
+    parser: FunctionParser,
+    max_tool_iterations: usize,
+}
+
+impl Default for ToolLoop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ToolLoop {
+    /// Loop recognizing explicit calls only (command, JSON and structured
+    /// formats), so prose like "run the tests" in an answer is not mistaken
+    /// for a tool call. Unknown tools are not rejected up front; the runner's
+    /// error goes back to the model like any other result.
+    pub fn new() -> Self {
+        Self {
+            parser: FunctionParser::with_formats(vec![
+                CallFormat::Command,
+                CallFormat::Json,
+                CallFormat::Structured,
+            ])
+            .without_validation(),
+            max_tool_iterations: DEFAULT_MAX_TOOL_ITERATIONS,
+        }
+    }
+
+    /// [`ToolLoop::new`] with the iteration cap taken from
+    /// `SENTIENT_MAX_TOOL_ITERATIONS` when set
+    pub fn from_env() -> Self {
+        let tool_loop = Self::new();
+        match std::env::var(MAX_TOOL_ITERATIONS_ENV) {
+            Ok(value) => match value.parse() {
+                Ok(max) => tool_loop.with_max_tool_iterations(max),
+                Err(_) => {
+                    log::warn!("Ignoring invalid {}={:?}", MAX_TOOL_ITERATIONS_ENV, value);
+                    tool_loop
+                }
+            },
+            Err(_) => tool_loop,
+        }
+    }
+
+    pub fn with_parser(mut self, parser: FunctionParser) -> Self {
+        self.parser = parser;
+        self
+    }
+
+    pub fn with_max_tool_iterations(mut self, max_tool_iterations: usize) -> Self {
+        self.max_tool_iterations = max_tool_iterations.max(1);
+        self
+    }
+
+    /// Ask `model` to continue `conversation`, running the tools it calls
+    /// and returning their results to it, until it replies without calls.
+    ///
+    /// A call repeating an earlier `(tool_id, arguments)` of this run halts
+    /// the loop with a "tool loop detected" result instead of running again.
+    /// Fails if the model is still calling tools after the iteration limit.
+    pub fn run(
+        &self,
+        model: &mut dyn TextBackend,
+        tools: &dyn ToolRunner,
+        conversation: &mut Conversation,
+    ) -> Result<ToolLoopOutcome> {
+        let mut tool_results = Vec::new();
+        let mut seen: Vec<(String, Option<Value>)> = Vec::new();
+
+        for iteration in 1..=self.max_tool_iterations {
+            let reply = model.generate(&conversation.to_prompt())?;
+            conversation.push(Message::Assistant {
+                content: reply.clone(),
+            });
+
+            let calls = self.parser.parse(&reply)?;
+            if calls.is_empty() {
+                return Ok(ToolLoopOutcome {
+                    answer: reply,
+                    tool_results,
+                    iterations: iteration,
+                    loop_detected: false,
+                });
+            }
+
+            for call in &calls {
+                let key = (call.tool_id.clone(), call.arguments.clone());
+                if seen.contains(&key) {
+                    let result = ToolResultMessage::loop_detected(call);
+                    let answer = result.error.clone().unwrap_or_default();
+                    conversation.push(Message::ToolResult(result.clone()));
+                    tool_results.push(result);
+                    return Ok(ToolLoopOutcome {
+                        answer,
+                        tool_results,
+                        iterations: iteration,
+                        loop_detected: true,
+                    });
+                }
+                seen.push(key);
+
+                let result = match tools.run(call) {
+                    Ok(result) => ToolResultMessage::from_execution(call, &result),
+                    Err(e) => ToolResultMessage::from_error(call, &e),
+                };
+                conversation.push(Message::ToolResult(result.clone()));
+                tool_results.push(result);
+            }
+        }
+
+        bail!(
+            "Model was still calling tools after {} iterations",
+            self.max_tool_iterations
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Calls `disk_info` once, then answers from the result it was given
+    struct ScriptedModel {
+        prompts: Vec<String>,
+    }
+
+    impl TextBackend for ScriptedModel {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        fn generate(&mut self, prompt: &str) -> Result<String> {
+            self.prompts.push(prompt.to_string());
+            match prompt.lines().rfind(|l| l.starts_with("Tool result: ")) {
+                None => Ok(
+                    "Let me check.\n{\"tool\": \"disk_info\", \"args\": {\"path\": \"/\"}}"
+                        .to_string(),
+                ),
+                Some(line) => {
+                    let result: ToolResultMessage =
+                        serde_json::from_str(line.trim_start_matches("Tool result: ")).unwrap();
+                    Ok(format!("The root disk is {} full.", result.stdout.trim()))
+                }
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeTools {
+        calls: RefCell<Vec<FunctionCall>>,
+    }
+
+    impl ToolRunner for FakeTools {
+        fn run(&self, call: &FunctionCall) -> Result<ExecutionResult> {
+            self.calls.borrow_mut().push(call.clone());
+            Ok(ExecutionResult {
+                exit_code: 0,
+                stdout: "42%\n".to_string(),
+                stderr: String::new(),
+                duration_ms: 3,
+                interrupted: false,
+                trace_id: None,
+            })
+        }
+    }
+
+    /// Replies with `replies` in order, then repeats the last one forever
+    struct CannedModel {
+        replies: Vec<&'static str>,
+        turns: usize,
+    }
+
+    impl CannedModel {
+        fn new(replies: Vec<&'static str>) -> Self {
+            Self { replies, turns: 0 }
+        }
+    }
+
+    impl TextBackend for CannedModel {
+        fn name(&self) -> &str {
+            "canned"
+        }
+
+        fn generate(&mut self, _prompt: &str) -> Result<String> {
+            let reply = self.replies[self.turns.min(self.replies.len() - 1)];
+            self.turns += 1;
+            Ok(reply.to_string())
+        }
+    }
+
+    /// Calls a tool with different arguments on every turn
+    struct LoopingModel {
+        turns: usize,
+    }
+
+    impl TextBackend for LoopingModel {
+        fn name(&self) -> &str {
+            "looping"
+        }
+
+        fn generate(&mut self, _prompt: &str) -> Result<String> {
+            self.turns += 1;
+            Ok(format!(
+                "{{\"tool\": \"disk_info\", \"args\": {{\"path\": \"/mnt/{}\"}}}}",
+                self.turns
+            ))
+        }
+    }
+
+    #[test]
+    fn test_tool_result_is_fed_back_until_final_answer() {
+        let mut model = ScriptedModel {
+            prompts: Vec::new(),
+        };
+        let tools = FakeTools::default();
+        let mut conversation = Conversation::from_user("How full is my disk?");
+
+        let outcome = ToolLoop::new()
+            .run(&mut model, &tools, &mut conversation)
+            .unwrap();
+
+        assert_eq!(outcome.answer, "The root disk is 42% full.");
+        assert_eq!(outcome.iterations, 2);
+        assert_eq!(outcome.tool_results.len(), 1);
+        assert!(outcome.tool_results[0].success);
+
+        let calls = tools.calls.borrow();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].tool_id, "disk_info");
+        assert_eq!(calls[0].arguments, Some(serde_json::json!({"path": "/"})));
+
+        // The first turn is the bare prompt, the second carries the result
+        assert_eq!(model.prompts[0], "How full is my disk?");
+        assert!(model.prompts[1].starts_with("User: How full is my disk?"));
+        assert!(model.prompts[1].ends_with("Assistant:"));
+        assert!(matches!(
+            conversation.messages.as_slice(),
+            [
+                Message::User { .. },
+                Message::Assistant { .. },
+                Message::ToolResult(ToolResultMessage { tool_id, .. }),
+                Message::Assistant { .. },
+            ] if tool_id == "disk_info"
+        ));
+    }
+
+    #[test]
+    fn test_iterations_are_bounded() {
+        let tools = FakeTools::default();
+        let err = ToolLoop::new()
+            .with_max_tool_iterations(3)
+            .run(
+                &mut LoopingModel { turns: 0 },
+                &tools,
+                &mut Conversation::from_user("hi"),
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("after 3 iterations"), "{}", err);
+        assert_eq!(tools.calls.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_distinct_calls_across_steps_complete() {
+        let mut model = CannedModel::new(vec![
+            "!@ disk_info",
+            "!@ memory_info",
+            "Disk and memory both look fine.",
+        ]);
+        let tools = FakeTools::default();
+
+        let outcome = ToolLoop::new()
+            .run(
+                &mut model,
+                &tools,
+                &mut Conversation::from_user("Health check"),
+            )
+            .unwrap();
+
+        assert!(!outcome.loop_detected);
+        assert_eq!(outcome.answer, "Disk and memory both look fine.");
+        assert_eq!(outcome.iterations, 3);
+        let called: Vec<String> = tools
+            .calls
+            .borrow()
+            .iter()
+            .map(|c| c.tool_id.clone())
+            .collect();
+        assert_eq!(called, vec!["disk_info", "memory_info"]);
+    }
+
+    #[test]
+    fn test_repeated_call_is_cut_off_as_a_loop() {
+        let mut model = CannedModel::new(vec!["!@ disk_info"]);
+        let tools = FakeTools::default();
+        let mut conversation = Conversation::from_user("How full is my disk?");
+
+        let outcome = ToolLoop::new()
+            .with_max_tool_iterations(10)
+            .run(&mut model, &tools, &mut conversation)
+            .unwrap();
+
+        // The repeat is not run; the model and user both get the detection
+        assert!(outcome.loop_detected);
+        assert_eq!(outcome.iterations, 2);
+        assert_eq!(tools.calls.borrow().len(), 1);
+        assert!(outcome.answer.starts_with("Tool loop detected: disk_info"));
+        match conversation.messages.last() {
+            Some(Message::ToolResult(result)) => {
+                assert!(!result.success);
+                assert_eq!(result.error.as_deref(), Some(outcome.answer.as_str()));
+            }
+            other => panic!("expected a tool result, got {:?}", other),
+        }
+    }
+}
@@ -1,10 +1,49 @@
 use anyhow::Result;
+use clap::Parser;
 use std::io::{self, Write};
+use std::path::PathBuf;
 
+use sentient_shell::output::OutputFormat;
 use sentient_shell::{ShellState, BANNER};
 
+#[derive(Parser, Debug)]
+#[command(name = "sentient-shell", about = "SentientShell - AI-Native CLI")]
+struct Cli {
+    /// Run commands from a script file non-interactively
+    #[arg(long, value_name = "FILE", conflicts_with = "command")]
+    script: Option<PathBuf>,
+
+    /// Run a single command non-interactively
+    #[arg(short = 'c', value_name = "CMD")]
+    command: Option<String>,
+
+    /// Continue executing after a failing command
+    #[arg(long)]
+    keep_going: bool,
+
+    /// Emit machine-readable JSON from built-in commands
+    #[arg(long)]
+    json: bool,
+}
+
+impl Cli {
+    fn output_format(&self) -> OutputFormat {
+        if self.json {
+            OutputFormat::Json
+        } else {
+            OutputFormat::Human
+        }
+    }
+}
+
 fn main() -> Result<()> {
-    env_logger::init();
+    init_logging();
+
+    let cli = Cli::parse();
+    if cli.script.is_some() || cli.command.is_some() {
+        let code = run_batch(&cli)?;
+        std::process::exit(code);
+    }
 
     // Check if we're running in serial mode (for kernel/QEMU)
     let serial_mode = std::env::var("SENTIENT_SERIAL").is_ok();
@@ -22,10 +61,52 @@ fn main() -> Result<()> {
     }
 
     log::info!("Running in terminal mode");
-    run_terminal_shell()
+    run_terminal_shell(cli.output_format())
+}
+
+/// `env_logger` with the current trace id, if any, after each record's target
+fn init_logging() {
+    env_logger::Builder::from_default_env()
+        .format(|buf, record| {
+            let trace = sentient_shell::trace_id::current()
+                .map(|id| format!(" trace={}", id))
+                .unwrap_or_default();
+            writeln!(
+                buf,
+                "[{} {:<5} {}{}] {}",
+                buf.timestamp(),
+                record.level(),
+                record.target(),
+                trace,
+                record.args()
+            )
+        })
+        .init();
+}
+
+fn run_batch(cli: &Cli) -> Result<i32> {
+    let mut shell = ShellState::new().with_output_format(cli.output_format());
+
+    let report = if let Some(path) = &cli.script {
+        log::info!("Running script {}", path.display());
+        sentient_shell::batch::run_script(&mut shell, path, cli.keep_going)?
+    } else {
+        let command = cli.command.as_deref().unwrap_or_default();
+        sentient_shell::batch::run_lines(&mut shell, [command], cli.keep_going)
+    };
+
+    if !report.success() {
+        eprintln!(
+            "{} of {} command(s) failed",
+            report.failures.len(),
+            report.executed
+        );
+    }
+
+    Ok(report.exit_code())
 }
 
-fn run_terminal_shell() -> Result<()> {
+fn run_terminal_shell(format: OutputFormat) -> Result<()> {
     println!("{}", BANNER);
     println!("Type 'help' for available commands.\n");
 
@@ -34,7 +115,7 @@ fn run_terminal_shell() -> Result<()> {
         log::warn!("Failed to initialize service manager: {}", e);
     }
 
-    let mut shell = ShellState::new();
+    let mut shell = ShellState::new().with_output_format(format);
 
     loop {
         print!("sentient> ");
@@ -0,0 +1,176 @@
+//! Command output rendering
+//!
+//! Built-in commands produce a `CommandOutput` value which is rendered by the
+//! `OutputFormat` chosen at startup: decorated text for humans, or JSON for
+//! tools and the dashboard.
+
+use crate::selftest::{CheckOutcome, SelfTestReport};
+use crate::service::ServiceInfo;
+use serde::Serialize;
+use std::fmt::Write;
+
+/// How command output is rendered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+}
+
+/// Result of a connectivity probe
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "state", content = "detail", rename_all = "snake_case")]
+pub enum Connectivity {
+    Connected,
+    Unreachable,
+    Error(String),
+}
+
+impl From<anyhow::Result<bool>> for Connectivity {
+    fn from(result: anyhow::Result<bool>) -> Self {
+        match result {
+            Ok(true) => Connectivity::Connected,
+            Ok(false) => Connectivity::Unreachable,
+            Err(e) => Connectivity::Error(e.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusReport {
+    pub shell_version: String,
+    pub ollama_url: String,
+    pub sd_url: String,
+    pub ollama_status: Connectivity,
+    pub sd_status: Connectivity,
+    pub preferred_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_model_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelsReport {
+    pub ollama_models: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ollama_error: Option<String>,
+    pub sd_models: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sd_error: Option<String>,
+}
+
+/// Structured output of a built-in command
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum CommandOutput {
+    Status(StatusReport),
+    Models(ModelsReport),
+    Services(Vec<ServiceInfo>),
+    SelfTest(SelfTestReport),
+}
+
+impl OutputFormat {
+    pub fn render(&self, output: &CommandOutput) -> String {
+        match self {
+            OutputFormat::Human => render_human(output),
+            OutputFormat::Json => serde_json::to_string_pretty(output)
+                .unwrap_or_else(|e| format!("{{\"error\": \"{}\"}}", e)),
+        }
+    }
+}
+
+fn render_human(output: &CommandOutput) -> String {
+    let mut out = String::new();
+
+    match output {
+        CommandOutput::Status(status) => {
+            let _ = writeln!(out, "System Status:");
+            let _ = writeln!(out, "  Shell Version: {}", status.shell_version);
+            let _ = writeln!(out, "  Ollama Server: {}", status.ollama_url);
+            let _ = writeln!(out, "  SD Server: {}", status.sd_url);
+            let _ = writeln!(out, "  Ollama Status: {}", human_connectivity(&status.ollama_status));
+            let _ = writeln!(out, "  SD Status: {}", human_connectivity(&status.sd_status));
+            match (&status.preferred_model, &status.preferred_model_error) {
+                (_, Some(e)) => {
+                    let _ = writeln!(out, "  Preferred Model: Error - {}", e);
+                }
+                (Some(model), None) => {
+                    let _ = writeln!(out, "  Preferred Model: {}", model);
+                }
+                (None, None) => {
+                    let _ = writeln!(out, "  Preferred Model: None available");
+                }
+            }
+        }
+        CommandOutput::Models(models) => {
+            let _ = writeln!(out, "Available Models:");
+            let _ = writeln!(out, "\nOllama Models:");
+            human_model_list(&mut out, &models.ollama_models, models.ollama_error.as_deref());
+            let _ = writeln!(out, "\nStable Diffusion Models:");
+            human_model_list(&mut out, &models.sd_models, models.sd_error.as_deref());
+        }
+        CommandOutput::Services(services) => {
+            if services.is_empty() {
+                return "No services configured".to_string();
+            }
+
+            out.push_str("Services:\n");
+            out.push_str("NAME            STATUS       PID     RESTARTS\n");
+            out.push_str("─────────────────────────────────────────────\n");
+
+            for service in services {
+                let pid_str = service
+                    .pid
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(|| "-".to_string());
+
+                let _ = writeln!(
+                    out,
+                    "{:<15} {:<12} {:<7} {}",
+                    service.name,
+                    service.status.to_string(),
+                    pid_str,
+                    service.restart_count
+                );
+            }
+        }
+        CommandOutput::SelfTest(report) => {
+            let _ = writeln!(out, "Self-test:");
+            for check in &report.checks {
+                let (mark, detail) = match &check.outcome {
+                    CheckOutcome::Passed(detail) => ("✓", detail.as_str()),
+                    CheckOutcome::Failed(error) => ("✗", error.as_str()),
+                    CheckOutcome::Skipped => ("-", "skipped"),
+                };
+                let _ = writeln!(
+                    out,
+                    "  {} {:<11} {:>6}ms  {}",
+                    mark, check.component, check.duration_ms, detail
+                );
+            }
+            let verdict = if report.passed { "PASS" } else { "FAIL" };
+            let _ = writeln!(out, "{} ({}ms)", verdict, report.duration_ms);
+        }
+    }
+
+    out
+}
+
+fn human_connectivity(status: &Connectivity) -> String {
+    match status {
+        Connectivity::Connected => "Connected ✓".to_string(),
+        Connectivity::Unreachable => "Not reachable ✗".to_string(),
+        Connectivity::Error(e) => format!("Error: {}", e),
+    }
+}
+
+fn human_model_list(out: &mut String, models: &[String], error: Option<&str>) {
+    if let Some(e) = error {
+        let _ = writeln!(out, "  Error listing models: {}", e);
+    } else if models.is_empty() {
+        let _ = writeln!(out, "  No models found");
+    } else {
+        for model in models {
+            let _ = writeln!(out, "  - {}", model);
+        }
+    }
+}
@@ -9,6 +9,21 @@ use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 use tokio::time::{interval, Duration};
 use serde_json::json;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use sentient_rl_agent::ppo_full::PPOAgentFull;
+use sentient_rl_agent::PPOConfig;
+use sentient_rl_core::{AgentMode, VectorObservation};
+
+use crate::clock::{system_clock, Clock};
+use crate::rl_training::CheckpointMetadata;
+use crate::reward_model::RewardModel;
+
+pub use sentient_rl_env::system_observation::{
+    GoalOutcome, HostMetrics, HostProbe, ObservationSpec, SysinfoProbe, SystemObservation,
+    SystemObservationEncoder, OBSERVATION_SPEC_VERSION,
+};
 
 /// Policy injection configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,6 +40,29 @@ pub struct PolicyInjectorConfig {
     pub confidence_threshold: f32,
     /// Goal priority for injected goals
     pub goal_priority: String,
+    /// Where the learned reward model is persisted
+    #[serde(default = "default_state_path")]
+    pub state_path: PathBuf,
+    /// Seconds after startup before the first injection, so the metrics
+    /// behind the first observation have settled
+    #[serde(default = "default_warmup_secs")]
+    pub warmup_secs: u64,
+    /// Seconds after injecting a goal before a goal of the same category
+    /// (the template it came from) is injected again
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+fn default_state_path() -> PathBuf {
+    PathBuf::from("logs/policy_injector_state.json")
+}
+
+fn default_warmup_secs() -> u64 {
+    60
+}
+
+fn default_cooldown_secs() -> u64 {
+    300
 }
 
 impl Default for PolicyInjectorConfig {
@@ -36,33 +74,60 @@ impl Default for PolicyInjectorConfig {
             max_goals_per_interval: 1,
             confidence_threshold: 0.7,
             goal_priority: "medium".to_string(),
+            state_path: default_state_path(),
+            warmup_secs: default_warmup_secs(),
+            cooldown_secs: default_cooldown_secs(),
         }
     }
 }
 
-/// System observation for policy
-#[derive(Debug, Clone, Serialize)]
-pub struct SystemObservation {
-    /// CPU usage percentage
-    pub cpu_usage: f32,
-    /// Memory usage percentage
-    pub memory_usage: f32,
-    /// Disk usage percentage
-    pub disk_usage: f32,
-    /// Number of active processes
-    pub process_count: usize,
-    /// Recent goal success rate
-    pub goal_success_rate: f32,
-    /// Average goal execution time (ms)
-    pub avg_execution_time: f32,
-    /// Number of errors in last interval
-    pub error_count: usize,
-    /// Time since last goal (seconds)
-    pub time_since_last_goal: f32,
-    /// Current time of day (normalized)
-    pub time_of_day: f32,
-    /// Day of week (normalized)
-    pub day_of_week: f32,
+/// Read the observation spec saved next to a checkpoint (`<checkpoint>.json`).
+///
+/// Checkpoints from before specs were recorded are assumed to use the
+/// default layout, as long as their observation dimension agrees with it.
+pub async fn load_observation_spec(checkpoint_path: &Path) -> Result<ObservationSpec> {
+    let meta = read_checkpoint_metadata(checkpoint_path).await?;
+    observation_spec(meta.as_ref())
+}
+
+/// Metadata saved next to a checkpoint, `None` if there is none
+async fn read_checkpoint_metadata(checkpoint_path: &Path) -> Result<Option<CheckpointMetadata>> {
+    let meta_path = checkpoint_path.with_extension("json");
+    let content = match tokio::fs::read_to_string(&meta_path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            log::warn!("No checkpoint metadata at {:?}; assuming the default observation spec", meta_path);
+            return Ok(None);
+        }
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", meta_path)),
+    };
+    let meta = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid checkpoint metadata in {:?}", meta_path))?;
+    Ok(Some(meta))
+}
+
+/// Observation spec a checkpoint was trained with, validated for the injector
+fn observation_spec(meta: Option<&CheckpointMetadata>) -> Result<ObservationSpec> {
+    let Some(meta) = meta else {
+        return Ok(ObservationSpec::default());
+    };
+    let spec = match &meta.observation_spec {
+        Some(spec) => spec.clone(),
+        None => {
+            let spec = ObservationSpec::default();
+            if meta.observation_dim != spec.len() {
+                anyhow::bail!(
+                    "Checkpoint '{}' has no observation spec and was trained on {} features; \
+                     the default spec has {}",
+                    meta.id, meta.observation_dim, spec.len()
+                );
+            }
+            spec
+        }
+    };
+    spec.validate()
+        .with_context(|| format!("Checkpoint '{}' is incompatible with the policy injector", meta.id))?;
+    Ok(spec)
 }
 
 /// Goal suggestion from policy
@@ -73,6 +138,10 @@ pub struct GoalSuggestion {
     pub reasoning: String,
     pub expected_reward: f32,
     pub metadata: serde_json::Value,
+    /// Policy input the suggestion was made from
+    pub features: Vec<f32>,
+    /// Seed of the RNG that chose the action, recorded for replay
+    pub seed: u64,
 }
 
 /// Feedback for executed goals
@@ -86,22 +155,47 @@ pub struct GoalFeedback {
     pub error: Option<String>,
     pub reward: f32,
     pub timestamp: DateTime<Utc>,
+    /// Operator verdict on the injection: approved, or ignored/rejected.
+    /// `None` when no operator weighed in.
+    #[serde(default)]
+    pub approved: Option<bool>,
+}
+
+impl GoalFeedback {
+    /// Reward model target: the operator's verdict when given, else success
+    fn label(&self) -> f32 {
+        if self.approved.unwrap_or(self.success) {
+            1.0
+        } else {
+            0.0
+        }
+    }
 }
 
 /// Policy injector service
 pub struct PolicyInjector {
     config: PolicyInjectorConfig,
     policy: Arc<RwLock<Option<Box<dyn Policy>>>>,
+    encoder: Arc<RwLock<SystemObservationEncoder>>,
+    host_probe: Arc<dyn HostProbe>,
+    reward_model: Arc<RwLock<RewardModel>>,
     is_running: Arc<RwLock<bool>>,
     injection_history: Arc<RwLock<Vec<InjectionRecord>>>,
     feedback_buffer: Arc<RwLock<Vec<GoalFeedback>>>,
+    clock: Arc<dyn Clock>,
+    /// When the last injection cycle ran
+    last_cycle: Arc<RwLock<Option<DateTime<Utc>>>>,
+    /// When the service started; the warmup counts from here
+    started_at: Arc<RwLock<Option<DateTime<Utc>>>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
 struct InjectionRecord {
+    goal_id: String,
     timestamp: DateTime<Utc>,
     goal: String,
     confidence: f32,
+    features: Vec<f32>,
     injected: bool,
     feedback: Option<GoalFeedback>,
 }
@@ -111,25 +205,78 @@ impl PolicyInjector {
         Self {
             config,
             policy: Arc::new(RwLock::new(None)),
+            encoder: Arc::new(RwLock::new(SystemObservationEncoder::default())),
+            host_probe: Arc::new(SysinfoProbe),
+            reward_model: Arc::new(RwLock::new(
+                RewardModel::new(GOAL_TEMPLATES.len(), ObservationSpec::default().len()),
+            )),
             is_running: Arc::new(RwLock::new(false)),
             injection_history: Arc::new(RwLock::new(Vec::new())),
             feedback_buffer: Arc::new(RwLock::new(Vec::new())),
+            clock: system_clock(),
+            last_cycle: Arc::new(RwLock::new(None)),
+            started_at: Arc::new(RwLock::new(None)),
         }
     }
     
+    /// Use `clock` for injection timing and timestamps
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+    
+    /// Read host metrics for observations from `probe` instead of `sysinfo`
+    pub fn with_host_probe(mut self, probe: Arc<dyn HostProbe>) -> Self {
+        self.host_probe = probe;
+        self
+    }
+    
     /// Load policy from checkpoint
     pub async fn load_policy(&self) -> Result<()> {
         log::info!("Loading policy from: {:?}", self.config.checkpoint_path);
         
-        // In real implementation, would deserialize the policy
-        // For now, create a mock policy
-        let policy = Box::new(MockPolicy::new());
+        let meta = read_checkpoint_metadata(&self.config.checkpoint_path).await?;
+        let spec = observation_spec(meta.as_ref())?;
+        let encoder = SystemObservationEncoder::new(spec.clone())?;
+        
+        let action_dim = meta.map_or(GOAL_TEMPLATES.len(), |meta| meta.action_dim);
+        let mut policy: Box<dyn Policy> = Box::new(
+            CheckpointPolicy::load(&self.config.checkpoint_path, spec.len(), action_dim).await?,
+        );
+        // Injected goals act on the live system, so never explore or learn
+        policy.set_mode(AgentMode::Eval);
         *self.policy.write().await = Some(policy);
+        self.load_state(&spec).await?;
+        *self.encoder.write().await = encoder;
         
         log::info!("Policy loaded successfully");
         Ok(())
     }
     
+    /// Mode of the loaded policy, `None` if none is loaded
+    pub async fn mode(&self) -> Option<AgentMode> {
+        self.policy.read().await.as_ref().map(|policy| policy.mode())
+    }
+    
+    /// Switch the loaded policy between training and evaluation
+    pub async fn set_mode(&self, mode: AgentMode) -> Result<()> {
+        self.policy.write().await
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("No policy loaded"))?
+            .set_mode(mode);
+        Ok(())
+    }
+    
+    /// Action the loaded policy picks for observation `features`, already
+    /// in the policy's layout. `None` if it gave no usable confidence.
+    pub async fn choose_action(&self, features: &[f32]) -> Result<Option<usize>> {
+        let policy = self.policy.read().await;
+        let policy = policy.as_ref().ok_or_else(|| anyhow::anyhow!("No policy loaded"))?;
+        
+        let action = policy.predict(features).await?;
+        Ok(select_action(&action).map(|(idx, _)| idx))
+    }
+    
     /// Start the injector service
     pub async fn start(&self) -> Result<()> {
         if *self.is_running.read().await {
@@ -142,6 +289,7 @@ impl PolicyInjector {
         }
         
         *self.is_running.write().await = true;
+        *self.started_at.write().await = Some(self.clock.now());
         log::info!("Starting policy injector service");
         
         // Start injection loop
@@ -162,124 +310,115 @@ impl PolicyInjector {
     
     /// Main injection loop
     async fn injection_loop(&self) {
-        let mut interval = interval(Duration::from_secs(self.config.injection_interval_secs));
+        // Poll often; the clock decides when a cycle is actually due
+        let mut poll = interval(Duration::from_secs(1));
         
         while *self.is_running.read().await {
-            interval.tick().await;
-            
-            if !self.config.auto_inject {
-                continue;
-            }
-            
-            // Get system observation
-            let observation = match self.get_system_observation().await {
-                Ok(obs) => obs,
-                Err(e) => {
-                    log::error!("Failed to get system observation: {}", e);
-                    continue;
-                }
-            };
-            
-            // Get goal suggestions from policy
-            let suggestions = match self.get_goal_suggestions(&observation).await {
-                Ok(sugg) => sugg,
-                Err(e) => {
-                    log::error!("Failed to get goal suggestions: {}", e);
-                    continue;
-                }
-            };
-            
-            // Inject goals
-            for (i, suggestion) in suggestions.iter().enumerate() {
-                if i >= self.config.max_goals_per_interval {
-                    break;
-                }
-                
-                if suggestion.confidence >= self.config.confidence_threshold {
-                    if let Err(e) = self.inject_goal(suggestion).await {
-                        log::error!("Failed to inject goal: {}", e);
-                    }
-                }
-            }
-            
-            // Process feedback
-            self.process_feedback().await;
+            poll.tick().await;
+            self.tick().await;
         }
     }
     
-    /// Get current system observation
-    async fn get_system_observation(&self) -> Result<SystemObservation> {
-        use sysinfo::{System, SystemExt, CpuExt};
-        
-        let mut system = System::new_all();
-        system.refresh_all();
-        
-        // Get system metrics
-        let cpu_usage = system.global_cpu_info().cpu_usage();
-        let total_mem = system.total_memory() as f32;
-        let used_mem = system.used_memory() as f32;
-        let memory_usage = (used_mem / total_mem) * 100.0;
-        
-        // Get disk usage (simplified - just root)
-        let mut disk_usage = 0.0;
-        for disk in system.disks() {
-            if disk.mount_point() == Path::new("/") {
-                let total = disk.total_space() as f32;
-                let available = disk.available_space() as f32;
-                disk_usage = ((total - available) / total) * 100.0;
-                break;
+    /// Run an injection cycle if a full interval has passed since the last
+    /// one. Returns whether a cycle ran.
+    async fn tick(&self) -> bool {
+        let now = self.clock.now();
+        {
+            let mut last_cycle = self.last_cycle.write().await;
+            let interval = chrono::Duration::seconds(self.config.injection_interval_secs as i64);
+            if matches!(*last_cycle, Some(last) if now - last < interval) {
+                return false;
             }
+            *last_cycle = Some(now);
         }
         
-        let process_count = system.processes().len();
-        
-        // Get goal execution metrics from history
-        let history = self.injection_history.read().await;
-        let recent_goals: Vec<_> = history.iter()
-            .rev()
-            .take(10)
-            .filter_map(|r| r.feedback.as_ref())
-            .collect();
+        self.run_cycle().await;
+        true
+    }
+    
+    /// One pass of observe, suggest, inject and learn
+    async fn run_cycle(&self) {
+        if !self.config.auto_inject {
+            return;
+        }
         
-        let goal_success_rate = if !recent_goals.is_empty() {
-            let successes = recent_goals.iter().filter(|f| f.success).count();
-            successes as f32 / recent_goals.len() as f32
-        } else {
-            0.5 // Default
+        // Get system observation
+        let observation = match self.get_system_observation().await {
+            Ok(obs) => obs,
+            Err(e) => {
+                log::error!("Failed to get system observation: {}", e);
+                return;
+            }
         };
         
-        let avg_execution_time = if !recent_goals.is_empty() {
-            let total_time: u64 = recent_goals.iter().map(|f| f.execution_time_ms).sum();
-            total_time as f32 / recent_goals.len() as f32
-        } else {
-            100.0 // Default
+        // Get goal suggestions from policy
+        let suggestions = match self.get_goal_suggestions(&observation).await {
+            Ok(sugg) => sugg,
+            Err(e) => {
+                log::error!("Failed to get goal suggestions: {}", e);
+                return;
+            }
         };
         
-        let error_count = recent_goals.iter().filter(|f| !f.success).count();
+        // Inject goals
+        for suggestion in self.injectable(&suggestions).await {
+            if let Err(e) = self.inject_goal(suggestion).await {
+                log::error!("Failed to inject goal: {}", e);
+            }
+        }
         
-        // Time features
-        let now = Utc::now();
-        let time_since_last_goal = if let Some(last) = history.last() {
-            (now - last.timestamp).num_seconds() as f32
-        } else {
-            300.0 // Default 5 minutes
-        };
+        // Process feedback
+        self.process_feedback().await;
+    }
+    
+    /// Suggestions to inject now: at most `max_goals_per_interval`, confident
+    /// enough, and none during the warmup or while their category is
+    /// cooling down
+    async fn injectable<'a>(&self, suggestions: &'a [GoalSuggestion]) -> Vec<&'a GoalSuggestion> {
+        let now = self.clock.now();
+        let started_at = *self.started_at.write().await.get_or_insert(now);
+        let warmup = chrono::Duration::seconds(self.config.warmup_secs as i64);
+        if now - started_at < warmup {
+            log::debug!("Warming up until {}; not injecting", started_at + warmup);
+            return Vec::new();
+        }
         
-        let time_of_day = (now.hour() as f32 + now.minute() as f32 / 60.0) / 24.0;
-        let day_of_week = now.weekday().num_days_from_monday() as f32 / 7.0;
-        
-        Ok(SystemObservation {
-            cpu_usage,
-            memory_usage,
-            disk_usage,
-            process_count,
-            goal_success_rate,
-            avg_execution_time,
-            error_count,
-            time_since_last_goal,
-            time_of_day,
-            day_of_week,
-        })
+        let cooldown = chrono::Duration::seconds(self.config.cooldown_secs as i64);
+        let history = self.injection_history.read().await;
+        suggestions.iter()
+            .take(self.config.max_goals_per_interval)
+            .filter(|suggestion| suggestion.confidence >= self.config.confidence_threshold)
+            .filter(|suggestion| {
+                let last = history.iter().rev()
+                    .find(|record| record.injected && record.goal == suggestion.goal);
+                match last {
+                    Some(record) if now - record.timestamp < cooldown => {
+                        log::debug!("Skipping '{}': cooling down until {}", suggestion.goal, record.timestamp + cooldown);
+                        false
+                    }
+                    _ => true,
+                }
+            })
+            .collect()
+    }
+    
+    /// Get current system observation, assembled exactly as `GoalTaskEnv`
+    /// assembles it during training
+    async fn get_system_observation(&self) -> Result<SystemObservation> {
+        let host = self.host_probe.host_metrics();
+        
+        // Completed goals, oldest first
+        let history = self.injection_history.read().await;
+        let goals: Vec<GoalOutcome> = history.iter()
+            .filter_map(|r| r.feedback.as_ref())
+            .map(|f| GoalOutcome {
+                success: f.success,
+                execution_time_ms: f.execution_time_ms,
+            })
+            .collect();
+        let last_goal_at = history.last().map(|r| r.timestamp);
+        
+        Ok(SystemObservation::from_parts(&host, &goals, last_goal_at, self.clock.now()))
     }
     
     /// Get goal suggestions from policy
@@ -287,14 +426,17 @@ impl PolicyInjector {
         let policy = self.policy.read().await;
         let policy = policy.as_ref().ok_or_else(|| anyhow::anyhow!("No policy loaded"))?;
         
-        // Convert observation to tensor
-        let obs_tensor = observation_to_tensor(observation);
+        // Convert observation to tensor in the policy's training-time layout
+        let obs_tensor = self.encoder.read().await.encode(observation);
         
         // Get action from policy
         let action = policy.predict(&obs_tensor).await?;
         
-        // Convert action to goal suggestions
-        let suggestions = action_to_goals(action, observation);
+        // Convert action to goal suggestions, sampling while the policy explores
+        let seed: u64 = rand::random();
+        let explore = !policy.mode().is_eval();
+        let reward_model = self.reward_model.read().await;
+        let suggestions = action_to_goals(action, observation, &obs_tensor, &reward_model, seed, explore);
         
         Ok(suggestions)
     }
@@ -302,12 +444,14 @@ impl PolicyInjector {
     /// Inject a goal into the system
     async fn inject_goal(&self, suggestion: &GoalSuggestion) -> Result<()> {
         let goal_id = uuid::Uuid::new_v4().to_string();
+        let now = self.clock.now();
         
         let injection = json!({
             "goal_id": goal_id,
+            "seed": suggestion.seed,
             "goal": suggestion.goal.clone(),
             "source": "rl_policy",
-            "timestamp": Utc::now().to_rfc3339(),
+            "timestamp": now.to_rfc3339(),
             "reasoning": suggestion.reasoning.clone(),
             "priority": self.config.goal_priority,
             "confidence": suggestion.confidence,
@@ -318,26 +462,15 @@ impl PolicyInjector {
         });
         
         // Write to goal injection file
-        let logs_dir = Path::new("logs");
-        let injection_file = logs_dir.join("goal_injections.jsonl");
-        
-        use tokio::fs::OpenOptions;
-        use tokio::io::AsyncWriteExt;
-        
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&injection_file)
-            .await?;
-        
-        file.write_all(serde_json::to_string(&injection)?.as_bytes()).await?;
-        file.write_all(b"\n").await?;
+        crate::goal_injection::append_goal_injection_async(Path::new("logs"), &injection).await?;
         
         // Record injection
         let record = InjectionRecord {
-            timestamp: Utc::now(),
+            goal_id: goal_id.clone(),
+            timestamp: now,
             goal: suggestion.goal.clone(),
             confidence: suggestion.confidence,
+            features: suggestion.features.clone(),
             injected: true,
             feedback: None,
         };
@@ -355,21 +488,101 @@ impl PolicyInjector {
         let feedback: Vec<_> = buffer.drain(..).collect();
         drop(buffer);
         
+        let mut trained = false;
         for fb in feedback {
-            // Update injection history with feedback
+            // Update injection history with feedback: the injection it names,
+            // else the latest one of that goal still waiting for feedback
             let mut history = self.injection_history.write().await;
-            for record in history.iter_mut().rev() {
-                if record.goal == fb.goal && record.feedback.is_none() {
-                    record.feedback = Some(fb.clone());
-                    break;
+            let position = history.iter().rposition(|r| !fb.goal_id.is_empty() && r.goal_id == fb.goal_id)
+                .or_else(|| history.iter().rposition(|r| r.goal == fb.goal && r.feedback.is_none()));
+            if let Some(record) = position.map(|i| &mut history[i]) {
+                record.feedback = Some(fb.clone());
+                
+                // Learn from it when the goal maps to a known class
+                if let Some(class) = goal_class(&fb.goal) {
+                    self.reward_model.write().await.update(class, &record.features, fb.label());
+                    trained = true;
                 }
             }
+            drop(history);
             
             // Log feedback for training
             if let Err(e) = self.log_feedback(&fb).await {
                 log::error!("Failed to log feedback: {}", e);
             }
         }
+        
+        if trained {
+            if let Err(e) = self.save_state().await {
+                log::error!("Failed to save injector state: {}", e);
+            }
+        }
+    }
+    
+    /// Restore the reward model saved at `state_path`, starting fresh when
+    /// there is none or it was learned on a different observation layout
+    pub(crate) async fn load_state(&self, spec: &ObservationSpec) -> Result<()> {
+        let path = &self.config.state_path;
+        let state: Option<InjectorState> = match tokio::fs::read_to_string(path).await {
+            Ok(content) => Some(serde_json::from_str(&content)
+                .with_context(|| format!("Invalid injector state in {:?}", path))?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {:?}", path)),
+        };
+        
+        let model = match state {
+            Some(state) if state.reward_model.num_features() == spec.len()
+                && state.reward_model.num_classes() == GOAL_TEMPLATES.len() =>
+            {
+                log::info!("Restored reward model ({} updates)", state.reward_model.updates());
+                state.reward_model
+            }
+            Some(_) => {
+                log::warn!("Saved reward model does not fit the policy's observation spec; starting fresh");
+                RewardModel::new(GOAL_TEMPLATES.len(), spec.len())
+            }
+            None => RewardModel::new(GOAL_TEMPLATES.len(), spec.len()),
+        };
+        *self.reward_model.write().await = model;
+        Ok(())
+    }
+    
+    /// Learned state as it is persisted, for bundling into a snapshot
+    pub async fn export_state(&self) -> InjectorState {
+        InjectorState {
+            reward_model: self.reward_model.read().await.clone(),
+        }
+    }
+    
+    /// Fail if `state` was learned for other goals or observations
+    pub async fn check_state(&self, state: &InjectorState) -> Result<()> {
+        let num_features = self.encoder.read().await.dim();
+        let model = &state.reward_model;
+        if model.num_classes() != GOAL_TEMPLATES.len() || model.num_features() != num_features {
+            anyhow::bail!(
+                "Injector state has {} goal classes and {} features, but the injector uses {} and {}",
+                model.num_classes(), model.num_features(), GOAL_TEMPLATES.len(), num_features
+            );
+        }
+        Ok(())
+    }
+    
+    /// Replace the learned state, e.g. from a snapshot, and persist it.
+    /// Fails if `check_state` rejects it.
+    pub async fn import_state(&self, state: InjectorState) -> Result<()> {
+        self.check_state(&state).await?;
+        *self.reward_model.write().await = state.reward_model;
+        self.save_state().await
+    }
+    
+    /// Persist the reward model to `state_path`
+    async fn save_state(&self) -> Result<()> {
+        let state = self.export_state().await;
+        if let Some(parent) = self.config.state_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&self.config.state_path, serde_json::to_string_pretty(&state)?).await?;
+        Ok(())
     }
     
     /// Log feedback for future training
@@ -396,6 +609,31 @@ impl PolicyInjector {
         self.feedback_buffer.write().await.push(feedback);
     }
     
+    /// Feedback recording the operator's verdict on injection `goal_id`.
+    /// Keeps the execution result already reported for it; an injection
+    /// not yet executed counts as succeeding only if approved.
+    pub async fn review(&self, goal_id: &str, approved: bool) -> Result<GoalFeedback> {
+        let history = self.injection_history.read().await;
+        let record = history.iter().rev()
+            .find(|r| r.goal_id == goal_id)
+            .ok_or_else(|| anyhow::anyhow!("No injected goal with id {}", goal_id))?;
+        
+        let mut feedback = record.feedback.clone().unwrap_or_else(|| GoalFeedback {
+            goal_id: goal_id.to_string(),
+            goal: record.goal.clone(),
+            success: approved,
+            execution_time_ms: 0,
+            output: None,
+            error: None,
+            reward: 0.0,
+            timestamp: self.clock.now(),
+            approved: None,
+        });
+        feedback.goal_id = goal_id.to_string();
+        feedback.approved = Some(approved);
+        Ok(feedback)
+    }
+    
     /// Get injection statistics
     pub async fn get_stats(&self) -> InjectorStats {
         let history = self.injection_history.read().await;
@@ -439,13 +677,25 @@ impl Clone for PolicyInjector {
         Self {
             config: self.config.clone(),
             policy: self.policy.clone(),
+            encoder: self.encoder.clone(),
+            host_probe: self.host_probe.clone(),
+            reward_model: self.reward_model.clone(),
             is_running: self.is_running.clone(),
             injection_history: self.injection_history.clone(),
             feedback_buffer: self.feedback_buffer.clone(),
+            clock: self.clock.clone(),
+            last_cycle: self.last_cycle.clone(),
+            started_at: self.started_at.clone(),
         }
     }
 }
 
+/// Learned injector state persisted across restarts
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InjectorState {
+    pub reward_model: RewardModel,
+}
+
 /// Injector statistics
 #[derive(Debug, Clone, Serialize)]
 pub struct InjectorStats {
@@ -457,46 +707,81 @@ pub struct InjectorStats {
     pub last_injection: Option<DateTime<Utc>>,
 }
 
-/// Convert system observation to tensor
-fn observation_to_tensor(obs: &SystemObservation) -> Vec<f32> {
-    vec![
-        obs.cpu_usage / 100.0,
-        obs.memory_usage / 100.0,
-        obs.disk_usage / 100.0,
-        (obs.process_count as f32 / 1000.0).tanh(),
-        obs.goal_success_rate,
-        (obs.avg_execution_time / 1000.0).tanh(),
-        (obs.error_count as f32 / 10.0).tanh(),
-        (obs.time_since_last_goal / 300.0).tanh(),
-        obs.time_of_day,
-        obs.day_of_week,
-    ]
-}
-
-/// Convert policy action to goal suggestions
-fn action_to_goals(action: Vec<f32>, observation: &SystemObservation) -> Vec<GoalSuggestion> {
+/// Highest-confidence action and its confidence. Ties go to the lowest
+/// action index so identical policy outputs always select the same goal;
+/// NaN entries are never selected.
+fn select_action(action: &[f32]) -> Option<(usize, f32)> {
+    let mut best: Option<(usize, f32)> = None;
+    for (idx, &confidence) in action.iter().enumerate() {
+        if confidence.is_nan() {
+            continue;
+        }
+        // Strictly greater, so an equal later entry never displaces the first
+        match best {
+            Some((_, best_confidence)) if confidence <= best_confidence => {}
+            _ => best = Some((idx, confidence)),
+        }
+    }
+    best
+}
+
+/// Action drawn from the policy's distribution with `rng`, for exploration.
+/// NaN and negative entries are never drawn; without any positive weight
+/// this falls back to `select_action`.
+fn sample_action(action: &[f32], rng: &mut StdRng) -> Option<(usize, f32)> {
+    let weights: Vec<f32> = action.iter()
+        .map(|&p| if p.is_nan() || p < 0.0 { 0.0 } else { p })
+        .collect();
+    match WeightedIndex::new(&weights) {
+        Ok(dist) => {
+            let idx = dist.sample(rng);
+            Some((idx, action[idx]))
+        }
+        Err(_) => select_action(action),
+    }
+}
+
+/// Goal templates (goal, reasoning) indexed by policy action
+const GOAL_TEMPLATES: [(&str, &str); 10] = [
+    ("Monitor disk I/O activity", "High disk usage detected"),
+    ("Check memory usage patterns", "Memory optimization needed"),
+    ("Analyze CPU load distribution", "CPU usage requires attention"),
+    ("Review network connections", "Network monitoring suggested"),
+    ("Scan system logs for errors", "Error detection required"),
+    ("Verify service health status", "Service health check needed"),
+    ("Check disk space usage", "Disk space monitoring needed"),
+    ("Monitor process count", "Process management suggested"),
+    ("Analyze system performance", "Performance analysis needed"),
+    ("Review security events", "Security monitoring suggested"),
+];
+
+/// Action index of the template a goal was made from
+fn goal_class(goal: &str) -> Option<usize> {
+    GOAL_TEMPLATES.iter().position(|(template, _)| *template == goal)
+}
+
+/// Convert policy action to goal suggestions, scoring each with the
+/// learned reward model. When exploring the action is sampled with an RNG
+/// seeded from `seed`, so the same seed replays the same choice; otherwise
+/// the most confident action is taken.
+fn action_to_goals(
+    action: Vec<f32>,
+    observation: &SystemObservation,
+    features: &[f32],
+    reward_model: &RewardModel,
+    seed: u64,
+    explore: bool,
+) -> Vec<GoalSuggestion> {
     let mut suggestions = Vec::new();
     
-    // Goal templates based on action indices
-    let goal_templates = vec![
-        ("Monitor disk I/O activity", "High disk usage detected"),
-        ("Check memory usage patterns", "Memory optimization needed"),
-        ("Analyze CPU load distribution", "CPU usage requires attention"),
-        ("Review network connections", "Network monitoring suggested"),
-        ("Scan system logs for errors", "Error detection required"),
-        ("Verify service health status", "Service health check needed"),
-        ("Check disk space usage", "Disk space monitoring needed"),
-        ("Monitor process count", "Process management suggested"),
-        ("Analyze system performance", "Performance analysis needed"),
-        ("Review security events", "Security monitoring suggested"),
-    ];
-    
-    // Find highest confidence action
-    if let Some((idx, &confidence)) = action.iter().enumerate().max_by(|(_, a), (_, b)| {
-        a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)
-    }) {
-        if idx < goal_templates.len() {
-            let (goal, reasoning) = goal_templates[idx];
+    let selected = if explore {
+        sample_action(&action, &mut StdRng::seed_from_u64(seed))
+    } else {
+        select_action(&action)
+    };
+    if let Some((idx, confidence)) = selected {
+        if idx < GOAL_TEMPLATES.len() {
+            let (goal, reasoning) = GOAL_TEMPLATES[idx];
             
             // Adjust reasoning based on observation
             let reasoning = format!("{} (CPU: {:.1}%, Mem: {:.1}%, Disk: {:.1}%)",
@@ -506,11 +791,13 @@ fn action_to_goals(action: Vec<f32>, observation: &SystemObservation) -> Vec<Goa
                 goal: goal.to_string(),
                 confidence,
                 reasoning,
-                expected_reward: confidence * observation.goal_success_rate,
+                expected_reward: reward_model.predict(idx, features),
                 metadata: json!({
                     "action_idx": idx,
                     "observation": observation,
                 }),
+                features: features.to_vec(),
+                seed,
             });
         }
     }
@@ -518,34 +805,36 @@ fn action_to_goals(action: Vec<f32>, observation: &SystemObservation) -> Vec<Goa
     suggestions
 }
 
-/// Mock policy for testing
-struct MockPolicy;
+/// PPO policy restored from a training checkpoint
+struct CheckpointPolicy {
+    agent: PPOAgentFull,
+}
 
-impl MockPolicy {
-    fn new() -> Self {
-        Self
+impl CheckpointPolicy {
+    /// Load the weights saved at `path` into a policy of the given shape
+    async fn load(path: &Path, observation_dim: usize, action_dim: usize) -> Result<Self> {
+        let agent = PPOAgentFull::new(PPOConfig::default(), observation_dim, action_dim).await?;
+        agent.load_bin(path).await
+            .with_context(|| format!("Failed to load policy weights from {:?}", path))?;
+        Ok(Self { agent })
     }
 }
 
 #[async_trait::async_trait]
-impl Policy for MockPolicy {
+impl Policy for CheckpointPolicy {
     async fn predict(&self, observation: &[f32]) -> Result<Vec<f32>> {
-        // Simple heuristic policy for testing
-        let mut action = vec![0.1; 10];
-        
-        // Prioritize based on system state
-        if observation[0] > 0.8 { // High CPU
-            action[2] = 0.9; // Analyze CPU
-        } else if observation[1] > 0.8 { // High memory
-            action[1] = 0.9; // Check memory
-        } else if observation[2] > 0.8 { // High disk
-            action[0] = 0.9; // Monitor disk
-        } else {
-            // Default to system performance analysis
-            action[8] = 0.7;
-        }
-        
-        Ok(action)
+        let observation = VectorObservation {
+            data: observation.iter().map(|&x| f64::from(x)).collect(),
+        };
+        self.agent.action_probabilities(&observation).await
+    }
+    
+    fn set_mode(&mut self, mode: AgentMode) {
+        self.agent.set_mode(mode);
+    }
+    
+    fn mode(&self) -> AgentMode {
+        self.agent.mode()
     }
 }
 
@@ -553,6 +842,12 @@ impl Policy for MockPolicy {
 #[async_trait::async_trait]
 trait Policy: Send + Sync {
     async fn predict(&self, observation: &[f32]) -> Result<Vec<f32>>;
+    
+    /// Switch exploration and learning on or off
+    fn set_mode(&mut self, mode: AgentMode);
+    
+    /// Whether the policy explores and learns
+    fn mode(&self) -> AgentMode;
 }
 
 /// Global policy injector instance
@@ -597,6 +892,16 @@ pub async fn add_goal_feedback(feedback: GoalFeedback) -> Result<()> {
     Ok(())
 }
 
+/// Record the operator approving or rejecting injected goal `goal_id`
+pub async fn review_goal(goal_id: &str, approved: bool) -> Result<()> {
+    let feedback = {
+        let injector = POLICY_INJECTOR.read().await;
+        let inj = injector.as_ref().ok_or_else(|| anyhow::anyhow!("Policy injector not initialized"))?;
+        inj.review(goal_id, approved).await?
+    };
+    add_goal_feedback(feedback).await
+}
+
 /// Get injector statistics
 pub async fn get_injector_stats() -> Option<InjectorStats> {
     let injector = POLICY_INJECTOR.read().await;
@@ -605,4 +910,459 @@ pub async fn get_injector_stats() -> Option<InjectorStats> {
     } else {
         None
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedHost(HostMetrics);
+
+    impl HostProbe for FixedHost {
+        fn host_metrics(&self) -> HostMetrics {
+            self.0
+        }
+    }
+
+    fn observation() -> SystemObservation {
+        SystemObservation {
+            cpu_usage: 20.0,
+            memory_usage: 30.0,
+            disk_usage: 40.0,
+            process_count: 100,
+            goal_success_rate: 0.5,
+            avg_execution_time: 100.0,
+            error_count: 0,
+            time_since_last_goal: 300.0,
+            time_of_day: 0.5,
+            day_of_week: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_tied_confidences_select_lowest_index() {
+        let mut action = vec![0.1; 10];
+        action[3] = 0.8;
+        action[6] = 0.8;
+        action[9] = 0.8;
+        let model = RewardModel::new(GOAL_TEMPLATES.len(), 0);
+
+        for _ in 0..10 {
+            let suggestions = action_to_goals(action.clone(), &observation(), &[], &model, 0, false);
+            assert_eq!(suggestions.len(), 1);
+            assert_eq!(suggestions[0].goal, "Review network connections");
+            assert_eq!(suggestions[0].metadata["action_idx"], 3);
+        }
+
+        assert_eq!(select_action(&[0.5, f32::NAN, 0.5]), Some((0, 0.5)));
+        assert_eq!(select_action(&[f32::NAN, 0.2]), Some((1, 0.2)));
+        assert_eq!(select_action(&[]), None);
+    }
+
+    #[test]
+    fn test_exploring_selection_replays_from_seed() {
+        let mut action = vec![0.0; 10];
+        action[2] = 0.3;
+        action[5] = 0.3;
+        action[8] = 0.4;
+        let model = RewardModel::new(GOAL_TEMPLATES.len(), 0);
+        let choose = |seed: u64| {
+            let suggestions = action_to_goals(action.clone(), &observation(), &[], &model, seed, true);
+            assert_eq!(suggestions.len(), 1);
+            assert_eq!(suggestions[0].seed, seed);
+            suggestions[0].metadata["action_idx"].as_u64().unwrap()
+        };
+
+        let mut chosen = std::collections::BTreeSet::new();
+        for seed in 0..50 {
+            let idx = choose(seed);
+            assert_eq!(choose(seed), idx, "seed {} replayed a different action", seed);
+            chosen.insert(idx);
+        }
+        // Only actions the policy gives weight to are drawn, and not just one
+        assert!(chosen.iter().all(|idx| [2, 5, 8].contains(idx)), "{:?}", chosen);
+        assert!(chosen.len() > 1, "{:?}", chosen);
+    }
+
+    #[tokio::test]
+    async fn test_operator_approvals_raise_learned_reward() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = PolicyInjectorConfig {
+            state_path: dir.path().join("injector_state.json"),
+            ..Default::default()
+        };
+        let injector = PolicyInjector::new(config.clone());
+        let features = ObservationSpec::default().build(&observation()).unwrap();
+        let (goal, _) = GOAL_TEMPLATES[2];
+
+        let mut previous = injector.reward_model.read().await.predict(2, &features);
+        for _ in 0..20 {
+            injector.injection_history.write().await.push(InjectionRecord {
+                goal_id: String::new(),
+                timestamp: Utc::now(),
+                goal: goal.to_string(),
+                confidence: 0.9,
+                features: features.clone(),
+                injected: true,
+                feedback: None,
+            });
+            // The operator's approval outweighs the failed execution
+            injector.add_feedback(GoalFeedback {
+                goal_id: uuid::Uuid::new_v4().to_string(),
+                goal: goal.to_string(),
+                success: false,
+                execution_time_ms: 100,
+                output: None,
+                error: None,
+                reward: 0.0,
+                timestamp: Utc::now(),
+                approved: Some(true),
+            }).await;
+            injector.process_feedback().await;
+
+            let predicted = injector.reward_model.read().await.predict(2, &features);
+            assert!(predicted > previous, "{} <= {}", predicted, previous);
+            previous = predicted;
+        }
+
+        let mut action = vec![0.1; 10];
+        action[2] = 0.9;
+        let suggestions = action_to_goals(
+            action,
+            &observation(),
+            &features,
+            &*injector.reward_model.read().await,
+            0,
+            false,
+        );
+        assert_eq!(suggestions[0].expected_reward, previous);
+
+        // The learned model survives a restart
+        let restored = PolicyInjector::new(config);
+        restored.load_state(&ObservationSpec::default()).await.unwrap();
+        assert_eq!(restored.reward_model.read().await.predict(2, &features), previous);
+        assert_eq!(restored.reward_model.read().await.updates(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_operator_verdict_updates_executed_goal() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let injector = PolicyInjector::new(PolicyInjectorConfig {
+            state_path: dir.path().join("injector_state.json"),
+            ..Default::default()
+        });
+        let features = ObservationSpec::default().build(&observation()).unwrap();
+        let (goal, _) = GOAL_TEMPLATES[4];
+        for goal_id in ["goal-1", "goal-2"] {
+            injector.injection_history.write().await.push(InjectionRecord {
+                goal_id: goal_id.to_string(),
+                timestamp: Utc::now(),
+                goal: goal.to_string(),
+                confidence: 0.9,
+                features: features.clone(),
+                injected: true,
+                feedback: None,
+            });
+        }
+        assert!(injector.review("goal-3", true).await.is_err());
+
+        // The older injection ran fine, then the operator rejected it
+        injector.add_feedback(GoalFeedback {
+            goal_id: "goal-1".to_string(),
+            goal: goal.to_string(),
+            success: true,
+            execution_time_ms: 120,
+            output: Some("ok".to_string()),
+            error: None,
+            reward: 1.0,
+            timestamp: Utc::now(),
+            approved: None,
+        }).await;
+        injector.process_feedback().await;
+        let before = injector.reward_model.read().await.predict(4, &features);
+
+        let verdict = injector.review("goal-1", false).await.unwrap();
+        assert!(verdict.success);
+        assert_eq!(verdict.execution_time_ms, 120);
+        assert_eq!(verdict.approved, Some(false));
+        injector.add_feedback(verdict).await;
+        injector.process_feedback().await;
+
+        assert!(injector.reward_model.read().await.predict(4, &features) < before);
+        let history = injector.injection_history.read().await;
+        assert_eq!(history[0].feedback.as_ref().unwrap().approved, Some(false));
+        assert!(history[1].feedback.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_with_different_spec_is_rejected() {
+        use crate::rl_training::{RLTrainingConfig, TrainingSession};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let save = |spec: Option<ObservationSpec>, id: &'static str| {
+            let config = RLTrainingConfig {
+                // Legacy goal-task checkpoints were trained on 64 features
+                observation_dim: spec.as_ref().map_or(64, ObservationSpec::len),
+                observation_spec: spec,
+                ..Default::default()
+            };
+            let session = TrainingSession::with_checkpoint_dir(config, dir.path().to_path_buf());
+            async move { session.write_checkpoint_metadata(id, 0).await.unwrap() }
+        };
+        let checkpoint = |id: &str| dir.path().join(format!("{}.bin", id));
+
+        // A reordered layout is rebuilt in the checkpoint's order
+        let mut reordered = ObservationSpec::default();
+        reordered.features.swap(0, 1);
+        save(Some(reordered.clone()), "checkpoint_ep0").await;
+        let spec = load_observation_spec(&checkpoint("checkpoint_ep0")).await.unwrap();
+        assert_eq!(spec, reordered);
+        let obs = spec.build(&observation()).unwrap();
+        assert_eq!(&obs[..2], &[0.3, 0.2]);
+
+        let mut extra = ObservationSpec::default();
+        extra.features.push("gpu_usage".to_string());
+        save(Some(extra), "checkpoint_ep1").await;
+        let injector = PolicyInjector::new(PolicyInjectorConfig {
+            checkpoint_path: checkpoint("checkpoint_ep1"),
+            ..Default::default()
+        });
+        let err = format!("{:#}", injector.load_policy().await.unwrap_err());
+        assert!(err.contains("checkpoint_ep1"), "{}", err);
+        assert!(err.contains("cannot build: gpu_usage"), "{}", err);
+        assert!(injector.policy.read().await.is_none());
+
+        let newer = ObservationSpec {
+            version: OBSERVATION_SPEC_VERSION + 1,
+            ..Default::default()
+        };
+        save(Some(newer), "checkpoint_ep2").await;
+        let err = load_observation_spec(&checkpoint("checkpoint_ep2")).await.unwrap_err();
+        let err = format!("{:#}", err);
+        assert!(err.contains("version mismatch"), "{}", err);
+
+        // Legacy metadata without a spec is only trusted if the dimension agrees
+        save(None, "checkpoint_ep3").await;
+        let err = load_observation_spec(&checkpoint("checkpoint_ep3")).await.unwrap_err();
+        let err = format!("{:#}", err);
+        assert!(err.contains("trained on 64 features"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_loaded_policy_uses_checkpoint_weights() {
+        use crate::rl_training::{RLTrainingConfig, TrainingSession};
+        use sentient_rl_core::Policy as _;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let spec = ObservationSpec::default();
+        let session = TrainingSession::with_checkpoint_dir(
+            RLTrainingConfig {
+                observation_dim: spec.len(),
+                action_dim: GOAL_TEMPLATES.len(),
+                observation_spec: Some(spec.clone()),
+                ..Default::default()
+            },
+            dir.path().to_path_buf(),
+        );
+        let agent = PPOAgentFull::new_seeded(PPOConfig::default(), spec.len(), GOAL_TEMPLATES.len(), 7)
+            .await
+            .unwrap();
+        agent.set_mode(AgentMode::Eval);
+        session.write_checkpoint_metadata("checkpoint_ep0", 0).await.unwrap();
+        agent.save_bin(&dir.path().join("checkpoint_ep0.bin")).await.unwrap();
+
+        let injector = PolicyInjector::new(PolicyInjectorConfig {
+            checkpoint_path: dir.path().join("checkpoint_ep0.bin"),
+            ..Default::default()
+        });
+        injector.load_policy().await.unwrap();
+        assert_eq!(injector.mode().await, Some(AgentMode::Eval));
+
+        for cpu_usage in [5.0, 50.0, 95.0] {
+            let features = spec.build(&SystemObservation { cpu_usage, ..observation() }).unwrap();
+            let obs = VectorObservation {
+                data: features.iter().map(|&x| f64::from(x)).collect(),
+            };
+            let policy = injector.policy.read().await;
+            assert_eq!(
+                policy.as_ref().unwrap().predict(&features).await.unwrap(),
+                agent.action_probabilities(&obs).await.unwrap()
+            );
+            drop(policy);
+            assert_eq!(
+                injector.choose_action(&features).await.unwrap(),
+                Some(agent.act(&obs).await.unwrap().0)
+            );
+        }
+
+        injector.set_mode(AgentMode::Train).await.unwrap();
+        assert_eq!(injector.mode().await, Some(AgentMode::Train));
+
+        // Metadata alone is not a policy
+        session.write_checkpoint_metadata("checkpoint_ep1", 1).await.unwrap();
+        let injector = PolicyInjector::new(PolicyInjectorConfig {
+            checkpoint_path: dir.path().join("checkpoint_ep1.bin"),
+            ..Default::default()
+        });
+        let err = format!("{:#}", injector.load_policy().await.unwrap_err());
+        assert!(err.contains("Failed to load policy weights"), "{}", err);
+        assert!(injector.policy.read().await.is_none());
+        assert_eq!(injector.mode().await, None);
+        assert!(injector.set_mode(AgentMode::Eval).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_injection_timing_follows_the_clock() {
+        use crate::clock::MockClock;
+        use chrono::Duration as ChronoDuration;
+
+        let clock = MockClock::default();
+        let injector = PolicyInjector::new(PolicyInjectorConfig {
+            injection_interval_secs: 30,
+            ..Default::default()
+        })
+        .with_clock(Arc::new(clock.clone()))
+        .with_host_probe(Arc::new(FixedHost(HostMetrics::default())));
+        let since_last_goal = || async {
+            injector.get_system_observation().await.unwrap().time_since_last_goal
+        };
+
+        // The first cycle runs straight away, the next one a full interval later
+        assert!(injector.tick().await);
+        assert!(!injector.tick().await);
+        clock.advance(ChronoDuration::seconds(29));
+        assert!(!injector.tick().await);
+        clock.advance(ChronoDuration::seconds(1));
+        assert!(injector.tick().await);
+        clock.advance(ChronoDuration::minutes(10));
+        assert!(injector.tick().await);
+        assert!(!injector.tick().await);
+
+        assert_eq!(since_last_goal().await, 300.0);
+        injector.injection_history.write().await.push(InjectionRecord {
+            goal_id: String::new(),
+            timestamp: clock.now(),
+            goal: GOAL_TEMPLATES[0].0.to_string(),
+            confidence: 0.9,
+            features: Vec::new(),
+            injected: true,
+            feedback: None,
+        });
+        assert_eq!(since_last_goal().await, 0.0);
+        clock.advance(ChronoDuration::seconds(90));
+        assert_eq!(since_last_goal().await, 90.0);
+    }
+
+    #[tokio::test]
+    async fn test_live_observation_matches_training_encoding() {
+        use crate::clock::MockClock;
+        use chrono::Duration as ChronoDuration;
+
+        let host = HostMetrics {
+            cpu_usage: 42.0,
+            memory_usage: 61.5,
+            disk_usage: 80.0,
+            process_count: 312,
+        };
+        let clock = MockClock::default();
+        let injector = PolicyInjector::new(PolicyInjectorConfig::default())
+            .with_clock(Arc::new(clock.clone()))
+            .with_host_probe(Arc::new(FixedHost(host)));
+
+        // Twelve finished goals, and a newer injection still awaiting feedback
+        let goals: Vec<GoalOutcome> = (0..12)
+            .map(|i| GoalOutcome { success: i % 3 != 0, execution_time_ms: 50 * i })
+            .collect();
+        for goal in &goals {
+            let feedback = GoalFeedback {
+                goal_id: String::new(),
+                goal: GOAL_TEMPLATES[0].0.to_string(),
+                success: goal.success,
+                execution_time_ms: goal.execution_time_ms,
+                output: None,
+                error: None,
+                reward: 0.0,
+                timestamp: clock.now(),
+                approved: None,
+            };
+            injector.injection_history.write().await.push(InjectionRecord {
+                goal_id: String::new(),
+                timestamp: clock.now(),
+                goal: feedback.goal.clone(),
+                confidence: 0.9,
+                features: Vec::new(),
+                injected: true,
+                feedback: Some(feedback),
+            });
+        }
+        let last_goal_at = clock.now();
+        injector.injection_history.write().await.push(InjectionRecord {
+            goal_id: String::new(),
+            timestamp: last_goal_at,
+            goal: GOAL_TEMPLATES[1].0.to_string(),
+            confidence: 0.9,
+            features: Vec::new(),
+            injected: true,
+            feedback: None,
+        });
+        clock.advance(ChronoDuration::seconds(90));
+
+        // What `GoalTaskEnv` feeds the policy for the same history and host
+        let expected = SystemObservationEncoder::default()
+            .encode(&SystemObservation::from_parts(&host, &goals, Some(last_goal_at), clock.now()));
+        let observation = injector.get_system_observation().await.unwrap();
+        assert_eq!(injector.encoder.read().await.encode(&observation), expected);
+    }
+
+    #[tokio::test]
+    async fn test_warmup_and_cooldown_hold_back_injections() {
+        use crate::clock::MockClock;
+        use chrono::Duration as ChronoDuration;
+
+        let clock = MockClock::default();
+        let injector = PolicyInjector::new(PolicyInjectorConfig {
+            max_goals_per_interval: 2,
+            warmup_secs: 60,
+            cooldown_secs: 300,
+            ..Default::default()
+        })
+        .with_clock(Arc::new(clock.clone()));
+        let suggest = |goal: &str| GoalSuggestion {
+            goal: goal.to_string(),
+            confidence: 0.9,
+            reasoning: String::new(),
+            expected_reward: 0.0,
+            metadata: serde_json::Value::Null,
+            features: Vec::new(),
+            seed: 0,
+        };
+        let disk = GOAL_TEMPLATES[0].0;
+        let memory = GOAL_TEMPLATES[1].0;
+        let suggestions = vec![suggest(disk), suggest(memory)];
+        let injectable = || async {
+            injector.injectable(&suggestions).await.iter().map(|s| s.goal.clone()).collect::<Vec<_>>()
+        };
+
+        // Nothing during the warmup, however confident the policy is
+        assert!(injectable().await.is_empty());
+        clock.advance(ChronoDuration::seconds(59));
+        assert!(injectable().await.is_empty());
+        clock.advance(ChronoDuration::seconds(1));
+        assert_eq!(injectable().await, vec![disk, memory]);
+
+        // A disk goal went out; only the memory category is free for a while
+        injector.injection_history.write().await.push(InjectionRecord {
+            goal_id: String::new(),
+            timestamp: clock.now(),
+            goal: disk.to_string(),
+            confidence: 0.9,
+            features: Vec::new(),
+            injected: true,
+            feedback: None,
+        });
+        clock.advance(ChronoDuration::seconds(299));
+        assert_eq!(injectable().await, vec![memory]);
+        clock.advance(ChronoDuration::seconds(1));
+        assert_eq!(injectable().await, vec![disk, memory]);
+    }
+}
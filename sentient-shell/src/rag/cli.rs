@@ -35,20 +35,20 @@ pub fn handle_rag_command(args: &[&str]) -> Result<String> {
         "init" => init_rag(),
         "index" => {
             if args.len() < 2 {
-                return Ok("Usage: rag index <docs|logs|memory|all>".to_string());
+                anyhow::bail!("Usage: rag index <docs|logs|memory|all>");
             }
             index_command(&args[1..])
         }
         "query" | "ask" => {
             if args.len() < 2 {
-                return Ok("Usage: rag query <question>".to_string());
+                anyhow::bail!("Usage: rag query <question>");
             }
             let query = args[1..].join(" ");
             query_command(&query)
         }
         "stats" => stats_command(),
         "help" => Ok(rag_help()),
-        _ => Ok(format!("Unknown RAG command: {}. Try 'rag help'", args[0])),
+        _ => anyhow::bail!("Unknown RAG command: {}. Try 'rag help'", args[0]),
     }
 }
 
@@ -153,7 +153,7 @@ fn index_command(args: &[&str]) -> Result<String> {
                 .and_then(|s| s.parse::<usize>().ok())
                 .unwrap_or(0);
         }
-        _ => return Ok(format!("Unknown index type: {}", args[0])),
+        _ => anyhow::bail!("Unknown index type: {}", args[0]),
     }
     
     // Save index
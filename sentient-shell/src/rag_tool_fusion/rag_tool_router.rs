@@ -94,7 +94,8 @@ impl RagToolRouter {
     
     pub async fn execute(&mut self, prompt: &str, explain: bool) -> Result<ExecutionPipeline> {
         let start = Instant::now();
-        let trace_id = uuid::Uuid::new_v4().to_string();
+        // Share the id of the shell command that asked, if there is one
+        let trace_id = crate::trace_id::current().unwrap_or_else(crate::trace_id::new_trace_id);
         
         // Detect hybrid intent
         let intent = self.detect_hybrid_intent(prompt).await?;
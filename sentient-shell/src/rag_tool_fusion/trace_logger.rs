@@ -1,5 +1,6 @@
 use anyhow::{Result, Context};
 use chrono::{DateTime, Utc};
+use sentient_rl_env::TraceHeader;
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use tokio::fs::{self, OpenOptions};
@@ -30,6 +31,21 @@ pub struct ExecutionTrace {
 pub struct TraceLogger {
     log_path: PathBuf,
     file_mutex: Arc<Mutex<()>>,
+    /// Written as the first line of a new log
+    header: TraceHeader,
+}
+
+/// Entries of a trace log, after its header line if it has one. The
+/// header's schema and version are validated.
+fn parse_entries(content: &str) -> Result<Vec<TraceEntry>> {
+    let mut lines = content.lines().filter(|line| !line.is_empty()).peekable();
+    if lines.peek().map(|line| TraceHeader::parse(line)).transpose()?.flatten().is_some() {
+        lines.next();
+    }
+    lines
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to parse trace entries")
 }
 
 impl TraceLogger {
@@ -46,6 +62,7 @@ impl TraceLogger {
         Ok(Self {
             log_path,
             file_mutex: Arc::new(Mutex::new(())),
+            header: TraceHeader::default(),
         })
     }
     
@@ -55,6 +72,7 @@ impl TraceLogger {
         let json_line = serde_json::to_string(&entry)
             .context("Failed to serialize trace entry")?;
         
+        let is_new = fs::metadata(&self.log_path).await.map_or(true, |m| m.len() == 0);
         let mut file = OpenOptions::new()
             .create(true)
             .append(true)
@@ -62,6 +80,10 @@ impl TraceLogger {
             .await
             .context("Failed to open trace log file")?;
         
+        if is_new {
+            file.write_all(self.header.to_line()?.as_bytes()).await?;
+            file.write_all(b"\n").await?;
+        }
         file.write_all(json_line.as_bytes()).await?;
         file.write_all(b"\n").await?;
         file.flush().await?;
@@ -77,12 +99,7 @@ impl TraceLogger {
             .await
             .context("Failed to read trace log")?;
         
-        let mut entries: Vec<TraceEntry> = content
-            .lines()
-            .filter(|line| !line.is_empty())
-            .map(|line| serde_json::from_str(line))
-            .collect::<Result<Vec<_>, _>>()
-            .context("Failed to parse trace entries")?;
+        let mut entries = parse_entries(&content)?;
         
         // Update the specific entry
         let mut updated = false;
@@ -106,6 +123,8 @@ impl TraceLogger {
             .await
             .context("Failed to open trace log for writing")?;
         
+        file.write_all(self.header.to_line()?.as_bytes()).await?;
+        file.write_all(b"\n").await?;
         for entry in entries {
             let json_line = serde_json::to_string(&entry)?;
             file.write_all(json_line.as_bytes()).await?;
@@ -122,12 +141,7 @@ impl TraceLogger {
             .await
             .unwrap_or_default();
         
-        let entries: Vec<TraceEntry> = content
-            .lines()
-            .filter(|line| !line.is_empty())
-            .map(|line| serde_json::from_str(line))
-            .collect::<Result<Vec<_>, _>>()
-            .context("Failed to parse trace entries")?;
+        let entries = parse_entries(&content)?;
         
         Ok(ExecutionTrace { entries })
     }
@@ -249,5 +263,49 @@ mod tests {
         
         let traces = logger.load_traces().await.unwrap();
         assert_eq!(traces.entries[0].reward, Some(0.8));
+        
+        // The log starts with a header, kept when rewards are rewritten
+        let content = fs::read_to_string(&log_path).await.unwrap();
+        let first = content.lines().next().unwrap();
+        assert_eq!(TraceHeader::parse(first).unwrap(), Some(TraceHeader::default()));
+    }
+    
+    #[tokio::test]
+    async fn test_loads_migrated_trace_file() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("v1_trace.jsonl");
+        
+        let entry = TraceEntry {
+            trace_id: "v1-entry".to_string(),
+            timestamp: Utc::now(),
+            prompt: "Old prompt".to_string(),
+            intent: "PureQuery".to_string(),
+            model_used: "phi2_local".to_string(),
+            tool_executed: None,
+            rag_used: false,
+            conditions_evaluated: vec![],
+            success: true,
+            duration_ms: 80,
+            reward: None,
+        };
+        fs::write(&log_path, format!("{}\n", serde_json::to_string(&entry).unwrap()))
+            .await
+            .unwrap();
+        
+        let header = TraceHeader::default();
+        sentient_rl_env::JSONLEnv::migrate_trace_file(&log_path, header.obs_dim, header.action_dim)
+            .await
+            .unwrap();
+        
+        let logger = TraceLogger::new(&log_path).await.unwrap();
+        let traces = logger.load_traces().await.unwrap();
+        assert_eq!(traces.entries.len(), 1);
+        assert_eq!(traces.entries[0].trace_id, "v1-entry");
+        
+        // Appending to the migrated log doesn't add a second header
+        logger.log(TraceEntry { trace_id: "v2-entry".to_string(), ..entry }).await.unwrap();
+        let content = fs::read_to_string(&log_path).await.unwrap();
+        assert_eq!(content.lines().filter(|line| line.contains("_schema")).count(), 1);
+        assert_eq!(logger.load_traces().await.unwrap().entries.len(), 2);
     }
 }
\ No newline at end of file
@@ -0,0 +1,117 @@
+// Feedback Reward Model - learns which injected goals are worth proposing
+// Online logistic regression over the goal class and the policy observation
+
+use serde::{Deserialize, Serialize};
+
+/// Step size used by [`RewardModel::new`]
+pub const DEFAULT_LEARNING_RATE: f32 = 0.1;
+
+/// Logistic model of the probability that an injected goal is approved
+/// and succeeds, given its goal class and the observation it was made from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewardModel {
+    /// Per-goal-class logit offset
+    class_weights: Vec<f32>,
+    /// Observation feature weights, shared by all classes
+    feature_weights: Vec<f32>,
+    bias: f32,
+    learning_rate: f32,
+    /// Feedback samples learned from so far
+    updates: u64,
+}
+
+impl RewardModel {
+    /// Untrained model predicting 0.5 for everything
+    pub fn new(num_classes: usize, num_features: usize) -> Self {
+        Self {
+            class_weights: vec![0.0; num_classes],
+            feature_weights: vec![0.0; num_features],
+            bias: 0.0,
+            learning_rate: DEFAULT_LEARNING_RATE,
+            updates: 0,
+        }
+    }
+
+    pub fn with_learning_rate(mut self, learning_rate: f32) -> Self {
+        self.learning_rate = learning_rate;
+        self
+    }
+
+    pub fn num_classes(&self) -> usize {
+        self.class_weights.len()
+    }
+
+    pub fn num_features(&self) -> usize {
+        self.feature_weights.len()
+    }
+
+    pub fn updates(&self) -> u64 {
+        self.updates
+    }
+
+    /// Predicted reward in `[0, 1]` for proposing `class` under `features`.
+    /// Unknown classes get no class offset; extra features are ignored.
+    pub fn predict(&self, class: usize, features: &[f32]) -> f32 {
+        sigmoid(self.logit(class, features))
+    }
+
+    /// One SGD step on the log loss towards `label` (1.0 = approved and
+    /// successful, 0.0 = ignored or failed). Returns the prediction before
+    /// the update.
+    pub fn update(&mut self, class: usize, features: &[f32], label: f32) -> f32 {
+        let prediction = self.predict(class, features);
+        let step = self.learning_rate * (label - prediction);
+
+        if let Some(weight) = self.class_weights.get_mut(class) {
+            *weight += step;
+        }
+        for (weight, &x) in self.feature_weights.iter_mut().zip(features) {
+            *weight += step * x;
+        }
+        self.bias += step;
+        self.updates += 1;
+
+        prediction
+    }
+
+    fn logit(&self, class: usize, features: &[f32]) -> f32 {
+        let class_weight = self.class_weights.get(class).copied().unwrap_or(0.0);
+        let dot: f32 = self
+            .feature_weights
+            .iter()
+            .zip(features)
+            .map(|(w, x)| w * x)
+            .sum();
+        self.bias + class_weight + dot
+    }
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consistent_approvals_raise_predicted_reward() {
+        let mut model = RewardModel::new(10, 3);
+        let features = [0.2, 0.5, 0.9];
+        assert!((model.predict(2, &features) - 0.5).abs() < 1e-6);
+
+        let mut previous = model.predict(2, &features);
+        for _ in 0..200 {
+            model.update(2, &features, 1.0);
+            model.update(5, &features, 0.0);
+
+            let approved = model.predict(2, &features);
+            assert!(approved > previous, "{} <= {}", approved, previous);
+            previous = approved;
+        }
+
+        assert!(previous > 0.9, "approved class only reached {}", previous);
+        assert!(model.predict(5, &features) < 0.1);
+        assert_eq!(model.updates(), 400);
+    }
+}
@@ -4,18 +4,31 @@
 use anyhow::{Result, Context};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 use tokio::fs;
+use tokio_util::sync::CancellationToken;
 use serde_json::json;
 
-// Import RL components (these would come from the crates)
-// use sentient_rl_core::{Agent, Environment};
-// use sentient_rl_agent::{PPOAgent, PPOConfig};
-// use sentient_rl_env::{GoalTaskEnv, GoalTaskEnvConfig};
+// Import RL components
+use sentient_rl_core::{
+    check_compatibility, ActionSpace, AgentConfig, AgentSpaces, DiscreteAction, Environment,
+    ObservationSpace, SpaceSpec, Step, StepInfo, Trajectory, Transition, VectorObservation,
+    VectorState,
+};
+use sentient_rl_agent::ppo_full::PPOAgentFull;
+use sentient_rl_agent::PPOConfig;
+use crate::policy_injector::ObservationSpec;
+use sentient_rl_env::registry::BoxedEnv;
+use sentient_rl_env::{GoalTaskEnv, GoalTaskEnvConfig, JSONLEnv, JSONLEnvConfig};
 // use sentient_memory::RLMemoryStore;
 
+/// Where each training episode's steps are written as `<episode>.json`,
+/// for `rl trace export`
+pub const EPISODE_TRACE_DIR: &str = "logs/rl_episodes";
+
 /// RL Training Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RLTrainingConfig {
@@ -25,11 +38,21 @@ pub struct RLTrainingConfig {
     pub steps_per_rollout: usize,
     pub checkpoint_interval: usize,
     pub log_interval: usize,
+    /// Training stops once the mean per-step reward over the last
+    /// `convergence_window` episodes reaches this value
     pub reward_goal_threshold: f32,
+    #[serde(default = "default_convergence_window")]
+    pub convergence_window: usize,
     pub observation_dim: usize,
     pub action_dim: usize,
     pub learning_rate: f32,
     pub trace_file: Option<PathBuf>,
+    /// Checkpoint to continue from: an id, "latest" or "best"
+    #[serde(default)]
+    pub resume_from: Option<String>,
+    /// Feature layout of the observations, recorded in each checkpoint
+    #[serde(default)]
+    pub observation_spec: Option<ObservationSpec>,
 }
 
 impl Default for RLTrainingConfig {
@@ -42,14 +65,24 @@ impl Default for RLTrainingConfig {
             checkpoint_interval: 100,
             log_interval: 10,
             reward_goal_threshold: 0.8,
-            observation_dim: 64,
+            convergence_window: default_convergence_window(),
+            observation_dim: ObservationSpec::default().len(),
             action_dim: 10,
             learning_rate: 3e-4,
             trace_file: None,
+            resume_from: None,
+            observation_spec: None,
         }
     }
 }
 
+fn default_convergence_window() -> usize {
+    10
+}
+
+/// Tag on the checkpoint saved when training stops early at the reward goal
+pub const CONVERGED_TAG: &str = "converged";
+
 /// Episode training statistics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EpisodeStats {
@@ -66,33 +99,204 @@ pub struct EpisodeStats {
     pub success_rate: f32,
 }
 
+/// Metadata saved next to each checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointMetadata {
+    pub id: String,
+    pub episode: usize,
+    pub total_steps: usize,
+    #[serde(deserialize_with = "deserialize_best_reward")]
+    pub best_reward: f32,
+    pub agent_type: String,
+    pub environment: String,
+    pub observation_dim: usize,
+    pub action_dim: usize,
+    /// Checkpoint this run was resumed from, if any
+    pub parent: Option<String>,
+    /// Labels such as `converged`
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Feature layout of the observations, for policies served by the injector
+    #[serde(default)]
+    pub observation_spec: Option<ObservationSpec>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// JSON has no infinities, so a checkpoint saved before any episode finished
+/// stores its best reward of negative infinity as `null`
+fn deserialize_best_reward<'de, D>(deserializer: D) -> std::result::Result<f32, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<f32>::deserialize(deserializer)?.unwrap_or(f32::NEG_INFINITY))
+}
+
 /// Training session state
 pub struct TrainingSession {
     config: RLTrainingConfig,
     episode_stats: Arc<RwLock<Vec<EpisodeStats>>>,
     best_reward: Arc<RwLock<f32>>,
     current_episode: Arc<RwLock<usize>>,
+    /// Environment steps across this run and any run it resumed
+    total_steps: Arc<RwLock<usize>>,
+    /// Checkpoint this session resumed from
+    parent_checkpoint: Arc<RwLock<Option<String>>>,
+    /// Per-step rewards of the last `convergence_window` episodes
+    recent_rewards: Arc<RwLock<VecDeque<f32>>>,
+    /// Episode at which the reward goal was reached
+    converged_at: Arc<RwLock<Option<usize>>>,
     is_running: Arc<RwLock<bool>>,
+    /// Cancelled by `stop` to abort in-flight environment work
+    cancel: Arc<RwLock<CancellationToken>>,
+    started_at: Arc<RwLock<Option<DateTime<Utc>>>>,
     checkpoint_dir: PathBuf,
     stats_file: PathBuf,
+    /// Per-episode step traces
+    episode_dir: PathBuf,
 }
 
 impl TrainingSession {
     pub fn new(config: RLTrainingConfig) -> Self {
-        let checkpoint_dir = PathBuf::from("/var/rl_checkpoints");
+        Self {
+            episode_dir: PathBuf::from(EPISODE_TRACE_DIR),
+            ..Self::with_checkpoint_dir(config, PathBuf::from("/var/rl_checkpoints"))
+        }
+    }
+    
+    /// Session keeping checkpoints, stats and episode traces under `checkpoint_dir`
+    pub fn with_checkpoint_dir(config: RLTrainingConfig, checkpoint_dir: PathBuf) -> Self {
         let stats_file = checkpoint_dir.join("training_stats.jsonl");
+        let episode_dir = checkpoint_dir.join("episodes");
         
         Self {
             config,
             episode_stats: Arc::new(RwLock::new(Vec::new())),
             best_reward: Arc::new(RwLock::new(f32::NEG_INFINITY)),
             current_episode: Arc::new(RwLock::new(0)),
+            total_steps: Arc::new(RwLock::new(0)),
+            parent_checkpoint: Arc::new(RwLock::new(None)),
+            recent_rewards: Arc::new(RwLock::new(VecDeque::new())),
+            converged_at: Arc::new(RwLock::new(None)),
             is_running: Arc::new(RwLock::new(false)),
+            cancel: Arc::new(RwLock::new(CancellationToken::new())),
+            started_at: Arc::new(RwLock::new(None)),
             checkpoint_dir,
             stats_file,
+            episode_dir,
         }
     }
     
+    /// Find a checkpoint's metadata by id, "latest" or "best"
+    pub async fn resolve_checkpoint(&self, id: &str) -> Result<CheckpointMetadata> {
+        match id {
+            "latest" => self.read_metadata(&self.checkpoint_dir.join("latest.json")).await
+                .context("No latest checkpoint to resume from"),
+            "best" => {
+                let mut best: Option<CheckpointMetadata> = None;
+                let mut entries = fs::read_dir(&self.checkpoint_dir).await
+                    .context("Failed to read checkpoint directory")?;
+                while let Some(entry) = entries.next_entry().await? {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    if !name.starts_with("checkpoint_") || !name.ends_with(".json") {
+                        continue;
+                    }
+                    let meta = self.read_metadata(&entry.path()).await?;
+                    if best.as_ref().map_or(true, |b| meta.best_reward > b.best_reward) {
+                        best = Some(meta);
+                    }
+                }
+                best.ok_or_else(|| anyhow::anyhow!("No checkpoints to resume from"))
+            }
+            id => {
+                let id = if id.starts_with("checkpoint_") {
+                    id.to_string()
+                } else {
+                    format!("checkpoint_ep{}", id)
+                };
+                self.read_metadata(&self.checkpoint_dir.join(format!("{}.json", id))).await
+                    .with_context(|| format!("Checkpoint '{}' not found", id))
+            }
+        }
+    }
+    
+    async fn read_metadata(&self, path: &Path) -> Result<CheckpointMetadata> {
+        let content = fs::read_to_string(path).await
+            .with_context(|| format!("Failed to read {:?}", path))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid checkpoint metadata in {:?}", path))
+    }
+    
+    /// Refuse checkpoints trained against a different env or shape
+    fn check_compatible(&self, meta: &CheckpointMetadata) -> Result<()> {
+        if meta.environment != self.config.environment
+            || meta.observation_dim != self.config.observation_dim
+            || meta.action_dim != self.config.action_dim
+        {
+            anyhow::bail!(
+                "Cannot resume from checkpoint '{}': it was trained on env '{}' (obs dim {}, action dim {}) \
+                 but this run uses env '{}' (obs dim {}, action dim {})",
+                meta.id, meta.environment, meta.observation_dim, meta.action_dim,
+                self.config.environment, self.config.observation_dim, self.config.action_dim,
+            );
+        }
+        Ok(())
+    }
+    
+    /// Restore counters from the configured checkpoint.
+    ///
+    /// Returns the checkpoint to load agent weights from and the episode to
+    /// continue at, or `None` when starting fresh.
+    pub async fn prepare_resume(&self) -> Result<Option<(CheckpointMetadata, usize)>> {
+        let id = match &self.config.resume_from {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+        
+        let meta = self.resolve_checkpoint(id).await?;
+        self.check_compatible(&meta)?;
+        
+        *self.total_steps.write().await = meta.total_steps;
+        *self.best_reward.write().await = meta.best_reward;
+        *self.current_episode.write().await = meta.episode + 1;
+        *self.parent_checkpoint.write().await = Some(meta.id.clone());
+        
+        log::info!("Resuming from {} at episode {} ({} steps)", meta.id, meta.episode + 1, meta.total_steps);
+        let start = meta.episode + 1;
+        Ok(Some((meta, start)))
+    }
+    
+    /// Account for a finished episode
+    async fn record_episode(&self, stats: &EpisodeStats) -> Result<()> {
+        self.log_episode_stats(stats).await?;
+        *self.total_steps.write().await += stats.steps;
+        
+        let mut best = self.best_reward.write().await;
+        if stats.total_reward > *best {
+            *best = stats.total_reward;
+            log::info!("New best reward: {:.3}", stats.total_reward);
+        }
+        Ok(())
+    }
+    
+    /// Track an episode's reward against the goal threshold.
+    ///
+    /// Returns the mean reward once the last `convergence_window` episodes
+    /// average at or above `reward_goal_threshold`.
+    async fn check_convergence(&self, stats: &EpisodeStats) -> Option<f32> {
+        let window = self.config.convergence_window.max(1);
+        let mut recent = self.recent_rewards.write().await;
+        recent.push_back(stats.average_reward);
+        while recent.len() > window {
+            recent.pop_front();
+        }
+        
+        if recent.len() < window {
+            return None;
+        }
+        let mean = recent.iter().sum::<f32>() / window as f32;
+        (mean >= self.config.reward_goal_threshold).then_some(mean)
+    }
+    
     /// Start training session
     pub async fn start(&self) -> Result<()> {
         // Check if already running
@@ -101,6 +305,8 @@ impl TrainingSession {
         }
         
         *self.is_running.write().await = true;
+        *self.cancel.write().await = CancellationToken::new();
+        *self.started_at.write().await = Some(Utc::now());
         
         // Create checkpoint directory
         fs::create_dir_all(&self.checkpoint_dir).await?;
@@ -125,17 +331,36 @@ impl TrainingSession {
         let mut env = self.create_environment().await?;
         
         // Create agent
-        let mut agent = self.create_agent().await?;
+        let agent = self.create_agent().await?;
+        
+        // Fail fast if the agent was built for different spaces than the env
+        check_compatibility(&self.agent_spaces(), &env)?;
+        
+        // Continue from a checkpoint if requested
+        let start_episode = match self.prepare_resume().await? {
+            Some((meta, start)) => {
+                self.restore_agent(&agent, &meta).await?;
+                start
+            }
+            None => 0,
+        };
         
         // Training loop
-        for episode in 0..self.config.episodes {
+        let cancel = self.cancel.read().await.clone();
+        for episode in start_episode..self.config.episodes {
             *self.current_episode.write().await = episode;
             
-            // Collect rollout
-            let rollout_stats = self.collect_rollout(&mut agent, &mut env).await?;
+            // Collect rollout, abandoning it as soon as training is stopped
+            let rollout_stats = tokio::select! {
+                () = cancel.cancelled() => {
+                    log::info!("Training stopped by user during rollout");
+                    break;
+                }
+                stats = self.collect_rollout(&agent, &mut env) => stats?,
+            };
             
             // Train on rollout
-            let train_stats = self.train_on_rollout(&mut agent).await?;
+            let train_stats = self.train_on_rollout(&agent).await?;
             
             // Create episode stats
             let stats = EpisodeStats {
@@ -152,15 +377,9 @@ impl TrainingSession {
                 success_rate: rollout_stats.success_rate,
             };
             
-            // Log stats
-            self.log_episode_stats(&stats).await?;
-            
-            // Update best reward
-            let mut best = self.best_reward.write().await;
-            if stats.total_reward > *best {
-                *best = stats.total_reward;
-                log::info!("New best reward: {:.3}", stats.total_reward);
-            }
+            // Log stats and update counters
+            self.record_episode(&stats).await?;
+            self.save_episode_trace(&rollout_stats.trajectory).await?;
             
             // Save checkpoint
             if episode % self.config.checkpoint_interval == 0 {
@@ -176,9 +395,14 @@ impl TrainingSession {
                 );
             }
             
-            // Check goal threshold
-            if stats.average_reward >= self.config.reward_goal_threshold {
-                log::info!("Reached reward goal threshold! Training complete.");
+            // Stop early once the recent mean reward reaches the goal
+            if let Some(mean) = self.check_convergence(&stats).await {
+                log::info!(
+                    "Converged at episode {}: mean reward {:.3} over the last {} episodes reached {:.3}",
+                    episode, mean, self.config.convergence_window, self.config.reward_goal_threshold
+                );
+                self.save_tagged_checkpoint(&agent, episode, &[CONVERGED_TAG]).await?;
+                *self.converged_at.write().await = Some(episode);
                 break;
             }
             
@@ -193,17 +417,25 @@ impl TrainingSession {
     }
     
     /// Create environment based on config
-    async fn create_environment(&self) -> Result<Box<dyn Environment>> {
+    async fn create_environment(&self) -> Result<BoxedEnv> {
         match self.config.environment.as_str() {
             "goal-task" => {
-                // Create GoalTaskEnv
+                // Create GoalTaskEnv; its observations are laid out by the spec
+                let observation_spec = self.config.observation_spec.clone().unwrap_or_default();
+                if observation_spec.len() != self.config.observation_dim {
+                    anyhow::bail!(
+                        "goal-task observations have {} features, but observation_dim is {}",
+                        observation_spec.len(), self.config.observation_dim
+                    );
+                }
                 let config = GoalTaskEnvConfig {
                     execute_real_commands: true,
                     max_steps: self.config.steps_per_rollout,
-                    observation_dim: self.config.observation_dim,
+                    observation_spec,
                     ..Default::default()
                 };
-                Ok(Box::new(GoalTaskEnv::new(config)))
+                let cancel = self.cancel.read().await.child_token();
+                Ok(Box::new(GoalTaskEnv::new(config)?.with_cancellation(cancel)))
             }
             "jsonl" => {
                 // Create JSONLEnv
@@ -223,74 +455,65 @@ impl TrainingSession {
         }
     }
     
+    /// Spaces the configured agent is built for
+    fn agent_spaces(&self) -> AgentSpaces {
+        AgentSpaces {
+            observation: SpaceSpec::continuous(self.config.observation_dim),
+            action: SpaceSpec::discrete(self.config.action_dim),
+        }
+    }
+    
     /// Create agent based on config
-    async fn create_agent(&self) -> Result<Box<dyn Agent>> {
+    pub(crate) async fn create_agent(&self) -> Result<PPOAgentFull> {
         match self.config.agent_type.as_str() {
             "ppo" => {
                 let config = PPOConfig {
                     base: AgentConfig {
-                        learning_rate: self.config.learning_rate,
+                        learning_rate: f64::from(self.config.learning_rate),
                         ..Default::default()
                     },
+                    n_steps: self.config.steps_per_rollout,
                     ..Default::default()
                 };
                 
-                Ok(Box::new(PPOAgent::new(
+                PPOAgentFull::new(
                     config,
                     self.config.observation_dim,
                     self.config.action_dim,
-                ).await?))
+                ).await
             }
             _ => Err(anyhow::anyhow!("Unknown agent type: {}", self.config.agent_type)),
         }
     }
     
+    /// Load a checkpoint's weights and the agent's own timestep counter into
+    /// `agent`, both read from its `.bin` file. The session's step count
+    /// comes from `meta` in `prepare_resume`.
+    pub(crate) async fn restore_agent(&self, agent: &PPOAgentFull, meta: &CheckpointMetadata) -> Result<()> {
+        agent.load_bin(&self.checkpoint_path(&meta.id)).await
+            .with_context(|| format!("Failed to load agent weights from checkpoint '{}'", meta.id))
+    }
+    
+    /// Where the agent weights of checkpoint `id` live
+    fn checkpoint_path(&self, id: &str) -> PathBuf {
+        self.checkpoint_dir.join(format!("{}.bin", id))
+    }
+    
     /// Collect rollout data
-    async fn collect_rollout(
-        &self,
-        agent: &mut Box<dyn Agent>,
-        env: &mut Box<dyn Environment>,
-    ) -> Result<RolloutStats> {
-        let mut total_reward = 0.0;
-        let mut steps = 0;
-        let mut goals_executed = Vec::new();
-        let mut successes = 0;
-        
-        // Collect rollout
-        for _ in 0..self.config.steps_per_rollout {
-            // Agent and environment interaction would happen here
-            // This is a placeholder for the actual implementation
-            steps += 1;
-            total_reward += 0.1; // Placeholder reward
-            
-            // Track goals
-            goals_executed.push(format!("Goal_{}", steps));
-            if rand::random::<f32>() > 0.3 {
-                successes += 1;
-            }
-        }
-        
-        let average_reward = total_reward / steps as f32;
-        let success_rate = successes as f32 / goals_executed.len() as f32;
-        
-        Ok(RolloutStats {
-            total_reward,
-            average_reward,
-            steps,
-            goals_executed,
-            success_rate,
-        })
+    async fn collect_rollout(&self, agent: &PPOAgentFull, env: &mut BoxedEnv) -> Result<RolloutStats> {
+        let episode = *self.current_episode.read().await;
+        let mut recorder = RolloutRecorder::new(env, episode);
+        agent.collect_rollout(&mut recorder).await?;
+        Ok(recorder.finish())
     }
     
     /// Train agent on collected rollout
-    async fn train_on_rollout(&self, agent: &mut Box<dyn Agent>) -> Result<TrainStats> {
-        // Placeholder for actual training
-        // In real implementation, would call agent.train()
-        
+    async fn train_on_rollout(&self, agent: &PPOAgentFull) -> Result<TrainStats> {
+        let stats = agent.train().await?;
         Ok(TrainStats {
-            policy_loss: rand::random::<f32>() * 0.1,
-            value_loss: rand::random::<f32>() * 0.1,
-            entropy: rand::random::<f32>() * 0.05,
+            policy_loss: stats.policy_loss,
+            value_loss: stats.value_loss,
+            entropy: stats.entropy,
         })
     }
     
@@ -313,13 +536,32 @@ impl TrainingSession {
         Ok(())
     }
     
+    /// Write an episode's steps to `<episode_dir>/<episode>.json`
+    async fn save_episode_trace(&self, trajectory: &EpisodeTrajectory) -> Result<()> {
+        fs::create_dir_all(&self.episode_dir).await?;
+        let path = self.episode_trace_path(&trajectory.episode_id);
+        fs::write(&path, serde_json::to_vec(trajectory)?)
+            .await
+            .with_context(|| format!("Failed to write episode trace {}", path.display()))
+    }
+    
+    fn episode_trace_path(&self, episode_id: &str) -> PathBuf {
+        self.episode_dir.join(format!("{}.json", episode_id))
+    }
+    
     /// Save checkpoint
-    async fn save_checkpoint(&self, agent: &Box<dyn Agent>, episode: usize) -> Result<()> {
-        let checkpoint_path = self.checkpoint_dir
-            .join(format!("checkpoint_ep{}.bin", episode));
+    pub(crate) async fn save_checkpoint(&self, agent: &PPOAgentFull, episode: usize) -> Result<()> {
+        self.save_tagged_checkpoint(agent, episode, &[]).await
+    }
+    
+    /// Save checkpoint with labels recorded in its metadata
+    async fn save_tagged_checkpoint(&self, agent: &PPOAgentFull, episode: usize, tags: &[&str]) -> Result<()> {
+        let id = format!("checkpoint_ep{}", episode);
+        let checkpoint_path = self.checkpoint_path(&id);
         
-        // In real implementation, would serialize agent state
         log::info!("Saving checkpoint at episode {}", episode);
+        agent.save_bin(&checkpoint_path).await?;
+        self.write_tagged_checkpoint_metadata(&id, episode, tags).await?;
         
         // Also save to 'latest' symlink
         let latest_path = self.checkpoint_dir.join("latest.bin");
@@ -336,9 +578,43 @@ impl TrainingSession {
         Ok(())
     }
     
+    /// Write `<id>.json` and `latest.json` describing a checkpoint
+    pub(crate) async fn write_checkpoint_metadata(&self, id: &str, episode: usize) -> Result<CheckpointMetadata> {
+        self.write_tagged_checkpoint_metadata(id, episode, &[]).await
+    }
+    
+    async fn write_tagged_checkpoint_metadata(
+        &self,
+        id: &str,
+        episode: usize,
+        tags: &[&str],
+    ) -> Result<CheckpointMetadata> {
+        let meta = CheckpointMetadata {
+            id: id.to_string(),
+            episode,
+            total_steps: *self.total_steps.read().await,
+            best_reward: *self.best_reward.read().await,
+            agent_type: self.config.agent_type.clone(),
+            environment: self.config.environment.clone(),
+            observation_dim: self.config.observation_dim,
+            action_dim: self.config.action_dim,
+            parent: self.parent_checkpoint.read().await.clone(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            observation_spec: self.config.observation_spec.clone(),
+            created_at: Utc::now(),
+        };
+        
+        let json = serde_json::to_string_pretty(&meta)?;
+        fs::write(self.checkpoint_dir.join(format!("{}.json", id)), &json).await?;
+        fs::write(self.checkpoint_dir.join("latest.json"), &json).await?;
+        
+        Ok(meta)
+    }
+    
     /// Stop training
     pub async fn stop(&self) -> Result<()> {
         *self.is_running.write().await = false;
+        self.cancel.read().await.cancel();
         Ok(())
     }
     
@@ -349,6 +625,17 @@ impl TrainingSession {
         let best_reward = *self.best_reward.read().await;
         let is_running = *self.is_running.read().await;
         
+        let session_steps: usize = stats.iter().map(|s| s.steps).sum();
+        let total_steps = *self.total_steps.read().await;
+        let elapsed_secs = self.started_at.read().await
+            .map(|t| (Utc::now() - t).num_milliseconds() as f32 / 1000.0)
+            .unwrap_or(0.0);
+        let steps_per_sec = if elapsed_secs > 0.0 {
+            session_steps as f32 / elapsed_secs
+        } else {
+            0.0
+        };
+        
         TrainingStats {
             current_episode,
             total_episodes: self.config.episodes,
@@ -358,11 +645,24 @@ impl TrainingSession {
                 .take(100)
                 .map(|s| s.total_reward)
                 .collect(),
+            recent_episodes: stats[stats.len().saturating_sub(100)..]
+                .iter()
+                .map(|s| (s.episode, s.total_reward))
+                .collect(),
             is_running,
+            total_steps,
+            steps_per_sec,
+            converged_at: *self.converged_at.read().await,
+            // Sessions train PPO agents, which explore by sampling their
+            // policy rather than at a set rate
+            exploration_rate: None,
         }
     }
 }
 
+/// Steps of one training episode, as written for `rl trace export`
+type EpisodeTrajectory = Trajectory<VectorObservation, DiscreteAction, VectorState>;
+
 /// Rollout statistics
 struct RolloutStats {
     total_reward: f32,
@@ -370,6 +670,93 @@ struct RolloutStats {
     steps: usize,
     goals_executed: Vec<String>,
     success_rate: f32,
+    trajectory: EpisodeTrajectory,
+}
+
+/// Passes a rollout through to the environment, tallying rewards and the
+/// goals it executed along the way and recording every step
+struct RolloutRecorder<'a> {
+    env: &'a mut BoxedEnv,
+    stats: RolloutStats,
+    successes: usize,
+    /// Observation the next action is taken from
+    observation: Option<VectorObservation>,
+}
+
+impl<'a> RolloutRecorder<'a> {
+    fn new(env: &'a mut BoxedEnv, episode: usize) -> Self {
+        Self {
+            env,
+            stats: RolloutStats {
+                total_reward: 0.0,
+                average_reward: 0.0,
+                steps: 0,
+                goals_executed: Vec::new(),
+                success_rate: 0.0,
+                trajectory: Trajectory::new(episode.to_string()),
+            },
+            successes: 0,
+            observation: None,
+        }
+    }
+    
+    fn finish(mut self) -> RolloutStats {
+        if self.stats.steps > 0 {
+            self.stats.average_reward = self.stats.total_reward / self.stats.steps as f32;
+        }
+        if !self.stats.goals_executed.is_empty() {
+            self.stats.success_rate = self.successes as f32 / self.stats.goals_executed.len() as f32;
+        }
+        self.stats
+    }
+}
+
+#[async_trait::async_trait]
+impl Environment for RolloutRecorder<'_> {
+    type Observation = VectorObservation;
+    type Action = DiscreteAction;
+    type State = VectorState;
+    
+    fn observation_space(&self) -> Box<dyn ObservationSpace<Observation = VectorObservation>> {
+        self.env.observation_space()
+    }
+    
+    fn action_space(&self) -> Box<dyn ActionSpace<Action = DiscreteAction>> {
+        self.env.action_space()
+    }
+    
+    async fn reset(&mut self) -> sentient_rl_core::Result<(VectorObservation, StepInfo)> {
+        let (observation, info) = self.env.reset().await?;
+        self.observation = Some(observation.clone());
+        Ok((observation, info))
+    }
+    
+    async fn step(&mut self, action: DiscreteAction) -> sentient_rl_core::Result<Step<VectorObservation, VectorState>> {
+        let step = self.env.step(action.clone()).await?;
+        if let Some(observation) = self.observation.replace(step.observation.clone()) {
+            self.stats.trajectory.push_with_info(
+                Transition {
+                    observation,
+                    action,
+                    reward: step.reward,
+                    next_observation: step.observation.clone(),
+                    done: step.done || step.truncated,
+                    state: None,
+                    next_state: step.state.clone(),
+                },
+                step.info.clone(),
+            );
+        }
+        self.stats.steps += 1;
+        self.stats.total_reward += step.reward.0 as f32;
+        if let Some(goal) = step.info.get("goal").and_then(|g| g.as_str()) {
+            self.stats.goals_executed.push(goal.to_string());
+            if step.info.get("success").and_then(|s| s.as_bool()) == Some(true) {
+                self.successes += 1;
+            }
+        }
+        Ok(step)
+    }
 }
 
 /// Training statistics
@@ -385,13 +772,22 @@ pub struct TrainingStats {
     pub current_episode: usize,
     pub total_episodes: usize,
     pub best_reward: f32,
+    /// Total rewards of the latest finished episodes, newest first
     pub recent_rewards: Vec<f32>,
+    /// `(episode, total_reward)` of the latest finished episodes, oldest first
+    pub recent_episodes: Vec<(usize, f32)>,
     pub is_running: bool,
+    pub total_steps: usize,
+    pub steps_per_sec: f32,
+    /// Episode at which training stopped early at the reward goal
+    pub converged_at: Option<usize>,
+    /// Exploration rate of a value-based agent, if one is training
+    pub exploration_rate: Option<f32>,
 }
 
 /// Global training session manager
 lazy_static::lazy_static! {
-    static ref TRAINING_MANAGER: Arc<RwLock<Option<TrainingSession>>> = 
+    static ref TRAINING_MANAGER: Arc<RwLock<Option<Arc<TrainingSession>>>> = 
         Arc::new(RwLock::new(None));
 }
 
@@ -451,26 +847,151 @@ pub async fn load_policy(checkpoint_path: &Path) -> Result<()> {
     Ok(())
 }
 
-// Placeholder trait definitions (would come from crates)
-#[async_trait::async_trait]
-trait Environment: Send + Sync {
-    async fn reset(&mut self) -> Result<Observation>;
-    async fn step(&mut self, action: Action) -> Result<StepInfo>;
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
 
-#[async_trait::async_trait]
-trait Agent: Send + Sync {
-    async fn act(&self, observation: &Observation) -> Result<Action>;
-    async fn train(&mut self) -> Result<()>;
-}
+    fn episode(episode: usize, steps: usize, reward: f32) -> EpisodeStats {
+        EpisodeStats {
+            episode,
+            total_reward: reward,
+            average_reward: reward / steps as f32,
+            steps,
+            policy_loss: 0.0,
+            value_loss: 0.0,
+            entropy: 0.0,
+            learning_rate: 3e-4,
+            timestamp: Utc::now(),
+            goals_executed: Vec::new(),
+            success_rate: 1.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resume_continues_step_counter() {
+        let dir = TempDir::new().unwrap();
+        
+        let first = TrainingSession::with_checkpoint_dir(RLTrainingConfig::default(), dir.path().to_path_buf());
+        for ep in 0..5 {
+            first.record_episode(&episode(ep, 200, ep as f32)).await.unwrap();
+        }
+        first.write_checkpoint_metadata("checkpoint_ep4", 4).await.unwrap();
+        
+        let config = RLTrainingConfig {
+            resume_from: Some("latest".to_string()),
+            ..Default::default()
+        };
+        let resumed = TrainingSession::with_checkpoint_dir(config, dir.path().to_path_buf());
+        let (meta, start) = resumed.prepare_resume().await.unwrap().unwrap();
+        assert_eq!(meta.id, "checkpoint_ep4");
+        assert_eq!(start, 5);
+        assert_eq!(resumed.get_stats().await.total_steps, 1000);
+        
+        resumed.record_episode(&episode(5, 200, 1.0)).await.unwrap();
+        assert_eq!(resumed.get_stats().await.total_steps, 1200);
+        assert_eq!(resumed.get_stats().await.best_reward, 4.0);
+        
+        let child = resumed.write_checkpoint_metadata("checkpoint_ep5", 5).await.unwrap();
+        assert_eq!(child.parent.as_deref(), Some("checkpoint_ep4"));
+    }
 
-struct Observation;
-struct Action;
-struct StepInfo;
-struct GoalTaskEnv;
-struct GoalTaskEnvConfig;
-struct JSONLEnv;
-struct JSONLEnvConfig;
-struct PPOAgent;
-struct PPOConfig;
-struct AgentConfig;
\ No newline at end of file
+    #[tokio::test]
+    async fn test_resume_restores_agent_parameters() {
+        let dir = TempDir::new().unwrap();
+        let first = TrainingSession::with_checkpoint_dir(RLTrainingConfig::default(), dir.path().to_path_buf());
+        let agent = first.create_agent().await.unwrap();
+        first.record_episode(&episode(0, 200, 1.0)).await.unwrap();
+        first.save_checkpoint(&agent, 0).await.unwrap();
+        
+        let config = RLTrainingConfig {
+            resume_from: Some("latest".to_string()),
+            ..Default::default()
+        };
+        let resumed = TrainingSession::with_checkpoint_dir(config, dir.path().to_path_buf());
+        let restored = resumed.create_agent().await.unwrap();
+        let saved = agent.parameters().await.unwrap();
+        assert_ne!(restored.parameters().await.unwrap(), saved);
+        
+        let (meta, _) = resumed.prepare_resume().await.unwrap().unwrap();
+        resumed.restore_agent(&restored, &meta).await.unwrap();
+        assert_eq!(restored.parameters().await.unwrap(), saved);
+    }
+
+    #[tokio::test]
+    async fn test_stops_when_recent_mean_reaches_threshold() {
+        let dir = TempDir::new().unwrap();
+        let config = RLTrainingConfig {
+            reward_goal_threshold: 0.5,
+            convergence_window: 3,
+            ..Default::default()
+        };
+        let session = TrainingSession::with_checkpoint_dir(config, dir.path().to_path_buf());
+        
+        // Per-step rewards; a single episode at 0.9 is not enough on its own.
+        // Means of the last three from episode 2: 0.4, 0.367, 0.367, 0.467, 0.6
+        let rewards = [0.1, 0.2, 0.9, 0.0, 0.2, 1.2, 0.4, 0.9];
+        let mut stopped_at = None;
+        for (ep, &reward) in rewards.iter().enumerate() {
+            if session.check_convergence(&episode(ep, 1, reward)).await.is_some() {
+                stopped_at = Some(ep);
+                break;
+            }
+        }
+        assert_eq!(stopped_at, Some(6));
+        
+        let meta = session.write_tagged_checkpoint_metadata("checkpoint_ep6", 6, &[CONVERGED_TAG]).await.unwrap();
+        assert_eq!(meta.tags, vec![CONVERGED_TAG]);
+        let latest = session.resolve_checkpoint("latest").await.unwrap();
+        assert_eq!(latest.episode, 6);
+        assert_eq!(latest.tags, vec![CONVERGED_TAG]);
+    }
+
+    #[tokio::test]
+    async fn test_rollout_steps_are_saved_for_export() {
+        let dir = TempDir::new().unwrap();
+        let config = RLTrainingConfig {
+            observation_dim: 4,
+            action_dim: 2,
+            steps_per_rollout: 16,
+            ..Default::default()
+        };
+        let session = TrainingSession::with_checkpoint_dir(config, dir.path().to_path_buf());
+        let agent = session.create_agent().await.unwrap();
+        let mut env: BoxedEnv = Box::new(
+            sentient_rl_env::CartPoleEnv::new(sentient_rl_core::EnvironmentConfig::default()).unwrap(),
+        );
+        *session.current_episode.write().await = 3;
+        
+        let stats = session.collect_rollout(&agent, &mut env).await.unwrap();
+        assert_eq!(stats.trajectory.len(), stats.steps);
+        session.save_episode_trace(&stats.trajectory).await.unwrap();
+        
+        // Read back the way `rl trace export` does, without the concrete types
+        let saved: Trajectory<serde_json::Value, serde_json::Value, serde_json::Value> =
+            serde_json::from_slice(&std::fs::read(session.episode_trace_path("3")).unwrap()).unwrap();
+        assert_eq!(saved.episode_id, "3");
+        assert_eq!(saved.len(), 16);
+        assert!((saved.total_reward - f64::from(stats.total_reward)).abs() < 1e-3);
+        let mut csv = Vec::new();
+        saved.to_csv(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap().lines().count(), 17);
+    }
+
+    #[tokio::test]
+    async fn test_resume_rejects_incompatible_observation_dim() {
+        let dir = TempDir::new().unwrap();
+        let first = TrainingSession::with_checkpoint_dir(RLTrainingConfig::default(), dir.path().to_path_buf());
+        first.write_checkpoint_metadata("checkpoint_ep0", 0).await.unwrap();
+        
+        let config = RLTrainingConfig {
+            observation_dim: 32,
+            resume_from: Some("0".to_string()),
+            ..Default::default()
+        };
+        let resumed = TrainingSession::with_checkpoint_dir(config, dir.path().to_path_buf());
+        let err = resumed.prepare_resume().await.unwrap_err().to_string();
+        assert!(err.contains("obs dim 10"), "{}", err);
+        assert!(err.contains("obs dim 32"), "{}", err);
+    }
+}
@@ -0,0 +1,303 @@
+//! End-to-end self-test
+//!
+//! `selftest` runs one small round trip through each AI component: a canned
+//! prompt through the router, a RAG query, a replay store write/read and a
+//! checkpoint save/load. Every check is timed and reported on its own, so a
+//! single broken backend doesn't hide the state of the others.
+
+use crate::ai_router::router::AIRouter;
+use crate::ai_router::{InferenceRequest, ModelCapability};
+use crate::rag::RAGSystem;
+use crate::rl_training::{RLTrainingConfig, TrainingSession};
+use anyhow::{Context, Result};
+use sentient_rl_agent::ReplayBuffer;
+use sentient_rl_core::{DiscreteAction, Experience, Reward, Transition};
+use sentient_rl_core::observation::VectorObservation;
+use sentient_rl_core::state::VectorState;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::time::Instant;
+
+/// Prompt sent through the router; short so any text model can answer it
+pub const SELFTEST_PROMPT: &str = "Reply with the single word: ready";
+/// Question asked of the RAG index
+pub const SELFTEST_RAG_QUERY: &str = "What is SentientOS?";
+
+/// One component exercised by the self-test
+pub trait SelfCheck {
+    /// Short name used in the report and by `--skip`
+    fn component(&self) -> &str;
+    /// Exercise the component, returning a one-line detail on success
+    fn run(&mut self) -> Result<String>;
+}
+
+/// Outcome of a single check
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "outcome", content = "detail", rename_all = "snake_case")]
+pub enum CheckOutcome {
+    Passed(String),
+    Failed(String),
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub component: String,
+    #[serde(flatten)]
+    pub outcome: CheckOutcome,
+    pub duration_ms: u64,
+}
+
+/// Aggregate result of a self-test run
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub duration_ms: u64,
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelfTestReport {
+    pub fn get(&self, component: &str) -> Option<&CheckResult> {
+        self.checks.iter().find(|c| c.component == component)
+    }
+
+    /// Number of checks that ran and failed
+    pub fn failures(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|c| matches!(c.outcome, CheckOutcome::Failed(_)))
+            .count()
+    }
+}
+
+/// Ordered set of checks
+pub struct SelfTest {
+    checks: Vec<Box<dyn SelfCheck>>,
+}
+
+impl SelfTest {
+    pub fn new(checks: Vec<Box<dyn SelfCheck>>) -> Self {
+        Self { checks }
+    }
+
+    /// Router, RAG, replay store and checkpoint checks against the live system
+    pub fn with_defaults() -> Self {
+        Self::new(vec![
+            Box::new(RouterCheck),
+            Box::new(RagCheck),
+            Box::new(ReplayStoreCheck),
+            Box::new(CheckpointCheck),
+        ])
+    }
+
+    pub fn component_names(&self) -> Vec<&str> {
+        self.checks.iter().map(|c| c.component()).collect()
+    }
+
+    /// Run every check not named in `skip`
+    pub fn run(&mut self, skip: &[String]) -> Result<SelfTestReport> {
+        let known = self.component_names();
+        if let Some(unknown) = skip.iter().find(|s| !known.contains(&s.as_str())) {
+            anyhow::bail!(
+                "Unknown self-test component '{}'. Expected one of: {}",
+                unknown,
+                known.join(", ")
+            );
+        }
+
+        let started = Instant::now();
+        let mut results = Vec::with_capacity(self.checks.len());
+
+        for check in self.checks.iter_mut() {
+            let component = check.component().to_string();
+            if skip.contains(&component) {
+                results.push(CheckResult {
+                    component,
+                    outcome: CheckOutcome::Skipped,
+                    duration_ms: 0,
+                });
+                continue;
+            }
+
+            log::debug!("selftest: running {}", component);
+            let check_started = Instant::now();
+            let outcome = match check.run() {
+                Ok(detail) => CheckOutcome::Passed(detail),
+                Err(e) => {
+                    log::warn!("selftest: {} failed: {:#}", component, e);
+                    CheckOutcome::Failed(format!("{:#}", e))
+                }
+            };
+            results.push(CheckResult {
+                component,
+                outcome,
+                duration_ms: check_started.elapsed().as_millis() as u64,
+            });
+        }
+
+        let mut report = SelfTestReport {
+            passed: false,
+            duration_ms: started.elapsed().as_millis() as u64,
+            checks: results,
+        };
+        report.passed = report.failures() == 0;
+        Ok(report)
+    }
+}
+
+/// Parse `--skip a,b` / `--skip a --skip b` from the `selftest` arguments
+pub fn parse_skip_args(args: &[&str]) -> Result<Vec<String>> {
+    let mut skip = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match *arg {
+            "--skip" => {
+                let value = iter.next().context("--skip requires a component list")?;
+                skip.extend(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(String::from),
+                );
+            }
+            other => anyhow::bail!("Unknown selftest argument '{}'. Usage: selftest [--skip <components>]", other),
+        }
+    }
+
+    Ok(skip)
+}
+
+/// Canned prompt through `AIRouter`
+pub struct RouterCheck;
+
+impl SelfCheck for RouterCheck {
+    fn component(&self) -> &str {
+        "router"
+    }
+
+    fn run(&mut self) -> Result<String> {
+        let request = InferenceRequest {
+            prompt: SELFTEST_PROMPT.to_string(),
+            capability: ModelCapability::TextGeneration,
+            max_tokens: Some(16),
+            temperature: Some(0.0),
+            system_prompt: None,
+            metadata: HashMap::new(),
+        };
+
+        let response = AIRouter::route_request(&request)?;
+        if response.text.as_deref().unwrap_or_default().trim().is_empty() {
+            anyhow::bail!("{} returned an empty response", response.model_used);
+        }
+        Ok(format!("{} answered", response.model_used))
+    }
+}
+
+/// Small query against the RAG index
+pub struct RagCheck;
+
+impl SelfCheck for RagCheck {
+    fn component(&self) -> &str {
+        "rag"
+    }
+
+    fn run(&mut self) -> Result<String> {
+        let mut rag = RAGSystem::new().context("Failed to open RAG index")?;
+        let result = rag.query(SELFTEST_RAG_QUERY)?;
+        Ok(format!(
+            "{} source(s), confidence {:.2}",
+            result.sources.len(),
+            result.confidence
+        ))
+    }
+}
+
+/// Write experiences to a scratch replay file and read them back into a buffer
+pub struct ReplayStoreCheck;
+
+const REPLAY_SAMPLES: usize = 4;
+
+impl SelfCheck for ReplayStoreCheck {
+    fn component(&self) -> &str {
+        "replay"
+    }
+
+    fn run(&mut self) -> Result<String> {
+        type Exp = Experience<VectorObservation, DiscreteAction, VectorState>;
+
+        let written: Vec<Exp> = (0..REPLAY_SAMPLES)
+            .map(|i| {
+                Experience::from(Transition {
+                    observation: VectorObservation { data: vec![i as f64; 4] },
+                    action: DiscreteAction(i),
+                    reward: Reward(i as f64),
+                    next_observation: VectorObservation { data: vec![i as f64 + 1.0; 4] },
+                    done: i + 1 == REPLAY_SAMPLES,
+                    state: None,
+                    next_state: None,
+                })
+            })
+            .collect();
+
+        let mut file = tempfile::NamedTempFile::new().context("Failed to create scratch replay file")?;
+        for experience in &written {
+            serde_json::to_writer(&mut file, experience)?;
+            writeln!(file)?;
+        }
+        file.flush()?;
+
+        let mut buffer = ReplayBuffer::new(REPLAY_SAMPLES);
+        for line in BufReader::new(file.reopen()?).lines() {
+            let experience: Exp = serde_json::from_str(&line?).context("Corrupt replay entry")?;
+            buffer.push(experience);
+        }
+
+        let batch = buffer
+            .sample(REPLAY_SAMPLES)
+            .with_context(|| format!("Read back {} of {} experiences", buffer.len(), REPLAY_SAMPLES))?;
+        let expected: f64 = written.iter().map(|e| e.transition.reward.0).sum();
+        let actual: f64 = batch.iter().map(|e| e.transition.reward.0).sum();
+        if (expected - actual).abs() > f64::EPSILON {
+            anyhow::bail!("Replay rewards changed on round trip ({} != {})", actual, expected);
+        }
+
+        Ok(format!("{} experiences round-tripped", batch.len()))
+    }
+}
+
+/// Save an agent checkpoint to a scratch directory, resolve it as `latest`
+/// and restore its weights into a fresh agent
+pub struct CheckpointCheck;
+
+impl SelfCheck for CheckpointCheck {
+    fn component(&self) -> &str {
+        "checkpoint"
+    }
+
+    fn run(&mut self) -> Result<String> {
+        let dir = tempfile::TempDir::new().context("Failed to create scratch checkpoint dir")?;
+        let session = TrainingSession::with_checkpoint_dir(RLTrainingConfig::default(), dir.path().to_path_buf());
+
+        let runtime = tokio::runtime::Runtime::new()?;
+        runtime.block_on(async {
+            let agent = session.create_agent().await?;
+            session.save_checkpoint(&agent, 0).await?;
+            let loaded = session.resolve_checkpoint("latest").await?;
+            if loaded.episode != 0 {
+                anyhow::bail!("Loaded checkpoint '{}' instead of episode 0", loaded.id);
+            }
+
+            let restored = session.create_agent().await?;
+            session.restore_agent(&restored, &loaded).await?;
+            let saved = agent.parameters().await?;
+            let actual = restored.parameters().await?;
+            if actual != saved {
+                anyhow::bail!("Checkpoint '{}' restored different parameters than were saved", loaded.id);
+            }
+            Ok(format!("saved and restored {} ({} parameters)", loaded.id, saved.len()))
+        })
+    }
+}
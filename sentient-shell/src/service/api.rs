@@ -1,5 +1,6 @@
 use super::*;
 use super::manager::{get_service_manager, ServiceManager};
+use crate::output::{CommandOutput, OutputFormat};
 use anyhow::Result;
 
 // CLI API for service commands
@@ -14,31 +15,31 @@ pub fn handle_command(args: &[&str]) -> Result<String> {
         "list" => list_services(manager),
         "status" => {
             if args.len() < 2 {
-                return Ok("Usage: service status <name>".to_string());
+                anyhow::bail!("Usage: service status <name>");
             }
             show_service_status(manager, args[1])
         },
         "start" => {
             if args.len() < 2 {
-                return Ok("Usage: service start <name>".to_string());
+                anyhow::bail!("Usage: service start <name>");
             }
             start_service(manager, args[1])
         },
         "stop" => {
             if args.len() < 2 {
-                return Ok("Usage: service stop <name>".to_string());
+                anyhow::bail!("Usage: service stop <name>");
             }
             stop_service(manager, args[1])
         },
         "restart" => {
             if args.len() < 2 {
-                return Ok("Usage: service restart <name>".to_string());
+                anyhow::bail!("Usage: service restart <name>");
             }
             restart_service(manager, args[1])
         },
         "logs" => {
             if args.len() < 2 {
-                return Ok("Usage: service logs <name> [lines]".to_string());
+                anyhow::bail!("Usage: service logs <name> [lines]");
             }
             let lines = args.get(2)
                 .and_then(|s| s.parse().ok())
@@ -51,11 +52,12 @@ pub fn handle_command(args: &[&str]) -> Result<String> {
         },
         "run" => {
             if args.len() < 2 {
-                return Ok("Usage: service run <name>".to_string());
+                anyhow::bail!("Usage: service run <name>");
             }
             run_service(args[1])
         },
-        _ => Ok(help()),
+        "help" => Ok(help()),
+        _ => anyhow::bail!("Unknown service command: {}. Try 'service help'", args[0]),
     }
 }
 
@@ -89,31 +91,12 @@ fn run_service(name: &str) -> Result<String> {
 }
 
 fn list_services(manager: &ServiceManager) -> Result<String> {
-    let services = manager.list_services()?;
-    
-    if services.is_empty() {
-        return Ok("No services configured".to_string());
-    }
-
-    let mut output = String::from("Services:\n");
-    output.push_str("NAME            STATUS       PID     RESTARTS\n");
-    output.push_str("─────────────────────────────────────────────\n");
-
-    for service in services {
-        let pid_str = service.pid
-            .map(|p| p.to_string())
-            .unwrap_or_else(|| "-".to_string());
-
-        output.push_str(&format!(
-            "{:<15} {:<12} {:<7} {}\n",
-            service.name,
-            service.status.to_string(),
-            pid_str,
-            service.restart_count
-        ));
-    }
+    Ok(OutputFormat::Human.render(&list_services_output(manager)?))
+}
 
-    Ok(output)
+/// Structured service listing for `service list`
+pub fn list_services_output(manager: &ServiceManager) -> Result<CommandOutput> {
+    Ok(CommandOutput::Services(manager.list_services()?))
 }
 
 fn show_service_status(manager: &ServiceManager, name: &str) -> Result<String> {
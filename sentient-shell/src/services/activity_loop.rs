@@ -3,15 +3,16 @@ use anyhow::{Result, Context};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::process::Command;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::{RwLock, Semaphore};
 use tokio::time::{sleep, Duration, Instant};
 use log::{info, warn, error, debug};
 use std::path::{Path, PathBuf};
 use std::fs::{OpenOptions, create_dir_all};
-use std::io::{Write, BufRead, BufReader};
+use std::io::{Write, BufRead, BufReader, Seek, SeekFrom};
 
 /// Goal execution entry for activity loop
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +39,27 @@ pub struct ActivityResult {
     pub execution_time: f32,
 }
 
+/// Executes a single goal
+#[async_trait]
+pub trait GoalRunner: Send + Sync {
+    async fn run_goal(&self, goal: ActivityGoalEntry) -> Result<ActivityResult>;
+}
+
+/// Dispatch counters for the activity loop
+#[derive(Debug, Default)]
+pub struct ActivityMetrics {
+    /// Goals pulled from the injection file and waiting for a slot
+    pub queue_depth: AtomicUsize,
+    /// Goals currently executing
+    pub in_flight: AtomicUsize,
+    /// Highest concurrency observed
+    pub peak_in_flight: AtomicUsize,
+    /// Goals finished, successfully or not
+    pub completed: AtomicUsize,
+    /// Goals left in the injection file because the queue was full
+    pub deferred: AtomicUsize,
+}
+
 /// Activity loop service - processes goals every 5 seconds with heartbeat every 60 seconds
 pub struct ActivityLoopService {
     name: String,
@@ -46,10 +68,19 @@ pub struct ActivityLoopService {
     last_heartbeat: Instant,
     logs_dir: PathBuf,
     processed_goals: Arc<RwLock<HashSet<String>>>,
+    /// Maximum number of goals executing at once
+    max_in_flight: usize,
+    /// Maximum number of goals queued waiting for a slot
+    queue_capacity: usize,
+    pending: VecDeque<ActivityGoalEntry>,
+    permits: Arc<Semaphore>,
+    runner: Arc<dyn GoalRunner>,
+    metrics: Arc<ActivityMetrics>,
 }
 
 impl ActivityLoopService {
     pub fn new() -> Self {
+        let max_in_flight = 4;
         Self {
             name: "activity-loop".to_string(),
             check_interval: Duration::from_secs(5),
@@ -57,9 +88,49 @@ impl ActivityLoopService {
             last_heartbeat: Instant::now(),
             logs_dir: PathBuf::from("logs"),
             processed_goals: Arc::new(RwLock::new(HashSet::new())),
+            max_in_flight,
+            queue_capacity: 64,
+            pending: VecDeque::new(),
+            permits: Arc::new(Semaphore::new(max_in_flight)),
+            runner: Arc::new(ShellGoalRunner),
+            metrics: Arc::new(ActivityMetrics::default()),
         }
     }
     
+    /// Set the concurrency limit and queue bound
+    pub fn with_limits(mut self, max_in_flight: usize, queue_capacity: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self.queue_capacity = queue_capacity.max(1);
+        self.permits = Arc::new(Semaphore::new(self.max_in_flight));
+        self
+    }
+    
+    pub fn with_runner(mut self, runner: Arc<dyn GoalRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+    
+    pub fn with_logs_dir(mut self, logs_dir: impl Into<PathBuf>) -> Self {
+        self.logs_dir = logs_dir.into();
+        self
+    }
+    
+    pub fn metrics(&self) -> Arc<ActivityMetrics> {
+        self.metrics.clone()
+    }
+}
+
+/// Default runner: maps goals to shell commands and scores their output
+pub struct ShellGoalRunner;
+
+#[async_trait]
+impl GoalRunner for ShellGoalRunner {
+    async fn run_goal(&self, goal: ActivityGoalEntry) -> Result<ActivityResult> {
+        self.process_goal(goal).await
+    }
+}
+
+impl ShellGoalRunner {
     /// Convert goal to actual executable command
     fn goal_to_command(&self, goal: &str) -> String {
         let goal_lower = goal.to_lowercase();
@@ -203,53 +274,87 @@ impl ActivityLoopService {
         
         Ok(result)
     }
-    
-    /// Load unprocessed goals from injection file
-    async fn load_goals(&self) -> Result<Vec<ActivityGoalEntry>> {
-        let injection_file = self.logs_dir.join("goal_injections.jsonl");
+}
+
+impl ActivityLoopService {
+    /// Load up to `limit` unprocessed goals; the rest stay queued in the file
+    async fn load_goals(&self, limit: usize) -> Result<Vec<ActivityGoalEntry>> {
+        let injection_file = crate::goal_injection::injection_file(&self.logs_dir);
         if !injection_file.exists() {
             return Ok(Vec::new());
         }
         
+        // Held until the rewrite is done, so goals appended meanwhile wait
+        // instead of being truncated away
         let mut goals = Vec::new();
-        let file = OpenOptions::new()
-            .read(true)
-            .open(&injection_file)?;
+        let mut file = crate::goal_injection::open_locked(&injection_file)?;
         
-        let reader = BufReader::new(file);
-        let lines: Vec<String> = reader.lines().collect::<std::io::Result<Vec<_>>>()?;
+        let lines: Vec<String> = BufReader::new(&file).lines().collect::<std::io::Result<Vec<_>>>()?;
         
         // Clear the file and rewrite with processed flags
-        let mut file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(&injection_file)?;
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
         
+        let mut deferred = 0;
         for line in lines {
             if let Ok(mut entry) = serde_json::from_str::<ActivityGoalEntry>(&line) {
                 if !entry.processed {
-                    goals.push(entry.clone());
-                    entry.processed = true;
+                    if goals.len() < limit {
+                        goals.push(entry.clone());
+                        entry.processed = true;
+                    } else {
+                        deferred += 1;
+                    }
                 }
                 writeln!(file, "{}", serde_json::to_string(&entry)?)?;
             }
         }
         
+        self.metrics.deferred.store(deferred, Ordering::Relaxed);
+        if deferred > 0 {
+            debug!("Queue full, deferred {} goals", deferred);
+        }
+        
         Ok(goals)
     }
     
-    /// Write execution log
-    async fn write_log(&self, entry: &ActivityResult) -> Result<()> {
-        let log_file = self.logs_dir.join(format!("activity_loop_log_{}.jsonl", 
-            Utc::now().format("%Y%m%d")));
-        
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_file)?;
+    /// Start queued goals while execution slots are free
+    fn dispatch_pending(&mut self) {
+        while !self.pending.is_empty() {
+            let permit = match self.permits.clone().try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) => break,
+            };
+            let goal = match self.pending.pop_front() {
+                Some(goal) => goal,
+                None => break,
+            };
+            
+            let runner = self.runner.clone();
+            let metrics = self.metrics.clone();
+            let logs_dir = self.logs_dir.clone();
+            
+            let running = metrics.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            metrics.peak_in_flight.fetch_max(running, Ordering::SeqCst);
+            
+            tokio::spawn(async move {
+                match runner.run_goal(goal).await {
+                    Ok(result) => {
+                        if let Err(e) = write_log(&logs_dir, &result) {
+                            error!("Failed to write activity log: {}", e);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Failed to process goal: {}", e);
+                    }
+                }
+                metrics.in_flight.fetch_sub(1, Ordering::SeqCst);
+                metrics.completed.fetch_add(1, Ordering::SeqCst);
+                drop(permit);
+            });
+        }
         
-        writeln!(file, "{}", serde_json::to_string(entry)?)?;
-        Ok(())
+        self.metrics.queue_depth.store(self.pending.len(), Ordering::Relaxed);
     }
     
     /// System heartbeat - inject health check goal
@@ -267,13 +372,7 @@ impl ActivityLoopService {
             };
             
             // Inject directly
-            let injection_file = self.logs_dir.join("goal_injections.jsonl");
-            let mut file = OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&injection_file)?;
-            
-            writeln!(file, "{}", serde_json::to_string(&health_goal)?)?;
+            crate::goal_injection::append_goal_injection(&self.logs_dir, &health_goal)?;
             
             self.last_heartbeat = current_time;
         }
@@ -298,6 +397,22 @@ impl SentientService for ActivityLoopService {
         info!("   Heartbeat interval: {:?}", self.heartbeat_interval);
         info!("   Logs directory: {:?}", self.logs_dir);
         
+        let max_in_flight = std::env::var("ACTIVITY_MAX_IN_FLIGHT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(self.max_in_flight);
+        let queue_capacity = std::env::var("ACTIVITY_QUEUE_CAPACITY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(self.queue_capacity);
+        if max_in_flight != self.max_in_flight || queue_capacity != self.queue_capacity {
+            self.max_in_flight = max_in_flight.max(1);
+            self.queue_capacity = queue_capacity.max(1);
+            self.permits = Arc::new(Semaphore::new(self.max_in_flight));
+        }
+        info!("   Max in-flight goals: {}", self.max_in_flight);
+        info!("   Queue capacity: {}", self.queue_capacity);
+        
         Ok(())
     }
     
@@ -326,23 +441,20 @@ impl SentientService for ActivityLoopService {
 impl ActivityLoopService {
     /// Run one iteration of the activity loop
     async fn run_iteration(&mut self) -> Result<()> {
-        // Load new goals
-        let goals = self.load_goals().await?;
+        // Only pull as many goals as the queue can hold
+        let capacity = self.queue_capacity.saturating_sub(self.pending.len());
+        let goals = self.load_goals(capacity).await?;
         
         if !goals.is_empty() {
             info!("📥 Found {} new goals", goals.len());
-            
-            // Process each goal
-            for goal in goals {
-                match self.process_goal(goal).await {
-                    Ok(result) => {
-                        self.write_log(&result).await?;
-                    }
-                    Err(e) => {
-                        error!("Failed to process goal: {}", e);
-                    }
-                }
-            }
+            self.pending.extend(goals);
+        }
+        
+        self.dispatch_pending();
+        if !self.pending.is_empty() {
+            debug!("Queue depth: {} (in flight: {})",
+                self.pending.len(),
+                self.metrics.in_flight.load(Ordering::Relaxed));
         }
         
         // Heartbeat check
@@ -352,13 +464,27 @@ impl ActivityLoopService {
     }
 }
 
+/// Append an execution result to today's activity log
+fn write_log(logs_dir: &Path, entry: &ActivityResult) -> Result<()> {
+    let log_file = logs_dir.join(format!("activity_loop_log_{}.jsonl", 
+        Utc::now().format("%Y%m%d")));
+    
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_file)?;
+    
+    writeln!(file, "{}", serde_json::to_string(entry)?)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     
     #[tokio::test]
     async fn test_goal_to_command() {
-        let service = ActivityLoopService::new();
+        let service = ShellGoalRunner;
         
         // Test disk activity mapping
         let cmd = service.goal_to_command("Check disk activity and I/O");
@@ -380,7 +506,7 @@ mod tests {
     
     #[test]
     fn test_calculate_reward() {
-        let service = ActivityLoopService::new();
+        let service = ShellGoalRunner;
         
         // Test failure
         assert_eq!(service.calculate_reward("", false), 0.0);
@@ -401,4 +527,77 @@ mod tests {
         // Test with error penalty
         assert!(service.calculate_reward("Error: something failed", true) < 0.5);
     }
-}
\ No newline at end of file
+    
+    /// Runner that sleeps and records how many goals overlap
+    struct SlowRunner {
+        running: AtomicUsize,
+        peak: AtomicUsize,
+    }
+    
+    #[async_trait]
+    impl GoalRunner for SlowRunner {
+        async fn run_goal(&self, goal: ActivityGoalEntry) -> Result<ActivityResult> {
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            sleep(Duration::from_millis(50)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            
+            Ok(ActivityResult {
+                timestamp: Utc::now(),
+                goal: goal.goal,
+                source: goal.source,
+                command: "true".to_string(),
+                output: String::new(),
+                success: true,
+                reward: 0.3,
+                execution_time: 0.05,
+            })
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_in_flight_limit_is_respected() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let injection_file = temp_dir.path().join("goal_injections.jsonl");
+        let mut file = OpenOptions::new().create(true).append(true).open(&injection_file).unwrap();
+        for i in 0..10 {
+            let entry = ActivityGoalEntry {
+                goal: format!("Goal {}", i),
+                source: "test".to_string(),
+                timestamp: Utc::now(),
+                processed: false,
+                priority: None,
+            };
+            writeln!(file, "{}", serde_json::to_string(&entry).unwrap()).unwrap();
+        }
+        
+        let runner = Arc::new(SlowRunner {
+            running: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+        });
+        let mut service = ActivityLoopService::new()
+            .with_logs_dir(temp_dir.path())
+            .with_limits(3, 4)
+            .with_runner(runner.clone());
+        let metrics = service.metrics();
+        
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while metrics.completed.load(Ordering::SeqCst) < 10 {
+            assert!(Instant::now() < deadline, "goals were not all processed");
+            service.run_iteration().await.unwrap();
+            assert!(metrics.queue_depth.load(Ordering::SeqCst) <= 4);
+            sleep(Duration::from_millis(10)).await;
+        }
+        
+        assert!(runner.peak.load(Ordering::SeqCst) <= 3);
+        assert!(metrics.peak_in_flight.load(Ordering::SeqCst) <= 3);
+        assert_eq!(metrics.completed.load(Ordering::SeqCst), 10);
+        
+        let log = std::fs::read_dir(temp_dir.path()).unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with("activity_loop_log_"))
+            .map(|e| std::fs::read_to_string(e.path()).unwrap())
+            .collect::<String>();
+        assert_eq!(log.lines().count(), 10);
+    }
+}
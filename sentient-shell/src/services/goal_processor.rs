@@ -3,34 +3,103 @@ use anyhow::{Result, Context};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::process::Command;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tokio::time::{sleep, Duration};
 use log::{info, warn, error};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::fs::{OpenOptions, create_dir_all};
 use std::io::Write;
+use sha2::{Digest, Sha256};
+
+/// File recording the keys of goals that have already run
+const PROCESSED_FILE: &str = "goal_processor_processed.txt";
 
 /// Goal execution entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GoalEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goal_id: Option<String>,
     pub goal: String,
     pub source: String,
     pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    /// Seed recorded by the injector, needed to replay the goal
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// What selected the goal, when it differs from `source`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action_source: Option<String>,
+    /// Trace id assigned when the goal was executed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    #[serde(default)]
     pub processed: bool,
+    #[serde(default)]
     pub command: Option<String>,
+    #[serde(default)]
     pub output: Option<String>,
+    #[serde(default)]
     pub success: bool,
+    #[serde(default)]
     pub reward: f32,
+    #[serde(default)]
     pub execution_time: f32,
 }
 
+impl GoalEntry {
+    /// Stable identity used for de-duplication
+    pub fn key(&self) -> String {
+        if let Some(id) = &self.goal_id {
+            return id.clone();
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(self.source.as_bytes());
+        hasher.update(self.timestamp.to_rfc3339().as_bytes());
+        hasher.update(self.goal.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+    
+    /// Dashboard activity record for this goal's execution
+    pub fn to_activity_entry(&self) -> crate::web_ui::ActivityEntry {
+        crate::web_ui::ActivityEntry {
+            timestamp: self.timestamp,
+            goal: self.goal.clone(),
+            command: self.command.clone().unwrap_or_default(),
+            output: self.output.clone().unwrap_or_default(),
+            success: self.success,
+            reward: self.reward,
+            execution_time: self.execution_time,
+            source: self.source.clone(),
+            goal_id: Some(self.key()),
+            seed: self.seed,
+            action_source: Some(self.action_source.clone().unwrap_or_else(|| self.source.clone())),
+            trace_id: self.trace_id.clone().or_else(crate::trace_id::current),
+        }
+    }
+    
+    /// Numeric priority, higher runs first
+    pub fn priority_rank(&self) -> u8 {
+        match self.priority.as_deref().map(|p| p.to_lowercase()) {
+            Some(p) if p == "critical" => 3,
+            Some(p) if p == "high" => 2,
+            Some(p) if p == "low" => 0,
+            _ => 1,
+        }
+    }
+}
+
 /// Goal processor service - executes goals every 5 seconds
 pub struct GoalProcessorService {
     name: String,
     goals_queue: Arc<RwLock<Vec<GoalEntry>>>,
+    /// Keys of goals already executed, persisted across restarts
+    processed: HashSet<String>,
+    /// Keys of goals currently sitting in the queue
+    queued: HashSet<String>,
     logs_dir: String,
     goal_interval_ms: u64,
     heartbeat_interval_ms: u64,
@@ -42,6 +111,8 @@ impl GoalProcessorService {
         Self {
             name: "goal-processor".to_string(),
             goals_queue: Arc::new(RwLock::new(Vec::new())),
+            processed: HashSet::new(),
+            queued: HashSet::new(),
             logs_dir: "logs".to_string(),
             goal_interval_ms: 5000,
             heartbeat_interval_ms: 60000,
@@ -142,6 +213,7 @@ impl GoalProcessorService {
     async fn execute_goal(&self, goal: &GoalEntry) -> (String, String, bool, f32, f32) {
         let start = std::time::Instant::now();
         let command = self.goal_to_command(&goal.goal);
+        let _trace = goal.trace_id.as_deref().map(crate::trace_id::enter);
         
         info!("🎯 Executing goal: {} -> {}", goal.goal, command);
         
@@ -169,28 +241,133 @@ impl GoalProcessorService {
         }
     }
     
-    /// Load goals from injection file
-    async fn load_goals(&self) -> Result<Vec<GoalEntry>> {
+    pub fn with_logs_dir(mut self, logs_dir: impl Into<String>) -> Self {
+        self.logs_dir = logs_dir.into();
+        self
+    }
+    
+    fn processed_file(&self) -> PathBuf {
+        Path::new(&self.logs_dir).join(PROCESSED_FILE)
+    }
+    
+    /// Restore the processed-key set written by previous runs
+    fn load_processed(&mut self) -> Result<()> {
+        let path = self.processed_file();
+        if path.exists() {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {:?}", path))?;
+            self.processed = content.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect();
+        }
+        Ok(())
+    }
+    
+    /// Record a goal as executed so it is never run again
+    fn mark_processed(&mut self, goal: &GoalEntry) -> Result<()> {
+        let key = goal.key();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.processed_file())?;
+        writeln!(file, "{}", key)?;
+        self.queued.remove(&key);
+        self.processed.insert(key);
+        Ok(())
+    }
+    
+    /// Load goals from injection file that have not run or been queued yet.
+    ///
+    /// The injection file is shared with other writers, so it is only ever
+    /// read: progress is tracked in the processed-key file instead of being
+    /// written back, and a trailing line without a newline is treated as a
+    /// write still in progress and left for the next pass.
+    async fn load_goals(&mut self) -> Result<Vec<GoalEntry>> {
         let injection_file = Path::new(&self.logs_dir).join("goal_injections.jsonl");
         if !injection_file.exists() {
             return Ok(Vec::new());
         }
         
         let content = tokio::fs::read_to_string(&injection_file).await?;
+        let complete = match content.rfind('\n') {
+            Some(end) => &content[..=end],
+            None => "",
+        };
         let mut goals = Vec::new();
         
-        for line in content.lines() {
-            if let Ok(mut entry) = serde_json::from_str::<GoalEntry>(line) {
-                if !entry.processed {
-                    entry.processed = true;
-                    goals.push(entry);
+        for line in complete.lines() {
+            if let Ok(entry) = serde_json::from_str::<GoalEntry>(line) {
+                if entry.processed {
+                    continue;
+                }
+                let key = entry.key();
+                if self.processed.contains(&key) || !self.queued.insert(key) {
+                    continue;
                 }
+                goals.push(entry);
             }
         }
         
         Ok(goals)
     }
     
+    /// Add goals to the queue, keeping it ordered by priority then age
+    async fn enqueue(&self, goals: Vec<GoalEntry>) {
+        let mut queue = self.goals_queue.write().await;
+        queue.extend(goals);
+        queue.sort_by(|a, b| {
+            b.priority_rank()
+                .cmp(&a.priority_rank())
+                .then(a.timestamp.cmp(&b.timestamp))
+        });
+    }
+    
+    /// Pull new goals and execute the highest-priority one
+    async fn process_next(&mut self) -> Option<GoalEntry> {
+        match self.load_goals().await {
+            Ok(new_goals) => {
+                if !new_goals.is_empty() {
+                    info!("📥 Loaded {} new goals", new_goals.len());
+                    self.enqueue(new_goals).await;
+                }
+            }
+            Err(e) => {
+                warn!("Failed to load goals: {}", e);
+            }
+        }
+        
+        let goal = {
+            let mut queue = self.goals_queue.write().await;
+            if queue.is_empty() { None } else { Some(queue.remove(0)) }
+        };
+        
+        let mut goal = goal?;
+        goal.trace_id = Some(crate::trace_id::new_trace_id());
+        let (command, output, success, reward, exec_time) = 
+            self.execute_goal(&goal).await;
+        
+        // Update goal with results
+        goal.processed = true;
+        goal.command = Some(command);
+        goal.output = Some(output);
+        goal.success = success;
+        goal.reward = reward;
+        goal.execution_time = exec_time;
+        
+        if let Err(e) = self.mark_processed(&goal) {
+            error!("Failed to record processed goal: {}", e);
+        }
+        
+        // Log execution
+        if let Err(e) = self.write_log(&goal).await {
+            error!("Failed to write log: {}", e);
+        }
+        crate::web_ui::record_activity(goal.to_activity_entry()).await;
+        
+        info!("✓ Goal processed: {} (reward: {:.2})", 
+              &goal.goal[..50.min(goal.goal.len())], reward);
+        
+        Some(goal)
+    }
+    
     /// Write execution log
     async fn write_log(&self, entry: &GoalEntry) -> Result<()> {
         let log_file = Path::new(&self.logs_dir)
@@ -217,9 +394,14 @@ impl GoalProcessorService {
             
             // Could inject a system health check goal here
             let health_goal = GoalEntry {
+                goal_id: None,
                 goal: "Check system health and resource usage".to_string(),
                 source: "heartbeat".to_string(),
                 timestamp: now,
+                priority: Some("low".to_string()),
+                seed: None,
+                action_source: None,
+                trace_id: None,
                 processed: false,
                 command: None,
                 output: None,
@@ -228,7 +410,8 @@ impl GoalProcessorService {
                 execution_time: 0.0,
             };
             
-            self.goals_queue.write().await.push(health_goal);
+            self.queued.insert(health_goal.key());
+            self.enqueue(vec![health_goal]).await;
         }
     }
 }
@@ -245,6 +428,9 @@ impl SentientService for GoalProcessorService {
         // Create logs directory
         create_dir_all(&self.logs_dir)?;
         
+        self.load_processed()?;
+        info!("  Previously processed goals: {}", self.processed.len());
+        
         // Load configuration from environment
         if let Ok(interval) = std::env::var("GOAL_INTERVAL_MS") {
             self.goal_interval_ms = interval.parse().unwrap_or(5000);
@@ -264,41 +450,8 @@ impl SentientService for GoalProcessorService {
         info!("✅ Goal Processor Service started");
         
         loop {
-            // Load new goals
-            match self.load_goals().await {
-                Ok(new_goals) => {
-                    if !new_goals.is_empty() {
-                        info!("📥 Loaded {} new goals", new_goals.len());
-                        self.goals_queue.write().await.extend(new_goals);
-                    }
-                }
-                Err(e) => {
-                    warn!("Failed to load goals: {}", e);
-                }
-            }
-            
-            // Process next goal
-            let goal = self.goals_queue.write().await.pop();
-            
-            if let Some(mut goal) = goal {
-                let (command, output, success, reward, exec_time) = 
-                    self.execute_goal(&goal).await;
-                
-                // Update goal with results
-                goal.command = Some(command);
-                goal.output = Some(output.clone());
-                goal.success = success;
-                goal.reward = reward;
-                goal.execution_time = exec_time;
-                
-                // Log execution
-                if let Err(e) = self.write_log(&goal).await {
-                    error!("Failed to write log: {}", e);
-                }
-                
-                info!("✓ Goal processed: {} (reward: {:.2})", 
-                      &goal.goal[..50.min(goal.goal.len())], reward);
-            }
+            // Load new goals and process the next one
+            self.process_next().await;
             
             // Heartbeat check
             self.heartbeat().await;
@@ -312,4 +465,121 @@ impl SentientService for GoalProcessorService {
         info!("Shutting down Goal Processor Service");
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    
+    fn injection(goal: &str, priority: &str, minutes_ago: i64) -> String {
+        serde_json::to_string(&json_goal(goal, priority, minutes_ago)).unwrap()
+    }
+    
+    fn json_goal(goal: &str, priority: &str, minutes_ago: i64) -> serde_json::Value {
+        serde_json::json!({
+            "goal": goal,
+            "source": "test",
+            "timestamp": (Utc::now() - chrono::Duration::minutes(minutes_ago)).to_rfc3339(),
+            "priority": priority,
+            "injected": true,
+            "processed": false,
+        })
+    }
+    
+    #[tokio::test]
+    async fn test_priority_order_and_single_execution() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().to_str().unwrap().to_string();
+        
+        let duplicate = injection("Echo medium", "medium", 3);
+        let lines = vec![
+            injection("Echo low", "low", 10),
+            injection("Echo late high", "high", 1),
+            duplicate.clone(),
+            injection("Echo early high", "high", 5),
+            duplicate,
+        ];
+        std::fs::write(
+            temp_dir.path().join("goal_injections.jsonl"),
+            lines.join("\n") + "\n",
+        ).unwrap();
+        
+        let mut service = GoalProcessorService::new().with_logs_dir(dir.clone());
+        service.load_processed().unwrap();
+        
+        let mut order = Vec::new();
+        while let Some(goal) = service.process_next().await {
+            order.push(goal.goal);
+        }
+        assert_eq!(order, vec!["Echo early high", "Echo late high", "Echo medium", "Echo low"]);
+        
+        // A restarted processor must not run anything again
+        let mut restarted = GoalProcessorService::new().with_logs_dir(dir);
+        restarted.load_processed().unwrap();
+        assert!(restarted.process_next().await.is_none());
+    }
+    
+    #[tokio::test]
+    async fn test_partial_line_is_left_for_next_pass() {
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("goal_injections.jsonl");
+        let complete = injection("Echo first", "medium", 2);
+        let partial = injection("Echo second", "medium", 1);
+        std::fs::write(&file, format!("{}\n{}", complete, &partial[..partial.len() / 2])).unwrap();
+        
+        let mut service = GoalProcessorService::new()
+            .with_logs_dir(temp_dir.path().to_str().unwrap());
+        assert_eq!(service.load_goals().await.unwrap().len(), 1);
+        
+        // The writer finishes its line
+        std::fs::write(&file, format!("{}\n{}\n", complete, partial)).unwrap();
+        let goals = service.load_goals().await.unwrap();
+        assert_eq!(goals.len(), 1);
+        assert_eq!(goals[0].goal, "Echo second");
+    }
+    
+    #[test]
+    fn test_activity_entry_carries_replay_fields() {
+        let mut value = json_goal("Echo replay", "high", 1);
+        value["goal_id"] = serde_json::json!("goal-7");
+        value["seed"] = serde_json::json!(1234);
+        value["action_source"] = serde_json::json!("rl_policy");
+        value["source"] = serde_json::json!("injector");
+        
+        let goal: GoalEntry = serde_json::from_value(value).unwrap();
+        let entry = goal.to_activity_entry();
+        assert_eq!(entry.goal_id.as_deref(), Some("goal-7"));
+        assert_eq!(entry.seed, Some(1234));
+        assert_eq!(entry.action_source.as_deref(), Some("rl_policy"));
+    }
+    
+    #[tokio::test]
+    async fn test_executed_goal_reaches_dashboard_activity() {
+        let dashboard = Arc::new(crate::web_ui::DashboardState::new());
+        crate::web_ui::attach_dashboard(dashboard.clone()).await;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let mut value = json_goal("Echo dashboard", "high", 1);
+        value["goal_id"] = serde_json::json!("goal-dashboard");
+        value["seed"] = serde_json::json!(99);
+        value["source"] = serde_json::json!("rl_policy");
+        std::fs::write(
+            temp_dir.path().join("goal_injections.jsonl"),
+            format!("{}\n", value),
+        ).unwrap();
+        
+        let mut service = GoalProcessorService::new()
+            .with_logs_dir(temp_dir.path().to_str().unwrap());
+        let goal = service.process_next().await.unwrap();
+        
+        let log = dashboard.activity_log.read().await;
+        let entry = log.iter()
+            .find(|entry| entry.goal_id.as_deref() == Some("goal-dashboard"))
+            .expect("executed goal missing from dashboard activity");
+        assert_eq!(entry.seed, Some(99));
+        assert_eq!(entry.action_source.as_deref(), Some("rl_policy"));
+        assert_eq!(entry.trace_id, goal.trace_id);
+        assert_eq!(entry.command, goal.command.unwrap());
+    }
+}
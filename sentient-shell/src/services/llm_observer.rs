@@ -6,9 +6,11 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tokio::time::{sleep, Duration};
 use log::{info, warn, error};
-use std::path::Path;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::fs::OpenOptions;
 use std::io::Write;
+use std::sync::Mutex;
 use rand::seq::SliceRandom;
 
 /// LLM response from Ollama
@@ -31,13 +33,213 @@ pub struct GoalInjection {
     pub processed: bool,
 }
 
+/// Thresholds for flagging abnormal model behavior
+#[derive(Debug, Clone)]
+pub struct AnomalyConfig {
+    /// Interactions kept per model for rolling statistics
+    pub window: usize,
+    /// Interactions needed before z-scores are trusted
+    pub min_samples: usize,
+    /// Latency z-score above which a spike is flagged
+    pub latency_z: f64,
+    /// Absolute response-length z-score above which a deviation is flagged
+    pub length_z: f64,
+    /// Consecutive empty responses that trigger a flag
+    pub empty_run: usize,
+    /// Consecutive refusals that trigger a flag
+    pub refusal_run: usize,
+    /// Lowercase phrases that mark a response as a refusal
+    pub refusal_markers: Vec<String>,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            window: 50,
+            min_samples: 10,
+            latency_z: 3.0,
+            length_z: 3.0,
+            empty_run: 3,
+            refusal_run: 3,
+            refusal_markers: vec![
+                "i can't".to_string(),
+                "i cannot".to_string(),
+                "i'm sorry".to_string(),
+                "as an ai".to_string(),
+            ],
+        }
+    }
+}
+
+/// One observed prompt/response exchange
+#[derive(Debug, Clone)]
+pub struct LlmInteraction {
+    pub model: String,
+    pub latency_ms: f64,
+    pub response: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyKind {
+    LatencySpike,
+    LengthDeviation,
+    EmptyRun,
+    RefusalRun,
+}
+
+/// Flag raised when a model deviates from its recent behavior
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnomalyFlag {
+    pub model: String,
+    pub kind: AnomalyKind,
+    pub value: f64,
+    pub z_score: Option<f64>,
+    pub empty_rate: f64,
+    pub refusal_rate: f64,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Fixed-size window of samples
+#[derive(Debug, Clone, Default)]
+struct RollingStats {
+    values: VecDeque<f64>,
+}
+
+impl RollingStats {
+    fn push(&mut self, value: f64, cap: usize) {
+        if self.values.len() >= cap {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    fn mean(&self) -> f64 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        self.values.iter().sum::<f64>() / self.values.len() as f64
+    }
+
+    fn std_dev(&self) -> f64 {
+        let n = self.values.len();
+        if n < 2 {
+            return 0.0;
+        }
+        let mean = self.mean();
+        let var = self.values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        var.sqrt()
+    }
+
+    /// Z-score of `value` against the window. A small floor on the deviation
+    /// keeps perfectly steady streams from flagging on trivial jitter.
+    fn z_score(&self, value: f64) -> f64 {
+        let mean = self.mean();
+        let std = self.std_dev().max(mean.abs() * 0.01).max(f64::EPSILON);
+        (value - mean) / std
+    }
+}
+
+#[derive(Debug, Default)]
+struct ModelStats {
+    latency: RollingStats,
+    length: RollingStats,
+    empty: RollingStats,
+    refusal: RollingStats,
+    empty_streak: usize,
+    refusal_streak: usize,
+}
+
+/// Tracks per-model rolling stats and flags deviations
+#[derive(Debug, Default)]
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    models: HashMap<String, ModelStats>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            models: HashMap::new(),
+        }
+    }
+
+    /// Record an interaction, returning any anomalies it reveals
+    pub fn observe(&mut self, interaction: &LlmInteraction) -> Vec<AnomalyFlag> {
+        let config = &self.config;
+        let stats = self.models.entry(interaction.model.clone()).or_default();
+        let mut flags = Vec::new();
+
+        let text = interaction.response.trim();
+        let is_empty = text.is_empty();
+        let lower = text.to_lowercase();
+        let is_refusal = !is_empty && config.refusal_markers.iter().any(|m| lower.contains(m.as_str()));
+        let length = text.chars().count() as f64;
+
+        stats.empty.push(if is_empty { 1.0 } else { 0.0 }, config.window);
+        stats.refusal.push(if is_refusal { 1.0 } else { 0.0 }, config.window);
+        let empty_rate = stats.empty.mean();
+        let refusal_rate = stats.refusal.mean();
+
+        let flag = |kind, value, z_score| AnomalyFlag {
+            model: interaction.model.clone(),
+            kind,
+            value,
+            z_score,
+            empty_rate,
+            refusal_rate,
+            timestamp: Utc::now(),
+        };
+
+        // Compare against the window before this sample joins it
+        if stats.latency.len() >= config.min_samples {
+            let z = stats.latency.z_score(interaction.latency_ms);
+            if z > config.latency_z {
+                flags.push(flag(AnomalyKind::LatencySpike, interaction.latency_ms, Some(z)));
+            }
+        }
+        if !is_empty && stats.length.len() >= config.min_samples {
+            let z = stats.length.z_score(length);
+            if z.abs() > config.length_z {
+                flags.push(flag(AnomalyKind::LengthDeviation, length, Some(z)));
+            }
+        }
+
+        // Runs fire once, when they reach the threshold
+        stats.empty_streak = if is_empty { stats.empty_streak + 1 } else { 0 };
+        if stats.empty_streak == config.empty_run {
+            flags.push(flag(AnomalyKind::EmptyRun, stats.empty_streak as f64, None));
+        }
+        stats.refusal_streak = if is_refusal { stats.refusal_streak + 1 } else { 0 };
+        if stats.refusal_streak == config.refusal_run {
+            flags.push(flag(AnomalyKind::RefusalRun, stats.refusal_streak as f64, None));
+        }
+
+        stats.latency.push(interaction.latency_ms, config.window);
+        if !is_empty {
+            stats.length.push(length, config.window);
+        }
+
+        flags
+    }
+}
+
 /// LLM Observer Service - Periodically injects AI-generated goals
 pub struct LlmObserverService {
     name: String,
     interval_ms: u64,
     ollama_url: String,
+    model: String,
     logs_dir: String,
     fallback_goals: Vec<String>,
+    detector: Mutex<AnomalyDetector>,
+    /// HiveFix-watched log that also receives anomaly events, if set
+    hivefix_log: Option<PathBuf>,
 }
 
 impl LlmObserverService {
@@ -46,6 +248,7 @@ impl LlmObserverService {
             name: "llm-observer".to_string(),
             interval_ms: 30000, // 30 seconds default
             ollama_url: "http://192.168.69.197:11434".to_string(),
+            model: "deepseek-v2:16b".to_string(),
             logs_dir: "logs".to_string(),
             fallback_goals: vec![
                 "Monitor disk I/O activity and report any anomalies".to_string(),
@@ -59,7 +262,56 @@ impl LlmObserverService {
                 "Analyze process count trends over time".to_string(),
                 "Identify potential performance bottlenecks".to_string(),
             ],
+            detector: Mutex::new(AnomalyDetector::default()),
+            hivefix_log: None,
+        }
+    }
+    
+    /// Feed an interaction to the detector and report any flags
+    fn record_interaction(&self, interaction: &LlmInteraction) -> Vec<AnomalyFlag> {
+        let flags = self.detector.lock().unwrap().observe(interaction);
+        for flag in &flags {
+            warn!("⚠️ LLM anomaly on {}: {:?} (value {:.1})", flag.model, flag.kind, flag.value);
+            if let Err(e) = self.report_anomaly(flag) {
+                error!("Failed to record anomaly: {}", e);
+            }
         }
+        flags
+    }
+    
+    /// Write an anomaly to the activity log and, if configured, the HiveFix log
+    fn report_anomaly(&self, flag: &AnomalyFlag) -> Result<()> {
+        let entry = json!({
+            "type": "llm_anomaly",
+            "source": "llm_observer",
+            "anomaly": flag,
+        });
+        
+        let log_file = Path::new(&self.logs_dir)
+            .join(format!("llm_activity_{}.jsonl", Utc::now().format("%Y%m%d")));
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_file)?;
+        writeln!(file, "{}", entry)?;
+        
+        if let Some(path) = &self.hivefix_log {
+            let event = crate::hivefix::ErrorEvent {
+                id: uuid::Uuid::new_v4().to_string(),
+                timestamp: flag.timestamp.into(),
+                source: crate::hivefix::ErrorSource::System,
+                message: format!("LLM anomaly {:?} on model {}", flag.kind, flag.model),
+                stack_trace: None,
+                context: Some(entry.to_string()),
+            };
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?;
+            writeln!(file, "{}", serde_json::to_string(&event)?)?;
+        }
+        
+        Ok(())
     }
     
     /// Query Ollama for goal generation
@@ -69,7 +321,7 @@ impl LlmObserverService {
             .build()?;
         
         let payload = json!({
-            "model": "deepseek-v2:16b",
+            "model": self.model,
             "prompt": prompt,
             "stream": false,
             "options": {
@@ -78,6 +330,7 @@ impl LlmObserverService {
             }
         });
         
+        let started = std::time::Instant::now();
         let response = client
             .post(format!("{}/api/generate", self.ollama_url))
             .json(&payload)
@@ -87,7 +340,13 @@ impl LlmObserverService {
         
         if response.status().is_success() {
             let data: OllamaResponse = response.json().await?;
-            Ok(data.response.trim().to_string())
+            let text = data.response.trim().to_string();
+            self.record_interaction(&LlmInteraction {
+                model: self.model.clone(),
+                latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+                response: text.clone(),
+            });
+            Ok(text)
         } else {
             Err(anyhow::anyhow!("Ollama returned status: {}", response.status()))
         }
@@ -143,14 +402,8 @@ Goal:"#;
         };
         
         // Write to goal injection file
-        let injection_file = Path::new(&self.logs_dir).join("goal_injections.jsonl");
-        let mut file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&injection_file)
-            .context("Failed to open injection file")?;
-        
-        writeln!(file, "{}", serde_json::to_string(&injection)?)?;
+        crate::goal_injection::append_goal_injection(Path::new(&self.logs_dir), &injection)
+            .context("Failed to write injection")?;
         
         info!("✓ Injected goal: {}...", &goal[..60.min(goal.len())]);
         
@@ -196,6 +449,19 @@ impl SentientService for LlmObserverService {
             self.ollama_url = url;
         }
         
+        if let Ok(path) = std::env::var("LLM_ANOMALY_HIVEFIX_LOG") {
+            self.hivefix_log = Some(PathBuf::from(path));
+        }
+        
+        let mut config = AnomalyConfig::default();
+        if let Some(z) = std::env::var("LLM_ANOMALY_LATENCY_Z").ok().and_then(|v| v.parse().ok()) {
+            config.latency_z = z;
+        }
+        if let Some(z) = std::env::var("LLM_ANOMALY_LENGTH_Z").ok().and_then(|v| v.parse().ok()) {
+            config.length_z = z;
+        }
+        self.detector = Mutex::new(AnomalyDetector::new(config));
+        
         info!("  Injection interval: {}ms", self.interval_ms);
         info!("  Ollama URL: {}", self.ollama_url);
         
@@ -261,4 +527,51 @@ mod tests {
         assert!(content.contains("Test goal"));
         assert!(content.contains("llm_observer"));
     }
-}
\ No newline at end of file
+    
+    fn interaction(latency_ms: f64, response: &str) -> LlmInteraction {
+        LlmInteraction {
+            model: "test-model".to_string(),
+            latency_ms,
+            response: response.to_string(),
+        }
+    }
+    
+    #[test]
+    fn test_latency_spike_flags_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = LlmObserverService::new();
+        service.logs_dir = temp_dir.path().to_str().unwrap().to_string();
+        
+        let normal = "Check disk usage on the root filesystem";
+        let mut flags = Vec::new();
+        for i in 0..30 {
+            flags.extend(service.record_interaction(&interaction(100.0 + (i % 5) as f64 * 4.0, normal)));
+        }
+        flags.extend(service.record_interaction(&interaction(2500.0, normal)));
+        for i in 0..10 {
+            flags.extend(service.record_interaction(&interaction(100.0 + (i % 5) as f64 * 4.0, normal)));
+        }
+        
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].kind, AnomalyKind::LatencySpike);
+        assert_eq!(flags[0].value, 2500.0);
+        
+        let log = std::fs::read_dir(temp_dir.path()).unwrap()
+            .map(|e| std::fs::read_to_string(e.unwrap().path()).unwrap())
+            .collect::<String>();
+        assert_eq!(log.matches("llm_anomaly").count(), 1);
+    }
+    
+    #[test]
+    fn test_empty_run_flags_at_threshold() {
+        let mut detector = AnomalyDetector::default();
+        let mut flags = Vec::new();
+        for _ in 0..5 {
+            flags.extend(detector.observe(&interaction(100.0, "")));
+        }
+        
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].kind, AnomalyKind::EmptyRun);
+        assert!(flags[0].empty_rate > 0.5);
+    }
+}
@@ -1,18 +1,260 @@
 use super::SentientService;
-use anyhow::Result;
+use super::activity_loop::ActivityResult;
+use crate::clock::{system_clock, Clock};
+use crate::hivefix::communicator::fingerprint_error;
+use crate::hivefix::{ErrorEvent, ErrorSource};
+use anyhow::{Result, Context};
 use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use log::{info, warn, error, debug};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs::{OpenOptions, create_dir_all};
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration};
+
+/// Maximum length accepted for an injected goal
+const MAX_GOAL_LEN: usize = 500;
+
+/// Repeated failures sharing one HiveFix signature
+#[derive(Debug, Clone)]
+pub struct FailureCluster {
+    pub signature: String,
+    pub count: usize,
+    pub sample_goal: String,
+    pub sample_command: String,
+    pub sample_output: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl FailureCluster {
+    /// Confidence grows with how often the failure repeats
+    pub fn confidence(&self) -> f32 {
+        let count = self.count as f32;
+        (count / (count + 2.0)).min(0.95)
+    }
+}
+
+/// Goal summarizing one failure cluster
+#[derive(Debug, Clone)]
+pub struct SummarizedGoal {
+    pub goal: String,
+    pub reasoning: String,
+    pub confidence: f32,
+    pub signature: String,
+}
 
 /// Reflective Analyzer Service - Analyzes system behavior and generates insights
 pub struct ReflectiveAnalyzerService {
     name: String,
+    logs_dir: PathBuf,
+    interval: Duration,
+    /// How far back to look for failures
+    lookback: ChronoDuration,
+    /// Failures needed before a cluster becomes a goal
+    min_failures: usize,
+    /// Minimum time between goals for the same signature
+    cooldown: ChronoDuration,
+    /// Cap on goals emitted in one analysis pass
+    max_goals_per_cycle: usize,
+    last_emitted: HashMap<String, DateTime<Utc>>,
+    clock: Arc<dyn Clock>,
 }
 
 impl ReflectiveAnalyzerService {
     pub fn new() -> Self {
         Self {
             name: "reflective-analyzer".to_string(),
+            logs_dir: PathBuf::from("logs"),
+            interval: Duration::from_secs(300),
+            lookback: ChronoDuration::hours(1),
+            min_failures: 3,
+            cooldown: ChronoDuration::minutes(30),
+            max_goals_per_cycle: 2,
+            last_emitted: HashMap::new(),
+            clock: system_clock(),
         }
     }
+
+    pub fn with_logs_dir(mut self, logs_dir: impl Into<PathBuf>) -> Self {
+        self.logs_dir = logs_dir.into();
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Read failed activity results newer than `since`
+    fn collect_failures(&self, since: DateTime<Utc>) -> Result<Vec<ActivityResult>> {
+        let mut failures = Vec::new();
+        if !self.logs_dir.exists() {
+            return Ok(failures);
+        }
+
+        for entry in std::fs::read_dir(&self.logs_dir)? {
+            let path = entry?.path();
+            let is_activity_log = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with("activity_loop_log_") && n.ends_with(".jsonl"))
+                .unwrap_or(false);
+            if !is_activity_log {
+                continue;
+            }
+
+            let file = OpenOptions::new().read(true).open(&path)
+                .with_context(|| format!("Failed to open {:?}", path))?;
+            for line in BufReader::new(file).lines() {
+                let line = line?;
+                match serde_json::from_str::<ActivityResult>(&line) {
+                    Ok(result) if !result.success && result.timestamp >= since => {
+                        failures.push(result)
+                    }
+                    Ok(_) => {}
+                    Err(e) => debug!("Skipping unparseable activity entry: {}", e),
+                }
+            }
+        }
+
+        Ok(failures)
+    }
+
+    /// Group failures by HiveFix error signature, most frequent first
+    pub fn cluster_failures(failures: &[ActivityResult]) -> Vec<FailureCluster> {
+        let mut clusters: HashMap<String, FailureCluster> = HashMap::new();
+
+        for failure in failures {
+            let signature = fingerprint_error(&ErrorEvent {
+                id: String::new(),
+                timestamp: failure.timestamp.into(),
+                source: ErrorSource::Shell,
+                message: format!("{}: {}", failure.command, failure.output),
+                stack_trace: None,
+                context: None,
+            });
+
+            clusters
+                .entry(signature.clone())
+                .and_modify(|c| {
+                    c.count += 1;
+                    c.first_seen = c.first_seen.min(failure.timestamp);
+                    c.last_seen = c.last_seen.max(failure.timestamp);
+                })
+                .or_insert_with(|| FailureCluster {
+                    signature,
+                    count: 1,
+                    sample_goal: failure.goal.clone(),
+                    sample_command: failure.command.clone(),
+                    sample_output: failure.output.clone(),
+                    first_seen: failure.timestamp,
+                    last_seen: failure.timestamp,
+                });
+        }
+
+        let mut clusters: Vec<_> = clusters.into_values().collect();
+        clusters.sort_by(|a, b| b.count.cmp(&a.count).then(b.last_seen.cmp(&a.last_seen)));
+        clusters
+    }
+
+    /// Turn a cluster into an improvement goal
+    pub fn summarize(cluster: &FailureCluster) -> SummarizedGoal {
+        let output: String = cluster.sample_output.lines().next().unwrap_or("").trim()
+            .chars()
+            .take(120)
+            .collect();
+
+        SummarizedGoal {
+            goal: format!(
+                "Investigate and fix repeated failure of '{}' (seen {} times)",
+                cluster.sample_command, cluster.count
+            ),
+            reasoning: format!(
+                "{} failures with signature {} between {} and {} while pursuing '{}'. Last error: {}",
+                cluster.count,
+                cluster.signature,
+                cluster.first_seen.format("%H:%M:%S"),
+                cluster.last_seen.format("%H:%M:%S"),
+                cluster.sample_goal,
+                output,
+            ),
+            confidence: cluster.confidence(),
+            signature: cluster.signature.clone(),
+        }
+    }
+
+    /// Run one analysis pass and return the goals that were injected
+    pub fn analyze(&mut self, now: DateTime<Utc>) -> Result<Vec<SummarizedGoal>> {
+        let failures = self.collect_failures(now - self.lookback)?;
+        let clusters = Self::cluster_failures(&failures);
+
+        let mut emitted = Vec::new();
+        for cluster in clusters.iter().filter(|c| c.count >= self.min_failures) {
+            if emitted.len() >= self.max_goals_per_cycle {
+                break;
+            }
+            if let Some(last) = self.last_emitted.get(&cluster.signature) {
+                if now - *last < self.cooldown {
+                    debug!("Signature {} still in cooldown", cluster.signature);
+                    continue;
+                }
+            }
+
+            let goal = Self::summarize(cluster);
+            match self.inject_goal(&goal) {
+                Ok(()) => {
+                    self.last_emitted.insert(cluster.signature.clone(), now);
+                    emitted.push(goal);
+                }
+                Err(e) => warn!("Rejected reflective goal: {}", e),
+            }
+        }
+
+        Ok(emitted)
+    }
+
+    /// Validate and append a goal to the injection queue
+    fn inject_goal(&self, goal: &SummarizedGoal) -> Result<()> {
+        validate_goal(&goal.goal)?;
+
+        let injection = json!({
+            "goal_id": uuid::Uuid::new_v4().to_string(),
+            "goal": goal.goal,
+            "source": "reflective_analyzer",
+            "timestamp": self.clock.now().to_rfc3339(),
+            "reasoning": goal.reasoning,
+            "priority": if goal.confidence >= 0.8 { "high" } else { "medium" },
+            "confidence": goal.confidence,
+            "metadata": { "signature": goal.signature },
+            "injected": true,
+            "processed": false,
+        });
+
+        crate::goal_injection::append_goal_injection(&self.logs_dir, &injection)
+            .context("Failed to write injection")?;
+        info!("🪞 Injected reflective goal: {} (confidence: {:.2})", goal.goal, goal.confidence);
+
+        Ok(())
+    }
+}
+
+/// Reject goals the activity loop cannot safely consume
+fn validate_goal(goal: &str) -> Result<()> {
+    let trimmed = goal.trim();
+    if trimmed.is_empty() {
+        anyhow::bail!("Goal cannot be empty");
+    }
+    if trimmed.len() > MAX_GOAL_LEN {
+        anyhow::bail!("Goal exceeds {} characters", MAX_GOAL_LEN);
+    }
+    if trimmed.contains('\n') {
+        anyhow::bail!("Goal must be a single line");
+    }
+    Ok(())
 }
 
 #[async_trait]
@@ -20,18 +262,155 @@ impl SentientService for ReflectiveAnalyzerService {
     fn name(&self) -> &str {
         &self.name
     }
-    
+
     async fn init(&mut self) -> Result<()> {
-        // TODO: Initialize reflection system
+        info!("🪞 Initializing Reflective Analyzer Service");
+
+        create_dir_all(&self.logs_dir)
+            .context("Failed to create logs directory")?;
+
+        if let Ok(interval) = std::env::var("REFLECT_INTERVAL_MS") {
+            self.interval = Duration::from_millis(interval.parse().unwrap_or(300000));
+        }
+
+        info!("  Analysis interval: {:?}", self.interval);
+        info!("  Lookback window: {} minutes", self.lookback.num_minutes());
+
         Ok(())
     }
-    
+
     async fn run(&mut self) -> Result<()> {
-        // TODO: Implement reflection analysis loop
-        Ok(())
+        info!("✅ Reflective Analyzer Service started");
+
+        loop {
+            match self.analyze(self.clock.now()) {
+                Ok(goals) if !goals.is_empty() => {
+                    info!("Reflection produced {} goal(s)", goals.len());
+                }
+                Ok(_) => debug!("No recurring failures found"),
+                Err(e) => error!("Reflection pass failed: {}", e),
+            }
+
+            sleep(self.interval).await;
+        }
     }
-    
+
     async fn shutdown(&mut self) -> Result<()> {
+        info!("Shutting down Reflective Analyzer Service");
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn failure(command: &str, output: &str) -> ActivityResult {
+        ActivityResult {
+            timestamp: Utc::now(),
+            goal: "Check disk space usage".to_string(),
+            source: "llm_observer".to_string(),
+            command: command.to_string(),
+            output: output.to_string(),
+            success: false,
+            reward: 0.0,
+            execution_time: 0.1,
+        }
+    }
+
+    fn write_log(dir: &TempDir, entries: &[ActivityResult]) {
+        let path = dir.path().join(format!("activity_loop_log_{}.jsonl", Utc::now().format("%Y%m%d")));
+        let mut file = OpenOptions::new().create(true).append(true).open(path).unwrap();
+        for entry in entries {
+            writeln!(file, "{}", serde_json::to_string(entry).unwrap()).unwrap();
+        }
+    }
+
+    fn injected_goals(dir: &TempDir) -> Vec<serde_json::Value> {
+        std::fs::read_to_string(dir.path().join("goal_injections.jsonl"))
+            .unwrap_or_default()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_repeated_failures_produce_single_goal() {
+        let dir = TempDir::new().unwrap();
+        let entries: Vec<_> = (0..5)
+            .map(|i| failure("df -h", &format!("df: /mnt/{}: No such file or directory", i)))
+            .collect();
+        write_log(&dir, &entries);
+
+        let mut service = ReflectiveAnalyzerService::new().with_logs_dir(dir.path());
+        let goals = service.analyze(Utc::now()).unwrap();
+
+        assert_eq!(goals.len(), 1);
+        assert!(goals[0].goal.contains("df -h"));
+        assert!(goals[0].reasoning.contains("5 failures"));
+
+        let injected = injected_goals(&dir);
+        assert_eq!(injected.len(), 1);
+        assert_eq!(injected[0]["source"], "reflective_analyzer");
+        assert!(injected[0]["confidence"].as_f64().unwrap() > 0.5);
+    }
+
+    #[test]
+    fn test_cooldown_prevents_repeat_goals() {
+        let dir = TempDir::new().unwrap();
+        write_log(&dir, &vec![failure("ps aux", "permission denied"); 4]);
+
+        let mut service = ReflectiveAnalyzerService::new().with_logs_dir(dir.path());
+        assert_eq!(service.analyze(Utc::now()).unwrap().len(), 1);
+        assert!(service.analyze(Utc::now()).unwrap().is_empty());
+        assert_eq!(injected_goals(&dir).len(), 1);
+    }
+
+    #[test]
+    fn test_cooldown_expires_with_the_clock() {
+        use crate::clock::MockClock;
+
+        let dir = TempDir::new().unwrap();
+        write_log(&dir, &vec![failure("ps aux", "permission denied"); 4]);
+
+        let clock = MockClock::default();
+        let mut service = ReflectiveAnalyzerService::new()
+            .with_logs_dir(dir.path())
+            .with_clock(Arc::new(clock.clone()));
+        assert_eq!(service.analyze(clock.now()).unwrap().len(), 1);
+
+        clock.advance(ChronoDuration::minutes(29));
+        assert!(service.analyze(clock.now()).unwrap().is_empty());
+
+        clock.advance(ChronoDuration::minutes(1));
+        assert_eq!(service.analyze(clock.now()).unwrap().len(), 1);
+
+        let injected = injected_goals(&dir);
+        assert_eq!(injected.len(), 2);
+        let stamped = DateTime::parse_from_rfc3339(injected[1]["timestamp"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(stamped, clock.now());
+    }
+
+    #[test]
+    fn test_rare_failures_are_ignored() {
+        let dir = TempDir::new().unwrap();
+        write_log(&dir, &[failure("uptime", "timeout"), failure("free -m", "oom")]);
+
+        let mut service = ReflectiveAnalyzerService::new().with_logs_dir(dir.path());
+        assert!(service.analyze(Utc::now()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_summary_truncates_non_ascii_output_on_char_boundary() {
+        // 119 ASCII bytes then a multi-byte character straddling byte 120
+        let output = format!("{}é — ошибка доступа", "x".repeat(119));
+        let cluster = ReflectiveAnalyzerService::cluster_failures(&vec![failure("ls /root", &output); 3])
+            .remove(0);
+
+        let goal = ReflectiveAnalyzerService::summarize(&cluster);
+        let expected: String = output.chars().take(120).collect();
+        assert!(goal.reasoning.ends_with(&format!("Last error: {}", expected)), "{}", goal.reasoning);
+    }
+}
@@ -5,6 +5,7 @@ use crate::tools::{
     exec::{ToolExecutor, ExecutionMode, ExecutionResult, execute_tool_with_mode},
 };
 use crate::llm::functions::{FunctionParser, FunctionCall, FunctionFormatter};
+use crate::llm::tool_loop::ToolRunner;
 use crate::ai_router::stream_parser::CommandPrefix;
 use anyhow::{Result, bail};
 use serde_json::Value;
@@ -33,6 +34,12 @@ impl ToolHandler {
         self
     }
     
+    /// Whether tool calls found in model replies are run
+    pub fn with_auto_discovery(mut self, enabled: bool) -> Self {
+        self.auto_discovery = enabled;
+        self
+    }
+    
     /// Handle tool-related commands
     pub fn handle_command(&self, args: &[&str]) -> Result<()> {
         if args.is_empty() {
@@ -46,11 +53,7 @@ impl ToolHandler {
             "call" => self.call_tool(&args[1..]),
             "search" => self.search_tools(&args[1..]),
             "help" => self.show_help(&args[1..]),
-            _ => {
-                println!("Unknown tool command: {}", args[0]);
-                self.show_usage();
-                Ok(())
-            }
+            _ => bail!("Unknown tool command: {}. Try 'tool help'", args[0]),
         }
     }
     
@@ -335,6 +338,22 @@ impl ToolHandler {
     }
 }
 
+/// Runs the calls of a multi-step tool loop, showing each result as it lands.
+/// With auto-discovery off, every call is refused and the model is told so.
+impl ToolRunner for ToolHandler {
+    fn run(&self, call: &FunctionCall) -> Result<ExecutionResult> {
+        if !self.auto_discovery {
+            bail!("Tool calls from model replies are disabled; {} was not run", call.tool_id);
+        }
+        let result = self.execute_function_call(call);
+        match &result {
+            Ok(result) => self.display_result(call, result),
+            Err(e) => println!("❌ Failed to execute {}: {}", call.tool_id, e),
+        }
+        result
+    }
+}
+
 /// Parse a value string
 fn parse_value(value: &str) -> Result<Value> {
     // Try number
@@ -376,4 +395,19 @@ mod tests {
         assert_eq!(parse_value("true").unwrap(), Value::Bool(true));
         assert_eq!(parse_value("hello").unwrap(), Value::String("hello".to_string()));
     }
+    
+    #[test]
+    fn test_disabled_auto_discovery_runs_no_tools() {
+        let handler = ToolHandler::new().with_auto_discovery(false);
+        assert!(handler.process_ai_response("!@ disk_info").unwrap().is_empty());
+        
+        let call = FunctionCall {
+            tool_id: "disk_info".to_string(),
+            arguments: None,
+            prefix: CommandPrefix::Validated,
+            raw_text: "!@ disk_info".to_string(),
+        };
+        let err = ToolRunner::run(&handler, &call).unwrap_err();
+        assert!(err.to_string().contains("disk_info was not run"), "{}", err);
+    }
 }
\ No newline at end of file
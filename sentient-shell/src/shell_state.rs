@@ -1,16 +1,17 @@
 use crate::ai;
 use crate::commands;
 use crate::commands_functions;
-#[cfg(feature = "local-inference")]
-use crate::inference;
+use crate::fallback::FallbackChain;
+use crate::output::{CommandOutput, OutputFormat};
 use crate::package;
 use anyhow::Result;
 
 pub struct ShellState {
     pub ai_client: ai::AiClient,
-    #[cfg(feature = "local-inference")]
-    pub local_inference: Option<inference::LocalInference>,
+    /// Local backends tried when the remote Ollama server fails
+    pub fallback_chain: FallbackChain,
     pub package_registry: package::PackageRegistry,
+    pub output_format: OutputFormat,
 }
 
 impl ShellState {
@@ -21,10 +22,9 @@ impl ShellState {
         let sd_url =
             std::env::var("SD_URL").unwrap_or_else(|_| "http://192.168.69.197:7860".to_string());
 
-        let ai_client = ai::AiClient::new(ollama_url, sd_url);
+        let ai_client = ai::AiClient::with_config(ollama_url, sd_url, ai::AiClientConfig::from_env());
 
-        #[cfg(feature = "local-inference")]
-        let local_inference = inference::LocalInference::new().ok();
+        let fallback_chain = FallbackChain::local_default();
 
         let mut package_registry = package::PackageRegistry::new();
         if let Err(e) = package_registry.init() {
@@ -33,65 +33,100 @@ impl ShellState {
 
         Self {
             ai_client,
-            #[cfg(feature = "local-inference")]
-            local_inference,
+            fallback_chain,
             package_registry,
+            output_format: OutputFormat::default(),
+        }
+    }
+
+    /// Select how built-in commands render their output
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = format;
+        self
+    }
+
+    /// Replace the local fallback chain
+    pub fn with_fallback_chain(mut self, chain: FallbackChain) -> Self {
+        self.fallback_chain = chain;
+        self
+    }
+
+    /// Render structured output with the selected formatter
+    fn emit(&self, output: &CommandOutput, format: OutputFormat) {
+        let rendered = format.render(output);
+        if rendered.ends_with('\n') {
+            print!("{}", rendered);
+        } else {
+            println!("{}", rendered);
         }
     }
 
     pub fn execute_command(&mut self, input: &str) -> Result<bool> {
+        // Everything this command does is logged under one trace id
+        let _trace = crate::trace_id::enter(crate::trace_id::new_trace_id());
+        
         // Check for prefix commands
         if input.starts_with("!@") || input.starts_with("!#") || 
            input.starts_with("!$") || input.starts_with("!&") || input.starts_with("!~") {
             return match crate::validated_exec::execute_with_prefix(input) {
                 Ok(_) => Ok(false),
-                Err(e) => {
-                    eprintln!("Validation error: {}", e);
-                    Ok(false)
-                }
+                Err(e) => Err(anyhow::anyhow!("Validation error: {}", e)),
             };
         }
         
-        let parts: Vec<&str> = input.split_whitespace().collect();
+        let mut parts: Vec<&str> = input.split_whitespace().collect();
         if parts.is_empty() {
             return Ok(false);
         }
 
+        // A trailing `--json` switches structured commands to JSON output
+        let mut format = self.output_format;
+        if matches!(parts[0], "status" | "models" | "service" | "selftest") && parts.contains(&"--json") {
+            parts.retain(|p| *p != "--json");
+            format = OutputFormat::Json;
+        }
+
         match parts[0] {
             "help" => {
                 commands_functions::show_help();
                 Ok(false)
             }
             "status" => {
-                commands_functions::show_status(&self.ai_client)?;
+                let status = commands_functions::collect_status(&self.ai_client);
+                self.emit(&CommandOutput::Status(status), format);
                 Ok(false)
             }
             "ask" => {
                 if parts.len() < 2 {
-                    println!("Usage: ask <prompt>");
+                    anyhow::bail!("Usage: ask <prompt>");
+                }
+                let options = commands_functions::parse_ask_args(&parts[1..])?;
+                if options.is_explicit() {
+                    let response = commands_functions::ask_explicit(&options)?;
+                    println!("{}", response.text.unwrap_or_default());
+                    println!("[model: {}]", response.model_used);
                     return Ok(false);
                 }
-                let prompt = parts[1..].join(" ");
-                
+                let prompt = options.prompt;
+
                 // Check if we should use boot LLM
                 if crate::boot_llm::should_use_boot_llm() {
-                    match crate::boot_llm::get_boot_llm_response(&prompt) {
-                        Ok(response) => println!("{}", response),
-                        Err(e) => eprintln!("Boot LLM error: {}", e),
-                    }
+                    let response = crate::boot_llm::get_boot_llm_response(&prompt)
+                        .map_err(|e| anyhow::anyhow!("Boot LLM error: {}", e))?;
+                    println!("{}", response);
                 } else {
-                    commands_functions::ask_ai(&mut self.ai_client, &prompt)?;
+                    commands_functions::ask_ai(&mut self.ai_client, &mut self.fallback_chain, &prompt)?;
                 }
                 Ok(false)
             }
             "models" => {
-                commands_functions::list_models(&self.ai_client)?;
+                let models = commands_functions::collect_models(&self.ai_client);
+                self.emit(&CommandOutput::Models(models), format);
                 Ok(false)
             }
             "image" => {
                 if parts.len() < 2 {
-                    println!("Usage: image <prompt>");
-                    return Ok(false);
+                    anyhow::bail!("Usage: image <prompt>");
                 }
                 let prompt = parts[1..].join(" ");
                 commands_functions::generate_image(&mut self.ai_client, &prompt)?;
@@ -110,6 +145,12 @@ impl ShellState {
                     println!("{}", crate::service::api::handle_command(&[])?);
                     return Ok(false);
                 }
+                if parts[1] == "list" {
+                    let manager = crate::service::manager::get_service_manager();
+                    let services = crate::service::api::list_services_output(manager)?;
+                    self.emit(&services, format);
+                    return Ok(false);
+                }
                 let result = crate::service::api::handle_command(&parts[1..])?;
                 println!("{}", result);
                 Ok(false)
@@ -137,10 +178,8 @@ impl ShellState {
                 Ok(false)
             }
             "llm" => {
-                match crate::ai_router::llm_cli::handle_llm_command(&parts[1..]) {
-                    Ok(result) => println!("{}", result),
-                    Err(e) => eprintln!("Error: {}", e),
-                }
+                let result = crate::ai_router::llm_cli::handle_llm_command(&parts[1..])?;
+                println!("{}", result);
                 Ok(false)
             }
             "rag_tool" => {
@@ -155,8 +194,7 @@ impl ShellState {
             }
             "sentient" => {
                 if parts.len() < 2 {
-                    println!("Usage: sentient goal <goal description>");
-                    return Ok(false);
+                    anyhow::bail!("Usage: sentient goal <goal description>");
                 }
                 if parts[1] == "goal" && parts.len() > 2 {
                     let goal = parts[2..].join(" ");
@@ -170,7 +208,17 @@ impl ShellState {
                     let runtime = tokio::runtime::Runtime::new()?;
                     runtime.block_on(crate::commands::sentient_goal::execute(args))?;
                 } else {
-                    println!("Unknown sentient command. Use: sentient goal <goal>");
+                    anyhow::bail!("Unknown sentient command. Use: sentient goal <goal>");
+                }
+                Ok(false)
+            }
+            "selftest" => {
+                let skip = crate::selftest::parse_skip_args(&parts[1..])?;
+                let report = crate::selftest::SelfTest::with_defaults().run(&skip)?;
+                let failures = report.failures();
+                self.emit(&CommandOutput::SelfTest(report), format);
+                if failures > 0 {
+                    anyhow::bail!("{} self-test check(s) failed", failures);
                 }
                 Ok(false)
             }
@@ -181,11 +229,10 @@ impl ShellState {
                     commands_functions::run_package(&mut self.ai_client, parts[0], &parts[1..])?;
                     Ok(false)
                 } else {
-                    println!(
+                    anyhow::bail!(
                         "Unknown command: {}. Type 'help' for available commands.",
                         parts[0]
-                    );
-                    Ok(false)
+                    )
                 }
             }
         }
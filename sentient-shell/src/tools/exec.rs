@@ -1,6 +1,7 @@
 //! Secure tool executor with sandboxing and privilege management
 
 use super::registry::{Tool, get_tool_registry};
+use crate::validated_exec::{ExecContext, UnixUserSwitcher, UserSwitcher};
 use crate::schema::Schema;
 use crate::schema::validate::JsonValidator;
 use anyhow::{Result, bail, Context};
@@ -41,6 +42,9 @@ pub struct ExecutionResult {
     
     /// Whether execution was interrupted
     pub interrupted: bool,
+    
+    /// Trace id of the command or goal the tool ran for
+    pub trace_id: Option<String>,
 }
 
 /// Tool executor
@@ -53,6 +57,12 @@ pub struct ToolExecutor {
     
     /// Whether to require confirmation for dangerous operations
     require_confirmation: bool,
+    
+    /// Capabilities granted to tools run by this executor
+    context: ExecContext,
+    
+    /// Applies a tool's declared `user` before spawning
+    user_switcher: Box<dyn UserSwitcher>,
 }
 
 impl ToolExecutor {
@@ -62,12 +72,27 @@ impl ToolExecutor {
             sandbox_dir: Some("/tmp/sentient-sandbox".to_string()),
             allow_privileged: false,
             require_confirmation: true,
+            context: ExecContext::default(),
+            user_switcher: Box::new(UnixUserSwitcher),
         }
     }
     
-    /// Enable privileged mode
+    /// Enable privileged mode, granting every capability
     pub fn with_privileges(mut self) -> Self {
         self.allow_privileged = true;
+        self.context = ExecContext::privileged();
+        self
+    }
+    
+    /// Restrict the capabilities granted to tools
+    pub fn with_context(mut self, context: ExecContext) -> Self {
+        self.context = context;
+        self
+    }
+    
+    /// Override how tools drop to their declared user
+    pub fn with_user_switcher(mut self, switcher: Box<dyn UserSwitcher>) -> Self {
+        self.user_switcher = switcher;
         self
     }
     
@@ -117,6 +142,14 @@ impl ToolExecutor {
         // Build command
         let mut command = self.build_command(&tool, args)?;
         
+        // Unprivileged tools run as their declared user
+        if !tool.requires_privilege {
+            if let Some(user) = &tool.user {
+                self.user_switcher.apply(&mut command, user)
+                    .with_context(|| format!("Failed to drop privileges to '{}'", user))?;
+            }
+        }
+        
         // Apply execution mode
         match mode {
             ExecutionMode::Safe => {},
@@ -132,7 +165,11 @@ impl ToolExecutor {
         }
         
         // Execute with timeout
-        self.execute_with_timeout(command, tool.timeout, mode == ExecutionMode::Background)
+        log::info!("🔧 Running tool {} ({:?})", tool.id, mode);
+        let mut result = self.execute_with_timeout(command, tool.timeout, mode == ExecutionMode::Background)?;
+        result.trace_id = crate::trace_id::current();
+        log::info!("🔧 Tool {} exited with {} in {}ms", tool.id, result.exit_code, result.duration_ms);
+        Ok(result)
     }
     
     /// Validate execution permissions
@@ -142,6 +179,9 @@ impl ToolExecutor {
             bail!("Tool '{}' requires elevated privileges", tool.id);
         }
         
+        // Check declared capabilities against the context
+        self.context.check_tool(tool)?;
+        
         // Validate mode compatibility
         if *mode == ExecutionMode::Privileged && !tool.requires_privilege {
             bail!("Tool '{}' does not support privileged execution", tool.id);
@@ -296,6 +336,7 @@ impl ToolExecutor {
                 stderr: String::new(),
                 duration_ms: start.elapsed().as_millis() as u64,
                 interrupted: false,
+                trace_id: None,
             });
         }
         
@@ -378,6 +419,7 @@ impl ToolExecutor {
                     stderr: "Process terminated due to timeout".to_string(),
                     duration_ms: start.elapsed().as_millis() as u64,
                     interrupted: true,
+                    trace_id: None,
                 })
             }
         };
@@ -395,6 +437,7 @@ impl ToolExecutor {
             stderr: stderr.join("\n"),
             duration_ms: start.elapsed().as_millis() as u64,
             interrupted: false,
+            trace_id: None,
         })
     }
 }
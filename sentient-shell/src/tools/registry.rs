@@ -39,6 +39,30 @@ pub struct Tool {
     
     /// Maximum execution time in seconds
     pub timeout: u64,
+    
+    /// Capabilities the execution context must grant
+    #[serde(default)]
+    pub capabilities: Vec<ToolCapability>,
+    
+    /// User to drop to when running an unprivileged tool
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+/// Capabilities a tool may require from its execution context
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolCapability {
+    /// Signal or terminate other processes
+    ProcessControl,
+    /// Reconfigure network interfaces
+    NetworkAdmin,
+    /// Start, stop or restart services
+    ServiceControl,
+    /// System-wide changes such as reboot or safe mode
+    SystemAdmin,
+    /// Write outside the sandbox
+    FilesystemWrite,
 }
 
 /// Tool categories
@@ -162,6 +186,8 @@ impl ToolRegistry {
             tags: vec!["system".to_string(), "storage".to_string()],
             examples: vec!["!@ call disk_info".to_string()],
             timeout: 5,
+            capabilities: Vec::new(),
+            user: None,
         }).ok();
         
         self.register(Tool {
@@ -175,6 +201,8 @@ impl ToolRegistry {
             tags: vec!["system".to_string(), "diagnostic".to_string()],
             examples: vec!["!@ call memory_info".to_string()],
             timeout: 5,
+            capabilities: Vec::new(),
+            user: None,
         }).ok();
         
         // Process management
@@ -189,6 +217,8 @@ impl ToolRegistry {
             tags: vec!["process".to_string(), "diagnostic".to_string()],
             examples: vec!["!@ call process_list".to_string()],
             timeout: 5,
+            capabilities: Vec::new(),
+            user: None,
         }).ok();
         
         self.register(Tool {
@@ -214,6 +244,8 @@ impl ToolRegistry {
                 r#"!$ call kill_process {"pid": 5678, "force": true}"#.to_string(),
             ],
             timeout: 5,
+            capabilities: vec![ToolCapability::ProcessControl],
+            user: None,
         }).ok();
         
         // Network tools
@@ -228,6 +260,8 @@ impl ToolRegistry {
             tags: vec!["network".to_string(), "diagnostic".to_string()],
             examples: vec!["!@ call network_status".to_string()],
             timeout: 5,
+            capabilities: Vec::new(),
+            user: None,
         }).ok();
         
         self.register(Tool {
@@ -253,6 +287,8 @@ impl ToolRegistry {
                 r#"!$ call reset_network {"confirm": true, "interface": "eth0"}"#.to_string(),
             ],
             timeout: 30,
+            capabilities: vec![ToolCapability::NetworkAdmin],
+            user: None,
         }).ok();
         
         // System services
@@ -275,6 +311,8 @@ impl ToolRegistry {
                 r#"!@ call service_status {"name": "sentd"}"#.to_string(),
             ],
             timeout: 5,
+            capabilities: Vec::new(),
+            user: None,
         }).ok();
         
         // Recovery tools
@@ -297,6 +335,8 @@ impl ToolRegistry {
                 r#"!$ call safe_mode {"confirm": true}"#.to_string(),
             ],
             timeout: 60,
+            capabilities: vec![ToolCapability::SystemAdmin],
+            user: None,
         }).ok();
         
         // HiveFix integration
@@ -311,6 +351,8 @@ impl ToolRegistry {
             tags: vec!["hivefix".to_string(), "diagnostic".to_string()],
             examples: vec!["!@ call hivefix_status".to_string()],
             timeout: 10,
+            capabilities: Vec::new(),
+            user: None,
         }).ok();
         
         self.register(Tool {
@@ -333,6 +375,8 @@ impl ToolRegistry {
                 r#"!@ call hivefix_analyze {"log_path": "/var/log/boot.log"}"#.to_string(),
             ],
             timeout: 30,
+            capabilities: Vec::new(),
+            user: None,
         }).ok();
     }
 }
@@ -366,6 +410,8 @@ mod tests {
             tags: vec!["test".to_string()],
             examples: vec![],
             timeout: 5,
+            capabilities: Vec::new(),
+            user: None,
         };
         
         assert!(registry.register(tool).is_ok());
@@ -388,6 +434,8 @@ mod tests {
             tags: vec![],
             examples: vec![],
             timeout: 5,
+            capabilities: Vec::new(),
+            user: None,
         };
         
         assert!(registry.register(dangerous_tool).is_err());
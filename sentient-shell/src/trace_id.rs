@@ -0,0 +1,212 @@
+//! Request correlation ids
+//!
+//! Each shell command and each processed goal gets a trace id. Work done on
+//! its behalf (model requests, tool executions, RAG-tool trace entries)
+//! records the same id, so one request can be followed through the logs.
+//!
+//! The id is current per thread while a [`TraceScope`] is held. Work moved to
+//! another thread carries it explicitly, e.g. in `InferenceRequest.metadata`
+//! under [`crate::ai_router::TRACE_ID_KEY`], and re-enters it there.
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+
+thread_local! {
+    static CURRENT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// A fresh trace id
+pub fn new_trace_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Trace id of the request this thread is working on, if any
+pub fn current() -> Option<String> {
+    CURRENT.with(|current| current.borrow().clone())
+}
+
+/// Make `trace_id` current on this thread until the returned scope drops
+pub fn enter(trace_id: impl Into<String>) -> TraceScope {
+    let previous = CURRENT.with(|current| current.replace(Some(trace_id.into())));
+    TraceScope {
+        previous,
+        _not_send: PhantomData,
+    }
+}
+
+/// Keeps a trace id current; the previous one is restored on drop. Not
+/// `Send`, so it cannot be held across an `.await` that may change threads.
+#[must_use = "the trace id is only current while the scope is held"]
+pub struct TraceScope {
+    previous: Option<String>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for TraceScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_router::registry::get_model_registry;
+    use crate::ai_router::router::AIRouter;
+    use crate::ai_router::{
+        InferenceRequest, InferenceResponse, ModelCapability, ModelEndpoint, ModelProvider, TRACE_ID_KEY,
+    };
+    use crate::tools::exec::{ExecutionMode, ToolExecutor};
+    use crate::tools::registry::{get_tool_registry, Tool};
+    use anyhow::Result;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    /// `(trace id, target)` of a log record, read the way the shell's log
+    /// format reads it
+    type Captured = Vec<(Option<String>, String)>;
+
+    thread_local! {
+        static CAPTURED: RefCell<Option<Captured>> = const { RefCell::new(None) };
+    }
+
+    /// Forwards records to the capture running on their thread, if any
+    struct CapturingLogger;
+
+    impl log::Log for CapturingLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            CAPTURED.with(|captured| captured.borrow().is_some())
+        }
+
+        fn log(&self, record: &log::Record) {
+            CAPTURED.with(|captured| {
+                if let Some(records) = captured.borrow_mut().as_mut() {
+                    records.push((current(), record.target().to_string()));
+                }
+            });
+        }
+
+        fn flush(&self) {}
+    }
+
+    /// Run `f`, returning the log records it made on this thread. Records
+    /// from other threads and other tests are not captured.
+    fn capture_logs<T>(f: impl FnOnce() -> T) -> (T, Captured) {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            log::set_boxed_logger(Box::new(CapturingLogger))
+                .expect("another logger is installed in the test binary");
+            log::set_max_level(log::LevelFilter::Info);
+        });
+
+        CAPTURED.with(|captured| *captured.borrow_mut() = Some(Vec::new()));
+        let result = f();
+        let records = CAPTURED.with(|captured| captured.borrow_mut().take()).unwrap_or_default();
+        (result, records)
+    }
+
+    /// Answers with the prompt and hands back the request's metadata
+    struct EchoProvider;
+
+    impl ModelProvider for EchoProvider {
+        fn name(&self) -> &str {
+            "trace-echo"
+        }
+
+        fn is_available(&self) -> Result<bool> {
+            Ok(true)
+        }
+
+        fn infer(&self, request: &InferenceRequest) -> Result<InferenceResponse> {
+            Ok(InferenceResponse {
+                text: Some(request.prompt.clone()),
+                embedding: None,
+                metadata: request.metadata.clone(),
+                model_used: String::new(),
+                tokens_used: None,
+                duration_ms: 0,
+            })
+        }
+
+        fn list_models(&self) -> Result<Vec<ModelEndpoint>> {
+            Ok(vec![ModelEndpoint {
+                name: "trace-echo".to_string(),
+                provider: "trace-echo".to_string(),
+                model_id: "echo".to_string(),
+                endpoint_url: String::new(),
+                capabilities: vec![ModelCapability::Custom("trace-echo".to_string())],
+                max_tokens: None,
+                context_window: None,
+                is_active: true,
+                priority: 0,
+            }])
+        }
+    }
+
+    #[test]
+    fn test_llm_call_and_tool_execution_share_the_command_trace_id() {
+        get_model_registry().register_provider(Arc::new(EchoProvider)).unwrap();
+        get_tool_registry().register(Tool {
+            id: "trace_echo".to_string(),
+            name: "Trace Echo".to_string(),
+            description: "Echoes a fixed line".to_string(),
+            command: "echo traced".to_string(),
+            requires_privilege: false,
+            requires_confirmation: false,
+            schema: None,
+            tags: vec!["test".to_string()],
+            examples: vec![],
+            timeout: 5,
+            capabilities: vec![],
+            user: None,
+        }).unwrap();
+
+        // One command: a model call, then a tool run
+        let trace_id = new_trace_id();
+        let ((response, result), records) = capture_logs(|| {
+            let _trace = enter(trace_id.clone());
+            let request = InferenceRequest {
+                prompt: "how full is the disk?".to_string(),
+                capability: ModelCapability::Custom("trace-echo".to_string()),
+                max_tokens: None,
+                temperature: None,
+                system_prompt: None,
+                metadata: HashMap::new(),
+            };
+            let response = AIRouter::route_request(&request).unwrap();
+            let result = ToolExecutor::new()
+                .without_confirmation()
+                .execute("trace_echo", None, ExecutionMode::Safe)
+                .unwrap();
+            (response, result)
+        });
+
+        assert_eq!(response.metadata[TRACE_ID_KEY], trace_id.as_str());
+        assert_eq!(result.trace_id.as_deref(), Some(trace_id.as_str()));
+
+        let traced = |module: &str| {
+            records.iter().any(|(id, target)| id.as_deref() == Some(trace_id.as_str()) && target.ends_with(module))
+        };
+        assert!(traced("ai_router::router"), "no router log record for trace {trace_id}");
+        assert!(traced("tools::exec"), "no tool log record for trace {trace_id}");
+    }
+
+    #[test]
+    fn test_scopes_nest_and_restore() {
+        assert_eq!(current(), None);
+        {
+            let _outer = enter("outer");
+            {
+                let _inner = enter("inner");
+                assert_eq!(current().as_deref(), Some("inner"));
+            }
+            assert_eq!(current().as_deref(), Some("outer"));
+
+            // Other threads do not see it
+            assert_eq!(std::thread::spawn(current).join().unwrap(), None);
+        }
+        assert_eq!(current(), None);
+        assert_ne!(new_trace_id(), new_trace_id());
+    }
+}
@@ -1,14 +1,346 @@
 //! Validated command execution with schema enforcement
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Result, bail};
 use crate::ai_router::stream_parser::{CommandPrefix, StreamDetector, StreamDetection};
-use std::collections::HashMap;
+use crate::tools::registry::{Tool, ToolCapability};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 use serde_json::Value;
 
+/// Default location of the execution policy file
+pub const DEFAULT_POLICY_PATH: &str = "/etc/sentient/exec_policy.toml";
+
+/// Default location of the validated-exec audit log
+pub const DEFAULT_AUDIT_PATH: &str = "logs/validated_exec_audit.jsonl";
+
+/// How a policy rule matches a command line
+#[derive(Debug, Clone)]
+pub enum RuleMatcher {
+    /// Command line starts with this text
+    Prefix(String),
+    /// Command line matches this regex
+    Regex(Regex),
+}
+
+impl RuleMatcher {
+    pub fn matches(&self, command_line: &str) -> bool {
+        match self {
+            RuleMatcher::Prefix(prefix) => command_line.starts_with(prefix.as_str()),
+            RuleMatcher::Regex(re) => re.is_match(command_line),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            RuleMatcher::Prefix(prefix) => format!("prefix '{}'", prefix),
+            RuleMatcher::Regex(re) => format!("regex '{}'", re.as_str()),
+        }
+    }
+}
+
+/// A single allow or deny rule, optionally restricted to some prefixes
+#[derive(Debug, Clone)]
+pub struct PolicyRule {
+    pub matcher: RuleMatcher,
+    /// Prefixes the rule applies to; empty means all prefixes
+    pub prefixes: Vec<CommandPrefix>,
+}
+
+impl PolicyRule {
+    pub fn prefix(text: &str) -> Self {
+        Self {
+            matcher: RuleMatcher::Prefix(text.to_string()),
+            prefixes: Vec::new(),
+        }
+    }
+
+    pub fn regex(pattern: &str) -> Result<Self> {
+        Ok(Self {
+            matcher: RuleMatcher::Regex(Regex::new(pattern)?),
+            prefixes: Vec::new(),
+        })
+    }
+
+    /// Restrict the rule to the given command prefixes
+    pub fn for_prefixes(mut self, prefixes: Vec<CommandPrefix>) -> Self {
+        self.prefixes = prefixes;
+        self
+    }
+
+    fn applies(&self, prefix: &CommandPrefix, command_line: &str) -> bool {
+        (self.prefixes.is_empty() || self.prefixes.contains(prefix)) && self.matcher.matches(command_line)
+    }
+}
+
+/// Outcome of evaluating a command against the policy
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyDecision {
+    pub allowed: bool,
+    pub reason: String,
+}
+
+/// Allowlist/denylist policy for validated execution.
+///
+/// Deny rules win over allow rules. Commands with the `!#` (dangerous) or
+/// `!$` (system) prefix are denied unless an allow rule matches them.
+#[derive(Debug, Clone, Default)]
+pub struct ExecPolicy {
+    pub allow: Vec<PolicyRule>,
+    pub deny: Vec<PolicyRule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PolicyFile {
+    #[serde(default)]
+    allow: Vec<PolicyRuleConfig>,
+    #[serde(default)]
+    deny: Vec<PolicyRuleConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PolicyRuleConfig {
+    prefix: Option<String>,
+    regex: Option<String>,
+    /// Command prefixes such as "!#" or "!$"
+    #[serde(default)]
+    applies_to: Vec<String>,
+}
+
+impl PolicyRuleConfig {
+    fn into_rule(self) -> Result<PolicyRule> {
+        let rule = match (self.prefix, self.regex) {
+            (Some(prefix), None) => PolicyRule::prefix(&prefix),
+            (None, Some(pattern)) => PolicyRule::regex(&pattern)
+                .with_context(|| format!("Invalid policy regex '{}'", pattern))?,
+            _ => bail!("Policy rule must set exactly one of `prefix` or `regex`"),
+        };
+
+        let mut prefixes = Vec::new();
+        for marker in &self.applies_to {
+            match CommandPrefix::parse(marker) {
+                (CommandPrefix::None, _) => bail!("Unknown command prefix '{}' in policy", marker),
+                (prefix, _) => prefixes.push(prefix),
+            }
+        }
+
+        Ok(rule.for_prefixes(prefixes))
+    }
+}
+
+impl ExecPolicy {
+    /// Parse a TOML policy with `[[allow]]` and `[[deny]]` tables
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let file: PolicyFile = toml::from_str(content).context("Failed to parse exec policy")?;
+        Ok(Self {
+            allow: file.allow.into_iter().map(PolicyRuleConfig::into_rule).collect::<Result<_>>()?,
+            deny: file.deny.into_iter().map(PolicyRuleConfig::into_rule).collect::<Result<_>>()?,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read exec policy {}", path.display()))?;
+        Self::from_toml(&content)
+    }
+
+    /// Load the policy from `SENTIENT_EXEC_POLICY` or the default path,
+    /// falling back to an empty (deny-by-default) policy
+    pub fn load_default() -> Self {
+        let path = std::env::var("SENTIENT_EXEC_POLICY")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| PathBuf::from(DEFAULT_POLICY_PATH));
+
+        if !path.exists() {
+            return Self::default();
+        }
+
+        match Self::load(&path) {
+            Ok(policy) => policy,
+            Err(e) => {
+                log::warn!("Ignoring invalid exec policy {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn evaluate(&self, prefix: &CommandPrefix, command_line: &str) -> PolicyDecision {
+        let command_line = command_line.trim();
+
+        if let Some(rule) = self.deny.iter().find(|r| r.applies(prefix, command_line)) {
+            return PolicyDecision {
+                allowed: false,
+                reason: format!("matched deny rule {}", rule.matcher.describe()),
+            };
+        }
+
+        if let Some(rule) = self.allow.iter().find(|r| r.applies(prefix, command_line)) {
+            return PolicyDecision {
+                allowed: true,
+                reason: format!("matched allow rule {}", rule.matcher.describe()),
+            };
+        }
+
+        match prefix {
+            CommandPrefix::Dangerous | CommandPrefix::System => PolicyDecision {
+                allowed: false,
+                reason: format!("{} commands are denied unless explicitly allowed", prefix.as_str()),
+            },
+            _ => PolicyDecision {
+                allowed: true,
+                reason: "no matching rule".to_string(),
+            },
+        }
+    }
+}
+
+/// One validated-exec decision in the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecAuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub command: String,
+    pub prefix: CommandPrefix,
+    pub allowed: bool,
+    pub reason: String,
+}
+
+/// Append-only JSONL audit trail of validated-exec decisions
+#[derive(Debug, Clone)]
+pub struct ExecAuditLog {
+    path: PathBuf,
+}
+
+impl ExecAuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Audit log at `SENTIENT_EXEC_AUDIT` or the default path
+    pub fn default_location() -> Self {
+        Self::new(std::env::var("SENTIENT_EXEC_AUDIT").unwrap_or_else(|_| DEFAULT_AUDIT_PATH.to_string()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn record(&self, entry: &ExecAuditEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open audit log {}", self.path.display()))?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    /// Read back every entry in the log
+    pub fn read_all(&self) -> Result<Vec<ExecAuditEntry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        std::fs::read_to_string(&self.path)?
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| serde_json::from_str(l).context("Corrupt audit entry"))
+            .collect()
+    }
+}
+
+/// Capabilities granted to the current execution context
+#[derive(Debug, Clone, Default)]
+pub struct ExecContext {
+    granted: HashSet<ToolCapability>,
+}
+
+impl ExecContext {
+    pub fn new(granted: impl IntoIterator<Item = ToolCapability>) -> Self {
+        Self {
+            granted: granted.into_iter().collect(),
+        }
+    }
+
+    /// Context granting every capability
+    pub fn privileged() -> Self {
+        Self::new([
+            ToolCapability::ProcessControl,
+            ToolCapability::NetworkAdmin,
+            ToolCapability::ServiceControl,
+            ToolCapability::SystemAdmin,
+            ToolCapability::FilesystemWrite,
+        ])
+    }
+
+    pub fn grants(&self, capability: ToolCapability) -> bool {
+        self.granted.contains(&capability)
+    }
+
+    /// Ensure every capability the tool declares is granted
+    pub fn check_tool(&self, tool: &Tool) -> Result<()> {
+        let missing: Vec<String> = tool
+            .capabilities
+            .iter()
+            .filter(|c| !self.grants(**c))
+            .map(|c| format!("{:?}", c))
+            .collect();
+
+        if !missing.is_empty() {
+            bail!(
+                "Tool '{}' requires capabilities not granted to this context: {}",
+                tool.id,
+                missing.join(", ")
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Switches a command to run as another user before it is spawned
+pub trait UserSwitcher: Send + Sync {
+    fn apply(&self, command: &mut Command, user: &str) -> Result<()>;
+}
+
+/// Drops to the named user's uid/gid via `setuid`/`setgid` on spawn
+pub struct UnixUserSwitcher;
+
+impl UserSwitcher for UnixUserSwitcher {
+    #[cfg(unix)]
+    fn apply(&self, command: &mut Command, user: &str) -> Result<()> {
+        use std::os::unix::process::CommandExt;
+
+        let entry = nix::unistd::User::from_name(user)
+            .with_context(|| format!("Failed to look up user '{}'", user))?
+            .ok_or_else(|| anyhow::anyhow!("Unknown user '{}'", user))?;
+
+        command.gid(entry.gid.as_raw()).uid(entry.uid.as_raw());
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn apply(&self, _command: &mut Command, user: &str) -> Result<()> {
+        bail!("Cannot drop privileges to '{}' on this platform", user)
+    }
+}
+
 /// Command validator using sentient-schema
 pub struct CommandValidator {
     /// Registered command schemas
     schemas: HashMap<String, CommandSchema>,
+    /// Allowlist/denylist policy
+    policy: ExecPolicy,
+    /// Where decisions are recorded
+    audit: Option<ExecAuditLog>,
 }
 
 #[derive(Debug, Clone)]
@@ -24,12 +356,26 @@ impl CommandValidator {
     pub fn new() -> Self {
         let mut validator = Self {
             schemas: HashMap::new(),
+            policy: ExecPolicy::load_default(),
+            audit: Some(ExecAuditLog::default_location()),
         };
         
         // Register built-in commands
         validator.register_builtin_commands();
         validator
     }
+
+    /// Replace the execution policy
+    pub fn with_policy(mut self, policy: ExecPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Record decisions to the given audit log (`None` disables auditing)
+    pub fn with_audit_log(mut self, audit: Option<ExecAuditLog>) -> Self {
+        self.audit = audit;
+        self
+    }
     
     fn register_builtin_commands(&mut self) {
         // Register dangerous commands
@@ -95,8 +441,36 @@ impl CommandValidator {
         });
     }
     
-    /// Validate a command with its prefix
+    /// Validate a command with its prefix, enforcing the policy and auditing the decision
     pub fn validate_command(&self, prefix: &CommandPrefix, command: &str) -> Result<ValidatedCommand> {
+        let decision = self.policy.evaluate(prefix, command);
+        let result = if decision.allowed {
+            self.check_schema(prefix, command)
+        } else {
+            Err(anyhow::anyhow!("Command denied by policy: {}", decision.reason))
+        };
+
+        if let Some(audit) = &self.audit {
+            let entry = ExecAuditEntry {
+                timestamp: Utc::now(),
+                command: command.trim().to_string(),
+                prefix: prefix.clone(),
+                allowed: result.is_ok(),
+                reason: match &result {
+                    Ok(_) => decision.reason.clone(),
+                    Err(e) if decision.allowed => e.to_string(),
+                    Err(_) => decision.reason.clone(),
+                },
+            };
+            if let Err(e) = audit.record(&entry) {
+                log::warn!("Failed to write exec audit entry: {}", e);
+            }
+        }
+
+        result
+    }
+
+    fn check_schema(&self, prefix: &CommandPrefix, command: &str) -> Result<ValidatedCommand> {
         let parts: Vec<&str> = command.trim().split_whitespace().collect();
         if parts.is_empty() {
             bail!("Empty command");
@@ -223,9 +597,20 @@ pub fn execute_with_prefix(input: &str) -> Result<()> {
 mod tests {
     use super::*;
     
+    fn test_validator(audit_path: &Path) -> CommandValidator {
+        let policy = ExecPolicy {
+            allow: vec![PolicyRule::prefix("rm -rf /tmp/").for_prefixes(vec![CommandPrefix::Dangerous])],
+            deny: Vec::new(),
+        };
+        CommandValidator::new()
+            .with_policy(policy)
+            .with_audit_log(Some(ExecAuditLog::new(audit_path)))
+    }
+
     #[test]
     fn test_command_validation() {
-        let validator = CommandValidator::new();
+        let dir = tempfile::tempdir().unwrap();
+        let validator = test_validator(&dir.path().join("audit.jsonl"));
         
         // Test dangerous command
         let validated = validator.validate_command(&CommandPrefix::Dangerous, "rm -rf /tmp/test").unwrap();
@@ -235,4 +620,56 @@ mod tests {
         let validated = validator.validate_command(&CommandPrefix::Validated, "service start test").unwrap();
         assert_eq!(validated.command, "service");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_allowed_command_is_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit_path = dir.path().join("audit.jsonl");
+        let validator = test_validator(&audit_path);
+
+        let validated = validator.validate_command(&CommandPrefix::Dangerous, "rm -rf /tmp/cache").unwrap();
+        assert_eq!(validated.command, "rm");
+
+        let entries = ExecAuditLog::new(&audit_path).read_all().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].allowed);
+        assert_eq!(entries[0].command, "rm -rf /tmp/cache");
+        assert_eq!(entries[0].prefix, CommandPrefix::Dangerous);
+        assert!(entries[0].reason.contains("allow rule"));
+    }
+
+    #[test]
+    fn test_dangerous_command_denied_by_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let audit_path = dir.path().join("audit.jsonl");
+        let validator = test_validator(&audit_path);
+
+        let err = validator.validate_command(&CommandPrefix::Dangerous, "rm -rf /").unwrap_err();
+        assert!(err.to_string().contains("denied by policy"));
+
+        let err = validator.validate_command(&CommandPrefix::System, "service stop sshd").unwrap_err();
+        assert!(err.to_string().contains("!$"));
+
+        let entries = ExecAuditLog::new(&audit_path).read_all().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| !e.allowed));
+        assert_eq!(entries[0].command, "rm -rf /");
+        assert!(entries[0].reason.contains("denied unless explicitly allowed"));
+    }
+
+    #[test]
+    fn test_deny_rule_overrides_allow() {
+        let policy = ExecPolicy::from_toml(r#"
+            [[allow]]
+            prefix = "pkg"
+
+            [[deny]]
+            regex = "^pkg\\s+uninstall"
+        "#).unwrap();
+
+        assert!(policy.evaluate(&CommandPrefix::Validated, "pkg install calc").allowed);
+        let decision = policy.evaluate(&CommandPrefix::Validated, "pkg uninstall calc");
+        assert!(!decision.allowed);
+        assert!(decision.reason.contains("deny rule"));
+    }
+}
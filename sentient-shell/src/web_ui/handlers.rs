@@ -1,8 +1,6 @@
 use super::*;
 use warp::Reply;
 use serde_json::json;
-use std::fs::OpenOptions;
-use std::io::Write as IoWrite;
 
 #[derive(Debug, Deserialize)]
 pub struct ActivityQuery {
@@ -51,6 +49,100 @@ pub async fn get_system_status(
     Ok(warp::reply::json(&response))
 }
 
+/// Get recent metric history shared by all dashboard clients
+pub async fn get_system_history(
+    state: Arc<DashboardState>,
+) -> Result<impl Reply, warp::Rejection> {
+    let metrics = state.metrics.read().await;
+    let points: Vec<_> = metrics.history.points().collect();
+    
+    let response = json!({
+        "interval_secs": super::metrics::UPDATE_INTERVAL_SECS,
+        "capacity": metrics.history.capacity(),
+        "points": points,
+    });
+    
+    Ok(warp::reply::json(&response))
+}
+
+/// Expose metrics in Prometheus text format
+pub async fn get_prometheus_metrics(
+    state: Arc<DashboardState>,
+) -> Result<impl Reply, warp::Rejection> {
+    let metrics = state.metrics.read().await;
+    let services = state.service_status.read().await;
+    let training = crate::rl_training::get_training_stats().await;
+    let injector = crate::policy_injector::get_injector_stats().await;
+    
+    let body = super::prometheus::render_metrics(
+        &metrics,
+        &services,
+        training.as_ref(),
+        injector.as_ref(),
+    );
+    
+    Ok(warp::reply::with_header(
+        body,
+        "content-type",
+        super::prometheus::CONTENT_TYPE,
+    ))
+}
+
+/// Effective LLM routing configuration, as `llm config dump` prints it
+pub async fn get_llm_config() -> Result<impl Reply, warp::Rejection> {
+    use crate::ai_router::intelligent_router::IntelligentRouter;
+    
+    // Probing asks every provider over blocking HTTP
+    let router = tokio::task::spawn_blocking(|| {
+        let router = IntelligentRouter::from_loaded_config()?;
+        router.probe_health();
+        Ok::<_, anyhow::Error>(router)
+    })
+    .await
+    .unwrap_or_else(|e| Err(anyhow::anyhow!("Health probe panicked: {}", e)));
+    
+    match router {
+        Ok(router) => Ok(warp::reply::with_status(
+            warp::reply::json(&router.export_effective_config()),
+            warp::http::StatusCode::OK,
+        )),
+        Err(e) => Ok(warp::reply::with_status(
+            warp::reply::json(&json!({"error": format!("{:#}", e)})),
+            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        )),
+    }
+}
+
+/// Liveness probe: answers as long as the process is serving requests
+pub async fn healthz() -> Result<impl Reply, warp::Rejection> {
+    Ok(warp::reply::json(&json!({
+        "status": "ok",
+        "timestamp": Utc::now().to_rfc3339(),
+    })))
+}
+
+/// Readiness probe: 200 once every required component has initialized
+pub async fn readyz(
+    state: Arc<DashboardState>,
+) -> Result<impl Reply, warp::Rejection> {
+    let readiness = state.readiness.read().await;
+    let ready = readiness.is_ready();
+    
+    let response = json!({
+        "status": if ready { "ready" } else { "not_ready" },
+        "components": readiness.components(),
+        "timestamp": Utc::now().to_rfc3339(),
+    });
+    
+    let code = if ready {
+        warp::http::StatusCode::OK
+    } else {
+        warp::http::StatusCode::SERVICE_UNAVAILABLE
+    };
+    
+    Ok(warp::reply::with_status(warp::reply::json(&response), code))
+}
+
 /// Get recent activity
 pub async fn get_recent_activity(
     query: ActivityQuery,
@@ -92,25 +184,10 @@ pub async fn inject_goal(
     });
     
     // Write to goal injection file
-    let logs_dir = Path::new("logs");
-    std::fs::create_dir_all(logs_dir).map_err(|e| {
-        log::error!("Failed to create logs directory: {}", e);
-        warp::reject::reject()
-    })?;
-    
-    let injection_file = logs_dir.join("goal_injections.jsonl");
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&injection_file)
-        .map_err(|e| {
-            log::error!("Failed to open injection file: {}", e);
-            warp::reject::reject()
-        })?;
-    
-    writeln!(file, "{}", serde_json::to_string(&injection).unwrap())
+    crate::goal_injection::append_goal_injection_async(Path::new("logs"), &injection)
+        .await
         .map_err(|e| {
-            log::error!("Failed to write injection: {}", e);
+            log::error!("Failed to write injection: {:#}", e);
             warp::reject::reject()
         })?;
     
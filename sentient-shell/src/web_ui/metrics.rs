@@ -1,6 +1,65 @@
 use sysinfo::{System, SystemExt, CpuExt, DiskExt};
+use serde::Serialize;
+use std::collections::VecDeque;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Seconds between metric samples
+pub const UPDATE_INTERVAL_SECS: u64 = 5;
+
+/// History points kept: 5 minutes at the update interval
+pub const DEFAULT_HISTORY_LEN: usize = 60;
+
+/// One sampled point of metric history
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MetricPoint {
+    /// Unix timestamp in seconds
+    pub timestamp: u64,
+    pub cpu_percent: f32,
+    pub memory_percent: f32,
+    pub disk_usage: f32,
+}
+
+/// Bounded ring of recent metric points, oldest first
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricHistory {
+    capacity: usize,
+    points: VecDeque<MetricPoint>,
+}
+
+impl MetricHistory {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            points: VecDeque::with_capacity(capacity),
+        }
+    }
+    
+    /// Append a point, dropping the oldest once full
+    pub fn push(&mut self, point: MetricPoint) {
+        if self.points.len() >= self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back(point);
+    }
+    
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+    
+    pub fn points(&self) -> impl Iterator<Item = &MetricPoint> {
+        self.points.iter()
+    }
+}
+
 /// System metrics
 #[derive(Debug, Clone)]
 pub struct SystemMetrics {
@@ -9,6 +68,7 @@ pub struct SystemMetrics {
     pub disk_usage: f32,
     pub process_count: usize,
     pub uptime: u64,
+    pub history: MetricHistory,
     system: System,
 }
 
@@ -23,6 +83,7 @@ impl SystemMetrics {
             disk_usage: 0.0,
             process_count: 0,
             uptime: 0,
+            history: MetricHistory::new(DEFAULT_HISTORY_LEN),
             system,
         }
     }
@@ -33,38 +94,150 @@ impl SystemMetrics {
         self.system.refresh_disks();
         self.system.refresh_processes();
         
+        let readings = RawReadings::from_system(&self.system);
+        self.apply(readings);
+    }
+    
+    /// Store sanitized values from a set of raw readings.
+    ///
+    /// Platforms where sysinfo reports NaN CPU usage, zero memory or no root
+    /// disk yield 0.0 for the affected metric instead of panicking.
+    pub fn apply(&mut self, readings: RawReadings) {
         // CPU usage (average across all cores)
-        self.cpu_percent = self.system.global_cpu_info().cpu_usage();
+        self.cpu_percent = sanitize_percent(readings.cpu_percent);
         
         // Memory usage
-        let total_memory = self.system.total_memory();
-        let used_memory = self.system.used_memory();
-        self.memory_percent = if total_memory > 0 {
-            (used_memory as f32 / total_memory as f32) * 100.0
+        self.memory_percent = if readings.total_memory > 0 {
+            sanitize_percent((readings.used_memory as f32 / readings.total_memory as f32) * 100.0)
         } else {
             0.0
         };
         
         // Disk usage (root filesystem)
-        self.disk_usage = self.system
-            .disks()
-            .iter()
-            .find(|disk| disk.mount_point() == std::path::Path::new("/"))
-            .map(|disk| {
-                let total = disk.total_space();
-                let available = disk.available_space();
-                if total > 0 {
-                    ((total - available) as f32 / total as f32) * 100.0
-                } else {
-                    0.0
-                }
-            })
-            .unwrap_or(0.0);
+        self.disk_usage = match readings.root_disk {
+            Some((total, available)) if total > 0 => {
+                let used = total.saturating_sub(available);
+                sanitize_percent((used as f32 / total as f32) * 100.0)
+            }
+            _ => 0.0,
+        };
         
-        // Process count
-        self.process_count = self.system.processes().len();
+        self.process_count = readings.process_count;
+        self.uptime = readings.uptime;
         
-        // Uptime
-        self.uptime = self.system.uptime();
+        self.history.push(MetricPoint {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            cpu_percent: self.cpu_percent,
+            memory_percent: self.memory_percent,
+            disk_usage: self.disk_usage,
+        });
     }
-}
\ No newline at end of file
+}
+
+/// Unvalidated readings from the platform
+#[derive(Debug, Clone, Default)]
+pub struct RawReadings {
+    pub cpu_percent: f32,
+    pub total_memory: u64,
+    pub used_memory: u64,
+    /// (total, available) bytes of the root filesystem, if one was found
+    pub root_disk: Option<(u64, u64)>,
+    pub process_count: usize,
+    pub uptime: u64,
+}
+
+impl RawReadings {
+    pub fn from_system(system: &System) -> Self {
+        Self {
+            cpu_percent: system.global_cpu_info().cpu_usage(),
+            total_memory: system.total_memory(),
+            used_memory: system.used_memory(),
+            root_disk: system
+                .disks()
+                .iter()
+                .find(|disk| disk.mount_point() == std::path::Path::new("/"))
+                .map(|disk| (disk.total_space(), disk.available_space())),
+            process_count: system.processes().len(),
+            uptime: system.uptime(),
+        }
+    }
+}
+
+/// Clamp a percentage to 0..=100, mapping NaN/inf to 0
+fn sanitize_percent(value: f32) -> f32 {
+    if value.is_finite() {
+        value.clamp(0.0, 100.0)
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(timestamp: u64) -> MetricPoint {
+        MetricPoint {
+            timestamp,
+            cpu_percent: timestamp as f32,
+            memory_percent: 0.0,
+            disk_usage: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_history_caps_and_drops_oldest() {
+        let mut history = MetricHistory::new(3);
+        for t in 0..5 {
+            history.push(point(t));
+        }
+        
+        assert_eq!(history.len(), 3);
+        let timestamps: Vec<u64> = history.points().map(|p| p.timestamp).collect();
+        assert_eq!(timestamps, vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_update_records_history() {
+        let mut metrics = SystemMetrics::new();
+        assert!(metrics.history.is_empty());
+        metrics.update();
+        metrics.update();
+        assert_eq!(metrics.history.len(), 2);
+        assert_eq!(metrics.history.capacity(), DEFAULT_HISTORY_LEN);
+    }
+
+    #[test]
+    fn test_nan_cpu_and_missing_disk_degrade_gracefully() {
+        let mut metrics = SystemMetrics::new();
+        metrics.apply(RawReadings {
+            cpu_percent: f32::NAN,
+            total_memory: 0,
+            used_memory: 10,
+            root_disk: None,
+            process_count: 3,
+            uptime: 42,
+        });
+        
+        assert_eq!(metrics.cpu_percent, 0.0);
+        assert_eq!(metrics.memory_percent, 0.0);
+        assert_eq!(metrics.disk_usage, 0.0);
+        assert_eq!(metrics.process_count, 3);
+        assert_eq!(metrics.uptime, 42);
+        
+        // Available space larger than total must not underflow
+        metrics.apply(RawReadings {
+            cpu_percent: f32::INFINITY,
+            total_memory: 100,
+            used_memory: 50,
+            root_disk: Some((100, 200)),
+            ..Default::default()
+        });
+        assert_eq!(metrics.cpu_percent, 0.0);
+        assert_eq!(metrics.memory_percent, 50.0);
+        assert_eq!(metrics.disk_usage, 0.0);
+    }
+}
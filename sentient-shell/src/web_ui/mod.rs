@@ -7,11 +7,20 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use warp::{Filter, Reply};
 use chrono::{DateTime, Utc};
-use std::collections::VecDeque;
-use std::path::Path;
+use std::collections::{BTreeMap, VecDeque};
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
 
 pub mod handlers;
 pub mod metrics;
+pub mod prometheus;
+pub mod reward_chart;
+pub mod reward_history;
+pub mod rl_dashboard;
 
 use handlers::*;
 use metrics::SystemMetrics;
@@ -21,6 +30,155 @@ pub struct DashboardState {
     pub metrics: Arc<RwLock<SystemMetrics>>,
     pub activity_log: Arc<RwLock<VecDeque<ActivityEntry>>>,
     pub service_status: Arc<RwLock<Vec<ServiceStatus>>>,
+    pub readiness: Arc<RwLock<Readiness>>,
+    activity_config: ActivityLogConfig,
+}
+
+/// Activity entries kept in memory unless configured otherwise
+pub const DEFAULT_ACTIVITY_CAPACITY: usize = 1000;
+
+/// What happens to the oldest activity entry once the log is full
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard it
+    #[default]
+    DropOldest,
+    /// Append it to the day's `activity_YYYYMMDD.jsonl` in this directory
+    /// before discarding it, so busy systems keep their full history
+    Persist(PathBuf),
+}
+
+/// Size of the in-memory activity log and what to do when it overflows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActivityLogConfig {
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for ActivityLogConfig {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_ACTIVITY_CAPACITY,
+            overflow: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+impl ActivityLogConfig {
+    /// Defaults overridden by `ACTIVITY_LOG_CAPACITY` and, to persist
+    /// evicted entries, `ACTIVITY_LOG_PERSIST_DIR`
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Some(capacity) = std::env::var("ACTIVITY_LOG_CAPACITY").ok().and_then(|v| v.parse().ok()) {
+            config.capacity = capacity;
+        }
+        if let Ok(dir) = std::env::var("ACTIVITY_LOG_PERSIST_DIR") {
+            config.overflow = OverflowPolicy::Persist(PathBuf::from(dir));
+        }
+        config
+    }
+}
+
+/// File under `dir` that entries evicted on `day` are appended to
+fn activity_archive_path(dir: &Path, day: DateTime<Utc>) -> PathBuf {
+    dir.join(format!("activity_{}.jsonl", day.format("%Y%m%d")))
+}
+
+/// Append evicted entries to today's archive file under `dir`
+async fn persist_activity(dir: &Path, entries: &[ActivityEntry]) -> Result<()> {
+    tokio::fs::create_dir_all(dir).await
+        .with_context(|| format!("Failed to create activity archive directory {:?}", dir))?;
+    
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&serde_json::to_string(entry)?);
+        lines.push('\n');
+    }
+    
+    let path = activity_archive_path(dir, Utc::now());
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+        .with_context(|| format!("Failed to open activity archive {:?}", path))?;
+    file.write_all(lines.as_bytes()).await?;
+    file.flush().await?;
+    Ok(())
+}
+
+/// Every activity entry persisted under `dir`, oldest file first
+pub async fn read_persisted_activity(dir: &Path) -> Result<Vec<ActivityEntry>> {
+    let mut paths = Vec::new();
+    let mut files = tokio::fs::read_dir(dir).await
+        .with_context(|| format!("Failed to read activity archive directory {:?}", dir))?;
+    while let Some(file) = files.next_entry().await? {
+        let name = file.file_name().to_string_lossy().to_string();
+        if name.starts_with("activity_") && name.ends_with(".jsonl") {
+            paths.push(file.path());
+        }
+    }
+    paths.sort();
+    
+    let mut entries = Vec::new();
+    for path in paths {
+        let content = tokio::fs::read_to_string(&path).await?;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            entries.push(serde_json::from_str(line)
+                .with_context(|| format!("Malformed activity entry in {:?}", path))?);
+        }
+    }
+    Ok(entries)
+}
+
+/// Component names tracked by `/readyz`
+pub const COMPONENT_METRICS: &str = "metrics_updater";
+pub const COMPONENT_SERVICE_MANAGER: &str = "service_manager";
+pub const COMPONENT_RL_TRAINING: &str = "rl_training";
+pub const COMPONENT_POLICY_INJECTOR: &str = "policy_injector";
+
+/// Initialization state of a subsystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentState {
+    Pending,
+    Ready,
+}
+
+/// Tracks which subsystems have finished initializing
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Readiness {
+    components: BTreeMap<String, ComponentState>,
+}
+
+impl Readiness {
+    pub fn new(components: &[&str]) -> Self {
+        Self {
+            components: components
+                .iter()
+                .map(|name| (name.to_string(), ComponentState::Pending))
+                .collect(),
+        }
+    }
+    
+    /// Require a component to be ready before `/readyz` succeeds
+    pub fn require(&mut self, name: &str) {
+        self.components
+            .entry(name.to_string())
+            .or_insert(ComponentState::Pending);
+    }
+    
+    pub fn mark_ready(&mut self, name: &str) {
+        self.components.insert(name.to_string(), ComponentState::Ready);
+    }
+    
+    pub fn is_ready(&self) -> bool {
+        self.components.values().all(|s| *s == ComponentState::Ready)
+    }
+    
+    pub fn components(&self) -> &BTreeMap<String, ComponentState> {
+        &self.components
+    }
 }
 
 /// Activity log entry
@@ -34,6 +192,18 @@ pub struct ActivityEntry {
     pub reward: f32,
     pub execution_time: f32,
     pub source: String,
+    /// Identifier of the injected goal this entry executed
+    #[serde(default)]
+    pub goal_id: Option<String>,
+    /// RNG seed used when the goal was chosen, for deterministic replay
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// What selected the action (e.g. "rl_policy", "llm_observer", "web_ui")
+    #[serde(default)]
+    pub action_source: Option<String>,
+    /// Trace id shared by the logs of the work behind this entry
+    #[serde(default)]
+    pub trace_id: Option<String>,
 }
 
 /// Service status
@@ -56,9 +226,40 @@ impl DashboardState {
     pub fn new() -> Self {
         Self {
             metrics: Arc::new(RwLock::new(SystemMetrics::new())),
-            activity_log: Arc::new(RwLock::new(VecDeque::with_capacity(1000))),
+            activity_log: Arc::new(RwLock::new(VecDeque::with_capacity(DEFAULT_ACTIVITY_CAPACITY))),
             service_status: Arc::new(RwLock::new(Vec::new())),
+            readiness: Arc::new(RwLock::new(Readiness::new(&[
+                COMPONENT_METRICS,
+                COMPONENT_SERVICE_MANAGER,
+            ]))),
+            activity_config: ActivityLogConfig::default(),
+        }
+    }
+    
+    /// Keep `config.capacity` activity entries in memory (at least one) and
+    /// handle older ones by `config.overflow`
+    pub fn with_activity_log(mut self, config: ActivityLogConfig) -> Self {
+        self.activity_config = ActivityLogConfig {
+            capacity: config.capacity.max(1),
+            ..config
+        };
+        self
+    }
+    
+    /// Also wait for the RL trainer and policy injector before reporting ready
+    pub fn with_rl_subsystems(self) -> Self {
+        {
+            let mut readiness = self.readiness.try_write()
+                .expect("readiness is not shared yet");
+            readiness.require(COMPONENT_RL_TRAINING);
+            readiness.require(COMPONENT_POLICY_INJECTOR);
         }
+        self
+    }
+    
+    /// Record that a component finished initializing
+    pub async fn mark_ready(&self, component: &str) {
+        self.readiness.write().await.mark_ready(component);
     }
     
     /// Update system metrics
@@ -67,13 +268,27 @@ impl DashboardState {
         metrics.update();
     }
     
-    /// Add activity entry
-    pub async fn add_activity(&self, entry: ActivityEntry) {
-        let mut log = self.activity_log.write().await;
-        if log.len() >= 1000 {
-            log.pop_front();
+    /// Add activity entry, evicting the oldest beyond the configured capacity.
+    /// An entry without a trace id takes the one current on this thread.
+    pub async fn add_activity(&self, mut entry: ActivityEntry) {
+        if entry.trace_id.is_none() {
+            entry.trace_id = crate::trace_id::current();
         }
+        // The lock is held through the persist so concurrent evictions reach
+        // the archive in the order they left the log
+        let mut log = self.activity_log.write().await;
         log.push_back(entry);
+        let excess = log.len().saturating_sub(self.activity_config.capacity);
+        let evicted: Vec<ActivityEntry> = log.drain(..excess).collect();
+        
+        if evicted.is_empty() {
+            return;
+        }
+        if let OverflowPolicy::Persist(dir) = &self.activity_config.overflow {
+            if let Err(e) = persist_activity(dir, &evicted).await {
+                log::warn!("Failed to persist {} evicted activity entries: {:#}", evicted.len(), e);
+            }
+        }
     }
     
     /// Update service status
@@ -83,31 +298,210 @@ impl DashboardState {
     }
 }
 
-/// Start the web UI server
+/// Dashboard of the running admin panel, which executed goals are reported to
+lazy_static::lazy_static! {
+    static ref DASHBOARD: Arc<RwLock<Option<Arc<DashboardState>>>> =
+        Arc::new(RwLock::new(None));
+}
+
+/// Report activity recorded anywhere in the process to `state`
+pub async fn attach_dashboard(state: Arc<DashboardState>) {
+    *DASHBOARD.write().await = Some(state);
+}
+
+/// Add an entry to the attached dashboard's activity log. Dropped when no
+/// admin panel is running.
+pub async fn record_activity(entry: ActivityEntry) {
+    if let Some(state) = DASHBOARD.read().await.as_ref() {
+        state.add_activity(entry).await;
+    }
+}
+
+/// Start the web UI server. With `SENTIENT_RL_ENABLED` set, `/readyz` also
+/// waits for the RL trainer and policy injector.
 pub async fn start_server(port: u16) -> Result<()> {
-    let state = Arc::new(DashboardState::new());
+    let state = server_state(std::env::var("SENTIENT_RL_ENABLED").is_ok());
+    start_server_with_state(port, Arc::new(state)).await
+}
+
+/// Dashboard state `start_server` serves
+fn server_state(rl_enabled: bool) -> DashboardState {
+    let state = DashboardState::new().with_activity_log(ActivityLogConfig::from_env());
+    if rl_enabled {
+        state.with_rl_subsystems()
+    } else {
+        state
+    }
+}
+
+/// How long in-flight requests get to finish once shutdown is requested
+pub const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Start the web UI server with a preconfigured state. Runs until SIGINT or
+/// SIGTERM, then drains in-flight requests.
+pub async fn start_server_with_state(port: u16, state: Arc<DashboardState>) -> Result<()> {
+    attach_dashboard(state.clone()).await;
     
-    // Clone for background tasks
-    let state_clone = state.clone();
+    let shutdown = CancellationToken::new();
+    let on_signal = shutdown.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        log::info!("Shutdown requested, admin panel no longer accepting connections");
+        on_signal.cancel();
+    });
+    
+    let (addr, server) = serve(([0, 0, 0, 0], port).into(), state, shutdown, SHUTDOWN_DRAIN_TIMEOUT)?;
+    log::info!("🎛️ SentientOS Admin Panel starting on http://{}", addr);
+    server.await;
+    log::info!("Admin panel stopped");
+    
+    Ok(())
+}
+
+/// Bind the dashboard to `addr` and start its background tasks. The returned
+/// future serves until `shutdown` is cancelled, which also stops the
+/// background tasks.
+pub fn serve(
+    addr: SocketAddr,
+    state: Arc<DashboardState>,
+    shutdown: CancellationToken,
+    drain_timeout: Duration,
+) -> Result<(SocketAddr, impl Future<Output = ()>)> {
+    spawn_background_tasks(state.clone(), shutdown.clone());
+    serve_routes(addr, routes(state), shutdown, drain_timeout)
+}
+
+/// Serve `routes` until `shutdown` is cancelled. New connections are refused
+/// from then on; requests already in flight get `drain_timeout` to finish
+/// before they are dropped.
+fn serve_routes<F>(
+    addr: SocketAddr,
+    routes: F,
+    shutdown: CancellationToken,
+    drain_timeout: Duration,
+) -> Result<(SocketAddr, impl Future<Output = ()>)>
+where
+    F: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static,
+    F::Extract: Reply,
+{
+    let stop = shutdown.clone();
+    let (addr, server) = warp::serve(routes)
+        .try_bind_with_graceful_shutdown(addr, async move { stop.cancelled().await })
+        .with_context(|| format!("Failed to bind admin panel to {}", addr))?;
     
+    let run = async move {
+        let mut server = tokio::spawn(server);
+        tokio::select! {
+            _ = &mut server => return,
+            _ = shutdown.cancelled() => {}
+        }
+        if tokio::time::timeout(drain_timeout, &mut server).await.is_err() {
+            log::warn!("Dropping requests still in flight after {:?}", drain_timeout);
+            server.abort();
+        }
+    };
+    Ok((addr, run))
+}
+
+/// Resolves on SIGINT or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::warn!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                log::warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+/// Metrics refresh, service manager start-up and RL readiness polling, all
+/// stopped when `shutdown` is cancelled
+fn spawn_background_tasks(state: Arc<DashboardState>, shutdown: CancellationToken) {
     // Start metrics updater
+    let state_clone = state.clone();
+    let stop = shutdown.clone();
     tokio::spawn(async move {
+        let mut first = true;
         loop {
             state_clone.update_metrics().await;
-            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+            if first {
+                state_clone.mark_ready(COMPONENT_METRICS).await;
+                first = false;
+            }
+            tokio::select! {
+                _ = stop.cancelled() => break,
+                _ = tokio::time::sleep(Duration::from_secs(metrics::UPDATE_INTERVAL_SECS)) => {}
+            }
         }
     });
     
-    // Define routes
-    let routes = routes(state);
-    
-    // Start server
-    log::info!("🎛️ SentientOS Admin Panel starting on http://0.0.0.0:{}", port);
-    warp::serve(routes)
-        .run(([0, 0, 0, 0], port))
+    // Service manager is a lazy global; touching it runs its initialization
+    let state_clone = state.clone();
+    tokio::spawn(async move {
+        let initialized = tokio::task::spawn_blocking(|| {
+            crate::service::manager::get_service_manager();
+        })
         .await;
+        if initialized.is_ok() {
+            state_clone.mark_ready(COMPONENT_SERVICE_MANAGER).await;
+        }
+    });
     
-    Ok(())
+    // Wait for configured RL subsystems to come up
+    tokio::spawn(async move {
+        loop {
+            let pending: Vec<String> = {
+                let readiness = state.readiness.read().await;
+                readiness
+                    .components()
+                    .iter()
+                    .filter(|(_, s)| **s == ComponentState::Pending)
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            };
+            
+            let mut waiting = false;
+            for name in pending {
+                let up = match name.as_str() {
+                    COMPONENT_RL_TRAINING => crate::rl_training::get_training_stats().await.is_some(),
+                    COMPONENT_POLICY_INJECTOR => crate::policy_injector::get_injector_stats().await.is_some(),
+                    _ => continue,
+                };
+                if up {
+                    state.mark_ready(&name).await;
+                } else {
+                    waiting = true;
+                }
+            }
+            
+            if !waiting {
+                break;
+            }
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = tokio::time::sleep(Duration::from_secs(2)) => {}
+            }
+        }
+    });
 }
 
 /// Configure all routes
@@ -140,6 +534,13 @@ fn routes(
         .and(with_state(state.clone()))
         .and_then(handlers::get_system_status);
     
+    let system_history = api
+        .and(warp::path("system"))
+        .and(warp::path("history"))
+        .and(warp::path::end())
+        .and(with_state(state.clone()))
+        .and_then(handlers::get_system_history);
+    
     let activity_recent = api
         .and(warp::path("activity"))
         .and(warp::path("recent"))
@@ -157,12 +558,44 @@ fn routes(
         .and(with_state(state.clone()))
         .and_then(handlers::inject_goal);
     
+    let llm_config = api
+        .and(warp::path("llm"))
+        .and(warp::path("config"))
+        .and(warp::path::end())
+        .and(warp::get())
+        .and_then(handlers::get_llm_config);
+    
+    // Prometheus scrape endpoint
+    let prometheus_metrics = warp::path("metrics")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_state(state.clone()))
+        .and_then(handlers::get_prometheus_metrics);
+    
+    // Orchestration probes
+    let healthz = warp::path("healthz")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and_then(handlers::healthz);
+    
+    let readyz = warp::path("readyz")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(with_state(state.clone()))
+        .and_then(handlers::readyz);
+    
     // Combine all routes
     index
         .or(dashboard_all)
         .or(system_status)
+        .or(system_history)
         .or(activity_recent)
         .or(inject_goal)
+        .or(llm_config)
+        .or(prometheus_metrics)
+        .or(healthz)
+        .or(readyz)
+        .or(rl_dashboard::rl_routes(Arc::new(rl_dashboard::RLDashboardState::new())))
         .with(cors)
 }
 
@@ -448,7 +881,7 @@ const INDEX_HTML: &str = r#"
     
     <script>
         let refreshInterval;
-        let cpuHistory = new Array(30).fill(0);
+        let cpuHistory = [];
         
         async function refreshData() {
             try {
@@ -463,10 +896,8 @@ const INDEX_HTML: &str = r#"
                 document.getElementById('disk-percent').textContent = 
                     data.system.disk_usage.toFixed(1) + '%';
                 
-                // Update CPU chart
-                cpuHistory.push(data.system.cpu_percent);
-                cpuHistory.shift();
-                updateChart();
+                // Update CPU chart from the server-side history
+                await refreshHistory();
                 
                 // Update service status
                 const serviceHtml = data.services.map(s => `
@@ -492,6 +923,7 @@ const INDEX_HTML: &str = r#"
                                 <strong>${time}</strong>
                                 <span style="color: #00ff88">reward: ${a.reward.toFixed(2)}</span>
                             </div>
+                            ${a.trace_id ? `<div style="color: #666; font-size: 0.8em">trace ${a.trace_id}</div>` : ''}
                             <div style="margin-top: 4px">${goalShort}</div>
                             ${a.output ? `<div style="margin-top: 4px; color: #666; font-size: 0.85em">${a.output.substring(0, 100)}...</div>` : ''}
                         </div>
@@ -511,6 +943,17 @@ const INDEX_HTML: &str = r#"
             }
         }
         
+        async function refreshHistory() {
+            try {
+                const response = await fetch('/api/system/history');
+                const history = await response.json();
+                cpuHistory = history.points.map(p => p.cpu_percent);
+                updateChart();
+            } catch (error) {
+                console.error('Failed to refresh history:', error);
+            }
+        }
+        
         function updateChart() {
             const chart = document.getElementById('cpu-chart');
             const max = Math.max(...cpuHistory, 1);
@@ -584,4 +1027,228 @@ const INDEX_HTML: &str = r#"
     </script>
 </body>
 </html>
-"#;
\ No newline at end of file
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activity_entry_reads_old_format() {
+        let old = r#"{
+            "timestamp": "2025-01-01T00:00:00Z",
+            "goal": "Check memory usage",
+            "command": "free -h",
+            "output": "Mem: 8G",
+            "success": true,
+            "reward": 0.7,
+            "execution_time": 0.1,
+            "source": "llm_observer"
+        }"#;
+        
+        let entry: ActivityEntry = serde_json::from_str(old).unwrap();
+        assert_eq!(entry.goal, "Check memory usage");
+        assert!(entry.goal_id.is_none());
+        assert!(entry.seed.is_none());
+        assert!(entry.action_source.is_none());
+    }
+
+    #[test]
+    fn test_activity_entry_round_trips_replay_fields() {
+        let entry = ActivityEntry {
+            timestamp: Utc::now(),
+            goal: "Analyze CPU load distribution".to_string(),
+            command: "uptime".to_string(),
+            output: "load average: 0.1".to_string(),
+            success: true,
+            reward: 0.9,
+            execution_time: 0.05,
+            source: "rl_policy".to_string(),
+            goal_id: Some("goal-123".to_string()),
+            seed: Some(42),
+            action_source: Some("rl_policy".to_string()),
+            trace_id: Some("7c9e6679-7425-40de-944b-e07fc1f90ae7".to_string()),
+        };
+        
+        let json = serde_json::to_string(&entry).unwrap();
+        let parsed: ActivityEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.goal_id.as_deref(), Some("goal-123"));
+        assert_eq!(parsed.seed, Some(42));
+        assert_eq!(parsed.action_source.as_deref(), Some("rl_policy"));
+        assert_eq!(parsed.trace_id, entry.trace_id);
+        assert_eq!(parsed.timestamp, entry.timestamp);
+    }
+
+    #[tokio::test]
+    async fn test_persist_overflow_archives_evicted_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = DashboardState::new().with_activity_log(ActivityLogConfig {
+            capacity: 2,
+            overflow: OverflowPolicy::Persist(dir.path().to_path_buf()),
+        });
+        
+        for i in 0..5 {
+            state.add_activity(ActivityEntry {
+                timestamp: Utc::now(),
+                goal: format!("goal {}", i),
+                command: "true".to_string(),
+                output: String::new(),
+                success: true,
+                reward: 0.5,
+                execution_time: 0.01,
+                source: "test".to_string(),
+                goal_id: None,
+                seed: None,
+                action_source: None,
+                trace_id: None,
+            }).await;
+        }
+        
+        let goals = |entries: &[ActivityEntry]| entries.iter().map(|e| e.goal.clone()).collect::<Vec<_>>();
+        let in_memory: Vec<ActivityEntry> = state.activity_log.read().await.iter().cloned().collect();
+        assert_eq!(goals(&in_memory), vec!["goal 3", "goal 4"]);
+        
+        let archived = read_persisted_activity(dir.path()).await.unwrap();
+        assert_eq!(goals(&archived), vec!["goal 0", "goal 1", "goal 2"]);
+        
+        // The default policy drops evictions without touching the disk
+        let dropping = DashboardState::new().with_activity_log(ActivityLogConfig {
+            capacity: 1,
+            ..ActivityLogConfig::default()
+        });
+        for _ in 0..3 {
+            dropping.add_activity(in_memory[0].clone()).await;
+        }
+        assert_eq!(dropping.activity_log.read().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_activity_takes_the_current_trace_id() {
+        let state = DashboardState::new();
+        let entry = |trace_id: Option<&str>| ActivityEntry {
+            timestamp: Utc::now(),
+            goal: "Check disk space".to_string(),
+            command: "df -h".to_string(),
+            output: String::new(),
+            success: true,
+            reward: 0.5,
+            execution_time: 0.01,
+            source: "test".to_string(),
+            goal_id: None,
+            seed: None,
+            action_source: None,
+            trace_id: trace_id.map(str::to_string),
+        };
+        
+        state.add_activity(entry(None)).await;
+        {
+            let _trace = crate::trace_id::enter("trace-current");
+            state.add_activity(entry(None)).await;
+            state.add_activity(entry(Some("trace-explicit"))).await;
+        }
+        
+        let trace_ids: Vec<Option<String>> =
+            state.activity_log.read().await.iter().map(|e| e.trace_id.clone()).collect();
+        assert_eq!(trace_ids, vec![
+            None,
+            Some("trace-current".to_string()),
+            Some("trace-explicit".to_string()),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_refuses_new_connections_and_drains_in_flight() {
+        let started = Arc::new(tokio::sync::Notify::new());
+        let handler_started = started.clone();
+        let slow = warp::path("slow").and_then(move || {
+            let started = handler_started.clone();
+            async move {
+                started.notify_one();
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                Ok::<_, warp::Rejection>("done")
+            }
+        });
+        let shutdown = CancellationToken::new();
+        let (addr, server) = serve_routes(
+            ([127, 0, 0, 1], 0).into(),
+            slow,
+            shutdown.clone(),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        let server = tokio::spawn(server);
+        
+        let in_flight = tokio::spawn(reqwest::get(format!("http://{}/slow", addr)));
+        started.notified().await;
+        shutdown.cancel();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        
+        assert!(
+            tokio::net::TcpStream::connect(addr).await.is_err(),
+            "new connection accepted after shutdown"
+        );
+        let response = in_flight.await.unwrap().unwrap();
+        assert!(response.status().is_success());
+        assert_eq!(response.text().await.unwrap(), "done");
+        
+        // Nothing left in flight, so the server finishes well inside the drain timeout
+        tokio::time::timeout(Duration::from_secs(1), server).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_healthz_always_ok() {
+        let state = Arc::new(DashboardState::new());
+        let response = warp::test::request()
+            .path("/healthz")
+            .reply(&routes(state))
+            .await;
+        
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["status"], "ok");
+    }
+
+    #[tokio::test]
+    async fn test_readyz_waits_for_initialization() {
+        let state = Arc::new(DashboardState::new().with_rl_subsystems());
+        let filter = routes(state.clone());
+        
+        let response = warp::test::request().path("/readyz").reply(&filter).await;
+        assert_eq!(response.status(), 503);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["status"], "not_ready");
+        assert_eq!(body["components"][COMPONENT_METRICS], "pending");
+        
+        for component in [
+            COMPONENT_METRICS,
+            COMPONENT_SERVICE_MANAGER,
+            COMPONENT_RL_TRAINING,
+        ] {
+            state.mark_ready(component).await;
+        }
+        let response = warp::test::request().path("/readyz").reply(&filter).await;
+        assert_eq!(response.status(), 503, "policy injector still pending");
+        
+        state.mark_ready(COMPONENT_POLICY_INJECTOR).await;
+        let response = warp::test::request().path("/readyz").reply(&filter).await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["status"], "ready");
+        assert_eq!(body["components"][COMPONENT_RL_TRAINING], "ready");
+    }
+    
+    #[tokio::test]
+    async fn test_server_waits_for_rl_only_when_enabled() {
+        let components = |state: DashboardState| {
+            let readiness = state.readiness.try_read().unwrap();
+            readiness.components().keys().cloned().collect::<Vec<_>>()
+        };
+        
+        let enabled = components(server_state(true));
+        assert!(enabled.contains(&COMPONENT_RL_TRAINING.to_string()));
+        assert!(enabled.contains(&COMPONENT_POLICY_INJECTOR.to_string()));
+        
+        let disabled = components(server_state(false));
+        assert_eq!(disabled, vec![COMPONENT_METRICS.to_string(), COMPONENT_SERVICE_MANAGER.to_string()]);
+    }
+}
@@ -0,0 +1,256 @@
+// Prometheus text exposition for the /metrics endpoint
+// Renders system, service, RL training and injector stats
+
+use super::metrics::SystemMetrics;
+use super::ServiceStatus;
+use crate::policy_injector::InjectorStats;
+use crate::rl_training::TrainingStats;
+use std::fmt::Write;
+
+/// Content type for Prometheus text format 0.0.4
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Metric type as declared on the TYPE line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricType {
+    Gauge,
+    Counter,
+}
+
+impl MetricType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricType::Gauge => "gauge",
+            MetricType::Counter => "counter",
+        }
+    }
+}
+
+/// Incrementally builds a Prometheus text exposition document
+#[derive(Debug, Default)]
+pub struct PrometheusWriter {
+    out: String,
+}
+
+impl PrometheusWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emit the HELP and TYPE header for a metric family
+    pub fn family(&mut self, name: &str, metric_type: MetricType, help: &str) -> &mut Self {
+        let _ = writeln!(self.out, "# HELP {} {}", name, escape_help(help));
+        let _ = writeln!(self.out, "# TYPE {} {}", name, metric_type.as_str());
+        self
+    }
+
+    /// Emit one sample, with optional labels
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            let rendered: Vec<String> = labels
+                .iter()
+                .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                .collect();
+            let _ = write!(self.out, "{{{}}}", rendered.join(","));
+        }
+        let _ = writeln!(self.out, " {}", format_value(value));
+        self
+    }
+
+    /// Shorthand for a family with a single unlabelled sample
+    pub fn single(&mut self, name: &str, metric_type: MetricType, help: &str, value: f64) -> &mut Self {
+        self.family(name, metric_type, help).sample(name, &[], value)
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Render all SentientOS metrics in Prometheus text format
+pub fn render_metrics(
+    system: &SystemMetrics,
+    services: &[ServiceStatus],
+    training: Option<&TrainingStats>,
+    injector: Option<&InjectorStats>,
+) -> String {
+    let mut w = PrometheusWriter::new();
+
+    // System
+    w.single("sentient_cpu_usage_percent", MetricType::Gauge,
+        "Global CPU usage in percent", system.cpu_percent as f64);
+    w.single("sentient_memory_usage_percent", MetricType::Gauge,
+        "Used memory in percent of total", system.memory_percent as f64);
+    w.single("sentient_disk_usage_percent", MetricType::Gauge,
+        "Root filesystem usage in percent", system.disk_usage as f64);
+    w.single("sentient_process_count", MetricType::Gauge,
+        "Number of running processes", system.process_count as f64);
+    w.single("sentient_uptime_seconds", MetricType::Counter,
+        "System uptime in seconds", system.uptime as f64);
+
+    // Services
+    w.family("sentient_service_up", MetricType::Gauge,
+        "Whether the service is running (1) or not (0)");
+    for service in services {
+        let up = if service.status == "running" { 1.0 } else { 0.0 };
+        w.sample("sentient_service_up", &[("service", &service.name), ("status", &service.status)], up);
+    }
+    w.family("sentient_service_uptime_seconds", MetricType::Gauge,
+        "Seconds since the service was started");
+    for service in services {
+        if let Some(uptime) = service.uptime {
+            w.sample("sentient_service_uptime_seconds", &[("service", &service.name)], uptime as f64);
+        }
+    }
+    let manager_services = crate::service::manager::get_service_manager()
+        .list_services()
+        .unwrap_or_default();
+    w.family("sentient_service_restarts_total", MetricType::Counter,
+        "Number of times the service manager restarted the service");
+    for info in &manager_services {
+        w.sample("sentient_service_restarts_total", &[("service", &info.name)], info.restart_count as f64);
+    }
+
+    // RL training
+    if let Some(stats) = training {
+        w.single("sentient_rl_training_running", MetricType::Gauge,
+            "Whether an RL training session is active", if stats.is_running { 1.0 } else { 0.0 });
+        w.single("sentient_rl_episodes_completed", MetricType::Gauge,
+            "Episodes completed in the current training session", stats.current_episode as f64);
+        w.single("sentient_rl_episodes_target", MetricType::Gauge,
+            "Episodes configured for the current training session", stats.total_episodes as f64);
+        w.single("sentient_rl_best_reward", MetricType::Gauge,
+            "Best episode reward seen in the current session", stats.best_reward as f64);
+        w.single("sentient_rl_steps_total", MetricType::Counter,
+            "Environment steps taken in the current session", stats.total_steps as f64);
+        w.single("sentient_rl_steps_per_second", MetricType::Gauge,
+            "Average environment steps per second", stats.steps_per_sec as f64);
+        if let Some(rate) = stats.exploration_rate {
+            w.single("sentient_rl_exploration_rate", MetricType::Gauge,
+                "Current exploration rate (epsilon) of the training agent", rate as f64);
+        }
+    }
+
+    // Policy injector
+    if let Some(stats) = injector {
+        w.single("sentient_injector_running", MetricType::Gauge,
+            "Whether the policy injector is active", if stats.is_running { 1.0 } else { 0.0 });
+        w.single("sentient_injector_injections_total", MetricType::Counter,
+            "Goals injected by the policy", stats.total_injections as f64);
+        w.single("sentient_injector_successful_injections_total", MetricType::Counter,
+            "Injected goals that completed successfully", stats.successful_injections as f64);
+        w.single("sentient_injector_success_rate", MetricType::Gauge,
+            "Fraction of injected goals that succeeded", stats.success_rate as f64);
+        w.single("sentient_injector_avg_confidence", MetricType::Gauge,
+            "Average policy confidence over recent injections", stats.avg_confidence as f64);
+    }
+
+    w.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal exposition-format check: every sample belongs to a family
+    /// declared with HELP and TYPE, and every value parses as a float.
+    fn assert_valid_exposition(text: &str) {
+        let mut declared = std::collections::HashSet::new();
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let mut parts = rest.split_whitespace();
+                let name = parts.next().unwrap();
+                let kind = parts.next().unwrap();
+                assert!(matches!(kind, "gauge" | "counter"), "bad type: {}", line);
+                declared.insert(name.to_string());
+            } else if line.starts_with("# HELP ") {
+                continue;
+            } else {
+                let (series, value) = line.rsplit_once(' ').expect("sample has a value");
+                let name = series.split('{').next().unwrap();
+                assert!(declared.contains(name), "undeclared metric: {}", name);
+                assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+                if let Some(labels) = series.strip_prefix(name) {
+                    if !labels.is_empty() {
+                        assert!(labels.starts_with('{') && labels.ends_with('}'), "bad labels: {}", line);
+                    }
+                }
+                value.parse::<f64>().unwrap_or_else(|_| panic!("bad value: {}", line));
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_metrics_is_valid_exposition() {
+        let system = SystemMetrics::new();
+        let services = vec![
+            ServiceStatus { name: "activity-loop".into(), status: "running".into(), pid: Some(7), uptime: Some(30) },
+            ServiceStatus { name: "llm-observer".into(), status: "stopped".into(), pid: None, uptime: None },
+        ];
+        let training = TrainingStats {
+            current_episode: 12,
+            total_episodes: 100,
+            best_reward: 4.5,
+            recent_rewards: vec![1.0, 2.0],
+            recent_episodes: vec![(10, 2.0), (11, 1.0)],
+            is_running: true,
+            total_steps: 1200,
+            steps_per_sec: 40.0,
+            converged_at: None,
+            exploration_rate: Some(0.25),
+        };
+        let injector = InjectorStats {
+            is_running: true,
+            total_injections: 10,
+            successful_injections: 7,
+            success_rate: 0.7,
+            avg_confidence: 0.8,
+            last_injection: None,
+        };
+
+        let text = render_metrics(&system, &services, Some(&training), Some(&injector));
+        assert_valid_exposition(&text);
+
+        for name in [
+            "sentient_cpu_usage_percent",
+            "sentient_memory_usage_percent",
+            "sentient_service_up{service=\"activity-loop\",status=\"running\"} 1",
+            "sentient_service_up{service=\"llm-observer\",status=\"stopped\"} 0",
+            "sentient_rl_best_reward 4.5",
+            "sentient_rl_steps_per_second 40",
+            "sentient_rl_exploration_rate 0.25",
+            "sentient_injector_injections_total 10",
+        ] {
+            assert!(text.contains(name), "missing {}", name);
+        }
+    }
+
+    #[test]
+    fn test_label_values_are_escaped() {
+        let mut w = PrometheusWriter::new();
+        w.family("x", MetricType::Gauge, "test").sample("x", &[("name", "a\"b\\c")], 1.0);
+        assert!(w.finish().contains(r#"x{name="a\"b\\c"} 1"#));
+    }
+}
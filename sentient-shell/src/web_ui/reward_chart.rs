@@ -0,0 +1,131 @@
+// Server-side reward chart for /api/rl/rewards.svg
+// Renders the reward history and its moving average as a standalone SVG
+
+use super::reward_history::moving_average;
+use std::fmt::Write;
+
+/// Content type for the rendered chart
+pub const CONTENT_TYPE: &str = "image/svg+xml";
+
+/// Moving-average window used when the request doesn't give one
+pub const DEFAULT_WINDOW: usize = 10;
+
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 300.0;
+const PADDING: f32 = 40.0;
+
+/// Render rewards (one per episode, starting at `first_episode`) with a
+/// `window`-episode moving average. A window of 0 or 1 draws the raw line only.
+pub fn render_svg(rewards: &[f32], first_episode: usize, window: usize) -> String {
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"##,
+        w = WIDTH,
+        h = HEIGHT
+    );
+    let _ = writeln!(svg, r##"<rect width="100%" height="100%" fill="#111"/>"##);
+
+    if rewards.is_empty() {
+        let _ = writeln!(
+            svg,
+            r##"<text x="{}" y="{}" fill="#888" font-family="sans-serif" font-size="14" text-anchor="middle">No reward data</text>"##,
+            WIDTH / 2.0,
+            HEIGHT / 2.0
+        );
+        svg.push_str("</svg>\n");
+        return svg;
+    }
+
+    let min = rewards.iter().copied().fold(f32::INFINITY, f32::min);
+    let max = rewards.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+
+    // Grid
+    for i in 0..=5 {
+        let y = PADDING + (HEIGHT - 2.0 * PADDING) * i as f32 / 5.0;
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{}" y1="{y:.1}" x2="{}" y2="{y:.1}" stroke="#333" stroke-width="1"/>"##,
+            PADDING,
+            WIDTH - PADDING
+        );
+    }
+
+    let _ = writeln!(
+        svg,
+        r##"<polyline class="reward" fill="none" stroke="#00ff88" stroke-width="2" points="{}"/>"##,
+        polyline_points(rewards, min, max)
+    );
+    if window > 1 {
+        let _ = writeln!(
+            svg,
+            r##"<polyline class="moving-average" fill="none" stroke="#ffaa00" stroke-width="2" points="{}"/>"##,
+            polyline_points(&moving_average(rewards, window), min, max)
+        );
+    }
+
+    // Labels: reward range on the left, episode range along the bottom
+    let label = r##"fill="#888" font-family="sans-serif" font-size="12""##;
+    let _ = writeln!(svg, r#"<text x="5" y="{}" {}>{:.2}</text>"#, PADDING, label, max);
+    let _ = writeln!(svg, r#"<text x="5" y="{}" {}>{:.2}</text>"#, HEIGHT - PADDING, label, min);
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" {}>ep {}</text>"#,
+        PADDING,
+        HEIGHT - 10.0,
+        label,
+        first_episode
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" {} text-anchor="end">ep {}</text>"#,
+        WIDTH - PADDING,
+        HEIGHT - 10.0,
+        label,
+        first_episode + rewards.len() - 1
+    );
+    if window > 1 {
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="20" {} text-anchor="end">{}-episode moving average</text>"#,
+            WIDTH - PADDING,
+            label,
+            window
+        );
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// `x,y` pairs for a polyline scaled to the plot area
+fn polyline_points(values: &[f32], min: f32, max: f32) -> String {
+    let plot_width = WIDTH - 2.0 * PADDING;
+    let plot_height = HEIGHT - 2.0 * PADDING;
+    let range = if max > min { max - min } else { 1.0 };
+    let last = values.len().saturating_sub(1).max(1) as f32;
+
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let x = PADDING + plot_width * i as f32 / last;
+            let y = PADDING + plot_height * (max - value) / range;
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_history_renders_placeholder() {
+        let svg = render_svg(&[], 0, DEFAULT_WINDOW);
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains("No reward data"));
+        assert!(!svg.contains("<polyline"));
+    }
+}
@@ -0,0 +1,244 @@
+// Episode reward history for the RL dashboard
+// Keeps the raw per-episode series and derives smoothed views from it on demand
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Points kept before the oldest are dropped
+pub const MAX_POINTS: usize = 1000;
+
+/// Buckets used by `histogram` when the caller does not choose
+pub const DEFAULT_BUCKETS: usize = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RewardPoint {
+    pub episode: usize,
+    pub reward: f32,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// How `smooth` is applied to the series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Smoothing {
+    /// Trailing moving average
+    #[default]
+    Ma,
+    /// Exponential moving average with span `window`
+    Ema,
+}
+
+/// One equal-width bin of a return histogram, covering `[low, high)`;
+/// the last bin also includes `high`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistogramBucket {
+    pub low: f32,
+    pub high: f32,
+    pub count: usize,
+}
+
+/// Raw reward series ordered by episode
+#[derive(Debug, Clone, Default)]
+pub struct RewardHistory {
+    points: Vec<RewardPoint>,
+}
+
+impl RewardHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one episode's reward, replacing any earlier value for it
+    pub fn record(&mut self, episode: usize, reward: f32) {
+        let point = RewardPoint {
+            episode,
+            reward,
+            timestamp: Utc::now(),
+        };
+        match self.points.binary_search_by_key(&episode, |p| p.episode) {
+            Ok(index) => self.points[index] = point,
+            Err(index) => self.points.insert(index, point),
+        }
+
+        if self.points.len() > MAX_POINTS {
+            self.points.drain(0..self.points.len() - MAX_POINTS);
+        }
+    }
+
+    pub fn contains(&self, episode: usize) -> bool {
+        self.points.binary_search_by_key(&episode, |p| p.episode).is_ok()
+    }
+
+    pub fn points(&self) -> &[RewardPoint] {
+        &self.points
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Raw rewards in episode order
+    pub fn rewards(&self) -> Vec<f32> {
+        self.points.iter().map(|p| p.reward).collect()
+    }
+
+    /// Trailing moving average over `window` episodes, one value per point.
+    /// Windows longer than the series average over every point seen so far.
+    pub fn smoothed(&self, window: usize) -> Vec<f32> {
+        moving_average(&self.rewards(), window)
+    }
+
+    /// Exponential moving average with smoothing factor `2 / (window + 1)`
+    pub fn ema(&self, window: usize) -> Vec<f32> {
+        exponential_moving_average(&self.rewards(), window)
+    }
+
+    pub fn smooth(&self, method: Smoothing, window: usize) -> Vec<f32> {
+        match method {
+            Smoothing::Ma => self.smoothed(window),
+            Smoothing::Ema => self.ema(window),
+        }
+    }
+
+    /// Episode returns bucketed into `buckets` equal-width bins
+    pub fn histogram(&self, buckets: usize) -> Vec<HistogramBucket> {
+        histogram(&self.rewards(), buckets)
+    }
+}
+
+/// Bucket `values` into at most `buckets` equal-width bins spanning
+/// their range (see [`sentient_rl_stats::histogram`])
+pub fn histogram(values: &[f32], buckets: usize) -> Vec<HistogramBucket> {
+    sentient_rl_stats::histogram(&widen(values), buckets)
+        .into_iter()
+        .map(|bucket| HistogramBucket {
+            low: bucket.low as f32,
+            high: bucket.high as f32,
+            count: bucket.count,
+        })
+        .collect()
+}
+
+/// Trailing moving average; early points average over what is available
+pub fn moving_average(values: &[f32], window: usize) -> Vec<f32> {
+    narrow(sentient_rl_stats::moving_average(&widen(values), window))
+}
+
+/// Exponential moving average seeded with the first value
+pub fn exponential_moving_average(values: &[f32], window: usize) -> Vec<f32> {
+    narrow(sentient_rl_stats::exponential_moving_average(&widen(values), window))
+}
+
+fn widen(values: &[f32]) -> Vec<f64> {
+    values.iter().map(|&v| f64::from(v)).collect()
+}
+
+fn narrow(values: Vec<f64>) -> Vec<f32> {
+    values.into_iter().map(|v| v as f32).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(rewards: &[f32]) -> RewardHistory {
+        let mut history = RewardHistory::new();
+        for (episode, &reward) in rewards.iter().enumerate() {
+            history.record(episode, reward);
+        }
+        history
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-5, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_smoothed_matches_hand_computed_average() {
+        let raw = [2.0, 8.0, 2.0, 8.0, 5.0, -1.0, 4.0];
+        let history = history(&raw);
+
+        // (2)/1, (2+8)/2, (2+8+2)/3, (8+2+8)/3, (2+8+5)/3, (8+5-1)/3, (5-1+4)/3
+        assert_close(
+            &history.smoothed(3),
+            &[2.0, 5.0, 4.0, 6.0, 5.0, 4.0, 8.0 / 3.0],
+        );
+        // Smoothing leaves the raw series untouched
+        assert_eq!(history.rewards(), raw);
+
+        // A window past the end is a running mean; 0 and 1 are the raw series
+        assert_close(&history.smoothed(100)[..3], &[2.0, 5.0, 4.0]);
+        assert_eq!(history.smoothed(1), raw);
+        assert_eq!(history.smoothed(0), raw);
+        assert!(RewardHistory::new().smoothed(5).is_empty());
+    }
+
+    #[test]
+    fn test_ema_weights_recent_rewards() {
+        // window 3 -> alpha 0.5
+        let history = history(&[4.0, 0.0, 2.0, 6.0]);
+        assert_close(&history.ema(3), &[4.0, 2.0, 2.0, 4.0]);
+        assert_eq!(history.smooth(Smoothing::Ema, 3), history.ema(3));
+    }
+
+    #[test]
+    fn test_histogram_separates_bimodal_returns() {
+        // Half the episodes fail near -10, half succeed near +10
+        let mut returns = Vec::new();
+        for i in 0..20 {
+            let jitter = (i % 5) as f32 * 0.2;
+            returns.push(-10.0 + jitter);
+            returns.push(10.0 - jitter);
+        }
+        let history = history(&returns);
+
+        let bins = history.histogram(10);
+        assert_eq!(bins.len(), 10);
+        assert_eq!(bins[0].low, -10.0);
+        assert_eq!(bins[9].high, 10.0);
+        let populated: Vec<usize> = bins
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.count > 0)
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(populated, vec![0, 9]);
+        assert_eq!(bins[0].count, 20);
+        assert_eq!(bins[9].count, 20);
+        // The mean sits in an empty bucket
+        let mean = returns.iter().sum::<f32>() / returns.len() as f32;
+        assert_eq!(bins[((mean + 10.0) / 2.0) as usize].count, 0);
+
+        assert_eq!(histogram(&[3.0, 3.0, f32::NAN], 4).len(), 1);
+        assert_eq!(histogram(&[3.0, 3.0], 4)[0].count, 2);
+        assert!(histogram(&[], 4).is_empty());
+        assert_eq!(histogram(&[0.0, 1.0], 0).len(), 1);
+    }
+
+    #[test]
+    fn test_record_replaces_and_caps() {
+        let mut history = RewardHistory::new();
+        history.record(1, 1.0);
+        history.record(0, 0.5);
+        history.record(1, 2.0);
+        assert_eq!(history.rewards(), vec![0.5, 2.0]);
+        history.record(4, 4.0);
+        history.record(3, 3.0);
+        let episodes: Vec<usize> = history.points().iter().map(|p| p.episode).collect();
+        assert_eq!(episodes, vec![0, 1, 3, 4]);
+        assert!(history.contains(3) && !history.contains(2));
+
+        for episode in 0..MAX_POINTS + 5 {
+            history.record(episode, episode as f32);
+        }
+        assert_eq!(history.len(), MAX_POINTS);
+        assert_eq!(history.points()[0].episode, 5);
+    }
+}
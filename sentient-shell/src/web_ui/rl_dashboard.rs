@@ -8,28 +8,24 @@ use warp::{Filter, Rejection, Reply};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use chrono::{DateTime, Utc};
+use sentient_rl_stats::MAX_BUCKETS;
 
-use crate::rl_training::{get_training_stats, start_training, stop_training, RLTrainingConfig};
-use crate::policy_injector::{get_injector_stats, start_policy_injector, stop_policy_injector};
+use crate::rl_training::{get_training_stats, start_training, stop_training, RLTrainingConfig, TrainingStats};
+use crate::policy_injector::{get_injector_stats, review_goal, start_policy_injector, stop_policy_injector};
+use super::reward_chart;
+use super::reward_history::{RewardHistory, Smoothing, DEFAULT_BUCKETS};
 
 /// RL Dashboard state
 #[derive(Debug, Clone)]
 pub struct RLDashboardState {
     /// Recent reward history for graph
-    reward_history: Arc<RwLock<Vec<RewardPoint>>>,
+    reward_history: Arc<RwLock<RewardHistory>>,
     /// Training configuration
     training_config: Arc<RwLock<Option<RLTrainingConfig>>>,
     /// Policy checkpoints
     policy_checkpoints: Arc<RwLock<Vec<PolicyCheckpoint>>>,
 }
 
-#[derive(Debug, Clone, Serialize)]
-struct RewardPoint {
-    episode: usize,
-    reward: f32,
-    timestamp: DateTime<Utc>,
-}
-
 #[derive(Debug, Clone, Serialize)]
 struct PolicyCheckpoint {
     id: String,
@@ -41,38 +37,32 @@ struct PolicyCheckpoint {
 impl RLDashboardState {
     pub fn new() -> Self {
         Self {
-            reward_history: Arc::new(RwLock::new(Vec::new())),
+            reward_history: Arc::new(RwLock::new(RewardHistory::new())),
             training_config: Arc::new(RwLock::new(None)),
             policy_checkpoints: Arc::new(RwLock::new(Vec::new())),
         }
     }
     
-    /// Update reward history from training stats
+    /// Update reward history from the live training session
     pub async fn update_rewards(&self) {
         if let Some(stats) = get_training_stats().await {
-            let mut history = self.reward_history.write().await;
-            
-            // Add new rewards
-            for (i, &reward) in stats.recent_rewards.iter().enumerate() {
-                let episode = stats.current_episode.saturating_sub(stats.recent_rewards.len() - i - 1);
-                
-                // Check if we already have this episode
-                if !history.iter().any(|p| p.episode == episode) {
-                    history.push(RewardPoint {
-                        episode,
-                        reward,
-                        timestamp: Utc::now(),
-                    });
-                }
-            }
-            
-            // Keep only last 1000 points
-            if history.len() > 1000 {
-                history.drain(0..history.len() - 1000);
-            }
+            self.ingest_rewards(&stats).await;
+        }
+    }
+    
+    /// Record the finished episodes reported in `stats`
+    async fn ingest_rewards(&self, stats: &TrainingStats) {
+        let mut history = self.reward_history.write().await;
+        for &(episode, reward) in &stats.recent_episodes {
+            history.record(episode, reward);
         }
     }
     
+    /// Record one episode's reward, replacing any earlier value for it
+    pub async fn record_reward(&self, episode: usize, reward: f32) {
+        self.reward_history.write().await.record(episode, reward);
+    }
+    
     /// Load policy checkpoints
     pub async fn load_checkpoints(&self) -> Result<()> {
         use tokio::fs;
@@ -122,11 +112,14 @@ pub fn rl_routes(state: Arc<RLDashboardState>) -> impl Filter<Extract = impl Rep
     let rl_api = warp::path("api").and(warp::path("rl")).and(
         get_status(state.clone())
             .or(get_rewards(state.clone()))
+            .or(get_rewards_svg(state.clone()))
+            .or(get_returns_histogram(state.clone()))
             .or(get_checkpoints(state.clone()))
             .or(start_training_route(state.clone()))
             .or(stop_training_route(state.clone()))
             .or(start_injector_route(state.clone()))
             .or(stop_injector_route(state.clone()))
+            .or(review_goal_route())
     );
     
     rl_page.or(rl_api)
@@ -153,6 +146,7 @@ async fn handle_get_status(state: Arc<RLDashboardState>) -> Result<impl Reply, R
             "current_episode": s.current_episode,
             "total_episodes": s.total_episodes,
             "best_reward": s.best_reward,
+            "exploration_rate": s.exploration_rate,
         })),
         "injector": injector_stats.map(|s| json!({
             "is_running": s.is_running,
@@ -166,19 +160,99 @@ async fn handle_get_status(state: Arc<RLDashboardState>) -> Result<impl Reply, R
     Ok(warp::reply::json(&response))
 }
 
+#[derive(Debug, Deserialize)]
+struct RewardsQuery {
+    /// Smoothing window in episodes; the raw series is always returned too
+    smooth: Option<usize>,
+    #[serde(default)]
+    method: Smoothing,
+}
+
 /// Get reward history
 fn get_rewards(state: Arc<RLDashboardState>) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     warp::path!("rewards")
         .and(warp::get())
+        .and(warp::query::<RewardsQuery>())
         .and(with_state(state))
         .and_then(handle_get_rewards)
 }
 
-async fn handle_get_rewards(state: Arc<RLDashboardState>) -> Result<impl Reply, Rejection> {
+async fn handle_get_rewards(
+    query: RewardsQuery,
+    state: Arc<RLDashboardState>,
+) -> Result<impl Reply, Rejection> {
+    state.update_rewards().await;
+    let history = state.reward_history.read().await;
+    
+    let mut response = json!({
+        "rewards": history.points(),
+        "count": history.len(),
+    });
+    if let Some(window) = query.smooth {
+        response["smoothed"] = json!({
+            "method": query.method,
+            "window": window,
+            "values": history.smooth(query.method, window),
+        });
+    }
+    
+    Ok(warp::reply::json(&response))
+}
+
+#[derive(Debug, Deserialize)]
+struct RewardChartQuery {
+    /// Moving-average window in episodes
+    window: Option<usize>,
+}
+
+/// Get reward history as an SVG chart
+fn get_rewards_svg(state: Arc<RLDashboardState>) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("rewards.svg")
+        .and(warp::get())
+        .and(warp::query::<RewardChartQuery>())
+        .and(with_state(state))
+        .and_then(handle_get_rewards_svg)
+}
+
+async fn handle_get_rewards_svg(
+    query: RewardChartQuery,
+    state: Arc<RLDashboardState>,
+) -> Result<impl Reply, Rejection> {
+    state.update_rewards().await;
+    let history = state.reward_history.read().await;
+    
+    let rewards = history.rewards();
+    let first_episode = history.points().first().map(|p| p.episode).unwrap_or(0);
+    let window = query.window.unwrap_or(reward_chart::DEFAULT_WINDOW);
+    let svg = reward_chart::render_svg(&rewards, first_episode, window);
+    
+    Ok(warp::reply::with_header(svg, "content-type", reward_chart::CONTENT_TYPE))
+}
+
+#[derive(Debug, Deserialize)]
+struct HistogramQuery {
+    /// Number of equal-width buckets, clamped to `1..=MAX_BUCKETS`
+    buckets: Option<usize>,
+}
+
+/// Get episode returns bucketed into a histogram
+fn get_returns_histogram(state: Arc<RLDashboardState>) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("returns" / "histogram")
+        .and(warp::get())
+        .and(warp::query::<HistogramQuery>())
+        .and(with_state(state))
+        .and_then(handle_get_returns_histogram)
+}
+
+async fn handle_get_returns_histogram(
+    query: HistogramQuery,
+    state: Arc<RLDashboardState>,
+) -> Result<impl Reply, Rejection> {
+    state.update_rewards().await;
     let history = state.reward_history.read().await;
     
     let response = json!({
-        "rewards": history.clone(),
+        "buckets": history.histogram(query.buckets.unwrap_or(DEFAULT_BUCKETS).clamp(1, MAX_BUCKETS)),
         "count": history.len(),
     });
     
@@ -296,6 +370,32 @@ async fn handle_stop_injector() -> Result<impl Reply, Rejection> {
     }
 }
 
+/// Approve or reject an injected goal: `goals/<goal_id>/approve|reject`
+fn review_goal_route() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    warp::path!("goals" / String / String)
+        .and(warp::post())
+        .and_then(handle_review_goal)
+}
+
+async fn handle_review_goal(goal_id: String, verdict: String) -> Result<impl Reply, Rejection> {
+    let approved = match verdict.as_str() {
+        "approve" => true,
+        "reject" => false,
+        _ => return Err(warp::reject::not_found()),
+    };
+    
+    match review_goal(&goal_id, approved).await {
+        Ok(_) => Ok(warp::reply::json(&json!({
+            "status": if approved { "approved" } else { "rejected" },
+            "goal_id": goal_id,
+        }))),
+        Err(e) => Ok(warp::reply::json(&json!({
+            "status": "error",
+            "message": format!("Failed to record verdict: {}", e)
+        }))),
+    }
+}
+
 /// Helper to pass state to handlers
 fn with_state(state: Arc<RLDashboardState>) -> impl Filter<Extract = (Arc<RLDashboardState>,), Error = std::convert::Infallible> + Clone {
     warp::any().map(move || state.clone())
@@ -552,6 +652,10 @@ const RL_DASHBOARD_HTML: &str = r#"
                         <div class="status-label">Best Reward</div>
                         <div class="status-value" id="bestReward">-</div>
                     </div>
+                    <div class="status-item">
+                        <div class="status-label">Exploration Rate</div>
+                        <div class="status-value" id="explorationRate">-</div>
+                    </div>
                 </div>
             </div>
         </div>
@@ -651,6 +755,8 @@ const RL_DASHBOARD_HTML: &str = r#"
                         `${data.training.current_episode} / ${data.training.total_episodes}`;
                     document.getElementById('bestReward').textContent = 
                         data.training.best_reward.toFixed(3);
+                    document.getElementById('explorationRate').textContent = 
+                        data.training.exploration_rate == null ? '-' : data.training.exploration_rate.toFixed(3);
                     
                     // Update buttons
                     document.getElementById('startTraining').disabled = data.training.is_running;
@@ -792,7 +898,7 @@ const RL_DASHBOARD_HTML: &str = r#"
                 checkpoint_interval: 100,
                 log_interval: 10,
                 reward_goal_threshold: 0.8,
-                observation_dim: 64,
+                observation_dim: 10,
                 action_dim: 10
             };
             
@@ -876,4 +982,161 @@ const RL_DASHBOARD_HTML: &str = r#"
     </script>
 </body>
 </html>
-"#;
\ No newline at end of file
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn polyline_point_counts(svg: &str) -> Vec<usize> {
+        svg.split("<polyline")
+            .skip(1)
+            .map(|rest| {
+                let points = rest.split("points=\"").nth(1).unwrap();
+                points[..points.find('"').unwrap()].split_whitespace().count()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_rewards_svg_draws_history_and_moving_average() {
+        let state = Arc::new(RLDashboardState::new());
+        for episode in 0..25 {
+            state.record_reward(episode, (episode % 5) as f32).await;
+        }
+        let filter = rl_routes(state);
+        
+        let response = warp::test::request()
+            .path("/api/rl/rewards.svg?window=5")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], reward_chart::CONTENT_TYPE);
+        
+        let svg = std::str::from_utf8(response.body()).unwrap();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""), "{}", svg);
+        assert!(svg.trim_end().ends_with("</svg>"));
+        assert_eq!(svg.matches("<svg").count(), 1);
+        assert_eq!(polyline_point_counts(svg), vec![25, 25]);
+        assert!(svg.contains("5-episode moving average"));
+        
+        // Without a window the default applies; window=1 draws the raw line only
+        let response = warp::test::request().path("/api/rl/rewards.svg").reply(&filter).await;
+        assert_eq!(response.status(), 200);
+        let response = warp::test::request()
+            .path("/api/rl/rewards.svg?window=1")
+            .reply(&filter)
+            .await;
+        let svg = std::str::from_utf8(response.body()).unwrap();
+        assert_eq!(polyline_point_counts(svg), vec![25]);
+    }
+
+    #[tokio::test]
+    async fn test_rewards_svg_follows_training_stats() {
+        let state = Arc::new(RLDashboardState::new());
+        let stats = |episodes: std::ops::Range<usize>| TrainingStats {
+            current_episode: episodes.end,
+            total_episodes: 100,
+            best_reward: 0.0,
+            recent_rewards: episodes.clone().rev().map(|e| e as f32).collect(),
+            recent_episodes: episodes.map(|e| (e, e as f32)).collect(),
+            is_running: true,
+            total_steps: 0,
+            steps_per_sec: 0.0,
+            converged_at: None,
+            exploration_rate: None,
+        };
+        state.ingest_rewards(&stats(0..3)).await;
+        state.ingest_rewards(&stats(1..6)).await;
+        
+        let history = state.reward_history.read().await;
+        assert_eq!(history.rewards(), vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+        drop(history);
+        
+        let filter = rl_routes(state);
+        let response = warp::test::request()
+            .path("/api/rl/rewards.svg?window=2")
+            .reply(&filter)
+            .await;
+        let svg = std::str::from_utf8(response.body()).unwrap();
+        assert_eq!(polyline_point_counts(svg), vec![6, 6]);
+    }
+
+    #[tokio::test]
+    async fn test_rewards_smooth_param_adds_smoothed_series() {
+        let state = Arc::new(RLDashboardState::new());
+        for (episode, reward) in [2.0, 8.0, 2.0, 8.0].into_iter().enumerate() {
+            state.record_reward(episode, reward).await;
+        }
+        let filter = rl_routes(state);
+        
+        let response = warp::test::request().path("/api/rl/rewards").reply(&filter).await;
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["count"], 4);
+        assert!(body.get("smoothed").is_none());
+        
+        let response = warp::test::request()
+            .path("/api/rl/rewards?smooth=2")
+            .reply(&filter)
+            .await;
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["smoothed"]["method"], "ma");
+        assert_eq!(body["smoothed"]["values"], json!([2.0, 5.0, 5.0, 5.0]));
+        assert_eq!(body["rewards"][1]["reward"], 8.0);
+        
+        let response = warp::test::request()
+            .path("/api/rl/rewards?smooth=3&method=ema")
+            .reply(&filter)
+            .await;
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["smoothed"]["values"], json!([2.0, 5.0, 3.5, 5.75]));
+        
+        let response = warp::test::request()
+            .path("/api/rl/rewards?smooth=2&method=median")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 400);
+    }
+    
+    #[tokio::test]
+    async fn test_returns_histogram_endpoint() {
+        let state = Arc::new(RLDashboardState::new());
+        for episode in 0..10 {
+            let reward = if episode % 2 == 0 { -5.0 } else { 5.0 };
+            state.record_reward(episode, reward).await;
+        }
+        let filter = rl_routes(state);
+        
+        let response = warp::test::request()
+            .path("/api/rl/returns/histogram?buckets=4")
+            .reply(&filter)
+            .await;
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["count"], 10);
+        let counts: Vec<u64> = body["buckets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| b["count"].as_u64().unwrap())
+            .collect();
+        assert_eq!(counts, vec![5, 0, 0, 5]);
+        assert_eq!(body["buckets"][0]["low"], -5.0);
+        
+        let response = warp::test::request()
+            .path("/api/rl/returns/histogram")
+            .reply(&filter)
+            .await;
+        let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["buckets"].as_array().unwrap().len(), DEFAULT_BUCKETS);
+        
+        for (requested, expected) in [(0, 1), (1_000_000, MAX_BUCKETS)] {
+            let response = warp::test::request()
+                .path(&format!("/api/rl/returns/histogram?buckets={}", requested))
+                .reply(&filter)
+                .await;
+            let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+            assert_eq!(body["buckets"].as_array().unwrap().len(), expected, "{}", requested);
+        }
+    }
+}
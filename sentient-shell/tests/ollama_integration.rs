@@ -232,3 +232,50 @@ fn test_error_handling() {
     let error = response.err().unwrap();
     println!("Expected error: {}", error);
 }
+
+#[test]
+fn test_slow_endpoint_times_out_within_bound() {
+    use sentient_shell::ai::{AiClientConfig, AiClientError};
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+
+    let mut server = mockito::Server::new();
+    let _slow = server
+        .mock("GET", "/api/tags")
+        .with_status(200)
+        .with_chunked_body(|w| {
+            std::thread::sleep(Duration::from_secs(3));
+            w.write_all(br#"{"models": []}"#)
+        })
+        .create();
+
+    let config = AiClientConfig {
+        request_timeout: Duration::from_millis(300),
+        ..AiClientConfig::default()
+    };
+    let client = AiClient::with_config(server.url(), server.url(), config);
+
+    let start = Instant::now();
+    let err = client.list_ollama_models().unwrap_err();
+    let elapsed = start.elapsed();
+
+    assert!(elapsed < Duration::from_secs(2), "took {:?}", elapsed);
+    let transport = err
+        .downcast_ref::<AiClientError>()
+        .expect("transport error is classified");
+    assert!(transport.is_timeout(), "expected timeout, got {}", transport);
+}
+
+#[test]
+fn test_refused_connection_is_not_a_timeout() {
+    use sentient_shell::ai::AiClientError;
+
+    // Nothing listens on port 1
+    let client = AiClient::new("http://127.0.0.1:1".to_string(), "http://127.0.0.1:1".to_string());
+    let err = client.list_ollama_models().unwrap_err();
+
+    match err.downcast_ref::<AiClientError>() {
+        Some(AiClientError::ConnectionFailed { .. }) => {}
+        other => panic!("expected connection failure, got {:?}", other),
+    }
+}
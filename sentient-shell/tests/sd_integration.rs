@@ -228,3 +228,93 @@ fn test_sd_performance() {
         }
     }
 }
+
+#[test]
+fn test_generate_image_reports_progress_with_mock_backend() {
+    use base64::Engine;
+    use sentient_shell::ai::ImageGenerationOptions;
+    use std::io::Write;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+
+    let mut server = mockito::Server::new();
+    let image_bytes = b"\x89PNG\r\n\x1a\nmock-image".to_vec();
+    let body = serde_json::json!({
+        "images": [base64::engine::general_purpose::STANDARD.encode(&image_bytes)]
+    })
+    .to_string();
+
+    // Slow txt2img so the client has time to poll progress
+    let _txt2img = server
+        .mock("POST", "/sdapi/v1/txt2img")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_chunked_body(move |w| {
+            std::thread::sleep(Duration::from_millis(300));
+            w.write_all(body.as_bytes())
+        })
+        .create();
+    let _progress = server
+        .mock("GET", mockito::Matcher::Regex("^/sdapi/v1/progress".to_string()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"progress": 0.5, "eta_relative": 2.0}"#)
+        .create();
+
+    let output_dir = tempfile::tempdir().unwrap();
+    let options = ImageGenerationOptions {
+        output_dir: output_dir.path().to_path_buf(),
+        poll_interval: Duration::from_millis(50),
+    };
+
+    let mut client = AiClient::new("http://127.0.0.1:1".to_string(), server.url());
+    let cancel = AtomicBool::new(false);
+    let mut seen = Vec::new();
+    let info = client
+        .generate_image_with_progress("a red square", &options, &cancel, |p| seen.push(p.progress))
+        .expect("generation succeeds");
+
+    assert!(seen.contains(&0.5), "progress callback fired: {:?}", seen);
+    assert_eq!(seen.last(), Some(&1.0));
+
+    let path = info.path.expect("image saved to disk");
+    assert!(path.starts_with(output_dir.path()));
+    assert_eq!(std::fs::read(&path).unwrap(), image_bytes);
+}
+
+#[test]
+fn test_generate_image_cancel_calls_interrupt() {
+    use sentient_shell::ai::ImageGenerationOptions;
+    use std::io::Write;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+
+    let mut server = mockito::Server::new();
+    let _txt2img = server
+        .mock("POST", "/sdapi/v1/txt2img")
+        .with_status(200)
+        .with_chunked_body(|w| {
+            std::thread::sleep(Duration::from_millis(500));
+            w.write_all(br#"{"images": []}"#)
+        })
+        .create();
+    let interrupt = server
+        .mock("POST", "/sdapi/v1/interrupt")
+        .with_status(200)
+        .expect(1)
+        .create();
+
+    let options = ImageGenerationOptions {
+        output_dir: tempfile::tempdir().unwrap().path().to_path_buf(),
+        poll_interval: Duration::from_millis(20),
+    };
+
+    let mut client = AiClient::new("http://127.0.0.1:1".to_string(), server.url());
+    let cancel = AtomicBool::new(true);
+    let err = client
+        .generate_image_with_progress("cancel me", &options, &cancel, |_| {})
+        .unwrap_err();
+
+    assert!(err.to_string().contains("cancelled"));
+    interrupt.assert();
+}
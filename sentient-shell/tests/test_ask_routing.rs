@@ -0,0 +1,99 @@
+//! Tests for explicit model/capability selection in `ask`
+
+use anyhow::Result;
+use sentient_shell::ai_router::registry::get_model_registry;
+use sentient_shell::ai_router::{
+    InferenceRequest, InferenceResponse, ModelCapability, ModelEndpoint, ModelProvider,
+    MODEL_OVERRIDE_KEY,
+};
+use sentient_shell::commands_functions::{ask_explicit, parse_ask_args};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Provider that echoes back the model it was asked to use
+struct EchoProvider;
+
+impl ModelProvider for EchoProvider {
+    fn name(&self) -> &str {
+        "echo"
+    }
+
+    fn is_available(&self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn infer(&self, request: &InferenceRequest) -> Result<InferenceResponse> {
+        let model = request
+            .metadata
+            .get(MODEL_OVERRIDE_KEY)
+            .and_then(|v| v.as_str())
+            .unwrap_or("default")
+            .to_string();
+
+        Ok(InferenceResponse {
+            text: Some(format!("{} answered: {}", model, request.prompt)),
+            embedding: None,
+            metadata: HashMap::new(),
+            model_used: model,
+            tokens_used: None,
+            duration_ms: 0,
+        })
+    }
+
+    fn list_models(&self) -> Result<Vec<ModelEndpoint>> {
+        Ok(vec![ModelEndpoint {
+            name: "echo/foo".to_string(),
+            provider: "echo".to_string(),
+            model_id: "foo".to_string(),
+            endpoint_url: "mem://echo".to_string(),
+            capabilities: vec![ModelCapability::CodeGeneration],
+            max_tokens: None,
+            context_window: None,
+            is_active: true,
+            priority: 1,
+        }])
+    }
+}
+
+fn register_echo() {
+    get_model_registry()
+        .register_provider(Arc::new(EchoProvider))
+        .unwrap();
+}
+
+#[test]
+fn test_parse_ask_flags() {
+    let options = parse_ask_args(&["--model", "foo", "--capability", "code", "sort", "a", "list"]).unwrap();
+    assert_eq!(options.model.as_deref(), Some("foo"));
+    assert_eq!(options.capability, Some(ModelCapability::CodeGeneration));
+    assert_eq!(options.prompt, "sort a list");
+    assert!(options.is_explicit());
+
+    let plain = parse_ask_args(&["hello", "world"]).unwrap();
+    assert!(!plain.is_explicit());
+
+    assert!(parse_ask_args(&["--capability", "telepathy", "hi"]).is_err());
+    assert!(parse_ask_args(&["--model"]).is_err());
+}
+
+#[test]
+fn test_ask_model_targets_model() {
+    register_echo();
+
+    let options = parse_ask_args(&["--model", "foo", "write", "code"]).unwrap();
+    let response = ask_explicit(&options).unwrap();
+
+    assert_eq!(response.model_used, "echo:foo");
+    assert_eq!(response.text.as_deref(), Some("foo answered: write code"));
+}
+
+#[test]
+fn test_ask_unknown_model_errors_clearly() {
+    register_echo();
+
+    let options = parse_ask_args(&["--model", "does-not-exist", "hi"]).unwrap();
+    let err = ask_explicit(&options).unwrap_err().to_string();
+
+    assert!(err.contains("does-not-exist"));
+    assert!(err.contains("not available"));
+}
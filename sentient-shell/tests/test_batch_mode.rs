@@ -0,0 +1,89 @@
+//! Tests for non-interactive batch mode
+
+use sentient_shell::batch::{run_lines, run_script};
+use sentient_shell::ShellState;
+use std::io::Write;
+
+fn offline_shell() -> ShellState {
+    // Point at unroutable endpoints so `status` fails fast
+    std::env::set_var("OLLAMA_URL", "http://127.0.0.1:1");
+    std::env::set_var("SD_URL", "http://127.0.0.1:1");
+    ShellState::new()
+}
+
+#[test]
+fn test_script_help_status_succeeds() {
+    let mut script = tempfile::NamedTempFile::new().unwrap();
+    writeln!(script, "# provisioning smoke test").unwrap();
+    writeln!(script, "help").unwrap();
+    writeln!(script).unwrap();
+    writeln!(script, "status").unwrap();
+
+    let mut shell = offline_shell();
+    let report = run_script(&mut shell, script.path(), false).unwrap();
+
+    assert_eq!(report.executed, 2);
+    assert!(report.success());
+    assert_eq!(report.exit_code(), 0);
+}
+
+#[test]
+fn test_script_stops_on_first_error() {
+    let mut shell = offline_shell();
+    let report = run_lines(&mut shell, ["help", "tool info", "status"], false);
+
+    assert_eq!(report.executed, 2);
+    assert_eq!(report.failures.len(), 1);
+    assert_eq!(report.failures[0].0, 2);
+    assert_eq!(report.exit_code(), 1);
+}
+
+#[test]
+fn test_reported_errors_fail_the_script() {
+    // Unknown commands, unknown or incomplete subcommands and failing
+    // subcommands count as failures
+    for failing in [
+        "no-such-command",
+        "llm model info no_such_model",
+        "llm no-such-subcommand",
+        "rag no-such-subcommand",
+        "tool no-such-subcommand",
+        "pkg no-such-subcommand",
+        "pkg install",
+        "ai query",
+        "ask",
+    ] {
+        let mut shell = offline_shell();
+        let report = run_lines(&mut shell, ["help", failing, "help"], false);
+
+        assert_eq!(report.executed, 2, "{}", failing);
+        assert_eq!(report.failures.len(), 1, "{}", failing);
+        assert_eq!(report.exit_code(), 1, "{}", failing);
+    }
+}
+
+#[test]
+fn test_script_keep_going() {
+    let mut shell = offline_shell();
+    let report = run_lines(&mut shell, ["tool info", "help", "tool call"], true);
+
+    assert_eq!(report.executed, 3);
+    assert_eq!(report.failures.len(), 2);
+    assert_eq!(report.exit_code(), 1);
+}
+
+#[test]
+fn test_script_exit_stops_execution() {
+    let mut shell = offline_shell();
+    let report = run_lines(&mut shell, ["help", "exit", "tool info"], false);
+
+    assert!(report.exited_early);
+    assert_eq!(report.executed, 2);
+    assert!(report.success());
+}
+
+#[test]
+fn test_missing_script_is_error() {
+    let mut shell = offline_shell();
+    assert!(run_script(&mut shell, std::path::Path::new("/nonexistent/script.sh"), false).is_err());
+}
@@ -0,0 +1,66 @@
+//! Tests for the shell's remote -> local fallback chain
+
+use anyhow::Result;
+use sentient_shell::ai::AiClient;
+use sentient_shell::fallback::{FallbackChain, TextBackend};
+
+struct StubBackend {
+    name: &'static str,
+    reply: Option<&'static str>,
+}
+
+impl TextBackend for StubBackend {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn generate(&mut self, prompt: &str) -> Result<String> {
+        match self.reply {
+            Some(reply) => Ok(format!("{}: {}", reply, prompt)),
+            None => anyhow::bail!("{} offline", self.name),
+        }
+    }
+}
+
+fn unreachable_remote() -> AiClient {
+    AiClient::new("http://127.0.0.1:1".to_string(), "http://127.0.0.1:1".to_string())
+}
+
+#[test]
+fn test_remote_failure_falls_back_to_local() {
+    let mut chain = FallbackChain::new(vec![Box::new(StubBackend {
+        name: "local",
+        reply: Some("local says"),
+    })]);
+
+    let answer = chain.ask(&mut unreachable_remote(), "hello").unwrap();
+
+    assert_eq!(answer.backend, "local");
+    assert_eq!(answer.text, "local says: hello");
+    assert_eq!(answer.failures.len(), 1);
+    assert_eq!(answer.failures[0].0, "ollama");
+}
+
+#[test]
+fn test_chain_skips_failing_backends_in_order() {
+    let mut chain = FallbackChain::new(vec![
+        Box::new(StubBackend { name: "local", reply: None }),
+        Box::new(StubBackend { name: "boot", reply: Some("boot says") }),
+    ]);
+
+    let answer = chain.ask(&mut unreachable_remote(), "status").unwrap();
+
+    assert_eq!(answer.backend, "boot");
+    let tried: Vec<&str> = answer.failures.iter().map(|(n, _)| n.as_str()).collect();
+    assert_eq!(tried, vec!["ollama", "local"]);
+}
+
+#[test]
+fn test_exhausted_chain_reports_every_backend() {
+    let mut chain = FallbackChain::new(vec![Box::new(StubBackend { name: "local", reply: None })]);
+
+    let err = chain.ask(&mut unreachable_remote(), "hi").unwrap_err().to_string();
+
+    assert!(err.contains("ollama"));
+    assert!(err.contains("local offline"));
+}
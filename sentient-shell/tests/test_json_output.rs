@@ -0,0 +1,82 @@
+//! Tests for structured command output
+
+use sentient_shell::ai::AiClient;
+use sentient_shell::commands_functions::{collect_models, collect_status};
+use sentient_shell::output::{CommandOutput, Connectivity, OutputFormat, StatusReport};
+use sentient_shell::service::{ServiceInfo, ServiceStatus};
+
+fn offline_client() -> AiClient {
+    AiClient::new("http://127.0.0.1:1".to_string(), "http://127.0.0.1:1".to_string())
+}
+
+fn sample_status() -> StatusReport {
+    StatusReport {
+        shell_version: "1.0.0".to_string(),
+        ollama_url: "http://ollama:11434".to_string(),
+        sd_url: "http://sd:7860".to_string(),
+        ollama_status: Connectivity::Connected,
+        sd_status: Connectivity::Unreachable,
+        preferred_model: Some("deepseek-v2".to_string()),
+        preferred_model_error: None,
+    }
+}
+
+#[test]
+fn test_status_json_has_expected_keys() {
+    let status = collect_status(&offline_client());
+    let rendered = OutputFormat::Json.render(&CommandOutput::Status(status));
+
+    let value: serde_json::Value = serde_json::from_str(&rendered).expect("status --json is valid JSON");
+    for key in ["shell_version", "ollama_url", "sd_url", "ollama_status", "sd_status", "preferred_model"] {
+        assert!(value.get(key).is_some(), "missing key {}", key);
+    }
+    assert_eq!(value["ollama_url"], "http://127.0.0.1:1");
+    assert_eq!(value["ollama_status"]["state"], "unreachable");
+}
+
+#[test]
+fn test_models_json_is_valid() {
+    let models = collect_models(&offline_client());
+    let rendered = OutputFormat::Json.render(&CommandOutput::Models(models));
+
+    let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+    assert!(value["ollama_models"].is_array());
+    assert!(value["sd_models"].is_array());
+    assert!(value["ollama_error"].is_string());
+}
+
+#[test]
+fn test_human_format_is_default_and_unchanged() {
+    assert_eq!(OutputFormat::default(), OutputFormat::Human);
+
+    let rendered = OutputFormat::Human.render(&CommandOutput::Status(sample_status()));
+    let expected = "System Status:\n  \
+        Shell Version: 1.0.0\n  \
+        Ollama Server: http://ollama:11434\n  \
+        SD Server: http://sd:7860\n  \
+        Ollama Status: Connected ✓\n  \
+        SD Status: Not reachable ✗\n  \
+        Preferred Model: deepseek-v2\n";
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_service_list_json() {
+    let services = vec![ServiceInfo {
+        name: "activity-loop".to_string(),
+        status: ServiceStatus::Running,
+        pid: Some(42),
+        started_at: None,
+        restart_count: 1,
+        last_exit_code: None,
+    }];
+
+    let rendered = OutputFormat::Json.render(&CommandOutput::Services(services.clone()));
+    let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+    assert_eq!(value[0]["name"], "activity-loop");
+    assert_eq!(value[0]["status"], "Running");
+
+    let human = OutputFormat::Human.render(&CommandOutput::Services(services));
+    assert!(human.starts_with("Services:\n"));
+    assert!(human.contains("activity-loop"));
+}
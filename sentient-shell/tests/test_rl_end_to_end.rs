@@ -0,0 +1,106 @@
+//! End-to-end test of the RL stack: CartPole from the `EnvRegistry`, a PPO
+//! agent sized from the environment's spaces and trained by the HPO
+//! `Trainer`, and a checkpoint round trip through `PolicyStorage`.
+
+use chrono::Utc;
+use sentient_memory::rl_store::{PolicyCheckpoint, PolicyMetadata, PolicyStorage};
+use sentient_rl_agent::hpo::HyperParams;
+use sentient_rl_agent::ppo_full::{CheckpointFormat, PPOAgentFull};
+use sentient_rl_agent::{PPOConfig, PPOTrainer, Trainer};
+use sentient_rl_core::{AgentMode, Environment, EnvironmentConfig, Policy, SpaceSignature};
+use sentient_rl_env::{make_env, registry::BoxedEnv};
+use tempfile::TempDir;
+use uuid::Uuid;
+
+const ROLLOUTS: usize = 5;
+const ROLLOUT_STEPS: usize = 64;
+
+fn cartpole(seed: u64) -> anyhow::Result<BoxedEnv> {
+    Ok(make_env(
+        "cartpole",
+        EnvironmentConfig {
+            seed: Some(seed),
+            ..EnvironmentConfig::default()
+        },
+    )?)
+}
+
+#[tokio::test]
+async fn test_trained_agent_survives_checkpoint_round_trip() {
+    let mut env = cartpole(0).unwrap();
+    let obs_dim = env.observation_spec().dim;
+    let action_dim = env.action_spec().dim;
+
+    // The trainer runs its own runtime, so keep it off this one
+    let mut trainer = PPOTrainer::new(cartpole, obs_dim, action_dim);
+    trainer.iterations = ROLLOUTS;
+    trainer.rollout_steps = ROLLOUT_STEPS;
+    trainer.eval_episodes = 2;
+    let (score, agent) = tokio::task::spawn_blocking(move || {
+        let score = trainer.train_and_evaluate(&HyperParams::new(), 7).unwrap();
+        (score, trainer.take_agent().unwrap())
+    })
+    .await
+    .unwrap();
+    assert!(score > 0.0);
+    let total_steps = ROLLOUTS * ROLLOUT_STEPS;
+    assert_eq!(agent.total_timesteps().await, total_steps);
+
+    // Persist through the policy store
+    let dir = TempDir::new().unwrap();
+    let weights_path = dir.path().join("ppo.json");
+    agent.save_as(&weights_path, CheckpointFormat::Json).await.unwrap();
+
+    let storage = PolicyStorage::new(dir.path().join("policies"));
+    storage.init().await.unwrap();
+    let id = storage
+        .save_checkpoint(PolicyCheckpoint {
+            id: Uuid::new_v4(),
+            model_type: "ppo".to_string(),
+            parameters: tokio::fs::read(&weights_path).await.unwrap(),
+            metadata: PolicyMetadata {
+                episode: ROLLOUTS,
+                total_steps,
+                average_reward: score as f32,
+                best_reward: score as f32,
+                training_time_hours: 0.0,
+                hyperparameters: serde_json::to_value(PPOConfig::default()).unwrap(),
+            },
+            created_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+
+    // Reload into a fresh agent
+    let checkpoint = storage.load_checkpoint(id).await.unwrap();
+    assert_eq!(checkpoint.model_type, "ppo");
+    let restored_path = dir.path().join("restored.json");
+    tokio::fs::write(&restored_path, &checkpoint.parameters)
+        .await
+        .unwrap();
+
+    let restored = PPOAgentFull::new(PPOConfig::default(), obs_dim, action_dim)
+        .await
+        .unwrap();
+    restored
+        .load_as(&restored_path, CheckpointFormat::Json)
+        .await
+        .unwrap();
+    restored.set_mode(AgentMode::Eval);
+    assert_eq!(restored.total_timesteps().await, total_steps);
+    assert_eq!(restored.parameters().await.unwrap(), agent.parameters().await.unwrap());
+
+    // Same greedy action on every observation of a few episodes
+    let (mut obs, _) = env.reset().await.unwrap();
+    for _ in 0..50 {
+        let action = agent.act(&obs).await.unwrap();
+        assert_eq!(restored.act(&obs).await.unwrap(), action);
+
+        let step = env.step(action).await.unwrap();
+        obs = if step.done || step.truncated {
+            env.reset().await.unwrap().0
+        } else {
+            step.observation
+        };
+    }
+}
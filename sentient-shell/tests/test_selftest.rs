@@ -0,0 +1,99 @@
+//! Tests for the `selftest` command with stubbed components
+
+use anyhow::Result;
+use sentient_shell::output::{CommandOutput, OutputFormat};
+use sentient_shell::selftest::{parse_skip_args, CheckOutcome, SelfCheck, SelfTest};
+use std::cell::Cell;
+use std::rc::Rc;
+
+struct StubCheck {
+    component: &'static str,
+    error: Option<&'static str>,
+    runs: Rc<Cell<usize>>,
+}
+
+impl SelfCheck for StubCheck {
+    fn component(&self) -> &str {
+        self.component
+    }
+
+    fn run(&mut self) -> Result<String> {
+        self.runs.set(self.runs.get() + 1);
+        match self.error {
+            Some(error) => anyhow::bail!("{}", error),
+            None => Ok(format!("{} ok", self.component)),
+        }
+    }
+}
+
+fn stub(component: &'static str, error: Option<&'static str>, runs: &Rc<Cell<usize>>) -> Box<dyn SelfCheck> {
+    Box::new(StubCheck {
+        component,
+        error,
+        runs: Rc::clone(runs),
+    })
+}
+
+#[test]
+fn test_report_reflects_each_component() {
+    let runs = Rc::new(Cell::new(0));
+    let mut selftest = SelfTest::new(vec![
+        stub("router", None, &runs),
+        stub("rag", Some("index missing"), &runs),
+        stub("replay", None, &runs),
+        stub("checkpoint", None, &runs),
+    ]);
+
+    let report = selftest.run(&["checkpoint".to_string()]).unwrap();
+
+    assert_eq!(runs.get(), 3, "skipped checks must not run");
+    assert!(!report.passed);
+    assert_eq!(report.failures(), 1);
+    assert_eq!(report.get("router").unwrap().outcome, CheckOutcome::Passed("router ok".to_string()));
+    assert_eq!(report.get("rag").unwrap().outcome, CheckOutcome::Failed("index missing".to_string()));
+    assert_eq!(report.get("replay").unwrap().outcome, CheckOutcome::Passed("replay ok".to_string()));
+    assert_eq!(report.get("checkpoint").unwrap().outcome, CheckOutcome::Skipped);
+
+    let rendered = OutputFormat::Json.render(&CommandOutput::SelfTest(report));
+    let value: serde_json::Value = serde_json::from_str(&rendered).expect("selftest --json is valid JSON");
+    assert_eq!(value["passed"], false);
+    let checks = value["checks"].as_array().unwrap();
+    let outcomes: Vec<&str> = checks.iter().map(|c| c["outcome"].as_str().unwrap()).collect();
+    assert_eq!(outcomes, vec!["passed", "failed", "passed", "skipped"]);
+    assert_eq!(checks[1]["detail"], "index missing");
+    assert!(checks.iter().all(|c| c.get("duration_ms").is_some()));
+}
+
+#[test]
+fn test_skipping_the_failing_component_passes() {
+    let runs = Rc::new(Cell::new(0));
+    let mut selftest = SelfTest::new(vec![
+        stub("router", None, &runs),
+        stub("rag", Some("index missing"), &runs),
+    ]);
+
+    let skip = parse_skip_args(&["--skip", "rag"]).unwrap();
+    let report = selftest.run(&skip).unwrap();
+    assert!(report.passed);
+    assert_eq!(runs.get(), 1);
+
+    let rendered = OutputFormat::Human.render(&CommandOutput::SelfTest(report));
+    assert!(rendered.contains("router ok"), "{}", rendered);
+    assert!(rendered.contains("skipped"), "{}", rendered);
+}
+
+#[test]
+fn test_skip_arguments_are_validated() {
+    assert_eq!(
+        parse_skip_args(&["--skip", "rag,replay", "--skip", "router"]).unwrap(),
+        vec!["rag", "replay", "router"]
+    );
+    assert!(parse_skip_args(&["--skip"]).is_err());
+    assert!(parse_skip_args(&["--deep"]).is_err());
+
+    let runs = Rc::new(Cell::new(0));
+    let mut selftest = SelfTest::new(vec![stub("router", None, &runs)]);
+    let err = selftest.run(&["routr".to_string()]).unwrap_err().to_string();
+    assert!(err.contains("routr") && err.contains("router"), "{}", err);
+    assert_eq!(runs.get(), 0);
+}
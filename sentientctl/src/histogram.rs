@@ -0,0 +1,47 @@
+// Return histograms for `sentientctl rl reward-graph --histogram`
+// Binning lives in sentient-rl-stats, shared with the dashboard's
+// /api/rl/returns/histogram so both views agree
+
+use sentient_rl_stats::Bucket;
+
+/// Width of the longest bar in characters
+const BAR_WIDTH: usize = 40;
+
+/// One line per bin: its range, a bar scaled to the fullest bin, and the count
+pub fn render_bars(bins: &[Bucket]) -> Vec<String> {
+    let most = bins.iter().map(|b| b.count).max().unwrap_or(0).max(1);
+    bins.iter()
+        .map(|b| {
+            let len = (b.count * BAR_WIDTH).div_ceil(most);
+            format!(
+                "{:>9.2} .. {:<9.2} │{:<width$} {}",
+                b.low,
+                b.high,
+                "█".repeat(len),
+                b.count,
+                width = BAR_WIDTH
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sentient_rl_stats::histogram;
+
+    #[test]
+    fn test_bars_scale_to_the_fullest_bucket() {
+        let mut returns = Vec::new();
+        for i in 0..15 {
+            returns.push(-20.0 + i as f64 * 0.1);
+            returns.push(20.0 - i as f64 * 0.1);
+        }
+
+        let lines = render_bars(&histogram(&returns, 8));
+        assert_eq!(lines.len(), 8);
+        assert!(lines[0].contains(&"█".repeat(BAR_WIDTH)));
+        assert!(!lines[3].contains('█'));
+        assert!(lines[7].trim_end().ends_with("15"));
+    }
+}
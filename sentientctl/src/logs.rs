@@ -0,0 +1,385 @@
+// Log viewing for sentientctl
+// Reads the daily JSONL logs, filters them, and prints them for people or scripts
+
+use anyhow::{Result, Context};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// How `sentientctl logs` prints entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Decorated lines, newest first
+    Text,
+    /// A JSON array of the raw entries, oldest first; one entry per line
+    /// when following
+    Json,
+}
+
+/// Filters an entry must pass to be shown
+#[derive(Debug, Default)]
+pub struct LogFilter {
+    pub goal_id: Option<String>,
+    /// Substring of the entry's tool
+    pub tool: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub failed_only: bool,
+}
+
+impl LogFilter {
+    pub fn new(
+        goal_id: Option<String>,
+        tool: Option<String>,
+        since: Option<String>,
+        failed_only: bool,
+    ) -> Result<Self> {
+        let since = since
+            .map(|s| {
+                DateTime::parse_from_rfc3339(&s)
+                    .map(|t| t.with_timezone(&Utc))
+                    .with_context(|| format!("Invalid --since timestamp {:?} (expected RFC 3339)", s))
+            })
+            .transpose()?;
+        Ok(Self { goal_id, tool, since, failed_only })
+    }
+
+    pub fn matches(&self, entry: &Value) -> bool {
+        if let Some(ref id) = self.goal_id {
+            if entry.get("goal_id").and_then(|v| v.as_str()) != Some(id) {
+                return false;
+            }
+        }
+
+        if let Some(ref t) = self.tool {
+            if !entry.get("tool").and_then(|v| v.as_str())
+                .map(|s| s.contains(t))
+                .unwrap_or(false) {
+                return false;
+            }
+        }
+
+        if let Some(since) = self.since {
+            let timestamp = entry.get("timestamp")
+                .and_then(|v| v.as_str())
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok());
+            if !timestamp.map(|t| t >= since).unwrap_or(false) {
+                return false;
+            }
+        }
+
+        if self.failed_only && entry.get("success").and_then(|v| v.as_bool()) != Some(false) {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// A log entry and the file it came from
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub entry: Value,
+    pub file: String,
+}
+
+/// The last `lines` entries of `day`'s logs in `logs_dir` that pass
+/// `filter`, oldest first
+pub fn read_log_entries(
+    logs_dir: &Path,
+    day: NaiveDate,
+    filter: &LogFilter,
+    lines: usize,
+) -> Result<Vec<LogRecord>> {
+    let mut records = Vec::new();
+
+    for filename in log_file_names(day) {
+        let path = logs_dir.join(&filename);
+        if path.exists() {
+            let file = File::open(&path)?;
+            let reader = BufReader::new(file);
+
+            for line in reader.lines() {
+                let line = line?;
+                if let Ok(entry) = serde_json::from_str::<Value>(&line) {
+                    if filter.matches(&entry) {
+                        records.push(LogRecord { entry, file: filename.clone() });
+                    }
+                }
+            }
+        }
+    }
+
+    // Sort by timestamp
+    records.sort_by_key(|r| {
+        r.entry.get("timestamp")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    });
+
+    let start = records.len().saturating_sub(lines);
+    Ok(records.split_off(start))
+}
+
+/// Daily log files `sentientctl logs` reads
+fn log_file_names(day: NaiveDate) -> Vec<String> {
+    let day = day.format("%Y%m%d");
+    vec![
+        format!("activity_loop_log_{}.jsonl", day),
+        format!("goal_processor_log_{}.jsonl", day),
+        format!("sentient_log_{}.jsonl", day),
+    ]
+}
+
+/// Complete entries appended to `day`'s logs since `offsets` (bytes read
+/// per file) that pass `filter`, oldest first. A partly written last line
+/// is left for the next call; a file shorter than its offset was rotated
+/// and is read from the start.
+pub fn read_new_entries(
+    logs_dir: &Path,
+    day: NaiveDate,
+    filter: &LogFilter,
+    offsets: &mut HashMap<String, u64>,
+) -> Result<Vec<LogRecord>> {
+    let mut records = Vec::new();
+
+    for filename in log_file_names(day) {
+        let Ok(mut file) = File::open(logs_dir.join(&filename)) else {
+            continue;
+        };
+        let offset = offsets.entry(filename.clone()).or_insert(0);
+        if file.metadata()?.len() < *offset {
+            *offset = 0;
+        }
+        file.seek(SeekFrom::Start(*offset))?;
+        let mut appended = Vec::new();
+        file.read_to_end(&mut appended)?;
+
+        let complete = appended.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        *offset += complete as u64;
+        for line in String::from_utf8_lossy(&appended[..complete]).lines() {
+            if let Ok(entry) = serde_json::from_str::<Value>(line) {
+                if filter.matches(&entry) {
+                    records.push(LogRecord { entry, file: filename.clone() });
+                }
+            }
+        }
+    }
+
+    records.sort_by_key(|r| {
+        r.entry.get("timestamp")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string()
+    });
+    Ok(records)
+}
+
+/// The entry with every logged field plus `log_file`
+fn json_entry(record: &LogRecord) -> Value {
+    let mut entry = record.entry.clone();
+    if let Some(fields) = entry.as_object_mut() {
+        fields.insert("log_file".to_string(), Value::String(record.file.clone()));
+    }
+    entry
+}
+
+/// Write the records as a JSON array, each entry with every logged field
+/// plus `log_file`
+pub fn write_json(records: &[LogRecord], out: &mut impl Write) -> Result<()> {
+    let entries: Vec<Value> = records.iter().map(json_entry).collect();
+    serde_json::to_writer_pretty(&mut *out, &entries)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Write one record as `format` gives it, on its own line(s)
+pub fn write_record(record: &LogRecord, format: LogFormat, out: &mut impl Write) -> Result<()> {
+    match format {
+        LogFormat::Text => write_text(record, out)?,
+        LogFormat::Json => writeln!(out, "{}", json_entry(record))?,
+    }
+    Ok(())
+}
+
+fn write_text(LogRecord { entry, file }: &LogRecord, out: &mut impl Write) -> std::io::Result<()> {
+    let timestamp = entry.get("timestamp").and_then(|v| v.as_str()).unwrap_or("?");
+    let goal = entry.get("goal").and_then(|v| v.as_str()).unwrap_or("?");
+    let success = entry.get("success").and_then(|v| v.as_bool()).unwrap_or(false);
+    let reward = entry.get("reward").and_then(|v| v.as_f64()).unwrap_or(0.0);
+
+    let status = if success { "✅" } else { "❌" };
+
+    writeln!(out, "{} {} [{}] {} (reward: {:.2})",
+             timestamp, status, file, goal, reward)?;
+
+    if let Some(output) = entry.get("output").and_then(|v| v.as_str()) {
+        writeln!(out, "   Output: {}...", &output[..50.min(output.len())])?;
+    }
+    Ok(())
+}
+
+fn print_text(records: &[LogRecord]) -> Result<()> {
+    let mut out = std::io::stdout().lock();
+    for record in records.iter().rev() {
+        write_text(record, &mut out)?;
+    }
+    Ok(())
+}
+
+pub fn show_logs(lines: usize, filter: &LogFilter, format: LogFormat) -> Result<()> {
+    let records = read_log_entries(Path::new("logs"), Utc::now().date_naive(), filter, lines)?;
+
+    match format {
+        LogFormat::Text => print_text(&records)?,
+        LogFormat::Json => write_json(&records, &mut std::io::stdout().lock())?,
+    }
+
+    Ok(())
+}
+
+/// Print entries as they are appended to today's logs, formatted like
+/// `show_logs` but one entry at a time, until interrupted
+pub fn follow_logs(filter: &LogFilter, format: LogFormat) -> Result<()> {
+    use notify::{RecursiveMode, Watcher};
+
+    let logs_dir = Path::new("logs");
+    std::fs::create_dir_all(logs_dir)?;
+
+    // Start from the current end of each file: only new entries are shown
+    let mut offsets = HashMap::new();
+    read_new_entries(logs_dir, Utc::now().date_naive(), &LogFilter::default(), &mut offsets)?;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(logs_dir, RecursiveMode::NonRecursive)?;
+
+    eprintln!("📜 Following logs (press Ctrl+C to stop)...\n");
+    loop {
+        // Wake up at least once a second so a missed event or a new day's
+        // files are still picked up
+        match rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(Err(e)) => eprintln!("Watch error: {:?}", e),
+            Ok(Ok(_)) | Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+
+        let records = read_new_entries(logs_dir, Utc::now().date_naive(), filter, &mut offsets)?;
+        let mut out = std::io::stdout().lock();
+        for record in &records {
+            write_record(record, format, &mut out)?;
+        }
+        out.flush()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_output_holds_exactly_the_filtered_entries() {
+        let dir = std::env::temp_dir().join(format!("sentientctl_logs_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+
+        let activity = [
+            json!({"timestamp": "2024-03-01T10:00:00Z", "goal_id": "g1", "goal": "Check disk",
+                   "tool": "disk_info", "success": true, "reward": 1.0}),
+            json!({"timestamp": "2024-03-01T11:00:00Z", "goal_id": "g2", "goal": "Check memory",
+                   "tool": "mem_stats", "success": false, "reward": 0.0, "error": "timeout"}),
+            json!({"timestamp": "2024-03-01T12:30:00Z", "goal_id": "g3", "goal": "Check disk again",
+                   "tool": "disk_info", "success": false, "reward": 0.1}),
+        ];
+        let goals = [
+            json!({"timestamp": "2024-03-01T12:00:00Z", "goal_id": "g4", "goal": "Scan logs",
+                   "tool": "disk_usage", "success": false, "reward": 0.2}),
+            json!({"timestamp": "2024-03-01T09:00:00Z", "goal_id": "g5", "goal": "Old failure",
+                   "tool": "disk_info", "success": false, "reward": 0.0}),
+        ];
+        let write = |name: &str, entries: &[Value]| {
+            let mut content: String = entries.iter().map(|e| format!("{}\n", e)).collect();
+            content.push_str("not json\n");
+            std::fs::write(dir.join(name), content).unwrap();
+        };
+        write("activity_loop_log_20240301.jsonl", &activity);
+        write("goal_processor_log_20240301.jsonl", &goals);
+
+        let filter = LogFilter::new(
+            None,
+            Some("disk".to_string()),
+            Some("2024-03-01T10:30:00Z".to_string()),
+            true,
+        )
+        .unwrap();
+        let records = read_log_entries(&dir, day, &filter, 20).unwrap();
+        let mut out = Vec::new();
+        write_json(&records, &mut out).unwrap();
+
+        let parsed: Vec<Value> = serde_json::from_slice(&out).unwrap();
+        let ids: Vec<&str> = parsed.iter().map(|e| e["goal_id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["g4", "g3"]);
+        assert_eq!(parsed[0]["log_file"], "goal_processor_log_20240301.jsonl");
+        assert_eq!(parsed[1]["reward"], 0.1);
+        assert_eq!(parsed[1]["tool"], "disk_info");
+
+        // The line limit keeps the newest matches
+        let records = read_log_entries(&dir, day, &filter, 1).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].entry["goal_id"], "g3");
+
+        assert!(LogFilter::new(None, None, Some("yesterday".to_string()), false).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_followed_entries_are_formatted() {
+        let dir = std::env::temp_dir().join(format!("sentientctl_follow_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+        let path = dir.join("activity_loop_log_20240302.jsonl");
+        let old = json!({"timestamp": "2024-03-02T08:00:00Z", "goal_id": "g0", "success": true});
+        std::fs::write(&path, format!("{}\n", old)).unwrap();
+
+        let filter = LogFilter::new(None, None, None, true).unwrap();
+        let mut offsets = HashMap::new();
+        read_new_entries(&dir, day, &LogFilter::default(), &mut offsets).unwrap();
+
+        // Only the appended failure passes; the half-written line waits
+        let failed = json!({"timestamp": "2024-03-02T09:00:00Z", "goal_id": "g1", "goal": "Check disk",
+                            "success": false, "reward": 0.5});
+        let ok = json!({"timestamp": "2024-03-02T09:01:00Z", "goal_id": "g2", "success": true});
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        write!(file, "{}\n{}\n{{\"goal_id\": \"g3\"", failed, ok).unwrap();
+
+        let records = read_new_entries(&dir, day, &filter, &mut offsets).unwrap();
+        assert_eq!(records.len(), 1);
+
+        let mut out = Vec::new();
+        write_record(&records[0], LogFormat::Json, &mut out).unwrap();
+        let line = String::from_utf8(out).unwrap();
+        assert_eq!(line.lines().count(), 1);
+        let parsed: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed["goal_id"], "g1");
+        assert_eq!(parsed["log_file"], "activity_loop_log_20240302.jsonl");
+
+        let mut out = Vec::new();
+        write_record(&records[0], LogFormat::Text, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("2024-03-02T09:00:00Z ❌ [activity_loop_log_20240302.jsonl] Check disk"), "{}", text);
+
+        // Finishing the line makes it visible
+        writeln!(file, ", \"success\": false}}").unwrap();
+        let records = read_new_entries(&dir, day, &filter, &mut offsets).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].entry["goal_id"], "g3");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
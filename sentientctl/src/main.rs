@@ -9,6 +9,9 @@ use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::Path;
 
+mod histogram;
+mod logs;
+mod progress;
 mod rl_commands;
 
 #[derive(Parser)]
@@ -60,6 +63,10 @@ enum Commands {
         /// Follow log output
         #[arg(short, long)]
         follow: bool,
+        
+        /// Output format
+        #[arg(long, value_enum, default_value_t = logs::LogFormat::Text)]
+        format: logs::LogFormat,
     },
     
     /// Service management
@@ -109,6 +116,10 @@ enum RLCommands {
         /// Save checkpoint interval
         #[arg(long, default_value = "100")]
         checkpoint_interval: usize,
+        
+        /// Continue from a checkpoint (id, "latest" or "best"; defaults to latest)
+        #[arg(long, num_args = 0..=1, default_missing_value = "latest")]
+        resume: Option<String>,
     },
     
     /// Show policy information
@@ -122,6 +133,22 @@ enum RLCommands {
         /// Number of recent episodes to show
         #[arg(short = 'n', long, default_value = "100")]
         episodes: usize,
+        
+        /// Show the distribution of episode returns instead of the series
+        #[arg(long)]
+        histogram: bool,
+        
+        /// Number of histogram buckets
+        #[arg(long, default_value = "10", requires = "histogram")]
+        buckets: usize,
+        
+        /// Plot rewards smoothed over this many episodes
+        #[arg(long, value_name = "WINDOW", conflicts_with = "histogram")]
+        smooth: Option<usize>,
+        
+        /// Smoothing applied by --smooth
+        #[arg(long, value_enum, default_value = "ma", requires = "smooth")]
+        method: rl_commands::Smoothing,
     },
     
     /// Inject goal from trained policy
@@ -129,6 +156,52 @@ enum RLCommands {
         /// Policy checkpoint ID
         checkpoint_id: Option<String>,
     },
+    
+    /// Replay a recorded trace and score how often a policy checkpoint
+    /// would have chosen the recorded actions, without touching the system
+    EvalTrace {
+        /// Policy checkpoint ID ("latest" or "best" also work)
+        #[arg(long, default_value = "latest")]
+        checkpoint: String,
+        
+        /// JSONL trace file to replay
+        #[arg(long)]
+        trace: String,
+    },
+    
+    /// Play random episodes of an environment to check it is wired
+    /// correctly, reporting reward statistics
+    Smoke {
+        /// Environment name (cartpole, mountaincar)
+        #[arg(long)]
+        env: String,
+        
+        /// Number of episodes to play
+        #[arg(long, default_value = "10")]
+        episodes: usize,
+    },
+    
+    /// Save or restore all RL state (replay buffers, trajectories,
+    /// checkpoints, injector state)
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotAction {
+    /// Write a snapshot to a new or empty directory
+    Create {
+        /// Snapshot directory
+        dir: String,
+    },
+    
+    /// Restore a snapshot into this system
+    Restore {
+        /// Snapshot directory
+        dir: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -202,11 +275,13 @@ fn main() -> Result<()> {
             since,
             failed_only,
             follow,
+            format,
         } => {
+            let filter = logs::LogFilter::new(goal_id, tool, since, failed_only)?;
             if follow {
-                follow_logs()?;
+                logs::follow_logs(&filter, format)?;
             } else {
-                show_logs(lines, goal_id, tool, since, failed_only)?;
+                logs::show_logs(lines, &filter, format)?;
             }
         }
         
@@ -246,12 +321,7 @@ fn inject_goal(goal: &str, priority: &str, source: &str) -> Result<()> {
     std::fs::create_dir_all(logs_dir)?;
     
     let injection_file = logs_dir.join("goal_injections.jsonl");
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&injection_file)?;
-    
-    writeln!(file, "{}", serde_json::to_string(&injection)?)?;
+    append_jsonl_locked(&injection_file, &injection)?;
     
     println!("✅ Goal injected successfully");
     println!("   Goal: {}", goal);
@@ -261,118 +331,23 @@ fn inject_goal(goal: &str, priority: &str, source: &str) -> Result<()> {
     Ok(())
 }
 
-fn show_logs(
-    lines: usize,
-    goal_id: Option<String>,
-    tool: Option<String>,
-    since: Option<String>,
-    failed_only: bool,
-) -> Result<()> {
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
-    
-    let logs_dir = Path::new("logs");
-    let today = Utc::now().format("%Y%m%d");
-    
-    // Find log files
-    let log_files = vec![
-        format!("activity_loop_log_{}.jsonl", today),
-        format!("goal_processor_log_{}.jsonl", today),
-        format!("sentient_log_{}.jsonl", today),
-    ];
-    
-    let mut entries = Vec::new();
-    
-    for filename in log_files {
-        let path = logs_dir.join(&filename);
-        if path.exists() {
-            let file = File::open(&path)?;
-            let reader = BufReader::new(file);
-            
-            for line in reader.lines() {
-                let line = line?;
-                if let Ok(entry) = serde_json::from_str::<serde_json::Value>(&line) {
-                    // Apply filters
-                    if let Some(ref id) = goal_id {
-                        if entry.get("goal_id").and_then(|v| v.as_str()) != Some(id) {
-                            continue;
-                        }
-                    }
-                    
-                    if let Some(ref t) = tool {
-                        if !entry.get("tool").and_then(|v| v.as_str())
-                            .map(|s| s.contains(t))
-                            .unwrap_or(false) {
-                            continue;
-                        }
-                    }
-                    
-                    if failed_only {
-                        if entry.get("success").and_then(|v| v.as_bool()) != Some(false) {
-                            continue;
-                        }
-                    }
-                    
-                    entries.push((entry, filename.clone()));
-                }
-            }
-        }
-    }
-    
-    // Sort by timestamp
-    entries.sort_by_key(|(e, _)| {
-        e.get("timestamp")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string()
-    });
-    
-    // Show last N entries
-    for (entry, source) in entries.iter().rev().take(lines) {
-        let timestamp = entry.get("timestamp").and_then(|v| v.as_str()).unwrap_or("?");
-        let goal = entry.get("goal").and_then(|v| v.as_str()).unwrap_or("?");
-        let success = entry.get("success").and_then(|v| v.as_bool()).unwrap_or(false);
-        let reward = entry.get("reward").and_then(|v| v.as_f64()).unwrap_or(0.0);
-        
-        let status = if success { "✅" } else { "❌" };
-        
-        println!("{} {} [{}] {} (reward: {:.2})", 
-                 timestamp, status, source, goal, reward);
-        
-        if let Some(output) = entry.get("output").and_then(|v| v.as_str()) {
-            println!("   Output: {}...", &output[..50.min(output.len())]);
-        }
-    }
+/// Append `record` as one line in a single write, holding the exclusive
+/// file lock every goal injector takes, so concurrent appends never
+/// interleave
+fn append_jsonl_locked(path: &Path, record: &serde_json::Value) -> Result<()> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
     
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.lock()?;
+    file.write_all(&line)?;
     Ok(())
 }
 
-fn follow_logs() -> Result<()> {
-    use notify::{Watcher, RecursiveMode, watcher};
-    use std::sync::mpsc::channel;
-    use std::time::Duration;
-    
-    println!("📜 Following logs (press Ctrl+C to stop)...\n");
-    
-    // Set up file watcher
-    let (tx, rx) = channel();
-    let mut watcher = watcher(tx, Duration::from_secs(1))?;
-    
-    let logs_dir = Path::new("logs");
-    watcher.watch(logs_dir, RecursiveMode::NonRecursive)?;
-    
-    // Print new log entries as they arrive
-    loop {
-        match rx.recv() {
-            Ok(event) => {
-                // Handle file change events
-                // Read and display new entries
-            }
-            Err(e) => eprintln!("Watch error: {:?}", e),
-        }
-    }
-}
-
 fn handle_service_command(action: ServiceAction) -> Result<()> {
     use std::process::Command;
     
@@ -462,25 +437,32 @@ fn monitor_system(interval: u64) -> Result<()> {
         println!("🖥️  SentientOS System Monitor - {}", Utc::now().format("%H:%M:%S"));
         println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         
-        // CPU
+        // CPU (some platforms report NaN before the first sample)
         let cpu_usage = system.global_cpu_info().cpu_usage();
-        println!("CPU Usage: {:.1}%", cpu_usage);
+        if cpu_usage.is_finite() {
+            println!("CPU Usage: {:.1}%", cpu_usage);
+        } else {
+            println!("CPU Usage: n/a");
+        }
         
         // Memory
         let total_mem = system.total_memory();
         let used_mem = system.used_memory();
-        let mem_percent = (used_mem as f32 / total_mem as f32) * 100.0;
+        let mem_percent = if total_mem > 0 {
+            (used_mem as f32 / total_mem as f32) * 100.0
+        } else {
+            0.0
+        };
         println!("Memory: {:.1}% ({:.1} GB / {:.1} GB)", 
                  mem_percent,
                  used_mem as f32 / 1_073_741_824.0,
                  total_mem as f32 / 1_073_741_824.0);
         
         // Disk
-        for disk in system.disks() {
-            if disk.mount_point() == Path::new("/") {
+        match system.disks().iter().find(|disk| disk.mount_point() == Path::new("/")) {
+            Some(disk) if disk.total_space() > 0 => {
                 let total = disk.total_space();
-                let available = disk.available_space();
-                let used = total - available;
+                let used = total.saturating_sub(disk.available_space());
                 let disk_percent = (used as f32 / total as f32) * 100.0;
                 
                 println!("Disk (/): {:.1}% ({:.1} GB / {:.1} GB)",
@@ -488,12 +470,13 @@ fn monitor_system(interval: u64) -> Result<()> {
                          used as f32 / 1_073_741_824.0,
                          total as f32 / 1_073_741_824.0);
             }
+            _ => println!("Disk (/): n/a"),
         }
         
         // Processes
         println!("\nTop Processes:");
         let mut processes: Vec<_> = system.processes().values().collect();
-        processes.sort_by(|a, b| b.cpu_usage().partial_cmp(&a.cpu_usage()).unwrap());
+        processes.sort_by(|a, b| b.cpu_usage().total_cmp(&a.cpu_usage()));
         
         for (i, process) in processes.iter().take(5).enumerate() {
             println!("{:2}. {:20} CPU: {:5.1}% MEM: {:5.1} MB",
@@ -549,10 +532,20 @@ fn validate_system(component: Option<String>) -> Result<()> {
     
     // Check Ollama connectivity
     print!("Checking Ollama connectivity... ");
-    match reqwest::blocking::get("http://192.168.69.197:11434/api/tags") {
-        Ok(resp) if resp.status().is_success() => println!("✅"),
-        _ => {
-            println!("❌ Cannot reach Ollama");
+    let _ = std::io::stdout().flush();
+    let client = reqwest::blocking::Client::new();
+    let backoff = sentient_reconnect::Backoff {
+        deadline: std::time::Duration::from_secs(10),
+        ..Default::default()
+    };
+    match sentient_reconnect::get(&client, "http://192.168.69.197:11434/api/tags", &backoff) {
+        Ok(_) => println!("✅"),
+        Err(e) if e.is_unreachable() => {
+            println!("❌ Cannot reach Ollama ({})", e);
+            all_valid = false;
+        }
+        Err(e) => {
+            println!("❌ Ollama is misconfigured ({})", e);
             all_valid = false;
         }
     }
@@ -0,0 +1,190 @@
+// Training progress tracking and rendering for `sentientctl rl train`
+
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// How often progress is printed when stdout is not a terminal
+const LINE_PRINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Width of the progress bar in characters
+const BAR_WIDTH: usize = 30;
+
+/// Episodes per second and steps per second
+pub fn throughput(elapsed: Duration, completed_episodes: usize, total_steps: usize) -> (f64, f64) {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        return (0.0, 0.0);
+    }
+    (completed_episodes as f64 / secs, total_steps as f64 / secs)
+}
+
+/// Remaining time at the current episode rate, if it can be estimated
+pub fn eta(elapsed: Duration, completed_episodes: usize, total_episodes: usize) -> Option<Duration> {
+    if completed_episodes == 0 {
+        return None;
+    }
+    let remaining = total_episodes.saturating_sub(completed_episodes);
+    let per_episode = elapsed.as_secs_f64() / completed_episodes as f64;
+    Some(Duration::from_secs_f64(per_episode * remaining as f64))
+}
+
+/// Format a duration as `1h02m03s`, `2m03s` or `3s`
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (h, m, s) = (secs / 3600, (secs % 3600) / 60, secs % 60);
+    if h > 0 {
+        format!("{}h{:02}m{:02}s", h, m, s)
+    } else if m > 0 {
+        format!("{}m{:02}s", m, s)
+    } else {
+        format!("{}s", s)
+    }
+}
+
+/// Live view of a training run
+pub struct TrainingProgress {
+    total_episodes: usize,
+    completed: usize,
+    total_steps: usize,
+    reward_sum: f64,
+    current_reward: f64,
+    best_reward: Option<f64>,
+    started: Instant,
+    last_line_print: Option<Instant>,
+    is_tty: bool,
+}
+
+impl TrainingProgress {
+    pub fn new(total_episodes: usize) -> Self {
+        Self {
+            total_episodes,
+            completed: 0,
+            total_steps: 0,
+            reward_sum: 0.0,
+            current_reward: 0.0,
+            best_reward: None,
+            started: Instant::now(),
+            last_line_print: None,
+            is_tty: std::io::stdout().is_terminal(),
+        }
+    }
+
+    /// Record a finished episode
+    pub fn record_episode(&mut self, reward: f64, steps: usize) {
+        self.completed += 1;
+        self.total_steps += steps;
+        self.reward_sum += reward;
+        self.current_reward = reward;
+        self.best_reward = Some(self.best_reward.map_or(reward, |best| best.max(reward)));
+    }
+
+    pub fn completed(&self) -> usize {
+        self.completed
+    }
+
+    pub fn best_reward(&self) -> Option<f64> {
+        self.best_reward
+    }
+
+    pub fn mean_reward(&self) -> f64 {
+        if self.completed == 0 {
+            0.0
+        } else {
+            self.reward_sum / self.completed as f64
+        }
+    }
+
+    /// One-line status: bar, counts, rewards, throughput and ETA
+    pub fn status_line(&self) -> String {
+        let elapsed = self.started.elapsed();
+        let (_, steps_per_sec) = throughput(elapsed, self.completed, self.total_steps);
+        let fraction = if self.total_episodes == 0 {
+            1.0
+        } else {
+            (self.completed as f64 / self.total_episodes as f64).min(1.0)
+        };
+        let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+        let eta = eta(elapsed, self.completed, self.total_episodes)
+            .map(format_duration)
+            .unwrap_or_else(|| "?".to_string());
+
+        format!(
+            "[{}{}] {}/{} | reward {:.3} (mean {:.3}) | {:.1} steps/s | ETA {}",
+            "█".repeat(filled),
+            "░".repeat(BAR_WIDTH - filled),
+            self.completed,
+            self.total_episodes,
+            self.current_reward,
+            self.mean_reward(),
+            steps_per_sec,
+            eta,
+        )
+    }
+
+    /// Redraw the bar on a terminal, or print a line periodically otherwise
+    pub fn render(&mut self) {
+        if self.is_tty {
+            print!("\r\x1B[2K{}", self.status_line());
+            let _ = std::io::stdout().flush();
+        } else {
+            let due = self
+                .last_line_print
+                .map_or(true, |last| last.elapsed() >= LINE_PRINT_INTERVAL);
+            if due {
+                println!("{}", self.status_line());
+                self.last_line_print = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Leave the bar line so later output starts on a fresh line
+    pub fn finish(&self) {
+        if self.is_tty {
+            println!();
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throughput_and_eta() {
+        let elapsed = Duration::from_secs(20);
+
+        let (episodes_per_sec, steps_per_sec) = throughput(elapsed, 10, 2000);
+        assert!((episodes_per_sec - 0.5).abs() < 1e-9);
+        assert!((steps_per_sec - 100.0).abs() < 1e-9);
+
+        // 10 of 50 done in 20s -> 2s per episode, 40 left
+        assert_eq!(eta(elapsed, 10, 50), Some(Duration::from_secs(80)));
+        assert_eq!(eta(elapsed, 50, 50), Some(Duration::ZERO));
+        assert_eq!(eta(elapsed, 0, 50), None);
+        assert_eq!(throughput(Duration::ZERO, 0, 0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(5)), "5s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m05s");
+        assert_eq!(format_duration(Duration::from_secs(3723)), "1h02m03s");
+    }
+
+    #[test]
+    fn test_record_episode_tracks_best_and_mean() {
+        let mut progress = TrainingProgress::new(4);
+        progress.record_episode(1.0, 10);
+        progress.record_episode(3.0, 10);
+        progress.record_episode(2.0, 10);
+
+        assert_eq!(progress.completed(), 3);
+        assert_eq!(progress.best_reward(), Some(3.0));
+        assert!((progress.mean_reward() - 2.0).abs() < 1e-9);
+        assert!(progress.status_line().contains("3/4"));
+    }
+}
@@ -2,9 +2,44 @@
 
 use anyhow::{Result, Context};
 use serde_json::json;
-use std::path::Path;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use crate::{RLCommands, PolicyAction, inject_goal};
+use crate::histogram;
+use crate::progress::{format_duration, TrainingProgress};
+use crate::{RLCommands, PolicyAction, SnapshotAction, inject_goal};
+
+/// Where the training process writes checkpoints and episode stats
+const CHECKPOINT_DIR: &str = "/var/rl_checkpoints";
+
+/// How often the stats file is polled while training runs
+const PROGRESS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How `rl reward-graph --smooth` smooths the series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Smoothing {
+    /// Trailing moving average
+    Ma,
+    /// Exponential moving average with span WINDOW
+    Ema,
+}
+
+impl Smoothing {
+    fn apply(self, rewards: &[f64], window: usize) -> Vec<f64> {
+        match self {
+            Smoothing::Ma => sentient_rl_stats::moving_average(rewards, window),
+            Smoothing::Ema => sentient_rl_stats::exponential_moving_average(rewards, window),
+        }
+    }
+    
+    fn label(self) -> &'static str {
+        match self {
+            Smoothing::Ma => "moving average",
+            Smoothing::Ema => "EMA",
+        }
+    }
+}
 
 pub fn handle_rl_command(cmd: RLCommands) -> Result<()> {
     match cmd {
@@ -14,21 +49,34 @@ pub fn handle_rl_command(cmd: RLCommands) -> Result<()> {
             episodes,
             trace_file,
             checkpoint_interval,
+            resume,
         } => {
-            start_training(agent, env, episodes, trace_file, checkpoint_interval)?;
+            start_training(agent, env, episodes, trace_file, checkpoint_interval, resume)?;
         }
         
         RLCommands::Policy { action } => {
             handle_policy_command(action)?;
         }
         
-        RLCommands::RewardGraph { episodes } => {
-            show_reward_graph(episodes)?;
+        RLCommands::RewardGraph { episodes, histogram, buckets, smooth, method } => {
+            show_reward_graph(episodes, histogram.then_some(buckets), smooth.map(|window| (method, window)))?;
         }
         
         RLCommands::InjectPolicy { checkpoint_id } => {
             inject_from_policy(checkpoint_id)?;
         }
+        
+        RLCommands::Snapshot { action } => {
+            handle_snapshot_command(action)?;
+        }
+        
+        RLCommands::EvalTrace { checkpoint, trace } => {
+            eval_trace(&checkpoint, &trace)?;
+        }
+        
+        RLCommands::Smoke { env, episodes } => {
+            smoke(&env, episodes)?;
+        }
     }
     
     Ok(())
@@ -40,6 +88,7 @@ fn start_training(
     episodes: usize,
     trace_file: Option<String>,
     checkpoint_interval: usize,
+    resume: Option<String>,
 ) -> Result<()> {
     println!("🤖 Starting RL Training");
     println!("   Agent: {}", agent);
@@ -51,6 +100,19 @@ fn start_training(
         println!("   Trace file: {}", trace);
     }
     
+    // Episodes already covered by the checkpoint being resumed
+    let start_episode = match resume {
+        Some(ref id) => {
+            let start = resume_start_episode(id);
+            match start {
+                Some(start) => println!("   Resuming from: {} (episode {})", id, start),
+                None => println!("   Resuming from: {}", id),
+            }
+            start.unwrap_or(0)
+        }
+        None => 0,
+    };
+    
     // Create config file for training
     let config = json!({
         "agent_type": agent,
@@ -60,22 +122,43 @@ fn start_training(
         "trace_file": trace_file,
         "log_interval": 10,
         "reward_goal_threshold": 0.8,
+        "resume_from": resume,
     });
     
     // Write config to temporary file
     let config_path = "/tmp/rl_training_config.json";
     std::fs::write(config_path, serde_json::to_string_pretty(&config)?)?;
     
+    // Only episodes appended after launch belong to this run
+    let stats_path = Path::new(CHECKPOINT_DIR).join("training_stats.jsonl");
+    let mut stats_offset = std::fs::metadata(&stats_path).map(|m| m.len()).unwrap_or(0);
+    
     // Start training process
     println!("\n🚀 Launching training process...");
     
     let mut child = std::process::Command::new("sentient-shell")
         .args(&["rl", "train", "--config", config_path])
+        .stdout(std::process::Stdio::null())
         .spawn()
         .context("Failed to start training process")?;
     
-    // Wait for training to complete
-    let status = child.wait()?;
+    // Follow episode stats until training exits
+    let mut progress = TrainingProgress::new(episodes.saturating_sub(start_episode));
+    let status = loop {
+        for (reward, steps) in read_new_episodes(&stats_path, &mut stats_offset) {
+            progress.record_episode(reward, steps);
+        }
+        progress.render();
+        
+        if let Some(status) = child.try_wait()? {
+            for (reward, steps) in read_new_episodes(&stats_path, &mut stats_offset) {
+                progress.record_episode(reward, steps);
+            }
+            break status;
+        }
+        std::thread::sleep(PROGRESS_POLL_INTERVAL);
+    };
+    progress.finish();
     
     if status.success() {
         println!("\n✅ Training completed successfully!");
@@ -83,9 +166,75 @@ fn start_training(
         eprintln!("\n❌ Training failed with status: {}", status);
     }
     
+    println!("   Episodes: {}/{}", start_episode + progress.completed(), episodes);
+    match progress.best_reward() {
+        Some(best) => println!("   Best reward: {:.3}", best),
+        None => println!("   Best reward: n/a"),
+    }
+    println!("   Mean reward: {:.3}", progress.mean_reward());
+    println!("   Duration: {}", format_duration(progress.elapsed()));
+    if let Some(checkpoint) = latest_checkpoint() {
+        println!("   Checkpoint: {}", checkpoint.display());
+    }
+    
     Ok(())
 }
 
+/// Episode a resumed run continues at, read from the checkpoint metadata
+fn resume_start_episode(id: &str) -> Option<usize> {
+    let name = match id {
+        "latest" => "latest".to_string(),
+        "best" => return None,
+        id if id.starts_with("checkpoint_") => id.to_string(),
+        id => format!("checkpoint_ep{}", id),
+    };
+    let path = Path::new(CHECKPOINT_DIR).join(format!("{}.json", name));
+    let content = std::fs::read_to_string(path).ok()?;
+    let meta: serde_json::Value = serde_json::from_str(&content).ok()?;
+    meta.get("episode").and_then(|e| e.as_u64()).map(|e| e as usize + 1)
+}
+
+/// Read episode (reward, steps) pairs appended to the stats file since `offset`
+fn read_new_episodes(path: &Path, offset: &mut u64) -> Vec<(f64, usize)> {
+    let mut episodes = Vec::new();
+    let mut file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(_) => return episodes,
+    };
+    
+    let mut buf = String::new();
+    if file.seek(SeekFrom::Start(*offset)).is_err() || file.read_to_string(&mut buf).is_err() {
+        return episodes;
+    }
+    
+    // Leave a partially written trailing line for the next poll
+    let complete = match buf.rfind('\n') {
+        Some(end) => &buf[..=end],
+        None => return episodes,
+    };
+    *offset += complete.len() as u64;
+    
+    for line in complete.lines() {
+        if let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) {
+            let reward = entry["total_reward"].as_f64()
+                .or_else(|| entry["episode_reward"].as_f64())
+                .unwrap_or(0.0);
+            let steps = entry["steps"].as_u64().unwrap_or(0) as usize;
+            episodes.push((reward, steps));
+        }
+    }
+    
+    episodes
+}
+
+/// Checkpoint the `latest.bin` link points at, if any
+fn latest_checkpoint() -> Option<PathBuf> {
+    let latest = Path::new(CHECKPOINT_DIR).join("latest.bin");
+    std::fs::read_link(&latest)
+        .ok()
+        .or_else(|| latest.exists().then_some(latest))
+}
+
 fn handle_policy_command(action: PolicyAction) -> Result<()> {
     match action {
         PolicyAction::List => {
@@ -189,7 +338,75 @@ fn handle_policy_command(action: PolicyAction) -> Result<()> {
     Ok(())
 }
 
-fn show_reward_graph(episodes: usize) -> Result<()> {
+/// `sentient-shell` set up to run `line` as one non-interactive command
+fn shell_command(line: &str) -> std::process::Command {
+    let mut command = std::process::Command::new("sentient-shell");
+    command.args(["-c", line]);
+    command
+}
+
+fn handle_snapshot_command(action: SnapshotAction) -> Result<()> {
+    let (verb, dir) = match action {
+        SnapshotAction::Create { dir } => ("create", dir),
+        SnapshotAction::Restore { dir } => ("restore", dir),
+    };
+    
+    // The RL runtime owns the store and injector state; it writes and
+    // validates the snapshot manifest
+    let result = shell_command(&format!("rl snapshot {} {}", verb, dir))
+        .output()
+        .context("Failed to run sentient-shell")?;
+    
+    print!("{}", String::from_utf8_lossy(&result.stdout));
+    if !result.status.success() {
+        eprintln!("❌ Snapshot {} failed", verb);
+        eprintln!("{}", String::from_utf8_lossy(&result.stderr));
+        anyhow::bail!("Snapshot {} failed for {}", verb, dir);
+    }
+    
+    Ok(())
+}
+
+fn eval_trace(checkpoint: &str, trace: &str) -> Result<()> {
+    // Evaluation runs in the RL runtime, which loads the policy and env
+    let result = shell_command(&format!("rl eval-trace --checkpoint {} --trace {}", checkpoint, trace))
+        .output()
+        .context("Failed to run sentient-shell")?;
+    
+    print!("{}", String::from_utf8_lossy(&result.stdout));
+    if !result.status.success() {
+        eprintln!("❌ Trace evaluation failed");
+        eprintln!("{}", String::from_utf8_lossy(&result.stderr));
+        anyhow::bail!("Could not evaluate {} on {}", checkpoint, trace);
+    }
+    
+    Ok(())
+}
+
+fn smoke(env: &str, episodes: usize) -> Result<()> {
+    // Environments are built and played by the RL runtime
+    let result = shell_command(&format!("rl smoke --env {} --episodes {}", env, episodes))
+        .output()
+        .context("Failed to run sentient-shell")?;
+    
+    print!("{}", String::from_utf8_lossy(&result.stdout));
+    if !result.status.success() {
+        eprintln!("❌ Smoke test failed");
+        eprintln!("{}", String::from_utf8_lossy(&result.stderr));
+        anyhow::bail!("{} did not pass the smoke test", env);
+    }
+    
+    Ok(())
+}
+
+/// Plot recent episode rewards, or bucket them into `histogram_buckets` bins.
+/// With `smoothing`, the plot shows the smoothed series; the averages run
+/// over the full history so the first plotted points are smoothed too.
+fn show_reward_graph(
+    episodes: usize,
+    histogram_buckets: Option<usize>,
+    smoothing: Option<(Smoothing, usize)>,
+) -> Result<()> {
     println!("📊 Reward Graph (last {} episodes)\n", episodes);
     
     // Read training stats
@@ -224,6 +441,24 @@ fn show_reward_graph(episodes: usize) -> Result<()> {
     let min_reward = recent_rewards.iter().fold(f64::INFINITY, |a, &b| a.min(b));
     let avg_reward = recent_rewards.iter().sum::<f64>() / recent_rewards.len() as f64;
     
+    if let Some(buckets) = histogram_buckets {
+        println!("Max: {:.2}  Avg: {:.2}  Min: {:.2}\n", max_reward, avg_reward, min_reward);
+        for line in histogram::render_bars(&sentient_rl_stats::histogram(recent_rewards, buckets)) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+    
+    let plotted = match smoothing {
+        Some((method, window)) => {
+            println!("Smoothing: {}-episode {}", window, method.label());
+            method.apply(&rewards, window).split_off(start)
+        }
+        None => recent_rewards.to_vec(),
+    };
+    let plot_max = plotted.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b));
+    let plot_min = plotted.iter().fold(f64::INFINITY, |a, &b| a.min(b));
+    
     // Simple ASCII graph
     let graph_height = 10;
     let graph_width = 50;
@@ -235,10 +470,10 @@ fn show_reward_graph(episodes: usize) -> Result<()> {
         print!("│");
         
         for i in 0..graph_width {
-            let episode_idx = (i * recent_rewards.len()) / graph_width;
-            if episode_idx < recent_rewards.len() {
-                let reward = recent_rewards[episode_idx];
-                let normalized = (reward - min_reward) / (max_reward - min_reward + 1e-6);
+            let episode_idx = (i * plotted.len()) / graph_width;
+            if episode_idx < plotted.len() {
+                let reward = plotted[episode_idx];
+                let normalized = (reward - plot_min) / (plot_max - plot_min + 1e-6);
                 let bar_height = (normalized * graph_height as f64) as usize;
                 
                 if bar_height >= h {